@@ -0,0 +1,37 @@
+use candid::{CandidType, Deserialize, Nat};
+
+use crate::icrc1::{
+    account::{Account, Subaccount},
+    transfer::{BlockIndex, Memo},
+};
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ApproveArgs {
+    #[serde(default)]
+    pub from_subaccount: Option<Subaccount>,
+    pub spender: Account,
+    pub amount: Nat,
+    #[serde(default)]
+    pub expected_allowance: Option<Nat>,
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    #[serde(default)]
+    pub fee: Option<Nat>,
+    #[serde(default)]
+    pub memo: Option<Memo>,
+    #[serde(default)]
+    pub created_at_time: Option<u64>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ApproveError {
+    BadFee { expected_fee: Nat },
+    InsufficientFunds { balance: Nat },
+    AllowanceChanged { current_allowance: Nat },
+    Expired { ledger_time: u64 },
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    Duplicate { duplicate_of: BlockIndex },
+    TemporarilyUnavailable,
+    GenericError { error_code: Nat, message: String },
+}
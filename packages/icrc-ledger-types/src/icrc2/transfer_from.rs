@@ -0,0 +1,34 @@
+use candid::{CandidType, Deserialize, Nat};
+
+use crate::icrc1::{
+    account::{Account, Subaccount},
+    transfer::{BlockIndex, Memo},
+};
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TransferFromArgs {
+    #[serde(default)]
+    pub spender_subaccount: Option<Subaccount>,
+    pub from: Account,
+    pub to: Account,
+    pub amount: Nat,
+    #[serde(default)]
+    pub fee: Option<Nat>,
+    #[serde(default)]
+    pub memo: Option<Memo>,
+    #[serde(default)]
+    pub created_at_time: Option<u64>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TransferFromError {
+    BadFee { expected_fee: Nat },
+    BadBurn { min_burn_amount: Nat },
+    InsufficientFunds { balance: Nat },
+    InsufficientAllowance { allowance: Nat },
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    Duplicate { duplicate_of: BlockIndex },
+    TemporarilyUnavailable,
+    GenericError { error_code: Nat, message: String },
+}
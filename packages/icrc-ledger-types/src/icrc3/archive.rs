@@ -1,7 +1,7 @@
 use crate::icrc1::transfer::BlockIndex;
 
 use super::{
-    blocks::{BlockRange, GetBlocksRequest},
+    blocks::{BlockRange, GetBlocksRequest, GetBlocksResult},
     transactions::{GetTransactionsRequest, TransactionRange},
 };
 use candid::{CandidType, Deserialize, Nat, Principal};
@@ -74,8 +74,8 @@ impl<Input: CandidType, Output: CandidType> CandidType for QueryArchiveFn<Input,
     fn _ty() -> candid::types::Type {
         candid::types::Type::Func(candid::types::Function {
             modes: vec![candid::parser::types::FuncMode::Query],
-            args: vec![Input::_ty()],
-            rets: vec![Output::_ty()],
+            args: vec![Input::ty()],
+            rets: vec![Output::ty()],
         })
     }
 
@@ -94,4 +94,5 @@ pub struct ArchiveInfo {
     pub block_range_end: BlockIndex,
 }
 pub type QueryBlockArchiveFn = QueryArchiveFn<GetBlocksRequest, BlockRange>;
+pub type QueryIcrc3BlocksArchiveFn = QueryArchiveFn<Vec<GetBlocksRequest>, GetBlocksResult>;
 pub type QueryTxArchiveFn = QueryArchiveFn<GetTransactionsRequest, TransactionRange>;
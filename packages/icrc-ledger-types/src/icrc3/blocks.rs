@@ -1,5 +1,5 @@
 use crate::icrc3::archive::ArchivedRange;
-use crate::icrc3::archive::QueryBlockArchiveFn;
+use crate::icrc3::archive::{QueryBlockArchiveFn, QueryIcrc3BlocksArchiveFn};
 use crate::{icrc::generic_value::Value, icrc1::transfer::BlockIndex};
 use candid::{CandidType, Deserialize, Nat};
 use serde_bytes::ByteBuf;
@@ -52,3 +52,41 @@ pub struct DataCertificate {
     pub certificate: Option<serde_bytes::ByteBuf>,
     pub hash_tree: serde_bytes::ByteBuf,
 }
+
+/// A block together with its index in the ledger.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BlockWithId {
+    pub id: Nat,
+    pub block: GenericBlock,
+}
+
+/// Block ranges that the caller must fetch from an archive by calling the
+/// callback with the specified arguments.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ArchivedBlocks {
+    pub args: Vec<GetBlocksRequest>,
+    pub callback: QueryIcrc3BlocksArchiveFn,
+}
+
+/// The result of an `icrc3_get_blocks` call.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GetBlocksResult {
+    /// The total number of blocks in the log.
+    pub log_length: Nat,
+    /// The blocks the canister serves, in the order of the requested ranges.
+    pub blocks: Vec<BlockWithId>,
+    /// The requested blocks that the canister moved to archives.
+    pub archived_blocks: Vec<ArchivedBlocks>,
+}
+
+/// A certificate of the last block of the ledger returned by
+/// `icrc3_get_tip_certificate`.
+#[derive(Debug, CandidType, Deserialize)]
+pub struct ICRC3DataCertificate {
+    /// The certificate of the canister certified data, see
+    /// https://internetcomputer.org/docs/current/references/ic-interface-spec/#certification
+    pub certificate: serde_bytes::ByteBuf,
+    /// The CBOR-encoded hash tree containing the index and the hash of the
+    /// last block.
+    pub hash_tree: serde_bytes::ByteBuf,
+}
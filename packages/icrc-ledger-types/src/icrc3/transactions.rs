@@ -27,6 +27,7 @@ pub struct Mint {
 pub struct Burn {
     pub amount: Nat,
     pub from: Account,
+    pub spender: Option<Account>,
     pub memo: Option<Memo>,
     pub created_at_time: Option<u64>,
 }
@@ -36,18 +37,32 @@ pub struct Transfer {
     pub amount: Nat,
     pub from: Account,
     pub to: Account,
+    pub spender: Option<Account>,
     pub memo: Option<Memo>,
     pub fee: Option<Nat>,
     pub created_at_time: Option<u64>,
 }
 
-// Representation of a Transaction which supports the Icrc1 Standard functionalities
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Approve {
+    pub from: Account,
+    pub spender: Account,
+    pub amount: Nat,
+    pub expected_allowance: Option<Nat>,
+    pub expires_at: Option<u64>,
+    pub memo: Option<Memo>,
+    pub fee: Option<Nat>,
+    pub created_at_time: Option<u64>,
+}
+
+// Representation of a Transaction which supports the Icrc1 and Icrc2 Standard functionalities
 #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct Transaction {
     pub kind: String,
     pub mint: Option<Mint>,
     pub burn: Option<Burn>,
     pub transfer: Option<Transfer>,
+    pub approve: Option<Approve>,
     pub timestamp: u64,
 }
 
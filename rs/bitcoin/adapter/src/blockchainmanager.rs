@@ -1,6 +1,10 @@
 use crate::{
     blockchainstate::{AddHeaderError, BlockchainState},
     common::{BlockHeight, MINIMUM_VERSION_NUMBER},
+    compact_filters::{
+        CompactFilterError, CompactFilterStore, BASIC_FILTER_TYPE, MAX_CFHEADERS_SIZE,
+    },
+    config::Config,
     metrics::RouterMetrics,
     Channel, Command, ProcessBitcoinNetworkMessageError,
 };
@@ -8,13 +12,14 @@ use bitcoin::{
     network::{
         message::{NetworkMessage, MAX_INV_SIZE},
         message_blockdata::{GetHeadersMessage, Inventory},
+        message_filter::{CFHeaders, CFilter, GetCFHeaders, GetCFilters},
     },
     Block, BlockHash, BlockHeader,
 };
 use hashlink::{LinkedHashMap, LinkedHashSet};
 use ic_logger::{debug, error, info, trace, warn, ReplicaLogger};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     net::SocketAddr,
     sync::Arc,
     time::Instant,
@@ -87,6 +92,24 @@ pub enum ReceivedBlockMessageError {
     /// BlockchainState.
     #[error("Failed to add block")]
     BlockNotAdded,
+    /// This variant represents that the block's output scripts are not part of its compact filter.
+    #[error("Block does not match its compact filter: {0}")]
+    BlockDoesNotMatchFilter(CompactFilterError),
+}
+
+/// The possible errors the `BlockchainManager` may produce while processing `cfheaders` and
+/// `cfilter` messages.
+#[derive(Debug, Error)]
+enum ReceivedCompactFilterMessageError {
+    /// This variant represents when a message is received while compact filters are disabled.
+    #[error("Compact block filters are disabled")]
+    CompactFiltersDisabled,
+    /// This variant represents when a message was not requested from the peer.
+    #[error("Unsolicited compact filter message")]
+    Unsolicited,
+    /// This variant represents when the filter headers or the filter could not be verified.
+    #[error("Invalid compact filter message: {0}")]
+    Invalid(CompactFilterError),
 }
 
 /// This struct stores the information regarding a peer with respect to synchronizing the blockchain.
@@ -136,6 +159,19 @@ struct GetDataRequestInfo {
     sent_at: Option<Instant>,
 }
 
+/// This struct stores the information related to the outstanding "getcfheaders" request.
+#[derive(Debug)]
+struct GetCFHeadersRequest {
+    /// This field stores the socket address of the Bitcoin node to which the request was sent.
+    socket: SocketAddr,
+    /// The block preceding the requested range. `None` if the range starts at the genesis block.
+    previous_block_hash: Option<BlockHash>,
+    /// The hashes of the requested blocks in ascending height order.
+    block_hashes: Vec<BlockHash>,
+    /// This field contains the time at which the getcfheaders request was sent.
+    sent_at: Instant,
+}
+
 /// The BlockChainManager struct handles interactions that involve the headers.
 pub struct BlockchainManager {
     /// This field contains the BlockchainState, which stores and manages
@@ -170,6 +206,23 @@ pub struct BlockchainManager {
     /// A block hash is removed when it is determined a peer can receive another `getdata` message.
     block_sync_queue: LinkedHashSet<BlockHash>,
 
+    /// This field stores the verified compact block filters. It is only set if the adapter
+    /// is configured to drive block downloads with BIP157/158 compact filters.
+    compact_filters: Option<CompactFilterStore>,
+
+    /// When compact filters are enabled, this queue stores the block hashes whose filter has yet
+    /// to be downloaded and verified. Once the filter is verified, the block hash is moved to the
+    /// `block_sync_queue`.
+    filter_sync_queue: LinkedHashSet<BlockHash>,
+
+    /// Records the outstanding `getcfheaders` request. Filter headers are synced from one peer
+    /// at a time as each batch has to connect to the previous one.
+    getcfheaders_request: Option<GetCFHeadersRequest>,
+
+    /// This HashMap stores the information related to each `getcfilters` request sent by the
+    /// BlockChainManager. An entry is removed when the corresponding "cfilter" response is received.
+    getcfilters_request_info: LinkedHashMap<BlockHash, GetDataRequestInfo>,
+
     /// This field contains a logger for the blockchain manager's use.
     logger: ReplicaLogger,
     metrics: RouterMetrics,
@@ -181,6 +234,7 @@ impl BlockchainManager {
     /// BTC network.
     pub fn new(
         blockchain: Arc<Mutex<BlockchainState>>,
+        config: &Config,
         logger: ReplicaLogger,
         metrics: RouterMetrics,
     ) -> Self {
@@ -194,6 +248,12 @@ impl BlockchainManager {
             getheaders_requests: HashMap::new(),
             catchup_headers: HashSet::new(),
             block_sync_queue: LinkedHashSet::new(),
+            compact_filters: config
+                .compact_block_filters
+                .then(CompactFilterStore::default),
+            filter_sync_queue: LinkedHashSet::new(),
+            getcfheaders_request: None,
+            getcfilters_request_info: LinkedHashMap::new(),
             logger,
             metrics,
         }
@@ -207,6 +267,12 @@ impl BlockchainManager {
 
         self.block_sync_queue.clear();
         self.getdata_request_info.clear();
+        self.filter_sync_queue.clear();
+        self.getcfheaders_request = None;
+        self.getcfilters_request_info.clear();
+        if let Some(compact_filters) = self.compact_filters.as_mut() {
+            compact_filters.clear_filters();
+        }
         self.peer_info.clear();
         self.blockchain.lock().await.clear_blocks();
     }
@@ -439,6 +505,22 @@ impl BlockchainManager {
             block_hash
         );
 
+        if let Some(compact_filters) = self.compact_filters.as_mut() {
+            if let Err(err) = compact_filters.check_block(block) {
+                warn!(
+                    self.logger,
+                    "Received block {} from {} that does not match its filter: {}",
+                    block_hash,
+                    addr,
+                    err
+                );
+                // Either the block or the filter may have been forged, so both are requested again.
+                compact_filters.remove_filter(&block_hash);
+                self.filter_sync_queue.insert(block_hash);
+                return Err(ReceivedBlockMessageError::BlockDoesNotMatchFilter(err));
+            }
+        }
+
         match self.blockchain.lock().await.add_block(block.clone()) {
             Ok(block_height) => {
                 trace!(
@@ -499,6 +581,16 @@ impl BlockchainManager {
             }
         }
 
+        // Retry the `getcfilters` and `getcfheaders` requests that have been sent to the peer.
+        for request in self.getcfilters_request_info.values_mut() {
+            if request.socket == *addr {
+                request.sent_at = None;
+            }
+        }
+        if matches!(&self.getcfheaders_request, Some(request) if request.socket == *addr) {
+            self.getcfheaders_request = None;
+        }
+
         // Remove getheaders request sent to peer.
         self.getheaders_requests.remove(addr);
         // Unset catch-up flag
@@ -633,6 +725,235 @@ impl BlockchainManager {
         }
     }
 
+    /// This function sends the `getcfheaders` and `getcfilters` requests needed to download
+    /// the compact filters of the blocks in the `filter_sync_queue`. It does nothing if
+    /// compact filters are disabled.
+    async fn sync_filters(&mut self, channel: &mut impl Channel) {
+        let compact_filters = match self.compact_filters.as_ref() {
+            Some(compact_filters) => compact_filters,
+            None => return,
+        };
+
+        // Timeout requests so they may be retried again.
+        let expired_getcfilters_requests: Vec<BlockHash> = self
+            .getcfilters_request_info
+            .iter()
+            .filter_map(|(block_hash, request)| match request.sent_at {
+                Some(sent_at) if sent_at.elapsed().as_secs() <= GETDATA_REQUEST_TIMEOUT_SECS => {
+                    None
+                }
+                _ => Some(*block_hash),
+            })
+            .collect();
+        for block_hash in expired_getcfilters_requests {
+            self.getcfilters_request_info.remove(&block_hash);
+            self.filter_sync_queue.insert(block_hash);
+        }
+
+        if let Some(request) = &self.getcfheaders_request {
+            if request.sent_at.elapsed().as_secs() >= GETHEADERS_REQUEST_TIMEOUT_SECS {
+                channel.discard(&request.socket);
+                self.getcfheaders_request = None;
+            }
+        }
+
+        if self.filter_sync_queue.is_empty() || self.peer_info.is_empty() {
+            return;
+        }
+
+        let blockchain = self.blockchain.lock().await;
+
+        // Request the filter headers of the first block that is lacking them. The range starts
+        // right after the last ancestor with a verified filter header.
+        if self.getcfheaders_request.is_none() {
+            let maybe_block_hash = self
+                .filter_sync_queue
+                .iter()
+                .find(|block_hash| !compact_filters.has_filter_header(block_hash))
+                .copied();
+            let peer = self.peer_info.keys().next().copied();
+            if let (Some(block_hash), Some(peer)) = (maybe_block_hash, peer) {
+                let mut block_hashes = vec![];
+                let mut previous_block_hash = None;
+                let mut current = blockchain.get_cached_header(&block_hash);
+                while let Some(cached) = current {
+                    block_hashes.push(cached.header.block_hash());
+                    if cached.height == 0 {
+                        break;
+                    }
+                    let prev_blockhash = cached.header.prev_blockhash;
+                    if compact_filters.has_filter_header(&prev_blockhash) {
+                        previous_block_hash = Some(prev_blockhash);
+                        break;
+                    }
+                    current = blockchain.get_cached_header(&prev_blockhash);
+                }
+                block_hashes.reverse();
+                block_hashes.truncate(MAX_CFHEADERS_SIZE);
+
+                let start_height = block_hashes
+                    .first()
+                    .and_then(|hash| blockchain.get_cached_header(hash))
+                    .map(|cached| cached.height);
+                if let (Some(start_height), Some(stop_hash)) =
+                    (start_height, block_hashes.last().copied())
+                {
+                    trace!(
+                        self.logger,
+                        "Sending getcfheaders to {} : start height {}, stop hash {}",
+                        peer,
+                        start_height,
+                        stop_hash
+                    );
+                    channel
+                        .send(Command {
+                            address: Some(peer),
+                            message: NetworkMessage::GetCFHeaders(GetCFHeaders {
+                                filter_type: BASIC_FILTER_TYPE,
+                                start_height,
+                                stop_hash,
+                            }),
+                        })
+                        .ok();
+                    self.getcfheaders_request = Some(GetCFHeadersRequest {
+                        socket: peer,
+                        previous_block_hash,
+                        block_hashes,
+                        sent_at: Instant::now(),
+                    });
+                }
+            }
+        }
+
+        // Request the filters of the blocks whose filter headers have been verified.
+        let mut requests_per_peer: HashMap<SocketAddr, u32> =
+            self.peer_info.keys().map(|addr| (*addr, 0)).collect();
+        for info in self.getcfilters_request_info.values() {
+            let counter = requests_per_peer.entry(info.socket).or_insert(0);
+            *counter = counter.saturating_add(1);
+        }
+
+        let mut ready_block_hashes = self
+            .filter_sync_queue
+            .iter()
+            .filter(|block_hash| compact_filters.has_filter_header(block_hash))
+            .copied()
+            .collect::<VecDeque<_>>();
+        'peers: for peer in self.peer_info.keys() {
+            let requests_sent_to_peer = requests_per_peer.get(peer).unwrap_or(&0);
+            for _ in 0..INV_PER_GET_DATA_REQUEST.saturating_sub(*requests_sent_to_peer) {
+                let block_hash = match ready_block_hashes.pop_front() {
+                    Some(block_hash) => block_hash,
+                    None => break 'peers,
+                };
+                self.filter_sync_queue.remove(&block_hash);
+                let height = match blockchain.get_cached_header(&block_hash) {
+                    Some(cached) => cached.height,
+                    None => continue,
+                };
+
+                trace!(
+                    self.logger,
+                    "Sending getcfilters to {} : Block {}",
+                    peer,
+                    block_hash
+                );
+                channel
+                    .send(Command {
+                        address: Some(*peer),
+                        message: NetworkMessage::GetCFilters(GetCFilters {
+                            filter_type: BASIC_FILTER_TYPE,
+                            start_height: height,
+                            stop_hash: block_hash,
+                        }),
+                    })
+                    .ok();
+                self.getcfilters_request_info.insert(
+                    block_hash,
+                    GetDataRequestInfo {
+                        socket: *peer,
+                        sent_at: Some(Instant::now()),
+                    },
+                );
+            }
+        }
+    }
+
+    /// This function processes "cfheaders" messages received from Bitcoin nodes.
+    fn received_cfheaders_message(
+        &mut self,
+        addr: &SocketAddr,
+        message: &CFHeaders,
+    ) -> Result<(), ReceivedCompactFilterMessageError> {
+        let compact_filters = self
+            .compact_filters
+            .as_mut()
+            .ok_or(ReceivedCompactFilterMessageError::CompactFiltersDisabled)?;
+
+        let request = match self.getcfheaders_request.take() {
+            Some(request)
+                if request.socket == *addr
+                    && request.block_hashes.last() == Some(&message.stop_hash) =>
+            {
+                request
+            }
+            request => {
+                self.getcfheaders_request = request;
+                return Err(ReceivedCompactFilterMessageError::Unsolicited);
+            }
+        };
+
+        trace!(
+            self.logger,
+            "Received cfheaders message from {} : {} filter hashes up to {}",
+            addr,
+            message.filter_hashes.len(),
+            message.stop_hash
+        );
+
+        compact_filters
+            .received_cfheaders(
+                request.previous_block_hash.as_ref(),
+                &request.block_hashes,
+                message,
+            )
+            .map_err(ReceivedCompactFilterMessageError::Invalid)
+    }
+
+    /// This function processes "cfilter" messages received from Bitcoin nodes.
+    /// Once a filter is verified, the corresponding block is queued for download.
+    fn received_cfilter_message(
+        &mut self,
+        addr: &SocketAddr,
+        message: &CFilter,
+    ) -> Result<(), ReceivedCompactFilterMessageError> {
+        let compact_filters = self
+            .compact_filters
+            .as_mut()
+            .ok_or(ReceivedCompactFilterMessageError::CompactFiltersDisabled)?;
+
+        match self.getcfilters_request_info.get(&message.block_hash) {
+            Some(request) if request.socket == *addr => {
+                self.getcfilters_request_info.remove(&message.block_hash);
+            }
+            _ => return Err(ReceivedCompactFilterMessageError::Unsolicited),
+        }
+
+        if let Err(err) = compact_filters.received_cfilter(message) {
+            self.filter_sync_queue.insert(message.block_hash);
+            return Err(ReceivedCompactFilterMessageError::Invalid(err));
+        }
+
+        trace!(
+            self.logger,
+            "Received cfilter message from {} : Block {}",
+            addr,
+            message.block_hash
+        );
+        self.block_sync_queue.insert(message.block_hash);
+        Ok(())
+    }
+
     /// This function is called by the adapter when a new event takes place.
     /// The event could be receiving "getheaders", "getdata", "inv" messages from bitcion peers.
     /// The event could be change in connection status with a bitcoin peer.
@@ -666,6 +987,16 @@ impl BlockchainManager {
                     return Err(ProcessBitcoinNetworkMessageError::InvalidMessage);
                 }
             }
+            NetworkMessage::CFHeaders(cfheaders) => {
+                if self.received_cfheaders_message(&addr, cfheaders).is_err() {
+                    return Err(ProcessBitcoinNetworkMessageError::InvalidMessage);
+                }
+            }
+            NetworkMessage::CFilter(cfilter) => {
+                if self.received_cfilter_message(&addr, cfilter).is_err() {
+                    return Err(ProcessBitcoinNetworkMessageError::InvalidMessage);
+                }
+            }
             _ => {}
         };
         Ok(())
@@ -703,21 +1034,34 @@ impl BlockchainManager {
             }
         }
 
+        self.sync_filters(channel).await;
         self.sync_blocks(channel).await;
         self.handle_getheaders_timeouts(channel);
     }
 
     /// Add block hashes to the sync queue that are not already being synced, planned to be synced,
-    /// or in the block cache.
+    /// or in the block cache. If compact filters are enabled, the block hashes are added to the
+    /// filter sync queue instead unless the block's filter has already been verified.
     pub async fn enqueue_new_blocks_to_download(&mut self, next_headers: Vec<BlockHeader>) {
         let state = self.blockchain.lock().await;
         for header in next_headers {
             let hash = header.block_hash();
-            if state.get_block(&hash).is_none()
-                && !self.block_sync_queue.contains(&hash)
-                && !self.getdata_request_info.contains_key(&hash)
+            if state.get_block(&hash).is_some()
+                || self.block_sync_queue.contains(&hash)
+                || self.getdata_request_info.contains_key(&hash)
             {
-                self.block_sync_queue.insert(hash);
+                continue;
+            }
+
+            match self.compact_filters.as_ref() {
+                Some(compact_filters) if !compact_filters.has_filter(&hash) => {
+                    if !self.getcfilters_request_info.contains_key(&hash) {
+                        self.filter_sync_queue.insert(hash);
+                    }
+                }
+                _ => {
+                    self.block_sync_queue.insert(hash);
+                }
             }
         }
     }
@@ -748,11 +1092,28 @@ impl BlockchainManager {
             self.block_sync_queue.retain(|b| {
                 blockchain.get_cached_header(b).map_or(0, |c| c.height) >= filter_height
             });
+
+            self.filter_sync_queue.retain(|b| {
+                blockchain.get_cached_header(b).map_or(0, |c| c.height) >= filter_height
+            });
+
+            self.getcfilters_request_info.retain(|b, _| {
+                blockchain.get_cached_header(b).map_or(0, |c| c.height) >= filter_height
+            });
+
+            if let Some(compact_filters) = self.compact_filters.as_mut() {
+                compact_filters.retain_filters(|b| {
+                    blockchain.get_cached_header(b).map_or(0, |c| c.height) >= filter_height
+                        && !processed_block_hashes.contains(b)
+                });
+            }
         };
 
         for block_hash in processed_block_hashes {
             self.getdata_request_info.remove(&block_hash);
             self.block_sync_queue.remove(&block_hash);
+            self.filter_sync_queue.remove(&block_hash);
+            self.getcfilters_request_info.remove(&block_hash);
         }
     }
 
@@ -789,7 +1150,11 @@ pub mod test {
     use crate::config::Config;
     use bitcoin::blockdata::constants::genesis_block;
     use bitcoin::consensus::deserialize;
+    use bitcoin::hash_types::{FilterHash, FilterHeader};
+    use bitcoin::hashes::Hash;
+    use bitcoin::util::bip158::BlockFilter;
     use bitcoin::Network;
+    use bitcoin::Script;
     use bitcoin::{
         network::message::NetworkMessage, network::message_blockdata::Inventory, BlockHash,
     };
@@ -805,6 +1170,7 @@ pub mod test {
             blockchain_state.genesis().clone().header,
             BlockchainManager::new(
                 Arc::new(Mutex::new(blockchain_state)),
+                config,
                 no_op_logger(),
                 RouterMetrics::new(&MetricsRegistry::default()),
             ),
@@ -1136,6 +1502,128 @@ pub mod test {
         }
     }
 
+    fn basic_filter(block: &Block) -> BlockFilter {
+        BlockFilter::new_script_filter(block, |_| Ok(Script::new()))
+            .expect("failed to build filter")
+    }
+
+    /// This test ensures that, with compact filters enabled, blocks are only requested once their
+    /// filter has been verified and that blocks which do not match their filter are rejected.
+    #[tokio::test]
+    async fn test_filter_driven_block_sync() {
+        let peer_addr = SocketAddr::from_str("127.0.0.1:8333").expect("bad address format");
+        let mut channel = TestChannel::new(vec![peer_addr]);
+        let config = ConfigBuilder::new()
+            .with_compact_block_filters(true)
+            .build();
+        let (genesis, mut blockchain_manager) = create_blockchain_manager(&config);
+
+        let encoded_block_1 = Vec::from_hex(BLOCK_1_ENCODED).expect("unable to make vec from hex");
+        let encoded_block_2 = Vec::from_hex(BLOCK_2_ENCODED).expect("unable to make vec from hex");
+        let block_1: Block = deserialize(&encoded_block_1).expect("failed to decoded block 1");
+        let block_2: Block = deserialize(&encoded_block_2).expect("failed to decoded block 2");
+        let genesis_block = genesis_block(Network::Bitcoin);
+        assert_eq!(genesis_block.block_hash(), genesis.block_hash());
+
+        let headers = vec![block_1.header, block_2.header];
+        {
+            let (added_headers, maybe_err) = blockchain_manager
+                .blockchain
+                .lock()
+                .await
+                .add_headers(&headers);
+            assert_eq!(added_headers.len(), headers.len());
+            assert!(maybe_err.is_none());
+        }
+        blockchain_manager
+            .enqueue_new_blocks_to_download(headers)
+            .await;
+        assert_eq!(blockchain_manager.filter_sync_queue.len(), 2);
+        assert!(blockchain_manager.block_sync_queue.is_empty());
+
+        blockchain_manager.add_peer(&mut channel, &peer_addr).await;
+        channel.pop_front().expect("getheaders not sent");
+
+        // No filter header is known yet, so the filter headers are requested from the genesis block.
+        blockchain_manager.sync_filters(&mut channel).await;
+        blockchain_manager.sync_blocks(&mut channel).await;
+        assert_eq!(channel.command_count(), 1);
+        let command = channel.pop_front().expect("getcfheaders not sent");
+        assert!(matches!(
+            command.message,
+            NetworkMessage::GetCFHeaders(GetCFHeaders { start_height: 0, stop_hash, .. }) if stop_hash == block_2.block_hash()
+        ));
+
+        let cfheaders = CFHeaders {
+            filter_type: BASIC_FILTER_TYPE,
+            stop_hash: block_2.block_hash(),
+            previous_filter_header: FilterHeader::default(),
+            filter_hashes: [&genesis_block, &block_1, &block_2]
+                .iter()
+                .map(|block| FilterHash::hash(&basic_filter(block).content))
+                .collect(),
+        };
+        assert!(blockchain_manager
+            .process_bitcoin_network_message(
+                &mut channel,
+                peer_addr,
+                &NetworkMessage::CFHeaders(cfheaders)
+            )
+            .await
+            .is_ok());
+
+        // The filters are requested once the filter headers are verified.
+        blockchain_manager.sync_filters(&mut channel).await;
+        assert_eq!(channel.command_count(), 2);
+        assert_eq!(blockchain_manager.getcfilters_request_info.len(), 2);
+        assert!(blockchain_manager.filter_sync_queue.is_empty());
+
+        for block in [&block_1, &block_2] {
+            let cfilter = CFilter {
+                filter_type: BASIC_FILTER_TYPE,
+                block_hash: block.block_hash(),
+                filter: basic_filter(block).content,
+            };
+            assert!(blockchain_manager
+                .process_bitcoin_network_message(
+                    &mut channel,
+                    peer_addr,
+                    &NetworkMessage::CFilter(cfilter)
+                )
+                .await
+                .is_ok());
+        }
+        assert_eq!(blockchain_manager.block_sync_queue.len(), 2);
+
+        blockchain_manager.sync_blocks(&mut channel).await;
+        assert_eq!(blockchain_manager.getdata_request_info.len(), 2);
+
+        let result = blockchain_manager
+            .received_block_message(&peer_addr, &block_1)
+            .await;
+        assert!(result.is_ok());
+
+        // The peer tampers with the outputs of block 2, which keeps the block hash the same.
+        let mut tampered_block_2 = block_2.clone();
+        tampered_block_2.txdata[0].output[0].script_pubkey = Script::from(vec![0x51]);
+        let result = blockchain_manager
+            .received_block_message(&peer_addr, &tampered_block_2)
+            .await;
+        assert!(matches!(
+            result,
+            Err(ReceivedBlockMessageError::BlockDoesNotMatchFilter(_))
+        ));
+        assert!(blockchain_manager
+            .filter_sync_queue
+            .contains(&block_2.block_hash()));
+        assert!(blockchain_manager
+            .blockchain
+            .lock()
+            .await
+            .get_block(&block_2.block_hash())
+            .is_none());
+    }
+
     /// This function tests to ensure that the BlockchainManager does not send out `getdata`
     /// requests when the block cache has reached the size threshold.
     #[tokio::test]
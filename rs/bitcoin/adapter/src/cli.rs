@@ -1,5 +1,6 @@
 //! A parser for the command line flags and configuration file.
 use crate::config::Config;
+use bitcoin::Network;
 use clap::Parser;
 use http::Uri;
 use std::{fs::File, io, path::PathBuf};
@@ -44,6 +45,13 @@ impl Cli {
                 ));
             }
         }
+
+        // A regtest chain has no DNS seeds, so the nodes of the local chain have to be provided.
+        if config.network == Network::Regtest && config.nodes.is_empty() {
+            return Err(CliError::Validation(
+                "At least one node needs to be provided for regtest".to_string(),
+            ));
+        }
         Ok(config)
     }
 }
@@ -52,8 +60,8 @@ impl Cli {
 pub mod test {
     use super::*;
     use crate::config::IncomingSource;
-    use bitcoin::Network;
     use std::io::Write;
+    use std::net::SocketAddr;
     use std::path::PathBuf;
     use std::str::FromStr;
     use tempfile::NamedTempFile;
@@ -91,6 +99,16 @@ pub mod test {
         "ipv6_only": true    
     }"#;
 
+    const REGTEST_CONFIG: &str = r#"{
+        "network": "regtest",
+        "nodes": ["127.0.0.1:18444"],
+        "compact_block_filters": true
+    }"#;
+
+    const REGTEST_NO_NODES_CONFIG: &str = r#"{
+        "network": "regtest"
+    }"#;
+
     const TESTNET_BAD_SOCKS_CONFIG: &str = r#"{
         "network": "testnet",
         "socks_proxy": "socks5.notaproxy.com"        
@@ -175,4 +193,37 @@ pub mod test {
             IncomingSource::Path(PathBuf::from("/tmp/ic-btc-adapter.socket"))
         );
     }
+
+    #[test]
+    fn test_cli_get_config_good_regtest_json() {
+        let mut tmpfile = NamedTempFile::new().expect("Failed to create tmp file");
+        writeln!(tmpfile, "{}", REGTEST_CONFIG).expect("Failed to write to tmp file");
+        let cli = Cli {
+            config: tmpfile.path().to_owned(),
+        };
+        let config = cli.get_config().unwrap();
+        assert_eq!(config.network, Network::Regtest);
+        assert_eq!(config.network_port(), 18444);
+        assert!(config.dns_seeds.is_empty());
+        assert_eq!(
+            config.nodes,
+            vec![SocketAddr::from_str("127.0.0.1:18444").unwrap()]
+        );
+        assert!(config.compact_block_filters);
+    }
+
+    #[test]
+    fn test_cli_regtest_without_nodes() {
+        let mut tmpfile = NamedTempFile::new().expect("Failed to create tmp file");
+        writeln!(tmpfile, "{}", REGTEST_NO_NODES_CONFIG).expect("Failed to write to tmp file");
+        let cli = Cli {
+            config: tmpfile.path().to_owned(),
+        };
+        let result = cli.get_config();
+        let matches = match result.unwrap_err() {
+            CliError::Validation(message) => message.contains("regtest"),
+            _ => false,
+        };
+        assert!(matches);
+    }
 }
@@ -0,0 +1,379 @@
+use bitcoin::{
+    hash_types::{FilterHash, FilterHeader},
+    hashes::Hash,
+    network::message_filter::{CFHeaders, CFilter},
+    util::bip158::BlockFilter,
+    Block, BlockHash,
+};
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// The only filter type defined by BIP158.
+/// https://github.com/bitcoin/bips/blob/master/bip-0158.mediawiki#block-filters
+pub const BASIC_FILTER_TYPE: u8 = 0;
+
+/// This constant represents the maximum number of filter hashes in a `cfheaders` message.
+/// https://github.com/bitcoin/bips/blob/master/bip-0157.mediawiki#getcfheaders
+pub const MAX_CFHEADERS_SIZE: usize = 2_000;
+
+/// The possible errors the `CompactFilterStore` may produce while processing
+/// `cfheaders` and `cfilter` messages or while checking blocks against their filters.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CompactFilterError {
+    /// The message refers to a filter type other than the basic filter.
+    #[error("Unsupported filter type {0}")]
+    UnsupportedFilterType(u8),
+    /// The number of filter hashes does not match the number of requested blocks.
+    #[error("Expected {expected} filter hashes, received {received}")]
+    UnexpectedNumberOfFilterHashes {
+        /// The number of blocks covered by the request.
+        expected: usize,
+        /// The number of filter hashes in the `cfheaders` message.
+        received: usize,
+    },
+    /// The previous filter header in the `cfheaders` message does not extend the verified
+    /// filter header chain.
+    #[error("Filter headers do not connect to the known filter header chain")]
+    FilterHeadersDoNotConnect,
+    /// A filter header for the block was already verified and the peer sent a different one.
+    #[error("Conflicting filter header for block {0}")]
+    ConflictingFilterHeader(BlockHash),
+    /// No filter hash has been verified for the block the filter belongs to.
+    #[error("No filter header known for block {0}")]
+    UnknownFilterHeader(BlockHash),
+    /// The filter does not hash to the value committed to in the filter header chain.
+    #[error("Filter for block {0} does not match its filter header")]
+    FilterDoesNotMatchHeader(BlockHash),
+    /// No verified filter is available for the block.
+    #[error("No filter known for block {0}")]
+    UnknownFilter(BlockHash),
+    /// The block contains output scripts that are not part of its filter.
+    #[error("Block {0} does not match its filter")]
+    BlockDoesNotMatchFilter(BlockHash),
+}
+
+/// This struct stores the BIP157 filter header chain and the BIP158 basic filters received
+/// from peers that advertise `NODE_COMPACT_FILTERS`.
+///
+/// Filter headers are chained in the same way as block headers, so a filter received from any
+/// peer can be checked against the filter hash committed to by the chain. Verified filters are
+/// kept until the corresponding block has been downloaded and checked against them.
+#[derive(Debug, Default)]
+pub struct CompactFilterStore {
+    /// This field stores the verified filter headers keyed by the hash of the block they commit to.
+    filter_headers: HashMap<BlockHash, FilterHeader>,
+    /// This field stores the filter hashes committed to by the verified filter headers.
+    filter_hashes: HashMap<BlockHash, FilterHash>,
+    /// This field stores the verified filters of the blocks that have yet to be downloaded.
+    filters: HashMap<BlockHash, BlockFilter>,
+}
+
+impl CompactFilterStore {
+    /// Returns true if the filter header of the given block has been verified.
+    pub fn has_filter_header(&self, block_hash: &BlockHash) -> bool {
+        self.filter_headers.contains_key(block_hash)
+    }
+
+    /// Returns true if a verified filter is available for the given block.
+    pub fn has_filter(&self, block_hash: &BlockHash) -> bool {
+        self.filters.contains_key(block_hash)
+    }
+
+    /// Processes a `cfheaders` message that was sent in response to a `getcfheaders` request.
+    ///
+    /// `previous_block_hash` is the block preceding the first block of the requested range
+    /// (`None` if the range starts at the genesis block) and `block_hashes` are the hashes of
+    /// the requested blocks in ascending height order.
+    pub fn received_cfheaders(
+        &mut self,
+        previous_block_hash: Option<&BlockHash>,
+        block_hashes: &[BlockHash],
+        message: &CFHeaders,
+    ) -> Result<(), CompactFilterError> {
+        if message.filter_type != BASIC_FILTER_TYPE {
+            return Err(CompactFilterError::UnsupportedFilterType(
+                message.filter_type,
+            ));
+        }
+
+        if message.filter_hashes.len() != block_hashes.len() {
+            return Err(CompactFilterError::UnexpectedNumberOfFilterHashes {
+                expected: block_hashes.len(),
+                received: message.filter_hashes.len(),
+            });
+        }
+
+        let expected_previous_header = match previous_block_hash {
+            Some(hash) => *self
+                .filter_headers
+                .get(hash)
+                .ok_or(CompactFilterError::FilterHeadersDoNotConnect)?,
+            None => FilterHeader::default(),
+        };
+        if message.previous_filter_header != expected_previous_header {
+            return Err(CompactFilterError::FilterHeadersDoNotConnect);
+        }
+
+        // Compute the complete chain first so that a conflicting header leaves the store untouched.
+        let mut previous_header = message.previous_filter_header;
+        let mut filter_headers = Vec::with_capacity(block_hashes.len());
+        for (block_hash, filter_hash) in block_hashes.iter().zip(message.filter_hashes.iter()) {
+            let header = filter_header(filter_hash, &previous_header);
+            if matches!(self.filter_headers.get(block_hash), Some(known) if *known != header) {
+                return Err(CompactFilterError::ConflictingFilterHeader(*block_hash));
+            }
+            filter_headers.push((*block_hash, *filter_hash, header));
+            previous_header = header;
+        }
+
+        for (block_hash, filter_hash, header) in filter_headers {
+            self.filter_headers.insert(block_hash, header);
+            self.filter_hashes.insert(block_hash, filter_hash);
+        }
+
+        Ok(())
+    }
+
+    /// Processes a `cfilter` message. The filter is only stored if it hashes to the
+    /// filter hash committed to by the verified filter header chain.
+    pub fn received_cfilter(&mut self, message: &CFilter) -> Result<(), CompactFilterError> {
+        if message.filter_type != BASIC_FILTER_TYPE {
+            return Err(CompactFilterError::UnsupportedFilterType(
+                message.filter_type,
+            ));
+        }
+
+        let expected_filter_hash = self
+            .filter_hashes
+            .get(&message.block_hash)
+            .ok_or(CompactFilterError::UnknownFilterHeader(message.block_hash))?;
+
+        if FilterHash::hash(&message.filter) != *expected_filter_hash {
+            return Err(CompactFilterError::FilterDoesNotMatchHeader(
+                message.block_hash,
+            ));
+        }
+
+        self.filters
+            .insert(message.block_hash, BlockFilter::new(&message.filter));
+        Ok(())
+    }
+
+    /// Checks that every output script of the block is part of the block's verified filter.
+    ///
+    /// The block hash only commits to the header, so this check ensures that the peer serving
+    /// the block did not tamper with its transactions. On success, the filter is dropped as it
+    /// is no longer needed.
+    pub fn check_block(&mut self, block: &Block) -> Result<(), CompactFilterError> {
+        let block_hash = block.block_hash();
+        let filter = self
+            .filters
+            .get(&block_hash)
+            .ok_or(CompactFilterError::UnknownFilter(block_hash))?;
+
+        // BIP158 excludes empty and `OP_RETURN` output scripts from the basic filter.
+        let scripts: Vec<&[u8]> = block
+            .txdata
+            .iter()
+            .flat_map(|tx| tx.output.iter())
+            .map(|output| &output.script_pubkey)
+            .filter(|script| !script.is_empty() && !script.is_op_return())
+            .map(|script| script.as_bytes())
+            .collect();
+
+        if !scripts.is_empty()
+            && !filter
+                .match_all(&block_hash, &mut scripts.into_iter())
+                .unwrap_or(false)
+        {
+            return Err(CompactFilterError::BlockDoesNotMatchFilter(block_hash));
+        }
+
+        self.filters.remove(&block_hash);
+        Ok(())
+    }
+
+    /// Removes the filter of the given block so it will be requested again.
+    pub fn remove_filter(&mut self, block_hash: &BlockHash) {
+        self.filters.remove(block_hash);
+    }
+
+    /// Only keeps the filters of blocks for which `keep` returns true. The filter header chain
+    /// is kept so that following `cfheaders` messages can still be connected to it.
+    pub fn retain_filters<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&BlockHash) -> bool,
+    {
+        self.filters.retain(|block_hash, _| keep(block_hash));
+    }
+
+    /// Removes all filters that have not been used yet.
+    pub fn clear_filters(&mut self) {
+        self.filters.clear();
+    }
+}
+
+/// Computes the filter header of a block from its filter hash and the previous filter header.
+/// https://github.com/bitcoin/bips/blob/master/bip-0157.mediawiki#filter-headers
+fn filter_header(filter_hash: &FilterHash, previous_filter_header: &FilterHeader) -> FilterHeader {
+    let mut header_data = [0u8; 64];
+    header_data[..32].copy_from_slice(&filter_hash[..]);
+    header_data[32..].copy_from_slice(&previous_filter_header[..]);
+    FilterHeader::hash(&header_data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::test_common::{block_1, block_2};
+    use bitcoin::{Script, TxOut};
+
+    fn basic_filter(block: &Block) -> BlockFilter {
+        BlockFilter::new_script_filter(block, |_| Ok(Script::new()))
+            .expect("failed to build filter")
+    }
+
+    fn cfheaders(previous_filter_header: FilterHeader, blocks: &[&Block]) -> CFHeaders {
+        CFHeaders {
+            filter_type: BASIC_FILTER_TYPE,
+            stop_hash: blocks.last().expect("no blocks").block_hash(),
+            previous_filter_header,
+            filter_hashes: blocks
+                .iter()
+                .map(|block| FilterHash::hash(&basic_filter(block).content))
+                .collect(),
+        }
+    }
+
+    fn cfilter(block: &Block) -> CFilter {
+        CFilter {
+            filter_type: BASIC_FILTER_TYPE,
+            block_hash: block.block_hash(),
+            filter: basic_filter(block).content,
+        }
+    }
+
+    /// Tests that filter headers are chained and that filters matching the chain are accepted.
+    #[test]
+    fn test_received_cfheaders_and_cfilter() {
+        let block_1 = block_1();
+        let block_2 = block_2();
+        let mut store = CompactFilterStore::default();
+
+        let message = cfheaders(FilterHeader::default(), &[&block_1]);
+        store
+            .received_cfheaders(None, &[block_1.block_hash()], &message)
+            .expect("failed to add the first filter header");
+        assert!(store.has_filter_header(&block_1.block_hash()));
+
+        // The second batch must connect to the filter header of block 1.
+        let bad_message = cfheaders(FilterHeader::default(), &[&block_2]);
+        assert_eq!(
+            store.received_cfheaders(
+                Some(&block_1.block_hash()),
+                &[block_2.block_hash()],
+                &bad_message
+            ),
+            Err(CompactFilterError::FilterHeadersDoNotConnect)
+        );
+
+        let previous_header = *store
+            .filter_headers
+            .get(&block_1.block_hash())
+            .expect("missing filter header");
+        let message = cfheaders(previous_header, &[&block_2]);
+        store
+            .received_cfheaders(
+                Some(&block_1.block_hash()),
+                &[block_2.block_hash()],
+                &message,
+            )
+            .expect("failed to add the second filter header");
+        assert!(store.has_filter_header(&block_2.block_hash()));
+
+        store
+            .received_cfilter(&cfilter(&block_2))
+            .expect("failed to add the filter");
+        assert!(store.has_filter(&block_2.block_hash()));
+
+        // A filter for another block does not hash to the committed filter hash.
+        let mut bad_filter = cfilter(&block_1);
+        bad_filter.block_hash = block_2.block_hash();
+        assert_eq!(
+            store.received_cfilter(&bad_filter),
+            Err(CompactFilterError::FilterDoesNotMatchHeader(
+                block_2.block_hash()
+            ))
+        );
+    }
+
+    /// Tests that a `cfheaders` message must contain one filter hash per requested block.
+    #[test]
+    fn test_received_cfheaders_with_wrong_number_of_hashes() {
+        let block_1 = block_1();
+        let block_2 = block_2();
+        let mut store = CompactFilterStore::default();
+
+        let message = cfheaders(FilterHeader::default(), &[&block_1]);
+        assert_eq!(
+            store.received_cfheaders(
+                None,
+                &[block_1.block_hash(), block_2.block_hash()],
+                &message
+            ),
+            Err(CompactFilterError::UnexpectedNumberOfFilterHashes {
+                expected: 2,
+                received: 1
+            })
+        );
+        assert!(!store.has_filter_header(&block_1.block_hash()));
+    }
+
+    /// Tests that a filter is rejected if no filter header commits to it.
+    #[test]
+    fn test_received_cfilter_without_filter_header() {
+        let block_1 = block_1();
+        let mut store = CompactFilterStore::default();
+        assert_eq!(
+            store.received_cfilter(&cfilter(&block_1)),
+            Err(CompactFilterError::UnknownFilterHeader(
+                block_1.block_hash()
+            ))
+        );
+    }
+
+    /// Tests that blocks are checked against their filters and that tampered outputs are detected.
+    #[test]
+    fn test_check_block() {
+        let block_1 = block_1();
+        let mut store = CompactFilterStore::default();
+        let message = cfheaders(FilterHeader::default(), &[&block_1]);
+        store
+            .received_cfheaders(None, &[block_1.block_hash()], &message)
+            .expect("failed to add the filter header");
+        store
+            .received_cfilter(&cfilter(&block_1))
+            .expect("failed to add the filter");
+
+        // The header stays the same, but an output was added to the coinbase transaction.
+        let mut tampered_block = block_1.clone();
+        tampered_block.txdata[0].output.push(TxOut {
+            value: 0,
+            script_pubkey: Script::from(vec![0x51, 0x52, 0x53]),
+        });
+        assert_eq!(
+            store.check_block(&tampered_block),
+            Err(CompactFilterError::BlockDoesNotMatchFilter(
+                block_1.block_hash()
+            ))
+        );
+        assert!(store.has_filter(&block_1.block_hash()));
+
+        assert_eq!(store.check_block(&block_1), Ok(()));
+        assert!(!store.has_filter(&block_1.block_hash()));
+        assert_eq!(
+            store.check_block(&block_1),
+            Err(CompactFilterError::UnknownFilter(block_1.block_hash()))
+        );
+    }
+}
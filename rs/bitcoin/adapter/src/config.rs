@@ -51,6 +51,14 @@ pub struct Config {
     /// Specifies which unix domain socket should be used for serving incoming requests.
     #[serde(default)]
     pub incoming_source: IncomingSource,
+    /// When this field is set to `true`, the adapter only connects to Bitcoin nodes that
+    /// serve BIP157/158 compact block filters. Blocks requested through `GetSuccessors` are
+    /// only downloaded once their filter has been verified against the filter header chain,
+    /// and each downloaded block is checked against its filter.
+    ///
+    /// A local `bitcoind` serves filters when started with `-blockfilterindex=1 -peerblockfilters=1`.
+    #[serde(default)]
+    pub compact_block_filters: bool,
 }
 
 /// Set the default idle seconds to one hour.
@@ -64,7 +72,18 @@ impl Config {
         match self.network {
             Network::Bitcoin => 8333,
             Network::Testnet => 18333,
-            _ => 8333,
+            Network::Signet => 38333,
+            Network::Regtest => 18444,
+        }
+    }
+
+    /// Creates a configuration for a local regtest chain. As a regtest chain has no
+    /// DNS seeds, the adapter only connects to the provided nodes.
+    pub fn regtest(nodes: Vec<SocketAddr>) -> Self {
+        Self {
+            network: Network::Regtest,
+            nodes,
+            ..Default::default()
         }
     }
 }
@@ -80,6 +99,7 @@ impl Default for Config {
             ipv6_only: false,
             logger: LoggerConfig::default(),
             incoming_source: Default::default(),
+            compact_block_filters: false,
         }
     }
 }
@@ -121,6 +141,11 @@ pub mod test {
             self
         }
 
+        pub fn with_compact_block_filters(mut self, compact_block_filters: bool) -> Self {
+            self.config.compact_block_filters = compact_block_filters;
+            self
+        }
+
         pub fn build(self) -> Config {
             self.config
         }
@@ -102,6 +102,8 @@ pub struct ConnectionManager {
     network_message_sender: Sender<(SocketAddr, NetworkMessage)>,
     /// This field is used for the version nonce generation.
     rng: StdRng,
+    /// When set, only nodes advertising `NODE_COMPACT_FILTERS` are valid connections.
+    compact_block_filters: bool,
     metrics: RouterMetrics,
 }
 
@@ -129,6 +131,7 @@ impl ConnectionManager {
             current_height: 0,
             connections: HashMap::with_capacity(max_connections),
             rng: StdRng::from_entropy(),
+            compact_block_filters: config.compact_block_filters,
             socks_proxy: config.socks_proxy.clone(),
             stream_event_sender,
             network_message_sender,
@@ -568,8 +571,10 @@ impl ConnectionManager {
     /// * The start height is at least the configured minimum.
     /// * The version is at least the configured minimum.
     /// * The services in the main message match the sender services.
+    /// * The node serves compact block filters if the adapter is configured to use them.
     fn validate_received_version(&self, message: &VersionMessage) -> bool {
         validate_services(&message.services)
+            && (!self.compact_block_filters || message.services.has(ServiceFlags::COMPACT_FILTERS))
             && message.start_height >= self.current_height as i32
             && message.version >= MINIMUM_VERSION_NUMBER
     }
@@ -791,6 +796,43 @@ mod test {
         assert!(!manager.validate_received_version(&version_message));
     }
 
+    #[test]
+    fn validate_received_version_compact_filters_required() {
+        let socket_1 = SocketAddr::from_str("127.0.0.1:18444").expect("bad address format");
+        let socket_2 = SocketAddr::from_str("127.0.0.1:18444").expect("bad address format");
+        let services = ServiceFlags::NETWORK | ServiceFlags::WITNESS;
+        let receiver = Address::new(&socket_1, services);
+        let sender = Address::new(&socket_2, ServiceFlags::NONE);
+        let mut version_message = VersionMessage::new(
+            services,
+            0,
+            receiver,
+            sender,
+            1,
+            String::from("test"),
+            60_000,
+        );
+
+        let config = ConfigBuilder::new()
+            .with_network(Network::Regtest)
+            .with_nodes(vec![socket_1])
+            .with_compact_block_filters(true)
+            .build();
+        let (network_message_sender, _network_message_receiver) =
+            channel::<(SocketAddr, NetworkMessage)>(DEFAULT_CHANNEL_BUFFER_SIZE);
+
+        let manager = ConnectionManager::new(
+            &config,
+            no_op_logger(),
+            network_message_sender,
+            RouterMetrics::new(&MetricsRegistry::default()),
+        );
+        assert!(!manager.validate_received_version(&version_message));
+
+        version_message.services = services | ServiceFlags::COMPACT_FILTERS;
+        assert!(manager.validate_received_version(&version_message));
+    }
+
     fn simple_handle(config: StreamConfig) -> JoinHandle<()> {
         tokio::task::spawn(async move {
             let _ = &config;
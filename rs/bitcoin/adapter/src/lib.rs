@@ -22,6 +22,9 @@ mod blockchainstate;
 pub mod cli;
 /// This module contains constants and types that are shared by many modules.
 mod common;
+/// This module contains the storage and verification of BIP157/158 compact block filters
+/// used to drive block downloads.
+mod compact_filters;
 /// This module contains the basic configuration struct used to start up an
 /// adapter instance.
 pub mod config;
@@ -40,8 +40,12 @@ pub fn start_router(
 
     let router_metrics = RouterMetrics::new(metrics_registry);
 
-    let mut blockchain_manager =
-        BlockchainManager::new(blockchain_state, logger.clone(), router_metrics.clone());
+    let mut blockchain_manager = BlockchainManager::new(
+        blockchain_state,
+        config,
+        logger.clone(),
+        router_metrics.clone(),
+    );
     let mut transaction_manager = TransactionManager::new(logger.clone(), metrics_registry);
     let mut connection_manager = ConnectionManager::new(
         config,
@@ -95,6 +95,17 @@ pub fn build_dashboard() -> Vec<u8> {
                     </thead>
                     <tbody>{}</tbody>
                 </table>
+                <h3>Replaced transactions</h3>
+                <table>
+                    <thead>
+                        <tr>
+                            <th>Txid</th>
+                            <th>Replaced by</th>
+                            <th>Submitted at</th>
+                        </tr>
+                    </thead>
+                    <tbody>{}</tbody>
+                </table>
                 <h3>Finalized retrieve BTC requests</h3>
                 <table>
                     <thead>
@@ -187,6 +198,7 @@ pub fn build_dashboard() -> Vec<u8> {
         build_pending_request_tx(),
         build_requests_in_flight_tx(),
         build_submitted_transactions(),
+        build_stuck_transactions(),
         build_finalized_requests(),
         build_available_utxos(),
         build_unconfirmed_change(),
@@ -391,6 +403,28 @@ pub fn build_submitted_transactions() -> String {
     })
 }
 
+pub fn build_stuck_transactions() -> String {
+    with_utf8_buffer(|buf| {
+        state::read_state(|s| {
+            for tx in &s.stuck_transactions {
+                let replaced_by = s
+                    .replacement_txid
+                    .get(&tx.txid)
+                    .map(|txid| txid_link(txid.as_slice()))
+                    .unwrap_or_default();
+                writeln!(
+                    buf,
+                    "<tr><td>{}</td><td>{}</td><td class=\"ts-class\">{}</td></tr>",
+                    txid_link(&tx.txid),
+                    replaced_by,
+                    tx.submitted_at,
+                )
+                .unwrap();
+            }
+        })
+    })
+}
+
 pub fn build_finalized_requests() -> String {
     with_utf8_buffer(|buf| {
         state::read_state(|s| {
@@ -39,6 +39,16 @@ const MIN_NANOS: u64 = 60 * SEC_NANOS;
 pub const MIN_PENDING_REQUESTS: usize = 20;
 pub const MAX_REQUESTS_PER_BATCH: usize = 100;
 
+/// The minimum fee increment (in millisatoshi per vbyte) of a replacement
+/// transaction. Bitcoin nodes do not relay a replacement that does not pay at
+/// least the minimum relay fee for its own size on top of the original fee.
+/// https://github.com/bitcoin/bips/blob/master/bip-0125.mediawiki
+pub const MIN_RELAY_FEE_PER_VBYTE: MillisatoshiPerByte = 1_000;
+
+/// The number of blocks after which the minter considers a submitted
+/// transaction stuck and replaces it with a transaction paying a higher fee.
+pub const MIN_RESUBMISSION_DELAY_BLOCKS: u32 = 36;
+
 /// The constants used to compute the minter's fee to cover its own cycle consumption.
 /// The values are set to cover the cycle cost on a 28-node subnet.
 pub const MINTER_FEE_PER_INPUT: u64 = 246;
@@ -340,6 +350,7 @@ async fn submit_pending_requests() {
                                     used_utxos,
                                     change_output: Some(req.change_output),
                                     submitted_at: ic_cdk::api::time(),
+                                    fee_per_vbyte: Some(fee_millisatoshi_per_vbyte),
                                 },
                             );
                         });
@@ -388,6 +399,7 @@ async fn finalize_requests() {
         let wait_time = finalization_time_estimate(s.min_confirmations, s.btc_network);
         s.submitted_transactions
             .iter()
+            .chain(s.stuck_transactions.iter())
             .any(|req| req.submitted_at + (wait_time.as_nanos() as u64) < now)
     });
 
@@ -417,9 +429,12 @@ async fn finalize_requests() {
     // Transactions whose change outpoint is present in the newly fetched UTXOs
     // can be finalized.  Note that all new minter transactions must have a
     // change output because minter always charges a fee for converting tokens.
+    // Stuck transactions are included because any transaction of a replacement
+    // chain can end up in a block.
     let confirmed_transactions: Vec<_> = state::read_state(|s| {
         s.submitted_transactions
             .iter()
+            .chain(s.stuck_transactions.iter())
             .filter_map(|tx| {
                 tx.change_output.as_ref().and_then(|out| {
                     new_utxos
@@ -439,6 +454,147 @@ async fn finalize_requests() {
     });
 }
 
+/// Replaces submitted transactions that did not get confirmed within
+/// [MIN_RESUBMISSION_DELAY_BLOCKS] blocks with transactions that spend the same
+/// UTXOs for the same requests, but pay a higher fee.
+async fn resubmit_transactions() {
+    let now = ic_cdk::api::time();
+
+    let stuck_transactions: Vec<_> = state::read_state(|s| {
+        let resubmission_delay =
+            finalization_time_estimate(MIN_RESUBMISSION_DELAY_BLOCKS, s.btc_network);
+        s.submitted_transactions
+            .iter()
+            .filter(|tx| tx.submitted_at + (resubmission_delay.as_nanos() as u64) < now)
+            .cloned()
+            .collect()
+    });
+
+    if stuck_transactions.is_empty() {
+        return;
+    }
+
+    let (btc_network, key_name, ecdsa_public_key) = match state::read_state(|s| {
+        s.ecdsa_public_key
+            .clone()
+            .map(|key| (s.btc_network, s.ecdsa_key_name.clone(), key))
+    }) {
+        Some(config) => config,
+        None => {
+            log!(
+                P0,
+                "unreachable: have submitted transactions but the ECDSA key is not initialized",
+            );
+            return;
+        }
+    };
+
+    let fee_millisatoshi_per_vbyte = match estimate_fee_per_vbyte().await {
+        Some(fee) => fee,
+        None => return,
+    };
+
+    let main_account = Account {
+        owner: ic_cdk::id(),
+        subaccount: None,
+    };
+    let main_address = address::account_to_bitcoin_address(&ecdsa_public_key, &main_account);
+
+    for old_tx in stuck_transactions {
+        // The replacement must pay more than the original transaction, even if
+        // the current fee percentiles went down.
+        let tx_fee_per_vbyte = match old_tx.fee_per_vbyte {
+            Some(prev_fee) => fee_millisatoshi_per_vbyte.max(prev_fee + MIN_RELAY_FEE_PER_VBYTE),
+            None => fee_millisatoshi_per_vbyte,
+        };
+
+        let outputs: Vec<_> = old_tx
+            .requests
+            .iter()
+            .map(|req| (req.address.clone(), req.amount))
+            .collect();
+
+        let (unsigned_tx, change_output) = match build_unsigned_transaction_from_inputs(
+            &old_tx.used_utxos,
+            outputs,
+            main_address.clone(),
+            tx_fee_per_vbyte,
+        ) {
+            Ok(tx) => tx,
+            Err(err) => {
+                log!(
+                    P0,
+                    "[resubmit_transactions]: failed to rebuild stuck transaction {}: {:?}",
+                    tx::DisplayTxid(&old_tx.txid),
+                    err
+                );
+                continue;
+            }
+        };
+
+        let new_txid = unsigned_tx.txid();
+        if new_txid == old_tx.txid {
+            // The fee did not change, there is nothing to replace.
+            continue;
+        }
+
+        let outpoint_account = state::read_state(|s| filter_output_accounts(s, &unsigned_tx));
+
+        let signed_tx = match sign_transaction(
+            key_name.clone(),
+            &ecdsa_public_key,
+            &outpoint_account,
+            unsigned_tx,
+        )
+        .await
+        {
+            Ok(tx) => tx,
+            Err(err) => {
+                log!(
+                    P0,
+                    "[resubmit_transactions]: failed to sign a BTC transaction: {}",
+                    err
+                );
+                continue;
+            }
+        };
+
+        match management::send_transaction(&signed_tx, btc_network).await {
+            Ok(()) => {
+                log!(
+                    P0,
+                    "[resubmit_transactions]: sent transaction {} to replace stuck transaction {} ({} msat/vbyte)",
+                    tx::DisplayTxid(&new_txid),
+                    tx::DisplayTxid(&old_tx.txid),
+                    tx_fee_per_vbyte,
+                );
+
+                state::mutate_state(|s| {
+                    state::audit::replace_transaction(
+                        s,
+                        old_tx.txid,
+                        state::SubmittedBtcTransaction {
+                            requests: old_tx.requests,
+                            txid: new_txid,
+                            used_utxos: old_tx.used_utxos,
+                            change_output: Some(change_output),
+                            submitted_at: ic_cdk::api::time(),
+                            fee_per_vbyte: Some(tx_fee_per_vbyte),
+                        },
+                    );
+                });
+            }
+            Err(err) => {
+                log!(
+                    P0,
+                    "[resubmit_transactions]: failed to send a bitcoin transaction: {}",
+                    err
+                );
+            }
+        }
+    }
+}
+
 /// Builds the minimal OutPoint -> Account map required to sign a transaction.
 fn filter_output_accounts(
     state: &state::CkBtcMinterState,
@@ -628,12 +784,6 @@ pub fn build_unsigned_transaction(
 ) -> Result<(tx::UnsignedTransaction, state::ChangeOutput, Vec<Utxo>), BuildTxError> {
     assert!(!outputs.is_empty());
 
-    /// Having a sequence number lower than (0xffffffff - 1) signals the use of replacement by fee.
-    /// It allows us to increase the fee of a transaction already sent to the mempool.
-    /// The rbf option is used in `resubmit_retrieve_btc`.
-    /// https://github.com/bitcoin/bips/blob/master/bip-0125.mediawiki
-    const SEQUENCE_RBF_ENABLED: u32 = 0xfffffffd;
-
     let amount = outputs.iter().map(|(_, amount)| amount).sum::<u64>();
 
     let input_utxos = greedy(amount, minter_utxos);
@@ -650,11 +800,49 @@ pub fn build_unsigned_transaction(
         }
     });
 
-    let inputs_value = utxos_guard.iter().map(|u| u.value).sum::<u64>();
+    let (unsigned_tx, change_output) =
+        build_unsigned_transaction_from_inputs(&utxos_guard, outputs, main_address, fee_per_vbyte)?;
 
-    debug_assert!(inputs_value >= amount);
+    Ok((
+        unsigned_tx,
+        change_output,
+        ScopeGuard::into_inner(utxos_guard),
+    ))
+}
 
-    let minter_fee = MINTER_FEE_PER_INPUT * utxos_guard.len() as u64
+/// Builds a transaction that moves BTC to the specified destination accounts
+/// spending exactly the specified UTXOs. The receivers pay the fee.
+///
+/// This function is used to build both new transactions and replacements of
+/// stuck transactions that must spend the same inputs as the original.
+///
+/// # Panics
+///
+/// This function panics if the `outputs` vector is empty as it indicates a bug
+/// in the caller's code.
+pub fn build_unsigned_transaction_from_inputs(
+    input_utxos: &[Utxo],
+    outputs: Vec<(BitcoinAddress, Satoshi)>,
+    main_address: BitcoinAddress,
+    fee_per_vbyte: u64,
+) -> Result<(tx::UnsignedTransaction, state::ChangeOutput), BuildTxError> {
+    assert!(!outputs.is_empty());
+
+    /// Having a sequence number lower than (0xffffffff - 1) signals the use of replacement by fee.
+    /// It allows us to increase the fee of a transaction already sent to the mempool.
+    /// The rbf option is used in `resubmit_transactions`.
+    /// https://github.com/bitcoin/bips/blob/master/bip-0125.mediawiki
+    const SEQUENCE_RBF_ENABLED: u32 = 0xfffffffd;
+
+    let amount = outputs.iter().map(|(_, amount)| amount).sum::<u64>();
+
+    let inputs_value = input_utxos.iter().map(|u| u.value).sum::<u64>();
+
+    if inputs_value < amount {
+        return Err(BuildTxError::NotEnoughFunds);
+    }
+
+    let minter_fee = MINTER_FEE_PER_INPUT * input_utxos.len() as u64
         + MINTER_FEE_PER_OUTPUT * (outputs.len() + 1) as u64
         + MINTER_FEE_CONSTANT;
 
@@ -682,7 +870,7 @@ pub fn build_unsigned_transaction(
     );
 
     let mut unsigned_tx = tx::UnsignedTransaction {
-        inputs: utxos_guard
+        inputs: input_utxos
             .iter()
             .map(|utxo| tx::UnsignedInput {
                 previous_output: utxo.outpoint.clone(),
@@ -720,11 +908,7 @@ pub fn build_unsigned_transaction(
         fee + unsigned_tx.outputs.iter().map(|u| u.value).sum::<u64>()
     );
 
-    Ok((
-        unsigned_tx,
-        change_output,
-        ScopeGuard::into_inner(utxos_guard),
-    ))
+    Ok((unsigned_tx, change_output))
 }
 
 /// Distributes an amount across the specified number of shares as fairly as
@@ -772,6 +956,7 @@ pub fn timer() {
 
                 submit_pending_requests().await;
                 finalize_requests().await;
+                resubmit_transactions().await;
             });
         }
         TaskType::RefreshFeePercentiles => {
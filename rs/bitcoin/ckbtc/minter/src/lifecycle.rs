@@ -3,4 +3,4 @@ pub mod init;
 pub use init::init;
 
 pub mod upgrade;
-pub use upgrade::post_upgrade;
+pub use upgrade::{post_upgrade, pre_upgrade};
@@ -1,11 +1,12 @@
 use crate::logs::P0;
 use crate::state::eventlog::{replay, Event};
-use crate::state::{replace_state, Mode};
+use crate::state::{read_state, replace_state, Mode};
 use crate::storage::{count_events, events, record_event};
 use candid::{CandidType, Deserialize};
 use ic_base_types::CanisterId;
 use ic_canister_log::log;
 use serde::Serialize;
+use std::cell::Cell;
 
 #[derive(CandidType, Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Default)]
 pub struct UpgradeArgs {
@@ -34,6 +35,83 @@ pub struct UpgradeArgs {
     pub kyt_principal: Option<CanisterId>,
 }
 
+/// The maximum number of instructions that the event log verification in
+/// `pre_upgrade` may consume, well below the instruction limit of upgrades.
+const MAX_VERIFICATION_INSTRUCTIONS: u64 = 20_000_000_000;
+
+/// Checks that replaying the event log reproduces the current minter state.
+///
+/// The minter rebuilds its state from the event log after the upgrade, so a
+/// state transition that did not end up in the log would silently disappear.
+/// A mismatch is recorded as an `EventLogMismatch` event instead of trapping:
+/// the upgrade fixing the mismatch must still be possible, and the event
+/// survives the upgrade and shows up in `get_events`. The verification is
+/// skipped if the log is too long to replay within
+/// `MAX_VERIFICATION_INSTRUCTIONS`.
+pub fn pre_upgrade() {
+    let start = ic_cdk::api::instruction_counter();
+
+    verify_event_log(|| ic_cdk::api::instruction_counter() - start < MAX_VERIFICATION_INSTRUCTIONS);
+
+    let end = ic_cdk::api::instruction_counter();
+
+    log!(
+        P0,
+        "[upgrade]: verifying the event log consumed {} instructions",
+        end - start
+    );
+}
+
+/// Replays the event log while `has_instructions_left` returns true and
+/// records an `EventLogMismatch` event if the result differs from the current
+/// state.
+pub fn verify_event_log(has_instructions_left: impl Fn() -> bool) {
+    let out_of_instructions = Cell::new(false);
+    let replayed = replay(events().take_while(|_| {
+        if has_instructions_left() {
+            true
+        } else {
+            out_of_instructions.set(true);
+            false
+        }
+    }));
+
+    if out_of_instructions.get() {
+        log!(
+            P0,
+            "[upgrade]: skipping the state verification because replaying {} events takes more than {} instructions",
+            count_events(),
+            MAX_VERIFICATION_INSTRUCTIONS
+        );
+        return;
+    }
+
+    let result = match replayed {
+        Ok(recovered_state) => read_state(|s| {
+            // A running timer can temporarily violate invariants.
+            if s.is_timer_running {
+                log!(
+                    P0,
+                    "[upgrade]: skipping the state verification because the timer is running"
+                );
+                return Ok(());
+            }
+            s.check_semantically_eq(&recovered_state).map_err(|msg| {
+                format!(
+                    "the state recovered from the event log does not match the current state: {}",
+                    msg
+                )
+            })
+        }),
+        Err(e) => Err(format!("failed to replay the event log: {:?}", e)),
+    };
+
+    if let Err(reason) = result {
+        log!(P0, "[upgrade]: {}", reason);
+        record_event(&Event::EventLogMismatch { reason });
+    }
+}
+
 pub fn post_upgrade(upgrade_args: Option<UpgradeArgs>) {
     if let Some(upgrade_args) = upgrade_args {
         log!(
@@ -59,6 +137,13 @@ pub fn post_upgrade(upgrade_args: Option<UpgradeArgs>) {
         ic_cdk::trap("KYT principal is not set");
     }
 
+    if let Err(msg) = state.check_invariants() {
+        ic_cdk::trap(&format!(
+            "[upgrade]: the state recovered from the event log is invalid: {}",
+            msg
+        ));
+    }
+
     replace_state(state);
 
     let end = ic_cdk::api::instruction_counter();
@@ -2,7 +2,7 @@ use candid::candid_method;
 use candid::Principal;
 use ic_canister_log::export as export_logs;
 use ic_canisters_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
-use ic_cdk_macros::{init, post_upgrade, query, update};
+use ic_cdk_macros::{init, post_upgrade, pre_upgrade, query, update};
 use ic_ckbtc_minter::dashboard::build_dashboard;
 use ic_ckbtc_minter::lifecycle::upgrade::UpgradeArgs;
 use ic_ckbtc_minter::lifecycle::{self, init::MinterArg};
@@ -92,6 +92,11 @@ fn timer() {
     ic_ckbtc_minter::timer();
 }
 
+#[pre_upgrade]
+fn pre_upgrade() {
+    lifecycle::upgrade::pre_upgrade();
+}
+
 #[post_upgrade]
 fn post_upgrade(minter_arg: Option<MinterArg>) {
     let mut upgrade_arg: Option<UpgradeArgs> = None;
@@ -66,6 +66,12 @@ pub fn encode_metrics(
             }) as f64,
         )?;
 
+    metrics.encode_gauge(
+        "ckbtc_minter_stuck_transactions",
+        state::read_state(|s| s.stuck_transactions.len()) as f64,
+        "Total number of submitted transactions that the minter replaced with a higher fee.",
+    )?;
+
     metrics.encode_gauge(
         "ckbtc_minter_stored_finalized_requests",
         state::read_state(|s| s.finalized_requests.len()) as f64,
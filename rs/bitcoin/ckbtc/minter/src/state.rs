@@ -87,6 +87,10 @@ pub struct SubmittedBtcTransaction {
     /// The tx output from the submitted transaction that the minter owns.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub change_output: Option<ChangeOutput>,
+    /// The fee per vbyte (in millisatoshi) that we used for the transaction.
+    /// Transactions submitted before the minter recorded fees have no value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_per_vbyte: Option<u64>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -237,6 +241,19 @@ pub struct CkBtcMinterState {
     /// BTC transactions waiting for finalization.
     pub submitted_transactions: Vec<SubmittedBtcTransaction>,
 
+    /// Transactions that did not get enough confirmations in time and were
+    /// replaced by a transaction with a higher fee. Any transaction of a
+    /// replacement chain can still end up in a block because all of them spend
+    /// the same UTXOs.
+    pub stuck_transactions: Vec<SubmittedBtcTransaction>,
+
+    /// Maps the ID of a stuck transaction to the ID of its replacement.
+    pub replacement_txid: BTreeMap<[u8; 32], [u8; 32]>,
+
+    /// Maps the ID of a replacement transaction to the ID of the transaction
+    /// it replaced.
+    pub rev_replacement_txid: BTreeMap<[u8; 32], [u8; 32]>,
+
     /// Finalized retrieve_btc requests for which we received enough confirmations.
     pub finalized_requests: VecDeque<FinalizedBtcRetrieval>,
 
@@ -548,28 +565,116 @@ impl CkBtcMinterState {
     }
 
     fn finalize_transaction(&mut self, txid: &[u8; 32]) {
-        if let Some(pos) = self
+        let finalized_tx = if let Some(pos) = self
             .submitted_transactions
             .iter()
             .position(|req| &req.txid == txid)
         {
-            let submitted_tx = self.submitted_transactions.swap_remove(pos);
-            for utxo in submitted_tx.used_utxos.iter() {
-                self.forget_utxo(utxo);
-            }
-            self.finalized_requests_count += submitted_tx.requests.len() as u64;
-            for request in submitted_tx.requests {
-                self.push_finalized_request(FinalizedBtcRetrieval {
-                    request,
-                    state: FinalizedStatus::Confirmed { txid: *txid },
-                });
-            }
+            self.submitted_transactions.swap_remove(pos)
+        } else if let Some(pos) = self
+            .stuck_transactions
+            .iter()
+            .position(|req| &req.txid == txid)
+        {
+            self.stuck_transactions.swap_remove(pos)
         } else {
             ic_cdk::trap(&format!(
                 "Attempted to finalized a non-existent transaction {}",
                 crate::tx::DisplayTxid(txid)
             ));
+        };
+
+        for utxo in finalized_tx.used_utxos.iter() {
+            self.forget_utxo(utxo);
+        }
+        self.finalized_requests_count += finalized_tx.requests.len() as u64;
+        for request in finalized_tx.requests {
+            self.push_finalized_request(FinalizedBtcRetrieval {
+                request,
+                state: FinalizedStatus::Confirmed { txid: *txid },
+            });
+        }
+
+        self.cleanup_tx_replacement_chain(txid);
+    }
+
+    /// Removes all transactions of the replacement chain containing the
+    /// confirmed transaction. The other transactions of the chain spend the
+    /// same UTXOs, so they can never be confirmed.
+    fn cleanup_tx_replacement_chain(&mut self, confirmed_txid: &[u8; 32]) {
+        let mut txids_to_remove = BTreeSet::new();
+
+        // Collect the transactions that the confirmed transaction replaced.
+        let mut txid = *confirmed_txid;
+        while let Some(prev_txid) = self.rev_replacement_txid.remove(&txid) {
+            self.replacement_txid.remove(&prev_txid);
+            txids_to_remove.insert(prev_txid);
+            txid = prev_txid;
+        }
+
+        // Collect the transactions that replaced the confirmed transaction.
+        let mut txid = *confirmed_txid;
+        while let Some(next_txid) = self.replacement_txid.remove(&txid) {
+            self.rev_replacement_txid.remove(&next_txid);
+            txids_to_remove.insert(next_txid);
+            txid = next_txid;
+        }
+
+        if txids_to_remove.is_empty() {
+            return;
+        }
+
+        self.submitted_transactions
+            .retain(|tx| !txids_to_remove.contains(&tx.txid));
+        self.stuck_transactions
+            .retain(|tx| !txids_to_remove.contains(&tx.txid));
+    }
+
+    /// Replaces a submitted transaction with a new transaction that spends the
+    /// same UTXOs for the same requests with a higher fee.  The old transaction
+    /// becomes stuck.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the old transaction is not a submitted
+    /// transaction or if both transactions have the same identifier.
+    pub(crate) fn replace_transaction(
+        &mut self,
+        old_txid: &[u8; 32],
+        new_tx: SubmittedBtcTransaction,
+    ) {
+        assert_ne!(
+            old_txid, &new_tx.txid,
+            "a transaction cannot replace itself"
+        );
+        assert!(!self.replacement_txid.contains_key(old_txid));
+
+        let pos = self
+            .submitted_transactions
+            .iter()
+            .position(|tx| &tx.txid == old_txid)
+            .unwrap_or_else(|| {
+                panic!(
+                    "Attempted to replace a non-existent transaction {}",
+                    crate::tx::DisplayTxid(old_txid)
+                )
+            });
+        let old_tx = self.submitted_transactions.swap_remove(pos);
+
+        self.replacement_txid.insert(*old_txid, new_tx.txid);
+        self.rev_replacement_txid.insert(new_tx.txid, *old_txid);
+        self.stuck_transactions.push(old_tx);
+        self.submitted_transactions.push(new_tx);
+    }
+
+    /// Returns the identifier of the first transaction of the replacement chain
+    /// containing the specified transaction.
+    pub fn original_txid(&self, txid: &[u8; 32]) -> [u8; 32] {
+        let mut txid = *txid;
+        while let Some(prev_txid) = self.rev_replacement_txid.get(&txid) {
+            txid = *prev_txid;
         }
+        txid
     }
 
     /// Removes a pending retrive_btc request with the specified block index.
@@ -735,6 +840,21 @@ impl CkBtcMinterState {
             "min_confirmations does not match"
         );
         ensure_eq!(self.ledger_id, other.ledger_id, "ledger_id does not match");
+        ensure_eq!(
+            self.tokens_minted,
+            other.tokens_minted,
+            "tokens_minted does not match"
+        );
+        ensure_eq!(
+            self.tokens_burned,
+            other.tokens_burned,
+            "tokens_burned does not match"
+        );
+        ensure_eq!(
+            self.finalized_requests_count,
+            other.finalized_requests_count,
+            "finalized_requests_count does not match"
+        );
         ensure_eq!(
             self.finalized_requests,
             other.finalized_requests,
@@ -791,6 +911,21 @@ impl CkBtcMinterState {
         let other_txs = as_sorted_vec(other.submitted_transactions.iter().cloned(), |tx| tx.txid);
         ensure_eq!(my_txs, other_txs, "submitted_transactions do not match");
 
+        let my_txs = as_sorted_vec(self.stuck_transactions.iter().cloned(), |tx| tx.txid);
+        let other_txs = as_sorted_vec(other.stuck_transactions.iter().cloned(), |tx| tx.txid);
+        ensure_eq!(my_txs, other_txs, "stuck_transactions do not match");
+
+        ensure_eq!(
+            self.replacement_txid,
+            other.replacement_txid,
+            "replacement_txid maps do not match"
+        );
+        ensure_eq!(
+            self.rev_replacement_txid,
+            other.rev_replacement_txid,
+            "rev_replacement_txid maps do not match"
+        );
+
         let my_requests = as_sorted_vec(self.pending_retrieve_btc_requests.iter().cloned(), |r| {
             r.block_index
         });
@@ -830,6 +965,9 @@ impl From<InitArgs> for CkBtcMinterState {
             pending_retrieve_btc_requests: Default::default(),
             requests_in_flight: Default::default(),
             submitted_transactions: Default::default(),
+            stuck_transactions: Default::default(),
+            replacement_txid: Default::default(),
+            rev_replacement_txid: Default::default(),
             finalized_requests: VecDeque::with_capacity(MAX_FINALIZED_REQUESTS),
             finalized_requests_count: 0,
             tokens_minted: 0,
@@ -11,10 +11,7 @@ use icrc_ledger_types::icrc1::account::Account;
 
 pub fn accept_retrieve_btc_request(state: &mut CkBtcMinterState, request: RetrieveBtcRequest) {
     record_event(&Event::AcceptedRetrieveBtcRequest(request.clone()));
-    state.pending_retrieve_btc_requests.push(request.clone());
-    if let Some(kyt_provider) = request.kyt_provider {
-        *state.owed_kyt_amount.entry(kyt_provider).or_insert(0) += state.kyt_fee;
-    }
+    state.push_back_pending_request(request);
 }
 
 pub fn add_utxos(
@@ -50,11 +47,33 @@ pub fn sent_transaction(state: &mut CkBtcMinterState, tx: SubmittedBtcTransactio
         utxos: tx.used_utxos.clone(),
         change_output: tx.change_output.clone(),
         submitted_at: tx.submitted_at,
+        fee_per_vbyte: tx.fee_per_vbyte,
     });
 
     state.push_submitted_transaction(tx);
 }
 
+pub fn replace_transaction(
+    state: &mut CkBtcMinterState,
+    old_txid: [u8; 32],
+    new_tx: SubmittedBtcTransaction,
+) {
+    record_event(&Event::ReplacedBtcTransaction {
+        old_txid,
+        new_txid: new_tx.txid,
+        change_output: new_tx
+            .change_output
+            .clone()
+            .expect("bug: all replacement transactions must have a change output"),
+        submitted_at: new_tx.submitted_at,
+        fee_per_vbyte: new_tx
+            .fee_per_vbyte
+            .expect("bug: all replacement transactions must have the fee"),
+    });
+
+    state.replace_transaction(&old_txid, new_tx);
+}
+
 pub fn confirm_transaction(state: &mut CkBtcMinterState, txid: &[u8; 32]) {
     record_event(&Event::ConfirmedBtcTransaction { txid: *txid });
     state.finalize_transaction(txid);
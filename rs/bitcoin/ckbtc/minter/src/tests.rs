@@ -1,10 +1,11 @@
 use crate::MINTER_FEE_CONSTANT;
 use crate::{
-    address::BitcoinAddress, build_unsigned_transaction, estimate_fee, fake_sign, greedy,
-    signature::EncodedSignature, tx, BuildTxError,
+    address::BitcoinAddress, build_unsigned_transaction, build_unsigned_transaction_from_inputs,
+    estimate_fee, fake_sign, greedy, signature::EncodedSignature, tx, BuildTxError,
 };
 use crate::{
     lifecycle::init::InitArgs,
+    state::eventlog::{replay, Event, ReplayLogError},
     state::{ChangeOutput, CkBtcMinterState, Mode, RetrieveBtcRequest, RetrieveBtcStatus},
 };
 use bitcoin::network::constants::Network as BtcNetwork;
@@ -263,6 +264,295 @@ fn test_no_zero_outputs() {
     assert_eq!(available_utxos.len(), 1);
 }
 
+#[test]
+fn test_replacement_pays_higher_fee() {
+    let input = Utxo {
+        outpoint: OutPoint {
+            txid: vec![0; 32],
+            vout: 0,
+        },
+        value: 1_000_000,
+        height: 10,
+    };
+    let mut available_utxos = BTreeSet::new();
+    available_utxos.insert(input.clone());
+
+    let minter_addr = BitcoinAddress::P2wpkhV0([0; 20]);
+    let out_addr = BitcoinAddress::P2wpkhV0([1; 20]);
+    let fee_per_vbyte = 10_000;
+
+    let (tx, change_output, used_utxos) = build_unsigned_transaction(
+        &mut available_utxos,
+        vec![(out_addr.clone(), 500_000)],
+        minter_addr.clone(),
+        fee_per_vbyte,
+    )
+    .expect("failed to build a transaction");
+    assert_eq!(used_utxos, vec![input]);
+
+    let (new_tx, new_change_output) = build_unsigned_transaction_from_inputs(
+        &used_utxos,
+        vec![(out_addr, 500_000)],
+        minter_addr,
+        fee_per_vbyte + crate::MIN_RELAY_FEE_PER_VBYTE,
+    )
+    .expect("failed to build a replacement transaction");
+
+    assert_ne!(tx.txid(), new_tx.txid());
+    assert_eq!(change_output, new_change_output);
+    assert_eq!(
+        tx.inputs
+            .iter()
+            .map(|i| &i.previous_output)
+            .collect::<Vec<_>>(),
+        new_tx
+            .inputs
+            .iter()
+            .map(|i| &i.previous_output)
+            .collect::<Vec<_>>()
+    );
+
+    let total_out = |tx: &tx::UnsignedTransaction| tx.outputs.iter().map(|o| o.value).sum::<u64>();
+    assert!(total_out(&new_tx) < total_out(&tx));
+}
+
+#[test]
+fn test_confirming_replaced_transaction_removes_chain() {
+    let account = Account {
+        owner: Principal::management_canister(),
+        subaccount: None,
+    };
+    let utxo = Utxo {
+        outpoint: OutPoint {
+            txid: vec![0; 32],
+            vout: 0,
+        },
+        value: 1_000_000,
+        height: 10,
+    };
+    let request = RetrieveBtcRequest {
+        amount: 500_000,
+        address: BitcoinAddress::P2wpkhV0([1; 20]),
+        block_index: 1,
+        received_at: 0,
+        kyt_provider: None,
+    };
+    let change_output = ChangeOutput {
+        vout: 1,
+        value: 500_000,
+    };
+    let replaced = |old_txid: [u8; 32], new_txid: [u8; 32], fee_per_vbyte: u64| {
+        Event::ReplacedBtcTransaction {
+            old_txid,
+            new_txid,
+            change_output: change_output.clone(),
+            submitted_at: 0,
+            fee_per_vbyte,
+        }
+    };
+
+    let events = vec![
+        Event::Init(InitArgs {
+            btc_network: Network::Regtest,
+            ecdsa_key_name: "".to_string(),
+            retrieve_btc_min_amount: 0,
+            ledger_id: CanisterId::from_u64(42),
+            max_time_in_queue_nanos: 0,
+            min_confirmations: None,
+            mode: Mode::GeneralAvailability,
+            kyt_fee: None,
+            kyt_principal: None,
+        }),
+        Event::ReceivedUtxos {
+            mint_txid: None,
+            to_account: account,
+            utxos: vec![utxo.clone()],
+        },
+        Event::AcceptedRetrieveBtcRequest(request.clone()),
+        Event::SentBtcTransaction {
+            request_block_indices: vec![request.block_index],
+            txid: [1; 32],
+            utxos: vec![utxo],
+            change_output: Some(change_output.clone()),
+            submitted_at: 0,
+            fee_per_vbyte: Some(10_000),
+        },
+        replaced([1; 32], [2; 32], 11_000),
+        replaced([2; 32], [3; 32], 12_000),
+    ];
+
+    let state = replay(events.clone().into_iter()).expect("failed to replay the log");
+    assert_eq!(state.submitted_transactions.len(), 1);
+    assert_eq!(state.submitted_transactions[0].txid, [3; 32]);
+    assert_eq!(state.submitted_transactions[0].fee_per_vbyte, Some(12_000));
+    assert_eq!(state.stuck_transactions.len(), 2);
+    assert_eq!(state.original_txid(&[3; 32]), [1; 32]);
+    // A stuck transaction cannot be replaced again.
+    assert!(matches!(
+        replay(
+            events
+                .iter()
+                .cloned()
+                .chain(std::iter::once(replaced([1; 32], [4; 32], 13_000)))
+        ),
+        Err(ReplayLogError::InconsistentLog(_))
+    ));
+
+    let state = replay(
+        events
+            .into_iter()
+            .chain(std::iter::once(Event::ConfirmedBtcTransaction {
+                txid: [2; 32],
+            })),
+    )
+    .expect("failed to replay the log");
+
+    assert!(state.submitted_transactions.is_empty());
+    assert!(state.stuck_transactions.is_empty());
+    assert!(state.replacement_txid.is_empty());
+    assert!(state.rev_replacement_txid.is_empty());
+    assert_eq!(
+        state.retrieve_btc_status(request.block_index),
+        RetrieveBtcStatus::Confirmed { txid: [2; 32] }
+    );
+}
+
+#[test]
+fn test_audit_log_replays_to_the_same_state() {
+    use crate::state::{audit, SubmittedBtcTransaction};
+    use crate::storage;
+
+    let init_args = InitArgs {
+        btc_network: Network::Regtest,
+        ecdsa_key_name: "".to_string(),
+        retrieve_btc_min_amount: 0,
+        ledger_id: CanisterId::from_u64(42),
+        max_time_in_queue_nanos: 0,
+        min_confirmations: None,
+        mode: Mode::GeneralAvailability,
+        kyt_fee: Some(1_000),
+        kyt_principal: Some(CanisterId::from_u64(43)),
+    };
+    storage::record_event(&Event::Init(init_args.clone()));
+    let mut state = CkBtcMinterState::from(init_args);
+
+    let check_replay = |state: &CkBtcMinterState| {
+        let recovered_state = replay(storage::events()).expect("failed to replay the log");
+        recovered_state
+            .check_invariants()
+            .expect("invariant check failed");
+        state
+            .check_semantically_eq(&recovered_state)
+            .expect("the replayed state does not match");
+    };
+
+    let account = Account {
+        owner: Principal::management_canister(),
+        subaccount: None,
+    };
+    let utxo = Utxo {
+        outpoint: OutPoint {
+            txid: vec![0; 32],
+            vout: 0,
+        },
+        value: 1_000_000,
+        height: 10,
+    };
+    audit::add_utxos(&mut state, Some(1), account, vec![utxo.clone()]);
+    check_replay(&state);
+
+    let request = RetrieveBtcRequest {
+        amount: 500_000,
+        address: BitcoinAddress::P2wpkhV0([1; 20]),
+        block_index: 2,
+        received_at: 0,
+        kyt_provider: Some(Principal::management_canister()),
+    };
+    audit::accept_retrieve_btc_request(&mut state, request.clone());
+    check_replay(&state);
+    assert_eq!(state.tokens_burned, request.amount);
+
+    let batch = state.build_batch(1);
+    assert_eq!(batch, vec![request.clone()]);
+    assert!(state.available_utxos.remove(&utxo));
+    let change_output = ChangeOutput {
+        vout: 1,
+        value: 500_000,
+    };
+    audit::sent_transaction(
+        &mut state,
+        SubmittedBtcTransaction {
+            requests: batch.clone(),
+            txid: [1; 32],
+            used_utxos: vec![utxo.clone()],
+            change_output: Some(change_output.clone()),
+            submitted_at: 0,
+            fee_per_vbyte: Some(10_000),
+        },
+    );
+    check_replay(&state);
+
+    audit::replace_transaction(
+        &mut state,
+        [1; 32],
+        SubmittedBtcTransaction {
+            requests: batch,
+            txid: [2; 32],
+            used_utxos: vec![utxo],
+            change_output: Some(change_output),
+            submitted_at: 1,
+            fee_per_vbyte: Some(11_000),
+        },
+    );
+    check_replay(&state);
+
+    audit::confirm_transaction(&mut state, &[2; 32]);
+    check_replay(&state);
+    assert_eq!(
+        state.retrieve_btc_status(request.block_index),
+        RetrieveBtcStatus::Confirmed { txid: [2; 32] }
+    );
+}
+
+#[test]
+fn test_event_log_mismatch_is_recorded_before_upgrade() {
+    use crate::lifecycle::upgrade::verify_event_log;
+    use crate::state::{mutate_state, replace_state};
+    use crate::storage;
+
+    let init_args = InitArgs {
+        btc_network: Network::Regtest,
+        ecdsa_key_name: "".to_string(),
+        retrieve_btc_min_amount: 0,
+        ledger_id: CanisterId::from_u64(42),
+        max_time_in_queue_nanos: 0,
+        min_confirmations: None,
+        mode: Mode::GeneralAvailability,
+        kyt_fee: Some(1_000),
+        kyt_principal: Some(CanisterId::from_u64(43)),
+    };
+    storage::record_event(&Event::Init(init_args.clone()));
+    replace_state(CkBtcMinterState::from(init_args));
+
+    // A consistent state does not produce any event.
+    verify_event_log(|| true);
+    assert_eq!(storage::count_events(), 1);
+
+    // Diverge the live state from the event log.
+    mutate_state(|s| s.tokens_minted += 1);
+    verify_event_log(|| true);
+
+    assert_eq!(storage::count_events(), 2);
+    match storage::events().last() {
+        Some(Event::EventLogMismatch { reason }) => {
+            assert!(reason.contains("tokens_minted"), "{}", reason)
+        }
+        event => panic!("expected an event log mismatch, got {:?}", event),
+    }
+    // The recorded mismatch does not prevent the minter from upgrading.
+    replay(storage::events()).expect("failed to replay the log");
+}
+
 #[test]
 fn blocklist_is_sorted() {
     use crate::blocklist::BTC_ADDRESS_BLOCKLIST;
@@ -136,6 +136,7 @@ impl Execution {
                 allocated_bytes,
                 allocated_message_bytes,
                 instance_stats,
+                canister_log_records,
             },
             deltas,
             instance_or_system_api,
@@ -199,6 +200,7 @@ impl Execution {
                     allocated_message_bytes,
                     num_instructions_left,
                     instance_stats,
+                    canister_log_records,
                 };
                 self.sandbox_manager.controller.execution_finished(
                     protocol::ctlsvc::ExecutionFinishedRequest {
@@ -224,6 +226,7 @@ impl Execution {
                     allocated_bytes,
                     allocated_message_bytes,
                     instance_stats,
+                    canister_log_records,
                 };
 
                 self.sandbox_manager.controller.execution_finished(
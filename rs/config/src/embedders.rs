@@ -61,6 +61,9 @@ pub const STABLE_MEMORY_DIRTY_PAGE_LIMIT: u64 = 8 * GiB / (PAGE_SIZE as u64);
 // Maximum number of stable memory pages that a single message execution
 // is allowed to access.
 pub const STABLE_MEMORY_ACCESSED_PAGE_LIMIT: u64 = 8 * GiB / (PAGE_SIZE as u64);
+// Default maximum size of the Wasm heap of canisters that declare a 64-bit
+// memory.
+pub const DEFAULT_MAX_WASM64_MEMORY_SIZE: NumBytes = NumBytes::new(8 * GiB);
 
 #[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 pub struct FeatureFlags {
@@ -68,6 +71,8 @@ pub struct FeatureFlags {
     /// Track dirty pages with a write barrier instead of the signal handler.
     pub write_barrier: FlagStatus,
     pub wasm_native_stable_memory: FlagStatus,
+    /// Accept modules that declare a 64-bit Wasm memory.
+    pub wasm64: FlagStatus,
 }
 
 impl FeatureFlags {
@@ -76,6 +81,7 @@ impl FeatureFlags {
             rate_limiting_of_debug_prints: FlagStatus::Enabled,
             write_barrier: FlagStatus::Disabled,
             wasm_native_stable_memory: FlagStatus::Disabled,
+            wasm64: FlagStatus::Disabled,
         }
     }
 }
@@ -129,6 +135,10 @@ pub struct Config {
     // can access.
     pub stable_memory_accessed_page_limit: NumPages,
 
+    /// The maximum size of the Wasm heap of canisters that declare a 64-bit
+    /// memory. Has no effect unless the `wasm64` feature flag is enabled.
+    pub max_wasm64_memory_size: NumBytes,
+
     /// Sandbox process eviction does not activate if the number of sandbox
     /// processes is below this threshold.
     pub min_sandbox_count: usize,
@@ -168,6 +178,7 @@ impl Config {
             feature_flags: FeatureFlags::const_default(),
             stable_memory_dirty_page_limit: NumPages::new(STABLE_MEMORY_DIRTY_PAGE_LIMIT),
             stable_memory_accessed_page_limit: NumPages::new(STABLE_MEMORY_ACCESSED_PAGE_LIMIT),
+            max_wasm64_memory_size: DEFAULT_MAX_WASM64_MEMORY_SIZE,
             min_sandbox_count: DEFAULT_MIN_SANDBOX_COUNT,
             max_sandbox_count: DEFAULT_MAX_SANDBOX_COUNT,
             max_sandbox_idle_time: DEFAULT_MAX_SANDBOX_IDLE_TIME,
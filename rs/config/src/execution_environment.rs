@@ -26,6 +26,12 @@ const GIB: u64 = MIB * 1024;
 /// canister's data and the deltas.
 const SUBNET_MEMORY_CAPACITY: NumBytes = NumBytes::new(450 * GIB);
 
+/// Once the subnet memory usage exceeds this threshold, new memory allocations
+/// of canisters reserve cycles for future storage payments. The amount of
+/// reserved cycles grows linearly from zero at the threshold to the maximum at
+/// the subnet memory capacity.
+const SUBNET_MEMORY_THRESHOLD: NumBytes = NumBytes::new(300 * GIB);
+
 /// This is the upper limit on how much memory can be used by all canister
 /// messages on a given subnet.
 ///
@@ -93,6 +99,11 @@ const QUERY_SCHEDULING_TIME_SLICE_PER_CANISTER: Duration = Duration::from_millis
 /// executions and user errors.
 const QUERY_CACHE_CAPACITY: NumBytes = NumBytes::new(100 * MIB);
 
+/// The upper limit on how long a query cache entry stays valid while the batch
+/// time advances. The entry is also invalidated earlier if the canister version
+/// or the canister cycles balance changes.
+const QUERY_CACHE_MAX_EXPIRY_TIME: Duration = Duration::from_secs(1);
+
 // The ID of the Bitcoin testnet canister.
 pub const BITCOIN_TESTNET_CANISTER_ID: &str = "g4xu7-jiaaa-aaaan-aaaaq-cai";
 
@@ -124,6 +135,10 @@ pub struct Config {
     /// the subnet.
     pub subnet_memory_capacity: NumBytes,
 
+    /// The subnet memory usage above which new memory allocations of canisters
+    /// reserve cycles for future storage payments.
+    pub subnet_memory_threshold: NumBytes,
+
     /// The maximum amount of logical storage available to canister messages
     /// across the whole subnet.
     pub subnet_message_memory_capacity: NumBytes,
@@ -215,6 +230,10 @@ pub struct Config {
     /// Query cache capacity in bytes
     pub query_cache_capacity: NumBytes,
 
+    /// The maximum time a query cache entry stays valid since its creation,
+    /// measured in batch time.
+    pub query_cache_max_expiry_time: Duration,
+
     /// Sandbox process eviction does not activate if the number of sandbox
     /// processes is below this threshold.
     pub min_sandbox_count: usize,
@@ -251,6 +270,7 @@ impl Default for Config {
             create_funds_whitelist: String::default(),
             max_instructions_for_message_acceptance_calls: MAX_INSTRUCTIONS_PER_MESSAGE_WITHOUT_DTS,
             subnet_memory_capacity: SUBNET_MEMORY_CAPACITY,
+            subnet_memory_threshold: SUBNET_MEMORY_THRESHOLD,
             subnet_message_memory_capacity: SUBNET_MESSAGE_MEMORY_CAPACITY,
             ingress_history_memory_capacity: INGRESS_HISTORY_MEMORY_CAPACITY,
             subnet_wasm_custom_sections_memory_capacity:
@@ -293,9 +313,10 @@ impl Default for Config {
                 testnet_canister_id: Some(bitcoin_testnet_canister_id),
                 mainnet_canister_id: Some(bitcoin_mainnet_canister_id),
             },
-            composite_queries: FlagStatus::Disabled,
+            composite_queries: FlagStatus::Enabled,
             query_caching: FlagStatus::Disabled,
             query_cache_capacity: QUERY_CACHE_CAPACITY,
+            query_cache_max_expiry_time: QUERY_CACHE_MAX_EXPIRY_TIME,
             min_sandbox_count: embedders::DEFAULT_MIN_SANDBOX_COUNT,
             max_sandbox_count: embedders::DEFAULT_MAX_SANDBOX_COUNT,
             max_sandbox_idle_time: embedders::DEFAULT_MAX_SANDBOX_IDLE_TIME,
@@ -379,6 +379,11 @@ pub struct CyclesAccountManagerConfig {
 
     /// Fee per byte for networking and consensus work done for a http request or response.
     pub http_request_per_byte_fee: Cycles,
+
+    /// The upper bound on the storage period that is prepaid by reserving
+    /// cycles when a canister allocates memory while the subnet memory usage
+    /// is above the threshold.
+    pub max_storage_reservation_period: Duration,
 }
 
 impl CyclesAccountManagerConfig {
@@ -407,6 +412,8 @@ impl CyclesAccountManagerConfig {
             ecdsa_signature_fee: ECDSA_SIGNATURE_FEE,
             http_request_baseline_fee: Cycles::new(400_000_000),
             http_request_per_byte_fee: Cycles::new(100_000),
+            // Roughly 10 years.
+            max_storage_reservation_period: Duration::from_secs(300_000_000),
         }
     }
 
@@ -435,6 +442,7 @@ impl CyclesAccountManagerConfig {
             ecdsa_signature_fee: ECDSA_SIGNATURE_FEE,
             http_request_baseline_fee: Cycles::new(0),
             http_request_per_byte_fee: Cycles::new(0),
+            max_storage_reservation_period: Duration::from_secs(0),
         }
     }
 }
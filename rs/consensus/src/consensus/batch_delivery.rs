@@ -28,7 +28,7 @@ use ic_types::{
         threshold_sig::ni_dkg::{NiDkgId, NiDkgTag, NiDkgTranscript},
     },
     messages::{CallbackId, Payload, RejectContext, Response},
-    CanisterId, Cycles, Height, PrincipalId, Randomness, ReplicaVersion, SubnetId,
+    CanisterId, Cycles, Height, NumBytes, PrincipalId, Randomness, ReplicaVersion, SubnetId,
 };
 use std::collections::BTreeMap;
 
@@ -76,6 +76,7 @@ pub fn deliver_batches(
                 );
                 // Compute consensus' responses to subnet calls.
                 let consensus_responses = generate_responses_to_subnet_calls(&block, log);
+                let canister_http_received_bytes = canister_http_received_bytes(&block);
 
                 if block.payload.is_summary() {
                     info!(log, "Delivering finalized batch at CUP height of {}", h);
@@ -146,6 +147,7 @@ pub fn deliver_batches(
                     registry_version: block.context.registry_version,
                     time: block.context.time,
                     consensus_responses,
+                    canister_http_received_bytes,
                 };
 
                 debug!(
@@ -343,6 +345,24 @@ pub fn generate_execution_responses_for_canister_http_responses(
         .collect()
 }
 
+/// Returns the number of bytes the adapter received for each canister http
+/// response included in the block.
+pub fn canister_http_received_bytes(block: &Block) -> BTreeMap<CallbackId, NumBytes> {
+    if block.payload.is_summary() {
+        return BTreeMap::new();
+    }
+    block
+        .payload
+        .as_ref()
+        .as_data()
+        .batch
+        .canister_http
+        .responses
+        .iter()
+        .map(|response| (response.content.id, response.content.received_bytes))
+        .collect()
+}
+
 struct TranscriptResults {
     low_threshold: Option<Result<NiDkgTranscript, String>>,
     high_threshold: Option<Result<NiDkgTranscript, String>>,
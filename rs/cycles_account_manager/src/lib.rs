@@ -61,6 +61,81 @@ impl std::fmt::Display for CyclesAccountManagerError {
     }
 }
 
+/// Describes the saturation of a resource such as the subnet memory.
+///
+/// The saturation is zero while the usage is below the threshold and grows
+/// linearly until the usage reaches the capacity. It is used to compute the
+/// amount of cycles that a canister has to reserve when it allocates the
+/// resource.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceSaturation {
+    usage: u64,
+    threshold: u64,
+    capacity: u64,
+}
+
+impl ResourceSaturation {
+    /// Creates a new resource saturation. The threshold and the usage are
+    /// capped at the capacity.
+    pub fn new(usage: u64, threshold: u64, capacity: u64) -> Self {
+        let threshold = threshold.min(capacity);
+        let usage = usage.min(capacity);
+        Self {
+            usage,
+            threshold,
+            capacity,
+        }
+    }
+
+    /// Creates a new resource saturation where all values are divided by the
+    /// given scaling factor. This is used when the available resource is split
+    /// equally between multiple execution threads.
+    pub fn new_scaled(usage: u64, threshold: u64, capacity: u64, scaling: u64) -> Self {
+        let scaling = scaling.max(1);
+        Self::new(usage / scaling, threshold / scaling, capacity / scaling)
+    }
+
+    pub fn usage(&self) -> u64 {
+        self.usage
+    }
+
+    pub fn threshold(&self) -> u64 {
+        self.threshold
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    /// Returns the part of the usage that is above the threshold.
+    pub fn usage_above_threshold(&self) -> u64 {
+        self.usage.saturating_sub(self.threshold)
+    }
+
+    /// Returns the saturation after allocating the given amount.
+    pub fn add(&self, allocation: u64) -> Self {
+        Self::new(
+            self.usage.saturating_add(allocation),
+            self.threshold,
+            self.capacity,
+        )
+    }
+
+    /// Returns the amount of the given allocation weighted by the saturation
+    /// of the resource while allocating it, i.e. the integral of the linear
+    /// saturation function between the current and the new usage. The result
+    /// is in the range `[0, allocation]`.
+    pub fn reservation_factor(&self, allocation: u64) -> u64 {
+        let range = (self.capacity - self.threshold) as u128;
+        if range == 0 {
+            return 0;
+        }
+        let before = self.usage_above_threshold() as u128;
+        let after = self.add(allocation).usage_above_threshold() as u128;
+        ((after * after - before * before) / (2 * range)) as u64
+    }
+}
+
 /// Handles any operation related to cycles accounting, such as charging (due to
 /// using system resources) or refunding unused cycles.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -500,6 +575,9 @@ impl CyclesAccountManager {
 
     /// Subtracts the cycles cost of using a `bytes` amount of memory.
     ///
+    /// The cost is paid from the reserved balance of the canister first and
+    /// only the remainder is paid from the main balance.
+    ///
     /// Note: The following charges for memory taken by the canister. It
     /// currently takes into account all the pages in the canister's heap and
     /// stable memory (among other things). This will be revised in the future
@@ -517,13 +595,34 @@ impl CyclesAccountManager {
         subnet_size: usize,
     ) -> Result<(), CanisterOutOfCyclesError> {
         let cycles_amount = self.memory_cost(bytes, duration, subnet_size);
+        let from_reserved_balance = cycles_amount.min(system_state.reserved_balance());
 
         // Can charge all the way to the empty account (zero cycles)
         self.consume_with_threshold(
             system_state,
-            cycles_amount,
+            cycles_amount - from_reserved_balance,
             Cycles::zero(),
             CyclesUseCase::Memory,
+        )?;
+        system_state.consume_reserved_cycles(from_reserved_balance, CyclesUseCase::Memory);
+        Ok(())
+    }
+
+    /// Returns the amount of cycles that a canister has to reserve when it
+    /// allocates `allocated_bytes` of memory while the subnet memory has the
+    /// given saturation. The reserved cycles pay for the storage of up to
+    /// `max_storage_reservation_period`, depending on the saturation.
+    pub fn storage_reservation_cycles(
+        &self,
+        allocated_bytes: NumBytes,
+        subnet_memory_saturation: &ResourceSaturation,
+        subnet_size: usize,
+    ) -> Cycles {
+        let weighted_bytes = subnet_memory_saturation.reservation_factor(allocated_bytes.get());
+        self.memory_cost(
+            NumBytes::from(weighted_bytes),
+            self.config.max_storage_reservation_period,
+            subnet_size,
         )
     }
 
@@ -859,6 +958,33 @@ impl CyclesAccountManager {
         Ok(())
     }
 
+    /// Charges a canister for the storage of its snapshots for the duration
+    /// specified.
+    pub fn charge_canister_for_snapshots(
+        &self,
+        log: &ReplicaLogger,
+        canister: &mut CanisterState,
+        snapshots_memory_usage: NumBytes,
+        duration_between_blocks: Duration,
+        subnet_size: usize,
+    ) -> Result<(), CanisterOutOfCyclesError> {
+        if let Err(err) = self.charge_for_memory(
+            &mut canister.system_state,
+            snapshots_memory_usage,
+            duration_between_blocks,
+            subnet_size,
+        ) {
+            info!(
+                log,
+                "Charging canister {} for snapshot storage failed with {}",
+                canister.canister_id(),
+                err
+            );
+            return Err(err);
+        }
+        Ok(())
+    }
+
     pub fn http_request_fee(
         &self,
         request_size: NumBytes,
@@ -877,6 +1003,66 @@ impl CyclesAccountManager {
             subnet_size,
         )
     }
+
+    /// Returns the part of the [`Self::http_request_fee`] that was reserved
+    /// for response bytes that were not received.
+    ///
+    /// The fee is charged upfront based on `response_size_limit`. Once the
+    /// response is delivered, the caller is refunded the difference to the fee
+    /// computed with the actual `response_size`.
+    pub fn http_request_fee_refund(
+        &self,
+        request_size: NumBytes,
+        response_size_limit: Option<NumBytes>,
+        response_size: NumBytes,
+        subnet_size: usize,
+    ) -> Cycles {
+        let response_size_limit =
+            response_size_limit.unwrap_or(NumBytes::new(MAX_CANISTER_HTTP_RESPONSE_BYTES));
+        let charged_fee =
+            self.http_request_fee(request_size, Some(response_size_limit), subnet_size);
+        let actual_fee = self.http_request_fee(
+            request_size,
+            Some(response_size.min(response_size_limit)),
+            subnet_size,
+        );
+        charged_fee - actual_fee
+    }
+
+    /// Returns the fee for an http request that is performed by a single
+    /// designated node instead of every node of the subnet.
+    ///
+    /// The unscaled [`Self::http_request_fee`] is priced for a subnet of
+    /// `reference_subnet_size` nodes, so the single-node fee is that fee
+    /// divided by the reference subnet size.
+    pub fn non_replicated_http_request_fee(
+        &self,
+        request_size: NumBytes,
+        response_size_limit: Option<NumBytes>,
+    ) -> Cycles {
+        let reference_subnet_size = self.config.reference_subnet_size;
+        self.http_request_fee(request_size, response_size_limit, reference_subnet_size)
+            / reference_subnet_size
+    }
+
+    /// Returns the part of the [`Self::non_replicated_http_request_fee`] that
+    /// was reserved for response bytes that were not received.
+    pub fn non_replicated_http_request_fee_refund(
+        &self,
+        request_size: NumBytes,
+        response_size_limit: Option<NumBytes>,
+        response_size: NumBytes,
+    ) -> Cycles {
+        let response_size_limit =
+            response_size_limit.unwrap_or(NumBytes::new(MAX_CANISTER_HTTP_RESPONSE_BYTES));
+        let charged_fee =
+            self.non_replicated_http_request_fee(request_size, Some(response_size_limit));
+        let actual_fee = self.non_replicated_http_request_fee(
+            request_size,
+            Some(response_size.min(response_size_limit)),
+        );
+        charged_fee - actual_fee
+    }
 }
 
 /// Encapsulates the payer and cost of inducting an ingress messages.
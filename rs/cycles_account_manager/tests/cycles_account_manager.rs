@@ -1,7 +1,7 @@
 use ic_base_types::NumSeconds;
 use ic_config::subnet_config::SubnetConfigs;
 use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
-use ic_cycles_account_manager::IngressInductionCost;
+use ic_cycles_account_manager::{IngressInductionCost, ResourceSaturation};
 use ic_ic00_types::{CanisterIdRecord, Payload, IC_00};
 use ic_interfaces::execution_environment::CanisterOutOfCyclesError;
 use ic_logger::replica_logger::no_op_logger;
@@ -765,3 +765,165 @@ fn consume_cycles_updates_consumed_cycles() {
         NominalCycles::from(1_000_000)
     );
 }
+
+#[test]
+fn resource_saturation_reservation_factor() {
+    // Below the threshold nothing needs to be reserved.
+    let saturation = ResourceSaturation::new(100, 1_000, 2_000);
+    assert_eq!(saturation.reservation_factor(900), 0);
+
+    // Allocations above the threshold are weighted by the saturation.
+    let saturation = ResourceSaturation::new(1_000, 1_000, 2_000);
+    assert_eq!(saturation.reservation_factor(1_000), 500);
+    let saturation = ResourceSaturation::new(1_500, 1_000, 2_000);
+    assert_eq!(saturation.reservation_factor(500), 375);
+
+    // Allocations crossing the threshold are weighted only above it.
+    let saturation = ResourceSaturation::new(500, 1_000, 2_000);
+    assert_eq!(saturation.reservation_factor(1_500), 500);
+
+    // Allocations beyond the capacity are capped.
+    let saturation = ResourceSaturation::new(2_000, 1_000, 2_000);
+    assert_eq!(saturation.reservation_factor(1_000), 0);
+
+    // A threshold equal to the capacity disables the reservation.
+    let saturation = ResourceSaturation::new(2_000, 2_000, 2_000);
+    assert_eq!(saturation.reservation_factor(1_000), 0);
+}
+
+#[test]
+fn storage_reservation_cycles_depend_on_saturation() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new()
+        .with_subnet_type(SubnetType::Application)
+        .build();
+    let gib = 1 << 30;
+    let bytes = NumBytes::from(gib);
+
+    let below_threshold = ResourceSaturation::new(0, 100 * gib, 200 * gib);
+    assert_eq!(
+        cycles_account_manager.storage_reservation_cycles(
+            bytes,
+            &below_threshold,
+            SMALL_APP_SUBNET_MAX_SIZE
+        ),
+        Cycles::zero()
+    );
+
+    let above_threshold = ResourceSaturation::new(150 * gib, 100 * gib, 200 * gib);
+    let reserved = cycles_account_manager.storage_reservation_cycles(
+        bytes,
+        &above_threshold,
+        SMALL_APP_SUBNET_MAX_SIZE,
+    );
+    assert!(reserved > Cycles::zero());
+
+    let more_saturated = ResourceSaturation::new(190 * gib, 100 * gib, 200 * gib);
+    assert!(
+        cycles_account_manager.storage_reservation_cycles(
+            bytes,
+            &more_saturated,
+            SMALL_APP_SUBNET_MAX_SIZE
+        ) > reserved
+    );
+}
+
+#[test]
+fn charge_for_memory_uses_reserved_balance_first() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new()
+        .with_subnet_type(SubnetType::Application)
+        .build();
+    let mut system_state = SystemStateBuilder::new()
+        .initial_cycles(INITIAL_CYCLES)
+        .build();
+    let gib = NumBytes::from(1 << 30);
+    let duration = Duration::from_secs(1);
+    let cost = cycles_account_manager.memory_cost(gib, duration, SMALL_APP_SUBNET_MAX_SIZE);
+
+    system_state.reserve_cycles(cost * 3_u64).unwrap();
+    let balance_before = system_state.balance();
+    cycles_account_manager
+        .charge_for_memory(&mut system_state, gib, duration, SMALL_APP_SUBNET_MAX_SIZE)
+        .unwrap();
+    assert_eq!(system_state.balance(), balance_before);
+    assert_eq!(system_state.reserved_balance(), cost * 2_u64);
+
+    // Once the reserved balance is depleted the main balance is charged.
+    system_state.consume_reserved_cycles(cost * 2_u64, CyclesUseCase::Memory);
+    cycles_account_manager
+        .charge_for_memory(&mut system_state, gib, duration, SMALL_APP_SUBNET_MAX_SIZE)
+        .unwrap();
+    assert_eq!(system_state.balance(), balance_before - cost);
+    assert_eq!(system_state.reserved_balance(), Cycles::zero());
+}
+
+#[test]
+fn http_request_fee_refund_covers_unused_response_bytes() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new()
+        .with_subnet_type(SubnetType::Application)
+        .build();
+    let request_size = NumBytes::from(100);
+    let limit = NumBytes::from(10_000);
+    let charged = cycles_account_manager.http_request_fee(
+        request_size,
+        Some(limit),
+        SMALL_APP_SUBNET_MAX_SIZE,
+    );
+
+    // Receiving fewer bytes than the limit refunds the difference.
+    let received = NumBytes::from(1_000);
+    let refund = cycles_account_manager.http_request_fee_refund(
+        request_size,
+        Some(limit),
+        received,
+        SMALL_APP_SUBNET_MAX_SIZE,
+    );
+    assert_eq!(
+        charged - refund,
+        cycles_account_manager.http_request_fee(
+            request_size,
+            Some(received),
+            SMALL_APP_SUBNET_MAX_SIZE
+        )
+    );
+
+    // Nothing is refunded if the whole limit was used.
+    assert_eq!(
+        cycles_account_manager.http_request_fee_refund(
+            request_size,
+            Some(limit),
+            limit,
+            SMALL_APP_SUBNET_MAX_SIZE,
+        ),
+        Cycles::zero()
+    );
+}
+
+#[test]
+fn non_replicated_http_request_fee_is_charged_for_a_single_node() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new()
+        .with_subnet_type(SubnetType::Application)
+        .build();
+    let request_size = NumBytes::from(100);
+    let limit = NumBytes::from(10_000);
+    let replicated_fee = cycles_account_manager.http_request_fee(
+        request_size,
+        Some(limit),
+        SMALL_APP_SUBNET_MAX_SIZE,
+    );
+    let non_replicated_fee =
+        cycles_account_manager.non_replicated_http_request_fee(request_size, Some(limit));
+    assert!(non_replicated_fee > Cycles::zero());
+    assert!(non_replicated_fee < replicated_fee);
+
+    // The refund is consistent with the single-node fee.
+    let received = NumBytes::from(1_000);
+    let refund = cycles_account_manager.non_replicated_http_request_fee_refund(
+        request_size,
+        Some(limit),
+        received,
+    );
+    assert_eq!(
+        non_replicated_fee - refund,
+        cycles_account_manager.non_replicated_http_request_fee(request_size, Some(received))
+    );
+}
@@ -36,6 +36,7 @@ fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -
         registry_version: RegistryVersion::from(1),
         time: UNIX_EPOCH,
         consensus_responses: vec![],
+        canister_http_received_bytes: BTreeMap::new(),
     }
 }
 
@@ -49,6 +50,7 @@ fn build_batch_with_full_state_hash(message_routing: &dyn MessageRouting) -> Bat
         registry_version: RegistryVersion::from(1),
         time: UNIX_EPOCH,
         consensus_responses: vec![],
+        canister_http_received_bytes: BTreeMap::new(),
     }
 }
 
@@ -1,12 +1,16 @@
 //! Standalone interface for testing application canisters.
 
-use crate::message::{msg_stream_from_file, Message};
+use crate::message::{
+    lines_from_file, parse_message, Context, ExpectedReject, ExpectedReply, Message,
+};
+use candid::IDLArgs;
 use hex::encode;
 use ic_config::{subnet_config::SubnetConfigs, Config};
 use ic_cycles_account_manager::CyclesAccountManager;
-use ic_error_types::{ErrorCode, UserError};
+use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_execution_environment::ExecutionServices;
 use ic_http_endpoints_metrics::MetricsHttpEndpoint;
+use ic_ic00_types::{CanisterIdRecord, Payload};
 use ic_interfaces::{execution_environment::IngressHistoryReader, messaging::MessageRouting};
 use ic_interfaces_state_manager::StateReader;
 use ic_messaging::MessageRoutingImpl;
@@ -63,20 +67,28 @@ pub struct DrunOptions {
     pub instruction_limit: Option<u64>,
 }
 
-/// Deliver a single message to the Message Routing layer
+/// Deliver a single message to the Message Routing layer and return its result
 fn deliver_message(
     msg: SignedIngress,
     message_routing: &dyn MessageRouting,
     ingress_hist_reader: &dyn IngressHistoryReader,
     extra_batches: u64,
-) {
+    time_offset: Duration,
+) -> Result<WasmResult, UserError> {
     let message_id = msg.id();
 
-    let _ = execute_ingress_message(message_routing, msg, &message_id, ingress_hist_reader);
+    let result = execute_ingress_message(
+        message_routing,
+        msg,
+        &message_id,
+        ingress_hist_reader,
+        time_offset,
+    );
     // print result after waiting, to not interleave the result
     // with debug.print messages from subsequent calls. revise after DFN-1269.
-    wait_extra_batches(message_routing, extra_batches);
+    wait_extra_batches(message_routing, extra_batches, time_offset);
     print_ingress_result(&message_id, ingress_hist_reader);
+    result
 }
 
 fn setup_logger(log_file: PathBuf) -> Logger {
@@ -181,7 +193,7 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
         subnet_id,
     };
 
-    let mut msg_stream = msg_stream_from_file(&msg_filename)?;
+    let lines = lines_from_file(&msg_filename)?;
     let log = match log_file {
         Some(log_file) => setup_logger(log_file),
         None => slog::Logger::root(slog::Discard, slog::o!()),
@@ -248,46 +260,178 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
         MaliciousFlags::default(),
     );
 
-    msg_stream.try_for_each(|parse_result| {
-        parse_result.map(|msg| match msg {
+    let mut context = Context::default();
+    // The result of the last ingress message or query, which expectations
+    // are checked against.
+    let mut last_result = None;
+    let mut failed_expectations = 0;
+    for line in lines {
+        let (line_number, line) = line?;
+        let msg = parse_message(&line, line_number as u64, &context)
+            .map_err(|e| format!("Line {}: {}", line_number, e))?;
+        match msg {
             Message::Install(msg) => {
-                deliver_message(
+                last_result = Some(deliver_message(
                     msg,
                     &message_routing,
                     ingress_hist_reader.as_ref(),
                     extra_batches,
-                );
+                    context.time_offset,
+                ));
             }
 
             Message::Query(q) => {
                 // NOTE: Data certificates aren't supported in drun yet.
                 // To support them, we'd need to do something similar to
                 // http_handler::get_latest_certified_state_and_data_certificate
-                print_query_result(query_handler.query(
-                    q,
-                    state_manager.get_latest_state().take(),
-                    Vec::new(),
-                ));
+                let result =
+                    query_handler.query(q, state_manager.get_latest_state().take(), Vec::new());
+                print_query_result(result.clone());
+                last_result = Some(result);
             }
 
             Message::Ingress(msg) => {
-                deliver_message(
+                last_result = Some(deliver_message(
                     msg,
                     &message_routing,
                     ingress_hist_reader.as_ref(),
                     extra_batches,
-                );
+                    context.time_offset,
+                ));
             }
-            Message::Create(msg) => {
-                deliver_message(
+            Message::Create(msg, name) => {
+                let result = deliver_message(
                     msg,
                     &message_routing,
                     ingress_hist_reader.as_ref(),
                     extra_batches,
+                    context.time_offset,
                 );
+                if let Some(name) = name {
+                    let canister_id = created_canister_id(&result).map_err(|e| {
+                        format!(
+                            "Line {}: Failed to create canister {}: {}",
+                            line_number, name, e
+                        )
+                    })?;
+                    context.canisters.insert(name, canister_id);
+                }
+                last_result = Some(result);
+            }
+            Message::ExpectReply(expected) => {
+                if let Err(e) = check_reply(&expected, last_result.as_ref()) {
+                    eprintln!("Line {}: {}", line_number, e);
+                    failed_expectations += 1;
+                }
+            }
+            Message::ExpectReject(expected) => {
+                if let Err(e) = check_reject(&expected, last_result.as_ref()) {
+                    eprintln!("Line {}: {}", line_number, e);
+                    failed_expectations += 1;
+                }
+            }
+            Message::AdvanceTime(duration) => context.time_offset += duration,
+            Message::Tick(batches) => {
+                wait_extra_batches(&message_routing, batches, context.time_offset)
+            }
+        }
+    }
+
+    if failed_expectations > 0 {
+        return Err(format!("{} expectation(s) failed", failed_expectations));
+    }
+    Ok(())
+}
+
+/// Returns the id of the canister created by a `create` message with the
+/// given result.
+fn created_canister_id(result: &Result<WasmResult, UserError>) -> Result<CanisterId, String> {
+    match result {
+        Ok(WasmResult::Reply(reply)) => CanisterIdRecord::decode(reply)
+            .map(|record| record.get_canister_id())
+            .map_err(|e| e.to_string()),
+        Ok(WasmResult::Reject(message)) => Err(format!("Reject: {}", message)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Checks that `result` is a reply with the expected data.
+fn check_reply(
+    expected: &ExpectedReply,
+    result: Option<&Result<WasmResult, UserError>>,
+) -> Result<(), String> {
+    let reply = match result {
+        Some(Ok(WasmResult::Reply(reply))) => reply,
+        Some(Ok(WasmResult::Reject(message))) => {
+            return Err(format!("Expected a reply, got reject: {}", message))
+        }
+        Some(Err(e)) => return Err(format!("Expected a reply, got error: {}", e)),
+        None => return Err("Expected a reply, but no message was executed before".to_string()),
+    };
+    match expected {
+        ExpectedReply::Bytes(bytes) if bytes == reply => Ok(()),
+        ExpectedReply::Bytes(bytes) => Err(format!(
+            "Expected reply 0x{}, got 0x{}",
+            encode(bytes),
+            encode(reply)
+        )),
+        // Encodings of the same values can differ, e.g. in the order of the
+        // type table, so the values are compared instead of the bytes.
+        ExpectedReply::Candid(bytes) => {
+            let expected =
+                IDLArgs::from_bytes(bytes).expect("The expected reply is encoded by drun");
+            match IDLArgs::from_bytes(reply) {
+                Ok(actual) if actual.args == expected.args => Ok(()),
+                Ok(actual) => Err(format!("Expected reply {}, got {}", expected, actual)),
+                Err(_) => Err(format!(
+                    "Expected reply {}, got 0x{} which is not Candid",
+                    expected,
+                    encode(reply)
+                )),
             }
-        })
-    })
+        }
+    }
+}
+
+/// Checks that `result` is a reject with the expected reject or error code.
+fn check_reject(
+    expected: &ExpectedReject,
+    result: Option<&Result<WasmResult, UserError>>,
+) -> Result<(), String> {
+    // A reject by the canister itself has a reject code but no error code.
+    let (reject_code, error_code, message) = match result {
+        Some(Ok(WasmResult::Reject(message))) => {
+            (RejectCode::CanisterReject, None, message.as_str())
+        }
+        Some(Err(e)) => (e.reject_code(), Some(e.code()), e.description()),
+        Some(Ok(WasmResult::Reply(reply))) => {
+            return Err(format!("Expected a reject, got reply 0x{}", encode(reply)))
+        }
+        None => return Err("Expected a reject, but no message was executed before".to_string()),
+    };
+    let (matches, expected) = match expected {
+        ExpectedReject::RejectCode(code) => (
+            *code == reject_code,
+            format!("reject code {}", *code as u64),
+        ),
+        ExpectedReject::ErrorCode(code) => {
+            (Some(*code) == error_code, format!("error code {}", code))
+        }
+    };
+    if matches {
+        return Ok(());
+    }
+    let actual = match error_code {
+        Some(error_code) => format!(
+            "reject code {} and error code {}",
+            reject_code as u64, error_code
+        ),
+        None => format!("reject code {}", reject_code as u64),
+    };
+    Err(format!(
+        "Expected {}, got {}: {}",
+        expected, actual, message
+    ))
 }
 
 fn print_query_result(res: Result<WasmResult, UserError>) {
@@ -333,7 +477,11 @@ fn get_random_seed() -> [u8; 32] {
     seed.try_into().unwrap()
 }
 
-fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -> Batch {
+fn build_batch(
+    message_routing: &dyn MessageRouting,
+    msgs: Vec<SignedIngress>,
+    time_offset: Duration,
+) -> Batch {
     Batch {
         batch_number: message_routing.expected_batch_height(),
         requires_full_state_hash: !msgs.is_empty(),
@@ -344,8 +492,9 @@ fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -
         randomness: Randomness::from(get_random_seed()),
         ecdsa_subnet_public_keys: BTreeMap::new(),
         registry_version: RegistryVersion::from(1),
-        time: time::current_time(),
+        time: time::current_time() + time_offset,
         consensus_responses: vec![],
+        canister_http_received_bytes: BTreeMap::new(),
     }
 }
 /// Block till the given ingress message has finished executing and
@@ -357,8 +506,9 @@ fn execute_ingress_message(
     msg: SignedIngress,
     msg_id: &MessageId,
     ingress_history: &dyn IngressHistoryReader,
+    time_offset: Duration,
 ) -> Result<WasmResult, UserError> {
-    let mut batch = build_batch(message_routing, vec![msg]);
+    let mut batch = build_batch(message_routing, vec![msg], time_offset);
     for _ in 0..MAX_BATCHES_UNTIL_RESPONSE {
         // In the first batch we try to send the ingress message itself. If it fails, we
         // repeat with the same batch.
@@ -369,7 +519,7 @@ fn execute_ingress_message(
         // potential inter-canister messages that the ingress message may have
         // triggered.
         if message_routing.deliver_batch(batch.clone()).is_ok() {
-            batch = build_batch(message_routing, vec![])
+            batch = build_batch(message_routing, vec![], time_offset)
         }
         sleep(WAIT_PER_BATCH);
 
@@ -415,10 +565,14 @@ fn execute_ingress_message(
 ///
 /// This is a temporary measure until DFN-1269 is resolved. In that ticket, we
 /// will actually try to wait until all messages have been executed.
-fn wait_extra_batches(message_routing: &dyn MessageRouting, extra_batches: u64) {
+fn wait_extra_batches(
+    message_routing: &dyn MessageRouting,
+    extra_batches: u64,
+    time_offset: Duration,
+) {
     for _ in 0..extra_batches {
         loop {
-            let batch = build_batch(message_routing, vec![]);
+            let batch = build_batch(message_routing, vec![], time_offset);
             let ok = message_routing.deliver_batch(batch).is_ok();
             sleep(WAIT_PER_BATCH);
             if ok {
@@ -431,6 +585,8 @@ fn wait_extra_batches(message_routing: &dyn MessageRouting, extra_batches: u64)
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::str::FromStr;
+
     #[test]
     fn test_get_random_seed() {
         let seed_1 = get_random_seed();
@@ -444,4 +600,49 @@ mod tests {
         }
         assert_ne!(equal, len);
     }
+
+    #[test]
+    fn test_check_reply() {
+        let reply = Ok(WasmResult::Reply(vec![1, 2]));
+        assert!(check_reply(&ExpectedReply::Bytes(vec![1, 2]), Some(&reply)).is_ok());
+        assert!(check_reply(&ExpectedReply::Bytes(vec![1]), Some(&reply)).is_err());
+        assert!(check_reply(&ExpectedReply::Bytes(vec![1, 2]), None).is_err());
+        assert!(check_reply(
+            &ExpectedReply::Bytes(vec![1, 2]),
+            Some(&Ok(WasmResult::Reject("no".to_string())))
+        )
+        .is_err());
+
+        let expected = ExpectedReply::Candid(
+            IDLArgs::from_str("(42 : nat, \"hello\")")
+                .unwrap()
+                .to_bytes()
+                .unwrap(),
+        );
+        let reply = candid::Encode!(&candid::Nat::from(42_u64), &"hello").unwrap();
+        assert!(check_reply(&expected, Some(&Ok(WasmResult::Reply(reply)))).is_ok());
+        let reply = candid::Encode!(&candid::Nat::from(43_u64), &"hello").unwrap();
+        assert!(check_reply(&expected, Some(&Ok(WasmResult::Reply(reply)))).is_err());
+        assert!(check_reply(&expected, Some(&Ok(WasmResult::Reply(vec![1, 2])))).is_err());
+    }
+
+    #[test]
+    fn test_check_reject() {
+        let reject = Ok(WasmResult::Reject("no".to_string()));
+        let error = Err(UserError::new(ErrorCode::CanisterCalledTrap, "trapped"));
+
+        let canister_reject = ExpectedReject::RejectCode(RejectCode::CanisterReject);
+        assert!(check_reject(&canister_reject, Some(&reject)).is_ok());
+        assert!(check_reject(&canister_reject, Some(&error)).is_err());
+        assert!(check_reject(&canister_reject, None).is_err());
+        assert!(check_reject(&canister_reject, Some(&Ok(WasmResult::Reply(vec![])))).is_err());
+
+        let canister_error = ExpectedReject::RejectCode(RejectCode::CanisterError);
+        assert!(check_reject(&canister_error, Some(&error)).is_ok());
+        assert!(check_reject(&canister_error, Some(&reject)).is_err());
+
+        let trap = ExpectedReject::ErrorCode(ErrorCode::CanisterCalledTrap);
+        assert!(check_reject(&trap, Some(&error)).is_ok());
+        assert!(check_reject(&trap, Some(&reject)).is_err());
+    }
 }
@@ -39,9 +39,6 @@ async fn drun_main() -> Result<(), String> {
             .value_of(ARG_CONF)
             .map(|arg| ConfigSource::File(PathBuf::from(arg)))
             .unwrap_or(ConfigSource::Default);
-        // Enable composite queries in drun by default to allow local
-        // development and testing.
-        default_config.hypervisor.composite_queries = FlagStatus::Enabled;
         default_config.hypervisor.rate_limiting_of_debug_prints = FlagStatus::Disabled;
         default_config.hypervisor.rate_limiting_of_heap_delta = FlagStatus::Disabled;
         default_config.hypervisor.rate_limiting_of_instructions = FlagStatus::Disabled;
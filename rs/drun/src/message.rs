@@ -1,20 +1,27 @@
 use super::CanisterId;
 
+use candid::IDLArgs;
 use hex::decode;
-use ic_ic00_types::{self as ic00, CanisterInstallMode, Payload};
+use ic_error_types::{ErrorCode, RejectCode};
+use ic_ic00_types::{
+    self as ic00, CanisterIdRecord, CanisterInstallMode, CanisterSettingsArgsBuilder, Payload,
+    ProvisionalTopUpCanisterArgs, UpdateSettingsArgs,
+};
 use ic_types::{
     messages::{SignedIngress, UserQuery},
     time::expiry_time_from_now,
-    PrincipalId, UserId,
+    PrincipalId, Time, UserId,
 };
 
 use std::{
+    collections::BTreeMap,
     convert::TryFrom,
     fmt,
     fs::File,
     io::{self, Read},
-    str::Chars,
+    str::{Chars, FromStr},
     string::FromUtf8Error,
+    time::Duration,
 };
 
 #[derive(Debug, PartialEq)]
@@ -22,7 +29,48 @@ pub(crate) enum Message {
     Ingress(SignedIngress),
     Query(UserQuery),
     Install(SignedIngress),
-    Create(SignedIngress),
+    /// Creates a canister and binds its id to the given name, if any.
+    Create(SignedIngress, Option<String>),
+    /// Checks that the previous message was replied to with the given data.
+    ExpectReply(ExpectedReply),
+    /// Checks that the previous message was rejected with the given code.
+    ExpectReject(ExpectedReject),
+    /// Advances the time of the subsequent batches.
+    AdvanceTime(Duration),
+    /// Executes the given number of empty batches.
+    Tick(u64),
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum ExpectedReply {
+    /// The reply has to be exactly these bytes.
+    Bytes(Vec<u8>),
+    /// The reply has to decode to the same Candid values as these bytes.
+    Candid(Vec<u8>),
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum ExpectedReject {
+    RejectCode(RejectCode),
+    ErrorCode(ErrorCode),
+}
+
+/// The state of a drun run that messages are parsed in.
+#[derive(Default)]
+pub(crate) struct Context {
+    /// Canisters created by `create <name>`, which can be referred to by name
+    /// instead of by id.
+    pub(crate) canisters: BTreeMap<String, CanisterId>,
+    /// How far the time has been advanced by `advance-time`.
+    pub(crate) time_offset: Duration,
+}
+
+impl Context {
+    /// The expiry time of messages, relative to the time of the next batch,
+    /// so that messages don't expire once the time has been advanced.
+    fn expiry_time(&self) -> Time {
+        expiry_time_from_now() + self.time_offset
+    }
 }
 
 #[derive(Debug)]
@@ -123,9 +171,11 @@ impl<R: Read> Iterator for LineIterator<R> {
     }
 }
 
-pub(crate) fn msg_stream_from_file(
+/// Returns the non-empty lines of the file that aren't comments, together with
+/// their line numbers.
+pub(crate) fn lines_from_file(
     filename: &str,
-) -> Result<impl Iterator<Item = Result<Message, String>>, String> {
+) -> Result<impl Iterator<Item = Result<(usize, String), String>>, String> {
     let f = File::open(filename).map_err(|e| e.to_string())?;
     let line_iterator = LineIterator::new(f);
 
@@ -137,14 +187,12 @@ pub(crate) fn msg_stream_from_file(
             _ => true,
         })
         .map(|(i, line)| match line {
-            Ok(line) => {
-                parse_message(&line, i as u64).map_err(|e| format!("Line {}: {}", i + 1, e))
-            }
-            Err(e) => Err(format!("Error while reading line {}: {}", i, e)),
+            Ok(line) => Ok((i + 1, line)),
+            Err(e) => Err(format!("Error while reading line {}: {}", i + 1, e)),
         }))
 }
 
-fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
+pub(crate) fn parse_message(s: &str, nonce: u64, context: &Context) -> Result<Message, String> {
     let s = s.trim_end();
     let tokens: Vec<&str> = s.splitn(4, char::is_whitespace).collect();
 
@@ -153,7 +201,7 @@ fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
         ["ingress", canister_id, method_name, payload] => {
             use ic_test_utilities::types::messages::SignedIngressBuilder;
 
-            let canister_id = parse_canister_id(canister_id)?;
+            let canister_id = parse_canister_id(canister_id, context)?;
             let method_name = validate_method_name(method_name)?;
             let method_payload = parse_octet_string(payload)?;
 
@@ -164,27 +212,52 @@ fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
                 .method_name(method_name)
                 .method_payload(method_payload)
                 .nonce(nonce)
+                .expiry_time(context.expiry_time())
                 .build();
             Ok(Message::Ingress(signed_ingress))
         }
         ["query", canister_id, method_name, payload] => Ok(Message::Query(UserQuery {
             source: UserId::from(PrincipalId::new_anonymous()),
-            receiver: parse_canister_id(canister_id)?,
+            receiver: parse_canister_id(canister_id, context)?,
             method_name: validate_method_name(method_name)?,
             method_payload: parse_octet_string(payload)?,
-            ingress_expiry: expiry_time_from_now().as_nanos_since_unix_epoch(),
+            ingress_expiry: context.expiry_time().as_nanos_since_unix_epoch(),
             nonce: Some(nonce.to_le_bytes().to_vec()),
         })),
-        ["create"] => parse_create(nonce),
+        ["create"] => parse_create(nonce, None, context),
+        ["create", name] => parse_create(nonce, Some(name), context),
         ["install", canister_id, wasm_file, payload] => {
-            parse_install(nonce, canister_id, payload, wasm_file, "install")
+            parse_install(nonce, canister_id, payload, wasm_file, "install", context)
         }
         ["reinstall", canister_id, wasm_file, payload] => {
-            parse_install(nonce, canister_id, payload, wasm_file, "reinstall")
+            parse_install(nonce, canister_id, payload, wasm_file, "reinstall", context)
         }
         ["upgrade", canister_id, wasm_file, payload] => {
-            parse_install(nonce, canister_id, payload, wasm_file, "upgrade")
+            parse_install(nonce, canister_id, payload, wasm_file, "upgrade", context)
+        }
+        ["cycles", canister_id, amount] => parse_top_up(nonce, canister_id, amount, context),
+        ["stop", canister_id] => {
+            parse_canister_management(nonce, ic00::Method::StopCanister, canister_id, context)
+        }
+        ["start", canister_id] => {
+            parse_canister_management(nonce, ic00::Method::StartCanister, canister_id, context)
         }
+        ["delete", canister_id] => {
+            parse_canister_management(nonce, ic00::Method::DeleteCanister, canister_id, context)
+        }
+        ["update-settings", canister_id, ..] => {
+            parse_update_settings(nonce, canister_id, s.split_whitespace().skip(2), context)
+        }
+        // The expected reply may contain whitespace, so it is the rest of the line.
+        ["expect-reply", _, ..] => {
+            parse_expected_reply(s["expect-reply".len()..].trim_start()).map(Message::ExpectReply)
+        }
+        ["expect-reject", code] => parse_expected_reject(code).map(Message::ExpectReject),
+        ["advance-time", duration] => parse_duration(duration).map(Message::AdvanceTime),
+        ["tick", batches] => batches
+            .parse()
+            .map(Message::Tick)
+            .map_err(|e| format!("Invalid number of batches {}: {}", batches, e)),
         _ => Err(format!(
             "Failed to parse line {}, don't have a pattern to match this with",
             s
@@ -192,8 +265,11 @@ fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
     }
 }
 
-fn parse_canister_id(canister_id: &str) -> Result<CanisterId, String> {
-    use std::str::FromStr;
+/// Parses a canister id, or the name of a canister created by `create <name>`.
+fn parse_canister_id(canister_id: &str, context: &Context) -> Result<CanisterId, String> {
+    if let Some(id) = context.canisters.get(canister_id) {
+        return Ok(*id);
+    }
     match PrincipalId::from_str(canister_id) {
         Ok(id) => match CanisterId::new(id) {
             Ok(id) => Ok(id),
@@ -209,17 +285,27 @@ fn parse_canister_id(canister_id: &str) -> Result<CanisterId, String> {
     }
 }
 
-fn parse_create(nonce: u64) -> Result<Message, String> {
+fn parse_create(nonce: u64, name: Option<&str>, context: &Context) -> Result<Message, String> {
     use ic_test_utilities::types::messages::SignedIngressBuilder;
 
+    if let Some(name) = name {
+        if !is_identifier(name) {
+            return Err(format!("Illegal canister name: {}.", name));
+        }
+        if context.canisters.contains_key(name) {
+            return Err(format!("Canister name {} is already in use.", name));
+        }
+    }
+
     let signed_ingress = SignedIngressBuilder::new()
         .method_name(ic00::Method::ProvisionalCreateCanisterWithCycles)
         .canister_id(ic00::IC_00)
         .method_payload(ic00::ProvisionalCreateCanisterWithCyclesArgs::new(None, None).encode())
         .nonce(nonce)
+        .expiry_time(context.expiry_time())
         .build();
 
-    Ok(Message::Create(signed_ingress))
+    Ok(Message::Create(signed_ingress, name.map(String::from)))
 }
 
 fn parse_install(
@@ -228,6 +314,7 @@ fn parse_install(
     payload: &str,
     wasm_file: &str,
     mode: &str,
+    context: &Context,
 ) -> Result<Message, String> {
     use ic_test_utilities::types::messages::SignedIngressBuilder;
 
@@ -238,7 +325,7 @@ fn parse_install(
         .read_to_end(&mut wasm_data)
         .map_err(|e| e.to_string())?;
 
-    let canister_id = parse_canister_id(canister_id)?;
+    let canister_id = parse_canister_id(canister_id, context)?;
     let payload = parse_octet_string(payload)?;
 
     let signed_ingress = SignedIngressBuilder::new()
@@ -259,11 +346,192 @@ fn parse_install(
             .encode(),
         )
         .nonce(nonce)
+        .expiry_time(context.expiry_time())
         .build();
     Ok(Message::Install(signed_ingress))
 }
 
-fn validate_method_name(method_name: &str) -> Result<String, String> {
+/// Builds an ingress message that calls `method` of the management canister.
+fn management_ingress(
+    nonce: u64,
+    method: ic00::Method,
+    payload: Vec<u8>,
+    context: &Context,
+) -> SignedIngress {
+    use ic_test_utilities::types::messages::SignedIngressBuilder;
+
+    SignedIngressBuilder::new()
+        .canister_id(ic00::IC_00)
+        .method_name(method)
+        .method_payload(payload)
+        .nonce(nonce)
+        .expiry_time(context.expiry_time())
+        .build()
+}
+
+fn parse_top_up(
+    nonce: u64,
+    canister_id: &str,
+    amount: &str,
+    context: &Context,
+) -> Result<Message, String> {
+    let canister_id = parse_canister_id(canister_id, context)?;
+    let amount = amount
+        .parse()
+        .map_err(|e| format!("Invalid amount of cycles {}: {}", amount, e))?;
+    Ok(Message::Ingress(management_ingress(
+        nonce,
+        ic00::Method::ProvisionalTopUpCanister,
+        ProvisionalTopUpCanisterArgs::new(canister_id, amount).encode(),
+        context,
+    )))
+}
+
+/// Parses the management canister calls that take only a canister id, i.e.
+/// `stop`, `start` and `delete`.
+fn parse_canister_management(
+    nonce: u64,
+    method: ic00::Method,
+    canister_id: &str,
+    context: &Context,
+) -> Result<Message, String> {
+    let canister_id = parse_canister_id(canister_id, context)?;
+    Ok(Message::Ingress(management_ingress(
+        nonce,
+        method,
+        CanisterIdRecord::from(canister_id).encode(),
+        context,
+    )))
+}
+
+/// Parses the settings of `update-settings`, given as `<name>=<value>`
+/// pairs. The controllers are given as a comma-separated list.
+fn parse_update_settings<'a>(
+    nonce: u64,
+    canister_id: &str,
+    settings: impl Iterator<Item = &'a str>,
+    context: &Context,
+) -> Result<Message, String> {
+    fn parse_value<T: FromStr>(name: &str, value: &str) -> Result<T, String>
+    where
+        T::Err: fmt::Display,
+    {
+        value
+            .parse()
+            .map_err(|e| format!("Invalid value {} of setting {}: {}", value, name, e))
+    }
+
+    let canister_id = parse_canister_id(canister_id, context)?;
+    let mut builder = CanisterSettingsArgsBuilder::new();
+    for setting in settings {
+        let (name, value) = setting
+            .split_once('=')
+            .ok_or_else(|| format!("Illegal setting {}, expected <name>=<value>.", setting))?;
+        builder = match name {
+            "controllers" => builder.with_controllers(
+                value
+                    .split(',')
+                    .filter(|controller| !controller.is_empty())
+                    .map(|controller| parse_principal_id(controller, context))
+                    .collect::<Result<_, _>>()?,
+            ),
+            "compute_allocation" => builder.with_compute_allocation(parse_value(name, value)?),
+            "memory_allocation" => builder.with_memory_allocation(parse_value(name, value)?),
+            "freezing_threshold" => builder.with_freezing_threshold(parse_value(name, value)?),
+            "wasm_memory_limit" => builder.with_wasm_memory_limit(parse_value(name, value)?),
+            "reserved_cycles_limit" => {
+                builder.with_reserved_cycles_limit(parse_value(name, value)?)
+            }
+            _ => return Err(format!("Unknown setting {}.", name)),
+        };
+    }
+    Ok(Message::Ingress(management_ingress(
+        nonce,
+        ic00::Method::UpdateSettings,
+        UpdateSettingsArgs::new(canister_id, builder.build()).encode(),
+        context,
+    )))
+}
+
+/// Parses a principal id, or the name of a canister created by
+/// `create <name>`.
+fn parse_principal_id(principal_id: &str, context: &Context) -> Result<PrincipalId, String> {
+    match context.canisters.get(principal_id) {
+        Some(canister_id) => Ok(canister_id.get()),
+        None => PrincipalId::from_str(principal_id).map_err(|err| {
+            format!(
+                "Failed to convert {} to principal id with {}",
+                principal_id, err
+            )
+        }),
+    }
+}
+
+/// Parses the expected reply, given either as an octet string or as Candid
+/// values in textual form, e.g. `(42 : nat, "hello")`.
+fn parse_expected_reply(s: &str) -> Result<ExpectedReply, String> {
+    if s.starts_with('(') {
+        let args =
+            IDLArgs::from_str(s).map_err(|e| format!("Invalid Candid values {}: {}", s, e))?;
+        let bytes = args
+            .to_bytes()
+            .map_err(|e| format!("Failed to encode Candid values {}: {}", s, e))?;
+        Ok(ExpectedReply::Candid(bytes))
+    } else {
+        parse_octet_string(s).map(ExpectedReply::Bytes)
+    }
+}
+
+/// Parses the expected reject, given either as a reject code (e.g. `5`) or as
+/// an error code (e.g. `IC0503`).
+fn parse_expected_reject(code: &str) -> Result<ExpectedReject, String> {
+    let expected = match code.strip_prefix("IC") {
+        Some(error_code) => error_code
+            .parse::<u64>()
+            .ok()
+            .and_then(|error_code| ErrorCode::try_from(error_code).ok())
+            .map(ExpectedReject::ErrorCode),
+        None => code
+            .parse::<u64>()
+            .ok()
+            .and_then(|reject_code| RejectCode::try_from(reject_code).ok())
+            .map(ExpectedReject::RejectCode),
+    };
+    expected.ok_or_else(|| {
+        format!(
+            "Illegal reject code {}, expected a reject code (e.g. 5) or an error code (e.g. IC0503).",
+            code
+        )
+    })
+}
+
+/// Parses a duration given as an integer followed by one of the units `ns`,
+/// `us`, `ms`, `s`, `m`, `h` or `d`, e.g. `90s`.
+fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let unit_start = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(duration.len());
+    let (value, unit) = duration.split_at(unit_start);
+    let value: u64 = value
+        .parse()
+        .map_err(|_| format!("Illegal duration {}.", duration))?;
+    let nanos_per_unit: u64 = match unit {
+        "ns" => 1,
+        "us" => 1_000,
+        "ms" => 1_000_000,
+        "s" => 1_000_000_000,
+        "m" => 60 * 1_000_000_000,
+        "h" => 60 * 60 * 1_000_000_000,
+        "d" => 24 * 60 * 60 * 1_000_000_000,
+        _ => return Err(format!("Illegal unit of duration {}.", duration)),
+    };
+    value
+        .checked_mul(nanos_per_unit)
+        .map(Duration::from_nanos)
+        .ok_or_else(|| format!("Duration {} is too long.", duration))
+}
+
+fn is_identifier(s: &str) -> bool {
     fn is_ident_start(c: char) -> bool {
         c.is_ascii() && (c.is_alphabetic() || c == '_')
     }
@@ -272,11 +540,14 @@ fn validate_method_name(method_name: &str) -> Result<String, String> {
         c.is_ascii() && (c.is_alphanumeric() || c == '_')
     }
 
-    let mut chars = method_name.chars();
+    let mut chars = s.chars();
     let is_legal_start = chars.next().map(is_ident_start).unwrap_or(false);
     let is_legal_tail = chars.all(is_ident_tail);
+    is_legal_start && is_legal_tail
+}
 
-    if !(is_legal_start && is_legal_tail) {
+fn validate_method_name(method_name: &str) -> Result<String, String> {
+    if !is_identifier(method_name) {
         Err(format!("Illegal method name: {}.", method_name))
     } else {
         Ok(String::from(method_name))
@@ -383,7 +654,7 @@ mod tests {
             "ingress {} write \"payload \\x0a\\b00010001\"",
             APP_CANISTER_URL
         );
-        let parsed_message = parse_message(s, 0).unwrap();
+        let parsed_message = parse_message(s, 0, &Context::default()).unwrap();
         let expiry_time = match &parsed_message {
             Message::Ingress(signed_ingress) => signed_ingress.expiry_time(),
             _ => panic!(
@@ -406,7 +677,7 @@ mod tests {
     #[test]
     fn test_parse_message_hex_payload_succeeds() {
         let s = &format!("ingress {} write 0x010203", APP_CANISTER_URL);
-        let parsed_message = parse_message(s, 0).unwrap();
+        let parsed_message = parse_message(s, 0, &Context::default()).unwrap();
         let expiry_time = match &parsed_message {
             Message::Ingress(signed_ingress) => signed_ingress.expiry_time(),
             _ => panic!(
@@ -427,7 +698,7 @@ mod tests {
 
         let s = &format!("query {} read 0x010203", APP_CANISTER_URL);
         let nonce: u64 = 0;
-        let parsed_message = parse_message(s, 0).unwrap();
+        let parsed_message = parse_message(s, 0, &Context::default()).unwrap();
         let ingress_expiry = match &parsed_message {
             Message::Query(query) => query.ingress_expiry,
             _ => panic!(
@@ -449,25 +720,146 @@ mod tests {
     #[test]
     fn test_parse_message_invalid_escapes_fails() {
         let s = &format!("query {} read \"\\xzz\"", APP_CANISTER_URL);
-        assert!(parse_message(s, 0).is_err());
+        assert!(parse_message(s, 0, &Context::default()).is_err());
 
         let s = &format!("query {} read \"\\b01\"", APP_CANISTER_URL);
-        assert!(parse_message(s, 0).is_err());
+        assert!(parse_message(s, 0, &Context::default()).is_err());
 
         let s = &format!("query {} read \"\\x1\"", APP_CANISTER_URL);
-        assert!(parse_message(s, 0).is_err());
+        assert!(parse_message(s, 0, &Context::default()).is_err());
 
         let s = &format!("query {} read \"\\b2\"", APP_CANISTER_URL);
-        assert!(parse_message(s, 0).is_err());
+        assert!(parse_message(s, 0, &Context::default()).is_err());
     }
 
     #[test]
     fn test_illegal_method_name_must_fail() {
         let s = &format!("query {} 0read \"\\xzz\"", APP_CANISTER_URL);
-        assert!(parse_message(s, 0).is_err());
+        assert!(parse_message(s, 0, &Context::default()).is_err());
 
         let s = &format!("query {} üread \"\\xzz\"", APP_CANISTER_URL);
-        assert!(parse_message(s, 0).is_err());
+        assert!(parse_message(s, 0, &Context::default()).is_err());
+    }
+
+    #[test]
+    fn test_parse_message_resolves_canister_names() {
+        let mut context = Context::default();
+        context
+            .canisters
+            .insert("counter".to_string(), canister_test_id(APP_CANISTER_ID));
+        let parsed_message = parse_message("query counter read 0x01", 0, &context).unwrap();
+        match parsed_message {
+            Message::Query(query) => {
+                assert_eq!(query.receiver, canister_test_id(APP_CANISTER_ID))
+            }
+            _ => panic!(
+                "parse_message() returned an unexpected message type: {:?}",
+                parsed_message
+            ),
+        }
+
+        assert!(parse_message("query unknown read 0x01", 0, &context).is_err());
+        assert!(parse_message("create counter", 0, &context).is_err());
+        assert!(parse_message("create 0counter", 0, &context).is_err());
+        match parse_message("create ledger", 0, &context).unwrap() {
+            Message::Create(_, name) => assert_eq!(name, Some("ledger".to_string())),
+            message => panic!(
+                "parse_message() returned an unexpected message type: {:?}",
+                message
+            ),
+        }
+    }
+
+    #[test]
+    fn test_parse_expectations() {
+        let context = Context::default();
+        assert_eq!(
+            parse_message("expect-reply 0x0102", 0, &context).unwrap(),
+            Message::ExpectReply(ExpectedReply::Bytes(vec![1, 2]))
+        );
+        assert_eq!(
+            parse_message("expect-reply \"a b\"", 0, &context).unwrap(),
+            Message::ExpectReply(ExpectedReply::Bytes(b"a b".to_vec()))
+        );
+        assert_eq!(
+            parse_message("expect-reply (42 : nat, \"a b\")", 0, &context).unwrap(),
+            Message::ExpectReply(ExpectedReply::Candid(
+                IDLArgs::from_str("(42 : nat, \"a b\")")
+                    .unwrap()
+                    .to_bytes()
+                    .unwrap()
+            ))
+        );
+        assert!(parse_message("expect-reply (42 : nat", 0, &context).is_err());
+
+        assert_eq!(
+            parse_message("expect-reject 5", 0, &context).unwrap(),
+            Message::ExpectReject(ExpectedReject::RejectCode(RejectCode::CanisterError))
+        );
+        assert_eq!(
+            parse_message("expect-reject IC0503", 0, &context).unwrap(),
+            Message::ExpectReject(ExpectedReject::ErrorCode(ErrorCode::CanisterCalledTrap))
+        );
+        assert!(parse_message("expect-reject 6", 0, &context).is_err());
+        assert!(parse_message("expect-reject IC9999", 0, &context).is_err());
+    }
+
+    #[test]
+    fn test_parse_time_directives() {
+        let context = Context::default();
+        assert_eq!(
+            parse_message("advance-time 90s", 0, &context).unwrap(),
+            Message::AdvanceTime(Duration::from_secs(90))
+        );
+        assert_eq!(
+            parse_message("advance-time 2d", 0, &context).unwrap(),
+            Message::AdvanceTime(Duration::from_secs(2 * 24 * 60 * 60))
+        );
+        assert_eq!(
+            parse_message("advance-time 5ms", 0, &context).unwrap(),
+            Message::AdvanceTime(Duration::from_millis(5))
+        );
+        assert!(parse_message("advance-time 5", 0, &context).is_err());
+        assert!(parse_message("advance-time s", 0, &context).is_err());
+        assert!(parse_message("advance-time 5w", 0, &context).is_err());
+
+        assert_eq!(
+            parse_message("tick 3", 0, &context).unwrap(),
+            Message::Tick(3)
+        );
+        assert!(parse_message("tick -1", 0, &context).is_err());
+    }
+
+    #[test]
+    fn test_parse_management_directives() {
+        let context = Context::default();
+        for line in [
+            format!("cycles {} 1000000", APP_CANISTER_URL),
+            format!("stop {}", APP_CANISTER_URL),
+            format!("start {}", APP_CANISTER_URL),
+            format!("delete {}", APP_CANISTER_URL),
+            format!(
+                "update-settings {} controllers={},{} freezing_threshold=100",
+                APP_CANISTER_URL,
+                APP_CANISTER_URL,
+                PrincipalId::new_anonymous()
+            ),
+        ] {
+            match parse_message(&line, 0, &context).unwrap() {
+                Message::Ingress(ingress) => assert_eq!(ingress.canister_id(), ic00::IC_00),
+                message => panic!(
+                    "parse_message() returned an unexpected message type: {:?}",
+                    message
+                ),
+            }
+        }
+
+        let s = &format!("cycles {} many", APP_CANISTER_URL);
+        assert!(parse_message(s, 0, &context).is_err());
+        let s = &format!("update-settings {} controllers", APP_CANISTER_URL);
+        assert!(parse_message(s, 0, &context).is_err());
+        let s = &format!("update-settings {} color=blue", APP_CANISTER_URL);
+        assert!(parse_message(s, 0, &context).is_err());
     }
 
     #[test]
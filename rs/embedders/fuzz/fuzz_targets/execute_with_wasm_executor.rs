@@ -1,5 +1,6 @@
 #![no_main]
 use ic_config::{embedders::Config, flag_status::FlagStatus, subnet_config::SchedulerConfig};
+use ic_cycles_account_manager::ResourceSaturation;
 use ic_embedders::{
     wasm_executor::{WasmExecutor, WasmExecutorImpl},
     CompilationCache, WasmExecutionInput, WasmtimeEmbedder,
@@ -97,6 +98,7 @@ fn setup_wasm_execution_input(func_ref: FuncRef) -> WasmExecutionInput {
         compute_allocation: ComputeAllocation::default(),
         subnet_type: SubnetType::Application,
         execution_mode: ExecutionMode::Replicated,
+        subnet_memory_saturation: ResourceSaturation::default(),
     };
 
     let subnet_available_memory =
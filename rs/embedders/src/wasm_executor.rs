@@ -486,6 +486,7 @@ pub fn wasm_execution_error(
             allocated_bytes: NumBytes::from(0),
             allocated_message_bytes: NumBytes::from(0),
             instance_stats: InstanceStats::default(),
+            canister_log_records: vec![],
         },
         None,
     )
@@ -647,6 +648,7 @@ pub fn process(
                     allocated_bytes: NumBytes::from(0),
                     allocated_message_bytes: NumBytes::from(0),
                     instance_stats: InstanceStats::default(),
+                    canister_log_records: vec![],
                 },
                 None,
                 Err(system_api),
@@ -654,6 +656,16 @@ pub fn process(
         }
     };
 
+    // `memory.grow` fails and returns -1 if it would exceed the Wasm memory
+    // limit of the canister.
+    if let Some(limit) = instance
+        .store_data()
+        .system_api
+        .wasm_memory_limit_in_pages()
+    {
+        instance.set_heap_grow_limit(limit);
+    }
+
     // Set the instruction limit for the first slice.
     instance.set_instruction_counter(first_slice_instruction_limit.get() as i64);
 
@@ -689,13 +701,28 @@ pub fn process(
         .take_execution_result(run_result.as_ref().err());
 
     let wasm_heap_size_after = instance.heap_size(CanisterMemoryType::Heap);
-    let wasm_heap_limit =
-        NumWasmPages::from(wasmtime_environ::WASM32_MAX_PAGES as usize) - wasm_reserved_pages;
+    let wasm_heap_max_pages = if instance.is_wasm64() {
+        embedder.config().max_wasm64_memory_size.get() as usize
+            / wasmtime_environ::WASM_PAGE_SIZE as usize
+    } else {
+        wasmtime_environ::WASM32_MAX_PAGES as usize
+    };
+    let wasm_heap_limit = NumWasmPages::from(wasm_heap_max_pages) - wasm_reserved_pages;
 
     if wasm_heap_size_after > wasm_heap_limit {
         wasm_result = Err(HypervisorError::WasmReservedPages);
     }
 
+    if wasm_result.is_ok() {
+        if let Err(err) = instance
+            .store_data()
+            .system_api
+            .check_wasm_memory_limit(wasm_heap_size_after)
+        {
+            wasm_result = Err(err);
+        }
+    }
+
     let mut allocated_bytes = NumBytes::from(0);
     let mut allocated_message_bytes = NumBytes::from(0);
     let mut execution_complexity = ExecutionComplexity::default();
@@ -757,6 +784,13 @@ pub fn process(
         Err(_) => None,
     };
 
+    // The log records are taken regardless of the outcome, so that the logs of
+    // a failed execution are not lost.
+    let canister_log_records = instance
+        .store_data_mut()
+        .system_api
+        .take_canister_log_records();
+
     (
         SliceExecutionOutput {
             executed_instructions: slice_instructions_executed,
@@ -768,6 +802,7 @@ pub fn process(
             allocated_bytes,
             allocated_message_bytes,
             instance_stats,
+            canister_log_records,
         },
         wasm_state_changes,
         Ok(instance),
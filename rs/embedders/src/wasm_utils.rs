@@ -210,6 +210,7 @@ fn validate_and_instrument(
         config.feature_flags.wasm_native_stable_memory,
         config.subnet_type,
         config.dirty_page_overhead,
+        config.max_wasm64_memory_size,
     )?;
     Ok((wasm_validation_details, instrumentation_output))
 }
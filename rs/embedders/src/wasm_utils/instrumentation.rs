@@ -109,6 +109,14 @@
 //! (memory (export "stable_memory_bytemap") i32 (i64.const STABLE_BYTEMAP_SIZE) (i64.const STABLE_BYTEMAP_SIZE))
 //! ```
 //!
+//! # Wasm64
+//!
+//! If the heap is a 64-bit memory, its maximum size is capped at the
+//! configured Wasm64 heap limit and the heap bytemap is sized accordingly.
+//! Addresses and `memory.grow` arguments are `i64` values, so the injected
+//! write barrier works on `i64` addresses and the arguments to
+//! `update_available_memory` are wrapped to `i32`.
+//!
 
 use super::system_api_replacements::replacement_functions;
 use super::validation::API_VERSION_IC0;
@@ -118,7 +126,7 @@ use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::NumWasmPages;
 use ic_sys::PAGE_SIZE;
 use ic_types::{methods::WasmMethod, MAX_WASM_MEMORY_IN_BYTES};
-use ic_types::{NumInstructions, MAX_STABLE_MEMORY_IN_BYTES};
+use ic_types::{NumBytes, NumInstructions, MAX_STABLE_MEMORY_IN_BYTES};
 use ic_wasm_types::{BinaryEncodedWasm, WasmError, WasmInstrumentationError};
 use wasmtime_environ::WASM_PAGE_SIZE;
 
@@ -375,6 +383,7 @@ pub(super) struct SpecialIndices {
     pub count_clean_pages_fn: Option<u32>,
     pub start_fn_ix: Option<u32>,
     pub stable_memory_index: u32,
+    pub is_wasm64: bool,
 }
 
 /// Takes a Wasm binary and inserts the instructions metering and memory grow
@@ -389,12 +398,18 @@ pub(super) fn instrument(
     wasm_native_stable_memory: FlagStatus,
     subnet_type: SubnetType,
     dirty_page_overhead: NumInstructions,
+    max_wasm64_memory_size: NumBytes,
 ) -> Result<InstrumentationOutput, WasmInstrumentationError> {
     let stable_memory_index;
+    let is_wasm64 = module.memories.first().map_or(false, |m| m.memory64);
     let mut module = inject_helper_functions(module, wasm_native_stable_memory);
     module = export_table(module);
-    (module, stable_memory_index) =
-        update_memories(module, write_barrier, wasm_native_stable_memory);
+    (module, stable_memory_index) = update_memories(
+        module,
+        write_barrier,
+        wasm_native_stable_memory,
+        max_wasm64_memory_size,
+    );
 
     let mut extra_strs: Vec<String> = Vec::new();
     module = export_mutable_globals(module, &mut extra_strs);
@@ -440,6 +455,7 @@ pub(super) fn instrument(
         count_clean_pages_fn,
         start_fn_ix: module.start,
         stable_memory_index,
+        is_wasm64,
     };
 
     if special_indices.start_fn_ix.is_some() {
@@ -468,9 +484,9 @@ pub(super) fn instrument(
     if !func_types.is_empty() {
         let func_bodies = &mut module.code_sections;
         for (func_ix, func_type) in func_types.into_iter().enumerate() {
-            inject_update_available_memory(&mut func_bodies[func_ix], &func_type);
+            inject_update_available_memory(&mut func_bodies[func_ix], &func_type, is_wasm64);
             if write_barrier == FlagStatus::Enabled {
-                inject_mem_barrier(&mut func_bodies[func_ix], &func_type);
+                inject_mem_barrier(&mut func_bodies[func_ix], &func_type, is_wasm64);
             }
         }
     }
@@ -927,6 +943,19 @@ fn inject_metering(code: &mut Vec<Operator>, export_data_module: &SpecialIndices
                     ]);
                 }
             }
+            InjectionPointCostDetail::DynamicCost
+                if export_data_module.is_wasm64
+                    && matches!(
+                        orig_elems[point.position],
+                        MemoryFill { .. } | MemoryCopy { .. }
+                    ) =>
+            {
+                // The size argument of bulk memory instructions on a 64-bit
+                // memory is already an `i64`.
+                elems.push(Call {
+                    function_index: export_data_module.decr_instruction_counter_fn,
+                });
+            }
             InjectionPointCostDetail::DynamicCost => {
                 elems.extend_from_slice(&[
                     I64ExtendI32U,
@@ -952,11 +981,48 @@ fn write_barrier_instructions<'a>(
     offset: u64,
     val_arg_idx: u32,
     addr_arg_idx: u32,
+    is_wasm64: bool,
 ) -> Vec<Operator<'a>> {
     use Operator::*;
     let page_size_shift = PAGE_SIZE.trailing_zeros() as i32;
     let tracking_mem_idx = 1;
-    if offset % PAGE_SIZE as u64 == 0 {
+    if is_wasm64 {
+        // The address is an `i64`, but the bytemap itself is a 32-bit memory
+        // that is small enough to be indexed by the wrapped page number.
+        vec![
+            LocalSet {
+                local_index: val_arg_idx,
+            }, // value
+            LocalTee {
+                local_index: addr_arg_idx,
+            }, // address
+            I64Const {
+                value: offset as i64,
+            },
+            I64Add,
+            I64Const {
+                value: page_size_shift as i64,
+            },
+            I64ShrU,
+            I32WrapI64,
+            I32Const { value: 1 },
+            I32Store8 {
+                memarg: wasmparser::MemArg {
+                    align: 0,
+                    max_align: 0,
+                    offset: 0,
+                    memory: tracking_mem_idx,
+                },
+            },
+            // Put original params on the stack
+            LocalGet {
+                local_index: addr_arg_idx,
+            },
+            LocalGet {
+                local_index: val_arg_idx,
+            },
+        ]
+    } else if offset % PAGE_SIZE as u64 == 0 {
         vec![
             LocalSet {
                 local_index: val_arg_idx,
@@ -1021,7 +1087,7 @@ fn write_barrier_instructions<'a>(
     }
 }
 
-fn inject_mem_barrier(func_body: &mut wasm_transform::Body, func_type: &FuncType) {
+fn inject_mem_barrier(func_body: &mut wasm_transform::Body, func_type: &FuncType, is_wasm64: bool) {
     use Operator::*;
     let mut val_i32_needed = false;
     let mut val_i64_needed = false;
@@ -1060,7 +1126,7 @@ fn inject_mem_barrier(func_body: &mut wasm_transform::Body, func_type: &FuncType
         // the total number of locals.
         let n_locals: u32 = func_body.locals.iter().map(|x| x.0).sum();
         let mut next_local = func_type.params().len() as u32 + n_locals;
-        let arg_i32_addr_idx = next_local;
+        let arg_addr_idx = next_local;
         next_local += 1;
 
         // conditionally add following locals
@@ -1069,7 +1135,16 @@ fn inject_mem_barrier(func_body: &mut wasm_transform::Body, func_type: &FuncType
         let arg_f32_val_idx;
         let arg_f64_val_idx;
 
-        if val_i32_needed {
+        if is_wasm64 {
+            func_body.locals.push((1, ValType::I64)); // addr local
+            if val_i32_needed {
+                arg_i32_val_idx = next_local;
+                next_local += 1;
+                func_body.locals.push((1, ValType::I32));
+            } else {
+                arg_i32_val_idx = u32::MAX; // not used
+            }
+        } else if val_i32_needed {
             arg_i32_val_idx = next_local;
             next_local += 1;
             func_body.locals.push((2, ValType::I32)); // addr and val locals
@@ -1114,7 +1189,8 @@ fn inject_mem_barrier(func_body: &mut wasm_transform::Body, func_type: &FuncType
                     elems.extend_from_slice(&write_barrier_instructions(
                         memarg.offset,
                         arg_i32_val_idx,
-                        arg_i32_addr_idx,
+                        arg_addr_idx,
+                        is_wasm64,
                     ));
                 }
                 I64Store { memarg }
@@ -1124,21 +1200,24 @@ fn inject_mem_barrier(func_body: &mut wasm_transform::Body, func_type: &FuncType
                     elems.extend_from_slice(&write_barrier_instructions(
                         memarg.offset,
                         arg_i64_val_idx,
-                        arg_i32_addr_idx,
+                        arg_addr_idx,
+                        is_wasm64,
                     ));
                 }
                 F32Store { memarg } => {
                     elems.extend_from_slice(&write_barrier_instructions(
                         memarg.offset,
                         arg_f32_val_idx,
-                        arg_i32_addr_idx,
+                        arg_addr_idx,
+                        is_wasm64,
                     ));
                 }
                 F64Store { memarg } => {
                     elems.extend_from_slice(&write_barrier_instructions(
                         memarg.offset,
                         arg_f64_val_idx,
-                        arg_i32_addr_idx,
+                        arg_addr_idx,
+                        is_wasm64,
                     ));
                 }
                 _ => {}
@@ -1157,19 +1236,25 @@ fn inject_mem_barrier(func_body: &mut wasm_transform::Body, func_type: &FuncType
 // `table.grow` instruction to make sure that there's enough available memory
 // left to support the requested extra memory. If no `memory.grow` or
 // `table.grow` instructions are present then the code remains unchanged.
-fn inject_update_available_memory(func_body: &mut wasm_transform::Body, func_type: &FuncType) {
+fn inject_update_available_memory(
+    func_body: &mut wasm_transform::Body,
+    func_type: &FuncType,
+    is_wasm64: bool,
+) {
     // This is an overestimation of table element size computed based on the
     // existing canister limits.
     const TABLE_ELEMENT_SIZE: u32 = 1024;
     use Operator::*;
-    let mut injection_points: Vec<(usize, u32)> = Vec::new();
+    // The third field is true if the argument and the result of the
+    // instruction are `i64` values, i.e. for `memory.grow` on a 64-bit memory.
+    let mut injection_points: Vec<(usize, u32, bool)> = Vec::new();
     {
         for (idx, instr) in func_body.instructions.iter().enumerate() {
             if let MemoryGrow { .. } = instr {
-                injection_points.push((idx, WASM_PAGE_SIZE));
+                injection_points.push((idx, WASM_PAGE_SIZE, is_wasm64));
             }
             if let TableGrow { .. } = instr {
-                injection_points.push((idx, TABLE_ELEMENT_SIZE));
+                injection_points.push((idx, TABLE_ELEMENT_SIZE, false));
             }
         }
     }
@@ -1182,13 +1267,42 @@ fn inject_update_available_memory(func_body: &mut wasm_transform::Body, func_typ
         let n_locals: u32 = func_body.locals.iter().map(|x| x.0).sum();
         let memory_local_ix = func_type.params().len() as u32 + n_locals;
         func_body.locals.push((1, ValType::I32));
+        let memory64_local_ix = memory_local_ix + 1;
+        if injection_points.iter().any(|(_, _, is_64)| *is_64) {
+            func_body.locals.push((1, ValType::I64));
+        }
 
         let orig_elems = &func_body.instructions;
         let mut elems: Vec<Operator> = Vec::new();
         let mut last_injection_position = 0;
-        for (point, element_size) in injection_points {
+        for (point, element_size, is_64) in injection_points {
             let update_available_memory_instr = orig_elems[point].clone();
             elems.extend_from_slice(&orig_elems[last_injection_position..point]);
+            if is_64 {
+                // `update_available_memory` works on `i32` values. Wrapping is
+                // safe because a successful `memory.grow` never exceeds the
+                // maximum size of the heap, which is far below 2^31 pages.
+                elems.extend_from_slice(&[
+                    LocalTee {
+                        local_index: memory64_local_ix,
+                    },
+                    update_available_memory_instr,
+                    I32WrapI64,
+                    LocalGet {
+                        local_index: memory64_local_ix,
+                    },
+                    I32WrapI64,
+                    I32Const {
+                        value: element_size as i32,
+                    },
+                    Call {
+                        function_index: InjectedImports::UpdateAvailableMemory as u32,
+                    },
+                    I64ExtendI32S,
+                ]);
+                last_injection_position = point + 1;
+                continue;
+            }
             // At this point we have a memory.grow so the argument to it will be on top of
             // the stack, which we just assign to `memory_local_ix` with a local.tee
             // instruction.
@@ -1284,6 +1398,7 @@ fn get_data(
                     offset_expr,
                 } => match offset_expr {
                     Operator::I32Const { value } => *value as usize,
+                    Operator::I64Const { value } => *value as usize,
                     _ => return Err(WasmInstrumentationError::WasmDeserializeError(WasmError::new(
                         "complex initialization expressions for data segments are not supported!".into()
                     ))),
@@ -1330,9 +1445,22 @@ fn update_memories(
     mut module: Module,
     write_barrier: FlagStatus,
     wasm_native_stable_memory: FlagStatus,
+    max_wasm64_memory_size: NumBytes,
 ) -> (Module, u32) {
     let mut stable_index = 0;
 
+    // A 64-bit heap is capped at the configured limit, which also determines
+    // the size of its bytemap.
+    let mut bytemap_size_in_wasm_pages = BYTEMAP_SIZE_IN_WASM_PAGES;
+    if let Some(memory) = module.memories.first_mut().filter(|m| m.memory64) {
+        let max_pages = max_wasm64_memory_size.get() / WASM_PAGE_SIZE as u64;
+        memory.maximum = Some(memory.maximum.map_or(max_pages, |m| m.min(max_pages)));
+        // There is one byte for each OS page in the heap.
+        let bytemap_size_in_bytes = max_pages * (WASM_PAGE_SIZE as u64 / PAGE_SIZE as u64);
+        bytemap_size_in_wasm_pages =
+            (bytemap_size_in_bytes + WASM_PAGE_SIZE as u64 - 1) / WASM_PAGE_SIZE as u64;
+    }
+
     let mut memory_already_exported = false;
     for export in &mut module.exports {
         if let ExternalKind::Memory = export.kind {
@@ -1354,8 +1482,8 @@ fn update_memories(
         module.memories.push(MemoryType {
             memory64: false,
             shared: false,
-            initial: BYTEMAP_SIZE_IN_WASM_PAGES,
-            maximum: Some(BYTEMAP_SIZE_IN_WASM_PAGES),
+            initial: bytemap_size_in_wasm_pages,
+            maximum: Some(bytemap_size_in_wasm_pages),
         });
 
         module.exports.push(Export {
@@ -3,9 +3,10 @@
 
 use super::{WasmImportsDetails, WasmValidationDetails};
 
-use ic_config::embedders::Config as EmbeddersConfig;
-use ic_replicated_state::canister_state::execution_state::{
-    CustomSection, CustomSectionType, WasmMetadata,
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
+use ic_replicated_state::canister_state::{
+    execution_state::{CustomSection, CustomSectionType, WasmMetadata},
+    WASM_PAGE_SIZE_IN_BYTES,
 };
 use ic_types::{NumBytes, NumInstructions};
 use ic_wasm_types::{BinaryEncodedWasm, WasmValidationError};
@@ -56,7 +57,7 @@ pub(super) const API_VERSION_IC0: &str = "ic0";
 // user tries to import a function that doesn't exist in any of the expected
 // modules vs the case where the function exists but is imported from the wrong
 // module.
-fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>> {
+fn get_valid_system_apis(ptr_type: ValType) -> HashMap<String, HashMap<String, FunctionSignature>> {
     let valid_system_apis = vec![
         (
             // Public methods
@@ -65,7 +66,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValType::I32],
+                    return_type: vec![ptr_type],
                 },
             )],
         ),
@@ -74,7 +75,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -85,7 +86,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValType::I32],
+                    return_type: vec![ptr_type],
                 },
             )],
         ),
@@ -94,7 +95,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -105,7 +106,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValType::I32],
+                    return_type: vec![ptr_type],
                 },
             )],
         ),
@@ -114,7 +115,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -145,7 +146,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValType::I32],
+                    return_type: vec![ptr_type],
                 },
             )],
         ),
@@ -154,7 +155,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -164,7 +165,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -184,7 +185,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -195,7 +196,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValType::I32],
+                    return_type: vec![ptr_type],
                 },
             )],
         ),
@@ -204,7 +205,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -215,7 +216,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValType::I32],
+                    return_type: vec![ptr_type],
                 },
             )],
         ),
@@ -224,7 +225,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -246,14 +247,8 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![
-                        ValType::I32,
-                        ValType::I32,
-                        ValType::I32,
-                        ValType::I32,
-                        ValType::I32,
-                        ValType::I32,
-                        ValType::I32,
-                        ValType::I32,
+                        ptr_type, ptr_type, ptr_type, ptr_type, ptr_type, ptr_type, ptr_type,
+                        ptr_type,
                     ],
                     return_type: vec![],
                 },
@@ -264,7 +259,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -274,7 +269,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -305,7 +300,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -435,7 +430,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -485,7 +480,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -506,7 +501,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValType::I32],
+                    return_type: vec![ptr_type],
                 },
             )],
         ),
@@ -515,7 +510,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -555,7 +550,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32],
+                    param_types: vec![ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -565,7 +560,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32],
+                    param_types: vec![ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -575,7 +570,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32],
+                    param_types: vec![ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -585,7 +580,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I64, ValType::I64, ValType::I32],
+                    param_types: vec![ValType::I64, ValType::I64, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -595,7 +590,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type],
                     return_type: vec![ValType::I32],
                 },
             )],
@@ -747,7 +742,13 @@ fn validate_import_section(module: &Module) -> Result<WasmImportsDetails, WasmVa
     let mut imports_details = WasmImportsDetails::default();
 
     if !module.imports.is_empty() {
-        let valid_system_apis = get_valid_system_apis();
+        // Modules with a 64-bit memory pass pointers and sizes as `i64`.
+        let ptr_type = if module.memories.first().map_or(false, |m| m.memory64) {
+            ValType::I64
+        } else {
+            ValType::I32
+        };
+        let valid_system_apis = get_valid_system_apis(ptr_type);
         for entry in &module.imports {
             let import_module = entry.module;
             let field = entry.name;
@@ -922,7 +923,7 @@ fn validate_data_section(module: &Module) -> Result<(), WasmValidationError> {
                 memory_index: _,
                 offset_expr,
             } => match offset_expr {
-                Operator::I32Const { .. } => Ok(()),
+                Operator::I32Const { .. } | Operator::I64Const { .. } => Ok(()),
                 _ => Err(WasmValidationError::InvalidDataSection(format!(
                     "Invalid offset expression in data segment: {:?}",
                     offset_expr
@@ -937,6 +938,36 @@ fn validate_data_section(module: &Module) -> Result<(), WasmValidationError> {
     Ok(())
 }
 
+// Checks that a 64-bit Wasm memory is only declared if the `wasm64` feature
+// is enabled and that its initial size doesn't exceed the configured limit.
+fn validate_memory_section(
+    module: &Module,
+    config: &EmbeddersConfig,
+) -> Result<(), WasmValidationError> {
+    let memory = match module.memories.first() {
+        Some(memory) if memory.memory64 => memory,
+        _ => return Ok(()),
+    };
+    if config.feature_flags.wasm64 == FlagStatus::Disabled {
+        return Err(WasmValidationError::InvalidMemorySection(
+            "64-bit memories are not supported on this subnet.".to_string(),
+        ));
+    }
+    if config.feature_flags.wasm_native_stable_memory == FlagStatus::Enabled {
+        return Err(WasmValidationError::InvalidMemorySection(
+            "64-bit memories are not supported with Wasm-native stable memory.".to_string(),
+        ));
+    }
+    let max_pages = config.max_wasm64_memory_size.get() / WASM_PAGE_SIZE_IN_BYTES as u64;
+    if memory.initial > max_pages {
+        return Err(WasmValidationError::InvalidMemorySection(format!(
+            "Initial memory size of {} Wasm pages exceeds the limit of {} Wasm pages.",
+            memory.initial, max_pages
+        )));
+    }
+    Ok(())
+}
+
 // Checks that no more than `max_globals` are defined in the module.
 fn validate_global_section(module: &Module, max_globals: usize) -> Result<(), WasmValidationError> {
     if module.globals.len() > max_globals {
@@ -1156,9 +1187,15 @@ pub fn ensure_determinism(config: &mut Config) {
         .cranelift_nan_canonicalization(true);
 }
 
-fn can_compile(wasm: &BinaryEncodedWasm) -> Result<(), WasmValidationError> {
+fn can_compile(
+    wasm: &BinaryEncodedWasm,
+    embedders_config: &EmbeddersConfig,
+) -> Result<(), WasmValidationError> {
     let mut config = wasmtime::Config::default();
     ensure_determinism(&mut config);
+    if embedders_config.feature_flags.wasm64 == FlagStatus::Enabled {
+        config.wasm_memory64(true);
+    }
     let engine = wasmtime::Engine::new(&config).map_err(|_| {
         WasmValidationError::WasmtimeValidation(String::from("Failed to initialize Wasm engine"))
     })?;
@@ -1180,6 +1217,7 @@ fn can_compile(wasm: &BinaryEncodedWasm) -> Result<(), WasmValidationError> {
 /// * Export
 /// * Code
 /// * Data
+/// * Memory
 /// * Global
 /// * Function
 /// * CustomSections
@@ -1189,7 +1227,7 @@ pub(super) fn validate_wasm_binary<'a>(
     wasm: &'a BinaryEncodedWasm,
     config: &EmbeddersConfig,
 ) -> Result<(WasmValidationDetails, Module<'a>), WasmValidationError> {
-    can_compile(wasm)?;
+    can_compile(wasm, config)?;
     let module = Module::parse(wasm.as_slice(), false)
         .map_err(|err| WasmValidationError::DecodingError(format!("{}", err)))?;
     let imports_details = validate_import_section(&module)?;
@@ -1199,6 +1237,7 @@ pub(super) fn validate_wasm_binary<'a>(
         config.max_sum_exported_function_name_lengths,
     )?;
     validate_data_section(&module)?;
+    validate_memory_section(&module, config)?;
     validate_global_section(&module, config.max_globals)?;
     validate_function_section(&module, config.max_functions)?;
     let largest_function_instruction_count = validate_code_section(&module)?;
@@ -43,7 +43,7 @@ use crate::{serialized_module::SerializedModuleBytes, wasm_utils::validation::en
 
 use super::InstanceRunResult;
 
-use self::host_memory::{MemoryPageSize, MemoryStart};
+use self::host_memory::{CreatedMemories, MemoryGrowLimit, MemoryPageSize, MemoryStart};
 
 #[cfg(test)]
 mod wasmtime_embedder_tests;
@@ -166,7 +166,7 @@ pub struct WasmtimeEmbedder {
     // `SigsegvMemoryTracker` is created it will look up the corresponding memory in the map
     // and remove it. So memories will only be in this map for the time between module
     // instatiation and creation of the corresponding `SigsegvMemoryTracker`.
-    created_memories: Arc<Mutex<HashMap<MemoryStart, MemoryPageSize>>>,
+    created_memories: CreatedMemories,
 }
 
 impl WasmtimeEmbedder {
@@ -191,7 +191,9 @@ impl WasmtimeEmbedder {
         {
             config.wasm_multi_memory(true);
         }
-        if embedder_config.feature_flags.wasm_native_stable_memory == FlagStatus::Enabled {
+        if embedder_config.feature_flags.wasm_native_stable_memory == FlagStatus::Enabled
+            || embedder_config.feature_flags.wasm64 == FlagStatus::Enabled
+        {
             config.wasm_memory64(true);
         }
         config
@@ -327,14 +329,30 @@ impl WasmtimeEmbedder {
             },
         );
 
-        let linker = system_api::syscalls(
-            self.log.clone(),
-            canister_id,
-            &store,
-            self.config.feature_flags,
-            self.config.stable_memory_dirty_page_limit,
-            self.config.stable_memory_accessed_page_limit,
+        // Modules with a 64-bit heap pass `i64` pointers to the System API.
+        let is_wasm64 = matches!(
+            module.get_export(WASM_HEAP_MEMORY_NAME),
+            Some(wasmtime::ExternType::Memory(memory_type)) if memory_type.is_64()
         );
+        let linker = if is_wasm64 {
+            system_api::syscalls::<S, i64>(
+                self.log.clone(),
+                canister_id,
+                &store,
+                self.config.feature_flags,
+                self.config.stable_memory_dirty_page_limit,
+                self.config.stable_memory_accessed_page_limit,
+            )
+        } else {
+            system_api::syscalls::<S, i32>(
+                self.log.clone(),
+                canister_id,
+                &store,
+                self.config.feature_flags,
+                self.config.stable_memory_dirty_page_limit,
+                self.config.stable_memory_accessed_page_limit,
+            )
+        };
 
         let instance = match linker.instantiate(&mut store, module) {
             Ok(instance) => instance,
@@ -430,17 +448,22 @@ impl WasmtimeEmbedder {
                 self.instantiate_memory(memory_info, &instance, store, &mut memories, canister_id)?;
         }
 
+        let heap_grow_limit = memories
+            .get(&CanisterMemoryType::Heap)
+            .map(|info| info.grow_limit.clone());
         let memory_trackers = sigsegv_memory_tracker(memories, &mut store, self.log.clone());
 
         let signal_stack = WasmtimeSignalStack::new();
         Ok(WasmtimeInstance {
             instance,
             memory_trackers,
+            heap_grow_limit,
             signal_stack,
             log: self.log.clone(),
             instance_stats: InstanceStats::default(),
             store,
             write_barrier: self.config.feature_flags.write_barrier,
+            is_wasm64,
             wasm_native_stable_memory: self.config.feature_flags.wasm_native_stable_memory,
             modification_tracking,
             #[cfg(debug_assertions)]
@@ -468,7 +491,7 @@ impl WasmtimeEmbedder {
             }
             let start = MemoryStart(instance_memory.data_ptr(&store) as usize);
             let mut created_memories = self.created_memories.lock().unwrap();
-            let current_size = match created_memories.remove(&start) {
+            let (current_size, grow_limit) = match created_memories.remove(&start) {
                 None => {
                     error!(
                         self.log,
@@ -483,13 +506,14 @@ impl WasmtimeEmbedder {
                         store.into_data().system_api,
                     ));
                 }
-                Some(current_memory_size_in_pages) => current_memory_size_in_pages,
+                Some(created_memory) => created_memory,
             };
             memories_to_track.insert(
                 memory_info.memory_type,
                 MemorySigSegvInfo {
                     instance_memory,
                     current_memory_size_in_pages: current_size,
+                    grow_limit,
                     page_map: memory_info.memory.page_map.clone(),
                     dirty_page_tracking: memory_info.dirty_page_tracking,
                 },
@@ -519,7 +543,7 @@ impl WasmtimeEmbedder {
         bytemap_name: &str,
         instance: &Instance,
         mut store: Store<StoreData<S>>,
-        created_memories: &mut HashMap<MemoryStart, MemoryPageSize>,
+        created_memories: &mut HashMap<MemoryStart, (MemoryPageSize, MemoryGrowLimit)>,
         canister_id: CanisterId,
     ) -> Result<Store<StoreData<S>>, (HypervisorError, S)> {
         match instance
@@ -542,7 +566,7 @@ impl WasmtimeEmbedder {
                     store.into_data().system_api,
                 ))
             }
-            Some((instance_memory, current_memory_size_in_pages)) => {
+            Some((instance_memory, (current_memory_size_in_pages, _))) => {
                 let addr = instance_memory.data_ptr(&mut store) as usize;
                 let size_in_bytes =
                     current_memory_size_in_pages.load(Ordering::SeqCst) * WASM_PAGE_SIZE_IN_BYTES;
@@ -579,6 +603,7 @@ unsafe impl Send for StoreRef {}
 pub struct MemorySigSegvInfo {
     instance_memory: wasmtime::Memory,
     current_memory_size_in_pages: MemoryPageSize,
+    grow_limit: MemoryGrowLimit,
     page_map: PageMap,
     dirty_page_tracking: DirtyPageTracking,
 }
@@ -595,6 +620,7 @@ fn sigsegv_memory_tracker<S>(
         MemorySigSegvInfo {
             instance_memory,
             current_memory_size_in_pages,
+            grow_limit: _,
             page_map,
             dirty_page_tracking,
         },
@@ -652,11 +678,13 @@ pub struct PageAccessResults {
 pub struct WasmtimeInstance<S: SystemApi> {
     instance: wasmtime::Instance,
     memory_trackers: HashMap<CanisterMemoryType, Arc<Mutex<SigsegvMemoryTracker>>>,
+    heap_grow_limit: Option<MemoryGrowLimit>,
     signal_stack: WasmtimeSignalStack,
     log: ReplicaLogger,
     instance_stats: InstanceStats,
     store: wasmtime::Store<StoreData<S>>,
     write_barrier: FlagStatus,
+    is_wasm64: bool,
     wasm_native_stable_memory: FlagStatus,
     modification_tracking: ModificationTracking,
     #[cfg(debug_assertions)]
@@ -676,6 +704,19 @@ impl<S: SystemApi> WasmtimeInstance<S> {
         self.store.data()
     }
 
+    /// Makes `memory.grow` on the Wasm heap fail and return -1 if the heap
+    /// would exceed the given number of pages.
+    pub fn set_heap_grow_limit(&mut self, limit: NumWasmPages) {
+        if let Some(grow_limit) = &self.heap_grow_limit {
+            grow_limit.set(limit.get());
+        }
+    }
+
+    /// Returns true if the Wasm heap of the instance is a 64-bit memory.
+    pub fn is_wasm64(&self) -> bool {
+        self.is_wasm64
+    }
+
     fn invoke_export(&mut self, export: &str, args: &[Val]) -> HypervisorResult<()> {
         self.instance
             .get_export(&mut self.store, export)
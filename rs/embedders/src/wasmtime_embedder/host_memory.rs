@@ -42,12 +42,26 @@ impl Deref for MemoryPageSize {
     }
 }
 
+/// The number of Wasm pages beyond which `memory.grow` fails and returns -1.
+/// The limit is `usize::MAX` unless it is set after instantiation.
+#[derive(Clone)]
+pub(crate) struct MemoryGrowLimit(Arc<AtomicUsize>);
+
+impl MemoryGrowLimit {
+    pub(crate) fn set(&self, pages: usize) {
+        self.0.store(pages, Ordering::SeqCst);
+    }
+}
+
+pub(crate) type CreatedMemories =
+    Arc<Mutex<HashMap<MemoryStart, (MemoryPageSize, MemoryGrowLimit)>>>;
+
 pub struct WasmtimeMemoryCreator {
-    created_memories: Arc<Mutex<HashMap<MemoryStart, MemoryPageSize>>>,
+    created_memories: CreatedMemories,
 }
 
 impl WasmtimeMemoryCreator {
-    pub(crate) fn new(created_memories: Arc<Mutex<HashMap<MemoryStart, MemoryPageSize>>>) -> Self {
+    pub(crate) fn new(created_memories: CreatedMemories) -> Self {
         Self { created_memories }
     }
 }
@@ -95,7 +109,10 @@ unsafe impl wasmtime::MemoryCreator for WasmtimeMemoryCreator {
                 let new_memory = WasmtimeMemory::new(mem, min, max);
                 created_memories.insert(
                     MemoryStart(wasmtime::LinearMemory::as_ptr(&new_memory) as usize),
-                    MemoryPageSize(Arc::clone(&new_memory.used)),
+                    (
+                        MemoryPageSize(Arc::clone(&new_memory.used)),
+                        new_memory.grow_limit.clone(),
+                    ),
                 );
                 Ok(Box::new(new_memory))
             }
@@ -192,6 +209,7 @@ pub struct WasmtimeMemory<M: LinearMemory> {
     mem: M,
     maximum: usize,
     used: MemoryPageSize,
+    grow_limit: MemoryGrowLimit,
 }
 
 impl<M: LinearMemory + Send> WasmtimeMemory<M> {
@@ -200,6 +218,7 @@ impl<M: LinearMemory + Send> WasmtimeMemory<M> {
             mem,
             maximum,
             used: MemoryPageSize(Arc::new(AtomicUsize::new(min))),
+            grow_limit: MemoryGrowLimit(Arc::new(AtomicUsize::new(usize::MAX))),
         }
     }
 }
@@ -230,10 +249,11 @@ unsafe impl<M: LinearMemory + Send + Sync + 'static> wasmtime::LinearMemory for
             )
         }
         let new_pages = new_size / WASM_PAGE_SIZE as usize;
+        let grow_limit = self.grow_limit.0.load(Ordering::SeqCst);
         match self
             .used
             .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |prev_pages| {
-                if new_pages <= prev_pages || new_pages > self.maximum {
+                if new_pages <= prev_pages || new_pages > self.maximum || new_pages > grow_limit {
                     None
                 } else {
                     Some(new_pages)
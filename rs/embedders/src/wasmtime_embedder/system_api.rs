@@ -360,7 +360,61 @@ fn ic0_performance_counter_helper<S: SystemApi>(
     }
 }
 
-pub(crate) fn syscalls<S: SystemApi>(
+/// The type of the pointer and size arguments of the System API functions
+/// that access the Wasm heap: `i32` for modules with a 32-bit memory and `i64`
+/// for modules with a 64-bit memory.
+pub(crate) trait WasmPtr: wasmtime::WasmTy + Copy + Send + Sync + 'static {
+    /// Converts a callback function index or environment to `u32`, failing if
+    /// it is out of the range supported by `WasmClosure`.
+    fn to_u32(self) -> HypervisorResult<u32>;
+
+    /// Converts the argument to an offset or size in the heap. The System API
+    /// checks it against the heap bounds.
+    fn to_usize(self) -> usize;
+
+    /// Converts a size returned by the System API to the argument type.
+    fn try_from_size(size: u64) -> Result<Self, std::num::TryFromIntError>;
+
+    /// The number of bytes to charge for when the argument is a size.
+    fn charged_bytes(self) -> u32 {
+        u32::try_from(self.to_usize()).unwrap_or(u32::MAX)
+    }
+}
+
+impl WasmPtr for i32 {
+    fn to_u32(self) -> HypervisorResult<u32> {
+        Ok(self as u32)
+    }
+
+    fn to_usize(self) -> usize {
+        self as u32 as usize
+    }
+
+    fn try_from_size(size: u64) -> Result<Self, std::num::TryFromIntError> {
+        i32::try_from(size)
+    }
+}
+
+impl WasmPtr for i64 {
+    fn to_u32(self) -> HypervisorResult<u32> {
+        u32::try_from(self as u64).map_err(|_| {
+            HypervisorError::ContractViolation(format!(
+                "System API argument {} of a Wasm64 module exceeds the 32-bit range",
+                self as u64
+            ))
+        })
+    }
+
+    fn to_usize(self) -> usize {
+        self as u64 as usize
+    }
+
+    fn try_from_size(size: u64) -> Result<Self, std::num::TryFromIntError> {
+        i64::try_from(size)
+    }
+}
+
+pub(crate) fn syscalls<S: SystemApi, I: WasmPtr>(
     log: ReplicaLogger,
     canister_id: CanisterId,
     store: &Store<StoreData<S>>,
@@ -406,7 +460,7 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_caller_copy", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: I, offset: I, size: I| {
                 observe_execution_complexity(
                     &log,
                     canister_id,
@@ -418,10 +472,15 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_msg_caller_copy(dst as u32, offset as u32, size as u32, memory)
+                    system_api.ic0_msg_caller_copy(
+                        dst.to_usize(),
+                        offset.to_usize(),
+                        size.to_usize(),
+                        memory,
+                    )
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as u32 as usize, size as u32 as usize)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), size.to_usize())
                 } else {
                     Ok(())
                 }
@@ -435,7 +494,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                 with_system_api(&mut caller, |s| s.ic0_msg_caller_size())
                     .map_err(|e| process_err(&mut caller, e))
                     .and_then(|s| {
-                        i32::try_from(s).map_err(|e| {
+                        I::try_from_size(s as u64).map_err(|e| {
                             anyhow::Error::msg(format!("ic0::msg_caller_size failed: {}", e))
                         })
                     })
@@ -449,7 +508,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                 with_system_api(&mut caller, |s| s.ic0_msg_arg_data_size())
                     .map_err(|e| process_err(&mut caller, e))
                     .and_then(|s| {
-                        i32::try_from(s).map_err(|e| {
+                        I::try_from_size(s as u64).map_err(|e| {
                             anyhow::Error::msg(format!("ic0::msg_arg_data_size failed: {}", e))
                         })
                     })
@@ -460,13 +519,13 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_arg_data_copy", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: I, offset: I, size: I| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::MSG_ARG_DATA_COPY,
-                    size as u32,
+                    size.charged_bytes(),
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::MSG_ARG_DATA_COPY,
                         ..Default::default()
@@ -475,10 +534,15 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, mem| {
-                    system_api.ic0_msg_arg_data_copy(dst as u32, offset as u32, size as u32, mem)
+                    system_api.ic0_msg_arg_data_copy(
+                        dst.to_usize(),
+                        offset.to_usize(),
+                        size.to_usize(),
+                        mem,
+                    )
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as u32 as usize, size as u32 as usize)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), size.to_usize())
                 } else {
                     Ok(())
                 }
@@ -492,7 +556,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                 with_system_api(&mut caller, |s| s.ic0_msg_method_name_size())
                     .map_err(|e| process_err(&mut caller, e))
                     .and_then(|s| {
-                        i32::try_from(s).map_err(|e| {
+                        I::try_from_size(s as u64).map_err(|e| {
                             anyhow::Error::msg(format!("ic0::msg_metohd_name_size failed: {}", e))
                         })
                     })
@@ -503,13 +567,13 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_method_name_copy", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: I, offset: I, size: I| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::MSG_METHOD_NAME_COPY,
-                    size as u32,
+                    size.charged_bytes(),
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::MSG_METHOD_NAME_COPY,
                         ..Default::default()
@@ -519,14 +583,14 @@ pub(crate) fn syscalls<S: SystemApi>(
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_msg_method_name_copy(
-                        dst as u32,
-                        offset as u32,
-                        size as u32,
+                        dst.to_usize(),
+                        offset.to_usize(),
+                        size.to_usize(),
                         memory,
                     )
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as u32 as usize, size as u32 as usize)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), size.to_usize())
                 } else {
                     Ok(())
                 }
@@ -546,13 +610,13 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_reply_data_append", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, src: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, src: I, size: I| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::MSG_REPLY_DATA_APPEND,
-                    size as u32,
+                    size.charged_bytes(),
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::MSG_REPLY_DATA_APPEND,
                         ..Default::default()
@@ -561,7 +625,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_msg_reply_data_append(src as u32, size as u32, memory)
+                    system_api.ic0_msg_reply_data_append(src.to_usize(), size.to_usize(), memory)
                 })
             }
         })
@@ -588,13 +652,13 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_reject", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, src: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, src: I, size: I| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::MSG_REJECT,
-                    size as u32,
+                    size.charged_bytes(),
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::MSG_REJECT,
                         ..Default::default()
@@ -603,7 +667,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_msg_reject(src as u32, size as u32, memory)
+                    system_api.ic0_msg_reject(src.to_usize(), size.to_usize(), memory)
                 })
             }
         })
@@ -615,7 +679,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                 with_system_api(&mut caller, |s| s.ic0_msg_reject_msg_size())
                     .map_err(|e| process_err(&mut caller, e))
                     .and_then(|s| {
-                        i32::try_from(s).map_err(|e| {
+                        I::try_from_size(s as u64).map_err(|e| {
                             anyhow::Error::msg(format!("ic0_msg_reject_msg_size failed: {}", e))
                         })
                     })
@@ -626,13 +690,13 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_reject_msg_copy", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: I, offset: I, size: I| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::MSG_REJECT_MSG_COPY,
-                    size as u32,
+                    size.charged_bytes(),
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::MSG_REJECT_MSG_COPY,
                         ..Default::default()
@@ -642,14 +706,14 @@ pub(crate) fn syscalls<S: SystemApi>(
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_msg_reject_msg_copy(
-                        dst as u32,
-                        offset as u32,
-                        size as u32,
+                        dst.to_usize(),
+                        offset.to_usize(),
+                        size.to_usize(),
                         memory,
                     )
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as u32 as usize, size as u32 as usize)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), size.to_usize())
                 } else {
                     Ok(())
                 }
@@ -663,7 +727,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                 with_system_api(&mut caller, |s| s.ic0_canister_self_size())
                     .map_err(|e| process_err(&mut caller, e))
                     .and_then(|s| {
-                        i32::try_from(s).map_err(|e| {
+                        I::try_from_size(s as u64).map_err(|e| {
                             anyhow::Error::msg(format!("ic0_canister_self_size failed: {}", e))
                         })
                     })
@@ -674,7 +738,7 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "canister_self_copy", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: I, offset: I, size: I| {
                 observe_execution_complexity(
                     &log,
                     canister_id,
@@ -687,14 +751,14 @@ pub(crate) fn syscalls<S: SystemApi>(
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_canister_self_copy(
-                        dst as u32,
-                        offset as u32,
-                        size as u32,
+                        dst.to_usize(),
+                        offset.to_usize(),
+                        size.to_usize(),
                         memory,
                     )
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as u32 as usize, size as u32 as usize)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), size.to_usize())
                 } else {
                     Ok(())
                 }
@@ -708,7 +772,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                 with_system_api(&mut caller, |s| s.ic0_controller_size())
                     .map_err(|e| process_err(&mut caller, e))
                     .and_then(|s| {
-                        i32::try_from(s).map_err(|e| {
+                        I::try_from_size(s as u64).map_err(|e| {
                             anyhow::Error::msg(format!("ic0_controller_size failed: {}", e))
                         })
                     })
@@ -719,7 +783,7 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "controller_copy", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: I, offset: I, size: I| {
                 observe_execution_complexity(
                     &log,
                     canister_id,
@@ -731,10 +795,15 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_controller_copy(dst as u32, offset as u32, size as u32, memory)
+                    system_api.ic0_controller_copy(
+                        dst.to_usize(),
+                        offset.to_usize(),
+                        size.to_usize(),
+                        memory,
+                    )
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as u32 as usize, size as u32 as usize)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), size.to_usize())
                 } else {
                     Ok(())
                 }
@@ -745,13 +814,13 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "debug_print", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, offset: i32, length: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, offset: I, length: I| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::DEBUG_PRINT,
-                    length as u32,
+                    length.charged_bytes(),
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::DEBUG_PRINT,
                         ..Default::default()
@@ -763,14 +832,24 @@ pub(crate) fn syscalls<S: SystemApi>(
                     caller.data().system_api.subnet_type(),
                     feature_flags.rate_limiting_of_debug_prints,
                 ) {
-                    // Debug print is a no-op on non-system subnets with rate limiting.
-                    (SubnetType::Application, FlagStatus::Enabled) => Ok(()),
-                    (SubnetType::VerifiedApplication, FlagStatus::Enabled) => Ok(()),
+                    // Debug print only records the message in the canister log on
+                    // non-system subnets with rate limiting.
+                    (SubnetType::Application, FlagStatus::Enabled)
+                    | (SubnetType::VerifiedApplication, FlagStatus::Enabled) => {
+                        with_memory_and_system_api(&mut caller, |system_api, memory| {
+                            system_api.save_log_message(
+                                offset.to_usize(),
+                                length.to_usize(),
+                                memory,
+                            );
+                            Ok(())
+                        })
+                    }
                     // If rate limiting is disabled or the subnet is a system subnet, then
                     // debug print produces output.
                     (_, FlagStatus::Disabled) | (SubnetType::System, FlagStatus::Enabled) => {
                         with_memory_and_system_api(&mut caller, |system_api, memory| {
-                            system_api.ic0_debug_print(offset as u32, length as u32, memory)
+                            system_api.ic0_debug_print(offset.to_usize(), length.to_usize(), memory)
                         })
                     }
                 }
@@ -781,13 +860,13 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "trap", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, offset: i32, length: i32| -> Result<(), _> {
+            move |mut caller: Caller<'_, StoreData<S>>, offset: I, length: I| -> Result<(), _> {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::TRAP,
-                    length as u32,
+                    length.charged_bytes(),
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::TRAP,
                         ..Default::default()
@@ -796,7 +875,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_trap(offset as u32, length as u32, memory)
+                    system_api.ic0_trap(offset.to_usize(), length.to_usize(), memory)
                 })
             }
         })
@@ -806,14 +885,14 @@ pub(crate) fn syscalls<S: SystemApi>(
         .func_wrap("ic0", "call_new", {
             let log = log.clone();
             move |mut caller: Caller<'_, StoreData<S>>,
-                  callee_src: i32,
-                  callee_size: i32,
-                  name_src: i32,
-                  name_len: i32,
-                  reply_fun: i32,
-                  reply_env: i32,
-                  reject_fun: i32,
-                  reject_env: i32| {
+                  callee_src: I,
+                  callee_size: I,
+                  name_src: I,
+                  name_len: I,
+                  reply_fun: I,
+                  reply_env: I,
+                  reject_fun: I,
+                  reject_env: I| {
                 observe_execution_complexity(
                     &log,
                     canister_id,
@@ -826,14 +905,14 @@ pub(crate) fn syscalls<S: SystemApi>(
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_call_new(
-                        callee_src as u32,
-                        callee_size as u32,
-                        name_src as u32,
-                        name_len as u32,
-                        reply_fun as u32,
-                        reply_env as u32,
-                        reject_fun as u32,
-                        reject_env as u32,
+                        callee_src.to_usize(),
+                        callee_size.to_usize(),
+                        name_src.to_usize(),
+                        name_len.to_usize(),
+                        reply_fun.to_u32()?,
+                        reply_env.to_u32()?,
+                        reject_fun.to_u32()?,
+                        reject_env.to_u32()?,
                         memory,
                     )
                 })
@@ -844,13 +923,13 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "call_data_append", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, src: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, src: I, size: I| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::CALL_DATA_APPEND,
-                    size as u32,
+                    size.charged_bytes(),
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::CALL_DATA_APPEND,
                         ..Default::default()
@@ -859,7 +938,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_call_data_append(src as u32, size as u32, memory)
+                    system_api.ic0_call_data_append(src.to_usize(), size.to_usize(), memory)
                 })
             }
         })
@@ -867,9 +946,9 @@ pub(crate) fn syscalls<S: SystemApi>(
 
     linker
         .func_wrap("ic0", "call_on_cleanup", {
-            move |mut caller: Caller<'_, StoreData<S>>, fun: i32, env: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, fun: I, env: I| {
                 with_system_api(&mut caller, |s| {
-                    s.ic0_call_on_cleanup(fun as u32, env as u32)
+                    s.ic0_call_on_cleanup(fun.to_u32()?, env.to_u32()?)
                 })
                 .map_err(|e| process_err(&mut caller, e))
             }
@@ -1059,7 +1138,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     )
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as u32 as usize, size as u32 as usize)
+                    mark_writes_on_bytemap(&mut caller, dst as u64 as usize, size as u64 as usize)
                 } else {
                     Ok(())
                 }
@@ -1088,7 +1167,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     system_api.ic0_stable64_read(dst as u64, offset as u64, size as u64, memory)
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as u32 as usize, size as u32 as usize)
+                    mark_writes_on_bytemap(&mut caller, dst as u64 as usize, size as u64 as usize)
                 } else {
                     Ok(())
                 }
@@ -1195,7 +1274,7 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "canister_cycle_balance128", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: u32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: I| {
                 observe_execution_complexity(
                     &log,
                     canister_id,
@@ -1207,10 +1286,10 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_canister_cycle_balance128(dst, memory)
+                    system_api.ic0_canister_cycle_balance128(dst.to_usize(), memory)
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, 16)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), 16)
                 } else {
                     Ok(())
                 }
@@ -1235,7 +1314,7 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_cycles_available128", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: u32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: I| {
                 observe_execution_complexity(
                     &log,
                     canister_id,
@@ -1247,10 +1326,10 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_msg_cycles_available128(dst, memory)
+                    system_api.ic0_msg_cycles_available128(dst.to_usize(), memory)
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, 16)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), 16)
                 } else {
                     Ok(())
                 }
@@ -1275,7 +1354,7 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_cycles_refunded128", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: u32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: I| {
                 observe_execution_complexity(
                     &log,
                     canister_id,
@@ -1287,10 +1366,10 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_msg_cycles_refunded128(dst, memory)
+                    system_api.ic0_msg_cycles_refunded128(dst.to_usize(), memory)
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, 16)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), 16)
                 } else {
                     Ok(())
                 }
@@ -1313,7 +1392,7 @@ pub(crate) fn syscalls<S: SystemApi>(
             move |mut caller: Caller<'_, StoreData<S>>,
                   amount_high: i64,
                   amount_low: i64,
-                  dst: u32| {
+                  dst: I| {
                 observe_execution_complexity(
                     &log,
                     canister_id,
@@ -1327,12 +1406,12 @@ pub(crate) fn syscalls<S: SystemApi>(
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_msg_cycles_accept128(
                         Cycles::from_parts(amount_high as u64, amount_low as u64),
-                        dst,
+                        dst.to_usize(),
                         memory,
                     )
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, 16)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), 16)
                 } else {
                     Ok(())
                 }
@@ -1408,7 +1487,7 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "certified_data_set", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, src: u32, size: u32| {
+            move |mut caller: Caller<'_, StoreData<S>>, src: I, size: I| {
                 observe_execution_complexity(
                     &log,
                     canister_id,
@@ -1420,7 +1499,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_certified_data_set(src, size, memory)
+                    system_api.ic0_certified_data_set(src.to_usize(), size.to_usize(), memory)
                 })
             }
         })
@@ -1440,6 +1519,11 @@ pub(crate) fn syscalls<S: SystemApi>(
             move |mut caller: Caller<'_, StoreData<S>>| {
                 with_system_api(&mut caller, |s| s.ic0_data_certificate_size())
                     .map_err(|e| process_err(&mut caller, e))
+                    .and_then(|s| {
+                        I::try_from_size(s as u64).map_err(|e| {
+                            anyhow::Error::msg(format!("ic0_data_certificate_size failed: {}", e))
+                        })
+                    })
             }
         })
         .unwrap();
@@ -1447,13 +1531,13 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "is_controller", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, src: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, src: I, size: I| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::IS_CONTROLLER,
-                    size as u32,
+                    size.charged_bytes(),
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::IS_CONTROLLER,
                         ..Default::default()
@@ -1462,7 +1546,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_is_controller(src as u32, size as u32, memory)
+                    system_api.ic0_is_controller(src.to_usize(), size.to_usize(), memory)
                 })
             }
         })
@@ -1470,7 +1554,7 @@ pub(crate) fn syscalls<S: SystemApi>(
 
     linker
         .func_wrap("ic0", "data_certificate_copy", {
-            move |mut caller: Caller<'_, StoreData<S>>, dst: u32, offset: u32, size: u32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: I, offset: I, size: I| {
                 observe_execution_complexity(
                     &log,
                     canister_id,
@@ -1482,10 +1566,15 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_data_certificate_copy(dst, offset, size, memory)
+                    system_api.ic0_data_certificate_copy(
+                        dst.to_usize(),
+                        offset.to_usize(),
+                        size.to_usize(),
+                        memory,
+                    )
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, size as usize)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), size.to_usize())
                 } else {
                     Ok(())
                 }
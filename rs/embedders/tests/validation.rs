@@ -1,5 +1,5 @@
 use assert_matches::assert_matches;
-use ic_config::embedders::Config as EmbeddersConfig;
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
 use ic_embedders::{
     wasm_utils::{
         validate_and_instrument_for_testing,
@@ -872,3 +872,79 @@ fn complex_function_rejected() {
         })
     )
 }
+
+fn wasm64_config() -> EmbeddersConfig {
+    let mut config = EmbeddersConfig::default();
+    config.feature_flags.wasm64 = FlagStatus::Enabled;
+    config
+}
+
+#[test]
+fn wasm64_memory_rejected_when_disabled() {
+    let wasm = wat2wasm(r#"(module (memory i64 1))"#).unwrap();
+    assert_matches!(
+        validate_wasm_binary(&wasm, &EmbeddersConfig::default()),
+        Err(WasmValidationError::WasmtimeValidation(_))
+    );
+}
+
+#[test]
+fn can_validate_wasm64_module_with_64_bit_system_api() {
+    let wasm = wat2wasm(
+        r#"(module
+                (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i64)))
+                (import "ic0" "msg_arg_data_copy"
+                    (func $msg_arg_data_copy (param i64 i64 i64)))
+                (import "ic0" "msg_reply_data_append"
+                    (func $msg_reply_data_append (param i64 i64)))
+                (import "ic0" "msg_reply" (func $msg_reply))
+                (func (export "canister_update echo")
+                    (call $msg_arg_data_copy (i64.const 0) (i64.const 0) (call $msg_arg_data_size))
+                    (call $msg_reply_data_append (i64.const 0) (call $msg_arg_data_size))
+                    (call $msg_reply))
+                (memory i64 1)
+                (data (i64.const 0) "x"))"#,
+    )
+    .unwrap();
+    assert_matches!(validate_wasm_binary(&wasm, &wasm64_config()), Ok(_));
+}
+
+#[test]
+fn wasm64_module_with_32_bit_system_api_is_invalid() {
+    let wasm = wat2wasm(
+        r#"(module
+                (import "ic0" "msg_reply_data_append"
+                    (func $msg_reply_data_append (param i32 i32)))
+                (memory i64 1))"#,
+    )
+    .unwrap();
+    assert_matches!(
+        validate_wasm_binary(&wasm, &wasm64_config()),
+        Err(WasmValidationError::InvalidFunctionSignature(_))
+    );
+}
+
+#[test]
+fn wasm64_memory_larger_than_limit_is_invalid() {
+    let mut config = wasm64_config();
+    config.max_wasm64_memory_size = NumBytes::new(64 * 1024 * 1024);
+    let wasm = wat2wasm(r#"(module (memory i64 1025))"#).unwrap();
+    assert_eq!(
+        validate_wasm_binary(&wasm, &config),
+        Err(WasmValidationError::InvalidMemorySection(
+            "Initial memory size of 1025 Wasm pages exceeds the limit of 1024 Wasm pages."
+                .to_string()
+        ))
+    );
+}
+
+#[test]
+fn wasm64_memory_rejected_with_wasm_native_stable_memory() {
+    let mut config = wasm64_config();
+    config.feature_flags.wasm_native_stable_memory = FlagStatus::Enabled;
+    let wasm = wat2wasm(r#"(module (memory i64 1))"#).unwrap();
+    assert_matches!(
+        validate_wasm_binary(&wasm, &config),
+        Err(WasmValidationError::InvalidMemorySection(_))
+    );
+}
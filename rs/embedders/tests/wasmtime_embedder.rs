@@ -1605,4 +1605,58 @@ mod test {
             .build();
         instance.run(func_ref("write_to_last_page")).unwrap();
     }
+
+    /// Test that a Wasm64 canister can pass System API buffers that lie above
+    /// the first 4 GiB of its heap.
+    #[test]
+    fn wasm64_system_api_buffers_beyond_32_bit_range() {
+        let gb = 1024 * 1024 * 1024;
+        // One Wasm page more than 4 GiB.
+        let heap_pages = 4 * gb / WASM_PAGE_SIZE_IN_BYTES as u64 + 1;
+        let dst = 4 * gb + 16;
+        let payload = b"beyond 4 GiB".to_vec();
+
+        let wat = format!(
+            r#"
+        (module
+            (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i64)))
+            (import "ic0" "msg_arg_data_copy"
+                (func $msg_arg_data_copy (param i64 i64 i64)))
+            (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i64 i64)))
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func (export "canister_update echo")
+                (call $msg_arg_data_copy (i64.const {dst}) (i64.const 0) (call $msg_arg_data_size))
+                (call $msg_reply_data_append (i64.const {dst}) (call $msg_arg_data_size))
+                (call $msg_reply)
+            )
+            (memory i64 {heap_pages})
+        )"#
+        );
+
+        let mut config = ic_config::embedders::Config::default();
+        config.feature_flags.wasm64 = ic_config::flag_status::FlagStatus::Enabled;
+        let mut instance = WasmtimeInstanceBuilder::new()
+            .with_config(config)
+            .with_wat(&wat)
+            .with_api_type(ic_system_api::ApiType::update(
+                mock_time(),
+                payload.clone(),
+                Cycles::zero(),
+                PrincipalId::new_user_test_id(0),
+                0.into(),
+            ))
+            .with_canister_memory_limit(NumBytes::from(8 * gb))
+            .build();
+        instance
+            .run(FuncRef::Method(WasmMethod::Update("echo".to_string())))
+            .unwrap();
+
+        let result = instance
+            .into_store_data()
+            .system_api
+            .take_execution_result(None)
+            .unwrap();
+        assert_eq!(result, Some(ic_types::ingress::WasmResult::Reply(payload)));
+    }
 }
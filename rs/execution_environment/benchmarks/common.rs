@@ -6,7 +6,7 @@ use ic_config::execution_environment::Config;
 use ic_config::flag_status::FlagStatus;
 use ic_config::subnet_config::{SchedulerConfig, SubnetConfigs};
 use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
-use ic_cycles_account_manager::CyclesAccountManager;
+use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
 use ic_error_types::RejectCode;
 use ic_execution_environment::{
     as_round_instructions, CompilationCostHandling, ExecutionEnvironment, Hypervisor,
@@ -147,6 +147,7 @@ where
         compute_allocation: canister_state.scheduler_state.compute_allocation,
         subnet_type: hypervisor.subnet_type(),
         execution_mode: ExecutionMode::Replicated,
+        subnet_memory_saturation: ResourceSaturation::default(),
     };
 
     let subnets = vec![own_subnet_id, nns_subnet_id];
@@ -257,6 +258,8 @@ where
         100,
         config,
         cycles_account_manager,
+        1,
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
     );
     for Benchmark(id, wat, expected_instructions) in benchmarks {
         run_benchmark(
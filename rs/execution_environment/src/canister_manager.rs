@@ -1,5 +1,6 @@
 use crate::execution::install_code::{
-    validate_compute_allocation, validate_controller, validate_memory_allocation, OriginalContext,
+    canister_layout, validate_compute_allocation, validate_controller, validate_memory_allocation,
+    OriginalContext,
 };
 use crate::execution::{install::execute_install, upgrade::execute_upgrade};
 use crate::execution_environment::{CompilationCostHandling, RoundContext, RoundLimits};
@@ -11,11 +12,12 @@ use crate::{
 };
 use ic_base_types::NumSeconds;
 use ic_config::flag_status::FlagStatus;
-use ic_cycles_account_manager::CyclesAccountManager;
+use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
-    CanisterInstallMode, CanisterStatusResultV2, CanisterStatusType, InstallCodeArgs,
-    Method as Ic00Method,
+    CanisterInstallMode, CanisterSnapshotResponse, CanisterStatusResultV2, CanisterStatusType,
+    ChunkHash, FetchCanisterLogsResponse, InstallCodeArgs, ListCanisterSnapshotsResponse,
+    LogVisibility, Method as Ic00Method, StoredChunksResponse,
 };
 use ic_interfaces::execution_environment::{
     CanisterOutOfCyclesError, HypervisorError, IngressHistoryWriter, SubnetAvailableMemory,
@@ -25,9 +27,10 @@ use ic_logger::{error, fatal, info, ReplicaLogger};
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::canister_state::system_state::CyclesUseCase;
+use ic_replicated_state::page_map::PageAllocatorFileDescriptor;
 use ic_replicated_state::{
-    CallOrigin, CanisterState, CanisterStatus, NetworkTopology, ReplicatedState, SchedulerState,
-    SystemState,
+    CallOrigin, CanisterSnapshot, CanisterState, CanisterStatus, NetworkTopology, ReplicatedState,
+    SchedulerState, SnapshotId, SystemState,
 };
 use ic_system_api::ExecutionParameters;
 use ic_types::messages::{MessageId, SignedIngressContent};
@@ -47,6 +50,9 @@ use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::{collections::BTreeSet, convert::TryFrom, str::FromStr, sync::Arc};
 
+/// The maximum number of snapshots a single canister can have at any time.
+pub(crate) const MAX_SNAPSHOTS_PER_CANISTER: usize = 1;
+
 #[derive(Debug, PartialEq, Eq)]
 pub(crate) struct InstallCodeResult {
     pub heap_delta: NumBytes,
@@ -261,6 +267,7 @@ pub(crate) struct CanisterManager {
     config: CanisterMgrConfig,
     cycles_account_manager: Arc<CyclesAccountManager>,
     ingress_history_writer: Arc<dyn IngressHistoryWriter<State = ReplicatedState>>,
+    fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
 }
 
 impl CanisterManager {
@@ -270,6 +277,7 @@ impl CanisterManager {
         config: CanisterMgrConfig,
         cycles_account_manager: Arc<CyclesAccountManager>,
         ingress_history_writer: Arc<dyn IngressHistoryWriter<State = ReplicatedState>>,
+        fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
     ) -> Self {
         CanisterManager {
             hypervisor,
@@ -277,6 +285,7 @@ impl CanisterManager {
             config,
             cycles_account_manager,
             ingress_history_writer,
+            fd_factory,
         }
     }
 
@@ -330,7 +339,15 @@ impl CanisterManager {
             | Ok(Ic00Method::DeleteCanister) |
             Ok(Ic00Method::UpdateSettings)|
             Ok(Ic00Method::InstallCode) |
-            Ok(Ic00Method::SetController) => {
+            Ok(Ic00Method::SetController) |
+            Ok(Ic00Method::TakeCanisterSnapshot) |
+            Ok(Ic00Method::LoadCanisterSnapshot) |
+            Ok(Ic00Method::ListCanisterSnapshots) |
+            Ok(Ic00Method::DeleteCanisterSnapshot) |
+            Ok(Ic00Method::UploadChunk) |
+            Ok(Ic00Method::ClearChunkStore) |
+            Ok(Ic00Method::StoredChunks) |
+            Ok(Ic00Method::InstallChunkedCode) => {
                 match effective_canister_id {
                     Some(canister_id) => {
                         let canister = state.canister_state(&canister_id).ok_or_else(|| UserError::new(
@@ -355,6 +372,34 @@ impl CanisterManager {
                 }
             },
 
+            // Canister logs can be fetched by anyone if the canister made them
+            // public, and only by the controllers otherwise.
+            Ok(Ic00Method::FetchCanisterLogs) => {
+                match effective_canister_id {
+                    Some(canister_id) => {
+                        let canister = state.canister_state(&canister_id).ok_or_else(|| UserError::new(
+                            ErrorCode::CanisterNotFound,
+                            format!("Canister {} not found", canister_id),
+                        ))?;
+                        match canister.system_state.log_visibility {
+                            LogVisibility::Public => Ok(()),
+                            LogVisibility::Controllers if canister.controllers().contains(&sender.get()) => Ok(()),
+                            LogVisibility::Controllers => Err(UserError::new(
+                                ErrorCode::CanisterInvalidController,
+                                format!(
+                                    "Only controllers of canister {} can call ic00 method {}",
+                                    canister_id, method_name,
+                                ),
+                            )),
+                        }
+                    },
+                    None =>  Err(UserError::new(
+                        ErrorCode::InvalidManagementPayload,
+                        format!("Failed to decode payload for ic00 method: {}", method_name),
+                    )),
+                }
+            },
+
             Ok(Ic00Method::ProvisionalCreateCanisterWithCycles)
             | Ok(Ic00Method::BitcoinGetSuccessors)
             | Ok(Ic00Method::ProvisionalTopUpCanister) => {
@@ -412,6 +457,86 @@ impl CanisterManager {
         ValidatedCanisterSettings::try_from((settings, self.config.max_controllers))
     }
 
+    /// Validates the requested reserved cycles limit and computes the amount of
+    /// cycles that need to be reserved if the memory allocation increases.
+    fn validate_reserved_cycles(
+        &self,
+        settings: &ValidatedCanisterSettings,
+        canister: &CanisterState,
+        old_mem: NumBytes,
+        subnet_memory_saturation: &ResourceSaturation,
+        subnet_size: usize,
+    ) -> Result<Cycles, CanisterManagerError> {
+        let reserved_balance = canister.system_state.reserved_balance();
+        if let Some(limit) = settings.reserved_cycles_limit {
+            if reserved_balance > limit {
+                return Err(CanisterManagerError::ReservedCyclesLimitIsTooLow {
+                    cycles: reserved_balance,
+                    limit,
+                });
+            }
+        }
+
+        let memory_allocation = match settings.memory_allocation {
+            Some(memory_allocation) => memory_allocation,
+            None => return Ok(Cycles::zero()),
+        };
+        let memory_usage = canister.memory_usage(self.config.own_subnet_type);
+        let new_mem = memory_allocation.bytes().max(memory_usage);
+        if new_mem <= old_mem {
+            return Ok(Cycles::zero());
+        }
+
+        let reservation_cycles = self.cycles_account_manager.storage_reservation_cycles(
+            new_mem - old_mem,
+            subnet_memory_saturation,
+            subnet_size,
+        );
+        if reservation_cycles.get() == 0 {
+            return Ok(Cycles::zero());
+        }
+
+        let limit = settings
+            .reserved_cycles_limit
+            .or_else(|| canister.system_state.reserved_balance_limit());
+        if let Some(limit) = limit {
+            let requested = reserved_balance + reservation_cycles;
+            if requested > limit {
+                return Err(
+                    CanisterManagerError::ReservedCyclesLimitExceededInMemoryAllocation {
+                        memory_allocation,
+                        requested,
+                        limit,
+                    },
+                );
+            }
+        }
+
+        // The canister must stay above its freezing threshold after the
+        // reservation, taking the new settings into account.
+        let threshold = self.cycles_account_manager.freeze_threshold_cycles(
+            settings
+                .freezing_threshold
+                .unwrap_or(canister.system_state.freeze_threshold),
+            memory_allocation,
+            memory_usage,
+            settings
+                .compute_allocation
+                .unwrap_or(canister.scheduler_state.compute_allocation),
+            subnet_size,
+        );
+        let available = canister.system_state.balance();
+        if available < threshold + reservation_cycles {
+            return Err(CanisterManagerError::InsufficientCyclesInMemoryAllocation {
+                memory_allocation,
+                available,
+                threshold: threshold + reservation_cycles,
+            });
+        }
+
+        Ok(reservation_cycles)
+    }
+
     /// Applies the requested settings on the canister.
     /// Note: Called only after validating the settings.
     fn do_update_settings(
@@ -457,16 +582,38 @@ impl CanisterManager {
         if let Some(freezing_threshold) = settings.freezing_threshold {
             canister.system_state.freeze_threshold = freezing_threshold;
         }
+        if let Some(log_visibility) = settings.log_visibility {
+            canister.system_state.log_visibility = log_visibility;
+        }
+        if let Some(wasm_memory_limit) = settings.wasm_memory_limit {
+            // A limit of zero removes the limit.
+            canister.system_state.wasm_memory_limit = if wasm_memory_limit.get() == 0 {
+                None
+            } else {
+                Some(wasm_memory_limit)
+            };
+        }
+        if let Some(reserved_cycles_limit) = settings.reserved_cycles_limit {
+            canister
+                .system_state
+                .set_reserved_balance_limit(Some(reserved_cycles_limit));
+        }
     }
 
     /// Tries to apply the requested settings on the canister identified by
     /// `canister_id`.
+    ///
+    /// If the memory allocation of the canister increases while the subnet
+    /// memory usage is above the threshold, then the canister has to reserve
+    /// cycles for future storage payments based on `subnet_memory_saturation`.
     pub(crate) fn update_settings(
         &self,
         sender: PrincipalId,
         settings: CanisterSettings,
         canister: &mut CanisterState,
         round_limits: &mut RoundLimits,
+        subnet_memory_saturation: &ResourceSaturation,
+        subnet_size: usize,
     ) -> Result<(), CanisterManagerError> {
         // Verify controller.
         validate_controller(canister, &sender)?;
@@ -494,8 +641,24 @@ impl CanisterManager {
             .max(old_usage);
         let old_compute_allocation = canister.scheduler_state.compute_allocation.as_percent();
 
+        let reservation_cycles = self.validate_reserved_cycles(
+            &validated_settings,
+            canister,
+            old_mem,
+            subnet_memory_saturation,
+            subnet_size,
+        )?;
+
         self.do_update_settings(validated_settings, canister);
 
+        // The reservation was validated above so this should always succeed.
+        canister
+            .system_state
+            .reserve_cycles(reservation_cycles)
+            .map_err(|err| CanisterManagerError::InvalidSettings {
+                message: err.to_string(),
+            })?;
+
         let new_compute_allocation = canister.scheduler_state.compute_allocation.as_percent();
         if old_compute_allocation < new_compute_allocation {
             round_limits.compute_allocation_used = round_limits
@@ -797,6 +960,9 @@ impl CanisterManager {
         }
 
         let rejects = uninstall_canister(&self.log, canister, time);
+        state
+            .canister_snapshots
+            .remove_canister_snapshots(canister_id);
         crate::util::process_responses(
             rejects,
             state,
@@ -955,6 +1121,14 @@ impl CanisterManager {
             compute_allocation.as_percent(),
             Some(memory_allocation.bytes().get()),
             freeze_threshold.get(),
+            canister
+                .system_state
+                .wasm_memory_limit
+                .map(|limit| limit.get()),
+            canister
+                .system_state
+                .reserved_balance_limit()
+                .map(|limit| limit.get()),
             self.cycles_account_manager
                 .idle_cycles_burned_rate(
                     memory_allocation,
@@ -963,6 +1137,7 @@ impl CanisterManager {
                     subnet_size,
                 )
                 .get(),
+            canister.system_state.reserved_balance().get(),
         ))
     }
 
@@ -975,6 +1150,8 @@ impl CanisterManager {
         new_controller: PrincipalId,
         state: &mut ReplicatedState,
         round_limits: &mut RoundLimits,
+        subnet_memory_saturation: &ResourceSaturation,
+        subnet_size: usize,
     ) -> Result<(), CanisterManagerError> {
         let canister = state
             .canister_state_mut(&canister_id)
@@ -983,7 +1160,14 @@ impl CanisterManager {
         let settings = CanisterSettingsBuilder::new()
             .with_controller(new_controller)
             .build();
-        self.update_settings(sender, settings, canister, round_limits)
+        self.update_settings(
+            sender,
+            settings,
+            canister,
+            round_limits,
+            subnet_memory_saturation,
+            subnet_size,
+        )
     }
 
     /// Permanently deletes a canister from `ReplicatedState`.
@@ -1027,8 +1211,11 @@ impl CanisterManager {
         // - its state is permanently deleted, and
         // - its cycles are discarded.
 
-        // Take out the canister from `ReplicatedState`.
+        // Take out the canister and its snapshots from `ReplicatedState`.
         let canister_to_delete = state.take_canister_state(&canister_id_to_delete).unwrap();
+        state
+            .canister_snapshots
+            .remove_canister_snapshots(canister_id_to_delete);
         // Leftover cycles in the balance are considered `consumed`.
         let leftover_cycles = NominalCycles::from(canister_to_delete.system_state.balance());
         let consumed_cycles_by_canister_to_delete = leftover_cycles
@@ -1067,6 +1254,394 @@ impl CanisterManager {
         Ok(())
     }
 
+    /// Takes a snapshot of the given canister and stores it in
+    /// `ReplicatedState`.
+    ///
+    /// If `replace_snapshot` is provided, the snapshot with that id is deleted
+    /// once the new snapshot has been taken. Otherwise the canister must have
+    /// fewer than `MAX_SNAPSHOTS_PER_CANISTER` snapshots.
+    pub(crate) fn take_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        replace_snapshot: Option<&[u8]>,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<CanisterSnapshotResponse, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+        validate_no_paused_execution(canister)?;
+
+        let replace_snapshot = match replace_snapshot {
+            Some(snapshot_id) => {
+                Some(self.validate_snapshot_id(state, canister_id, snapshot_id)?)
+            }
+            None => None,
+        };
+
+        let remaining_snapshots = state.canister_snapshots.count_snapshots(canister_id)
+            - replace_snapshot.map_or(0, |_| 1);
+        if remaining_snapshots >= MAX_SNAPSHOTS_PER_CANISTER {
+            return Err(CanisterManagerError::CanisterSnapshotLimitExceeded {
+                canister_id,
+                limit: MAX_SNAPSHOTS_PER_CANISTER,
+            });
+        }
+
+        let snapshot = CanisterSnapshot::from_canister(canister, state.time()).ok_or(
+            CanisterManagerError::CanisterSnapshotEmptyCanister(canister_id),
+        )?;
+
+        let new_size = snapshot.size();
+        let old_size = replace_snapshot
+            .and_then(|snapshot_id| state.canister_snapshots.get(&snapshot_id))
+            .map_or(NumBytes::from(0), |snapshot| snapshot.size());
+        if new_size > old_size {
+            let requested = new_size - old_size;
+            round_limits
+                .subnet_available_memory
+                .try_decrement(requested, NumBytes::from(0), NumBytes::from(0))
+                .map_err(
+                    |_| CanisterManagerError::SubnetMemoryCapacityOverSubscribed {
+                        requested_total: requested,
+                        requested_wasm_custom_sections: NumBytes::from(0),
+                        available_total: NumBytes::from(
+                            round_limits
+                                .subnet_available_memory
+                                .get_total_memory()
+                                .max(0) as u64,
+                        ),
+                        available_wasm_custom_sections: NumBytes::from(
+                            round_limits
+                                .subnet_available_memory
+                                .get_wasm_custom_sections_memory()
+                                .max(0) as u64,
+                        ),
+                    },
+                )?;
+        } else {
+            round_limits.subnet_available_memory.increment(
+                old_size - new_size,
+                NumBytes::from(0),
+                NumBytes::from(0),
+            );
+        }
+
+        if let Some(snapshot_id) = replace_snapshot {
+            state.canister_snapshots.remove(&snapshot_id);
+        }
+        let snapshot_id = state.metadata.generate_new_snapshot_id(canister_id);
+        let response = CanisterSnapshotResponse::new(
+            snapshot_id.to_vec(),
+            snapshot.taken_at_timestamp().as_nanos_since_unix_epoch(),
+            new_size.get(),
+        );
+        state
+            .canister_snapshots
+            .push(snapshot_id, Arc::new(snapshot));
+
+        info!(
+            self.log,
+            "Took snapshot {} of canister {} with size {}.",
+            snapshot_id,
+            canister_id,
+            new_size.display()
+        );
+        Ok(response)
+    }
+
+    /// Replaces the Wasm module, heap, stable memory, exported globals and
+    /// certified data of the given canister with those of one of its
+    /// snapshots.
+    pub(crate) fn load_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        snapshot_id: &[u8],
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<(), CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+        validate_no_paused_execution(canister)?;
+
+        let snapshot_id = self.validate_snapshot_id(state, canister_id, snapshot_id)?;
+        let snapshot = Arc::clone(state.canister_snapshots.get(&snapshot_id).unwrap());
+
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        let canister_root = match &canister.execution_state {
+            Some(execution_state) => execution_state.canister_root.clone(),
+            None => canister_layout(&PathBuf::from("NOT_USED"), &canister_id).raw_path(),
+        };
+        let old_usage = canister.memory_usage(self.config.own_subnet_type);
+        let new_execution_state =
+            snapshot.to_execution_state(canister_root, Arc::clone(&self.fd_factory));
+        let new_usage = old_usage
+            - canister
+                .execution_state
+                .as_ref()
+                .map_or(NumBytes::from(0), |es| es.memory_usage())
+            + new_execution_state.memory_usage();
+
+        match canister.memory_allocation() {
+            MemoryAllocation::Reserved(bytes) => {
+                if new_usage > bytes {
+                    return Err(CanisterManagerError::NotEnoughMemoryAllocationGiven {
+                        canister_id,
+                        memory_allocation_given: canister.memory_allocation(),
+                        memory_usage_needed: new_usage,
+                    });
+                }
+            }
+            MemoryAllocation::BestEffort => {
+                if new_usage > old_usage {
+                    let requested = new_usage - old_usage;
+                    round_limits
+                        .subnet_available_memory
+                        .try_decrement(requested, NumBytes::from(0), NumBytes::from(0))
+                        .map_err(
+                            |_| CanisterManagerError::SubnetMemoryCapacityOverSubscribed {
+                                requested_total: requested,
+                                requested_wasm_custom_sections: NumBytes::from(0),
+                                available_total: NumBytes::from(
+                                    round_limits
+                                        .subnet_available_memory
+                                        .get_total_memory()
+                                        .max(0) as u64,
+                                ),
+                                available_wasm_custom_sections: NumBytes::from(
+                                    round_limits
+                                        .subnet_available_memory
+                                        .get_wasm_custom_sections_memory()
+                                        .max(0) as u64,
+                                ),
+                            },
+                        )?;
+                } else {
+                    round_limits.subnet_available_memory.increment(
+                        old_usage - new_usage,
+                        NumBytes::from(0),
+                        NumBytes::from(0),
+                    );
+                }
+            }
+        }
+
+        canister.execution_state = Some(new_execution_state);
+        canister.system_state.certified_data = snapshot.certified_data().clone();
+        canister.system_state.canister_version += 1;
+
+        info!(
+            self.log,
+            "Loaded snapshot {} into canister {}.", snapshot_id, canister_id
+        );
+        Ok(())
+    }
+
+    /// Lists the snapshots of the given canister.
+    pub(crate) fn list_canister_snapshots(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &ReplicatedState,
+    ) -> Result<ListCanisterSnapshotsResponse, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        Ok(state
+            .canister_snapshots
+            .list_snapshots(canister_id)
+            .into_iter()
+            .map(|(snapshot_id, snapshot)| {
+                CanisterSnapshotResponse::new(
+                    snapshot_id.to_vec(),
+                    snapshot.taken_at_timestamp().as_nanos_since_unix_epoch(),
+                    snapshot.size().get(),
+                )
+            })
+            .collect())
+    }
+
+    /// Deletes a snapshot of the given canister.
+    pub(crate) fn delete_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        snapshot_id: &[u8],
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<(), CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        let snapshot_id = self.validate_snapshot_id(state, canister_id, snapshot_id)?;
+        let snapshot = state.canister_snapshots.remove(&snapshot_id).unwrap();
+        round_limits.subnet_available_memory.increment(
+            snapshot.size(),
+            NumBytes::from(0),
+            NumBytes::from(0),
+        );
+        Ok(())
+    }
+
+    /// Returns the records of the canister log. Depending on the log
+    /// visibility of the canister, either only its controllers or anyone can
+    /// fetch them.
+    pub(crate) fn fetch_canister_logs(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &ReplicatedState,
+    ) -> Result<FetchCanisterLogsResponse, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        match canister.system_state.log_visibility {
+            LogVisibility::Public => {}
+            LogVisibility::Controllers => validate_controller(canister, &sender)?,
+        }
+
+        Ok(FetchCanisterLogsResponse {
+            canister_log_records: canister
+                .system_state
+                .canister_log
+                .records()
+                .iter()
+                .cloned()
+                .collect(),
+        })
+    }
+
+    /// Stores a chunk of a Wasm module in the chunk store of the given
+    /// canister and returns its hash.
+    ///
+    /// The chunk counts towards the memory usage of the canister, so the
+    /// canister must have enough memory allocation (or the subnet enough
+    /// available memory) and must not become frozen by storing it.
+    pub(crate) fn upload_chunk(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        chunk: Vec<u8>,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+        subnet_size: usize,
+    ) -> Result<ChunkHash, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        canister
+            .system_state
+            .wasm_chunk_store
+            .can_insert_chunk(&chunk)
+            .map_err(|err| CanisterManagerError::WasmChunkStoreError {
+                message: err.to_string(),
+            })?;
+
+        let requested = canister
+            .system_state
+            .wasm_chunk_store
+            .memory_needed_for_chunk(&chunk);
+        let new_usage = canister.memory_usage(self.config.own_subnet_type) + requested;
+
+        self.cycles_account_manager
+            .can_withdraw_cycles(
+                &canister.system_state,
+                Cycles::zero(),
+                new_usage,
+                canister.scheduler_state.compute_allocation,
+                subnet_size,
+            )
+            .map_err(CanisterManagerError::WasmChunkStoreNotEnoughCycles)?;
+
+        match canister.memory_allocation() {
+            MemoryAllocation::Reserved(bytes) => {
+                if new_usage > bytes {
+                    return Err(CanisterManagerError::NotEnoughMemoryAllocationGiven {
+                        canister_id,
+                        memory_allocation_given: canister.memory_allocation(),
+                        memory_usage_needed: new_usage,
+                    });
+                }
+            }
+            MemoryAllocation::BestEffort => {
+                round_limits
+                    .subnet_available_memory
+                    .try_decrement(requested, NumBytes::from(0), NumBytes::from(0))
+                    .map_err(
+                        |_| CanisterManagerError::SubnetMemoryCapacityOverSubscribed {
+                            requested_total: requested,
+                            requested_wasm_custom_sections: NumBytes::from(0),
+                            available_total: NumBytes::from(
+                                round_limits
+                                    .subnet_available_memory
+                                    .get_total_memory()
+                                    .max(0) as u64,
+                            ),
+                            available_wasm_custom_sections: NumBytes::from(
+                                round_limits
+                                    .subnet_available_memory
+                                    .get_wasm_custom_sections_memory()
+                                    .max(0) as u64,
+                            ),
+                        },
+                    )?;
+            }
+        }
+
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        let hash = canister
+            .system_state
+            .wasm_chunk_store
+            .insert_chunk(chunk)
+            .expect("Inserting a validated chunk cannot fail.");
+        Ok(ChunkHash {
+            hash: hash.to_vec(),
+        })
+    }
+
+    /// Removes all chunks from the chunk store of the given canister.
+    pub(crate) fn clear_chunk_store(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<(), CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        if canister.memory_allocation() == MemoryAllocation::BestEffort {
+            round_limits.subnet_available_memory.increment(
+                canister.system_state.wasm_chunk_store.memory_usage(),
+                NumBytes::from(0),
+                NumBytes::from(0),
+            );
+        }
+        canister.system_state.wasm_chunk_store.clear();
+        Ok(())
+    }
+
+    /// Returns the hashes of all chunks in the chunk store of the given
+    /// canister.
+    pub(crate) fn stored_chunks(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &ReplicatedState,
+    ) -> Result<StoredChunksResponse, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        Ok(canister
+            .system_state
+            .wasm_chunk_store
+            .keys()
+            .map(|hash| ChunkHash {
+                hash: hash.to_vec(),
+            })
+            .collect())
+    }
+
     /// Creates a new canister with the cycles amount specified and inserts it
     /// into `ReplicatedState`.
     ///
@@ -1290,6 +1865,27 @@ impl CanisterManager {
             .canister_state(&canister_id)
             .ok_or(CanisterManagerError::CanisterNotFound(canister_id))
     }
+
+    /// Parses the given snapshot id and checks that it refers to an existing
+    /// snapshot of the given canister.
+    fn validate_snapshot_id(
+        &self,
+        state: &ReplicatedState,
+        canister_id: CanisterId,
+        snapshot_id: &[u8],
+    ) -> Result<SnapshotId, CanisterManagerError> {
+        let not_found = || CanisterManagerError::CanisterSnapshotNotFound {
+            canister_id,
+            snapshot_id: snapshot_id.to_vec(),
+        };
+        let parsed = SnapshotId::try_from(snapshot_id).map_err(|_| not_found())?;
+        // A snapshot of another canister is reported as not found so that
+        // the existence of other canisters' snapshots is not revealed.
+        if parsed.canister_id() != canister_id || state.canister_snapshots.get(&parsed).is_none() {
+            return Err(not_found());
+        }
+        Ok(parsed)
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -1341,6 +1937,34 @@ pub(crate) enum CanisterManagerError {
     CanisterNotHostedBySubnet {
         message: String,
     },
+    CanisterSnapshotNotFound {
+        canister_id: CanisterId,
+        snapshot_id: Vec<u8>,
+    },
+    CanisterSnapshotLimitExceeded {
+        canister_id: CanisterId,
+        limit: usize,
+    },
+    CanisterSnapshotEmptyCanister(CanisterId),
+    CanisterSnapshotPausedExecution(CanisterId),
+    WasmChunkStoreError {
+        message: String,
+    },
+    WasmChunkStoreNotEnoughCycles(CanisterOutOfCyclesError),
+    InsufficientCyclesInMemoryAllocation {
+        memory_allocation: MemoryAllocation,
+        available: Cycles,
+        threshold: Cycles,
+    },
+    ReservedCyclesLimitExceededInMemoryAllocation {
+        memory_allocation: MemoryAllocation,
+        requested: Cycles,
+        limit: Cycles,
+    },
+    ReservedCyclesLimitIsTooLow {
+        cycles: Cycles,
+        limit: Cycles,
+    },
 }
 
 impl From<CanisterManagerError> for UserError {
@@ -1499,6 +2123,69 @@ impl From<CanisterManagerError> for UserError {
                     format!("Unsuccessful validation of specified ID: {}", message),
                 )
             }
+            CanisterSnapshotNotFound { canister_id, snapshot_id } => {
+                Self::new(
+                    ErrorCode::CanisterSnapshotNotFound,
+                    format!("Could not find the snapshot ID {} for canister {}.", hex::encode(snapshot_id), canister_id),
+                )
+            }
+            CanisterSnapshotLimitExceeded { canister_id, limit } => {
+                Self::new(
+                    ErrorCode::CanisterContractViolation,
+                    format!("Canister {} has reached the maximum number of {} snapshots. Delete or replace an existing snapshot.", canister_id, limit),
+                )
+            }
+            CanisterSnapshotEmptyCanister(canister_id) => {
+                Self::new(
+                    ErrorCode::CanisterWasmModuleNotFound,
+                    format!("Canister {} is empty and cannot be snapshotted.", canister_id),
+                )
+            }
+            CanisterSnapshotPausedExecution(canister_id) => {
+                Self::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!("Canister {} has a long-running execution in progress. Please retry after some time.", canister_id),
+                )
+            }
+            WasmChunkStoreError { message } => {
+                Self::new(
+                    ErrorCode::CanisterContractViolation,
+                    format!("Error from Wasm chunk store: {}", message),
+                )
+            }
+            WasmChunkStoreNotEnoughCycles(err) => {
+                Self::new(
+                    ErrorCode::CanisterOutOfCycles,
+                    format!("Cannot upload chunk: {}", err),
+                )
+            }
+            InsufficientCyclesInMemoryAllocation { memory_allocation, available, threshold } => {
+                Self::new(
+                    ErrorCode::CanisterOutOfCycles,
+                    format!(
+                        "Cannot increase memory allocation to {} due to insufficient cycles. At least {} additional cycles are required.",
+                        memory_allocation, threshold - available
+                    ),
+                )
+            }
+            ReservedCyclesLimitExceededInMemoryAllocation { memory_allocation, requested, limit } => {
+                Self::new(
+                    ErrorCode::ReservedCyclesLimitExceededInMemoryAllocation,
+                    format!(
+                        "Cannot increase memory allocation to {} due to its reserved cycles limit. The current limit ({}) would be exceeded by {}.",
+                        memory_allocation, limit, requested - limit,
+                    ),
+                )
+            }
+            ReservedCyclesLimitIsTooLow { cycles, limit } => {
+                Self::new(
+                    ErrorCode::ReservedCyclesLimitIsTooLow,
+                    format!(
+                        "Cannot set the reserved cycles limit {} below the reserved cycles balance of the canister {}.",
+                        limit, cycles,
+                    ),
+                )
+            }
         }
     }
 }
@@ -1516,6 +2203,18 @@ impl From<CanisterManagerError> for RejectContext {
     }
 }
 
+/// Snapshots must not be taken or loaded while the canister is in the middle
+/// of a long-running execution, as that execution works on a copy of the
+/// canister state which would be inconsistent with the snapshot.
+fn validate_no_paused_execution(canister: &CanisterState) -> Result<(), CanisterManagerError> {
+    if canister.has_paused_execution() || canister.has_paused_install_code() {
+        return Err(CanisterManagerError::CanisterSnapshotPausedExecution(
+            canister.canister_id(),
+        ));
+    }
+    Ok(())
+}
+
 /// Uninstalls a canister.
 ///
 /// See https://sdk.dfinity.org/docs/interface-spec/index.html#ic-uninstall_code
@@ -1533,6 +2232,12 @@ pub fn uninstall_canister(
     // Drop its certified data.
     canister.system_state.certified_data = Vec::new();
 
+    // Drop its log records.
+    canister.system_state.canister_log.clear();
+
+    // Drop its uploaded Wasm chunks, which still count towards its memory usage.
+    canister.system_state.wasm_chunk_store.clear();
+
     // Deactivate global timer.
     canister.system_state.global_timer = CanisterTimer::Inactive;
     // Increment canister version.
@@ -1604,6 +2309,9 @@ struct ValidatedCanisterSettings {
     pub compute_allocation: Option<ComputeAllocation>,
     pub memory_allocation: Option<MemoryAllocation>,
     pub freezing_threshold: Option<NumSeconds>,
+    pub log_visibility: Option<LogVisibility>,
+    pub wasm_memory_limit: Option<NumBytes>,
+    pub reserved_cycles_limit: Option<Cycles>,
 }
 
 impl TryFrom<(CanisterSettings, usize)> for ValidatedCanisterSettings {
@@ -1639,6 +2347,9 @@ impl TryFrom<(CanisterSettings, usize)> for ValidatedCanisterSettings {
             compute_allocation: settings.compute_allocation(),
             memory_allocation: settings.memory_allocation(),
             freezing_threshold: settings.freezing_threshold(),
+            log_visibility: settings.log_visibility(),
+            wasm_memory_limit: settings.wasm_memory_limit(),
+            reserved_cycles_limit: settings.reserved_cycles_limit(),
         })
     }
 }
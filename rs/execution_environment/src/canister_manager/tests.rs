@@ -2,7 +2,7 @@ use crate::{
     as_num_instructions,
     canister_manager::{
         uninstall_canister, CanisterManager, CanisterManagerError, CanisterMgrConfig,
-        InstallCodeContext, StopCanisterResult,
+        InstallCodeContext, StopCanisterResult, MAX_SNAPSHOTS_PER_CANISTER,
     },
     canister_settings::{CanisterSettings, CanisterSettingsBuilder},
     execution_environment::as_round_instructions,
@@ -17,11 +17,12 @@ use ic_config::{
     execution_environment::Config, flag_status::FlagStatus, subnet_config::SchedulerConfig,
 };
 use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
-use ic_cycles_account_manager::CyclesAccountManager;
+use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
 use ic_error_types::{ErrorCode, UserError};
 use ic_ic00_types::{
     CanisterIdRecord, CanisterInstallMode, CanisterSettingsArgsBuilder, CanisterStatusType,
-    CreateCanisterArgs, EmptyBlob, InstallCodeArgs, Method, Payload, UpdateSettingsArgs,
+    CreateCanisterArgs, EmptyBlob, InstallCodeArgs, LogVisibility, Method, Payload,
+    UpdateSettingsArgs,
 };
 use ic_interfaces::{
     execution_environment::{
@@ -35,11 +36,13 @@ use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_routing_table::{CanisterIdRange, RoutingTable, CANISTER_IDS_PER_SUBNET};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    canister_state::system_state::CyclesUseCase, testing::SystemStateTesting, SystemState,
+    canister_state::system_state::{CyclesUseCase, MAX_WASM_CHUNK_SIZE},
+    testing::SystemStateTesting,
+    SystemState,
 };
 use ic_replicated_state::{
     page_map, testing::CanisterQueuesTesting, CallContextManager, CallOrigin, CanisterState,
-    CanisterStatus, NumWasmPages, PageMap, ReplicatedState,
+    CanisterStatus, Memory, NumWasmPages, PageMap, ReplicatedState, SnapshotId,
 };
 use ic_system_api::{ExecutionParameters, InstructionLimits};
 use ic_test_utilities::{
@@ -107,6 +110,7 @@ lazy_static! {
         compute_allocation: ComputeAllocation::default(),
         subnet_type: SubnetType::Application,
         execution_mode: ExecutionMode::Replicated,
+        subnet_memory_saturation: ResourceSaturation::default(),
     };
 }
 
@@ -225,6 +229,7 @@ impl CanisterManagerBuilder {
             ),
             cycles_account_manager,
             ingress_history_writer,
+            Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
         )
     }
 }
@@ -2087,6 +2092,8 @@ fn set_controller_with_incorrect_controller() {
                 new_controller,
                 &mut state,
                 &mut round_limits,
+                &ResourceSaturation::default(),
+                SMALL_APP_SUBNET_MAX_SIZE,
             ),
             Err(CanisterManagerError::CanisterInvalidController {
                 canister_id,
@@ -2130,7 +2137,9 @@ fn set_controller_with_correct_controller() {
                 canister_id,
                 new_controller,
                 &mut state,
-                &mut round_limits
+                &mut round_limits,
+                &ResourceSaturation::default(),
+                SMALL_APP_SUBNET_MAX_SIZE
             )
             .is_ok());
 
@@ -3529,7 +3538,14 @@ fn lower_memory_allocation_than_usage_fails() {
         let canister = state.canister_state_mut(&canister_id).unwrap();
 
         assert_matches!(
-            canister_manager.update_settings(sender, settings, canister, &mut round_limits,),
+            canister_manager.update_settings(
+                sender,
+                settings,
+                canister,
+                &mut round_limits,
+                &ResourceSaturation::default(),
+                SMALL_APP_SUBNET_MAX_SIZE
+            ),
             Err(CanisterManagerError::NotEnoughMemoryAllocationGiven { .. })
         );
     })
@@ -3597,7 +3613,14 @@ fn test_install_when_updating_memory_allocation_via_canister_settings() {
         let canister = state.canister_state_mut(&canister_id).unwrap();
 
         canister_manager
-            .update_settings(sender, settings, canister, &mut round_limits)
+            .update_settings(
+                sender,
+                settings,
+                canister,
+                &mut round_limits,
+                &ResourceSaturation::default(),
+                SMALL_APP_SUBNET_MAX_SIZE,
+            )
             .unwrap();
 
         install_code(
@@ -3713,7 +3736,14 @@ fn test_upgrade_when_updating_memory_allocation_via_canister_settings() {
         let canister = state.canister_state_mut(&canister_id).unwrap();
 
         canister_manager
-            .update_settings(sender, settings, canister, &mut round_limits)
+            .update_settings(
+                sender,
+                settings,
+                canister,
+                &mut round_limits,
+                &ResourceSaturation::default(),
+                SMALL_APP_SUBNET_MAX_SIZE,
+            )
             .unwrap();
 
         install_code(
@@ -3817,6 +3847,8 @@ fn test_install_when_setting_memory_allocation_to_zero() {
                 canister,
                 //memory_allocation_used,
                 &mut round_limits,
+                &ResourceSaturation::default(),
+                SMALL_APP_SUBNET_MAX_SIZE,
             )
             .unwrap();
 
@@ -3897,7 +3929,14 @@ fn test_upgrade_when_setting_memory_allocation_to_zero() {
         let canister = state.canister_state_mut(&canister_id).unwrap();
 
         canister_manager
-            .update_settings(sender, settings, canister, &mut round_limits)
+            .update_settings(
+                sender,
+                settings,
+                canister,
+                &mut round_limits,
+                &ResourceSaturation::default(),
+                SMALL_APP_SUBNET_MAX_SIZE,
+            )
             .unwrap();
 
         install_code(
@@ -5254,3 +5293,775 @@ fn delete_canister_with_non_empty_output_queue_fails() {
         );
     });
 }
+
+fn snapshot_round_limits(state: &ReplicatedState) -> RoundLimits {
+    RoundLimits {
+        instructions: as_round_instructions(EXECUTION_PARAMETERS.instruction_limits.message()),
+        execution_complexity: ExecutionComplexity::MAX,
+        subnet_available_memory: (*MAX_SUBNET_AVAILABLE_MEMORY),
+        compute_allocation_used: state.total_compute_allocation(),
+    }
+}
+
+fn canister_for_snapshots(canister_id: CanisterId, controller: PrincipalId) -> CanisterState {
+    CanisterStateBuilder::new()
+        .with_canister_id(canister_id)
+        .with_controller(controller)
+        .with_wasm(MINIMAL_WASM.to_vec())
+        .with_stable_memory(vec![1; 100])
+        .with_certified_data(vec![2, 3])
+        .build()
+}
+
+#[test]
+fn take_and_list_canister_snapshots() {
+    with_setup(|canister_manager, mut state, _| {
+        let canister_id = canister_test_id(0);
+        let controller = user_test_id(1).get();
+        state.put_canister_state(canister_for_snapshots(canister_id, controller));
+        let mut round_limits = snapshot_round_limits(&state);
+        let available_memory_before = round_limits.subnet_available_memory.get_total_memory();
+
+        let response = canister_manager
+            .take_canister_snapshot(controller, canister_id, None, &mut state, &mut round_limits)
+            .unwrap();
+
+        let snapshot_id = SnapshotId::try_from(response.id.as_slice()).unwrap();
+        assert_eq!(snapshot_id.canister_id(), canister_id);
+        let snapshot = state.canister_snapshots.get(&snapshot_id).unwrap();
+        assert_eq!(response.total_size, snapshot.size().get());
+        assert_eq!(snapshot.certified_data(), &vec![2, 3]);
+        assert_eq!(
+            round_limits.subnet_available_memory.get_total_memory(),
+            available_memory_before - response.total_size as i64
+        );
+
+        assert_eq!(
+            canister_manager.list_canister_snapshots(controller, canister_id, &state),
+            Ok(vec![response])
+        );
+    });
+}
+
+#[test]
+fn take_canister_snapshot_of_empty_canister_fails() {
+    with_setup(|canister_manager, mut state, _| {
+        let canister_id = canister_test_id(0);
+        let controller = user_test_id(1).get();
+        state.put_canister_state(
+            CanisterStateBuilder::new()
+                .with_canister_id(canister_id)
+                .with_controller(controller)
+                .build(),
+        );
+        let mut round_limits = snapshot_round_limits(&state);
+
+        assert_eq!(
+            canister_manager.take_canister_snapshot(
+                controller,
+                canister_id,
+                None,
+                &mut state,
+                &mut round_limits
+            ),
+            Err(CanisterManagerError::CanisterSnapshotEmptyCanister(
+                canister_id
+            ))
+        );
+        assert!(state.canister_snapshots.is_empty());
+    });
+}
+
+#[test]
+fn canister_snapshot_methods_require_a_controller() {
+    with_setup(|canister_manager, mut state, _| {
+        let canister_id = canister_test_id(0);
+        let controller = user_test_id(1).get();
+        let other = user_test_id(2).get();
+        state.put_canister_state(canister_for_snapshots(canister_id, controller));
+        let mut round_limits = snapshot_round_limits(&state);
+        let snapshot_id = canister_manager
+            .take_canister_snapshot(controller, canister_id, None, &mut state, &mut round_limits)
+            .unwrap()
+            .id;
+
+        let expected_err = CanisterManagerError::CanisterInvalidController {
+            canister_id,
+            controllers_expected: btreeset! {controller},
+            controller_provided: other,
+        };
+        assert_eq!(
+            canister_manager
+                .take_canister_snapshot(other, canister_id, None, &mut state, &mut round_limits)
+                .unwrap_err(),
+            expected_err
+        );
+        assert_eq!(
+            canister_manager
+                .load_canister_snapshot(
+                    other,
+                    canister_id,
+                    &snapshot_id,
+                    &mut state,
+                    &mut round_limits
+                )
+                .unwrap_err(),
+            expected_err
+        );
+        assert_eq!(
+            canister_manager
+                .list_canister_snapshots(other, canister_id, &state)
+                .unwrap_err(),
+            expected_err
+        );
+        assert_eq!(
+            canister_manager
+                .delete_canister_snapshot(
+                    other,
+                    canister_id,
+                    &snapshot_id,
+                    &mut state,
+                    &mut round_limits
+                )
+                .unwrap_err(),
+            expected_err
+        );
+    });
+}
+
+#[test]
+fn take_canister_snapshot_respects_limit_unless_replacing() {
+    with_setup(|canister_manager, mut state, _| {
+        let canister_id = canister_test_id(0);
+        let controller = user_test_id(1).get();
+        state.put_canister_state(canister_for_snapshots(canister_id, controller));
+        let mut round_limits = snapshot_round_limits(&state);
+        let first = canister_manager
+            .take_canister_snapshot(controller, canister_id, None, &mut state, &mut round_limits)
+            .unwrap();
+
+        assert_eq!(
+            canister_manager.take_canister_snapshot(
+                controller,
+                canister_id,
+                None,
+                &mut state,
+                &mut round_limits
+            ),
+            Err(CanisterManagerError::CanisterSnapshotLimitExceeded {
+                canister_id,
+                limit: MAX_SNAPSHOTS_PER_CANISTER,
+            })
+        );
+
+        let second = canister_manager
+            .take_canister_snapshot(
+                controller,
+                canister_id,
+                Some(&first.id),
+                &mut state,
+                &mut round_limits,
+            )
+            .unwrap();
+        assert_ne!(first.id, second.id);
+        assert_eq!(
+            canister_manager.list_canister_snapshots(controller, canister_id, &state),
+            Ok(vec![second])
+        );
+    });
+}
+
+#[test]
+fn load_canister_snapshot_restores_canister() {
+    with_setup(|canister_manager, mut state, _| {
+        let canister_id = canister_test_id(0);
+        let controller = user_test_id(1).get();
+        state.put_canister_state(canister_for_snapshots(canister_id, controller));
+        let mut round_limits = snapshot_round_limits(&state);
+        let snapshot_id = canister_manager
+            .take_canister_snapshot(controller, canister_id, None, &mut state, &mut round_limits)
+            .unwrap()
+            .id;
+        let expected_stable_memory = state
+            .canister_state(&canister_id)
+            .unwrap()
+            .execution_state
+            .as_ref()
+            .unwrap()
+            .stable_memory
+            .clone();
+
+        // Modify the canister after the snapshot was taken.
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        canister.system_state.certified_data = vec![42];
+        canister.execution_state.as_mut().unwrap().stable_memory =
+            Memory::new(PageMap::new_for_testing(), NumWasmPages::new(0));
+        let version_before = canister.system_state.canister_version;
+
+        canister_manager
+            .load_canister_snapshot(
+                controller,
+                canister_id,
+                &snapshot_id,
+                &mut state,
+                &mut round_limits,
+            )
+            .unwrap();
+
+        let canister = state.canister_state(&canister_id).unwrap();
+        assert_eq!(canister.system_state.certified_data, vec![2, 3]);
+        assert_eq!(
+            canister.execution_state.as_ref().unwrap().stable_memory,
+            expected_stable_memory
+        );
+        assert_eq!(canister.system_state.canister_version, version_before + 1);
+        // Loading a snapshot does not consume it.
+        assert_eq!(state.canister_snapshots.count_snapshots(canister_id), 1);
+    });
+}
+
+#[test]
+fn canister_snapshots_of_other_canisters_are_not_found() {
+    with_setup(|canister_manager, mut state, _| {
+        let (canister_1, canister_2) = (canister_test_id(0), canister_test_id(1));
+        let controller = user_test_id(1).get();
+        state.put_canister_state(canister_for_snapshots(canister_1, controller));
+        state.put_canister_state(canister_for_snapshots(canister_2, controller));
+        let mut round_limits = snapshot_round_limits(&state);
+        let snapshot_id = canister_manager
+            .take_canister_snapshot(controller, canister_1, None, &mut state, &mut round_limits)
+            .unwrap()
+            .id;
+
+        let expected_err = || CanisterManagerError::CanisterSnapshotNotFound {
+            canister_id: canister_2,
+            snapshot_id: snapshot_id.clone(),
+        };
+        assert_eq!(
+            canister_manager.load_canister_snapshot(
+                controller,
+                canister_2,
+                &snapshot_id,
+                &mut state,
+                &mut round_limits
+            ),
+            Err(expected_err())
+        );
+        assert_eq!(
+            canister_manager.delete_canister_snapshot(
+                controller,
+                canister_2,
+                &snapshot_id,
+                &mut state,
+                &mut round_limits
+            ),
+            Err(expected_err())
+        );
+        assert_eq!(
+            UserError::from(expected_err()).code(),
+            ErrorCode::CanisterSnapshotNotFound
+        );
+        assert_eq!(state.canister_snapshots.count_snapshots(canister_1), 1);
+    });
+}
+
+#[test]
+fn delete_canister_snapshot_frees_memory() {
+    with_setup(|canister_manager, mut state, _| {
+        let canister_id = canister_test_id(0);
+        let controller = user_test_id(1).get();
+        state.put_canister_state(canister_for_snapshots(canister_id, controller));
+        let mut round_limits = snapshot_round_limits(&state);
+        let available_memory_before = round_limits.subnet_available_memory.get_total_memory();
+        let snapshot_id = canister_manager
+            .take_canister_snapshot(controller, canister_id, None, &mut state, &mut round_limits)
+            .unwrap()
+            .id;
+
+        assert_eq!(
+            canister_manager.delete_canister_snapshot(
+                controller,
+                canister_id,
+                &snapshot_id,
+                &mut state,
+                &mut round_limits
+            ),
+            Ok(())
+        );
+        assert!(state.canister_snapshots.is_empty());
+        assert_eq!(
+            round_limits.subnet_available_memory.get_total_memory(),
+            available_memory_before
+        );
+    });
+}
+
+#[test]
+fn delete_canister_deletes_its_snapshots() {
+    with_setup(|canister_manager, mut state, _| {
+        let canister_id = canister_test_id(0);
+        let controller_id = canister_test_id(1);
+        state.put_canister_state(canister_for_snapshots(canister_id, controller_id.get()));
+        let mut round_limits = snapshot_round_limits(&state);
+        canister_manager
+            .take_canister_snapshot(
+                controller_id.get(),
+                canister_id,
+                None,
+                &mut state,
+                &mut round_limits,
+            )
+            .unwrap();
+        state
+            .canister_state_mut(&canister_id)
+            .unwrap()
+            .system_state
+            .status = CanisterStatus::Stopped;
+
+        assert_eq!(
+            canister_manager.delete_canister(controller_id.get(), canister_id, &mut state),
+            Ok(())
+        );
+        assert!(state.canister_snapshots.is_empty());
+    });
+}
+
+#[test]
+fn fetch_canister_logs_is_controller_only_by_default() {
+    with_setup(|canister_manager, mut state, _| {
+        let canister_id = canister_test_id(0);
+        let controller = user_test_id(1).get();
+        let other = user_test_id(2).get();
+        let mut canister = canister_for_snapshots(canister_id, controller);
+        canister
+            .system_state
+            .canister_log
+            .add_record(42, b"hello".to_vec());
+        state.put_canister_state(canister);
+
+        let response = canister_manager
+            .fetch_canister_logs(controller, canister_id, &state)
+            .unwrap();
+        assert_eq!(response.canister_log_records.len(), 1);
+        assert_eq!(response.canister_log_records[0].timestamp_nanos, 42);
+        assert_eq!(response.canister_log_records[0].content, b"hello".to_vec());
+
+        assert_eq!(
+            canister_manager.fetch_canister_logs(other, canister_id, &state),
+            Err(CanisterManagerError::CanisterInvalidController {
+                canister_id,
+                controllers_expected: btreeset! {controller},
+                controller_provided: other,
+            })
+        );
+    });
+}
+
+#[test]
+fn fetch_canister_logs_with_public_visibility() {
+    with_setup(|canister_manager, mut state, _| {
+        let canister_id = canister_test_id(0);
+        let controller = user_test_id(1).get();
+        let other = user_test_id(2).get();
+        state.put_canister_state(canister_for_snapshots(canister_id, controller));
+        let mut round_limits = snapshot_round_limits(&state);
+
+        let settings = CanisterSettingsBuilder::new()
+            .with_log_visibility(LogVisibility::Public)
+            .build();
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        canister_manager
+            .update_settings(
+                controller,
+                settings,
+                canister,
+                &mut round_limits,
+                &ResourceSaturation::default(),
+                SMALL_APP_SUBNET_MAX_SIZE,
+            )
+            .unwrap();
+        assert_eq!(canister.system_state.log_visibility, LogVisibility::Public);
+
+        assert!(canister_manager
+            .fetch_canister_logs(other, canister_id, &state)
+            .is_ok());
+    });
+}
+
+#[test]
+fn update_settings_sets_wasm_memory_limit() {
+    with_setup(|canister_manager, mut state, _| {
+        let canister_id = canister_test_id(0);
+        let controller = user_test_id(1).get();
+        state.put_canister_state(canister_for_snapshots(canister_id, controller));
+        let mut round_limits = snapshot_round_limits(&state);
+
+        let settings = CanisterSettingsBuilder::new()
+            .with_wasm_memory_limit(NumBytes::from(1 << 30))
+            .build();
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        canister_manager
+            .update_settings(
+                controller,
+                settings,
+                canister,
+                &mut round_limits,
+                &ResourceSaturation::default(),
+                SMALL_APP_SUBNET_MAX_SIZE,
+            )
+            .unwrap();
+        assert_eq!(
+            canister.system_state.wasm_memory_limit,
+            Some(NumBytes::from(1 << 30))
+        );
+        let status = canister_manager
+            .get_canister_status(controller, canister, SMALL_APP_SUBNET_MAX_SIZE)
+            .unwrap();
+        assert_eq!(status.wasm_memory_limit(), Some(1 << 30));
+
+        // A limit of zero removes the limit.
+        let settings = CanisterSettingsBuilder::new()
+            .with_wasm_memory_limit(NumBytes::from(0))
+            .build();
+        canister_manager
+            .update_settings(
+                controller,
+                settings,
+                canister,
+                &mut round_limits,
+                &ResourceSaturation::default(),
+                SMALL_APP_SUBNET_MAX_SIZE,
+            )
+            .unwrap();
+        assert_eq!(canister.system_state.wasm_memory_limit, None);
+        let status = canister_manager
+            .get_canister_status(controller, canister, SMALL_APP_SUBNET_MAX_SIZE)
+            .unwrap();
+        assert_eq!(status.wasm_memory_limit(), None);
+    });
+}
+
+#[test]
+fn update_settings_sets_reserved_cycles_limit() {
+    with_setup(|canister_manager, mut state, _| {
+        let canister_id = canister_test_id(0);
+        let controller = user_test_id(1).get();
+        state.put_canister_state(canister_for_snapshots(canister_id, controller));
+        let mut round_limits = snapshot_round_limits(&state);
+
+        let settings = CanisterSettingsBuilder::new()
+            .with_reserved_cycles_limit(Cycles::new(1_000))
+            .build();
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        canister_manager
+            .update_settings(
+                controller,
+                settings,
+                canister,
+                &mut round_limits,
+                &ResourceSaturation::default(),
+                SMALL_APP_SUBNET_MAX_SIZE,
+            )
+            .unwrap();
+        assert_eq!(
+            canister.system_state.reserved_balance_limit(),
+            Some(Cycles::new(1_000))
+        );
+
+        canister
+            .system_state
+            .reserve_cycles(Cycles::new(500))
+            .unwrap();
+        let status = canister_manager
+            .get_canister_status(controller, canister, SMALL_APP_SUBNET_MAX_SIZE)
+            .unwrap();
+        assert_eq!(status.reserved_cycles_limit(), Some(1_000));
+        assert_eq!(status.reserved_cycles(), 500);
+
+        // The limit cannot be set below the current reserved balance.
+        let settings = CanisterSettingsBuilder::new()
+            .with_reserved_cycles_limit(Cycles::new(100))
+            .build();
+        assert_eq!(
+            canister_manager.update_settings(
+                controller,
+                settings,
+                canister,
+                &mut round_limits,
+                &ResourceSaturation::default(),
+                SMALL_APP_SUBNET_MAX_SIZE,
+            ),
+            Err(CanisterManagerError::ReservedCyclesLimitIsTooLow {
+                cycles: Cycles::new(500),
+                limit: Cycles::new(100),
+            })
+        );
+    });
+}
+
+#[test]
+fn memory_allocation_increase_reserves_cycles_above_subnet_memory_threshold() {
+    with_setup(|canister_manager, mut state, _| {
+        let canister_id = canister_test_id(0);
+        let controller = user_test_id(1).get();
+        state.put_canister_state(canister_for_snapshots(canister_id, controller));
+        let mut round_limits = snapshot_round_limits(&state);
+        let gib = 1 << 30;
+        let memory_allocation = MemoryAllocation::try_from(NumBytes::from(10 << 20)).unwrap();
+
+        // Nothing is reserved below the threshold.
+        let below_threshold = ResourceSaturation::new(0, 100 * gib, 200 * gib);
+        let settings = CanisterSettingsBuilder::new()
+            .with_memory_allocation(memory_allocation)
+            .build();
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        canister_manager
+            .update_settings(
+                controller,
+                settings,
+                canister,
+                &mut round_limits,
+                &below_threshold,
+                SMALL_APP_SUBNET_MAX_SIZE,
+            )
+            .unwrap();
+        assert_eq!(canister.system_state.reserved_balance(), Cycles::zero());
+
+        // Above the threshold the increase of the memory allocation reserves cycles.
+        let above_threshold = ResourceSaturation::new(150 * gib, 100 * gib, 200 * gib);
+        let memory_allocation = MemoryAllocation::try_from(NumBytes::from(20 << 20)).unwrap();
+        let settings = CanisterSettingsBuilder::new()
+            .with_memory_allocation(memory_allocation)
+            .build();
+        let balance_before = canister.system_state.balance();
+        canister_manager
+            .update_settings(
+                controller,
+                settings,
+                canister,
+                &mut round_limits,
+                &above_threshold,
+                SMALL_APP_SUBNET_MAX_SIZE,
+            )
+            .unwrap();
+        let reserved = canister.system_state.reserved_balance();
+        assert!(reserved > Cycles::zero());
+        assert_eq!(canister.system_state.balance(), balance_before - reserved);
+
+        // The reservation fails if it exceeds the reserved cycles limit.
+        let memory_allocation = MemoryAllocation::try_from(NumBytes::from(30 << 20)).unwrap();
+        let settings = CanisterSettingsBuilder::new()
+            .with_memory_allocation(memory_allocation)
+            .with_reserved_cycles_limit(reserved)
+            .build();
+        assert_matches!(
+            canister_manager.update_settings(
+                controller,
+                settings,
+                canister,
+                &mut round_limits,
+                &above_threshold,
+                SMALL_APP_SUBNET_MAX_SIZE,
+            ),
+            Err(CanisterManagerError::ReservedCyclesLimitExceededInMemoryAllocation { .. })
+        );
+        assert_eq!(canister.system_state.reserved_balance(), reserved);
+        assert_eq!(
+            canister.system_state.memory_allocation.bytes(),
+            NumBytes::from(20 << 20)
+        );
+    });
+}
+
+#[test]
+fn uninstall_code_clears_canister_log() {
+    with_setup(|canister_manager, mut state, _| {
+        let canister_id = canister_test_id(0);
+        let controller = user_test_id(1).get();
+        let mut canister = canister_for_snapshots(canister_id, controller);
+        canister
+            .system_state
+            .canister_log
+            .add_record(42, b"hello".to_vec());
+        state.put_canister_state(canister);
+
+        canister_manager
+            .uninstall_code(canister_id, controller, &mut state)
+            .unwrap();
+
+        let canister = state.canister_state(&canister_id).unwrap();
+        assert!(canister.system_state.canister_log.is_empty());
+        assert_eq!(canister.system_state.canister_log.next_idx(), 1);
+    });
+}
+
+#[test]
+fn uninstall_code_clears_wasm_chunk_store() {
+    with_setup(|canister_manager, mut state, _| {
+        let canister_id = canister_test_id(0);
+        let controller = user_test_id(1).get();
+        let mut canister = canister_for_snapshots(canister_id, controller);
+        canister
+            .system_state
+            .wasm_chunk_store
+            .insert_chunk(vec![7; 1000])
+            .unwrap();
+        state.put_canister_state(canister);
+
+        canister_manager
+            .uninstall_code(canister_id, controller, &mut state)
+            .unwrap();
+
+        let canister = state.canister_state(&canister_id).unwrap();
+        assert!(canister.system_state.wasm_chunk_store.is_empty());
+        assert_eq!(canister.wasm_chunk_store_memory_usage(), NumBytes::from(0));
+    });
+}
+
+#[test]
+fn upload_and_clear_wasm_chunks() {
+    with_setup(|canister_manager, mut state, _| {
+        let canister_id = canister_test_id(0);
+        let controller = user_test_id(1).get();
+        state.put_canister_state(canister_for_snapshots(canister_id, controller));
+        let mut round_limits = snapshot_round_limits(&state);
+        let available_memory_before = round_limits.subnet_available_memory.get_total_memory();
+        let memory_usage_before = state
+            .canister_state(&canister_id)
+            .unwrap()
+            .memory_usage(SubnetType::Application);
+
+        let chunk = vec![7; 1000];
+        let hash = canister_manager
+            .upload_chunk(
+                controller,
+                canister_id,
+                chunk.clone(),
+                &mut state,
+                &mut round_limits,
+                SMALL_APP_SUBNET_MAX_SIZE,
+            )
+            .unwrap();
+        assert_eq!(
+            hash.hash,
+            CanisterModule::new(chunk.clone()).module_hash().to_vec()
+        );
+
+        // Uploading the same chunk again does not use more memory.
+        assert_eq!(
+            canister_manager.upload_chunk(
+                controller,
+                canister_id,
+                chunk,
+                &mut state,
+                &mut round_limits,
+                SMALL_APP_SUBNET_MAX_SIZE,
+            ),
+            Ok(hash.clone())
+        );
+        assert_eq!(
+            canister_manager.stored_chunks(controller, canister_id, &state),
+            Ok(vec![hash])
+        );
+        assert_eq!(
+            round_limits.subnet_available_memory.get_total_memory(),
+            available_memory_before - 1000
+        );
+        assert_eq!(
+            state
+                .canister_state(&canister_id)
+                .unwrap()
+                .memory_usage(SubnetType::Application),
+            memory_usage_before + NumBytes::from(1000)
+        );
+
+        assert_eq!(
+            canister_manager.clear_chunk_store(
+                controller,
+                canister_id,
+                &mut state,
+                &mut round_limits
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            canister_manager.stored_chunks(controller, canister_id, &state),
+            Ok(vec![])
+        );
+        assert_eq!(
+            round_limits.subnet_available_memory.get_total_memory(),
+            available_memory_before
+        );
+    });
+}
+
+#[test]
+fn upload_chunk_rejects_oversized_chunks() {
+    with_setup(|canister_manager, mut state, _| {
+        let canister_id = canister_test_id(0);
+        let controller = user_test_id(1).get();
+        state.put_canister_state(canister_for_snapshots(canister_id, controller));
+        let mut round_limits = snapshot_round_limits(&state);
+
+        let result = canister_manager.upload_chunk(
+            controller,
+            canister_id,
+            vec![0; MAX_WASM_CHUNK_SIZE + 1],
+            &mut state,
+            &mut round_limits,
+            SMALL_APP_SUBNET_MAX_SIZE,
+        );
+        assert_matches!(
+            result,
+            Err(CanisterManagerError::WasmChunkStoreError { .. })
+        );
+        assert!(state
+            .canister_state(&canister_id)
+            .unwrap()
+            .system_state
+            .wasm_chunk_store
+            .is_empty());
+    });
+}
+
+#[test]
+fn wasm_chunk_store_methods_require_a_controller() {
+    with_setup(|canister_manager, mut state, _| {
+        let canister_id = canister_test_id(0);
+        let controller = user_test_id(1).get();
+        let other = user_test_id(2).get();
+        state.put_canister_state(canister_for_snapshots(canister_id, controller));
+        let mut round_limits = snapshot_round_limits(&state);
+
+        let expected_err = CanisterManagerError::CanisterInvalidController {
+            canister_id,
+            controllers_expected: btreeset! {controller},
+            controller_provided: other,
+        };
+        assert_eq!(
+            canister_manager
+                .upload_chunk(
+                    other,
+                    canister_id,
+                    vec![1, 2, 3],
+                    &mut state,
+                    &mut round_limits,
+                    SMALL_APP_SUBNET_MAX_SIZE,
+                )
+                .unwrap_err(),
+            expected_err
+        );
+        assert_eq!(
+            canister_manager
+                .stored_chunks(other, canister_id, &state)
+                .unwrap_err(),
+            expected_err
+        );
+        assert_eq!(
+            canister_manager
+                .clear_chunk_store(other, canister_id, &mut state, &mut round_limits)
+                .unwrap_err(),
+            expected_err
+        );
+    });
+}
@@ -502,6 +502,24 @@ pub fn apply_canister_state_changes(
             }
         }
     }
+    // State changes of a failed execution are discarded, but its log records
+    // and the trap message are kept in the canister log to help debugging.
+    for record in std::mem::take(&mut output.canister_log_records) {
+        system_state
+            .canister_log
+            .add_record(record.timestamp_nanos, record.content);
+    }
+    if let Err(err) = &output.wasm_result {
+        let msg = match err {
+            HypervisorError::CalledTrap(msg) => msg.clone(),
+            HypervisorError::Trapped(code) => code.to_string(),
+            err => err.to_string(),
+        };
+        system_state.canister_log.add_record(
+            time.as_nanos_since_unix_epoch(),
+            format!("[TRAP]: {}", msg).into_bytes(),
+        );
+    }
 }
 
 pub(crate) fn finish_call_with_error(
@@ -11,11 +11,11 @@ use ic_replicated_state::canister_state::system_state::CyclesUseCase;
 use ic_replicated_state::testing::SystemStateTesting;
 use ic_replicated_state::{
     canister_state::{NextExecution, WASM_PAGE_SIZE_IN_BYTES},
-    CallOrigin,
+    CallOrigin, NumWasmPages,
 };
 use ic_state_machine_tests::{Cycles, WasmResult};
 use ic_sys::PAGE_SIZE;
-use ic_types::{NumInstructions, NumPages};
+use ic_types::{NumBytes, NumInstructions, NumPages};
 use ic_universal_canister::{call_args, wasm};
 
 use ic_test_utilities_execution_environment::{
@@ -802,3 +802,162 @@ fn stable_grow_returns_allocated_memory_on_error() {
         initial_canister_memory
     );
 }
+
+#[test]
+fn memory_grow_fails_above_wasm_memory_limit() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i32 i32)))
+            (func $grow_and_reply (param $pages i32)
+                (i32.store (i32.const 0) (memory.grow (local.get $pages)))
+                (call $msg_reply_data_append (i32.const 0) (i32.const 4))
+                (call $msg_reply)
+            )
+            (func (export "canister_update grow_10")
+                (call $grow_and_reply (i32.const 10))
+            )
+            (func (export "canister_update grow_4")
+                (call $grow_and_reply (i32.const 4))
+            )
+            (func (export "canister_pre_upgrade")
+                (drop (memory.grow (i32.const 10)))
+            )
+            (memory 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    test.canister_state_mut(canister_id)
+        .system_state
+        .wasm_memory_limit = Some(NumBytes::from(5 * WASM_PAGE_SIZE_IN_BYTES as u64));
+
+    // Growing beyond the limit fails and `memory.grow` returns -1.
+    let result = test.ingress(canister_id, "grow_10", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply((-1_i32).to_le_bytes().to_vec()));
+    assert_eq!(
+        test.execution_state(canister_id).wasm_memory.size,
+        NumWasmPages::new(1)
+    );
+
+    // Growing up to the limit succeeds and returns the previous size.
+    let result = test.ingress(canister_id, "grow_4", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(1_i32.to_le_bytes().to_vec()));
+    assert_eq!(
+        test.execution_state(canister_id).wasm_memory.size,
+        NumWasmPages::new(5)
+    );
+
+    // Upgrades are not subject to the limit.
+    test.upgrade_canister(canister_id, wat::parse_str(wat).unwrap())
+        .unwrap();
+}
+
+#[test]
+fn update_traps_when_heap_exceeds_wasm_memory_limit() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func (export "canister_update noop")
+                (call $msg_reply)
+            )
+            (memory 10)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    test.canister_state_mut(canister_id)
+        .system_state
+        .wasm_memory_limit = Some(NumBytes::from(5 * WASM_PAGE_SIZE_IN_BYTES as u64));
+
+    let err = test.ingress(canister_id, "noop", vec![]).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterOutOfMemory);
+
+    test.canister_state_mut(canister_id)
+        .system_state
+        .wasm_memory_limit = None;
+    let result = test.ingress(canister_id, "noop", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![]));
+}
+
+#[test]
+fn memory_grow_reserves_cycles_above_subnet_memory_threshold() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_total_memory(1 << 30)
+        .with_subnet_memory_threshold(0)
+        .build();
+    let wat = r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func (export "canister_update grow")
+                (drop (memory.grow (i32.const 1600)))
+                (call $msg_reply)
+            )
+            (memory 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .reserved_balance(),
+        Cycles::zero()
+    );
+
+    let result = test.ingress(canister_id, "grow", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![]));
+    let reserved = test
+        .canister_state(canister_id)
+        .system_state
+        .reserved_balance();
+    assert!(reserved > Cycles::zero());
+
+    // Growing further fails if the reserved cycles limit would be exceeded.
+    test.canister_state_mut(canister_id)
+        .system_state
+        .set_reserved_balance_limit(Some(reserved));
+    let err = test.ingress(canister_id, "grow", vec![]).unwrap_err();
+    assert_eq!(
+        err.code(),
+        ErrorCode::ReservedCyclesLimitExceededInMemoryGrow
+    );
+    assert_eq!(
+        test.execution_state(canister_id).wasm_memory.size,
+        NumWasmPages::new(1601)
+    );
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .reserved_balance(),
+        reserved
+    );
+}
+
+#[test]
+fn failed_update_keeps_debug_print_and_trap_in_canister_log() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"
+        (module
+            (import "ic0" "debug_print" (func $debug_print (param i32 i32)))
+            (func (export "canister_update test")
+                (call $debug_print (i32.const 0) (i32.const 5))
+                unreachable
+            )
+            (memory 1)
+            (data (i32.const 0) "hello")
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let err = test.ingress(canister_id, "test", vec![]).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterTrapped);
+
+    let contents: Vec<_> = test
+        .canister_state(canister_id)
+        .system_state
+        .canister_log
+        .records()
+        .iter()
+        .map(|r| String::from_utf8(r.content.clone()).unwrap())
+        .collect();
+    assert_eq!(contents.len(), 2);
+    assert_eq!(contents[0], "hello");
+    assert!(contents[1].starts_with("[TRAP]: "), "{}", contents[1]);
+    assert!(contents[1].contains("unreachable"), "{}", contents[1]);
+}
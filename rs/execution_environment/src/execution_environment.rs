@@ -5,7 +5,8 @@ use crate::{
     },
     canister_settings::CanisterSettings,
     execution::{
-        inspect_message, nonreplicated_query::execute_non_replicated_query,
+        inspect_message, install_code::validate_controller,
+        nonreplicated_query::execute_non_replicated_query,
         replicated_query::execute_replicated_query, response::execute_response,
         update::execute_update,
     },
@@ -21,15 +22,16 @@ use ic_config::execution_environment::Config as ExecutionConfig;
 use ic_config::flag_status::FlagStatus;
 use ic_constants::{LOG_CANISTER_OPERATION_CYCLES_THRESHOLD, SMALL_APP_SUBNET_MAX_SIZE};
 use ic_crypto_tecdsa::derive_tecdsa_public_key;
-use ic_cycles_account_manager::{CyclesAccountManager, IngressInductionCost};
+use ic_cycles_account_manager::{CyclesAccountManager, IngressInductionCost, ResourceSaturation};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
     CanisterHttpRequestArgs, CanisterIdRecord, CanisterSettingsArgs,
-    ComputeInitialEcdsaDealingsArgs, CreateCanisterArgs, ECDSAPublicKeyArgs,
-    ECDSAPublicKeyResponse, EcdsaKeyId, EmptyBlob, InstallCodeArgs, Method as Ic00Method,
+    ComputeInitialEcdsaDealingsArgs, CreateCanisterArgs, DeleteCanisterSnapshotArgs,
+    ECDSAPublicKeyArgs, ECDSAPublicKeyResponse, EcdsaKeyId, EmptyBlob, FetchCanisterLogsRequest,
+    InstallChunkedCodeArgs, InstallCodeArgs, LoadCanisterSnapshotArgs, Method as Ic00Method,
     Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs,
-    SetControllerArgs, SetupInitialDKGArgs, SignWithECDSAArgs, UninstallCodeArgs,
-    UpdateSettingsArgs, IC_00,
+    SetControllerArgs, SetupInitialDKGArgs, SignWithECDSAArgs, TakeCanisterSnapshotArgs,
+    UninstallCodeArgs, UpdateSettingsArgs, UploadChunkArgs, IC_00,
 };
 use ic_interfaces::{
     execution_environment::{
@@ -45,6 +47,7 @@ use ic_metrics::{MetricsRegistry, Timer};
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::canister_state::{system_state::CyclesUseCase, NextExecution};
+use ic_replicated_state::page_map::PageAllocatorFileDescriptor;
 use ic_replicated_state::ExecutionTask;
 use ic_replicated_state::{
     canister_state::system_state::PausedExecutionId,
@@ -58,7 +61,7 @@ use ic_replicated_state::{
 };
 use ic_system_api::{ExecutionParameters, InstructionLimits};
 use ic_types::{
-    canister_http::CanisterHttpRequestContext,
+    canister_http::{CanisterHttpRequestContext, Replication},
     crypto::canister_threshold_sig::{ExtendedDerivationPath, MasterEcdsaPublicKey},
     crypto::threshold_sig::ni_dkg::NiDkgTargetId,
     ingress::{IngressState, IngressStatus, WasmResult},
@@ -68,11 +71,11 @@ use ic_types::{
     },
     methods::SystemMethod,
     nominal_cycles::NominalCycles,
-    CanisterId, CpuComplexity, Cycles, LongExecutionMode, NumBytes, NumInstructions, SubnetId,
-    Time,
+    CanisterId, CpuComplexity, Cycles, LongExecutionMode, NodeId, NumBytes, NumInstructions,
+    SubnetId, Time,
 };
 use ic_types::{messages::MessageId, methods::WasmMethod};
-use ic_wasm_types::WasmHash;
+use ic_wasm_types::{CanisterModule, WasmHash};
 use phantom_newtype::AmountOf;
 use prometheus::IntCounter;
 use rand::RngCore;
@@ -267,6 +270,10 @@ pub struct ExecutionEnvironment {
     own_subnet_id: SubnetId,
     own_subnet_type: SubnetType,
     paused_execution_registry: Arc<Mutex<PausedExecutionRegistry>>,
+    // This scaling factor accounts for the execution threads running in
+    // parallel and potentially reserving resources. It should be initialized
+    // to the number of scheduler cores.
+    resource_saturation_scaling: usize,
 }
 
 /// This is a helper enum that indicates whether the current DTS execution of
@@ -288,6 +295,8 @@ impl ExecutionEnvironment {
         compute_capacity: usize,
         config: ExecutionConfig,
         cycles_account_manager: Arc<CyclesAccountManager>,
+        resource_saturation_scaling: usize,
+        fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
     ) -> Self {
         // Assert the flag implication: DTS => sandboxing.
         assert!(
@@ -312,6 +321,7 @@ impl ExecutionEnvironment {
             canister_manager_config,
             Arc::clone(&cycles_account_manager),
             Arc::clone(&ingress_history_writer),
+            fd_factory,
         );
         Self {
             log,
@@ -324,6 +334,7 @@ impl ExecutionEnvironment {
             own_subnet_id,
             own_subnet_type,
             paused_execution_registry: Default::default(),
+            resource_saturation_scaling,
         }
     }
 
@@ -359,6 +370,34 @@ impl ExecutionEnvironment {
         }
     }
 
+    /// Determines how the given canister http request is made. If the request
+    /// opts out of replication, a node of this subnet is picked at random to
+    /// be the only one to make the request.
+    fn canister_http_request_replication(
+        args: &CanisterHttpRequestArgs,
+        state: &ReplicatedState,
+        rng: &mut dyn RngCore,
+    ) -> Result<Replication, UserError> {
+        if args.is_replicated != Some(false) {
+            return Ok(Replication::FullyReplicated);
+        }
+        let nodes: Vec<NodeId> = state
+            .metadata
+            .network_topology
+            .subnets
+            .get(&state.metadata.own_subnet_id)
+            .map(|subnet| subnet.nodes.keys().cloned().collect())
+            .unwrap_or_default();
+        if nodes.is_empty() {
+            return Err(UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                "Failed to designate a node for the non-replicated http request: no nodes known on this subnet.",
+            ));
+        }
+        let index = (rng.next_u64() % nodes.len() as u64) as usize;
+        Ok(Replication::NonReplicated(nodes[index]))
+    }
+
     /// Executes a replicated message sent to a subnet.
     /// Returns the new replicated state and the number of left instructions.
     #[allow(clippy::cognitive_complexity)]
@@ -403,12 +442,53 @@ impl ExecutionEnvironment {
                             state.metadata.subnet_metrics.ecdsa_signature_agreements += 1;
                         }
 
+                        // The http request fee assumes a response of `max_response_bytes`.
+                        // Refund the part reserved for bytes that the adapter did not
+                        // receive. The transformed response may be much smaller than the
+                        // received one, so its size is not used.
+                        let http_request_fee_refund = match (&context, &response.response_payload) {
+                            (SubnetCallContext::CanisterHttpRequest(context), Payload::Data(_))
+                                if context.received_response_bytes.is_some() =>
+                            {
+                                let received_bytes = context.received_response_bytes.unwrap();
+                                let refund = match context.replication {
+                                    Replication::FullyReplicated => {
+                                        self.cycles_account_manager.http_request_fee_refund(
+                                            context.variable_parts_size(),
+                                            context.max_response_bytes,
+                                            received_bytes,
+                                            // Use the subnet size the fee was charged for.
+                                            context
+                                                .subnet_size
+                                                .unwrap_or(registry_settings.subnet_size),
+                                        )
+                                    }
+                                    Replication::NonReplicated(_) => self
+                                        .cycles_account_manager
+                                        .non_replicated_http_request_fee_refund(
+                                            context.variable_parts_size(),
+                                            context.max_response_bytes,
+                                            received_bytes,
+                                        ),
+                                };
+                                let refund_nominal = NominalCycles::from(refund);
+                                let subnet_metrics = &mut state.metadata.subnet_metrics;
+                                subnet_metrics.consumed_cycles_http_outcalls -= refund_nominal;
+                                subnet_metrics.observe_refunded_cycles_with_use_case(
+                                    CyclesUseCase::HTTPOutcalls,
+                                    refund_nominal,
+                                );
+                                refund
+                            }
+                            _ => Cycles::zero(),
+                        };
+
                         state.push_subnet_output_response(
                             Response {
                                 originator: request.sender,
                                 respondent: CanisterId::from(self.own_subnet_id),
                                 originator_reply_callback: request.sender_reply_callback,
-                                refund: request.payment,
+                                refund: request.payment + http_request_fee_refund,
                                 response_payload: response.response_payload.clone(),
                             }
                             .into(),
@@ -426,7 +506,7 @@ impl ExecutionEnvironment {
         let method = Ic00Method::from_str(msg.method_name());
         let payload = msg.method_payload();
         let result = match method {
-            Ok(Ic00Method::InstallCode) => {
+            Ok(Ic00Method::InstallCode) | Ok(Ic00Method::InstallChunkedCode) => {
                 // Tail call is needed for deterministic time slicing here to
                 // properly handle the case of a paused execution.
                 return self.execute_install_code(
@@ -573,6 +653,7 @@ impl ExecutionEnvironment {
                                 canister_id,
                                 &mut state,
                                 round_limits,
+                                registry_settings.subnet_size,
                             ),
                         };
                         // The induction cost of `UpdateSettings` is charged
@@ -619,17 +700,21 @@ impl ExecutionEnvironment {
             Ok(Ic00Method::SetController) => {
                 let res = match SetControllerArgs::decode(payload) {
                     Err(err) => Err(err),
-                    Ok(args) => self
-                        .canister_manager
-                        .set_controller(
-                            *msg.sender(),
-                            args.get_canister_id(),
-                            args.get_new_controller(),
-                            &mut state,
-                            round_limits,
-                        )
-                        .map(|()| EmptyBlob.encode())
-                        .map_err(|err| err.into()),
+                    Ok(args) => {
+                        let subnet_memory_saturation = self.subnet_memory_saturation(round_limits);
+                        self.canister_manager
+                            .set_controller(
+                                *msg.sender(),
+                                args.get_canister_id(),
+                                args.get_new_controller(),
+                                &mut state,
+                                round_limits,
+                                &subnet_memory_saturation,
+                                registry_settings.subnet_size,
+                            )
+                            .map(|()| EmptyBlob.encode())
+                            .map_err(|err| err.into())
+                    }
                 };
                 Some((res, msg.take_cycles()))
             }
@@ -688,6 +773,134 @@ impl ExecutionEnvironment {
                 Some((res, msg.take_cycles()))
             }
 
+            Ok(Ic00Method::TakeCanisterSnapshot) => {
+                let res = match TakeCanisterSnapshotArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self
+                        .canister_manager
+                        .take_canister_snapshot(
+                            *msg.sender(),
+                            args.get_canister_id(),
+                            args.replace_snapshot(),
+                            &mut state,
+                            round_limits,
+                        )
+                        .map(|response| response.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::LoadCanisterSnapshot) => {
+                let res = match LoadCanisterSnapshotArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self
+                        .canister_manager
+                        .load_canister_snapshot(
+                            *msg.sender(),
+                            args.get_canister_id(),
+                            args.snapshot_id(),
+                            &mut state,
+                            round_limits,
+                        )
+                        .map(|()| EmptyBlob.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::ListCanisterSnapshots) => {
+                let res = match CanisterIdRecord::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self
+                        .canister_manager
+                        .list_canister_snapshots(*msg.sender(), args.get_canister_id(), &state)
+                        .map(|response| response.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::FetchCanisterLogs) => {
+                let res = match FetchCanisterLogsRequest::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self
+                        .canister_manager
+                        .fetch_canister_logs(*msg.sender(), args.get_canister_id(), &state)
+                        .map(|response| response.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::DeleteCanisterSnapshot) => {
+                let res = match DeleteCanisterSnapshotArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self
+                        .canister_manager
+                        .delete_canister_snapshot(
+                            *msg.sender(),
+                            args.get_canister_id(),
+                            args.snapshot_id(),
+                            &mut state,
+                            round_limits,
+                        )
+                        .map(|()| EmptyBlob.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::UploadChunk) => {
+                let res = match UploadChunkArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => {
+                        let canister_id = args.get_canister_id();
+                        self.canister_manager
+                            .upload_chunk(
+                                *msg.sender(),
+                                canister_id,
+                                args.chunk,
+                                &mut state,
+                                round_limits,
+                                registry_settings.subnet_size,
+                            )
+                            .map(|response| response.encode())
+                            .map_err(|err| err.into())
+                    }
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::ClearChunkStore) => {
+                let res = match CanisterIdRecord::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self
+                        .canister_manager
+                        .clear_chunk_store(
+                            *msg.sender(),
+                            args.get_canister_id(),
+                            &mut state,
+                            round_limits,
+                        )
+                        .map(|()| EmptyBlob.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::StoredChunks) => {
+                let res = match CanisterIdRecord::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self
+                        .canister_manager
+                        .stored_chunks(*msg.sender(), args.get_canister_id(), &state)
+                        .map(|response| response.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
             Ok(Ic00Method::RawRand) => match &msg {
                 CanisterCall::Ingress(_) => Some((
                     Err(UserError::new(
@@ -723,19 +936,45 @@ impl ExecutionEnvironment {
                             Err(err) => Some((Err(err), msg.take_cycles())),
                             Ok(_) => match CanisterHttpRequestArgs::decode(payload) {
                                 Err(err) => Some((Err(err), msg.take_cycles())),
-                                Ok(args) => match CanisterHttpRequestContext::try_from((
-                                    state.time(),
-                                    request.as_ref(),
-                                    args,
-                                )) {
-                                    Err(err) => Some((Err(err.into()), msg.take_cycles())),
+                                Ok(args) => match Self::canister_http_request_replication(
+                                    &args, &state, rng,
+                                )
+                                .and_then(|replication| {
+                                    CanisterHttpRequestContext::try_from((
+                                        state.time(),
+                                        request.as_ref(),
+                                        args,
+                                    ))
+                                    .map(|context| CanisterHttpRequestContext {
+                                        replication,
+                                        ..context
+                                    })
+                                    .map_err(UserError::from)
+                                }) {
+                                    Err(err) => Some((Err(err), msg.take_cycles())),
                                     Ok(mut canister_http_request_context) => {
+                                        // A non-replicated request is performed by a single
+                                        // node, so it is charged the single-node fee.
                                         let http_request_fee =
-                                            self.cycles_account_manager.http_request_fee(
-                                                canister_http_request_context.variable_parts_size(),
-                                                canister_http_request_context.max_response_bytes,
-                                                registry_settings.subnet_size,
-                                            );
+                                            match canister_http_request_context.replication {
+                                                Replication::FullyReplicated => {
+                                                    self.cycles_account_manager.http_request_fee(
+                                                        canister_http_request_context
+                                                            .variable_parts_size(),
+                                                        canister_http_request_context
+                                                            .max_response_bytes,
+                                                        registry_settings.subnet_size,
+                                                    )
+                                                }
+                                                Replication::NonReplicated(_) => self
+                                                    .cycles_account_manager
+                                                    .non_replicated_http_request_fee(
+                                                        canister_http_request_context
+                                                            .variable_parts_size(),
+                                                        canister_http_request_context
+                                                            .max_response_bytes,
+                                                    ),
+                                            };
                                         if request.payment < http_request_fee {
                                             let err = Err(UserError::new(
                                                         ErrorCode::CanisterRejectedMessage,
@@ -748,6 +987,8 @@ impl ExecutionEnvironment {
                                         } else {
                                             canister_http_request_context.request.payment -=
                                                 http_request_fee;
+                                            canister_http_request_context.subnet_size =
+                                                Some(registry_settings.subnet_size);
                                             let http_fee = NominalCycles::from(http_request_fee);
                                             state
                                                 .metadata
@@ -1124,6 +1365,7 @@ impl ExecutionEnvironment {
                     &canister,
                     instruction_limits,
                     ExecutionMode::Replicated,
+                    ResourceSaturation::default(),
                 );
                 let request_cycles = req.cycles();
                 let result = execute_replicated_query(
@@ -1152,6 +1394,7 @@ impl ExecutionEnvironment {
                     &canister,
                     instruction_limits,
                     ExecutionMode::Replicated,
+                    self.subnet_memory_saturation(round_limits),
                 );
                 execute_update(
                     canister,
@@ -1182,8 +1425,12 @@ impl ExecutionEnvironment {
         round_limits: &mut RoundLimits,
         subnet_size: usize,
     ) -> ExecuteMessageResult {
-        let execution_parameters =
-            self.execution_parameters(&canister, instruction_limits, ExecutionMode::Replicated);
+        let execution_parameters = self.execution_parameters(
+            &canister,
+            instruction_limits,
+            ExecutionMode::Replicated,
+            self.subnet_memory_saturation(round_limits),
+        );
         execute_update(
             canister,
             CanisterCallOrTask::Task(task.clone()),
@@ -1215,6 +1462,7 @@ impl ExecutionEnvironment {
         canister: &CanisterState,
         instruction_limits: InstructionLimits,
         execution_mode: ExecutionMode,
+        subnet_memory_saturation: ResourceSaturation,
     ) -> ExecutionParameters {
         ExecutionParameters {
             instruction_limits,
@@ -1222,9 +1470,27 @@ impl ExecutionEnvironment {
             compute_allocation: canister.scheduler_state.compute_allocation,
             subnet_type: self.own_subnet_type,
             execution_mode,
+            subnet_memory_saturation,
         }
     }
 
+    /// Returns the saturation of the subnet memory based on the available
+    /// memory in the given round limits.
+    fn subnet_memory_saturation(&self, round_limits: &RoundLimits) -> ResourceSaturation {
+        let subnet_available_memory = round_limits
+            .subnet_available_memory
+            .get_total_memory()
+            .max(0) as u64;
+        let subnet_memory_capacity = self.config.subnet_memory_capacity.get();
+        let subnet_memory_usage = subnet_memory_capacity.saturating_sub(subnet_available_memory);
+        ResourceSaturation::new_scaled(
+            subnet_memory_usage,
+            self.config.subnet_memory_threshold.get(),
+            subnet_memory_capacity,
+            self.resource_saturation_scaling as u64,
+        )
+    }
+
     fn create_canister(
         &self,
         sender: PrincipalId,
@@ -1264,10 +1530,19 @@ impl ExecutionEnvironment {
         canister_id: CanisterId,
         state: &mut ReplicatedState,
         round_limits: &mut RoundLimits,
+        subnet_size: usize,
     ) -> Result<Vec<u8>, UserError> {
         let canister = get_canister_mut(canister_id, state)?;
+        let subnet_memory_saturation = self.subnet_memory_saturation(round_limits);
         self.canister_manager
-            .update_settings(sender, settings, canister, round_limits)
+            .update_settings(
+                sender,
+                settings,
+                canister,
+                round_limits,
+                &subnet_memory_saturation,
+                subnet_size,
+            )
             .map(|()| EmptyBlob.encode())
             .map_err(|err| err.into())
     }
@@ -1393,8 +1668,12 @@ impl ExecutionEnvironment {
         round_limits: &mut RoundLimits,
         subnet_size: usize,
     ) -> ExecuteMessageResult {
-        let execution_parameters =
-            self.execution_parameters(&canister, instruction_limits, ExecutionMode::Replicated);
+        let execution_parameters = self.execution_parameters(
+            &canister,
+            instruction_limits,
+            ExecutionMode::Replicated,
+            self.subnet_memory_saturation(round_limits),
+        );
         let round = RoundContext {
             network_topology: &network_topology,
             hypervisor: &self.hypervisor,
@@ -1487,8 +1766,12 @@ impl ExecutionEnvironment {
             self.config.max_instructions_for_message_acceptance_calls,
             self.config.max_instructions_for_message_acceptance_calls,
         );
-        let execution_parameters =
-            self.execution_parameters(canister_state, instruction_limits, execution_mode);
+        let execution_parameters = self.execution_parameters(
+            canister_state,
+            instruction_limits,
+            execution_mode,
+            ResourceSaturation::default(),
+        );
 
         // Letting the canister grow arbitrarily when executing the
         // query is fine as we do not persist state modifications.
@@ -1526,8 +1809,12 @@ impl ExecutionEnvironment {
             max_instructions_per_query,
             max_instructions_per_query,
         );
-        let execution_parameters =
-            self.execution_parameters(&canister, instruction_limits, ExecutionMode::NonReplicated);
+        let execution_parameters = self.execution_parameters(
+            &canister,
+            instruction_limits,
+            ExecutionMode::NonReplicated,
+            ResourceSaturation::default(),
+        );
         let subnet_available_memory = subnet_memory_capacity(&self.config);
         let mut round_limits = RoundLimits {
             instructions: as_round_instructions(max_instructions_per_query),
@@ -1869,7 +2156,13 @@ impl ExecutionEnvironment {
             state: &mut ReplicatedState,
         ) -> Result<(InstallCodeContext, CanisterState), UserError> {
             let payload = msg.method_payload();
-            let args = InstallCodeArgs::decode(payload)?;
+            let args = match Ic00Method::from_str(msg.method_name()) {
+                Ok(Ic00Method::InstallChunkedCode) => {
+                    let args = InstallChunkedCodeArgs::decode(payload)?;
+                    assemble_chunked_code(msg.sender(), args, state)?
+                }
+                _ => InstallCodeArgs::decode(payload)?,
+            };
             let install_context = InstallCodeContext::try_from((*msg.sender(), args))?;
             let canister = state
                 .take_canister_state(&install_context.canister_id)
@@ -1879,6 +2172,59 @@ impl ExecutionEnvironment {
             Ok((install_context, canister))
         }
 
+        // Assembles the Wasm module of an `install_chunked_code` message from
+        // the chunk store of the store canister and verifies its hash.
+        fn assemble_chunked_code(
+            sender: &PrincipalId,
+            args: InstallChunkedCodeArgs,
+            state: &ReplicatedState,
+        ) -> Result<InstallCodeArgs, CanisterManagerError> {
+            let store_canister_id = args.store_canister_id();
+            let store_canister = state
+                .canister_state(&store_canister_id)
+                .ok_or(CanisterManagerError::CanisterNotFound(store_canister_id))?;
+            validate_controller(store_canister, sender)?;
+
+            let chunk_store = &store_canister.system_state.wasm_chunk_store;
+            let mut wasm_module = vec![];
+            for chunk_hash in args.chunk_hashes_list.iter() {
+                let chunk = <[u8; 32]>::try_from(chunk_hash.hash.as_slice())
+                    .ok()
+                    .and_then(|hash| chunk_store.get_chunk(&hash))
+                    .ok_or_else(|| CanisterManagerError::WasmChunkStoreError {
+                        message: format!(
+                            "Chunk {} not found in the chunk store of canister {}",
+                            hex::encode(&chunk_hash.hash),
+                            store_canister_id
+                        ),
+                    })?;
+                wasm_module.extend_from_slice(chunk);
+            }
+
+            let wasm_module_hash = CanisterModule::new(wasm_module.clone()).module_hash();
+            if wasm_module_hash[..] != args.wasm_module_hash[..] {
+                return Err(CanisterManagerError::WasmChunkStoreError {
+                    message: format!(
+                        "Wasm module hash {} does not match the given hash {}",
+                        hex::encode(wasm_module_hash),
+                        hex::encode(&args.wasm_module_hash)
+                    ),
+                });
+            }
+
+            let mut install_args = InstallCodeArgs::new(
+                args.mode,
+                args.target_canister_id(),
+                wasm_module,
+                args.arg,
+                None,
+                None,
+                None,
+            );
+            install_args.sender_canister_version = args.sender_canister_version;
+            Ok(install_args)
+        }
+
         // Start logging execution time for `install_code`.
         let timer = Timer::start();
 
@@ -1920,8 +2266,12 @@ impl ExecutionEnvironment {
             install_context.wasm_module.is_empty().to_string(),
         );
 
-        let execution_parameters =
-            self.execution_parameters(&old_canister, instruction_limits, ExecutionMode::Replicated);
+        let execution_parameters = self.execution_parameters(
+            &old_canister,
+            instruction_limits,
+            ExecutionMode::Replicated,
+            self.subnet_memory_saturation(round_limits),
+        );
 
         let dts_result = self.canister_manager.install_code_dts(
             install_context,
@@ -26,7 +26,7 @@ use ic_test_utilities_execution_environment::{
 use ic_test_utilities_metrics::{fetch_histogram_vec_count, metric_vec};
 use ic_types::canister_http::Transform;
 use ic_types::{
-    canister_http::CanisterHttpMethod,
+    canister_http::{CanisterHttpMethod, Replication},
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{
         CallbackId, Payload, RejectContext, RequestOrResponse, Response, MAX_RESPONSE_COUNT_BYTES,
@@ -1465,6 +1465,7 @@ fn execute_canister_http_request() {
             }),
             context: transform_context.clone(),
         }),
+        is_replicated: None,
     };
 
     // Create request to HTTP_REQUEST method.
@@ -1519,6 +1520,64 @@ fn execute_canister_http_request() {
     );
 }
 
+#[test]
+fn execute_non_replicated_canister_http_request() {
+    let own_subnet = subnet_test_id(1);
+    let caller_canister = canister_test_id(10);
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(own_subnet)
+        .with_caller(own_subnet, caller_canister)
+        .build();
+    test.state_mut().metadata.own_subnet_features.http_requests = true;
+
+    let args = CanisterHttpRequestArgs {
+        url: "https://".to_string(),
+        max_response_bytes: None,
+        headers: Vec::new(),
+        body: Some(b"{}".to_vec()),
+        method: HttpMethod::PUT,
+        transform: None,
+        is_replicated: Some(false),
+    };
+    test.inject_call_to_ic00(
+        Method::HttpRequest,
+        args.encode(),
+        Cycles::new(1_000_000_000_000),
+    );
+    test.execute_all();
+
+    let http_request_context = test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .canister_http_request_contexts
+        .get(&CallbackId::from(0))
+        .unwrap();
+    assert_eq!(http_request_context.http_method, CanisterHttpMethod::PUT);
+    // The designated node must be a member of the own subnet.
+    match http_request_context.replication {
+        Replication::NonReplicated(node_id) => assert!(test
+            .state()
+            .metadata
+            .network_topology
+            .subnets
+            .get(&own_subnet)
+            .unwrap()
+            .nodes
+            .contains_key(&node_id)),
+        Replication::FullyReplicated => panic!("Expected a non-replicated request"),
+    }
+    // The request is charged the single-node fee.
+    let fee = test
+        .cycles_account_manager()
+        .non_replicated_http_request_fee(http_request_context.variable_parts_size(), None);
+    assert!(fee < test.http_request_fee(http_request_context.variable_parts_size(), None));
+    assert_eq!(
+        http_request_context.request.payment,
+        Cycles::new(1_000_000_000_000) - fee
+    );
+}
+
 #[test]
 fn execute_canister_http_request_disabled() {
     let own_subnet = subnet_test_id(1);
@@ -1544,6 +1603,7 @@ fn execute_canister_http_request_disabled() {
             }),
             context: vec![0, 1, 2],
         }),
+        is_replicated: None,
     };
 
     // Create request to HTTP_REQUEST method.
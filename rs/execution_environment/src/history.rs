@@ -250,6 +250,7 @@ fn dashboard_label_value_from(code: ErrorCode) -> &'static str {
     match code {
         SubnetOversubscribed => "Subnet Oversubscribed",
         MaxNumberOfCanistersReached => "Max Number of Canisters Reached",
+        CanisterHttpResponseTooLarge => "Http response exceeded the response size limit",
         IngressHistoryFull => "Ingress History Full",
         CanisterInvalidController => "Canister Invalid Controller",
         CanisterNotFound => "Canister Not Found",
@@ -257,6 +258,7 @@ fn dashboard_label_value_from(code: ErrorCode) -> &'static str {
         CanisterFunctionNotFound => "Canister Function Not Found",
         CanisterAlreadyInstalled => "Canister Already Installed",
         CanisterWasmModuleNotFound => "Canister WASM Module Not Found",
+        CanisterSnapshotNotFound => "Canister Snapshot Not Found",
         CanisterNonEmpty => "Canister Non-Empty",
         CanisterOutOfCycles => "Canister Out Of Cycles",
         CanisterTrapped => "Canister Trapped",
@@ -295,6 +297,10 @@ fn dashboard_label_value_from(code: ErrorCode) -> &'static str {
         QueryCallGraphTotalInstructionLimitExceeded => "Total instructions limit exceeded for query call graph",
         CompositeQueryCalledInReplicatedMode => "Composite query cannot be called in replicated mode",
         CanisterNotHostedBySubnet => "Canister is not hosted by subnet",
-        QueryTimeLimitExceeded => "Canister exceeded the time limit for composite query execution"
+        QueryTimeLimitExceeded => "Canister exceeded the time limit for composite query execution",
+        ReservedCyclesLimitExceededInMemoryAllocation => "Canister cannot increase memory allocation due to its reserved cycles limit",
+        ReservedCyclesLimitExceededInMemoryGrow => "Canister cannot grow memory due to its reserved cycles limit",
+        InsufficientCyclesInMemoryGrow => "Canister does not have enough cycles to grow memory",
+        ReservedCyclesLimitIsTooLow => "Canister cannot set the reserved cycles limit below the reserved cycles balance"
     }
 }
@@ -132,6 +132,8 @@ impl ExecutionServices {
             SchedulerImpl::compute_capacity_percent(scheduler_config.scheduler_cores),
             config.clone(),
             Arc::clone(&cycles_account_manager),
+            scheduler_config.scheduler_cores,
+            Arc::clone(&fd_factory),
         ));
         let sync_query_handler = Arc::new(InternalHttpQueryHandler::new(
             logger.clone(),
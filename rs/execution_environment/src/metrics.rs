@@ -5,7 +5,7 @@ use ic_metrics::{
 use ic_types::{
     NumInstructions, NumMessages, NumSlices, MAX_STABLE_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES,
 };
-use prometheus::{Histogram, IntCounter};
+use prometheus::{Histogram, IntCounter, IntCounterVec, IntGauge};
 use std::{cell::RefCell, rc::Rc, time::Instant};
 
 pub(crate) struct QueryHandlerMetrics {
@@ -15,6 +15,10 @@ pub(crate) struct QueryHandlerMetrics {
     pub query_spawned_calls: ScopedMetrics,
     pub query_cache_hits: IntCounter,
     pub query_cache_misses: IntCounter,
+    pub query_cache_evicted_entries: IntCounter,
+    pub query_cache_invalidated_entries: IntCounterVec,
+    pub query_cache_count_bytes: IntGauge,
+    pub query_cache_len: IntGauge,
 }
 
 impl QueryHandlerMetrics {
@@ -126,6 +130,25 @@ impl QueryHandlerMetrics {
                 "execution_query_cache_misses",
                 "The number of replica side query cache misses",
             ),
+            query_cache_evicted_entries: metrics_registry.int_counter(
+                "execution_query_cache_evicted_entries",
+                "The number of replica side query cache entries evicted to \
+                stay within the cache capacity",
+            ),
+            query_cache_invalidated_entries: metrics_registry.int_counter_vec(
+                "execution_query_cache_invalidated_entries",
+                "The number of replica side query cache entries invalidated \
+                by reason",
+                &["reason"],
+            ),
+            query_cache_count_bytes: metrics_registry.int_gauge(
+                "execution_query_cache_count_bytes",
+                "The size of the replica side query cache in bytes",
+            ),
+            query_cache_len: metrics_registry.int_gauge(
+                "execution_query_cache_len",
+                "The number of entries in the replica side query cache",
+            ),
         }
     }
 }
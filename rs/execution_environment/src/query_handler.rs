@@ -120,6 +120,7 @@ impl InternalHttpQueryHandler {
         cycles_account_manager: Arc<CyclesAccountManager>,
     ) -> Self {
         let query_cache_capacity = config.query_cache_capacity;
+        let query_cache_max_expiry_time = config.query_cache_max_expiry_time;
         Self {
             log,
             hypervisor,
@@ -128,7 +129,10 @@ impl InternalHttpQueryHandler {
             metrics: QueryHandlerMetrics::new(metrics_registry),
             max_instructions_per_query,
             cycles_account_manager,
-            query_cache: query_cache::QueryCache::new(query_cache_capacity),
+            query_cache: query_cache::QueryCache::new(
+                query_cache_capacity,
+                query_cache_max_expiry_time,
+            ),
         }
     }
 }
@@ -152,8 +156,7 @@ impl QueryHandler for InternalHttpQueryHandler {
             let key = query_cache::EntryKey::from(&query);
             let env = query_cache::EntryEnv::try_from((&key, state.as_ref()))?;
 
-            if let Some(result) = self.query_cache.get_valid_result(&key, &env) {
-                self.metrics.query_cache_hits.inc();
+            if let Some(result) = self.query_cache.get_valid_result(&key, &env, &self.metrics) {
                 return result;
             }
             (Some(key), Some(env))
@@ -192,9 +195,11 @@ impl QueryHandler for InternalHttpQueryHandler {
         // Add the query execution result to the query cache.
         if self.config.query_caching == FlagStatus::Enabled {
             if let (Some(key), Some(env)) = (cache_entry_key, cache_entry_env) {
-                self.metrics.query_cache_misses.inc();
-                self.query_cache
-                    .insert(key, query_cache::EntryValue::new(env, result.clone()));
+                self.query_cache.insert(
+                    key,
+                    query_cache::EntryValue::new(env, result.clone()),
+                    &self.metrics,
+                );
             }
         }
         result
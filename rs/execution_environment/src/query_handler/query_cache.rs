@@ -1,9 +1,10 @@
+use crate::metrics::QueryHandlerMetrics;
 use ic_base_types::{CanisterId, NumBytes};
 use ic_error_types::UserError;
 use ic_replicated_state::ReplicatedState;
 use ic_types::{ingress::WasmResult, messages::UserQuery, CountBytes, Cycles, Time, UserId};
 use ic_utils_lru_cache::LruCache;
-use std::{mem::size_of_val, sync::Mutex};
+use std::{mem::size_of_val, sync::Mutex, time::Duration};
 
 /// Query Cache entry key.
 ///
@@ -70,6 +71,28 @@ impl TryFrom<(&EntryKey, &ReplicatedState)> for EntryEnv {
     }
 }
 
+/// The reason a Query Cache entry is no longer valid.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum InvalidationReason {
+    /// The batch time moved backwards or beyond the entry expiry time.
+    BatchTime,
+    /// The receiving canister version changed.
+    CanisterVersion,
+    /// The receiving canister cycles balance changed.
+    CanisterBalance,
+}
+
+impl InvalidationReason {
+    /// Returns the metric label of the reason.
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::BatchTime => "batch_time",
+            Self::CanisterVersion => "canister_version",
+            Self::CanisterBalance => "canister_balance",
+        }
+    }
+}
+
 /// Query Cache entry value.
 pub(crate) struct EntryValue {
     env: EntryEnv,
@@ -87,10 +110,30 @@ impl EntryValue {
         Self { env, result }
     }
 
-    pub(crate) fn is_valid(&self, env: &EntryEnv) -> bool {
-        self.env.batch_time == env.batch_time
-            && self.env.canister_version == env.canister_version
-            && self.env.canister_balance == env.canister_balance
+    /// Returns the reason the entry is not valid in the given environment, or
+    /// `None` if the entry is still valid.
+    ///
+    /// The entry stays valid for the batch time it was created at and until
+    /// (excluding) `max_expiry_time` later, as long as the receiving canister
+    /// version and cycles balance stay the same.
+    pub(crate) fn invalidation_reason(
+        &self,
+        env: &EntryEnv,
+        max_expiry_time: Duration,
+    ) -> Option<InvalidationReason> {
+        if self.env.canister_version != env.canister_version {
+            return Some(InvalidationReason::CanisterVersion);
+        }
+        if self.env.canister_balance != env.canister_balance {
+            return Some(InvalidationReason::CanisterBalance);
+        }
+        if self.env.batch_time != env.batch_time
+            && (env.batch_time < self.env.batch_time
+                || env.batch_time >= self.env.batch_time + max_expiry_time)
+        {
+            return Some(InvalidationReason::BatchTime);
+        }
+        None
     }
 
     pub(crate) fn result(&self) -> Result<WasmResult, UserError> {
@@ -103,6 +146,8 @@ pub(crate) struct QueryCache {
     // We can't use `RwLock`, as the `LruCache::get()` requires mutable reference
     // to update the LRU.
     cache: Mutex<LruCache<EntryKey, EntryValue>>,
+    // The maximum time an entry stays valid since its creation.
+    max_expiry_time: Duration,
 }
 
 impl CountBytes for QueryCache {
@@ -115,35 +160,63 @@ impl Default for QueryCache {
     fn default() -> Self {
         QueryCache {
             cache: Mutex::new(LruCache::unbounded()),
+            max_expiry_time: Duration::ZERO,
         }
     }
 }
 
 impl QueryCache {
-    pub(crate) fn new(capacity: NumBytes) -> Self {
+    pub(crate) fn new(capacity: NumBytes, max_expiry_time: Duration) -> Self {
         QueryCache {
             cache: Mutex::new(LruCache::new(capacity)),
+            max_expiry_time,
         }
     }
 
+    /// Returns the cached result for the given key if it is still valid in
+    /// the given environment. Invalid entries are removed from the cache.
     pub(crate) fn get_valid_result(
         &self,
         key: &EntryKey,
         env: &EntryEnv,
+        metrics: &QueryHandlerMetrics,
     ) -> Option<Result<WasmResult, UserError>> {
         let mut cache = self.cache.lock().unwrap();
         if let Some(value) = cache.get(key) {
-            if value.is_valid(env) {
-                return Some(value.result());
-            } else {
-                // Remove the invalid entry.
-                cache.pop(key);
+            match value.invalidation_reason(env, self.max_expiry_time) {
+                None => {
+                    metrics.query_cache_hits.inc();
+                    return Some(value.result());
+                }
+                Some(reason) => {
+                    metrics
+                        .query_cache_invalidated_entries
+                        .with_label_values(&[reason.as_str()])
+                        .inc();
+                    // Remove the invalid entry.
+                    cache.pop(key);
+                    Self::observe_size(&cache, metrics);
+                }
             }
         }
         None
     }
 
-    pub(crate) fn insert(&self, key: EntryKey, value: EntryValue) {
-        self.cache.lock().unwrap().push(key, value);
+    /// Inserts the result of a query execution that missed the cache.
+    pub(crate) fn insert(&self, key: EntryKey, value: EntryValue, metrics: &QueryHandlerMetrics) {
+        metrics.query_cache_misses.inc();
+        let mut cache = self.cache.lock().unwrap();
+        let evicted = cache.push(key, value);
+        metrics
+            .query_cache_evicted_entries
+            .inc_by(evicted.len() as u64);
+        Self::observe_size(&cache, metrics);
+    }
+
+    fn observe_size(cache: &LruCache<EntryKey, EntryValue>, metrics: &QueryHandlerMetrics) {
+        metrics
+            .query_cache_count_bytes
+            .set(cache.count_bytes() as i64);
+        metrics.query_cache_len.set(cache.len() as i64);
     }
 }
@@ -37,11 +37,13 @@ struct PendingCall(CanisterState, CallOrigin, VecDeque<Arc<Request>>);
 /// query execution, the response callback execution may produce an optional
 /// response and a possibly empty set of outgoing query calls.
 ///
-/// In order to bound the resource usage, the DFS traversal has two limits:
+/// In order to bound the resource usage, the DFS traversal checks the limits of
+/// the query context:
 /// - the limit on the depth of the call graph, which is the maximum number of
 ///   nested query calls.
 /// - the limit on the total number of executed instructions by all queries and
 ///   response callbacks.
+/// - the limit on the walltime of the whole query context.
 ///
 /// A note on re-entrancy: currently re-entrant query calls are not allowed.
 /// In other words, if a canister is in the call stack, then an attemp to make a
@@ -52,7 +54,6 @@ pub(super) fn evaluate_query_call_graph(
     canister: CanisterState,
     call_origin: CallOrigin,
     requests: VecDeque<Arc<Request>>,
-    max_query_call_graph_depth: usize,
     measurement_scope: &MeasurementScope,
 ) -> QueryResponse {
     // The nodes of the query call graph that are being visited.
@@ -68,7 +69,7 @@ pub(super) fn evaluate_query_call_graph(
         // `(canister, call_origin)`.
 
         // First check the DFS limits.
-        if call_stack.len() >= max_query_call_graph_depth {
+        if query_context.call_depth_limit_reached(call_stack.len()) {
             let error = UserError::new(
                 ErrorCode::QueryCallGraphTooDeep,
                 "Composite query calls exceeded the maximum call depth.",
@@ -9,7 +9,7 @@ use crate::{
 use ic_base_types::NumBytes;
 use ic_config::flag_status::FlagStatus;
 use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
-use ic_cycles_account_manager::CyclesAccountManager;
+use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_interfaces::execution_environment::{
     ExecutionComplexity, ExecutionMode, HypervisorError, SubnetAvailableMemory,
@@ -255,7 +255,6 @@ impl<'a> QueryContext<'a> {
                     canister,
                     call_origin,
                     requests,
-                    self.max_query_call_graph_depth,
                     &measurement_scope,
                 );
                 match result {
@@ -599,6 +598,26 @@ impl<'a> QueryContext<'a> {
 
         let canister_id = request.receiver;
 
+        // Composite queries are executed against the state of this subnet only,
+        // so calls to canisters hosted elsewhere are rejected upfront.
+        if self.state.canister_state(&canister_id).is_none() {
+            let own_subnet_id = self.state.metadata.own_subnet_id;
+            if let Some(subnet_id) = self.network_topology.routing_table.route(canister_id.get()) {
+                if subnet_id != own_subnet_id {
+                    let error = UserError::new(
+                        ErrorCode::CanisterNotHostedBySubnet,
+                        format!(
+                            "Canister {} is hosted by subnet {}: composite queries can only call canisters on subnet {}",
+                            canister_id, subnet_id, own_subnet_id
+                        ),
+                    );
+                    return ExecutionResult::Response(to_query_result(Payload::Reject(
+                        RejectContext::from(error),
+                    )));
+                }
+            }
+        }
+
         let canister = match self.state.get_active_canister(&canister_id) {
             Ok(canister) => canister,
             Err(err) => {
@@ -785,6 +804,12 @@ impl<'a> QueryContext<'a> {
         self.round_limits.reached()
     }
 
+    /// Returns true if a call stack of the given depth cannot make any more
+    /// nested query calls.
+    pub fn call_depth_limit_reached(&self, depth: usize) -> bool {
+        depth >= self.max_query_call_graph_depth
+    }
+
     /// Return whether the time limit for this query context has been reached.
     pub fn time_limit_reached(&self) -> bool {
         self.query_context_time_start.elapsed() >= self.query_context_time_limit
@@ -832,6 +857,7 @@ impl<'a> QueryContext<'a> {
             compute_allocation: canister.scheduler_state.compute_allocation,
             subnet_type: self.own_subnet_type,
             execution_mode: ExecutionMode::NonReplicated,
+            subnet_memory_saturation: ResourceSaturation::default(),
         }
     }
 
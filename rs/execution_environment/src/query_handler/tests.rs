@@ -5,7 +5,7 @@ use ic_error_types::{ErrorCode, UserError};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::canister_state::system_state::CyclesUseCase;
 use ic_test_utilities::{
-    types::ids::user_test_id,
+    types::ids::{canister_test_id, subnet_test_id, user_test_id},
     universal_canister::{call_args, wasm},
 };
 use ic_test_utilities_execution_environment::{ExecutionTest, ExecutionTestBuilder};
@@ -534,6 +534,119 @@ fn query_cache_env_different_canister_balance_returns_different_results() {
     }
 }
 
+#[test]
+fn query_cache_env_batch_time_within_expiry_returns_cached_result() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_query_caching()
+        .with_query_cache_max_expiry_time(Duration::from_secs(10))
+        .build();
+    let canister_id = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+    let query = UserQuery {
+        source: user_test_id(1),
+        receiver: canister_id,
+        method_name: "query".into(),
+        method_payload: wasm().reply_data(&[42]).build(),
+        ingress_expiry: 0,
+        nonce: None,
+    };
+    let output_1 = test.query(query.clone(), Arc::new(test.state().clone()), vec![]);
+    test.state_mut().metadata.batch_time += Duration::from_secs(5);
+    let output_2 = test.query(query.clone(), Arc::new(test.state().clone()), vec![]);
+    {
+        let query_handler = downcast_query_handler(test.query_handler());
+        assert_eq!(query_handler.metrics.query_cache_hits.get(), 1);
+        assert_eq!(query_handler.metrics.query_cache_misses.get(), 1);
+        assert_eq!(output_1, output_2);
+    }
+    test.state_mut().metadata.batch_time += Duration::from_secs(5);
+    let output_3 = test.query(query, Arc::new(test.state().clone()), vec![]);
+    {
+        let query_handler = downcast_query_handler(test.query_handler());
+        assert_eq!(query_handler.metrics.query_cache_hits.get(), 1);
+        assert_eq!(query_handler.metrics.query_cache_misses.get(), 2);
+        assert_eq!(
+            query_handler
+                .metrics
+                .query_cache_invalidated_entries
+                .with_label_values(&["batch_time"])
+                .get(),
+            1
+        );
+        assert_eq!(output_1, output_3);
+    }
+}
+
+#[test]
+fn query_cache_invalidation_reasons_are_reported() {
+    let mut test = ExecutionTestBuilder::new().with_query_caching().build();
+    let canister_id = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+    let query = UserQuery {
+        source: user_test_id(1),
+        receiver: canister_id,
+        method_name: "query".into(),
+        method_payload: wasm().reply_data(&[42]).build(),
+        ingress_expiry: 0,
+        nonce: None,
+    };
+    let _ = test.query(query.clone(), Arc::new(test.state().clone()), vec![]);
+    test.canister_state_mut(canister_id)
+        .system_state
+        .canister_version += 1;
+    let _ = test.query(query.clone(), Arc::new(test.state().clone()), vec![]);
+    test.canister_state_mut(canister_id)
+        .system_state
+        .remove_cycles(1_u128.into(), CyclesUseCase::Memory);
+    let _ = test.query(query, Arc::new(test.state().clone()), vec![]);
+
+    let query_handler = downcast_query_handler(test.query_handler());
+    let invalidated = |reason| {
+        query_handler
+            .metrics
+            .query_cache_invalidated_entries
+            .with_label_values(&[reason])
+            .get()
+    };
+    assert_eq!(invalidated("canister_version"), 1);
+    assert_eq!(invalidated("canister_balance"), 1);
+    assert_eq!(invalidated("batch_time"), 0);
+    assert_eq!(query_handler.metrics.query_cache_misses.get(), 3);
+    assert_eq!(query_handler.metrics.query_cache_len.get(), 1);
+    assert!(query_handler.metrics.query_cache_count_bytes.get() > 0);
+}
+
+#[test]
+fn query_cache_evictions_are_reported() {
+    const REPLY_SIZE: usize = 10_000;
+
+    let mut test = ExecutionTestBuilder::new()
+        .with_query_caching()
+        .with_query_cache_capacity((REPLY_SIZE * 3 / 2) as u64)
+        .build();
+    let canister_id = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+
+    // Each entry stores the bytes twice: as payload and then as reply, so
+    // only one entry fits into the cache at a time.
+    for i in 0..3_u8 {
+        let _ = test.query(
+            UserQuery {
+                source: user_test_id(1),
+                receiver: canister_id,
+                method_name: "query".into(),
+                method_payload: wasm().reply_data(&[i; REPLY_SIZE / 2]).build(),
+                ingress_expiry: 0,
+                nonce: None,
+            },
+            Arc::new(test.state().clone()),
+            vec![],
+        );
+    }
+
+    let query_handler = downcast_query_handler(test.query_handler());
+    assert_eq!(query_handler.metrics.query_cache_misses.get(), 3);
+    assert_eq!(query_handler.metrics.query_cache_evicted_entries.get(), 2);
+    assert_eq!(query_handler.metrics.query_cache_len.get(), 1);
+}
+
 #[test]
 fn query_cache_env_old_invalid_entry_frees_memory() {
     static BIG_RESPONSE_SIZE: usize = 1_000_000;
@@ -1312,9 +1425,50 @@ fn composite_query_works_in_non_replicated_mode() {
     assert_eq!(result, WasmResult::Reply("hello".as_bytes().to_vec()));
 }
 
+#[test]
+fn composite_query_to_canister_on_another_subnet_is_rejected() {
+    let other_canister = canister_test_id(1);
+    let mut test = ExecutionTestBuilder::new()
+        .with_caller(subnet_test_id(2), other_canister)
+        .build();
+
+    let canister = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+
+    let a = wasm().composite_query(
+        other_canister,
+        call_args().on_reject(wasm().reject_message().reject()),
+    );
+
+    let result = test
+        .query(
+            UserQuery {
+                source: user_test_id(0),
+                receiver: canister,
+                method_name: "composite_query".to_string(),
+                method_payload: a.build(),
+                ingress_expiry: 0,
+                nonce: None,
+            },
+            Arc::new(test.state().clone()),
+            vec![],
+        )
+        .unwrap();
+
+    match result {
+        WasmResult::Reject(message) => assert!(
+            message.contains("composite queries can only call canisters on subnet"),
+            "Unexpected reject message: {}",
+            message
+        ),
+        WasmResult::Reply(_) => panic!("Expected the call to another subnet to be rejected"),
+    }
+}
+
 #[test]
 fn composite_query_fails_if_disabled() {
-    let mut test = ExecutionTestBuilder::new().build();
+    let mut test = ExecutionTestBuilder::new()
+        .without_composite_queries()
+        .build();
 
     let canister = test.canister_from_wat(COMPOSITE_QUERY_WAT).unwrap();
 
@@ -975,6 +975,16 @@ impl SchedulerImpl {
     ) {
         let state_time = state.time();
         let mut all_rejects = Vec::new();
+        let mut uninstalled_canisters = Vec::new();
+        let snapshots_memory_usage: BTreeMap<CanisterId, NumBytes> = state
+            .canister_snapshots
+            .iter()
+            .fold(BTreeMap::new(), |mut usage, (snapshot_id, snapshot)| {
+                *usage
+                    .entry(snapshot_id.canister_id())
+                    .or_insert_with(|| NumBytes::from(0)) += snapshot.size();
+                usage
+            });
         for canister in state.canisters_iter_mut() {
             // Postpone charging for resources when a canister has a paused execution
             // to avoid modifying the balance of a canister during an unfinished operation.
@@ -1004,9 +1014,24 @@ impl SchedulerImpl {
                         duration_since_last_charge,
                         subnet_size,
                     )
+                    .and_then(
+                        |()| match snapshots_memory_usage.get(&canister.canister_id()) {
+                            Some(usage) => {
+                                self.cycles_account_manager.charge_canister_for_snapshots(
+                                    &self.log,
+                                    canister,
+                                    *usage,
+                                    duration_since_last_charge,
+                                    subnet_size,
+                                )
+                            }
+                            None => Ok(()),
+                        },
+                    )
                     .is_err()
                 {
                     all_rejects.push(uninstall_canister(&self.log, canister, state_time));
+                    uninstalled_canisters.push(canister.canister_id());
                     canister.scheduler_state.compute_allocation = ComputeAllocation::zero();
                     canister.system_state.memory_allocation = MemoryAllocation::BestEffort;
                     // Burn the remaining balance of the canister.
@@ -1024,6 +1049,13 @@ impl SchedulerImpl {
             }
         }
 
+        // Snapshots of uninstalled canisters are deleted along with their code.
+        for canister_id in uninstalled_canisters {
+            state
+                .canister_snapshots
+                .remove_canister_snapshots(canister_id);
+        }
+
         // Send rejects to any requests that were forcibly closed while uninstalling.
         for rejects in all_rejects.into_iter() {
             process_responses(
@@ -2085,7 +2117,9 @@ fn can_execute_msg(
         };
 
         // Only one install code message allowed at a time.
-        if let Some(Ic00Method::InstallCode) = maybe_instal_code_method {
+        if let Some(Ic00Method::InstallCode | Ic00Method::InstallChunkedCode) =
+            maybe_instal_code_method
+        {
             return false;
         }
     }
@@ -2141,8 +2175,16 @@ fn get_instructions_limits_for_subnet_message(
             | BitcoinGetCurrentFeePercentiles
             | BitcoinGetSuccessors
             | ProvisionalCreateCanisterWithCycles
-            | ProvisionalTopUpCanister => default_limits,
-            InstallCode => InstructionLimits::new(
+            | ProvisionalTopUpCanister
+            | TakeCanisterSnapshot
+            | LoadCanisterSnapshot
+            | ListCanisterSnapshots
+            | DeleteCanisterSnapshot
+            | FetchCanisterLogs
+            | UploadChunk
+            | ClearChunkStore
+            | StoredChunks => default_limits,
+            InstallCode | InstallChunkedCode => InstructionLimits::new(
                 dts,
                 config.max_instructions_per_install_code,
                 config.max_instructions_per_install_code_slice,
@@ -32,6 +32,7 @@ use ic_registry_routing_table::{CanisterIdRange, RoutingTable};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
     canister_state::execution_state::{self, WasmMetadata},
+    page_map::TestPageAllocatorFileDescriptorImpl,
     testing::{CanisterQueuesTesting, ReplicatedStateTesting},
     CanisterState, ExecutionState, ExportedFunctions, InputQueueType, Memory, ReplicatedState,
 };
@@ -562,6 +563,10 @@ impl SchedulerTest {
         self.registry_settings.subnet_size
     }
 
+    pub fn set_subnet_size(&mut self, subnet_size: usize) {
+        self.registry_settings.subnet_size = subnet_size;
+    }
+
     pub fn ecdsa_signature_fee(&self) -> Cycles {
         self.scheduler
             .cycles_account_manager
@@ -840,6 +845,8 @@ impl SchedulerTestBuilder {
             SchedulerImpl::compute_capacity_percent(self.scheduler_config.scheduler_cores),
             config,
             Arc::clone(&cycles_account_manager),
+            self.scheduler_config.scheduler_cores,
+            Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
         );
         let scheduler = SchedulerImpl::new(
             self.scheduler_config,
@@ -1116,6 +1123,7 @@ impl TestWasmExecutorCore {
                 allocated_bytes: NumBytes::from(0),
                 allocated_message_bytes: NumBytes::from(0),
                 instance_stats: InstanceStats::default(),
+                canister_log_records: vec![],
             };
             self.schedule
                 .push((self.round, canister_id, instructions_to_execute));
@@ -1157,6 +1165,7 @@ impl TestWasmExecutorCore {
             allocated_message_bytes: NumBytes::from(0),
             num_instructions_left: instructions_left,
             instance_stats,
+            canister_log_records: vec![],
         };
         self.schedule
             .push((self.round, canister_id, instructions_to_execute));
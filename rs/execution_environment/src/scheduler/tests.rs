@@ -3086,6 +3086,7 @@ fn consumed_cycles_http_outcalls_are_added_to_consumed_cycles_total() {
             }),
             context: transform_context,
         }),
+        is_replicated: None,
     };
 
     // Create request to `HttpRequest` method.
@@ -3146,6 +3147,100 @@ fn consumed_cycles_http_outcalls_are_added_to_consumed_cycles_total() {
     );
 }
 
+#[test]
+fn http_outcalls_refund_unused_response_bytes() {
+    let mut test = SchedulerTestBuilder::new().build();
+    let caller_canister = test.create_canister();
+
+    test.state_mut().metadata.own_subnet_features.http_requests = true;
+
+    let response_size_limit = 10_000u64;
+    let args = CanisterHttpRequestArgs {
+        url: "https://".to_string(),
+        max_response_bytes: Some(response_size_limit),
+        headers: Vec::new(),
+        body: None,
+        method: HttpMethod::GET,
+        transform: None,
+        is_replicated: None,
+    };
+    test.inject_call_to_ic00(
+        Method::HttpRequest,
+        args.encode(),
+        Cycles::new(1_000_000_000),
+        caller_canister,
+        InputQueueType::RemoteSubnet,
+    );
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+
+    let (callback_id, context) = test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .canister_http_request_contexts
+        .iter()
+        .next()
+        .map(|(callback_id, context)| (*callback_id, context.clone()))
+        .unwrap();
+    let charged_fee = test.http_request_fee(
+        context.variable_parts_size(),
+        Some(NumBytes::from(response_size_limit)),
+    );
+    assert_eq!(
+        test.state()
+            .metadata
+            .subnet_metrics
+            .consumed_cycles_http_outcalls,
+        NominalCycles::from(charged_fee)
+    );
+    assert_eq!(context.subnet_size, Some(test.subnet_size()));
+
+    // The adapter received much less than the limit, and the transform shrank
+    // the response even further.
+    let received_bytes = 1_000;
+    test.state_mut()
+        .metadata
+        .subnet_call_context_manager
+        .canister_http_request_contexts
+        .get_mut(&callback_id)
+        .unwrap()
+        .received_response_bytes = Some(NumBytes::from(received_bytes));
+    test.state_mut().consensus_queue.push(Response {
+        originator: context.request.sender,
+        respondent: ic_types::CanisterId::ic_00(),
+        originator_reply_callback: callback_id,
+        refund: Cycles::zero(),
+        response_payload: Payload::Data(vec![0; 10]),
+    });
+
+    // Only the fee for the received bytes is consumed, at the subnet size the
+    // request was charged for.
+    let actual_fee = test.http_request_fee(
+        context.variable_parts_size(),
+        Some(NumBytes::from(received_bytes)),
+    );
+    assert!(actual_fee < charged_fee);
+    let subnet_size = test.subnet_size();
+    test.set_subnet_size(2 * subnet_size);
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+
+    assert_eq!(
+        test.state()
+            .metadata
+            .subnet_metrics
+            .consumed_cycles_http_outcalls,
+        NominalCycles::from(actual_fee)
+    );
+    assert_eq!(
+        test.state()
+            .metadata
+            .subnet_metrics
+            .get_consumed_cycles_by_use_case()
+            .get(&CyclesUseCase::HTTPOutcalls),
+        Some(&NominalCycles::from(actual_fee))
+    );
+}
+
 #[test]
 fn consumed_cycles_since_replica_started_are_updated_from_valid_canisters() {
     let mut test = SchedulerTestBuilder::new().build();
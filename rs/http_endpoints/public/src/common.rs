@@ -80,6 +80,7 @@ pub(crate) fn make_response(user_error: UserError) -> Response<Body> {
     let status = match user_error.code() {
         C::SubnetOversubscribed => StatusCode::SERVICE_UNAVAILABLE,
         C::MaxNumberOfCanistersReached => StatusCode::SERVICE_UNAVAILABLE,
+        C::CanisterHttpResponseTooLarge => StatusCode::INTERNAL_SERVER_ERROR,
         C::CanisterOutputQueueFull => StatusCode::SERVICE_UNAVAILABLE,
         C::CanisterQueueNotEmpty => StatusCode::SERVICE_UNAVAILABLE,
         C::IngressMessageTimeout => StatusCode::GATEWAY_TIMEOUT,
@@ -88,6 +89,7 @@ pub(crate) fn make_response(user_error: UserError) -> Response<Body> {
         C::CanisterMethodNotFound => StatusCode::NOT_FOUND,
         C::CanisterAlreadyInstalled => StatusCode::PRECONDITION_FAILED,
         C::CanisterWasmModuleNotFound => StatusCode::SERVICE_UNAVAILABLE,
+        C::CanisterSnapshotNotFound => StatusCode::NOT_FOUND,
         C::InsufficientMemoryAllocation => StatusCode::SERVICE_UNAVAILABLE,
         C::InsufficientCyclesForCreateCanister => StatusCode::SERVICE_UNAVAILABLE,
         C::SubnetNotFound => StatusCode::NOT_FOUND,
@@ -120,6 +122,10 @@ pub(crate) fn make_response(user_error: UserError) -> Response<Body> {
         C::CompositeQueryCalledInReplicatedMode => StatusCode::INTERNAL_SERVER_ERROR,
         C::CanisterNotHostedBySubnet => StatusCode::NOT_FOUND,
         C::QueryTimeLimitExceeded => StatusCode::INTERNAL_SERVER_ERROR,
+        C::ReservedCyclesLimitExceededInMemoryAllocation => StatusCode::INTERNAL_SERVER_ERROR,
+        C::ReservedCyclesLimitExceededInMemoryGrow => StatusCode::INTERNAL_SERVER_ERROR,
+        C::InsufficientCyclesInMemoryGrow => StatusCode::INTERNAL_SERVER_ERROR,
+        C::ReservedCyclesLimitIsTooLow => StatusCode::INTERNAL_SERVER_ERROR,
     };
     make_plaintext_response(status, user_error.description().to_string())
 }
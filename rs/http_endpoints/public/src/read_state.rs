@@ -418,7 +418,7 @@ mod test {
     use ic_interfaces_state_manager::Labeled;
     use ic_interfaces_state_manager_mocks::MockStateManager;
     use ic_registry_subnet_type::SubnetType;
-    use ic_replicated_state::{CanisterQueues, ReplicatedState, SystemMetadata};
+    use ic_replicated_state::{CanisterQueues, CanisterSnapshots, ReplicatedState, SystemMetadata};
     use ic_test_utilities::{
         mock_time,
         state::insert_dummy_canister,
@@ -554,6 +554,7 @@ mod test {
                         BTreeMap::new(),
                         metadata,
                         CanisterQueues::default(),
+                        CanisterSnapshots::default(),
                     )),
                 )
             });
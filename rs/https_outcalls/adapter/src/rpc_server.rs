@@ -102,6 +102,9 @@ impl CanisterHttpService for CanisterHttp {
                 HttpMethod::Get => Ok(Method::GET),
                 HttpMethod::Post => Ok(Method::POST),
                 HttpMethod::Head => Ok(Method::HEAD),
+                HttpMethod::Put => Ok(Method::PUT),
+                HttpMethod::Patch => Ok(Method::PATCH),
+                HttpMethod::Delete => Ok(Method::DELETE),
                 _ => {
                     self.metrics
                         .request_errors
@@ -254,14 +257,16 @@ impl CanisterHttpService for CanisterHttp {
             }
         })?;
 
+        let received_bytes = body_bytes.len() as u64 + headers_size_bytes as u64;
         self.metrics
             .network_traffic
             .with_label_values(&[LABEL_DOWNLOAD])
-            .inc_by(body_bytes.len() as u64 + headers_size_bytes as u64);
+            .inc_by(received_bytes);
         Ok(Response::new(CanisterHttpSendResponse {
             status,
             headers,
             content: body_bytes.to_vec(),
+            received_bytes,
         }))
     }
 }
@@ -1,7 +1,7 @@
 use crate::metrics::Metrics;
 use candid::Encode;
 use futures::future::TryFutureExt;
-use ic_error_types::{RejectCode, UserError};
+use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_https_outcalls_service::{
     canister_http_service_client::CanisterHttpServiceClient, CanisterHttpSendRequest,
     CanisterHttpSendResponse, HttpHeader, HttpMethod,
@@ -29,7 +29,7 @@ use tokio::{
         Receiver, Sender,
     },
 };
-use tonic::{transport::Channel, Code};
+use tonic::{transport::Channel, Code, Status};
 use tower::util::Oneshot;
 
 /// This client is returend if we fail to make connection to canister http adapter.
@@ -148,6 +148,9 @@ impl NonBlockingChannel<CanisterHttpRequest> for CanisterHttpAdapterClientImpl {
                         CanisterHttpMethod::GET => HttpMethod::Get.into(),
                         CanisterHttpMethod::POST => HttpMethod::Post.into(),
                         CanisterHttpMethod::HEAD => HttpMethod::Head.into(),
+                        CanisterHttpMethod::PUT => HttpMethod::Put.into(),
+                        CanisterHttpMethod::PATCH => HttpMethod::Patch.into(),
+                        CanisterHttpMethod::DELETE => HttpMethod::Delete.into(),
                     },
                     max_response_size_bytes: request_max_response_bytes.unwrap_or(NumBytes::new(MAX_CANISTER_HTTP_RESPONSE_BYTES)).get(),
                     headers: request_headers
@@ -161,15 +164,10 @@ impl NonBlockingChannel<CanisterHttpRequest> for CanisterHttpAdapterClientImpl {
                     // Socks proxy is only enabled on system subnets.
                     socks_proxy_allowed: matches!(subnet_type, SubnetType::System)
                 })
-                .map_err(|grpc_status| {
-                    (
-                        grpc_status_code_to_reject(grpc_status.code()),
-                        grpc_status.message().to_string(),
-                    )
-                })
+                .map_err(grpc_status_to_reject)
                 .and_then(|adapter_response| async move {
 
-                    let CanisterHttpSendResponse { status, headers, content: body} = adapter_response.into_inner();
+                    let CanisterHttpSendResponse { status, headers, content: body, received_bytes } = adapter_response.into_inner();
 
                     let canister_http_payload = CanisterHttpResponsePayload{
                         status: status as u128,
@@ -227,26 +225,29 @@ impl NonBlockingChannel<CanisterHttpRequest> for CanisterHttpAdapterClientImpl {
                         );
                     }
 
-                    Ok(transform_response)
+                    Ok((transform_response, NumBytes::from(received_bytes)))
                 });
 
             // Drive created future to completion and make response available on the channel.
+            let (content, received_bytes) = match adapter_canister_http_response.await {
+                Ok((resp, received_bytes)) => {
+                    metrics.request_total.with_label_values(&["success"]).inc();
+                    (CanisterHttpResponseContent::Success(resp), received_bytes)
+                },
+                Err((reject_code, message)) => {
+                    metrics.request_total.with_label_values(&[&reject_code.to_string()]).inc();
+                    (CanisterHttpResponseContent::Reject(CanisterHttpReject {
+                        reject_code,
+                        message,
+                    }), NumBytes::from(0))
+                },
+            };
             permit.send(CanisterHttpResponse {
                 id: request_id,
                 timeout: request_timeout,
                 canister_id: request_sender,
-                content: match adapter_canister_http_response.await {
-                    Ok(resp) => {
-                        metrics.request_total.with_label_values(&["success"]).inc();
-                        CanisterHttpResponseContent::Success(resp)
-                    },
-                    Err((reject_code, message)) => {
-                        metrics.request_total.with_label_values(&[&reject_code.to_string()]).inc();
-                        CanisterHttpResponseContent::Reject(CanisterHttpReject {
-                        reject_code,
-                        message,
-                    })},
-                }
+                content,
+                received_bytes,
             });
         });
         Ok(())
@@ -311,12 +312,17 @@ async fn transform_adapter_response(
     }
 }
 
-fn grpc_status_code_to_reject(code: Code) -> RejectCode {
-    match code {
+fn grpc_status_to_reject(status: Status) -> (RejectCode, String) {
+    match status.code() {
         // TODO: Is unavailable really transient
-        Code::Unavailable => RejectCode::SysTransient,
-        Code::InvalidArgument => RejectCode::SysFatal,
-        _ => RejectCode::SysFatal,
+        Code::Unavailable => (RejectCode::SysTransient, status.message().to_string()),
+        Code::InvalidArgument => (RejectCode::SysFatal, status.message().to_string()),
+        // The adapter stopped receiving the response because it exceeded `max_response_bytes`.
+        Code::OutOfRange => {
+            let err = UserError::new(ErrorCode::CanisterHttpResponseTooLarge, status.message());
+            (err.reject_code(), err.to_string())
+        }
+        _ => (RejectCode::SysFatal, status.message().to_string()),
     }
 }
 
@@ -330,7 +336,7 @@ mod tests {
     use ic_test_utilities::{mock_time, types::messages::RequestBuilder};
     use ic_types::canister_http::Transform;
     use ic_types::{
-        canister_http::CanisterHttpMethod,
+        canister_http::{CanisterHttpMethod, Replication},
         messages::{Blob, CallbackId},
         Time,
     };
@@ -423,6 +429,9 @@ mod tests {
                     context: vec![],
                 }),
                 time: mock_time(),
+                replication: Replication::FullyReplicated,
+                subnet_size: None,
+                received_response_bytes: None,
             },
         }
     }
@@ -441,6 +450,7 @@ mod tests {
                 reject_code,
                 message: reject_message,
             }),
+            received_bytes: NumBytes::from(0),
         }
     }
 
@@ -450,6 +460,7 @@ mod tests {
         status: u128,
         headers: Vec<HttpHeader>,
         body: Vec<u8>,
+        received_bytes: u64,
     ) -> CanisterHttpResponse {
         CanisterHttpResponse {
             id: CallbackId::from(request_id),
@@ -468,6 +479,7 @@ mod tests {
                 })
                 .unwrap(),
             ),
+            received_bytes: NumBytes::from(received_bytes),
         }
     }
 
@@ -521,6 +533,7 @@ mod tests {
             status: 200,
             headers: adapter_headers.clone(),
             content: adapter_body.clone(),
+            received_bytes: 1024,
         }))
         .await;
 
@@ -562,7 +575,8 @@ mod tests {
                             mock_time(),
                             200,
                             adapter_headers,
-                            adapter_body
+                            adapter_body,
+                            1024
                         )
                     );
                     break;
@@ -623,6 +637,60 @@ mod tests {
         }
     }
 
+    /// Test case where the adapter stops receiving a response that exceeds the
+    /// response size limit. This should be reported with a dedicated error code.
+    #[tokio::test]
+    async fn test_client_response_too_large_adapter_response() {
+        // Adapter mock setup.
+        let mock_grpc_channel = setup_adapter_mock(Err((
+            Code::OutOfRange,
+            "Http body exceeds size limit of 512 B bytes.".to_string(),
+        )))
+        .await;
+        // Asynchronous query handler mock setup. Does not serve any purpose in this test case.
+        let (svc, mut handle) = setup_anonymous_query_mock();
+
+        tokio::spawn(async move {
+            let (_, rsp) = handle.next_request().await.unwrap();
+            rsp.send_response(AnonymousQueryResponse::Rejected {
+                reject_code: RejectCode::SysFatal,
+                reject_message: "dsf".to_string(),
+            });
+        });
+
+        let mut client = CanisterHttpAdapterClientImpl::new(
+            tokio::runtime::Handle::current(),
+            mock_grpc_channel,
+            svc,
+            100,
+            MetricsRegistry::default(),
+            SubnetType::Application,
+        );
+
+        assert_eq!(
+            client.send(build_mock_canister_http_request(420, mock_time(), None)),
+            Ok(())
+        );
+        // Yield to execute the request on the client.
+        loop {
+            match client.try_receive() {
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+                Ok(r) => {
+                    assert_eq!(
+                        r,
+                        build_mock_canister_http_response_reject(
+                            420,
+                            mock_time(),
+                            RejectCode::SysFatal,
+                            "IC0103: Http body exceeds size limit of 512 B bytes.".to_string()
+                        )
+                    );
+                    break;
+                }
+            }
+        }
+    }
+
     /// Test case where transformed response exceeds consensus limit.
     #[tokio::test]
     async fn test_client_transformed_limit() {
@@ -631,6 +699,7 @@ mod tests {
             status: 200,
             headers: Vec::new(),
             content: Vec::new(),
+            received_bytes: 0,
         }))
         .await;
         // Asynchronous query handler mock setup. Does not serve any purpose in this test case.
@@ -762,6 +831,7 @@ mod tests {
             status: 200,
             headers: adapter_headers.clone(),
             content: adapter_body.clone(),
+            received_bytes: 1024,
         }))
         .await;
         // Asynchronous query handler mock setup. Does not serve any purpose in this test case.
@@ -822,7 +892,8 @@ mod tests {
                             mock_time(),
                             200,
                             adapter_headers,
-                            adapter_body
+                            adapter_body,
+                            1024
                         )
                     );
                     break;
@@ -853,6 +924,7 @@ mod tests {
             status: 200,
             headers: adapter_headers.clone(),
             content: adapter_body.clone(),
+            received_bytes: 1024,
         }))
         .await;
         // Asynchronous query handler mock setup. Does not serve any purpose in this test case.
@@ -1043,6 +1115,7 @@ mod tests {
                 0;
                 (MAX_CANISTER_HTTP_RESPONSE_BYTES as usize) - MAX_CANISTER_HTTP_HEADER_TOTAL_SIZE
             ],
+            MAX_CANISTER_HTTP_RESPONSE_BYTES,
         );
         if let CanisterHttpResponseContent::Success(content) = x.content {
             // Substract 50Kb for consensus overhead (CallbackID, Time, CanisterId, CanisterHttpResponseProof)
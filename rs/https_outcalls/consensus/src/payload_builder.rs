@@ -27,17 +27,18 @@ use ic_types::{
     batch::{CanisterHttpPayload, ValidationContext, MAX_CANISTER_HTTP_PAYLOAD_SIZE},
     canister_http::{
         CanisterHttpResponse, CanisterHttpResponseDivergence, CanisterHttpResponseMetadata,
-        CanisterHttpResponseProof, CanisterHttpResponseWithConsensus,
+        CanisterHttpResponseProof, CanisterHttpResponseWithConsensus, Replication,
         CANISTER_HTTP_MAX_RESPONSES_PER_BLOCK, CANISTER_HTTP_TIMEOUT_INTERVAL,
     },
     consensus::Committee,
     crypto::Signed,
+    messages::CallbackId,
     registry::RegistryClientError,
     signature::BasicSignature,
     CountBytes, Height, NodeId, NumBytes, RegistryVersion, SubnetId,
 };
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     mem::size_of,
     sync::{Arc, RwLock},
 };
@@ -208,6 +209,8 @@ impl CanisterHttpPayloadBuilder for CanisterHttpPayloadBuilderImpl {
         let mut candidates = vec![];
         let mut timeouts = vec![];
         let mut divergence_responses = vec![];
+        // The designated nodes of the outstanding non-replicated requests.
+        let mut non_replicated_nodes: BTreeMap<CallbackId, NodeId> = BTreeMap::new();
 
         // Metrics counters
         let mut unique_includable_responses = 0;
@@ -226,14 +229,21 @@ impl CanisterHttpPayloadBuilder for CanisterHttpPayloadBuilderImpl {
             .state_reader
             .get_state_at(validation_context.certified_height)
         {
-            // Iterate over all outstanding canister http requests
-            for (callback_id, request) in state
+            let http_contexts = &state
                 .get_ref()
                 .metadata
                 .subnet_call_context_manager
-                .canister_http_request_contexts
+                .canister_http_request_contexts;
+            non_replicated_nodes = http_contexts
                 .iter()
-            {
+                .filter_map(|(callback_id, request)| match request.replication {
+                    Replication::FullyReplicated => None,
+                    Replication::NonReplicated(node_id) => Some((*callback_id, node_id)),
+                })
+                .collect();
+
+            // Iterate over all outstanding canister http requests
+            for (callback_id, request) in http_contexts.iter() {
                 unique_includable_responses += 1;
                 let candidate_size = callback_id.count_bytes();
                 let size = NumBytes::new((accumulated_size + candidate_size) as u64);
@@ -287,7 +297,27 @@ impl CanisterHttpPayloadBuilder for CanisterHttpPayloadBuilderImpl {
 
             let candidates_and_divergences = response_candidates_by_callback_id
                 .into_iter()
-                .filter_map(|(_, grouped_shares)| {
+                .filter_map(|(callback_id, grouped_shares)| {
+                    // A response to a non-replicated request is included as soon as
+                    // the designated node has provided its share. There is no
+                    // divergence, since only a single node makes the request.
+                    if let Some(designated_node) = non_replicated_nodes.get(&callback_id) {
+                        unique_responses_count += 1;
+                        return grouped_shares.iter().find_map(|(metadata, shares)| {
+                            let share = shares
+                                .iter()
+                                .find(|share| share.signature.signer == *designated_node)?;
+                            pool_access
+                                .get_response_content_by_hash(&metadata.content_hash)
+                                .map(|content| {
+                                    CandidateOrDivergence::Candidate((
+                                        metadata.clone(),
+                                        BTreeSet::from([share.signature.clone()]),
+                                        content,
+                                    ))
+                                })
+                        });
+                    }
                     if let Some((metadata, shares)) = grouped_shares.iter().find(|(_, shares)| {
                         unique_responses_count += 1;
                         let signers: BTreeSet<_> =
@@ -514,6 +544,37 @@ impl CanisterHttpPayloadBuilder for CanisterHttpPayloadBuilderImpl {
         // NOTE: We do this in a separate loop because this check is expensive and we want to
         // do all the cheap checks first
         for response in &payload.responses {
+            // Responses to non-replicated requests must be signed by exactly
+            // the designated node, instead of meeting the threshold.
+            if let Some(Replication::NonReplicated(designated_node)) = http_contexts
+                .get(&response.content.id)
+                .map(|context| &context.replication)
+            {
+                let signers: Vec<NodeId> = response
+                    .proof
+                    .signature
+                    .signatures_map
+                    .keys()
+                    .cloned()
+                    .collect();
+                if signers != vec![*designated_node] {
+                    return Err(CanisterHttpPayloadValidationError::Permanent(
+                        CanisterHttpPermanentValidationError::InvalidNonReplicatedSigner {
+                            designated_node: *designated_node,
+                            signers,
+                        },
+                    ));
+                }
+                self.crypto
+                    .verify_aggregate(&response.proof, consensus_registry_version)
+                    .map_err(|err| {
+                        CanisterHttpPayloadValidationError::Permanent(
+                            CanisterHttpPermanentValidationError::SignatureError(Box::new(err)),
+                        )
+                    })?;
+                continue;
+            }
+
             let threshold = match self
                 .membership
                 .get_committee_threshold(height, Committee::CanisterHttp)
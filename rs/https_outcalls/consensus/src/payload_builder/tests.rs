@@ -33,7 +33,7 @@ use ic_types::{
     canister_http::{
         CanisterHttpMethod, CanisterHttpRequestContext, CanisterHttpResponse,
         CanisterHttpResponseContent, CanisterHttpResponseDivergence, CanisterHttpResponseMetadata,
-        CanisterHttpResponseShare, CanisterHttpResponseWithConsensus,
+        CanisterHttpResponseShare, CanisterHttpResponseWithConsensus, Replication,
         CANISTER_HTTP_MAX_RESPONSES_PER_BLOCK, CANISTER_HTTP_TIMEOUT_INTERVAL,
     },
     consensus::get_faults_tolerated,
@@ -310,6 +310,9 @@ fn timeout_priority() {
                     transform: None,
                     // this is the important one
                     time: mock_time(),
+                    replication: Replication::FullyReplicated,
+                    subnet_size: None,
+                    received_response_bytes: None,
                 };
                 init_state
                     .metadata
@@ -348,6 +351,91 @@ fn timeout_priority() {
     });
 }
 
+/// Check that the response to a non-replicated request is included with only
+/// the share of the designated node, and that a proof not signed by exactly
+/// the designated node is rejected.
+#[test]
+fn non_replicated_request_test() {
+    let designated_node = 2;
+    let (response, metadata) = test_response_and_metadata(0);
+
+    test_config_with_http_feature(4, |mut payload_builder, canister_http_pool| {
+        let mut init_state = ic_test_utilities::state::get_initial_state(0, 0);
+        init_state
+            .metadata
+            .subnet_call_context_manager
+            .canister_http_request_contexts
+            .insert(
+                response.id,
+                CanisterHttpRequestContext {
+                    request: RequestBuilder::default().build(),
+                    url: String::new(),
+                    max_response_bytes: None,
+                    headers: vec![],
+                    body: None,
+                    http_method: CanisterHttpMethod::POST,
+                    transform: None,
+                    time: mock_time(),
+                    replication: Replication::NonReplicated(node_test_id(designated_node)),
+                    subnet_size: None,
+                    received_response_bytes: None,
+                },
+            );
+        let state_manager = Arc::new(RefMockStateManager::default());
+        state_manager
+            .get_mut()
+            .expect_get_state_at()
+            .return_const(Ok(ic_interfaces_state_manager::Labeled::new(
+                Height::new(0),
+                Arc::new(init_state),
+            )));
+        payload_builder.state_reader = state_manager;
+
+        {
+            // Only the designated node provides a share
+            let mut pool_access = canister_http_pool.write().unwrap();
+            let share = metadata_to_share(designated_node, &metadata);
+            add_own_share_to_pool(pool_access.deref_mut(), &share, &response);
+        }
+
+        let validation_context = ValidationContext {
+            registry_version: RegistryVersion::new(1),
+            certified_height: Height::new(0),
+            time: mock_time() + Duration::from_secs(3),
+        };
+
+        let payload = payload_builder.get_canister_http_payload(
+            Height::new(1),
+            &validation_context,
+            &[],
+            NumBytes::new(4 * 1024 * 1024),
+        );
+        assert_eq!(payload.num_responses(), 1);
+        assert_eq!(payload.responses[0].content, response);
+        assert!(payload_builder
+            .validate_canister_http_payload(Height::new(1), &payload, &validation_context, &[])
+            .is_ok());
+
+        // A proof without the signature of the designated node is invalid
+        let payload = CanisterHttpPayload {
+            responses: vec![response_and_metadata_to_proof(&response, &metadata)],
+            timeouts: vec![],
+            divergence_responses: vec![],
+        };
+        match payload_builder.validate_canister_http_payload(
+            Height::new(1),
+            &payload,
+            &validation_context,
+            &[],
+        ) {
+            Err(ValidationError::Permanent(
+                CanisterHttpPermanentValidationError::InvalidNonReplicatedSigner { .. },
+            )) => (),
+            x => panic!("Expected InvalidNonReplicatedSigner, got {:?}", x),
+        }
+    });
+}
+
 /// Check that the payload builder includes a divergence responses
 #[test]
 fn divergence_response_inclusion_test() {
@@ -777,6 +865,7 @@ fn test_response_and_metadata_full(
         timeout,
         canister_id: canister_test_id(0),
         content,
+        received_bytes: NumBytes::from(0),
     };
     // Create metadata of response
     let metadata = CanisterHttpResponseMetadata {
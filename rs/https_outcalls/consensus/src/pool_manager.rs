@@ -184,6 +184,10 @@ impl CanisterHttpPoolManagerImpl {
             .collect();
 
         for (id, context) in http_requests {
+            // Non-replicated requests are only fetched by the designated node.
+            if !context.is_requested_from(&self.replica_config.node_id) {
+                continue;
+            }
             if !request_ids_already_made.contains(&id) {
                 let timeout = context.time + Duration::from_secs(5 * 60);
                 if let Err(err) = self
@@ -405,12 +409,12 @@ pub mod test {
     use ic_interfaces_state_manager::Labeled;
     use ic_metrics::MetricsRegistry;
     use ic_registry_subnet_type::SubnetType;
-    use ic_test_utilities::types::ids::subnet_test_id;
+    use ic_test_utilities::types::ids::{node_test_id, subnet_test_id};
     use ic_test_utilities_logger::with_test_replica_logger;
     use ic_types::{
         crypto::{CryptoHash, CryptoHashOf},
         messages::CallbackId,
-        Height, RegistryVersion, Time,
+        Height, NumBytes, RegistryVersion, Time,
     };
     use mockall::predicate::*;
     use mockall::*;
@@ -446,6 +450,7 @@ pub mod test {
             canister_id: ic_types::CanisterId::from(0),
             timeout: Time::from_nanos_since_unix_epoch(0),
             content: CanisterHttpResponseContent::Success(Vec::new()),
+            received_bytes: NumBytes::from(0),
         }
     }
 
@@ -479,6 +484,9 @@ pub mod test {
                     http_method: CanisterHttpMethod::GET,
                     transform: None,
                     time: ic_types::Time::from_nanos_since_unix_epoch(10),
+                    replication: Replication::FullyReplicated,
+                    subnet_size: None,
+                    received_response_bytes: None,
                 };
 
                 state_manager
@@ -626,6 +634,9 @@ pub mod test {
                     http_method: CanisterHttpMethod::GET,
                     transform: None,
                     time: ic_types::Time::from_nanos_since_unix_epoch(10),
+                    replication: Replication::FullyReplicated,
+                    subnet_size: None,
+                    received_response_bytes: None,
                 };
 
                 // Expect times to be called exactly once to check that already
@@ -702,4 +713,71 @@ pub mod test {
             });
         });
     }
+
+    #[test]
+    pub fn test_non_replicated_request_only_made_by_designated_node() {
+        ic_test_utilities::artifact_pool_config::with_test_pool_config(|pool_config| {
+            with_test_replica_logger(|log| {
+                let Dependencies {
+                    pool,
+                    replica_config,
+                    crypto,
+                    state_manager,
+                    registry,
+                    membership,
+                    ..
+                } = dependencies(pool_config.clone(), 4);
+                // No expectation is set on `send`, so the test fails if this
+                // node attempts to make a request designated to another node.
+                let mut shim_mock = MockNonBlockingChannel::<CanisterHttpRequest>::new();
+                shim_mock
+                    .expect_try_receive()
+                    .return_const(Err(TryReceiveError::Empty));
+                let shim: Arc<Mutex<CanisterHttpAdapterClient>> =
+                    Arc::new(Mutex::new(Box::new(shim_mock)));
+
+                let other_node = node_test_id(1234);
+                assert_ne!(other_node, replica_config.node_id);
+                let request = CanisterHttpRequestContext {
+                    request: ic_test_utilities::types::messages::RequestBuilder::new().build(),
+                    url: "".to_string(),
+                    max_response_bytes: None,
+                    headers: vec![],
+                    body: None,
+                    http_method: CanisterHttpMethod::PUT,
+                    transform: None,
+                    time: ic_types::Time::from_nanos_since_unix_epoch(10),
+                    replication: Replication::NonReplicated(other_node),
+                    subnet_size: None,
+                    received_response_bytes: None,
+                };
+
+                state_manager
+                    .get_mut()
+                    .expect_get_latest_state()
+                    .return_const(Labeled::new(
+                        Height::from(1),
+                        Arc::new(state_with_pending_http_calls(BTreeMap::from([(
+                            CallbackId::from(7),
+                            request,
+                        )]))),
+                    ));
+
+                let pool_manager = CanisterHttpPoolManagerImpl::new(
+                    state_manager,
+                    shim,
+                    crypto,
+                    membership,
+                    pool.get_cache(),
+                    replica_config,
+                    Arc::clone(&registry) as Arc<_>,
+                    MetricsRegistry::new(),
+                    log,
+                );
+                let canister_http_pool = CanisterHttpPoolImpl::new(MetricsRegistry::new());
+                let change_set = pool_manager.generate_change_set(&canister_http_pool);
+                assert_eq!(change_set.len(), 0);
+            });
+        });
+    }
 }
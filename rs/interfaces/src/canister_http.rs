@@ -65,6 +65,12 @@ pub enum CanisterHttpPermanentValidationError {
         signers: Vec<NodeId>,
         expected_threshold: Threshold,
     },
+    /// The response to a non-replicated request was not signed by exactly the
+    /// node that was designated to make the request
+    InvalidNonReplicatedSigner {
+        designated_node: NodeId,
+        signers: Vec<NodeId>,
+    },
     /// The payload contains a duplicate response
     DuplicateResponse(CallbackId),
     DivergenceProofContainsMultipleCallbackIds,
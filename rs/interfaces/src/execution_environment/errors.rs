@@ -1,6 +1,6 @@
 use ic_base_types::{CanisterIdError, PrincipalIdBlobParseError};
 use ic_error_types::UserError;
-use ic_types::{methods::WasmMethod, CanisterId, CountBytes, Cycles, NumInstructions};
+use ic_types::{methods::WasmMethod, CanisterId, CountBytes, Cycles, NumBytes, NumInstructions};
 use ic_wasm_types::{WasmEngineError, WasmInstrumentationError, WasmValidationError};
 use serde::{Deserialize, Serialize};
 
@@ -135,6 +135,26 @@ pub enum HypervisorError {
     },
     /// A canister has written too much new data in a single message.
     MemoryAccessLimitExceeded(String),
+    /// The Wasm heap of the canister grew beyond the `wasm_memory_limit` in
+    /// the canister settings.
+    WasmMemoryLimitExceeded {
+        bytes: NumBytes,
+        limit: NumBytes,
+    },
+    /// The canister does not have enough cycles to reserve for the storage
+    /// it tried to allocate while the subnet memory usage is high.
+    InsufficientCyclesInMemoryGrow {
+        bytes: NumBytes,
+        available: Cycles,
+        required: Cycles,
+    },
+    /// Reserving cycles for the storage the canister tried to allocate would
+    /// exceed the `reserved_cycles_limit` in the canister settings.
+    ReservedCyclesLimitExceededInMemoryGrow {
+        bytes: NumBytes,
+        requested: Cycles,
+        limit: Cycles,
+    },
 }
 
 impl From<WasmInstrumentationError> for HypervisorError {
@@ -320,6 +340,43 @@ impl HypervisorError {
                 format!("Canister exceeded memory access limits: {}", s)
 
             ),
+            Self::WasmMemoryLimitExceeded { bytes, limit } => UserError::new(
+                E::CanisterOutOfMemory,
+                format!(
+                    "Canister {} exceeded its Wasm memory limit: the Wasm memory \
+                    needs {} bytes, but the limit is {} bytes.",
+                    canister_id, bytes, limit
+                ),
+            ),
+            Self::InsufficientCyclesInMemoryGrow {
+                bytes,
+                available,
+                required,
+            } => UserError::new(
+                E::InsufficientCyclesInMemoryGrow,
+                format!(
+                    "Canister {} cannot grow memory by {} bytes due to insufficient cycles. \
+                    At least {} additional cycles are required.",
+                    canister_id,
+                    bytes,
+                    required - available
+                ),
+            ),
+            Self::ReservedCyclesLimitExceededInMemoryGrow {
+                bytes,
+                requested,
+                limit,
+            } => UserError::new(
+                E::ReservedCyclesLimitExceededInMemoryGrow,
+                format!(
+                    "Canister {} cannot grow memory by {} bytes due to its reserved cycles limit. \
+                    The current limit ({}) would be exceeded by {}.",
+                    canister_id,
+                    bytes,
+                    limit,
+                    requested - limit
+                ),
+            ),
         }
     }
 
@@ -350,6 +407,13 @@ impl HypervisorError {
             HypervisorError::Aborted => "Aborted",
             HypervisorError::SliceOverrun { .. } => "SliceOverrun",
             HypervisorError::MemoryAccessLimitExceeded(_) => "MemoryAccessLimitExceeded",
+            HypervisorError::WasmMemoryLimitExceeded { .. } => "WasmMemoryLimitExceeded",
+            HypervisorError::InsufficientCyclesInMemoryGrow { .. } => {
+                "InsufficientCyclesInMemoryGrow"
+            }
+            HypervisorError::ReservedCyclesLimitExceededInMemoryGrow { .. } => {
+                "ReservedCyclesLimitExceededInMemoryGrow"
+            }
         }
     }
 }
@@ -106,6 +106,19 @@ impl StateMachine for StateMachineImpl {
             .consensus_queue
             .append(&mut batch.consensus_responses);
 
+        // Record the response sizes of the canister http requests, which
+        // execution needs to refund the unused part of the request fee.
+        for (callback_id, received_bytes) in batch.canister_http_received_bytes {
+            if let Some(context) = state_with_messages
+                .metadata
+                .subnet_call_context_manager
+                .canister_http_request_contexts
+                .get_mut(&callback_id)
+            {
+                context.received_response_bytes = Some(received_bytes);
+            }
+        }
+
         self.observe_phase_duration(PHASE_INDUCTION, &phase_timer);
 
         let execution_round_type = if batch.requires_full_state_hash {
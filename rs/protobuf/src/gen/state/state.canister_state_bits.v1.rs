@@ -373,6 +373,16 @@ pub struct ConsumedCyclesByUseCase {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterLogRecord {
+    #[prost(uint64, tag = "1")]
+    pub idx: u64,
+    #[prost(uint64, tag = "2")]
+    pub timestamp_nanos: u64,
+    #[prost(bytes = "vec", tag = "3")]
+    pub content: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CanisterStateBits {
     #[prost(uint64, tag = "2")]
     pub last_full_execution_round: u64,
@@ -436,6 +446,24 @@ pub struct CanisterStateBits {
     #[prost(message, repeated, tag = "36")]
     pub consumed_cycles_since_replica_started_by_use_cases:
         ::prost::alloc::vec::Vec<ConsumedCyclesByUseCase>,
+    /// Who is allowed to fetch the canister log.
+    #[prost(enumeration = "LogVisibility", tag = "37")]
+    pub log_visibility: i32,
+    /// The most recent records of the canister log.
+    #[prost(message, repeated, tag = "38")]
+    pub canister_log_records: ::prost::alloc::vec::Vec<CanisterLogRecord>,
+    /// The index to be assigned to the next canister log record.
+    #[prost(uint64, tag = "39")]
+    pub next_canister_log_record_idx: u64,
+    /// Upper bound on the Wasm heap size of the canister, if any.
+    #[prost(uint64, optional, tag = "41")]
+    pub wasm_memory_limit: ::core::option::Option<u64>,
+    /// Cycles reserved for future storage payments.
+    #[prost(message, optional, tag = "42")]
+    pub reserved_balance: ::core::option::Option<super::super::queues::v1::Cycles>,
+    /// The upper bound on the reserved balance, if any.
+    #[prost(message, optional, tag = "43")]
+    pub reserved_balance_limit: ::core::option::Option<super::super::queues::v1::Cycles>,
     #[prost(oneof = "canister_state_bits::CanisterStatus", tags = "11, 12, 13")]
     pub canister_status: ::core::option::Option<canister_state_bits::CanisterStatus>,
 }
@@ -452,6 +480,38 @@ pub mod canister_state_bits {
         Stopped(super::CanisterStatusStopped),
     }
 }
+/// The Wasm chunk store of a canister, persisted next to (not inside) the
+/// `CanisterStateBits` so that unchanged chunks are neither rewritten nor
+/// rehashed at every checkpoint.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WasmChunkStore {
+    /// Chunks of Wasm modules uploaded for chunked code installation.
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub chunks: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterSnapshotBits {
+    /// The local id of the snapshot, unique within the subnet.
+    #[prost(uint64, tag = "1")]
+    pub snapshot_id: u64,
+    #[prost(message, optional, tag = "2")]
+    pub canister_id: ::core::option::Option<super::super::super::types::v1::CanisterId>,
+    /// Time at which the snapshot was taken, in nanoseconds since Unix epoch.
+    #[prost(uint64, tag = "3")]
+    pub taken_at_timestamp_nanos: u64,
+    /// The version of the canister at the time the snapshot was taken.
+    #[prost(uint64, tag = "4")]
+    pub canister_version: u64,
+    #[prost(bytes = "vec", tag = "5")]
+    pub certified_data: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, optional, tag = "6")]
+    pub execution_state_bits: ::core::option::Option<ExecutionStateBits>,
+    /// The size of the canister's stable memory in Wasm pages.
+    #[prost(uint64, tag = "7")]
+    pub stable_memory_size64: u64,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -514,3 +574,24 @@ impl CyclesUseCase {
         }
     }
 }
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum LogVisibility {
+    Unspecified = 0,
+    Controllers = 1,
+    Public = 2,
+}
+impl LogVisibility {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            LogVisibility::Unspecified => "LOG_VISIBILITY_UNSPECIFIED",
+            LogVisibility::Controllers => "LOG_VISIBILITY_CONTROLLERS",
+            LogVisibility::Public => "LOG_VISIBILITY_PUBLIC",
+        }
+    }
+}
@@ -155,6 +155,15 @@ pub struct CanisterHttpRequestContext {
     pub max_response_bytes: ::core::option::Option<u64>,
     #[prost(message, optional, tag = "10")]
     pub transform_context: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+    /// Set if only this node makes the request, i.e. the request is not replicated.
+    #[prost(message, optional, tag = "11")]
+    pub non_replicated_node: ::core::option::Option<super::super::super::types::v1::NodeId>,
+    /// The subnet size when the request fee was charged.
+    #[prost(uint64, optional, tag = "12")]
+    pub subnet_size: ::core::option::Option<u64>,
+    /// The number of response bytes the adapter received, once the response is delivered.
+    #[prost(uint64, optional, tag = "13")]
+    pub received_response_bytes: ::core::option::Option<u64>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -316,6 +325,10 @@ pub struct SystemMetadata {
     #[prost(message, repeated, tag = "18")]
     pub bitcoin_get_successors_follow_up_responses:
         ::prost::alloc::vec::Vec<BitcoinGetSuccessorsFollowUpResponses>,
+    /// The local id to be assigned to the next canister snapshot taken on this
+    /// subnet.
+    #[prost(uint64, tag = "19")]
+    pub next_snapshot_id: u64,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -331,6 +344,9 @@ pub enum HttpMethod {
     Get = 1,
     Post = 2,
     Head = 3,
+    Put = 4,
+    Patch = 5,
+    Delete = 6,
 }
 impl HttpMethod {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -343,6 +359,9 @@ impl HttpMethod {
             HttpMethod::Get => "HTTP_METHOD_GET",
             HttpMethod::Post => "HTTP_METHOD_POST",
             HttpMethod::Head => "HTTP_METHOD_HEAD",
+            HttpMethod::Put => "HTTP_METHOD_PUT",
+            HttpMethod::Patch => "HTTP_METHOD_PATCH",
+            HttpMethod::Delete => "HTTP_METHOD_DELETE",
         }
     }
 }
@@ -30,6 +30,9 @@ pub struct CanisterHttpResponse {
     pub canister_id: ::core::option::Option<super::super::types::v1::CanisterId>,
     #[prost(message, optional, tag = "3")]
     pub content: ::core::option::Option<CanisterHttpResponseContent>,
+    /// The number of bytes the adapter received before the transformation.
+    #[prost(uint64, tag = "5")]
+    pub received_bytes: u64,
 }
 #[derive(serde::Serialize, serde::Deserialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
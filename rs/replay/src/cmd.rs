@@ -75,6 +75,15 @@ pub enum SubCommand {
 
     /// Verify the signature of a CUP from a subnet
     VerifySubnetCUP(VerifySubnetCUPCmd),
+
+    /// Execute a single ingress message against the checkpoint at the given
+    /// height and print what the execution did, without persisting anything.
+    TraceIngress(TraceIngressCmd),
+
+    /// Restore from the backup while recording the partial state hash of
+    /// every height, or comparing it against the hashes recorded by a replay
+    /// with another replica build, stopping at the first mismatch.
+    FindDivergence(FindDivergenceCmd),
 }
 
 #[derive(Clone, Parser)]
@@ -176,3 +185,41 @@ pub struct VerifySubnetCUPCmd {
     /// File wih the content of the public key
     pub public_key_file: PathBuf,
 }
+
+#[derive(Clone, Parser)]
+pub struct TraceIngressCmd {
+    /// Height of the checkpoint to execute the message against.
+    pub height: u64,
+    /// The canister that receives the message.
+    pub canister_id: CanisterId,
+    /// The method to call.
+    pub method_name: String,
+    /// Candid-encoded argument of the call (in hex); `()` by default.
+    #[clap(long, default_value = "4449444c0000")]
+    pub arg: String,
+    /// The sender of the message; the anonymous principal by default.
+    #[clap(long)]
+    pub sender: Option<PrincipalId>,
+}
+
+#[derive(Clone, Parser)]
+pub struct FindDivergenceCmd {
+    #[clap(flatten)]
+    pub restore: RestoreFromBackupCmd,
+    /// New file to record the partial state hash of every height to, when
+    /// replaying with the reference build.
+    #[clap(
+        long,
+        conflicts_with = "compare_state_hashes",
+        required_unless_present = "compare_state_hashes"
+    )]
+    pub record_state_hashes: Option<PathBuf>,
+    /// File of partial state hashes recorded with the reference build, to
+    /// compare the hashes of this replay against.
+    #[clap(long)]
+    pub compare_state_hashes: Option<PathBuf>,
+    /// Checkpoint of the replay with the reference build at the first
+    /// diverging height, to diff the diverging state against.
+    #[clap(long)]
+    pub reference_checkpoint: Option<PathBuf>,
+}
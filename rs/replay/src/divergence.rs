@@ -0,0 +1,262 @@
+//! Finds the first height at which two replays of the same backup diverge.
+//!
+//! Two replica builds cannot run in the same process, so the replays are
+//! connected through a file of per-height partial state hashes: the replay
+//! with the reference build records the hash of every height it computes, and
+//! the replay with the build under test compares its hashes against the
+//! recorded ones and stops at the first mismatch. Given a checkpoint of the
+//! reference replay at that height, the canonical trees of both states are
+//! diffed to name the first diverging canister and subtree.
+
+use ic_crypto_tree_hash::Path as TreePath;
+use ic_registry_subnet_type::SubnetType;
+use ic_replicated_state::{page_map::PageAllocatorFileDescriptor, ReplicatedState};
+use ic_state_layout::CompleteCheckpointLayout;
+use ic_state_manager::{
+    checkpoint::load_checkpoint_parallel,
+    tree_diff::{diff, Changes, PrettyPrintedChanges},
+    tree_hash::hash_state,
+    CheckpointMetrics,
+};
+use ic_types::{CanisterId, CryptoHashOfPartialState, Height, PrincipalId};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Records partial state hashes to a file, or compares them against the
+/// hashes previously recorded in that file.
+pub enum StateHashCheck {
+    Record(BufWriter<File>),
+    Compare(BTreeMap<Height, String>),
+}
+
+impl StateHashCheck {
+    /// Records the hashes to a new file at `path`. Fails if the file exists,
+    /// so that the hashes of an earlier replay are not overwritten.
+    pub fn record(path: &Path) -> Result<Self, String> {
+        println!("Recording partial state hashes to {}", path.display());
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        Ok(Self::Record(BufWriter::new(file)))
+    }
+
+    /// Compares against the hashes recorded in `path`. Fails if the file does
+    /// not exist.
+    pub fn compare(path: &Path) -> Result<Self, String> {
+        println!("Comparing partial state hashes to {}", path.display());
+        let file =
+            File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        let mut hashes = BTreeMap::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            let (height, hash) = parse_line(&line)
+                .ok_or_else(|| format!("Invalid line in {}: {:?}", path.display(), line))?;
+            hashes.insert(height, hash);
+        }
+        Ok(Self::Compare(hashes))
+    }
+
+    /// Records the hash of the state at `height`, or compares it against the
+    /// recorded one.
+    pub fn check(&mut self, height: Height, hash: &CryptoHashOfPartialState) -> HashCheckResult {
+        let hash = hex::encode(&hash.get_ref().0);
+        match self {
+            Self::Record(writer) => {
+                writeln!(writer, "{} {}", height, hash)
+                    .and_then(|_| writer.flush())
+                    .expect("Failed to record the partial state hash");
+                HashCheckResult::Match
+            }
+            Self::Compare(hashes) => match hashes.get(&height) {
+                Some(expected) if *expected == hash => HashCheckResult::Match,
+                Some(expected) => {
+                    println!(
+                        "The partial state hash at height {} is {}, expected {}",
+                        height, hash, expected
+                    );
+                    HashCheckResult::Mismatch
+                }
+                None => match hashes.keys().next_back() {
+                    Some(last_height) if *last_height > height => {
+                        println!(
+                            "Warning: no partial state hash recorded at height {}, \
+                            the height is not compared",
+                            height
+                        );
+                        HashCheckResult::Missing
+                    }
+                    Some(last_height) => {
+                        println!(
+                            "The recorded partial state hashes end at height {}, \
+                            before height {} of this replay",
+                            last_height, height
+                        );
+                        HashCheckResult::Exhausted
+                    }
+                    None => {
+                        println!("No partial state hashes are recorded");
+                        HashCheckResult::Exhausted
+                    }
+                },
+            },
+        }
+    }
+}
+
+/// The result of checking the partial state hash of a height.
+#[derive(Debug, PartialEq, Eq)]
+pub enum HashCheckResult {
+    /// The hash was recorded or matches the recorded one.
+    Match,
+    /// The hash differs from the recorded one.
+    Mismatch,
+    /// No hash was recorded at this height, but at later heights.
+    Missing,
+    /// No hash was recorded at this height or any later height.
+    Exhausted,
+}
+
+fn parse_line(line: &str) -> Option<(Height, String)> {
+    let mut parts = line.split_whitespace();
+    let height = parts.next()?.parse().ok().map(Height::new)?;
+    let hash = parts.next()?.to_string();
+    match parts.next() {
+        None => Some((height, hash)),
+        Some(_) => None,
+    }
+}
+
+/// Returns the first canister whose subtree differs in `changes`, with the
+/// path of the first differing node below it.
+pub fn first_diverging_canister(changes: &Changes) -> Option<(CanisterId, &TreePath)> {
+    changes.keys().find_map(|path| match path.as_slice() {
+        [section, id, ..] if section.as_bytes() == b"canister" => {
+            let principal = PrincipalId::try_from(id.as_bytes()).ok()?;
+            Some((CanisterId::new(principal).ok()?, path))
+        }
+        _ => None,
+    })
+}
+
+/// Diffs the canonical tree of `state` against the one of the reference
+/// checkpoint at `checkpoint_path` and prints the differences.
+pub fn print_divergence(
+    state: &ReplicatedState,
+    height: Height,
+    checkpoint_path: &Path,
+    own_subnet_type: SubnetType,
+    fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+) -> Result<(), String> {
+    let metrics_registry = ic_metrics::MetricsRegistry::new();
+    let layout = CompleteCheckpointLayout::new_untracked(checkpoint_path.to_path_buf(), height)
+        .map_err(|e| format!("Failed to open the reference checkpoint: {}", e))?;
+    let reference = load_checkpoint_parallel(
+        &layout,
+        own_subnet_type,
+        &CheckpointMetrics::new(&metrics_registry),
+        fd_factory,
+    )
+    .map_err(|e| format!("Failed to load the reference checkpoint: {}", e))?;
+
+    let changes = diff(&hash_state(&reference), &hash_state(state));
+    match first_diverging_canister(&changes) {
+        Some((canister_id, path)) => {
+            println!("First diverging canister: {} at {}", canister_id, path)
+        }
+        None => println!("No canister diverges"),
+    }
+    if let Some(path) = changes.keys().next() {
+        println!("First differing subtree: {}", path);
+    }
+    println!("Changes from the reference state:");
+    print!("{}", PrettyPrintedChanges(&changes));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_crypto_tree_hash::{Digest, Label};
+    use ic_state_manager::tree_diff::Change;
+    use ic_types::crypto::CryptoHash;
+
+    fn path(labels: &[&[u8]]) -> TreePath {
+        TreePath::new(labels.iter().map(|label| Label::from(*label)).collect())
+    }
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(
+            parse_line("42 abcd"),
+            Some((Height::new(42), "abcd".to_string()))
+        );
+        assert_eq!(parse_line("42"), None);
+        assert_eq!(parse_line("x abcd"), None);
+        assert_eq!(parse_line("42 abcd ef"), None);
+    }
+
+    #[test]
+    fn test_record_and_compare_require_explicit_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("hashes");
+        assert!(StateHashCheck::compare(&path).is_err());
+
+        let hash = CryptoHashOfPartialState::from(CryptoHash(vec![1; 32]));
+        let mut check = StateHashCheck::record(&path).unwrap();
+        assert_eq!(check.check(Height::new(1), &hash), HashCheckResult::Match);
+        drop(check);
+        assert!(StateHashCheck::record(&path).is_err());
+
+        let mut check = StateHashCheck::compare(&path).unwrap();
+        assert_eq!(check.check(Height::new(1), &hash), HashCheckResult::Match);
+    }
+
+    #[test]
+    fn test_compare_reports_missing_heights() {
+        let hash = |byte: u8| CryptoHashOfPartialState::from(CryptoHash(vec![byte; 32]));
+        let mut check = StateHashCheck::Compare(BTreeMap::from([
+            (Height::new(1), hex::encode([1; 32])),
+            (Height::new(3), hex::encode([3; 32])),
+        ]));
+        assert_eq!(
+            check.check(Height::new(1), &hash(1)),
+            HashCheckResult::Match
+        );
+        assert_eq!(
+            check.check(Height::new(2), &hash(2)),
+            HashCheckResult::Missing
+        );
+        assert_eq!(
+            check.check(Height::new(3), &hash(4)),
+            HashCheckResult::Mismatch
+        );
+        assert_eq!(
+            check.check(Height::new(4), &hash(4)),
+            HashCheckResult::Exhausted
+        );
+    }
+
+    #[test]
+    fn test_first_diverging_canister() {
+        let canister_id = CanisterId::from_u64(7);
+        let mut changes = Changes::new();
+        changes.insert(path(&[b"metadata"]), Change::InsertLeaf(Digest([1; 32])));
+        assert_eq!(first_diverging_canister(&changes), None);
+
+        let canister_path = path(&[
+            b"canister",
+            canister_id.get_ref().as_slice(),
+            b"certified_data",
+        ]);
+        changes.insert(canister_path.clone(), Change::InsertLeaf(Digest([2; 32])));
+        assert_eq!(
+            first_diverging_canister(&changes),
+            Some((canister_id, &canister_path))
+        );
+    }
+}
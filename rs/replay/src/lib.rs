@@ -15,6 +15,7 @@
 //! Use `ic-replay --help` to find out more.
 
 use crate::cmd::{ReplayToolArgs, SubCommand};
+use crate::divergence::StateHashCheck;
 use crate::ingress::*;
 use crate::player::{Player, ReplayResult};
 
@@ -34,9 +35,11 @@ use std::rc::Rc;
 
 mod backup;
 pub mod cmd;
+mod divergence;
 pub mod ingress;
 mod mocks;
 pub mod player;
+mod trace;
 mod validator;
 
 /// Replays the past blocks and creates a checkpoint of the latest state.
@@ -111,6 +114,15 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
             })
             .0;
 
+        if let Some(SubCommand::TraceIngress(cmd)) = subcmd {
+            let _enter_guard = rt.enter();
+            if let Err(err) = trace::cmd_trace_ingress(cfg, subnet_id, cmd) {
+                println!("Tracing the ingress message failed: {}", err);
+                std::process::exit(1);
+            }
+            return;
+        }
+
         let target_height = args.replay_until_height;
         if let Some(h) = target_height {
             let question = format!("The checkpoint created at height {} ", h)
@@ -138,6 +150,37 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
             return;
         }
 
+        if let Some(SubCommand::FindDivergence(cmd)) = subcmd {
+            let _enter_guard = rt.enter();
+
+            let state_hash_check = match (&cmd.record_state_hashes, &cmd.compare_state_hashes) {
+                (Some(path), None) => StateHashCheck::record(path),
+                (None, Some(path)) => StateHashCheck::compare(path),
+                _ => Err(
+                    "Exactly one of --record-state-hashes and --compare-state-hashes \
+                    must be given"
+                        .to_string(),
+                ),
+            }
+            .unwrap_or_else(|err| {
+                println!("{}", err);
+                std::process::exit(1);
+            });
+            let mut player = Player::new_for_backup(
+                cfg,
+                ReplicaVersion::try_from(cmd.restore.replica_version.as_str())
+                    .expect("Couldn't parse the replica version"),
+                &cmd.restore.backup_spool_path,
+                &cmd.restore.registry_local_store_path,
+                subnet_id,
+                cmd.restore.start_height,
+            )
+            .with_replay_target_height(target_height)
+            .with_state_hash_check(state_hash_check, cmd.reference_checkpoint.clone());
+            *res_clone.borrow_mut() = player.restore(cmd.restore.start_height + 1);
+            return;
+        }
+
         {
             let _enter_guard = rt.enter();
             let player = match (subcmd.as_ref(), target_height) {
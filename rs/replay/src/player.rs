@@ -1,6 +1,7 @@
 use crate::ingress::IngressWithPrinter;
 use crate::{
     backup,
+    divergence::{print_divergence, HashCheckResult, StateHashCheck},
     validator::{InvalidArtifact, ReplayValidator},
 };
 use ic_artifact_pool::{
@@ -123,6 +124,12 @@ pub struct Player {
     // The target height until which the state will be replayed.
     // None means finalized height.
     replay_target_height: Option<u64>,
+    // Records or compares the partial state hash of every replayed height.
+    state_hash_check: Option<StateHashCheck>,
+    // Checkpoint of the reference replay to diff a diverging state against.
+    reference_checkpoint: Option<PathBuf>,
+    // The highest height whose partial state hash was checked.
+    last_checked_height: Height,
 }
 
 impl Player {
@@ -334,6 +341,9 @@ impl Player {
             _async_log_guard,
             tmp_dir: None,
             replay_target_height: None,
+            state_hash_check: None,
+            reference_checkpoint: None,
+            last_checked_height: Height::from(0),
         }
     }
 
@@ -343,6 +353,18 @@ impl Player {
         self
     }
 
+    /// Set the check of the partial state hashes computed during the restore,
+    /// and the reference checkpoint to diff the first diverging state against.
+    pub fn with_state_hash_check(
+        mut self,
+        state_hash_check: StateHashCheck,
+        reference_checkpoint: Option<PathBuf>,
+    ) -> Self {
+        self.state_hash_check = Some(state_hash_check);
+        self.reference_checkpoint = reference_checkpoint;
+        self
+    }
+
     /// In case a consensus pool was supplied, replay past finalized but
     /// un-executed blocks by delivering ingress messages for execution,
     /// and make a full checkpoint of the latest state when they all finish.
@@ -705,6 +727,7 @@ impl Player {
             registry_version,
             time,
             consensus_responses: Vec::new(),
+            canister_http_received_bytes: BTreeMap::new(),
         };
         let context_time = extra_batch.time;
         let extra_msgs = extra(self, context_time);
@@ -872,6 +895,59 @@ impl Player {
         }
     }
 
+    // Checks the partial state hashes of the heights computed since the last
+    // check. On a mismatch, the diverging state is diffed against the reference
+    // checkpoint, if there is one. Returns `false` once the recorded hashes end
+    // before the replayed heights, because later heights can't be compared.
+    fn check_state_hashes(&mut self) -> Result<bool, ReplayError> {
+        let state_hash_check = match self.state_hash_check.as_mut() {
+            Some(state_hash_check) => state_hash_check,
+            None => return Ok(true),
+        };
+        let mut hashes = self.state_manager.list_state_hashes_to_certify();
+        hashes.sort_by_key(|(height, _)| *height);
+        for (height, hash) in hashes {
+            if height <= self.last_checked_height {
+                continue;
+            }
+            self.last_checked_height = height;
+            match state_hash_check.check(height, &hash) {
+                HashCheckResult::Match | HashCheckResult::Missing => continue,
+                HashCheckResult::Exhausted => return Ok(false),
+                HashCheckResult::Mismatch => {}
+            }
+
+            println!("The state diverges at height {}", height);
+            match &self.reference_checkpoint {
+                Some(checkpoint_path) => {
+                    let state = self
+                        .state_manager
+                        .get_state_at(height)
+                        .unwrap_or_else(|err| {
+                            panic!("Couldn't get the state at height {}: {:?}", height, err)
+                        });
+                    if let Err(err) = print_divergence(
+                        state.get_ref(),
+                        height,
+                        checkpoint_path,
+                        state.get_ref().metadata.own_subnet_type,
+                        self.state_manager.get_fd_factory(),
+                    ) {
+                        println!("Couldn't diff against the reference checkpoint: {}", err);
+                    }
+                }
+                None => println!(
+                    "To find the diverging canister, replay with the reference build and \
+                    --replay-until-height {}, then rerun this replay with the resulting \
+                    checkpoint as --reference-checkpoint",
+                    height
+                ),
+            }
+            return Err(ReplayError::StateDivergence(height));
+        }
+        Ok(true)
+    }
+
     /// Restores the execution state starting from the given height.
     pub fn restore(&mut self, start_height: u64) -> ReplayResult {
         let target_height = self.replay_target_height.map(Height::from);
@@ -917,6 +993,13 @@ impl Player {
                 self.replay_target_height.map(Height::from),
             );
             self.wait_for_state(last_batch_height);
+            if !self.check_state_hashes()? {
+                println!(
+                    "Stopping at height {}: the remaining heights can't be compared.",
+                    self.last_checked_height
+                );
+                return Ok(self.get_latest_state_params(None, invalid_artifacts));
+            }
             if let Some(height) = target_height {
                 if last_batch_height >= height {
                     println!("Target height {} reached.", height);
@@ -1211,7 +1294,7 @@ fn write_records_to_local_store(
         .expect("Writing to the file system failed: Stop.");
 }
 
-fn setup_registry(
+pub(crate) fn setup_registry(
     config: Config,
     metrics_registry: Option<&MetricsRegistry>,
 ) -> std::sync::Arc<RegistryClientImpl> {
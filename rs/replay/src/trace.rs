@@ -0,0 +1,288 @@
+//! Executes a single ingress message against the state of a checkpoint and
+//! prints what the execution did, without persisting anything.
+//!
+//! The message is executed in isolation: it is handed directly to the
+//! execution environment instead of being delivered in a batch, so no other
+//! canister runs and no ingress induction fee is charged. Outgoing calls are
+//! printed but not executed.
+
+use crate::cmd::TraceIngressCmd;
+use crate::player::setup_registry;
+use ic_config::{flag_status::FlagStatus, subnet_config::SubnetConfigs, Config};
+use ic_constants::{MAX_INGRESS_TTL, SMALL_APP_SUBNET_MAX_SIZE};
+use ic_cycles_account_manager::CyclesAccountManager;
+use ic_execution_environment::{
+    ExecuteMessageResult, ExecutionEnvironment, ExecutionResponse, Hypervisor,
+    IngressHistoryWriterImpl, RoundInstructions, RoundLimits,
+};
+use ic_interfaces::execution_environment::ExecutionComplexity;
+use ic_interfaces::messages::{CanisterMessage, CanisterMessageOrTask};
+use ic_interfaces_registry::RegistryClient;
+use ic_logger::new_replica_logger_from_config;
+use ic_metrics::MetricsRegistry;
+use ic_replica::setup::get_subnet_type;
+use ic_replicated_state::{
+    canister_state::NextExecution,
+    page_map::{PageAllocatorFileDescriptor, PageAllocatorFileDescriptorImpl},
+    CanisterState, PageMap,
+};
+use ic_state_layout::StateLayout;
+use ic_state_manager::{checkpoint::load_checkpoint_parallel, CheckpointMetrics};
+use ic_system_api::InstructionLimits;
+use ic_types::{
+    ingress::{IngressState, IngressStatus, WasmResult},
+    messages::{Ingress, MessageId, RequestOrResponse},
+    Height, PrincipalId, SubnetId, UserId,
+};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+/// Loads the checkpoint at the height given in `cmd`, executes the ingress
+/// message described by `cmd` and prints the result, the instructions used,
+/// the cycles charged, the memory pages dirtied and the outgoing calls.
+pub(crate) fn cmd_trace_ingress(
+    cfg: Config,
+    subnet_id: SubnetId,
+    cmd: &TraceIngressCmd,
+) -> Result<(), String> {
+    let (log, _async_log_guard) = new_replica_logger_from_config(&cfg.logger);
+    let metrics_registry = MetricsRegistry::new();
+    let registry = setup_registry(cfg.clone(), None);
+    let subnet_type = get_subnet_type(
+        registry.as_ref(),
+        subnet_id,
+        registry.get_latest_version(),
+        &log,
+    );
+    let subnet_config = SubnetConfigs::default().own_subnet_config(subnet_type);
+    let scheduler_config = subnet_config.scheduler_config;
+    let cycles_account_manager = Arc::new(CyclesAccountManager::new(
+        scheduler_config.max_instructions_per_message,
+        subnet_type,
+        subnet_id,
+        subnet_config.cycles_account_manager_config,
+    ));
+
+    let height = Height::new(cmd.height);
+    let state_layout = StateLayout::try_new(
+        log.clone(),
+        cfg.state_manager.state_root(),
+        &metrics_registry,
+    )
+    .map_err(|err| format!("Failed to open the state layout: {}", err))?;
+    let checkpoint_layout = state_layout
+        .checkpoint(height)
+        .map_err(|err| format!("No checkpoint at height {}: {}", height, err))?;
+    // Page deltas produced by the execution are backed by files in a temporary
+    // directory, so that the state directory is left untouched.
+    let page_delta_dir = tempfile::Builder::new()
+        .prefix("replay_trace_page_deltas_")
+        .tempdir()
+        .map_err(|err| format!("Couldn't create a temporary directory: {}", err))?;
+    let fd_factory: Arc<dyn PageAllocatorFileDescriptor> = Arc::new(
+        PageAllocatorFileDescriptorImpl::new(page_delta_dir.path().to_path_buf()),
+    );
+    let mut state = load_checkpoint_parallel(
+        &checkpoint_layout,
+        subnet_type,
+        &CheckpointMetrics::new(&metrics_registry),
+        Arc::clone(&fd_factory),
+    )
+    .map_err(|err| {
+        format!(
+            "Failed to load the checkpoint at height {}: {}",
+            height, err
+        )
+    })?;
+
+    let hypervisor = Arc::new(Hypervisor::new(
+        cfg.hypervisor.clone(),
+        &metrics_registry,
+        subnet_id,
+        subnet_type,
+        log.clone(),
+        Arc::clone(&cycles_account_manager),
+        scheduler_config.dirty_page_overhead,
+        Arc::clone(&fd_factory),
+    ));
+    let ingress_history_writer = Arc::new(IngressHistoryWriterImpl::new(
+        cfg.hypervisor.clone(),
+        log.clone(),
+        &metrics_registry,
+    ));
+    let exec_env = ExecutionEnvironment::new(
+        log.clone(),
+        hypervisor,
+        ingress_history_writer,
+        &metrics_registry,
+        subnet_id,
+        subnet_type,
+        // Same as the compute capacity used by the scheduler.
+        scheduler_config.scheduler_cores.saturating_sub(1) * 100,
+        cfg.hypervisor.clone(),
+        cycles_account_manager,
+        scheduler_config.scheduler_cores,
+        fd_factory,
+    );
+
+    let canister_id = cmd.canister_id;
+    let canister_before = state
+        .canister_state(&canister_id)
+        .cloned()
+        .ok_or_else(|| format!("Canister {} not found at height {}", canister_id, height))?;
+    match canister_before.next_execution() {
+        NextExecution::None | NextExecution::StartNew => {}
+        NextExecution::ContinueLong | NextExecution::ContinueInstallCode => {
+            return Err(format!(
+                "Canister {} has a paused execution at height {}",
+                canister_id, height
+            ))
+        }
+    }
+
+    let method_payload =
+        hex::decode(&cmd.arg).map_err(|err| format!("The argument is not hex encoded: {}", err))?;
+    let time = state.metadata.batch_time;
+    let ingress = Ingress {
+        source: UserId::from(cmd.sender.unwrap_or_else(PrincipalId::new_anonymous)),
+        receiver: canister_id,
+        effective_canister_id: None,
+        method_name: cmd.method_name.clone(),
+        method_payload,
+        message_id: MessageId::from([0; 32]),
+        expiry_time: time + MAX_INGRESS_TTL,
+    };
+
+    let network_topology = Arc::new(state.metadata.network_topology.clone());
+    let subnet_size = network_topology
+        .get_subnet_size(&subnet_id)
+        .unwrap_or(SMALL_APP_SUBNET_MAX_SIZE);
+    let mut round_limits = RoundLimits {
+        instructions: RoundInstructions::from(i64::MAX),
+        execution_complexity: ExecutionComplexity::MAX,
+        subnet_available_memory: exec_env.subnet_available_memory(&state),
+        compute_allocation_used: state.total_compute_allocation(),
+    };
+    // Deterministic time slicing is disabled, so that the message runs to
+    // completion in a single slice.
+    let instruction_limits = InstructionLimits::new(
+        FlagStatus::Disabled,
+        scheduler_config.max_instructions_per_message,
+        scheduler_config.max_instructions_per_message,
+    );
+    let canister = state
+        .take_canister_state(&canister_id)
+        .expect("The canister was found above");
+    let result = exec_env.execute_canister_input(
+        canister,
+        instruction_limits,
+        scheduler_config.max_instructions_per_message_without_dts,
+        CanisterMessageOrTask::Message(CanisterMessage::Ingress(Arc::new(ingress))),
+        None,
+        time,
+        network_topology,
+        &mut round_limits,
+        subnet_size,
+    );
+
+    match result {
+        ExecuteMessageResult::Finished {
+            canister,
+            response,
+            instructions_used,
+            heap_delta,
+        } => {
+            println!(
+                "Executed {}.{} at height {} (batch time {})",
+                canister_id, cmd.method_name, height, time
+            );
+            print_response(&response);
+            println!("Instructions used: {}", instructions_used);
+            print_state_changes(&canister_before, canister, heap_delta.get());
+            Ok(())
+        }
+        ExecuteMessageResult::Paused { .. } => Err(format!(
+            "The execution of {}.{} was paused unexpectedly",
+            canister_id, cmd.method_name
+        )),
+    }
+}
+
+fn print_response(response: &ExecutionResponse) {
+    let state = match response {
+        ExecutionResponse::Ingress((_, IngressStatus::Known { state, .. })) => state,
+        _ => {
+            println!("Result: none");
+            return;
+        }
+    };
+    match state {
+        IngressState::Completed(WasmResult::Reply(bytes)) => {
+            println!("Result: reply {}", hex::encode(bytes))
+        }
+        IngressState::Completed(WasmResult::Reject(message)) => {
+            println!("Result: reject {}", message)
+        }
+        IngressState::Failed(err) => println!("Result: error {}", err),
+        IngressState::Processing => {
+            println!("Result: none yet, the call is waiting for responses to its outgoing calls")
+        }
+        state => println!("Result: {:?}", state),
+    }
+}
+
+fn dirty_pages(page_map: &PageMap) -> String {
+    let pages: Vec<_> = page_map
+        .get_page_delta_indices()
+        .iter()
+        .map(|page| page.get().to_string())
+        .collect();
+    format!("{} [{}]", pages.len(), pages.join(", "))
+}
+
+/// Prints how the execution changed the canister: the cycles charged, the
+/// memory pages dirtied and the calls that the canister made.
+fn print_state_changes(before: &CanisterState, mut after: CanisterState, heap_delta: u64) {
+    let balance_before = before.system_state.balance();
+    let balance_after = after.system_state.balance();
+    println!(
+        "Cycles balance: {} -> {} (charged {})",
+        balance_before,
+        balance_after,
+        balance_before - balance_after
+    );
+
+    println!("Heap delta: {} bytes", heap_delta);
+    if let Some(execution_state) = &after.execution_state {
+        println!(
+            "Heap pages dirtied: {}",
+            dirty_pages(&execution_state.wasm_memory.page_map)
+        );
+        println!(
+            "Stable memory pages dirtied: {}",
+            dirty_pages(&execution_state.stable_memory.page_map)
+        );
+    }
+
+    // Every outgoing call registers a new callback, which distinguishes the
+    // requests made by this execution from those already in the output queues.
+    let callbacks_before: BTreeSet<_> = before
+        .system_state
+        .call_context_manager()
+        .map(|manager| manager.callbacks().keys().cloned().collect())
+        .unwrap_or_default();
+    println!("Outgoing calls:");
+    for (_, message) in after.output_into_iter() {
+        if let RequestOrResponse::Request(request) = message {
+            if !callbacks_before.contains(&request.sender_reply_callback) {
+                println!(
+                    "  {}.{} payment={} arg={}",
+                    request.receiver,
+                    request.method_name,
+                    request.payment,
+                    hex::encode(&request.method_payload)
+                );
+            }
+        }
+    }
+}
@@ -709,6 +709,9 @@ fn can_get_canister_information() {
                 ComputeAllocation::default().as_percent(),
                 None,
                 2592000,
+                None,
+                None,
+                0u128,
                 0u128,
             )
         );
@@ -760,6 +763,9 @@ fn can_get_canister_information() {
                     ComputeAllocation::default().as_percent(),
                     None,
                     259200,
+                    None,
+                    None,
+                    0u128,
                     0u128,
                 ),
                 CanisterStatusResultV2::decode(&res).unwrap(),
@@ -0,0 +1,374 @@
+use crate::{
+    canister_state::execution_state::{Memory, WasmBinary, WasmMetadata},
+    num_bytes_try_from,
+    page_map::PageAllocatorFileDescriptor,
+    CanisterState, ExecutionState, ExportedFunctions, Global, PageMap,
+};
+use ic_types::{CanisterId, NumBytes, PrincipalId, Time};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    fmt,
+    path::PathBuf,
+    sync::Arc,
+};
+
+/// Length in bytes of the local id suffix of an encoded `SnapshotId`.
+const LOCAL_ID_LENGTH: usize = std::mem::size_of::<u64>();
+
+/// Uniquely identifies a canister snapshot on a subnet.
+///
+/// The id is made up of the id of the canister the snapshot belongs to and a
+/// local id that is unique within the subnet. Its external representation (the
+/// one exposed through the management canister) is the concatenation of the
+/// canister id bytes and the big-endian encoding of the local id.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SnapshotId {
+    canister_id: CanisterId,
+    local_id: u64,
+}
+
+impl SnapshotId {
+    pub fn new(canister_id: CanisterId, local_id: u64) -> Self {
+        Self {
+            canister_id,
+            local_id,
+        }
+    }
+
+    /// Returns the id of the canister this snapshot belongs to.
+    pub fn canister_id(&self) -> CanisterId {
+        self.canister_id
+    }
+
+    /// Returns the subnet-local id of this snapshot.
+    pub fn local_id(&self) -> u64 {
+        self.local_id
+    }
+
+    /// Returns the external (blob) representation of this id.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut bytes = self.canister_id.get_ref().as_slice().to_vec();
+        bytes.extend_from_slice(&self.local_id.to_be_bytes());
+        bytes
+    }
+}
+
+impl TryFrom<&[u8]> for SnapshotId {
+    type Error = String;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        if bytes.len() <= LOCAL_ID_LENGTH {
+            return Err(format!(
+                "Snapshot id must be longer than {} bytes, got {} bytes",
+                LOCAL_ID_LENGTH,
+                bytes.len()
+            ));
+        }
+        let (canister_id, local_id) = bytes.split_at(bytes.len() - LOCAL_ID_LENGTH);
+        let canister_id = PrincipalId::try_from(canister_id)
+            .map_err(|err| format!("Invalid canister id in snapshot id: {}", err))?;
+        let local_id = u64::from_be_bytes(local_id.try_into().unwrap());
+        Ok(Self {
+            canister_id: CanisterId::new(canister_id)
+                .map_err(|err| format!("Invalid canister id in snapshot id: {}", err))?,
+            local_id,
+        })
+    }
+}
+
+impl fmt::Display for SnapshotId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.to_vec() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// A point-in-time copy of a canister's Wasm module, heap, stable memory,
+/// exported globals and certified data.
+///
+/// Snapshots are immutable once taken. They are shared between states via
+/// `Arc` so cloning a `ReplicatedState` does not copy any snapshot data.
+#[derive(Clone, Debug)]
+pub struct CanisterSnapshot {
+    canister_id: CanisterId,
+    /// The time at which the snapshot was taken.
+    taken_at_timestamp: Time,
+    /// The canister version at the time the snapshot was taken.
+    canister_version: u64,
+    certified_data: Vec<u8>,
+    wasm_binary: Arc<WasmBinary>,
+    wasm_memory: Memory,
+    stable_memory: Memory,
+    exported_globals: Vec<Global>,
+    exports: ExportedFunctions,
+    metadata: WasmMetadata,
+}
+
+// We have to implement it by hand as `WasmBinary` can not be compared for
+// equality (its embedder cache doesn't need to be).
+impl PartialEq for CanisterSnapshot {
+    fn eq(&self, rhs: &Self) -> bool {
+        (
+            &self.canister_id,
+            &self.taken_at_timestamp,
+            &self.canister_version,
+            &self.certified_data,
+            &self.wasm_binary.binary,
+            &self.wasm_memory,
+            &self.stable_memory,
+            &self.exported_globals,
+            &self.exports,
+            &self.metadata,
+        ) == (
+            &rhs.canister_id,
+            &rhs.taken_at_timestamp,
+            &rhs.canister_version,
+            &rhs.certified_data,
+            &rhs.wasm_binary.binary,
+            &rhs.wasm_memory,
+            &rhs.stable_memory,
+            &rhs.exported_globals,
+            &rhs.exports,
+            &rhs.metadata,
+        )
+    }
+}
+
+impl CanisterSnapshot {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        canister_id: CanisterId,
+        taken_at_timestamp: Time,
+        canister_version: u64,
+        certified_data: Vec<u8>,
+        wasm_binary: Arc<WasmBinary>,
+        wasm_memory: Memory,
+        stable_memory: Memory,
+        exported_globals: Vec<Global>,
+        exports: ExportedFunctions,
+        metadata: WasmMetadata,
+    ) -> Self {
+        Self {
+            canister_id,
+            taken_at_timestamp,
+            canister_version,
+            certified_data,
+            wasm_binary,
+            wasm_memory,
+            stable_memory,
+            exported_globals,
+            exports,
+            metadata,
+        }
+    }
+
+    /// Takes a snapshot of the given canister.
+    ///
+    /// Returns `None` if the canister has no execution state, i.e. it is empty.
+    pub fn from_canister(canister: &CanisterState, taken_at_timestamp: Time) -> Option<Self> {
+        let execution_state = canister.execution_state.as_ref()?;
+        Some(Self {
+            canister_id: canister.canister_id(),
+            taken_at_timestamp,
+            canister_version: canister.system_state.canister_version,
+            certified_data: canister.system_state.certified_data.clone(),
+            wasm_binary: Arc::clone(&execution_state.wasm_binary),
+            // The snapshot must not share the sandbox state of the canister.
+            wasm_memory: Memory::new(
+                execution_state.wasm_memory.page_map.clone(),
+                execution_state.wasm_memory.size,
+            ),
+            stable_memory: Memory::new(
+                execution_state.stable_memory.page_map.clone(),
+                execution_state.stable_memory.size,
+            ),
+            exported_globals: execution_state.exported_globals.clone(),
+            exports: execution_state.exports.clone(),
+            metadata: execution_state.metadata.clone(),
+        })
+    }
+
+    /// Creates a fresh execution state with the contents of this snapshot.
+    ///
+    /// The memories are copied into new page maps rather than shared with the
+    /// snapshot, because the snapshot's page maps are backed by the snapshot's
+    /// own files and must never be flushed to the canister's files.
+    pub fn to_execution_state(
+        &self,
+        canister_root: PathBuf,
+        fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+    ) -> ExecutionState {
+        ExecutionState::new(
+            canister_root,
+            Arc::clone(&self.wasm_binary),
+            self.exports.clone(),
+            copy_memory(&self.wasm_memory, Arc::clone(&fd_factory)),
+            copy_memory(&self.stable_memory, fd_factory),
+            self.exported_globals.clone(),
+            self.metadata.clone(),
+        )
+    }
+
+    pub fn canister_id(&self) -> CanisterId {
+        self.canister_id
+    }
+
+    pub fn taken_at_timestamp(&self) -> Time {
+        self.taken_at_timestamp
+    }
+
+    pub fn canister_version(&self) -> u64 {
+        self.canister_version
+    }
+
+    pub fn certified_data(&self) -> &Vec<u8> {
+        &self.certified_data
+    }
+
+    pub fn wasm_binary(&self) -> &Arc<WasmBinary> {
+        &self.wasm_binary
+    }
+
+    pub fn wasm_memory(&self) -> &Memory {
+        &self.wasm_memory
+    }
+
+    pub fn stable_memory(&self) -> &Memory {
+        &self.stable_memory
+    }
+
+    pub fn exported_globals(&self) -> &Vec<Global> {
+        &self.exported_globals
+    }
+
+    pub fn exports(&self) -> &ExportedFunctions {
+        &self.exports
+    }
+
+    pub fn metadata(&self) -> &WasmMetadata {
+        &self.metadata
+    }
+
+    /// Returns the amount of memory taken by this snapshot, computed the same
+    /// way as the memory usage of an `ExecutionState` plus the certified data.
+    pub fn size(&self) -> NumBytes {
+        // We use 8 bytes per global.
+        let globals_size_bytes = 8 * self.exported_globals.len() as u64;
+        let wasm_binary_size_bytes = self.wasm_binary.binary.len() as u64;
+        num_bytes_try_from(self.wasm_memory.size)
+            .expect("could not convert from wasm memory number of pages to bytes")
+            + num_bytes_try_from(self.stable_memory.size)
+                .expect("could not convert from stable memory number of pages to bytes")
+            + NumBytes::from(globals_size_bytes)
+            + NumBytes::from(wasm_binary_size_bytes)
+            + self.metadata.memory_usage()
+            + NumBytes::from(self.certified_data.len() as u64)
+    }
+}
+
+/// Copies the contents of `memory` into a new `Memory` backed by a fresh
+/// `PageMap`.
+fn copy_memory(memory: &Memory, fd_factory: Arc<dyn PageAllocatorFileDescriptor>) -> Memory {
+    let mut page_map = PageMap::new(fd_factory);
+    let pages: Vec<_> = memory.page_map.host_pages_iter().collect();
+    page_map.update(&pages);
+    Memory::new(page_map, memory.size)
+}
+
+/// All canister snapshots stored on the subnet, indexed by `SnapshotId`.
+///
+/// Since `SnapshotId`s are ordered by canister id first, the snapshots of a
+/// given canister are stored contiguously.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CanisterSnapshots {
+    snapshots: BTreeMap<SnapshotId, Arc<CanisterSnapshot>>,
+}
+
+impl CanisterSnapshots {
+    pub fn new(snapshots: BTreeMap<SnapshotId, Arc<CanisterSnapshot>>) -> Self {
+        Self { snapshots }
+    }
+
+    /// Adds a new snapshot under the given id, replacing any snapshot that
+    /// was stored under the same id.
+    pub fn push(&mut self, snapshot_id: SnapshotId, snapshot: Arc<CanisterSnapshot>) {
+        debug_assert_eq!(snapshot_id.canister_id(), snapshot.canister_id());
+        self.snapshots.insert(snapshot_id, snapshot);
+    }
+
+    /// Returns the snapshot with the given id, if any.
+    pub fn get(&self, snapshot_id: &SnapshotId) -> Option<&Arc<CanisterSnapshot>> {
+        self.snapshots.get(snapshot_id)
+    }
+
+    /// Removes the snapshot with the given id and returns it, if it existed.
+    pub fn remove(&mut self, snapshot_id: &SnapshotId) -> Option<Arc<CanisterSnapshot>> {
+        self.snapshots.remove(snapshot_id)
+    }
+
+    /// Removes all snapshots belonging to the given canister.
+    pub fn remove_canister_snapshots(&mut self, canister_id: CanisterId) {
+        let ids: Vec<SnapshotId> = self
+            .list_snapshots(canister_id)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        for id in ids {
+            self.snapshots.remove(&id);
+        }
+    }
+
+    /// Returns the snapshots of the given canister, ordered by snapshot id.
+    pub fn list_snapshots(
+        &self,
+        canister_id: CanisterId,
+    ) -> Vec<(SnapshotId, &Arc<CanisterSnapshot>)> {
+        self.snapshots
+            .range(SnapshotId::new(canister_id, 0)..=SnapshotId::new(canister_id, u64::MAX))
+            .map(|(id, snapshot)| (*id, snapshot))
+            .collect()
+    }
+
+    /// Returns the number of snapshots of the given canister.
+    pub fn count_snapshots(&self, canister_id: CanisterId) -> usize {
+        self.snapshots
+            .range(SnapshotId::new(canister_id, 0)..=SnapshotId::new(canister_id, u64::MAX))
+            .count()
+    }
+
+    /// Returns the memory taken by the snapshots of the given canister.
+    pub fn canister_memory_usage(&self, canister_id: CanisterId) -> NumBytes {
+        self.snapshots
+            .range(SnapshotId::new(canister_id, 0)..=SnapshotId::new(canister_id, u64::MAX))
+            .map(|(_, snapshot)| snapshot.size())
+            .sum()
+    }
+
+    /// Returns the memory taken by all snapshots on the subnet.
+    pub fn memory_taken(&self) -> NumBytes {
+        self.snapshots
+            .values()
+            .map(|snapshot| snapshot.size())
+            .sum()
+    }
+
+    /// Returns the ids of all snapshots.
+    pub fn snapshot_ids(&self) -> BTreeSet<SnapshotId> {
+        self.snapshots.keys().copied().collect()
+    }
+
+    /// Returns an iterator over all snapshots, ordered by snapshot id.
+    pub fn iter(&self) -> impl Iterator<Item = (&SnapshotId, &Arc<CanisterSnapshot>)> {
+        self.snapshots.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests;
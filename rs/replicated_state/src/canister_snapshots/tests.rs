@@ -0,0 +1,93 @@
+use super::*;
+use crate::page_map::TestPageAllocatorFileDescriptorImpl;
+use ic_test_utilities::mock_time;
+use ic_test_utilities::state::CanisterStateBuilder;
+use ic_test_utilities::types::ids::canister_test_id;
+
+fn snapshot_of(canister_id: CanisterId) -> Arc<CanisterSnapshot> {
+    let canister = CanisterStateBuilder::new()
+        .with_canister_id(canister_id)
+        .with_wasm(vec![1, 2, 3])
+        .with_stable_memory(vec![4; 10])
+        .with_certified_data(vec![5, 6])
+        .build();
+    Arc::new(CanisterSnapshot::from_canister(&canister, mock_time()).unwrap())
+}
+
+#[test]
+fn snapshot_id_roundtrips_through_bytes() {
+    let snapshot_id = SnapshotId::new(canister_test_id(42), 1337);
+    let bytes = snapshot_id.to_vec();
+    assert_eq!(SnapshotId::try_from(bytes.as_slice()), Ok(snapshot_id));
+}
+
+#[test]
+fn snapshot_id_rejects_too_short_blobs() {
+    assert!(SnapshotId::try_from([0u8; LOCAL_ID_LENGTH].as_slice()).is_err());
+}
+
+#[test]
+fn snapshot_of_empty_canister_is_none() {
+    let canister = CanisterStateBuilder::new()
+        .with_canister_id(canister_test_id(1))
+        .build();
+    assert!(CanisterSnapshot::from_canister(&canister, mock_time()).is_none());
+}
+
+#[test]
+fn snapshot_size_includes_stable_memory_and_certified_data() {
+    let snapshot = snapshot_of(canister_test_id(1));
+    let execution_state = snapshot.to_execution_state(
+        "NOT_USED".into(),
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+    );
+    assert_eq!(
+        snapshot.size(),
+        execution_state.memory_usage() + NumBytes::from(2)
+    );
+}
+
+#[test]
+fn execution_state_from_snapshot_has_snapshot_memory() {
+    let snapshot = snapshot_of(canister_test_id(1));
+    let execution_state = snapshot.to_execution_state(
+        "NOT_USED".into(),
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+    );
+    assert_eq!(execution_state.stable_memory, *snapshot.stable_memory());
+    assert_eq!(execution_state.wasm_memory, *snapshot.wasm_memory());
+}
+
+#[test]
+fn snapshots_are_listed_per_canister() {
+    let mut snapshots = CanisterSnapshots::default();
+    let (canister_1, canister_2) = (canister_test_id(1), canister_test_id(2));
+    snapshots.push(SnapshotId::new(canister_1, 0), snapshot_of(canister_1));
+    snapshots.push(SnapshotId::new(canister_2, 1), snapshot_of(canister_2));
+    snapshots.push(SnapshotId::new(canister_1, 2), snapshot_of(canister_1));
+
+    let listed: Vec<SnapshotId> = snapshots
+        .list_snapshots(canister_1)
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect();
+    assert_eq!(
+        listed,
+        vec![
+            SnapshotId::new(canister_1, 0),
+            SnapshotId::new(canister_1, 2)
+        ]
+    );
+    assert_eq!(snapshots.count_snapshots(canister_2), 1);
+    assert_eq!(
+        snapshots.memory_taken(),
+        snapshots.canister_memory_usage(canister_1) + snapshots.canister_memory_usage(canister_2)
+    );
+
+    snapshots.remove_canister_snapshots(canister_1);
+    assert_eq!(snapshots.count_snapshots(canister_1), 0);
+    assert_eq!(
+        snapshots.snapshot_ids(),
+        BTreeSet::from([SnapshotId::new(canister_2, 1)])
+    );
+}
@@ -425,12 +425,13 @@ impl CanisterState {
 
     /// The amount of memory currently being used by the canister.
     ///
-    /// This only includes execution memory (heap, stable, globals, Wasm)
-    /// and canister history memory for system subnets; and execution memory
-    /// plus system state memory (canister messages and canister history)
-    /// for application subnets.
+    /// This only includes execution memory (heap, stable, globals, Wasm),
+    /// canister history memory and Wasm chunk store memory for system subnets;
+    /// and additionally canister message memory for application subnets.
     pub fn memory_usage(&self, own_subnet_type: SubnetType) -> NumBytes {
-        let mut result = self.raw_memory_usage() + self.canister_history_memory_usage();
+        let mut result = self.raw_memory_usage()
+            + self.canister_history_memory_usage()
+            + self.wasm_chunk_store_memory_usage();
         if own_subnet_type != SubnetType::System {
             result += self.message_memory_usage();
         }
@@ -464,6 +465,11 @@ impl CanisterState {
         self.system_state.canister_history_memory_usage()
     }
 
+    /// Returns the amount of memory used by the Wasm chunk store in bytes.
+    pub fn wasm_chunk_store_memory_usage(&self) -> NumBytes {
+        self.system_state.wasm_chunk_store.memory_usage()
+    }
+
     /// Hack to get the dashboard templating working.
     pub fn memory_usage_ref(&self, own_subnet_type: &SubnetType) -> NumBytes {
         self.memory_usage(*own_subnet_type)
@@ -1,4 +1,5 @@
 mod call_context_manager;
+mod wasm_chunk_store;
 
 use super::queues::can_push;
 pub use super::queues::memory_required_to_push_request;
@@ -6,7 +7,9 @@ pub use crate::canister_state::queues::CanisterOutputQueuesIterator;
 use crate::{CanisterQueues, CanisterState, InputQueueType, StateError};
 pub use call_context_manager::{CallContext, CallContextAction, CallContextManager, CallOrigin};
 use ic_base_types::NumSeconds;
-use ic_ic00_types::{CanisterChange, CanisterChangeDetails, CanisterChangeOrigin};
+use ic_ic00_types::{
+    CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterLogRecord, LogVisibility,
+};
 use ic_interfaces::messages::{CanisterCall, CanisterMessage, CanisterMessageOrTask, CanisterTask};
 use ic_logger::{error, ReplicaLogger};
 use ic_protobuf::{
@@ -29,6 +32,10 @@ use std::{
 };
 use std::{collections::BTreeSet, sync::Arc};
 use std::{collections::VecDeque, str::FromStr};
+pub use wasm_chunk_store::{
+    WasmChunkHash, WasmChunkStore, WasmChunkStoreError, MAX_WASM_CHUNKS_PER_CANISTER,
+    MAX_WASM_CHUNK_SIZE,
+};
 
 lazy_static! {
     static ref DEFAULT_PRINCIPAL_MULTIPLE_CONTROLLERS: PrincipalId =
@@ -40,6 +47,9 @@ lazy_static! {
 /// Maximum number of canister changes stored in the canister history.
 pub const MAX_CANISTER_HISTORY_CHANGES: u64 = 20;
 
+/// Maximum total size in bytes of the records stored in the canister log.
+pub const MAX_CANISTER_LOG_BUFFER_SIZE: usize = 4 * 1024;
+
 /// Enumerates use cases of consumed cycles.
 #[derive(Clone, Copy, Debug, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CyclesUseCase {
@@ -243,6 +253,128 @@ impl CanisterHistory {
     }
 }
 
+/// The canister log is a bounded ring buffer of the most recent records
+/// produced by `ic0.debug_print` and by traps, with the oldest records at
+/// lowest indices. The system drops the oldest records to keep the total size
+/// of the records within `MAX_CANISTER_LOG_BUFFER_SIZE`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CanisterLog {
+    /// The index assigned to the next record. Indices are never reused, even
+    /// if the system drops some records.
+    next_idx: u64,
+    /// The records stored in the order from the oldest to the most recent.
+    records: VecDeque<CanisterLogRecord>,
+    /// Sum over `r.data_size()` for all records `r`.
+    bytes_used: usize,
+}
+
+impl CanisterLog {
+    pub fn new(next_idx: u64, records: Vec<CanisterLogRecord>) -> Self {
+        let bytes_used = records.iter().map(|r| r.data_size()).sum();
+        Self {
+            next_idx,
+            records: records.into(),
+            bytes_used,
+        }
+    }
+
+    /// Appends a new record to the log, dropping the oldest records if
+    /// necessary. Content that does not fit into the buffer is truncated.
+    pub fn add_record(&mut self, timestamp_nanos: u64, content: Vec<u8>) {
+        let mut record = CanisterLogRecord {
+            idx: self.next_idx,
+            timestamp_nanos,
+            content,
+        };
+        let max_content_size =
+            MAX_CANISTER_LOG_BUFFER_SIZE - (record.data_size() - record.content.len());
+        record.content.truncate(max_content_size);
+        while self.bytes_used + record.data_size() > MAX_CANISTER_LOG_BUFFER_SIZE {
+            match self.records.pop_front() {
+                Some(dropped) => self.bytes_used -= dropped.data_size(),
+                None => break,
+            }
+        }
+        self.bytes_used += record.data_size();
+        self.records.push_back(record);
+        self.next_idx += 1;
+
+        debug_assert_eq!(
+            self.bytes_used,
+            self.records.iter().map(|r| r.data_size()).sum::<usize>()
+        );
+    }
+
+    /// Moves all records of `delta_log` into this log, assigning them new
+    /// indices while preserving their timestamps and order.
+    pub fn append_delta_log(&mut self, delta_log: &mut CanisterLog) {
+        for record in std::mem::take(&mut delta_log.records) {
+            self.add_record(record.timestamp_nanos, record.content);
+        }
+        delta_log.bytes_used = 0;
+    }
+
+    /// Removes and returns all records, keeping the index of the next record.
+    pub fn take_records(&mut self) -> Vec<CanisterLogRecord> {
+        self.bytes_used = 0;
+        std::mem::take(&mut self.records).into()
+    }
+
+    /// Drops all records but keeps the index of the next record.
+    pub fn clear(&mut self) {
+        self.records.clear();
+        self.bytes_used = 0;
+    }
+
+    pub fn records(&self) -> &VecDeque<CanisterLogRecord> {
+        &self.records
+    }
+
+    pub fn next_idx(&self) -> u64 {
+        self.next_idx
+    }
+
+    pub fn bytes_used(&self) -> usize {
+        self.bytes_used
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+/// Errors that can occur when reserving cycles for future storage payments.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReservationError {
+    /// The main balance does not have enough cycles to reserve.
+    InsufficientCycles {
+        requested: Cycles,
+        available: Cycles,
+    },
+    /// The reserved balance would exceed the reserved cycles limit.
+    ReservedLimitExceed { requested: Cycles, limit: Cycles },
+}
+
+impl std::fmt::Display for ReservationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InsufficientCycles {
+                requested,
+                available,
+            } => write!(
+                f,
+                "Cannot reserve {} cycles with only {} cycles available",
+                requested, available
+            ),
+            Self::ReservedLimitExceed { requested, limit } => write!(
+                f,
+                "The reserved balance of {} cycles would exceed the reserved cycles limit of {}",
+                requested, limit
+            ),
+        }
+    }
+}
+
 /// State that is controlled and owned by the system (IC).
 ///
 /// Contains structs needed for running and maintaining the canister on the IC.
@@ -298,6 +430,17 @@ pub struct SystemState {
     /// completes, it will apply `ingress_induction_cycles_debit` to `cycles_balance`.
     ingress_induction_cycles_debit: Cycles,
 
+    /// Cycles that are reserved for future storage payments.
+    ///
+    /// Cycles are moved here from `cycles_balance` when the canister allocates
+    /// memory while the subnet memory usage is above the threshold. They can
+    /// only be spent on storage fees.
+    reserved_balance: Cycles,
+
+    /// The upper bound on `reserved_balance`. Memory allocations that would
+    /// require reserving more cycles fail. `None` means that there is no limit.
+    reserved_balance_limit: Option<Cycles>,
+
     /// Tasks to execute before processing input messages.
     /// Currently the task queue is empty outside of execution rounds.
     pub task_queue: VecDeque<ExecutionTask>,
@@ -310,6 +453,20 @@ pub struct SystemState {
 
     /// Canister history.
     canister_history: CanisterHistory,
+
+    /// Who is allowed to fetch the canister log.
+    pub log_visibility: LogVisibility,
+
+    /// The most recent records produced by `ic0.debug_print` and traps.
+    pub canister_log: CanisterLog,
+
+    /// Chunks of Wasm modules uploaded for `install_chunked_code`.
+    pub wasm_chunk_store: WasmChunkStore,
+
+    /// Upper bound on the Wasm heap size of the canister. Once exceeded,
+    /// `memory.grow` fails and update calls trap, while upgrades still work.
+    /// `None` means that there is no limit.
+    pub wasm_memory_limit: Option<NumBytes>,
 }
 
 /// A wrapper around the different canister statuses.
@@ -678,6 +835,8 @@ impl SystemState {
             queues: CanisterQueues::default(),
             cycles_balance: initial_cycles,
             ingress_induction_cycles_debit: Cycles::zero(),
+            reserved_balance: Cycles::zero(),
+            reserved_balance_limit: None,
             memory_allocation: MemoryAllocation::BestEffort,
             freeze_threshold,
             status,
@@ -687,6 +846,10 @@ impl SystemState {
             global_timer: CanisterTimer::Inactive,
             canister_version: 0,
             canister_history: CanisterHistory::default(),
+            log_visibility: LogVisibility::default(),
+            canister_log: CanisterLog::default(),
+            wasm_chunk_store: WasmChunkStore::default(),
+            wasm_memory_limit: None,
         }
     }
 
@@ -722,6 +885,12 @@ impl SystemState {
         global_timer: CanisterTimer,
         canister_version: u64,
         canister_history: CanisterHistory,
+        log_visibility: LogVisibility,
+        canister_log: CanisterLog,
+        wasm_chunk_store: WasmChunkStore,
+        wasm_memory_limit: Option<NumBytes>,
+        reserved_balance: Cycles,
+        reserved_balance_limit: Option<Cycles>,
     ) -> Self {
         Self {
             controllers,
@@ -734,10 +903,16 @@ impl SystemState {
             canister_metrics,
             cycles_balance,
             ingress_induction_cycles_debit,
+            reserved_balance,
+            reserved_balance_limit,
             task_queue,
             global_timer,
             canister_version,
             canister_history,
+            log_visibility,
+            canister_log,
+            wasm_chunk_store,
+            wasm_memory_limit,
         }
     }
 
@@ -750,6 +925,64 @@ impl SystemState {
         self.cycles_balance
     }
 
+    /// Returns the amount of cycles reserved for future storage payments.
+    pub fn reserved_balance(&self) -> Cycles {
+        self.reserved_balance
+    }
+
+    /// Returns the upper bound on the reserved balance, if any.
+    pub fn reserved_balance_limit(&self) -> Option<Cycles> {
+        self.reserved_balance_limit
+    }
+
+    /// Sets the upper bound on the reserved balance. `None` removes the limit.
+    pub fn set_reserved_balance_limit(&mut self, limit: Option<Cycles>) {
+        self.reserved_balance_limit = limit;
+    }
+
+    /// Checks that the given amount of cycles could be moved to the reserved
+    /// balance if the main balance were `main_balance`.
+    pub fn validate_reservation(
+        &self,
+        amount: Cycles,
+        main_balance: Cycles,
+    ) -> Result<(), ReservationError> {
+        if amount == Cycles::zero() {
+            return Ok(());
+        }
+        if let Some(limit) = self.reserved_balance_limit {
+            let requested = self.reserved_balance + amount;
+            if requested > limit {
+                return Err(ReservationError::ReservedLimitExceed { requested, limit });
+            }
+        }
+        if main_balance < amount {
+            return Err(ReservationError::InsufficientCycles {
+                requested: amount,
+                available: main_balance,
+            });
+        }
+        Ok(())
+    }
+
+    /// Moves the given amount of cycles from the main balance to the reserved
+    /// balance. Fails without changing the state if the main balance is too
+    /// small or if the reserved balance would exceed its limit.
+    pub fn reserve_cycles(&mut self, amount: Cycles) -> Result<(), ReservationError> {
+        self.validate_reservation(amount, self.cycles_balance)?;
+        self.cycles_balance -= amount;
+        self.reserved_balance += amount;
+        Ok(())
+    }
+
+    /// Consumes up to `amount` cycles of the reserved balance.
+    pub fn consume_reserved_cycles(&mut self, amount: Cycles, use_case: CyclesUseCase) {
+        let amount = amount.min(self.reserved_balance);
+        self.reserved_balance -= amount;
+        self.observe_consumed_cycles(amount);
+        self.observe_consumed_cycles_with_use_case(amount, use_case, ConsumingCycles::Yes);
+    }
+
     /// Returns the balance after applying the pending 'ingress_induction_cycles_debit'.
     /// Returns 0 if the balance is smaller than the pending 'ingress_induction_cycles_debit'.
     pub fn debited_balance(&self) -> Cycles {
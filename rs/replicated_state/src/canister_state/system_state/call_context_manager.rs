@@ -545,6 +545,15 @@ impl CallContextManager {
         self.next_callback_id
     }
 
+    /// Drops all call contexts and callbacks.
+    ///
+    /// The ID counters are kept, so that responses to the dropped callbacks
+    /// that are still in flight can never match a callback registered later.
+    pub fn drop_call_contexts_and_callbacks(&mut self) {
+        self.call_contexts.clear();
+        self.callbacks.clear();
+    }
+
     /// Returns a collection of all call contexts older than the provided age.
     pub fn call_contexts_older_than(
         &self,
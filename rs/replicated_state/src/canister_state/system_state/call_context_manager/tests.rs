@@ -288,3 +288,49 @@ fn withdraw_cycles_succeeds_when_enough_available_cycles() {
         Ok(())
     );
 }
+
+#[test]
+fn drop_call_contexts_and_callbacks_keeps_ids() {
+    let mut ccm = CallContextManager::default();
+    let cc_id = ccm.new_call_context(
+        CallOrigin::CanisterUpdate(canister_test_id(42), CallbackId::from(1)),
+        Cycles::new(10),
+        Time::from_nanos_since_unix_epoch(0),
+    );
+    let cb_id = ccm.register_callback(Callback::new(
+        cc_id,
+        None,
+        None,
+        Cycles::zero(),
+        Some(Cycles::new(42)),
+        Some(Cycles::new(84)),
+        WasmClosure::new(0, 1),
+        WasmClosure::new(2, 3),
+        None,
+    ));
+
+    ccm.drop_call_contexts_and_callbacks();
+    assert!(ccm.call_contexts().is_empty());
+    assert!(ccm.callbacks().is_empty());
+    assert_eq!(ccm.next_callback_id(), cb_id.get());
+
+    // New call contexts and callbacks don't reuse the IDs of the dropped ones.
+    let new_cc_id = ccm.new_call_context(
+        CallOrigin::SystemTask,
+        Cycles::zero(),
+        Time::from_nanos_since_unix_epoch(0),
+    );
+    assert!(new_cc_id > cc_id);
+    let new_cb_id = ccm.register_callback(Callback::new(
+        new_cc_id,
+        None,
+        None,
+        Cycles::zero(),
+        Some(Cycles::new(42)),
+        Some(Cycles::new(84)),
+        WasmClosure::new(0, 1),
+        WasmClosure::new(2, 3),
+        None,
+    ));
+    assert!(new_cb_id > cb_id);
+}
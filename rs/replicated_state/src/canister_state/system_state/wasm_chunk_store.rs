@@ -0,0 +1,172 @@
+#[cfg(test)]
+mod tests;
+
+use ic_crypto_sha::Sha256;
+use ic_types::NumBytes;
+use std::{collections::BTreeMap, sync::Arc};
+
+/// The maximum size of a single chunk in the Wasm chunk store.
+pub const MAX_WASM_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// The maximum number of chunks a canister can store in its Wasm chunk store.
+pub const MAX_WASM_CHUNKS_PER_CANISTER: usize = 100;
+
+/// The SHA-256 hash of a chunk, used as its key in the store.
+pub type WasmChunkHash = [u8; 32];
+
+/// Errors that can occur when inserting a chunk into the `WasmChunkStore`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WasmChunkStoreError {
+    ChunkTooLarge { size: usize, max_size: usize },
+    StoreFull { max_chunks: usize },
+}
+
+impl std::fmt::Display for WasmChunkStoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ChunkTooLarge { size, max_size } => write!(
+                f,
+                "Chunk of {} bytes exceeds the maximum chunk size of {} bytes",
+                size, max_size
+            ),
+            Self::StoreFull { max_chunks } => write!(
+                f,
+                "Wasm chunk store already contains the maximum number of {} chunks",
+                max_chunks
+            ),
+        }
+    }
+}
+
+/// A per-canister store of Wasm module chunks keyed by their SHA-256 hash.
+///
+/// Chunks are uploaded one by one with `upload_chunk` and later assembled into
+/// a Wasm module by `install_chunked_code`, which allows installing modules
+/// larger than the ingress message size limit.
+///
+/// The store is persisted in its own file of the canister layout, which is
+/// only rewritten if the store changed since it was loaded from a checkpoint.
+#[derive(Clone, Debug, Default)]
+pub struct WasmChunkStore {
+    chunks: Arc<BTreeMap<WasmChunkHash, Arc<Vec<u8>>>>,
+    /// Sum of the sizes of all chunks. Pre-computed as the memory usage of
+    /// canisters is requested frequently.
+    memory_usage: NumBytes,
+    /// Whether the chunks are the ones in the checkpoint file the store was
+    /// loaded from. Not part of the equality, like the file backing a
+    /// `CanisterModule`.
+    persisted: bool,
+}
+
+// The equality ignores whether the store is persisted.
+impl PartialEq for WasmChunkStore {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.chunks, &other.chunks) || self.chunks == other.chunks
+    }
+}
+
+impl Eq for WasmChunkStore {}
+
+impl WasmChunkStore {
+    /// Creates a store from the given chunks.
+    pub fn from_chunks(chunks: Vec<Vec<u8>>) -> Self {
+        let mut store = Self::default();
+        for chunk in chunks {
+            store.insert(chunk);
+        }
+        store
+    }
+
+    /// Creates a store from the chunks in the checkpoint file of a canister.
+    pub fn from_checkpoint(chunks: Vec<Vec<u8>>) -> Self {
+        Self {
+            persisted: true,
+            ..Self::from_chunks(chunks)
+        }
+    }
+
+    /// Returns true if the store has not changed since it was loaded from a
+    /// checkpoint, i.e. its checkpoint file doesn't need to be rewritten.
+    pub fn is_persisted(&self) -> bool {
+        self.persisted
+    }
+
+    /// Checks whether the given chunk can be inserted into the store.
+    /// Re-uploading a chunk that is already stored is always possible.
+    pub fn can_insert_chunk(&self, chunk: &[u8]) -> Result<(), WasmChunkStoreError> {
+        if chunk.len() > MAX_WASM_CHUNK_SIZE {
+            return Err(WasmChunkStoreError::ChunkTooLarge {
+                size: chunk.len(),
+                max_size: MAX_WASM_CHUNK_SIZE,
+            });
+        }
+        if self.chunks.len() >= MAX_WASM_CHUNKS_PER_CANISTER
+            && !self.chunks.contains_key(&Sha256::hash(chunk))
+        {
+            return Err(WasmChunkStoreError::StoreFull {
+                max_chunks: MAX_WASM_CHUNKS_PER_CANISTER,
+            });
+        }
+        Ok(())
+    }
+
+    /// Inserts a chunk into the store and returns its hash.
+    pub fn insert_chunk(&mut self, chunk: Vec<u8>) -> Result<WasmChunkHash, WasmChunkStoreError> {
+        self.can_insert_chunk(&chunk)?;
+        Ok(self.insert(chunk))
+    }
+
+    fn insert(&mut self, chunk: Vec<u8>) -> WasmChunkHash {
+        let hash = Sha256::hash(&chunk);
+        if !self.chunks.contains_key(&hash) {
+            self.memory_usage += NumBytes::from(chunk.len() as u64);
+            self.persisted = false;
+            Arc::make_mut(&mut self.chunks).insert(hash, Arc::new(chunk));
+        }
+        hash
+    }
+
+    /// Returns the additional memory needed to store the given chunk.
+    pub fn memory_needed_for_chunk(&self, chunk: &[u8]) -> NumBytes {
+        if self.chunks.contains_key(&Sha256::hash(chunk)) {
+            NumBytes::from(0)
+        } else {
+            NumBytes::from(chunk.len() as u64)
+        }
+    }
+
+    pub fn get_chunk(&self, hash: &WasmChunkHash) -> Option<&[u8]> {
+        self.chunks.get(hash).map(|chunk| chunk.as_slice())
+    }
+
+    /// Returns the hashes of all stored chunks in ascending order.
+    pub fn keys(&self) -> impl Iterator<Item = &WasmChunkHash> {
+        self.chunks.keys()
+    }
+
+    /// Returns all stored chunks in the order of their hashes.
+    pub fn chunks(&self) -> impl Iterator<Item = &[u8]> {
+        self.chunks.values().map(|chunk| chunk.as_slice())
+    }
+
+    /// Removes all chunks from the store.
+    pub fn clear(&mut self) {
+        if !self.chunks.is_empty() {
+            self.chunks = Arc::new(BTreeMap::new());
+            self.memory_usage = NumBytes::from(0);
+            self.persisted = false;
+        }
+    }
+
+    pub fn memory_usage(&self) -> NumBytes {
+        self.memory_usage
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+}
@@ -0,0 +1,76 @@
+use super::*;
+
+#[test]
+fn insert_chunk_returns_sha256_and_tracks_memory() {
+    let mut store = WasmChunkStore::default();
+    let hash = store.insert_chunk(vec![1, 2, 3]).unwrap();
+
+    assert_eq!(hash, Sha256::hash(&[1, 2, 3]));
+    assert_eq!(store.get_chunk(&hash), Some(&[1u8, 2, 3][..]));
+    assert_eq!(store.memory_usage(), NumBytes::from(3));
+
+    // Uploading the same chunk again does not use more memory.
+    store.insert_chunk(vec![1, 2, 3]).unwrap();
+    assert_eq!(store.len(), 1);
+    assert_eq!(store.memory_usage(), NumBytes::from(3));
+}
+
+#[test]
+fn insert_chunk_rejects_oversized_chunk() {
+    let mut store = WasmChunkStore::default();
+    assert_eq!(
+        store.insert_chunk(vec![0; MAX_WASM_CHUNK_SIZE + 1]),
+        Err(WasmChunkStoreError::ChunkTooLarge {
+            size: MAX_WASM_CHUNK_SIZE + 1,
+            max_size: MAX_WASM_CHUNK_SIZE,
+        })
+    );
+    assert!(store.is_empty());
+}
+
+#[test]
+fn insert_chunk_rejects_chunks_beyond_limit() {
+    let mut store = WasmChunkStore::default();
+    for i in 0..MAX_WASM_CHUNKS_PER_CANISTER {
+        store
+            .insert_chunk((i as u32).to_le_bytes().to_vec())
+            .unwrap();
+    }
+    assert_eq!(
+        store.insert_chunk(vec![0xff; 8]),
+        Err(WasmChunkStoreError::StoreFull {
+            max_chunks: MAX_WASM_CHUNKS_PER_CANISTER,
+        })
+    );
+    // An already stored chunk can still be uploaded.
+    assert!(store.insert_chunk(0u32.to_le_bytes().to_vec()).is_ok());
+}
+
+#[test]
+fn clear_removes_all_chunks() {
+    let mut store = WasmChunkStore::from_chunks(vec![vec![1], vec![2, 3]]);
+    assert_eq!(store.len(), 2);
+    assert_eq!(store.memory_usage(), NumBytes::from(3));
+
+    store.clear();
+    assert!(store.is_empty());
+    assert_eq!(store.memory_usage(), NumBytes::from(0));
+}
+
+#[test]
+fn store_from_checkpoint_is_persisted_until_changed() {
+    let mut store = WasmChunkStore::from_checkpoint(vec![vec![1, 2, 3]]);
+    assert!(store.is_persisted());
+    assert_eq!(store, WasmChunkStore::from_chunks(vec![vec![1, 2, 3]]));
+
+    // Re-uploading a stored chunk doesn't change the store.
+    store.insert_chunk(vec![1, 2, 3]).unwrap();
+    assert!(store.is_persisted());
+
+    store.insert_chunk(vec![4, 5, 6]).unwrap();
+    assert!(!store.is_persisted());
+
+    let mut store = WasmChunkStore::from_checkpoint(vec![vec![1, 2, 3]]);
+    store.clear();
+    assert!(!store.is_persisted());
+}
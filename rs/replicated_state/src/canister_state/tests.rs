@@ -6,7 +6,8 @@ use crate::canister_state::execution_state::CustomSection;
 use crate::canister_state::execution_state::CustomSectionType;
 use crate::canister_state::execution_state::WasmMetadata;
 use crate::canister_state::system_state::{
-    CanisterHistory, CyclesUseCase, MAX_CANISTER_HISTORY_CHANGES,
+    CanisterHistory, CanisterLog, CyclesUseCase, MAX_CANISTER_HISTORY_CHANGES,
+    MAX_CANISTER_LOG_BUFFER_SIZE,
 };
 use crate::CallOrigin;
 use crate::Memory;
@@ -755,3 +756,62 @@ fn canister_history_operations() {
         ));
     }
 }
+
+#[test]
+fn canister_log_assigns_consecutive_indices() {
+    let mut canister_log = CanisterLog::default();
+    canister_log.add_record(10, b"first".to_vec());
+    canister_log.add_record(20, b"second".to_vec());
+
+    let records: Vec<_> = canister_log.records().iter().cloned().collect();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].idx, 0);
+    assert_eq!(records[0].timestamp_nanos, 10);
+    assert_eq!(records[0].content, b"first".to_vec());
+    assert_eq!(records[1].idx, 1);
+    assert_eq!(canister_log.next_idx(), 2);
+}
+
+#[test]
+fn canister_log_drops_oldest_records_when_full() {
+    let mut canister_log = CanisterLog::default();
+    let content = vec![b'x'; 1000];
+    for i in 0..10 {
+        canister_log.add_record(i, content.clone());
+    }
+
+    assert!(canister_log.bytes_used() <= MAX_CANISTER_LOG_BUFFER_SIZE);
+    assert_eq!(canister_log.next_idx(), 10);
+    let first = canister_log.records().front().unwrap();
+    let last = canister_log.records().back().unwrap();
+    assert!(first.idx > 0);
+    assert_eq!(last.idx, 9);
+}
+
+#[test]
+fn canister_log_truncates_oversized_record() {
+    let mut canister_log = CanisterLog::default();
+    canister_log.add_record(0, b"small".to_vec());
+    canister_log.add_record(1, vec![b'x'; 2 * MAX_CANISTER_LOG_BUFFER_SIZE]);
+
+    assert_eq!(canister_log.records().len(), 1);
+    assert_eq!(canister_log.bytes_used(), MAX_CANISTER_LOG_BUFFER_SIZE);
+    assert_eq!(canister_log.records()[0].idx, 1);
+}
+
+#[test]
+fn canister_log_append_delta_log_reassigns_indices() {
+    let mut canister_log = CanisterLog::default();
+    canister_log.add_record(0, b"a".to_vec());
+
+    let mut delta_log = CanisterLog::default();
+    delta_log.add_record(5, b"b".to_vec());
+    delta_log.add_record(6, b"c".to_vec());
+
+    canister_log.append_delta_log(&mut delta_log);
+
+    assert!(delta_log.is_empty());
+    let indices: Vec<_> = canister_log.records().iter().map(|r| r.idx).collect();
+    assert_eq!(indices, vec![0, 1, 2]);
+    assert_eq!(canister_log.records()[2].timestamp_nanos, 6);
+}
@@ -1,4 +1,5 @@
 mod bitcoin;
+pub mod canister_snapshots;
 pub mod canister_state;
 pub(crate) mod hash;
 pub mod metadata_state;
@@ -9,12 +10,14 @@ pub mod testing {
     pub use super::canister_state::testing::CanisterQueuesTesting;
     pub use super::replicated_state::testing::ReplicatedStateTesting;
 }
+pub use canister_snapshots::{CanisterSnapshot, CanisterSnapshots, SnapshotId};
 pub use canister_state::{
     execution_state::Memory,
     num_bytes_try_from,
     system_state::{
         memory_required_to_push_request, CallContext, CallContextAction, CallContextManager,
-        CallOrigin, CanisterMetrics, CanisterStatus, ExecutionTask, SystemState,
+        CallOrigin, CanisterLog, CanisterMetrics, CanisterStatus, ExecutionTask, SystemState,
+        WasmChunkStore,
     },
     CanisterQueues, CanisterState, EmbedderCache, ExecutionState, ExportedFunctions, Global,
     NumWasmPages, SchedulerState,
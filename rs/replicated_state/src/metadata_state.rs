@@ -3,7 +3,7 @@ pub mod subnet_call_context_manager;
 mod tests;
 
 use crate::{
-    canister_state::system_state::CyclesUseCase,
+    canister_snapshots::SnapshotId, canister_state::system_state::CyclesUseCase,
     metadata_state::subnet_call_context_manager::SubnetCallContextManager,
 };
 use ic_base_types::CanisterId;
@@ -144,6 +144,10 @@ pub struct SystemMetadata {
     /// response limit. To work around this limitation, large responses are paginated
     /// and are stored here temporarily until they're fetched by the calling canister.
     pub bitcoin_get_successors_follow_up_responses: BTreeMap<CanisterId, Vec<BlockBlob>>,
+
+    /// The local id to be assigned to the next canister snapshot taken on this
+    /// subnet. Snapshot ids are never reused.
+    next_snapshot_id: u64,
 }
 
 /// Full description of the IC network toplogy.
@@ -422,6 +426,18 @@ impl SubnetMetrics {
             .or_insert_with(|| NominalCycles::from(0)) += cycles;
     }
 
+    /// Reverts the consumption of the given cycles, e.g. when part of an
+    /// upfront fee is refunded.
+    pub fn observe_refunded_cycles_with_use_case(
+        &mut self,
+        use_case: CyclesUseCase,
+        cycles: NominalCycles,
+    ) {
+        if let Some(consumed) = self.consumed_cycles_by_use_case.get_mut(&use_case) {
+            *consumed -= cycles;
+        }
+    }
+
     pub fn get_consumed_cycles_by_use_case(&self) -> &BTreeMap<CyclesUseCase, NominalCycles> {
         &self.consumed_cycles_by_use_case
     }
@@ -522,6 +538,7 @@ impl From<&SystemMetadata> for pb_metadata::SystemMetadata {
                     },
                 )
                 .collect(),
+            next_snapshot_id: item.next_snapshot_id,
         }
     }
 }
@@ -618,6 +635,7 @@ impl TryFrom<pb_metadata::SystemMetadata> for SystemMetadata {
             },
             expected_compiled_wasms: BTreeSet::new(),
             bitcoin_get_successors_follow_up_responses,
+            next_snapshot_id: item.next_snapshot_id,
         })
     }
 }
@@ -651,6 +669,7 @@ impl SystemMetadata {
             subnet_metrics: Default::default(),
             expected_compiled_wasms: BTreeSet::new(),
             bitcoin_get_successors_follow_up_responses: BTreeMap::default(),
+            next_snapshot_id: 0,
         }
     }
 
@@ -708,6 +727,13 @@ impl SystemMetadata {
         Ok(())
     }
 
+    /// Generates a new, subnet-unique id for a snapshot of the given canister.
+    pub fn generate_new_snapshot_id(&mut self, canister_id: CanisterId) -> SnapshotId {
+        let snapshot_id = SnapshotId::new(canister_id, self.next_snapshot_id);
+        self.next_snapshot_id += 1;
+        snapshot_id
+    }
+
     /// Generates a new canister ID.
     ///
     /// If a canister ID from a second canister allocation range is generated, the
@@ -8,8 +8,8 @@ use ic_test_utilities::{
     mock_time,
     types::{
         ids::{
-            canister_test_id, message_test_id, subnet_test_id, user_test_id, SUBNET_0, SUBNET_1,
-            SUBNET_2,
+            canister_test_id, message_test_id, node_test_id, subnet_test_id, user_test_id,
+            SUBNET_0, SUBNET_1, SUBNET_2,
         },
         messages::{RequestBuilder, ResponseBuilder},
         xnet::{StreamHeaderBuilder, StreamSliceBuilder},
@@ -17,7 +17,7 @@ use ic_test_utilities::{
 };
 use ic_types::canister_http::Transform;
 use ic_types::{
-    canister_http::{CanisterHttpMethod, CanisterHttpRequestContext},
+    canister_http::{CanisterHttpMethod, CanisterHttpRequestContext, Replication},
     ingress::WasmResult,
     messages::{CallbackId, Payload},
 };
@@ -467,9 +467,12 @@ fn subnet_call_contexts_deserialization() {
         max_response_bytes: None,
         headers: Vec::new(),
         body: None,
-        http_method: CanisterHttpMethod::GET,
+        http_method: CanisterHttpMethod::PATCH,
         transform: Some(transform.clone()),
         time: mock_time(),
+        replication: Replication::NonReplicated(node_test_id(3)),
+        subnet_size: None,
+        received_response_bytes: None,
     };
     system_call_context_manager.push_http_request(canister_http_request);
 
@@ -492,9 +495,13 @@ fn subnet_call_contexts_deserialization() {
     assert_eq!(deserialized_http_request_context.url, url);
     assert_eq!(
         deserialized_http_request_context.http_method,
-        CanisterHttpMethod::GET
+        CanisterHttpMethod::PATCH
     );
     assert_eq!(deserialized_http_request_context.transform, Some(transform));
+    assert_eq!(
+        deserialized_http_request_context.replication,
+        Replication::NonReplicated(node_test_id(3))
+    );
 }
 
 #[test]
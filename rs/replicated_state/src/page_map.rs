@@ -440,6 +440,29 @@ impl PageMap {
         self.persist_to_file(&self.unflushed_delta, dst)
     }
 
+    /// Persists all pages of this page map (both the checkpoint and the heap
+    /// delta) to the specified destination, replacing any existing contents.
+    pub fn persist_all(&self, dst: &Path) -> Result<(), PersistenceError> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(dst)
+            .map_err(|err| PersistenceError::FileSystemError {
+                path: dst.display().to_string(),
+                context: "Failed to open file".to_string(),
+                internal_error: err.to_string(),
+            })?;
+        let mut buffer = WriteBuffer {
+            content: self.host_pages_iter().map(|(_, page)| &page[..]).collect(),
+            start_index: PageIndex::new(0),
+        };
+        if !buffer.content.is_empty() {
+            buffer.apply_to_file(&mut file, dst)?;
+        }
+        Ok(())
+    }
+
     /// Returns the iterator over host pages managed by this `PageMap`.
     pub fn host_pages_iter(&self) -> impl Iterator<Item = (PageIndex, &PageBytes)> + '_ {
         (0..self.num_host_pages()).map(move |i| {
@@ -159,6 +159,44 @@ fn persisted_map_is_equivalent_to_the_original() {
     assert_eq!(persisted_map, original_map);
 }
 
+#[test]
+fn persist_all_writes_checkpoint_and_delta_to_a_new_file() {
+    let tmp = tempfile::Builder::new()
+        .prefix("checkpoints")
+        .tempdir()
+        .unwrap();
+    let heap_file = tmp.path().join("heap");
+    let copy_file = tmp.path().join("copy");
+
+    let base_page = [42u8; PAGE_SIZE];
+    let mut base_map = PageMap::new_for_testing();
+    base_map.update(&[
+        (PageIndex::new(0), &base_page),
+        (PageIndex::new(7), &base_page),
+    ]);
+    base_map.persist_delta(&heap_file).unwrap();
+
+    let mut original_map = PageMap::open(
+        &heap_file,
+        Height::new(0),
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+    )
+    .unwrap();
+    let page_3 = [3u8; PAGE_SIZE];
+    let page_12 = [12u8; PAGE_SIZE];
+    original_map.update(&[(PageIndex::new(3), &page_3), (PageIndex::new(12), &page_12)]);
+
+    original_map.persist_all(&copy_file).unwrap();
+    let copied_map = PageMap::open(
+        &copy_file,
+        Height::new(0),
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+    )
+    .unwrap();
+
+    assert_equal_page_maps(&original_map, &copied_map);
+}
+
 #[test]
 fn can_persist_and_load_an_empty_page_map() {
     let tmp = tempfile::Builder::new()
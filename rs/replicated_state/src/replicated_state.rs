@@ -6,7 +6,7 @@ use crate::{
     canister_state::queues::CanisterQueuesLoopDetector,
     canister_state::system_state::{push_input, CanisterOutputQueuesIterator},
     metadata_state::StreamMap,
-    CanisterQueues,
+    CanisterQueues, CanisterSnapshots,
 };
 use ic_base_types::PrincipalId;
 use ic_btc_types_internal::BitcoinAdapterResponse;
@@ -323,10 +323,12 @@ pub struct MemoryTaken {
     wasm_custom_sections: NumBytes,
     /// Memory taken by canister history.
     canister_history: NumBytes,
+    /// Memory taken by canister snapshots.
+    snapshots: NumBytes,
     /// Total memory taken. This is the sum of `execution`, `messages`,
-    /// and `canister_history` on application subnets; and excludes
-    /// canister message memory (i.e. sum of `execution` and
-    /// `canister_history`) on system subnets.
+    /// `canister_history` and `snapshots` on application subnets; and
+    /// excludes canister message memory (i.e. sum of `execution`,
+    /// `canister_history` and `snapshots`) on system subnets.
     total: NumBytes,
 }
 
@@ -351,6 +353,11 @@ impl MemoryTaken {
         self.canister_history
     }
 
+    /// Returns the amount of memory taken by canister snapshots.
+    pub fn snapshots(&self) -> NumBytes {
+        self.snapshots
+    }
+
     /// Returns the total amount of memory taken.
     pub fn total(&self) -> NumBytes {
         self.total
@@ -383,6 +390,9 @@ pub struct ReplicatedState {
     /// The queue is, therefore, emptied at the end of every round.
     // TODO(EXE-109): Move this queue into `subnet_queues`
     pub consensus_queue: Vec<Response>,
+
+    /// Snapshots of canisters taken through the management canister.
+    pub canister_snapshots: CanisterSnapshots,
 }
 
 impl ReplicatedState {
@@ -393,6 +403,7 @@ impl ReplicatedState {
             metadata: SystemMetadata::new(own_subnet_id, own_subnet_type),
             subnet_queues: CanisterQueues::default(),
             consensus_queue: Vec::new(),
+            canister_snapshots: CanisterSnapshots::default(),
         }
     }
 
@@ -401,12 +412,14 @@ impl ReplicatedState {
         canister_states: BTreeMap<CanisterId, CanisterState>,
         metadata: SystemMetadata,
         subnet_queues: CanisterQueues,
+        canister_snapshots: CanisterSnapshots,
     ) -> Self {
         let mut res = Self {
             canister_states,
             metadata,
             subnet_queues,
             consensus_queue: Vec::new(),
+            canister_snapshots,
         };
         res.update_stream_responses_size_bytes();
         res
@@ -588,7 +601,9 @@ impl ReplicatedState {
 
         // Raw memory taken includes `wasm_custom_sections_memory_taken` so we
         // don't have to add it to the total memory taken separately.
-        let mut total_memory_taken = raw_memory_taken + canister_history_memory_taken;
+        let snapshots_memory_taken = self.canister_snapshots.memory_taken();
+        let mut total_memory_taken =
+            raw_memory_taken + canister_history_memory_taken + snapshots_memory_taken;
 
         // Add message memory taken to total for non-system subnets only.
         if self.metadata.own_subnet_type != SubnetType::System {
@@ -600,6 +615,7 @@ impl ReplicatedState {
             messages: message_memory_taken,
             wasm_custom_sections: wasm_custom_sections_memory_taken,
             canister_history: canister_history_memory_taken,
+            snapshots: snapshots_memory_taken,
             total: total_memory_taken,
         }
     }
@@ -289,6 +289,10 @@ impl Ledger {
                 CTE::TxDuplicate { duplicate_of } => PTE(TE::TxDuplicate { duplicate_of }),
                 CTE::InsufficientAllowance { .. } => todo!(),
                 CTE::ExpiredApproval { .. } => todo!(),
+                CTE::AllowanceChanged { .. } => unreachable!(
+                    "ICP approvals don't specify an expected allowance, so the allowance \
+                    can't have changed"
+                ),
                 CTE::TxThrottled => PaymentError::Reject(
                     concat!(
                         "Too many transactions in replay prevention window, ",
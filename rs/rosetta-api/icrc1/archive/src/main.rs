@@ -1,4 +1,4 @@
-use candid::{candid_method, Principal};
+use candid::{candid_method, Nat, Principal};
 use ic_canisters_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
 use ic_cdk_macros::{init, post_upgrade, query, update};
 use ic_icrc1::{blocks::icrc1_block_from_encoded, Block};
@@ -8,8 +8,10 @@ use ic_stable_structures::{
     cell::Cell as StableCell, log::Log as StableLog, memory_manager::MemoryManager,
     DefaultMemoryImpl, RestrictedMemory, Storable,
 };
-use icrc_ledger_types::icrc3::blocks::BlockRange;
 use icrc_ledger_types::icrc3::blocks::GenericBlock as IcrcBlock;
+use icrc_ledger_types::icrc3::blocks::{
+    BlockRange, BlockWithId, GetBlocksRequest, GetBlocksResult,
+};
 
 use icrc_ledger_types::icrc3::transactions::Transaction;
 use icrc_ledger_types::icrc3::transactions::{GetTransactionsRequest, TransactionRange};
@@ -265,6 +267,41 @@ fn get_blocks(req: GetTransactionsRequest) -> BlockRange {
     BlockRange { blocks }
 }
 
+/// Returns the blocks in the specified ranges that this archive stores.
+/// Ranges outside of the archive are silently truncated.
+#[query]
+#[candid_method(query)]
+fn icrc3_get_blocks(reqs: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    let (offset, max_blocks) =
+        with_archive_opts(|opts| (opts.block_index_offset, opts.max_transactions_per_response));
+
+    with_blocks(|log| {
+        let mut blocks = vec![];
+        for req in reqs {
+            let (start, length) = req
+                .as_start_and_length()
+                .unwrap_or_else(|msg| ic_cdk::api::trap(&msg));
+            let remaining = max_blocks.saturating_sub(blocks.len() as u64);
+            let first = start.max(offset);
+            let end = start
+                .saturating_add(length)
+                .min(offset.saturating_add(log.len()))
+                .min(first.saturating_add(remaining));
+            for id in first..end {
+                blocks.push(BlockWithId {
+                    id: Nat::from(id),
+                    block: decode_icrc1_block(id, log.get(id - offset).unwrap()),
+                });
+            }
+        }
+        GetBlocksResult {
+            log_length: Nat::from(offset.saturating_add(log.len())),
+            blocks,
+            archived_blocks: vec![],
+        }
+    })
+}
+
 #[query]
 fn __get_candid_interface_tmp_hack() -> &'static str {
     include_str!(env!("ARCHIVE_DID_PATH"))
@@ -2,9 +2,14 @@ use candid::{CandidType, Nat};
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_canister_profiler::{measure_span, SpanStats};
 use ic_cdk::api::stable::{StableReader, StableWriter};
-use icrc_ledger_types::icrc3::archive::QueryTxArchiveFn;
+use ic_icrc1::{blocks::encoded_block_from_generic_block, Block};
+use ic_ledger_core::block::BlockType;
+use icrc_ledger_types::icrc3::archive::{QueryBlockArchiveFn, QueryTxArchiveFn};
+use icrc_ledger_types::icrc3::blocks::{
+    BlockRange, GenericBlock, GetBlocksRequest, GetBlocksResponse,
+};
 use icrc_ledger_types::icrc3::transactions::{
-    GetTransactionsResponse, Transaction, TransactionRange, Transfer,
+    Approve, Burn, GetTransactionsResponse, Transaction, TransactionRange, Transfer,
 };
 use icrc_ledger_types::{
     icrc1::account::Account, icrc1::account::Subaccount, icrc3::archive::ArchivedRange,
@@ -14,7 +19,7 @@ use num_traits::cast::ToPrimitive;
 use scopeguard::{guard, ScopeGuard};
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
-use std::collections::{btree_map, BTreeMap};
+use std::collections::{btree_map, BTreeMap, BTreeSet};
 use std::ops::Bound::{Included, Unbounded};
 use std::time::Duration;
 
@@ -26,6 +31,10 @@ const MAX_SUBACCOUNTS_PER_RESPONSE: usize = 1000;
 // by [get_account_transactions]
 const MAX_TRANSACTIONS_PER_RESPONSE: usize = 1000;
 
+// Maximum number of accounts whose balance is compared with
+// the Ledger after each round of indexing
+const MAX_ACCOUNTS_TO_VERIFY: usize = 10;
+
 // One second in nanosecond
 const SEC_NANOS: u64 = 1_000_000_000;
 const DEFAULT_MAX_WAIT_TIME_NANOS: u64 = 60_u64 * SEC_NANOS;
@@ -51,6 +60,21 @@ struct Index {
 
     // The number of unique (principal, subaccount) pairs in the index.
     pub accounts_num: u64,
+
+    // The balance of each indexed account computed from the indexed transactions.
+    // The field is missing in the state of Index canisters that were
+    // upgraded from a version that did not track balances.
+    #[serde(default)]
+    pub balances: BTreeMap<PrincipalId, BTreeMap<Subaccount, u64>>,
+
+    // The fee collectors set by blocks with a `fee_col` field, by block index.
+    // Later blocks refer to them through their `fee_col_block` field.
+    #[serde(default)]
+    pub fee_collectors: BTreeMap<u64, Account>,
+
+    // The number of balances that did not match the ones reported by the Ledger.
+    #[serde(default)]
+    pub balance_mismatches: u64,
 }
 
 impl Index {
@@ -61,6 +85,57 @@ impl Index {
             is_build_index_running: false,
             account_index: BTreeMap::new(),
             accounts_num: 0,
+            balances: BTreeMap::new(),
+            fee_collectors: BTreeMap::new(),
+            balance_mismatches: 0,
+        }
+    }
+
+    fn balance_mut(&mut self, account: &Account) -> &mut u64 {
+        self.balances
+            .entry(account.owner.into())
+            .or_default()
+            .entry(*account.effective_subaccount())
+            .or_insert(0)
+    }
+
+    fn credit(&mut self, account: &Account, amount: u64) -> Result<(), String> {
+        let balance = self.balance_mut(account);
+        *balance = balance
+            .checked_add(amount)
+            .ok_or_else(|| format!("The balance of account {} overflows", account))?;
+        Ok(())
+    }
+
+    fn debit(&mut self, account: &Account, amount: u64) -> Result<(), String> {
+        let balance = self.balance_mut(account);
+        *balance = balance.checked_sub(amount).ok_or_else(|| {
+            format!(
+                "The account {} has balance {} which is less than {}",
+                account, balance, amount
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Returns the account that collects the fee of the block `txid`.
+    fn fee_collector(&mut self, txid: u64, block: &Block) -> Result<Option<Account>, String> {
+        if let Some(fee_collector) = block.fee_collector {
+            self.fee_collectors.insert(txid, fee_collector);
+            return Ok(Some(fee_collector));
+        }
+        match block.fee_collector_block_index {
+            None => Ok(None),
+            Some(fee_collector_block_index) => self
+                .fee_collectors
+                .get(&fee_collector_block_index)
+                .map(|fee_collector| Some(*fee_collector))
+                .ok_or_else(|| {
+                    format!(
+                        "Block {} refers to the fee collector of block {} which sets none",
+                        txid, fee_collector_block_index
+                    )
+                }),
         }
     }
 }
@@ -133,6 +208,18 @@ pub struct ListSubaccountsArgs {
     pub start: Option<Subaccount>,
 }
 
+/// Returns the balance of the account computed from the transactions indexed
+/// so far.
+pub fn icrc1_balance_of(account: Account) -> Nat {
+    with_index(|idx| {
+        idx.balances
+            .get(&account.owner.into())
+            .and_then(|subaccounts| subaccounts.get(account.effective_subaccount()))
+            .map(|balance| Nat::from(*balance))
+            .unwrap_or_else(|| Nat::from(0u64))
+    })
+}
+
 pub fn list_subaccounts(list_subaccounts_args: ListSubaccountsArgs) -> Vec<Subaccount> {
     with_index(
         |idx| match idx.account_index.get(&list_subaccounts_args.owner) {
@@ -168,6 +255,43 @@ async fn get_transactions_from_ledger(
     Ok(res)
 }
 
+async fn get_blocks_from_ledger(start: u64, length: usize) -> Result<GetBlocksResponse, String> {
+    let ledger_id = ledger_id();
+    let req = GetBlocksRequest {
+        start: Nat::from(start),
+        length: Nat::from(length),
+    };
+    let (res,): (GetBlocksResponse,) = ic_cdk::call(ledger_id.get().0, "get_blocks", (req,))
+        .await
+        .map_err(|(code, str)| format!("code: {:#?} message: {}", code, str))?;
+    Ok(res)
+}
+
+async fn get_blocks_from_archive(
+    archived: &ArchivedRange<QueryBlockArchiveFn>,
+) -> Result<BlockRange, String> {
+    let req = GetBlocksRequest {
+        start: archived.start.clone(),
+        length: archived.length.clone(),
+    };
+    let (res,): (BlockRange,) = ic_cdk::call(
+        archived.callback.canister_id,
+        &archived.callback.method,
+        (req,),
+    )
+    .await
+    .map_err(|(code, str)| format!("code: {:#?} message: {}", code, str))?;
+    Ok(res)
+}
+
+async fn get_balance_from_ledger(account: Account) -> Result<Nat, String> {
+    let ledger_id = ledger_id();
+    let (res,): (Nat,) = ic_cdk::call(ledger_id.get().0, "icrc1_balance_of", (account,))
+        .await
+        .map_err(|(code, str)| format!("code: {:#?} message: {}", code, str))?;
+    Ok(res)
+}
+
 async fn get_transactions_from_archive(
     archived: &ArchivedRange<QueryTxArchiveFn>,
 ) -> Result<TransactionRange, String> {
@@ -203,27 +327,34 @@ pub async fn build_index() -> Result<(), String> {
         });
     });
     let next_txid = with_index(|idx| idx.next_txid);
-    let res = get_transactions_from_ledger(next_txid, MAX_TRANSACTIONS_PER_RESPONSE).await?;
+    let res = get_blocks_from_ledger(next_txid, MAX_TRANSACTIONS_PER_RESPONSE).await?;
     let mut tx_indexed_cout: usize = 0;
-    for archived in res.archived_transactions {
+    let mut touched_accounts = BTreeSet::new();
+    for archived in res.archived_blocks {
         // The archive node limits the number of transactions returned by a
         // single get_transaction call.
         let last_txid = archived.start.clone() + archived.length.clone();
         let mut next_archived_txid = archived.start.clone();
         while next_archived_txid < last_txid {
-            let archived = ArchivedRange::<QueryTxArchiveFn> {
-                start: next_archived_txid,
-                length: archived.length.clone(),
+            let archived = ArchivedRange::<QueryBlockArchiveFn> {
+                start: next_archived_txid.clone(),
+                length: last_txid.clone() - next_archived_txid,
                 callback: archived.callback.clone(),
             };
-            let res = get_transactions_from_archive(&archived).await?;
+            let res = get_blocks_from_archive(&archived).await?;
+            if res.blocks.is_empty() {
+                return Err(format!(
+                    "The archive {} returned no transactions for the range starting at {}",
+                    archived.callback.canister_id, archived.start
+                ));
+            }
             let mut idx = archived
                 .start
                 .0
                 .to_u64()
                 .ok_or("The Ledger returned an index that is not a valid u64")?;
-            for transaction in res.transactions {
-                index_transaction(idx, transaction)?;
+            for block in res.blocks {
+                touched_accounts.extend(index_block(idx, block)?);
                 idx += 1;
                 tx_indexed_cout += 1;
             }
@@ -235,11 +366,20 @@ pub async fn build_index() -> Result<(), String> {
         .0
         .to_u64()
         .ok_or("The Ledger returned an index that is not a valid u64")?;
-    for transaction in res.transactions {
-        index_transaction(idx, transaction)?;
+    for block in res.blocks {
+        touched_accounts.extend(index_block(idx, block)?);
         idx += 1;
         tx_indexed_cout += 1;
     }
+    if idx == res.chain_length {
+        verify_balances(
+            touched_accounts
+                .into_iter()
+                .take(MAX_ACCOUNTS_TO_VERIFY)
+                .collect(),
+        )
+        .await?;
+    }
     let wait_time: u64 = compute_wait_time(tx_indexed_cout);
     ic_cdk::eprintln!(
         "{}Indexed: {} waiting : {}",
@@ -270,36 +410,140 @@ pub fn compute_wait_time(indexed_tx_count: usize) -> u64 {
         * DEFAULT_MAX_WAIT_TIME_NANOS as f64) as u64
 }
 
-fn index_transaction(txid: u64, transaction: Transaction) -> Result<(), String> {
+/// Compares the balances of the given accounts computed by the index with the
+/// ones reported by the Ledger.
+///
+/// The comparison only happens if the Ledger did not produce new blocks while
+/// the balances were fetched, so that both sides see the same chain. A
+/// mismatch is logged and counted, but it doesn't stop the indexing.
+async fn verify_balances(accounts: Vec<Account>) -> Result<(), String> {
+    let mut ledger_balances = Vec::with_capacity(accounts.len());
+    for account in accounts {
+        ledger_balances.push((account, get_balance_from_ledger(account).await?));
+    }
+    let chain_length = get_blocks_from_ledger(0, 0).await?.chain_length;
+    if chain_length != with_index(|idx| idx.next_txid) {
+        return Ok(());
+    }
+    for (account, ledger_balance) in ledger_balances {
+        let index_balance = icrc1_balance_of(account);
+        if index_balance != ledger_balance {
+            ic_cdk::eprintln!(
+                "{}The balance of account {} is {} but the Ledger reports {}",
+                LOG_PREFIX,
+                account,
+                index_balance,
+                ledger_balance
+            );
+            with_index_mut(|idx| idx.balance_mismatches += 1);
+        }
+    }
+    Ok(())
+}
+
+fn index_block(txid: u64, block: GenericBlock) -> Result<Vec<Account>, String> {
+    let block = encoded_block_from_generic_block(block)
+        .and_then(Block::decode)
+        .map_err(|e| format!("Unable to decode block {}: {}", txid, e))?;
+    let fee_collector = with_index_mut(|idx| idx.fee_collector(txid, &block))?;
+    index_transaction(txid, Transaction::from(block), fee_collector)
+}
+
+/// Indexes the transaction `txid` and returns the accounts whose balance
+/// it changed.
+fn index_transaction(
+    txid: u64,
+    transaction: Transaction,
+    fee_collector: Option<Account>,
+) -> Result<Vec<Account>, String> {
     match transaction.kind.as_str() {
         "mint" => {
             let mint = transaction
                 .mint
-                .ok_or("Got a transaction with kind 'mint' but the mint field was None")?
-                .to;
-            add_tx(txid, mint);
-            Ok(())
+                .ok_or("Got a transaction with kind 'mint' but the mint field was None")?;
+            let amount = tokens_to_u64(txid, &mint.amount)?;
+            with_index_mut(|idx| idx.credit(&mint.to, amount))?;
+            add_tx(txid, mint.to);
+            Ok(vec![mint.to])
         }
         "burn" => {
-            let burn = transaction
+            let Burn {
+                from,
+                spender,
+                amount,
+                ..
+            } = transaction
                 .burn
-                .ok_or("Got a transaction with kind 'burn' but the burn field was None")?
-                .from;
-            add_tx(txid, burn);
-            Ok(())
+                .ok_or("Got a transaction with kind 'burn' but the burn field was None")?;
+            let amount = tokens_to_u64(txid, &amount)?;
+            with_index_mut(|idx| idx.debit(&from, amount))?;
+            add_tx(txid, from);
+            if let Some(spender) = spender.filter(|spender| spender != &from) {
+                add_tx(txid, spender);
+            }
+            Ok(vec![from])
         }
         "transfer" => {
-            let Transfer { from, to, .. } = transaction
+            let Transfer {
+                from,
+                to,
+                spender,
+                amount,
+                fee,
+                ..
+            } = transaction
                 .transfer
                 .ok_or("Got a transaction with kind 'transfer' but the transfer field was None")?;
+            let amount = tokens_to_u64(txid, &amount)?;
+            let fee = fee
+                .map(|fee| tokens_to_u64(txid, &fee))
+                .transpose()?
+                .unwrap_or(0);
+            with_index_mut(|idx| {
+                idx.debit(&from, amount.saturating_add(fee))?;
+                idx.credit(&to, amount)?;
+                match &fee_collector {
+                    Some(fee_collector) => idx.credit(fee_collector, fee),
+                    None => Ok(()),
+                }
+            })?;
             add_tx(txid, from);
             add_tx(txid, to);
-            Ok(())
+            if let Some(spender) = spender.filter(|spender| spender != &from && spender != &to) {
+                add_tx(txid, spender);
+            }
+            Ok([Some(from), Some(to), fee_collector]
+                .into_iter()
+                .flatten()
+                .collect())
+        }
+        "approve" => {
+            let Approve {
+                from, spender, fee, ..
+            } = transaction
+                .approve
+                .ok_or("Got a transaction with kind 'approve' but the approve field was None")?;
+            if let Some(fee) = fee {
+                let fee = tokens_to_u64(txid, &fee)?;
+                with_index_mut(|idx| idx.debit(&from, fee))?;
+            }
+            add_tx(txid, from);
+            add_tx(txid, spender);
+            Ok(vec![from])
         }
         kind => Err(format!("Found transaction of unknown kind {}", kind)),
     }
 }
 
+fn tokens_to_u64(txid: u64, amount: &Nat) -> Result<u64, String> {
+    amount.0.to_u64().ok_or_else(|| {
+        format!(
+            "The amount {} in transaction {} is not a valid u64",
+            amount, txid
+        )
+    })
+}
+
 fn add_tx(txid: u64, account: Account) {
     measure_span(&PROFILING_DATA, "add_tx", move || {
         with_index_mut(|idx| {
@@ -385,6 +629,7 @@ pub async fn get_account_transactions(args: GetAccountTransactionsArgs) -> GetTr
                         transaction: tx,
                     })
                 } else if let Some(archive) = res.archived_transactions.get(0) {
+                    // The ledger might have archived the transaction after we indexed it.
                     match get_transactions_from_archive(archive).await {
                         Ok(res) if !res.transactions.is_empty() => txs.push(TransactionWithId {
                             id: Nat::from(*txid),
@@ -404,6 +649,13 @@ pub async fn get_account_transactions(args: GetAccountTransactionsArgs) -> GetTr
                             return Err(GetTransactionsErr { message });
                         }
                     }
+                } else {
+                    let message = format!(
+                        "Error fetching transaction {}: the ledger returned neither the transaction nor an archive",
+                        txid
+                    );
+                    ic_cdk::eprintln!("{}{}", LOG_PREFIX, message);
+                    return Err(GetTransactionsErr { message });
                 }
             }
             Err(e) => {
@@ -460,6 +712,11 @@ pub fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> st
         with_index(|idx| idx.accounts_num) as f64,
         "Total number of accounts indexed.",
     )?;
+    w.encode_counter(
+        "index_balance_mismatches",
+        with_index(|idx| idx.balance_mismatches) as f64,
+        "Total number of account balances that did not match the Ledger.",
+    )?;
     PROFILING_DATA.with(|cell| -> std::io::Result<()> {
         cell.borrow().record_metrics(w.histogram_vec(
             "index_profile_instructions",
@@ -478,12 +735,18 @@ pub fn pre_upgrade() {
 
 pub fn post_upgrade() {
     ic_cdk::println!("Running post-upgrade on index canister...");
-    INDEX.with(|idx| {
-        *idx.borrow_mut() = Some(
-            ciborium::de::from_reader(StableReader::default())
-                .expect("failed to decode index state"),
-        )
-    });
+    let mut index: Index =
+        ciborium::de::from_reader(StableReader::default()).expect("failed to decode index state");
+    if index.next_txid > 0 && index.balances.is_empty() {
+        // The previous version of the Index did not track balances.
+        // Rebuild the index from scratch to compute them.
+        ic_cdk::println!("The index state has no balances, reindexing all transactions...");
+        index.next_txid = 0;
+        index.account_index.clear();
+        index.accounts_num = 0;
+        index.fee_collectors.clear();
+    }
+    INDEX.with(|idx| *idx.borrow_mut() = Some(index));
 }
 
 #[cfg(test)]
@@ -496,8 +759,11 @@ mod tests {
 
     use proptest::{option, proptest};
 
+    use icrc_ledger_types::icrc3::transactions::{Mint, Transaction, Transfer};
+
     use crate::{
-        add_tx, get_account_transactions_ids, with_index, GetAccountTransactionsArgs, Index, INDEX,
+        add_tx, get_account_transactions_ids, icrc1_balance_of, index_transaction, with_index,
+        GetAccountTransactionsArgs, Index, INDEX,
     };
 
     fn account(n: u64) -> Account {
@@ -527,6 +793,9 @@ mod tests {
                 is_build_index_running: false,
                 account_index,
                 accounts_num: 0,
+                balances: BTreeMap::new(),
+                fee_collectors: BTreeMap::new(),
+                balance_mismatches: 0,
             });
         });
     }
@@ -659,6 +928,87 @@ mod tests {
         }
     }
 
+    fn mint_tx(to: Account, amount: u64) -> Transaction {
+        Transaction {
+            kind: "mint".to_string(),
+            mint: Some(Mint {
+                amount: Nat::from(amount),
+                to,
+                memo: None,
+                created_at_time: None,
+            }),
+            burn: None,
+            transfer: None,
+            approve: None,
+            timestamp: 0,
+        }
+    }
+
+    fn transfer_tx(from: Account, to: Account, amount: u64, fee: u64) -> Transaction {
+        Transaction {
+            kind: "transfer".to_string(),
+            mint: None,
+            burn: None,
+            transfer: Some(Transfer {
+                amount: Nat::from(amount),
+                from,
+                to,
+                spender: None,
+                memo: None,
+                created_at_time: None,
+                fee: Some(Nat::from(fee)),
+            }),
+            approve: None,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn balances() {
+        init_state(vec![]);
+
+        index_transaction(0, mint_tx(account(1), 1_000), None).unwrap();
+        assert_eq!(icrc1_balance_of(account(1)), Nat::from(1_000u64));
+        assert_eq!(icrc1_balance_of(account(2)), Nat::from(0u64));
+
+        index_transaction(1, transfer_tx(account(1), account(2), 100, 10), None).unwrap();
+        assert_eq!(icrc1_balance_of(account(1)), Nat::from(890u64));
+        assert_eq!(icrc1_balance_of(account(2)), Nat::from(100u64));
+
+        // A transfer exceeding the balance leaves the index unchanged.
+        assert!(index_transaction(2, transfer_tx(account(2), account(1), 100, 10), None).is_err());
+        assert_eq!(icrc1_balance_of(account(1)), Nat::from(890u64));
+        assert_eq!(icrc1_balance_of(account(2)), Nat::from(100u64));
+        assert_eq!(with_index(|idx| idx.next_txid), 2);
+    }
+
+    #[test]
+    fn balances_with_fee_collector() {
+        init_state(vec![]);
+
+        index_transaction(0, mint_tx(account(1), 1_000), None).unwrap();
+        let touched = index_transaction(
+            1,
+            transfer_tx(account(1), account(2), 100, 10),
+            Some(account(3)),
+        )
+        .unwrap();
+        assert_eq!(touched, vec![account(1), account(2), account(3)]);
+        assert_eq!(icrc1_balance_of(account(1)), Nat::from(890u64));
+        assert_eq!(icrc1_balance_of(account(2)), Nat::from(100u64));
+        assert_eq!(icrc1_balance_of(account(3)), Nat::from(10u64));
+
+        // The fee collector can spend the collected fees.
+        index_transaction(
+            2,
+            transfer_tx(account(3), account(1), 5, 5),
+            Some(account(3)),
+        )
+        .unwrap();
+        assert_eq!(icrc1_balance_of(account(1)), Nat::from(895u64));
+        assert_eq!(icrc1_balance_of(account(3)), Nat::from(5u64));
+    }
+
     #[test]
     fn account_num() {
         init_state(vec![]);
@@ -1,4 +1,5 @@
 use candid::candid_method;
+use candid::Nat;
 use ic_base_types::CanisterId;
 use ic_canisters_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
 use ic_cdk_macros::{init, post_upgrade, pre_upgrade, query, update};
@@ -6,7 +7,7 @@ use ic_icrc1_index::{
     encode_metrics, GetAccountTransactionsArgs, GetTransactionsResult, InitArgs,
     ListSubaccountsArgs,
 };
-use icrc_ledger_types::icrc1::account::Subaccount;
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use std::time::Duration;
 
 fn main() {}
@@ -33,6 +34,12 @@ fn list_subaccounts(args: ListSubaccountsArgs) -> Vec<Subaccount> {
     ic_icrc1_index::list_subaccounts(args)
 }
 
+#[query]
+#[candid_method(query)]
+fn icrc1_balance_of(account: Account) -> Nat {
+    ic_icrc1_index::icrc1_balance_of(account)
+}
+
 #[query]
 #[candid_method(query)]
 fn ledger_id() -> CanisterId {
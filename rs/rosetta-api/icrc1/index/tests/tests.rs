@@ -98,6 +98,15 @@ fn install_ledger(
     env: &StateMachine,
     initial_balances: Vec<(Account, u64)>,
     archive_options: ArchiveOptions,
+) -> CanisterId {
+    install_ledger_with_fee_collector(env, initial_balances, archive_options, None)
+}
+
+fn install_ledger_with_fee_collector(
+    env: &StateMachine,
+    initial_balances: Vec<(Account, u64)>,
+    archive_options: ArchiveOptions,
+    fee_collector_account: Option<Account>,
 ) -> CanisterId {
     let args = LedgerArgument::Init(LedgerInitArgs {
         minting_account: MINTER,
@@ -112,7 +121,7 @@ fn install_ledger(
             Value::entry(BLOB_META_KEY, BLOB_META_VALUE),
         ],
         archive_options,
-        fee_collector_account: None,
+        fee_collector_account,
     });
     env.install_canister(ledger_wasm(), Encode!(&args).unwrap(), None)
         .unwrap()
@@ -277,6 +286,35 @@ fn list_subaccounts(
     .expect("failed to decode list_subaccounts response")
 }
 
+fn icrc1_balance_of(env: &StateMachine, canister_id: CanisterId, account: Account) -> u64 {
+    Decode!(
+        &env.query(canister_id, "icrc1_balance_of", Encode!(&account).unwrap())
+            .expect("failed to query icrc1_balance_of")
+            .bytes(),
+        Nat
+    )
+    .expect("failed to decode icrc1_balance_of response")
+    .0
+    .to_u64()
+    .unwrap()
+}
+
+fn assert_index_balances_match_ledger(
+    env: &StateMachine,
+    ledger_id: CanisterId,
+    index_id: CanisterId,
+    accounts: &[Account],
+) {
+    for account in accounts {
+        assert_eq!(
+            icrc1_balance_of(env, ledger_id, *account),
+            icrc1_balance_of(env, index_id, *account),
+            "balance mismatch for account {}",
+            account
+        );
+    }
+}
+
 fn index_ledger_id(env: &StateMachine, index: CanisterId) -> CanisterId {
     Decode!(
         &env.query(index, "ledger_id", Encode!().unwrap())
@@ -568,3 +606,98 @@ fn test_index_archived_txs_paging() {
     let expected_txids: Vec<u64> = (0..ARCHIVE_TRIGGER_THRESHOLD).rev().collect();
     assert_eq!(expected_txids, actual_txids);
 }
+
+#[test]
+fn test_balances() {
+    let env = StateMachine::new();
+    let ledger_id = install_ledger(
+        &env,
+        vec![],
+        ArchiveOptions {
+            node_max_memory_size_bytes: Some(
+                MINT_BLOCKS_PER_ARCHIVE * mint_block().size_bytes() as u64,
+            ),
+            ..default_archive_options()
+        },
+    );
+    let index_id = install_index(&env, ledger_id);
+
+    let accounts = [
+        account(1),
+        account(2),
+        account_with_subaccount(1, 1),
+        account_with_subaccount(2, 2),
+    ];
+
+    mint(&env, ledger_id, accounts[0], 1_000_000);
+    mint(&env, ledger_id, accounts[1], 2_000_000);
+    for i in 0..ARCHIVE_TRIGGER_THRESHOLD {
+        transfer(&env, ledger_id, accounts[0], accounts[2], 1_000 + i);
+        transfer(&env, ledger_id, accounts[1], accounts[3], 2_000 + i);
+    }
+    burn(&env, ledger_id, accounts[2], FEE);
+    assert!(!archives(&env, ledger_id).is_empty());
+
+    for _ in 0..10 {
+        env.advance_time(Duration::from_secs(60));
+        env.tick();
+    }
+    assert_index_balances_match_ledger(&env, ledger_id, index_id, &accounts);
+
+    assert_eq!(
+        list_subaccounts(&env, index_id, account(1), None),
+        vec![[0; 32], *accounts[2].effective_subaccount()]
+    );
+
+    // The balances survive upgrades.
+    env.upgrade_canister(index_id, index_wasm(), vec![])
+        .expect("Failed to upgrade the Index canister");
+    assert_index_balances_match_ledger(&env, ledger_id, index_id, &accounts);
+
+    transfer(&env, ledger_id, accounts[3], accounts[0], 1);
+    env.advance_time(Duration::from_secs(60));
+    env.tick();
+    assert_index_balances_match_ledger(&env, ledger_id, index_id, &accounts);
+}
+
+#[test]
+fn test_balances_with_fee_collector() {
+    let env = StateMachine::new();
+    let fee_collector = account(3);
+    let ledger_id = install_ledger_with_fee_collector(
+        &env,
+        vec![],
+        ArchiveOptions {
+            node_max_memory_size_bytes: Some(
+                MINT_BLOCKS_PER_ARCHIVE * mint_block().size_bytes() as u64,
+            ),
+            ..default_archive_options()
+        },
+        Some(fee_collector),
+    );
+    let index_id = install_index(&env, ledger_id);
+
+    let accounts = [account(1), account(2), fee_collector];
+
+    mint(&env, ledger_id, accounts[0], 1_000_000);
+    for i in 0..ARCHIVE_TRIGGER_THRESHOLD {
+        transfer(&env, ledger_id, accounts[0], accounts[1], 1_000 + i);
+    }
+    assert!(!archives(&env, ledger_id).is_empty());
+
+    // The fee collector spends part of the collected fees.
+    transfer(&env, ledger_id, fee_collector, accounts[1], FEE);
+    burn(&env, ledger_id, fee_collector, FEE);
+
+    for _ in 0..10 {
+        env.advance_time(Duration::from_secs(60));
+        env.tick();
+    }
+    assert_index_balances_match_ledger(&env, ledger_id, index_id, &accounts);
+
+    // The Index keeps indexing the blocks after the ones spending the fees.
+    transfer(&env, ledger_id, accounts[1], accounts[0], 1);
+    env.advance_time(Duration::from_secs(60));
+    env.tick();
+    assert_index_balances_match_ledger(&env, ledger_id, index_id, &accounts);
+}
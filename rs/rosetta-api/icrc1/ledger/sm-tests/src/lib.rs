@@ -1,5 +1,6 @@
 use candid::{CandidType, Decode, Encode, Nat, Principal};
 use ic_base_types::PrincipalId;
+use ic_crypto_tree_hash::{LookupStatus, MixedHashTree};
 use ic_icrc1::{endpoints::StandardRecord, hash::Hash, Block, Operation, Transaction};
 use ic_ledger_canister_core::archive::ArchiveOptions;
 use ic_ledger_core::block::{BlockIndex, BlockType, HashOf};
@@ -7,10 +8,14 @@ use ic_state_machine_tests::{CanisterId, ErrorCode, StateMachine};
 use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue as Value;
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use icrc_ledger_types::icrc1::transfer::{Memo, TransferArg, TransferError};
+use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
+use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
+use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
 use icrc_ledger_types::icrc3::archive::ArchiveInfo;
 use icrc_ledger_types::icrc3::blocks::BlockRange;
 use icrc_ledger_types::icrc3::blocks::GenericBlock as IcrcBlock;
 use icrc_ledger_types::icrc3::blocks::GetBlocksResponse;
+use icrc_ledger_types::icrc3::blocks::{GetBlocksRequest, GetBlocksResult, ICRC3DataCertificate};
 use icrc_ledger_types::icrc3::transactions::GetTransactionsRequest;
 use icrc_ledger_types::icrc3::transactions::GetTransactionsResponse;
 use icrc_ledger_types::icrc3::transactions::Transaction as Tx;
@@ -201,6 +206,98 @@ fn transfer(
     )
 }
 
+fn send_approval(
+    env: &StateMachine,
+    ledger: CanisterId,
+    from: Principal,
+    arg: &ApproveArgs,
+) -> Result<BlockIndex, ApproveError> {
+    Decode!(
+        &env.execute_ingress_as(
+            PrincipalId(from),
+            ledger,
+            "icrc2_approve",
+            Encode!(arg)
+            .unwrap()
+        )
+        .expect("failed to approve funds")
+        .bytes(),
+        Result<Nat, ApproveError>
+    )
+    .expect("failed to decode approve response")
+    .map(|n| n.0.to_u64().unwrap())
+}
+
+fn send_transfer_from(
+    env: &StateMachine,
+    ledger: CanisterId,
+    spender: Principal,
+    arg: &TransferFromArgs,
+) -> Result<BlockIndex, TransferFromError> {
+    Decode!(
+        &env.execute_ingress_as(
+            PrincipalId(spender),
+            ledger,
+            "icrc2_transfer_from",
+            Encode!(arg)
+            .unwrap()
+        )
+        .expect("failed to transfer funds")
+        .bytes(),
+        Result<Nat, TransferFromError>
+    )
+    .expect("failed to decode transfer_from response")
+    .map(|n| n.0.to_u64().unwrap())
+}
+
+fn get_allowance(
+    env: &StateMachine,
+    ledger: CanisterId,
+    account: impl Into<Account>,
+    spender: impl Into<Account>,
+) -> Allowance {
+    let arg = AllowanceArgs {
+        account: account.into(),
+        spender: spender.into(),
+    };
+    Decode!(
+        &env.query(ledger, "icrc2_allowance", Encode!(&arg).unwrap())
+            .expect("failed to query the allowance")
+            .bytes(),
+        Allowance
+    )
+    .expect("failed to decode allowance response")
+}
+
+fn default_approve_args(spender: impl Into<Account>, amount: u64) -> ApproveArgs {
+    ApproveArgs {
+        from_subaccount: None,
+        spender: spender.into(),
+        amount: Nat::from(amount),
+        expected_allowance: None,
+        expires_at: None,
+        fee: None,
+        memo: None,
+        created_at_time: None,
+    }
+}
+
+fn default_transfer_from_args(
+    from: impl Into<Account>,
+    to: impl Into<Account>,
+    amount: u64,
+) -> TransferFromArgs {
+    TransferFromArgs {
+        spender_subaccount: None,
+        from: from.into(),
+        to: to.into(),
+        amount: Nat::from(amount),
+        fee: None,
+        memo: None,
+        created_at_time: None,
+    }
+}
+
 fn list_archives(env: &StateMachine, ledger: CanisterId) -> Vec<ArchiveInfo> {
     Decode!(
         &env.query(ledger, "archives", Encode!().unwrap())
@@ -396,21 +493,70 @@ fn arb_transfer() -> impl Strategy<Value = Operation> {
         .prop_map(|(from, to, amount, fee)| Operation::Transfer {
             from,
             to,
+            spender: None,
+            amount,
+            fee,
+        })
+}
+
+fn arb_transfer_from() -> impl Strategy<Value = Operation> {
+    (
+        arb_account(),
+        arb_account(),
+        arb_account(),
+        arb_amount(),
+        proptest::option::of(arb_amount()),
+    )
+        .prop_map(|(from, to, spender, amount, fee)| Operation::Transfer {
+            from,
+            to,
+            spender: Some(spender),
             amount,
             fee,
         })
 }
 
+fn arb_approve() -> impl Strategy<Value = Operation> {
+    (
+        arb_account(),
+        arb_account(),
+        arb_amount(),
+        proptest::option::of(arb_amount()),
+        proptest::option::of(any::<u64>()),
+        proptest::option::of(arb_amount()),
+    )
+        .prop_map(
+            |(from, spender, amount, expected_allowance, expires_at, fee)| Operation::Approve {
+                from,
+                spender,
+                amount,
+                expected_allowance,
+                expires_at,
+                fee,
+            },
+        )
+}
+
 fn arb_mint() -> impl Strategy<Value = Operation> {
     (arb_account(), arb_amount()).prop_map(|(to, amount)| Operation::Mint { to, amount })
 }
 
 fn arb_burn() -> impl Strategy<Value = Operation> {
-    (arb_account(), arb_amount()).prop_map(|(from, amount)| Operation::Burn { from, amount })
+    (arb_account(), arb_amount()).prop_map(|(from, amount)| Operation::Burn {
+        from,
+        spender: None,
+        amount,
+    })
 }
 
 fn arb_operation() -> impl Strategy<Value = Operation> {
-    prop_oneof![arb_transfer(), arb_mint(), arb_burn()]
+    prop_oneof![
+        arb_transfer(),
+        arb_transfer_from(),
+        arb_mint(),
+        arb_burn(),
+        arb_approve()
+    ]
 }
 
 fn arb_transaction() -> impl Strategy<Value = Transaction> {
@@ -1154,6 +1300,7 @@ pub fn test_archiving<T>(
                 owner: p2.0,
                 subaccount: None,
             },
+            spender: None,
             amount: Nat::from(10_000 + i - 1),
             fee: Some(Nat::from(FEE)),
             memo: None,
@@ -1191,6 +1338,7 @@ pub fn test_archiving<T>(
                     owner: p2.0,
                     subaccount: None
                 },
+                spender: None,
                 amount: Nat::from(10_000 + i - 1),
                 fee: Some(Nat::from(FEE)),
                 memo: None,
@@ -1282,6 +1430,148 @@ where
     assert_eq!(0, missing_blocks_reply.archived_blocks.len());
 }
 
+fn icrc3_get_blocks(
+    env: &StateMachine,
+    canister_id: Principal,
+    method: &str,
+    ranges: Vec<(u64, u64)>,
+) -> GetBlocksResult {
+    let args: Vec<GetBlocksRequest> = ranges
+        .into_iter()
+        .map(|(start, length)| GetBlocksRequest {
+            start: Nat::from(start),
+            length: Nat::from(length),
+        })
+        .collect();
+    Decode!(
+        &env.query(
+            CanisterId::new(PrincipalId(canister_id)).unwrap(),
+            method,
+            Encode!(&args).unwrap()
+        )
+        .expect("failed to query icrc3_get_blocks")
+        .bytes(),
+        GetBlocksResult
+    )
+    .expect("failed to decode icrc3_get_blocks response")
+}
+
+pub fn test_icrc3_get_blocks<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
+where
+    T: CandidType,
+{
+    let p1 = PrincipalId::new_user_test_id(1);
+    let p2 = PrincipalId::new_user_test_id(2);
+
+    let (env, canister_id) = setup(
+        ledger_wasm,
+        encode_init_args,
+        vec![(Account::from(p1.0), 10_000_000)],
+    );
+
+    for i in 0..ARCHIVE_TRIGGER_THRESHOLD {
+        transfer(&env, canister_id, p1.0, p2.0, 10_000 + i * 10_000).expect("transfer failed");
+    }
+
+    env.run_until_completion(/*max_ticks=*/ 10);
+
+    let chain_length = ARCHIVE_TRIGGER_THRESHOLD + 1;
+    let resp = icrc3_get_blocks(
+        &env,
+        canister_id.get().0,
+        "icrc3_get_blocks",
+        vec![(0, 1_000_000)],
+    );
+    assert_eq!(resp.log_length, Nat::from(chain_length));
+    assert_eq!(
+        resp.blocks.len(),
+        (chain_length - NUM_BLOCKS_TO_ARCHIVE) as usize
+    );
+    assert_eq!(resp.archived_blocks.len(), 1);
+    assert_eq!(
+        resp.archived_blocks[0].args,
+        vec![GetBlocksRequest {
+            start: Nat::from(0),
+            length: Nat::from(NUM_BLOCKS_TO_ARCHIVE),
+        }]
+    );
+
+    let archived = &resp.archived_blocks[0];
+    let archived_resp = icrc3_get_blocks(
+        &env,
+        archived.callback.canister_id,
+        &archived.callback.method,
+        archived
+            .args
+            .iter()
+            .map(|arg| arg.as_start_and_length().unwrap())
+            .collect(),
+    );
+    assert!(archived_resp.archived_blocks.is_empty());
+    assert_eq!(archived_resp.blocks.len(), NUM_BLOCKS_TO_ARCHIVE as usize);
+
+    // Check that the block ids are contiguous and the hash chain is correct.
+    let mut prev_hash = None;
+    for (i, block) in archived_resp
+        .blocks
+        .into_iter()
+        .chain(resp.blocks.into_iter())
+        .enumerate()
+    {
+        assert_eq!(block.id, Nat::from(i));
+        assert_eq!(
+            prev_hash,
+            get_phash(&block.block).expect("cannot get the hash of the previous block")
+        );
+        prev_hash = Some(block.block.hash());
+    }
+
+    // The ledger serves multiple ranges in the request order.
+    let resp = icrc3_get_blocks(
+        &env,
+        canister_id.get().0,
+        "icrc3_get_blocks",
+        vec![(chain_length - 1, 1), (NUM_BLOCKS_TO_ARCHIVE, 1), (100, 5)],
+    );
+    assert_eq!(
+        resp.blocks.iter().map(|b| b.id.clone()).collect::<Vec<_>>(),
+        vec![
+            Nat::from(chain_length - 1),
+            Nat::from(NUM_BLOCKS_TO_ARCHIVE)
+        ]
+    );
+    assert!(resp.archived_blocks.is_empty());
+
+    let certificate = Decode!(
+        &env.query(canister_id, "icrc3_get_tip_certificate", Encode!().unwrap())
+            .expect("failed to query icrc3_get_tip_certificate")
+            .bytes(),
+        Option<ICRC3DataCertificate>
+    )
+    .expect("failed to decode icrc3_get_tip_certificate response")
+    .expect("the tip certificate is missing in a query call");
+    assert!(!certificate.certificate.is_empty());
+
+    // The tree has the ICRC-3 labels next to the legacy `tip_hash`.
+    let hash_tree: MixedHashTree = ciborium::de::from_reader(certificate.hash_tree.as_slice())
+        .expect("failed to decode the hash tree");
+    let mut last_block_index = vec![];
+    leb128::write::unsigned(&mut last_block_index, chain_length - 1).unwrap();
+    let last_block_hash = prev_hash.expect("the chain is empty").as_slice().to_vec();
+    assert_eq!(
+        hash_tree.lookup(&[b"last_block_index"]),
+        LookupStatus::Found(&MixedHashTree::Leaf(last_block_index))
+    );
+    assert_eq!(
+        hash_tree.lookup(&[b"last_block_hash"]),
+        LookupStatus::Found(&MixedHashTree::Leaf(last_block_hash.clone()))
+    );
+    assert_eq!(
+        hash_tree.lookup(&[b"tip_hash"]),
+        LookupStatus::Found(&MixedHashTree::Leaf(last_block_hash))
+    );
+}
+
 // Generate random blocks and check that their CBOR encoding complies with the CDDL spec.
 pub fn block_encoding_agrees_with_the_schema() {
     use std::path::PathBuf;
@@ -1711,3 +2001,178 @@ where
         )
         .unwrap()
 }
+
+pub fn test_approve_and_transfer_from<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
+where
+    T: CandidType,
+{
+    let from = PrincipalId::new_user_test_id(1);
+    let spender = PrincipalId::new_user_test_id(2);
+    let to = PrincipalId::new_user_test_id(3);
+    let (env, canister_id) = setup(
+        ledger_wasm,
+        encode_init_args,
+        vec![(Account::from(from.0), 1_000_000)],
+    );
+
+    assert_eq!(
+        get_allowance(&env, canister_id, from.0, spender.0),
+        Allowance {
+            allowance: Nat::from(0),
+            expires_at: None
+        }
+    );
+
+    // The approval fee is charged to the approver.
+    send_approval(
+        &env,
+        canister_id,
+        from.0,
+        &default_approve_args(spender.0, 150_000),
+    )
+    .expect("approval failed");
+    assert_eq!(1_000_000 - FEE, balance_of(&env, canister_id, from.0));
+    assert_eq!(
+        get_allowance(&env, canister_id, from.0, spender.0).allowance,
+        Nat::from(150_000)
+    );
+
+    // A second approval replaces the allowance instead of adding to it.
+    send_approval(
+        &env,
+        canister_id,
+        from.0,
+        &default_approve_args(spender.0, 100_000),
+    )
+    .expect("approval failed");
+    assert_eq!(1_000_000 - 2 * FEE, balance_of(&env, canister_id, from.0));
+    assert_eq!(
+        get_allowance(&env, canister_id, from.0, spender.0).allowance,
+        Nat::from(100_000)
+    );
+
+    // The spender can only move what the allowance covers, fee included.
+    assert_eq!(
+        send_transfer_from(
+            &env,
+            canister_id,
+            spender.0,
+            &default_transfer_from_args(from.0, to.0, 100_000),
+        ),
+        Err(TransferFromError::InsufficientAllowance {
+            allowance: Nat::from(100_000)
+        })
+    );
+
+    send_transfer_from(
+        &env,
+        canister_id,
+        spender.0,
+        &default_transfer_from_args(from.0, to.0, 100_000 - FEE),
+    )
+    .expect("transfer_from failed");
+    assert_eq!(
+        1_000_000 - 3 * FEE - (100_000 - FEE),
+        balance_of(&env, canister_id, from.0)
+    );
+    assert_eq!(100_000 - FEE, balance_of(&env, canister_id, to.0));
+    assert_eq!(0, balance_of(&env, canister_id, spender.0));
+    assert_eq!(
+        get_allowance(&env, canister_id, from.0, spender.0).allowance,
+        Nat::from(0)
+    );
+
+    let tx = get_transactions(&env, canister_id.get().0, 3, 1)
+        .transactions
+        .pop()
+        .unwrap();
+    assert_eq!(tx.kind, "transfer");
+    assert_eq!(tx.transfer.unwrap().spender, Some(Account::from(spender.0)));
+}
+
+pub fn test_approve_expected_allowance_and_expiration<T>(
+    ledger_wasm: Vec<u8>,
+    encode_init_args: fn(InitArgs) -> T,
+) where
+    T: CandidType,
+{
+    let from = PrincipalId::new_user_test_id(1);
+    let spender = PrincipalId::new_user_test_id(2);
+    let (env, canister_id) = setup(
+        ledger_wasm,
+        encode_init_args,
+        vec![(Account::from(from.0), 1_000_000)],
+    );
+
+    send_approval(
+        &env,
+        canister_id,
+        from.0,
+        &default_approve_args(spender.0, 100_000),
+    )
+    .expect("approval failed");
+
+    // A mismatching expected allowance rejects the approval without charging a fee.
+    assert_eq!(
+        send_approval(
+            &env,
+            canister_id,
+            from.0,
+            &ApproveArgs {
+                expected_allowance: Some(Nat::from(50_000)),
+                ..default_approve_args(spender.0, 200_000)
+            },
+        ),
+        Err(ApproveError::AllowanceChanged {
+            current_allowance: Nat::from(100_000)
+        })
+    );
+    assert_eq!(1_000_000 - FEE, balance_of(&env, canister_id, from.0));
+
+    let expires_at = system_time_to_nanos(env.time()) + Duration::from_secs(3600).as_nanos() as u64;
+    send_approval(
+        &env,
+        canister_id,
+        from.0,
+        &ApproveArgs {
+            expected_allowance: Some(Nat::from(100_000)),
+            expires_at: Some(expires_at),
+            ..default_approve_args(spender.0, 200_000)
+        },
+    )
+    .expect("approval failed");
+    assert_eq!(
+        get_allowance(&env, canister_id, from.0, spender.0),
+        Allowance {
+            allowance: Nat::from(200_000),
+            expires_at: Some(expires_at)
+        }
+    );
+
+    let tx = get_transactions(&env, canister_id.get().0, 2, 1)
+        .transactions
+        .pop()
+        .unwrap();
+    assert_eq!(tx.kind, "approve");
+    let approve = tx.approve.unwrap();
+    assert_eq!(approve.expected_allowance, Some(Nat::from(100_000)));
+    assert_eq!(approve.expires_at, Some(expires_at));
+
+    // Expired allowances cannot be used.
+    env.advance_time(Duration::from_secs(2 * 3600));
+    assert_eq!(
+        get_allowance(&env, canister_id, from.0, spender.0).allowance,
+        Nat::from(0)
+    );
+    assert_eq!(
+        send_transfer_from(
+            &env,
+            canister_id,
+            spender.0,
+            &default_transfer_from_args(from.0, spender.0, 10_000),
+        ),
+        Err(TransferFromError::InsufficientAllowance {
+            allowance: Nat::from(0)
+        })
+    );
+}
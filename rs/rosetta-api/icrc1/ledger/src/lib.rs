@@ -5,7 +5,6 @@ use candid::{
     types::number::{Int, Nat},
     CandidType, Principal,
 };
-use ic_base_types::PrincipalId;
 use ic_crypto_tree_hash::{Label, MixedHashTree};
 use ic_icrc1::blocks::icrc1_block_from_encoded;
 use ic_icrc1::{Block, LedgerBalances, Transaction};
@@ -24,10 +23,15 @@ use ic_ledger_core::{
 };
 use icrc_ledger_types::icrc1::account::Account;
 use icrc_ledger_types::icrc3::transactions::Transaction as Tx;
-use icrc_ledger_types::icrc3::{blocks::GetBlocksResponse, transactions::GetTransactionsResponse};
+use icrc_ledger_types::icrc3::{
+    blocks::{ArchivedBlocks, BlockWithId, GetBlocksRequest, GetBlocksResponse, GetBlocksResult},
+    transactions::GetTransactionsResponse,
+};
 use icrc_ledger_types::{
     icrc::generic_metadata_value::MetadataValue as Value,
-    icrc3::archive::{ArchivedRange, QueryBlockArchiveFn, QueryTxArchiveFn},
+    icrc3::archive::{
+        ArchivedRange, QueryBlockArchiveFn, QueryIcrc3BlocksArchiveFn, QueryTxArchiveFn,
+    },
 };
 use serde::{Deserialize, Serialize};
 use serde_bytes::ByteBuf;
@@ -148,6 +152,8 @@ pub enum LedgerArgument {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Ledger {
     balances: LedgerBalances,
+    #[serde(default)]
+    approvals: AllowanceTable<ApprovalKey, Account, Account>,
     blockchain: Blockchain<CdkRuntime, Icrc1ArchiveWasm>,
 
     minting_account: Account,
@@ -178,6 +184,7 @@ impl Ledger {
     ) -> Self {
         let mut ledger = Self {
             balances: LedgerBalances::default(),
+            approvals: Default::default(),
             blockchain: Blockchain::new_with_archive(archive_options),
             transactions_by_hash: BTreeMap::new(),
             transactions_by_height: VecDeque::new(),
@@ -208,19 +215,19 @@ impl Ledger {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
-pub struct ApprovalKey(Account, PrincipalId);
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+pub struct ApprovalKey(Account, Account);
 
-impl From<(&Account, &PrincipalId)> for ApprovalKey {
-    fn from((account, principal): (&Account, &PrincipalId)) -> Self {
-        Self(*account, *principal)
+impl From<(&Account, &Account)> for ApprovalKey {
+    fn from((account, spender): (&Account, &Account)) -> Self {
+        Self(*account, *spender)
     }
 }
 
 impl LedgerContext for Ledger {
     type AccountId = Account;
-    type SpenderId = PrincipalId;
-    type Approvals = AllowanceTable<ApprovalKey, Account, PrincipalId>;
+    type SpenderId = Account;
+    type Approvals = AllowanceTable<ApprovalKey, Account, Account>;
     type BalancesStore = HashMap<Self::AccountId, Tokens>;
 
     fn balances(&self) -> &Balances<Self::BalancesStore> {
@@ -232,11 +239,11 @@ impl LedgerContext for Ledger {
     }
 
     fn approvals(&self) -> &Self::Approvals {
-        unimplemented!()
+        &self.approvals
     }
 
     fn approvals_mut(&mut self) -> &mut Self::Approvals {
-        unimplemented!()
+        &mut self.approvals
     }
 
     fn fee_collector(&self) -> Option<&FeeCollector<Self::AccountId>> {
@@ -367,19 +374,32 @@ impl Ledger {
         self.construct_hash_tree().digest().0
     }
 
+    /// Returns the certified tree of the ledger tip.
+    ///
+    /// The tree contains the ICRC-3 labels `last_block_index` (LEB128) and
+    /// `last_block_hash`, and keeps the `tip_hash` label for existing clients.
     pub fn construct_hash_tree(&self) -> MixedHashTree {
         match self.blockchain().last_hash {
             Some(hash) => {
                 let last_block_index = self.blockchain().chain_length().checked_sub(1).unwrap();
+                let mut last_block_index_encoded = vec![];
+                leb128::write::unsigned(&mut last_block_index_encoded, last_block_index)
+                    .expect("Failed to write LEB128");
                 MixedHashTree::Fork(Box::new((
                     MixedHashTree::Labeled(
-                        Label::from("last_block_index"),
-                        Box::new(MixedHashTree::Leaf(last_block_index.to_be_bytes().to_vec())),
-                    ),
-                    MixedHashTree::Labeled(
-                        Label::from("tip_hash"),
+                        Label::from("last_block_hash"),
                         Box::new(MixedHashTree::Leaf(hash.as_slice().to_vec())),
                     ),
+                    MixedHashTree::Fork(Box::new((
+                        MixedHashTree::Labeled(
+                            Label::from("last_block_index"),
+                            Box::new(MixedHashTree::Leaf(last_block_index_encoded)),
+                        ),
+                        MixedHashTree::Labeled(
+                            Label::from("tip_hash"),
+                            Box::new(MixedHashTree::Leaf(hash.as_slice().to_vec())),
+                        ),
+                    ))),
                 )))
             }
             None => MixedHashTree::Empty,
@@ -454,4 +474,56 @@ impl Ledger {
             archived_blocks,
         }
     }
+
+    /// Returns blocks in the specified ranges as generic values.
+    ///
+    /// The ledger returns at most MAX_TRANSACTIONS_PER_REQUEST blocks in
+    /// total; archived ranges are grouped by the archive serving them.
+    pub fn icrc3_get_blocks(&self, ranges: Vec<(BlockIndex, usize)>) -> GetBlocksResult {
+        let mut blocks = vec![];
+        let mut archived_ranges: BTreeMap<Principal, Vec<GetBlocksRequest>> = BTreeMap::new();
+
+        for (start, length) in ranges {
+            let locations = block_locations(self, start, length);
+
+            let local_blocks_range = range_utils::take(
+                &locations.local_blocks,
+                MAX_TRANSACTIONS_PER_REQUEST.saturating_sub(blocks.len()),
+            );
+            let first_index = local_blocks_range.start;
+            for (i, block) in self
+                .blockchain
+                .block_slice(local_blocks_range)
+                .iter()
+                .enumerate()
+            {
+                blocks.push(BlockWithId {
+                    id: Nat::from(first_index + i as u64),
+                    block: icrc1_block_from_encoded(block),
+                });
+            }
+
+            for (canister_id, slice) in locations.archived_blocks {
+                archived_ranges
+                    .entry(canister_id.get().0)
+                    .or_default()
+                    .push(GetBlocksRequest {
+                        start: Nat::from(slice.start),
+                        length: Nat::from(range_utils::range_len(&slice)),
+                    });
+            }
+        }
+
+        GetBlocksResult {
+            log_length: Nat::from(self.blockchain.chain_length()),
+            blocks,
+            archived_blocks: archived_ranges
+                .into_iter()
+                .map(|(canister_id, args)| ArchivedBlocks {
+                    args,
+                    callback: QueryIcrc3BlocksArchiveFn::new(canister_id, "icrc3_get_blocks"),
+                })
+                .collect(),
+        }
+    }
 }
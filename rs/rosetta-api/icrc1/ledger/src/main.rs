@@ -5,17 +5,22 @@ use ic_canisters_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
 use ic_cdk::api::stable::{StableReader, StableWriter};
 use ic_cdk_macros::{init, post_upgrade, pre_upgrade, query, update};
 use ic_icrc1::{
-    endpoints::{convert_transfer_error, StandardRecord},
+    endpoints::{
+        convert_approve_error, convert_transfer_error, convert_transfer_from_error, StandardRecord,
+    },
     Operation, Transaction,
 };
 use ic_icrc1_ledger::{Ledger, LedgerArgument};
 use ic_ledger_canister_core::ledger::{
     apply_transaction, archive_blocks, LedgerAccess, LedgerContext, LedgerData,
 };
-use ic_ledger_core::{timestamp::TimeStamp, tokens::Tokens};
+use ic_ledger_core::{approvals::Approvals, timestamp::TimeStamp, tokens::Tokens};
 use icrc_ledger_types::icrc1::account::Account;
 use icrc_ledger_types::icrc1::transfer::{TransferArg, TransferError};
-use icrc_ledger_types::icrc3::blocks::DataCertificate;
+use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
+use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
+use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
+use icrc_ledger_types::icrc3::blocks::{DataCertificate, GetBlocksResult, ICRC3DataCertificate};
 use icrc_ledger_types::{
     icrc::generic_metadata_value::MetadataValue as Value,
     icrc3::{
@@ -301,6 +306,7 @@ async fn icrc1_transfer(arg: TransferArg) -> Result<Nat, TransferError> {
                 Transaction {
                     operation: Operation::Burn {
                         from: from_account,
+                        spender: None,
                         amount: amount.get_e8s(),
                     },
                     created_at_time: created_at_time.map(|t| t.as_nanos_since_unix_epoch()),
@@ -349,6 +355,164 @@ async fn icrc1_transfer(arg: TransferArg) -> Result<Nat, TransferError> {
     Ok(Nat::from(block_idx))
 }
 
+#[update]
+#[candid_method(update)]
+async fn icrc2_approve(arg: ApproveArgs) -> Result<Nat, ApproveError> {
+    let block_idx = Access::with_ledger_mut(|ledger| {
+        let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
+
+        let from_account = Account {
+            owner: ic_cdk::api::caller(),
+            subaccount: arg.from_subaccount,
+        };
+        if from_account.owner == arg.spender.owner {
+            ic_cdk::trap("self approval is not allowed")
+        }
+        if &from_account == ledger.minting_account() {
+            ic_cdk::trap("the minting account cannot delegate mints")
+        }
+        let amount = arg.amount.0.to_u64().unwrap_or(u64::MAX);
+        let expected_allowance = match arg.expected_allowance {
+            Some(n) => match n.0.to_u64() {
+                Some(n) => Some(Tokens::from_e8s(n)),
+                None => {
+                    let current_allowance = ledger
+                        .approvals()
+                        .allowance(&from_account, &arg.spender, now)
+                        .amount;
+                    return Err(ApproveError::AllowanceChanged {
+                        current_allowance: Nat::from(current_allowance.get_e8s()),
+                    });
+                }
+            },
+            None => None,
+        };
+        let expected_fee_tokens = ledger.transfer_fee();
+        let expected_fee = Nat::from(expected_fee_tokens.get_e8s());
+        if arg.fee.is_some() && arg.fee.as_ref() != Some(&expected_fee) {
+            return Err(ApproveError::BadFee { expected_fee });
+        }
+
+        let tx = Transaction::approve(
+            from_account,
+            arg.spender,
+            Tokens::from_e8s(amount),
+            expected_allowance,
+            arg.expires_at.map(TimeStamp::from_nanos_since_unix_epoch),
+            arg.fee.map(|_| expected_fee_tokens),
+            arg.created_at_time
+                .map(TimeStamp::from_nanos_since_unix_epoch),
+            arg.memo,
+        );
+
+        let (block_idx, _) = apply_transaction(ledger, tx, now, expected_fee_tokens)
+            .map_err(convert_approve_error)?;
+        Ok(block_idx)
+    })?;
+
+    ic_cdk::api::set_certified_data(&Access::with_ledger(Ledger::root_hash));
+
+    archive_blocks::<Access>(&LOG, MAX_MESSAGE_SIZE).await;
+    Ok(Nat::from(block_idx))
+}
+
+#[update]
+#[candid_method(update)]
+async fn icrc2_transfer_from(arg: TransferFromArgs) -> Result<Nat, TransferFromError> {
+    let block_idx = Access::with_ledger_mut(|ledger| {
+        let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
+        let created_at_time = arg
+            .created_at_time
+            .map(TimeStamp::from_nanos_since_unix_epoch);
+
+        let spender = Account {
+            owner: ic_cdk::api::caller(),
+            subaccount: arg.spender_subaccount,
+        };
+        if &arg.from == ledger.minting_account() {
+            ic_cdk::trap("the minting account cannot delegate mints")
+        }
+        let amount = match arg.amount.0.to_u64() {
+            Some(n) => Tokens::from_e8s(n),
+            None => {
+                // No one can have so many tokens
+                let balance = Nat::from(ledger.balances().account_balance(&arg.from).get_e8s());
+                assert!(balance < arg.amount);
+                return Err(TransferFromError::InsufficientFunds { balance });
+            }
+        };
+
+        let (tx, effective_fee) = if &arg.to == ledger.minting_account() {
+            let expected_fee = Nat::from(0u64);
+            if arg.fee.is_some() && arg.fee.as_ref() != Some(&expected_fee) {
+                return Err(TransferFromError::BadFee { expected_fee });
+            }
+            if amount < ledger.transfer_fee() {
+                return Err(TransferFromError::BadBurn {
+                    min_burn_amount: Nat::from(ledger.transfer_fee().get_e8s()),
+                });
+            }
+
+            (
+                Transaction {
+                    operation: Operation::Burn {
+                        from: arg.from,
+                        spender: Some(spender),
+                        amount: amount.get_e8s(),
+                    },
+                    created_at_time: created_at_time.map(|t| t.as_nanos_since_unix_epoch()),
+                    memo: arg.memo,
+                },
+                Tokens::ZERO,
+            )
+        } else {
+            let expected_fee_tokens = ledger.transfer_fee();
+            let expected_fee = Nat::from(expected_fee_tokens.get_e8s());
+            if arg.fee.is_some() && arg.fee.as_ref() != Some(&expected_fee) {
+                return Err(TransferFromError::BadFee { expected_fee });
+            }
+            (
+                Transaction {
+                    operation: Operation::Transfer {
+                        from: arg.from,
+                        to: arg.to,
+                        spender: Some(spender),
+                        amount: amount.get_e8s(),
+                        fee: arg.fee.map(|_| expected_fee_tokens.get_e8s()),
+                    },
+                    created_at_time: created_at_time.map(|t| t.as_nanos_since_unix_epoch()),
+                    memo: arg.memo,
+                },
+                expected_fee_tokens,
+            )
+        };
+
+        let (block_idx, _) = apply_transaction(ledger, tx, now, effective_fee)
+            .map_err(convert_transfer_from_error)?;
+        Ok(block_idx)
+    })?;
+
+    ic_cdk::api::set_certified_data(&Access::with_ledger(Ledger::root_hash));
+
+    archive_blocks::<Access>(&LOG, MAX_MESSAGE_SIZE).await;
+    Ok(Nat::from(block_idx))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc2_allowance(arg: AllowanceArgs) -> Allowance {
+    Access::with_ledger(|ledger| {
+        let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
+        let allowance = ledger
+            .approvals()
+            .allowance(&arg.account, &arg.spender, now);
+        Allowance {
+            allowance: Nat::from(allowance.amount.get_e8s()),
+            expires_at: allowance.expires_at.map(|t| t.as_nanos_since_unix_epoch()),
+        }
+    })
+}
+
 #[query]
 fn archives() -> Vec<ArchiveInfo> {
     Access::with_ledger(|ledger| {
@@ -376,10 +540,16 @@ fn archives() -> Vec<ArchiveInfo> {
 #[query(name = "icrc1_supported_standards")]
 #[candid_method(query, rename = "icrc1_supported_standards")]
 fn supported_standards() -> Vec<StandardRecord> {
-    vec![StandardRecord {
-        name: "ICRC-1".to_string(),
-        url: "https://github.com/dfinity/ICRC-1".to_string(),
-    }]
+    vec![
+        StandardRecord {
+            name: "ICRC-1".to_string(),
+            url: "https://github.com/dfinity/ICRC-1".to_string(),
+        },
+        StandardRecord {
+            name: "ICRC-2".to_string(),
+            url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-2".to_string(),
+        },
+    ]
 }
 
 #[query]
@@ -400,6 +570,36 @@ fn get_blocks(req: GetBlocksRequest) -> GetBlocksResponse {
     Access::with_ledger(|ledger| ledger.get_blocks(start, length as usize))
 }
 
+#[query]
+#[candid_method(query)]
+fn icrc3_get_blocks(args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    let ranges = args
+        .iter()
+        .map(|arg| {
+            let (start, length) = arg
+                .as_start_and_length()
+                .unwrap_or_else(|msg| ic_cdk::api::trap(&msg));
+            (start, length as usize)
+        })
+        .collect();
+    Access::with_ledger(|ledger| ledger.icrc3_get_blocks(ranges))
+}
+
+/// Returns the certificate of the last block if the call is a non-replicated
+/// query.
+#[query]
+#[candid_method(query)]
+fn icrc3_get_tip_certificate() -> Option<ICRC3DataCertificate> {
+    let certificate = ByteBuf::from(ic_cdk::api::data_certificate()?);
+    let hash_tree = Access::with_ledger(|ledger| ledger.construct_hash_tree());
+    let mut tree_buf = vec![];
+    ciborium::ser::into_writer(&hash_tree, &mut tree_buf).unwrap();
+    Some(ICRC3DataCertificate {
+        certificate,
+        hash_tree: ByteBuf::from(tree_buf),
+    })
+}
+
 #[query]
 #[candid_method(query)]
 fn get_data_certificate() -> DataCertificate {
@@ -103,6 +103,11 @@ fn test_get_blocks() {
     ic_icrc1_ledger_sm_tests::test_get_blocks(ledger_wasm(), encode_init_args);
 }
 
+#[test]
+fn test_icrc3_get_blocks() {
+    ic_icrc1_ledger_sm_tests::test_icrc3_get_blocks(ledger_wasm(), encode_init_args);
+}
+
 // Generate random blocks and check that their CBOR encoding complies with the CDDL spec.
 #[test]
 fn block_encoding_agrees_with_the_schema() {
@@ -141,3 +146,16 @@ fn check_fee_collector() {
 fn check_fee_collector_blocks() {
     ic_icrc1_ledger_sm_tests::test_fee_collector_blocks(ledger_wasm(), encode_init_args);
 }
+
+#[test]
+fn test_approve_and_transfer_from() {
+    ic_icrc1_ledger_sm_tests::test_approve_and_transfer_from(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_approve_expected_allowance_and_expiration() {
+    ic_icrc1_ledger_sm_tests::test_approve_expected_allowance_and_expiration(
+        ledger_wasm(),
+        encode_init_args,
+    );
+}
@@ -169,6 +169,10 @@ pub struct Error {
 }
 
 const ERROR_CODE_INVALID_NETWORK_ID: u32 = 1;
+const ERROR_CODE_INVALID_REQUEST: u32 = 2;
+const ERROR_CODE_LEDGER_COMMUNICATION: u32 = 3;
+const ERROR_CODE_TRANSACTION_REJECTED: u32 = 4;
+const ERROR_CODE_TRANSACTION_EXPIRED: u32 = 5;
 
 impl IntoResponse for Error {
     fn into_response(self) -> axum::response::Response {
@@ -189,6 +193,46 @@ impl Error {
             details: None,
         }
     }
+
+    pub fn invalid_request(description: impl ToString) -> Self {
+        Self {
+            code: ERROR_CODE_INVALID_REQUEST,
+            message: "Invalid request".into(),
+            description: Some(description.to_string()),
+            retriable: false,
+            details: None,
+        }
+    }
+
+    pub fn ledger_communication(description: impl ToString) -> Self {
+        Self {
+            code: ERROR_CODE_LEDGER_COMMUNICATION,
+            message: "Error while communicating with the ledger".into(),
+            description: Some(description.to_string()),
+            retriable: true,
+            details: None,
+        }
+    }
+
+    pub fn transaction_rejected(description: impl ToString) -> Self {
+        Self {
+            code: ERROR_CODE_TRANSACTION_REJECTED,
+            message: "Transaction rejected by the ledger".into(),
+            description: Some(description.to_string()),
+            retriable: false,
+            details: None,
+        }
+    }
+
+    pub fn transaction_expired() -> Self {
+        Self {
+            code: ERROR_CODE_TRANSACTION_EXPIRED,
+            message: "Transaction expired".into(),
+            description: Some("None of the signed envelopes is valid at the current time".into()),
+            retriable: false,
+            details: None,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -198,3 +242,252 @@ pub struct NetworkRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<serde_json::Value>,
 }
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AccountIdentifier {
+    pub address: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_account: Option<SubAccountIdentifier>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SubAccountIdentifier {
+    pub address: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Amount {
+    pub value: String,
+
+    pub currency: Currency,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct OperationIdentifier {
+    pub index: u64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network_index: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Operation {
+    pub operation_identifier: OperationIdentifier,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub related_operations: Option<Vec<OperationIdentifier>>,
+
+    #[serde(rename = "type")]
+    pub _type: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<AccountIdentifier>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<Amount>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum CurveType {
+    #[serde(rename = "secp256k1")]
+    Secp256K1,
+    #[serde(rename = "edwards25519")]
+    Edwards25519,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublicKey {
+    pub hex_bytes: String,
+
+    pub curve_type: CurveType,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum SignatureType {
+    #[serde(rename = "ecdsa")]
+    Ecdsa,
+    #[serde(rename = "ed25519")]
+    Ed25519,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SigningPayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_identifier: Option<AccountIdentifier>,
+
+    pub hex_bytes: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature_type: Option<SignatureType>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Signature {
+    pub signing_payload: SigningPayload,
+
+    pub public_key: PublicKey,
+
+    pub signature_type: SignatureType,
+
+    pub hex_bytes: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TransactionIdentifier {
+    pub hash: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionDeriveRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    pub public_key: PublicKey,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionDeriveResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_identifier: Option<AccountIdentifier>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionPreprocessRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    pub operations: Vec<Operation>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionPreprocessResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<serde_json::Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required_public_keys: Option<Vec<AccountIdentifier>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionMetadataRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<serde_json::Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_keys: Option<Vec<PublicKey>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionMetadataResponse {
+    pub metadata: serde_json::Value,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggested_fee: Option<Vec<Amount>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionPayloadsRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    pub operations: Vec<Operation>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_keys: Option<Vec<PublicKey>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionPayloadsResponse {
+    pub unsigned_transaction: String,
+
+    pub payloads: Vec<SigningPayload>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionCombineRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    pub unsigned_transaction: String,
+
+    pub signatures: Vec<Signature>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionCombineResponse {
+    pub signed_transaction: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionParseRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    pub signed: bool,
+
+    pub transaction: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionParseResponse {
+    pub operations: Vec<Operation>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_identifier_signers: Option<Vec<AccountIdentifier>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionHashRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    pub signed_transaction: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionSubmitRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    pub signed_transaction: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TransactionIdentifierResponse {
+    pub transaction_identifier: TransactionIdentifier,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
@@ -44,6 +44,7 @@ pub mod strategies {
             }),
             (any::<u16>(), account_strategy()).prop_map(|(amount, from)| Operation::Burn {
                 from,
+                spender: None,
                 amount: amount.into()
             }),
             (
@@ -55,6 +56,7 @@ pub mod strategies {
                 .prop_map(|(amount, to, from, fee)| Operation::Transfer {
                     from,
                     to,
+                    spender: None,
                     amount: amount.into(),
                     fee
                 }),
@@ -158,12 +160,7 @@ pub mod strategies {
                         memo: block.transaction.memo,
                         amount: amount.into(),
                     },
-                    Operation::Transfer {
-                        from: _,
-                        to,
-                        amount,
-                        fee: _,
-                    } => TransferArg {
+                    Operation::Transfer { to, amount, .. } => TransferArg {
                         from_subaccount: sender.subaccount,
                         to,
                         fee: None,
@@ -171,7 +168,7 @@ pub mod strategies {
                         memo: block.transaction.memo,
                         amount: amount.into(),
                     },
-                    Operation::Burn { from: _, amount } => TransferArg {
+                    Operation::Burn { amount, .. } => TransferArg {
                         from_subaccount: sender.subaccount,
                         to: Principal::anonymous().into(),
                         fee: None,
@@ -179,6 +176,9 @@ pub mod strategies {
                         memo: block.transaction.memo,
                         amount: amount.into(),
                     },
+                    Operation::Approve { .. } => {
+                        unreachable!("the transaction strategy does not generate approvals")
+                    }
                 })
                 .collect()
         })
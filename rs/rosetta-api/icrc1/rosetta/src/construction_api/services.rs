@@ -0,0 +1,637 @@
+use crate::common::types::{
+    Amount, ConstructionCombineResponse, ConstructionDeriveResponse, ConstructionMetadataResponse,
+    ConstructionParseResponse, ConstructionPayloadsResponse, ConstructionPreprocessResponse,
+    Currency, CurveType, Error, Operation, PublicKey, Signature, SignatureType, SigningPayload,
+    TransactionIdentifier, TransactionIdentifierResponse,
+};
+use crate::construction_api::types::{
+    ConstructionPayloadsRequestMetadata, EnvelopePair, SignedTransaction, UnsignedTransaction,
+    TRANSFER_METHOD_NAME,
+};
+use crate::construction_api::utils::{
+    account_identifier_from_account, decode_memo, der_encode_public_key,
+    make_read_state_from_update, make_sig_data, operations_to_transfer, principal_from_public_key,
+    transaction_hash, transfer_from_update, transfer_to_operations,
+};
+use candid::{Decode, Encode, Nat, Principal};
+use ic_agent::agent::{Replied, RequestStatusResponse};
+use ic_agent::Agent;
+use ic_constants::{MAX_INGRESS_TTL, PERMITTED_DRIFT};
+use ic_types::messages::{
+    Blob, HttpCallContent, HttpCanisterUpdate, HttpReadStateContent, HttpRequestEnvelope,
+    MessageId, SignedRequestBytes,
+};
+use icrc_ledger_agent::{CallMode, Icrc1Agent};
+use icrc_ledger_types::icrc1::transfer::{TransferArg, TransferError};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+// Exponential backoff from 100ms to 10s with a multiplier of 1.3.
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(10);
+const POLL_INTERVAL_MULTIPLIER: f32 = 1.3;
+const SUBMIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The length of the interval covered by a single ingress expiry.
+fn ingress_interval() -> Duration {
+    MAX_INGRESS_TTL - PERMITTED_DRIFT - Duration::from_secs(120)
+}
+
+fn ingress_window_nanos() -> u64 {
+    (MAX_INGRESS_TTL - PERMITTED_DRIFT).as_nanos() as u64
+}
+
+/// Derives the account identifier of the default subaccount of the principal
+/// controlled by the given public key.
+pub fn construction_derive(public_key: &PublicKey) -> Result<ConstructionDeriveResponse, Error> {
+    let owner = principal_from_public_key(public_key)?;
+    Ok(ConstructionDeriveResponse {
+        address: None,
+        account_identifier: Some(account_identifier_from_account(&owner.into())),
+        metadata: None,
+    })
+}
+
+pub fn construction_preprocess(
+    operations: &[Operation],
+    metadata: Option<serde_json::Value>,
+    currency: &Currency,
+) -> Result<ConstructionPreprocessResponse, Error> {
+    let transfer = operations_to_transfer(operations, currency)?;
+    let metadata = ConstructionPayloadsRequestMetadata::from_json(metadata)?;
+    if let Some(memo) = &metadata.memo {
+        decode_memo(memo)?;
+    }
+    Ok(ConstructionPreprocessResponse {
+        options: Some(metadata.to_json()),
+        required_public_keys: Some(vec![account_identifier_from_account(&transfer.from)]),
+    })
+}
+
+/// Returns the options produced by `/construction/preprocess` together with
+/// the current transfer fee of the ledger.
+pub async fn construction_metadata(
+    options: Option<serde_json::Value>,
+    icrc1_agent: &Icrc1Agent,
+    currency: &Currency,
+) -> Result<ConstructionMetadataResponse, Error> {
+    let metadata = ConstructionPayloadsRequestMetadata::from_json(options)?;
+    let fee = icrc1_agent
+        .fee(CallMode::Query)
+        .await
+        .map_err(|e| Error::ledger_communication(format!("Unable to fetch the fee: {:?}", e)))?;
+    Ok(ConstructionMetadataResponse {
+        metadata: metadata.to_json(),
+        suggested_fee: Some(vec![Amount {
+            value: fee.0.to_string(),
+            currency: currency.clone(),
+            metadata: None,
+        }]),
+    })
+}
+
+fn signature_type(curve_type: CurveType) -> SignatureType {
+    match curve_type {
+        CurveType::Edwards25519 => SignatureType::Ed25519,
+        CurveType::Secp256K1 => SignatureType::Ecdsa,
+    }
+}
+
+/// Builds one `icrc1_transfer` call per ingress interval and the payloads
+/// that the owner of the source account must sign. `now` is the current
+/// time in nanoseconds since the UNIX epoch.
+pub fn construction_payloads(
+    operations: &[Operation],
+    metadata: Option<serde_json::Value>,
+    public_keys: &[PublicKey],
+    ledger_id: Principal,
+    currency: &Currency,
+    now: u64,
+) -> Result<ConstructionPayloadsResponse, Error> {
+    let transfer = operations_to_transfer(operations, currency)?;
+    let metadata = ConstructionPayloadsRequestMetadata::from_json(metadata)?;
+
+    let public_key = public_keys
+        .iter()
+        .find(|pk| principal_from_public_key(pk).ok() == Some(transfer.from.owner))
+        .ok_or_else(|| {
+            Error::invalid_request(format!(
+                "No public key matches the source account owner {}",
+                transfer.from.owner
+            ))
+        })?;
+
+    let ingress_start = metadata.ingress_start.unwrap_or(now);
+    let interval = ingress_interval().as_nanos() as u64;
+    let ingress_end = metadata
+        .ingress_end
+        .unwrap_or_else(|| ingress_start.saturating_add(interval));
+    if ingress_end <= ingress_start {
+        return Err(Error::invalid_request(format!(
+            "The ingress end {} must be after the ingress start {}",
+            ingress_end, ingress_start
+        )));
+    }
+
+    // All the calls share the same created_at_time so that the ledger
+    // rejects every submission after the first one as a duplicate.
+    let arg = TransferArg {
+        from_subaccount: transfer.from.subaccount,
+        to: transfer.to,
+        fee: transfer.fee.map(Nat::from),
+        created_at_time: Some(metadata.created_at_time.unwrap_or(ingress_start)),
+        memo: metadata.memo.as_deref().map(decode_memo).transpose()?,
+        amount: Nat::from(transfer.amount),
+    };
+    let arg = Encode!(&arg).map_err(|e| {
+        Error::invalid_request(format!("Unable to encode the transfer argument: {}", e))
+    })?;
+
+    let account_identifier = account_identifier_from_account(&transfer.from);
+    let make_payload = |message_id: &MessageId| SigningPayload {
+        address: None,
+        account_identifier: Some(account_identifier.clone()),
+        hex_bytes: hex::encode(make_sig_data(message_id)),
+        signature_type: Some(signature_type(public_key.curve_type)),
+    };
+
+    let mut updates = vec![];
+    let mut payloads = vec![];
+    let mut start = ingress_start;
+    while start < ingress_end {
+        let update = HttpCanisterUpdate {
+            canister_id: Blob(ledger_id.as_slice().to_vec()),
+            method_name: TRANSFER_METHOD_NAME.to_string(),
+            arg: Blob(arg.clone()),
+            sender: Blob(transfer.from.owner.as_slice().to_vec()),
+            ingress_expiry: start.saturating_add(ingress_window_nanos()),
+            nonce: None,
+        };
+        let read_state = make_read_state_from_update(&update);
+        payloads.push(make_payload(&update.id()));
+        payloads.push(make_payload(&MessageId::from(
+            read_state.representation_independent_hash(),
+        )));
+        updates.push(update);
+        start = start.saturating_add(interval);
+    }
+
+    Ok(ConstructionPayloadsResponse {
+        unsigned_transaction: UnsignedTransaction { updates }.to_string(),
+        payloads,
+    })
+}
+
+fn make_envelope<C>(
+    content: C,
+    signature: &Signature,
+    sender: &Blob,
+) -> Result<HttpRequestEnvelope<C>, Error> {
+    if signature.signature_type != signature_type(signature.public_key.curve_type) {
+        return Err(Error::invalid_request(format!(
+            "Signature type {:?} does not match the curve type {:?}",
+            signature.signature_type, signature.public_key.curve_type
+        )));
+    }
+    if principal_from_public_key(&signature.public_key)?.as_slice() != sender.0.as_slice() {
+        return Err(Error::invalid_request(
+            "The public key of the signature does not match the sender of the transaction",
+        ));
+    }
+    let sender_sig = hex::decode(&signature.hex_bytes)
+        .map_err(|e| Error::invalid_request(format!("Signature is not hex encoded: {}", e)))?;
+    Ok(HttpRequestEnvelope {
+        content,
+        sender_pubkey: Some(Blob(der_encode_public_key(&signature.public_key)?)),
+        sender_sig: Some(Blob(sender_sig)),
+        sender_delegation: None,
+    })
+}
+
+/// Attaches the signatures to the calls of the unsigned transaction.
+pub fn construction_combine(
+    unsigned_transaction: &str,
+    signatures: &[Signature],
+) -> Result<ConstructionCombineResponse, Error> {
+    let unsigned_transaction = UnsignedTransaction::from_str(unsigned_transaction)?;
+
+    let mut signatures_by_sig_data = HashMap::new();
+    for signature in signatures {
+        let sig_data = hex::decode(&signature.signing_payload.hex_bytes).map_err(|e| {
+            Error::invalid_request(format!("Signing payload is not hex encoded: {}", e))
+        })?;
+        signatures_by_sig_data.insert(sig_data, signature);
+    }
+    let find_signature = |message_id: &MessageId| {
+        signatures_by_sig_data
+            .get(&make_sig_data(message_id))
+            .copied()
+            .ok_or_else(|| {
+                Error::invalid_request(format!("Missing signature for message {}", message_id))
+            })
+    };
+
+    let mut envelope_pairs = vec![];
+    for update in unsigned_transaction.updates {
+        let read_state = make_read_state_from_update(&update);
+        let update_signature = find_signature(&update.id())?;
+        let read_state_signature = find_signature(&MessageId::from(
+            read_state.representation_independent_hash(),
+        ))?;
+        let sender = update.sender.clone();
+        envelope_pairs.push(EnvelopePair {
+            update: make_envelope(HttpCallContent::Call { update }, update_signature, &sender)?,
+            read_state: make_envelope(
+                HttpReadStateContent::ReadState { read_state },
+                read_state_signature,
+                &sender,
+            )?,
+        });
+    }
+
+    Ok(ConstructionCombineResponse {
+        signed_transaction: SignedTransaction { envelope_pairs }.to_string(),
+    })
+}
+
+pub fn construction_parse(
+    transaction: &str,
+    signed: bool,
+    currency: &Currency,
+) -> Result<ConstructionParseResponse, Error> {
+    let update = if signed {
+        SignedTransaction::from_str(transaction)?.envelope_pairs[0]
+            .update_content()
+            .clone()
+    } else {
+        UnsignedTransaction::from_str(transaction)?
+            .updates
+            .remove(0)
+    };
+    let (transfer, arg) = transfer_from_update(&update)?;
+    let metadata = ConstructionPayloadsRequestMetadata {
+        memo: arg.memo.map(|memo| hex::encode(memo.0)),
+        created_at_time: arg.created_at_time,
+        ingress_start: None,
+        ingress_end: None,
+    };
+    Ok(ConstructionParseResponse {
+        operations: transfer_to_operations(&transfer, currency),
+        account_identifier_signers: signed
+            .then(|| vec![account_identifier_from_account(&transfer.from)]),
+        metadata: Some(metadata.to_json()),
+    })
+}
+
+pub fn construction_hash(signed_transaction: &str) -> Result<TransactionIdentifierResponse, Error> {
+    let signed_transaction = SignedTransaction::from_str(signed_transaction)?;
+    let hash = transaction_hash(signed_transaction.envelope_pairs[0].update_content())?;
+    Ok(TransactionIdentifierResponse {
+        transaction_identifier: TransactionIdentifier { hash },
+        metadata: None,
+    })
+}
+
+/// Polls the status of the call until the ledger replies.
+async fn wait_for_reply(
+    agent: &Agent,
+    request_id: &ic_agent::RequestId,
+    ledger_id: Principal,
+    read_state: Vec<u8>,
+    deadline: Instant,
+) -> Result<Vec<u8>, Error> {
+    let mut poll_interval = MIN_POLL_INTERVAL;
+    while Instant::now() + poll_interval < deadline {
+        tokio::time::sleep(poll_interval).await;
+        match agent
+            .request_status_signed(request_id, ledger_id, read_state.clone(), false)
+            .await
+        {
+            Ok(RequestStatusResponse::Replied {
+                reply: Replied::CallReplied(reply),
+            }) => return Ok(reply),
+            Ok(RequestStatusResponse::Unknown)
+            | Ok(RequestStatusResponse::Received)
+            | Ok(RequestStatusResponse::Processing) => {}
+            Ok(RequestStatusResponse::Done) => {
+                return Err(Error::ledger_communication(
+                    "The call has completed but the reply has been pruned",
+                ))
+            }
+            Ok(status) => {
+                return Err(Error::transaction_rejected(format!(
+                    "The ledger rejected the call: {:?}",
+                    status
+                )))
+            }
+            Err(e) => debug!("Error while reading the status of the call: {}", e),
+        }
+        poll_interval = poll_interval
+            .mul_f32(POLL_INTERVAL_MULTIPLIER)
+            .min(MAX_POLL_INTERVAL);
+    }
+    Err(Error::ledger_communication(
+        "Timed out while waiting for the ledger to process the transaction",
+    ))
+}
+
+/// Submits the envelope of the signed transaction that is valid at time
+/// `now` and waits for the ledger to process it. Submitting the same
+/// transaction twice is not an error: the ledger reports the second
+/// submission as a duplicate of the first one, and the response carries
+/// the block index of the original transfer.
+pub async fn construction_submit(
+    signed_transaction: &str,
+    ledger_id: Principal,
+    icrc1_agent: &Icrc1Agent,
+    now: u64,
+) -> Result<TransactionIdentifierResponse, Error> {
+    let signed_transaction = SignedTransaction::from_str(signed_transaction)?;
+    let envelope_pair = signed_transaction
+        .envelope_pairs
+        .into_iter()
+        .find(|pair| {
+            let ingress_expiry = pair.update_content().ingress_expiry;
+            ingress_expiry.saturating_sub(ingress_window_nanos()) <= now && now < ingress_expiry
+        })
+        .ok_or_else(Error::transaction_expired)?;
+
+    let update = envelope_pair.update_content();
+    if update.canister_id.0.as_slice() != ledger_id.as_slice() {
+        return Err(Error::invalid_request(format!(
+            "The transaction is not addressed to the ledger {}",
+            ledger_id
+        )));
+    }
+    let hash = transaction_hash(update)?;
+
+    let update_bytes = SignedRequestBytes::try_from(envelope_pair.update)
+        .map_err(|e| Error::invalid_request(format!("Unable to encode the call: {}", e)))?;
+    let read_state_bytes = SignedRequestBytes::try_from(envelope_pair.read_state)
+        .map_err(|e| Error::invalid_request(format!("Unable to encode the read state: {}", e)))?;
+
+    let deadline = Instant::now() + SUBMIT_TIMEOUT;
+    let request_id = icrc1_agent
+        .agent
+        .update_signed(ledger_id, update_bytes.into())
+        .await
+        .map_err(|e| Error::ledger_communication(format!("Unable to submit the call: {}", e)))?;
+    let reply = wait_for_reply(
+        &icrc1_agent.agent,
+        &request_id,
+        ledger_id,
+        read_state_bytes.into(),
+        deadline,
+    )
+    .await?;
+
+    let block_index = match Decode!(&reply, Result<Nat, TransferError>)
+        .map_err(|e| Error::ledger_communication(format!("Unable to decode the reply: {}", e)))?
+    {
+        Ok(block_index) => block_index,
+        Err(TransferError::Duplicate { duplicate_of }) => duplicate_of,
+        Err(e) => return Err(Error::transaction_rejected(format!("{:?}", e))),
+    };
+
+    Ok(TransactionIdentifierResponse {
+        transaction_identifier: TransactionIdentifier { hash },
+        metadata: Some(serde_json::json!({ "block_index": block_index.0.to_string() })),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::types::{AccountIdentifier, SubAccountIdentifier};
+    use crate::construction_api::types::{
+        Transfer, OPERATION_TYPE_FEE, OPERATION_TYPE_TRANSACTION,
+    };
+    use crate::construction_api::utils::account_from_account_identifier;
+    use ic_ledger_canister_core::ledger::LedgerTransaction;
+    use icrc_ledger_types::icrc1::account::Account;
+    use icrc_ledger_types::icrc1::transfer::Memo;
+    use serde_bytes::ByteBuf;
+
+    const NOW: u64 = 1_685_000_000_000_000_000;
+
+    fn currency() -> Currency {
+        Currency {
+            symbol: "XTST".to_string(),
+            decimals: 8,
+            metadata: None,
+        }
+    }
+
+    fn public_key() -> PublicKey {
+        PublicKey {
+            hex_bytes: hex::encode([7u8; 32]),
+            curve_type: CurveType::Edwards25519,
+        }
+    }
+
+    fn ledger_id() -> Principal {
+        Principal::from_slice(&[0, 0, 0, 0, 0, 0, 0, 2, 1, 1])
+    }
+
+    fn transfer() -> Transfer {
+        Transfer {
+            from: Account {
+                owner: principal_from_public_key(&public_key()).unwrap(),
+                subaccount: Some([1; 32]),
+            },
+            to: Account {
+                owner: Principal::from_slice(&[1, 2, 3]),
+                subaccount: None,
+            },
+            amount: 1_000_000,
+            fee: Some(10_000),
+        }
+    }
+
+    fn sign_all(payloads: &[SigningPayload]) -> Vec<Signature> {
+        payloads
+            .iter()
+            .map(|payload| Signature {
+                signing_payload: payload.clone(),
+                public_key: public_key(),
+                signature_type: SignatureType::Ed25519,
+                hex_bytes: hex::encode([0u8; 64]),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_account_identifier_round_trip() {
+        let account = transfer().from;
+        let account_identifier = account_identifier_from_account(&account);
+        assert_eq!(
+            account_identifier.sub_account,
+            Some(SubAccountIdentifier {
+                address: hex::encode([1; 32]),
+                metadata: None
+            })
+        );
+        assert_eq!(
+            account_from_account_identifier(&account_identifier).unwrap(),
+            account
+        );
+
+        let default_subaccount = Account {
+            owner: account.owner,
+            subaccount: Some([0; 32]),
+        };
+        assert_eq!(
+            account_identifier_from_account(&default_subaccount),
+            AccountIdentifier {
+                address: account.owner.to_text(),
+                sub_account: None,
+                metadata: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_operations_must_balance() {
+        let mut operations = transfer_to_operations(&transfer(), &currency());
+        operations[1].amount.as_mut().unwrap().value = "999".to_string();
+        assert!(operations_to_transfer(&operations, &currency()).is_err());
+
+        let mut operations = transfer_to_operations(&transfer(), &currency());
+        operations[2].account = operations[1].account.clone();
+        assert!(operations_to_transfer(&operations, &currency()).is_err());
+
+        let mut operations = transfer_to_operations(&transfer(), &currency());
+        operations[0].amount.as_mut().unwrap().currency.symbol = "ICP".to_string();
+        assert!(operations_to_transfer(&operations, &currency()).is_err());
+
+        let operations = transfer_to_operations(&transfer(), &currency());
+        assert_eq!(operations[0]._type, OPERATION_TYPE_TRANSACTION);
+        assert_eq!(operations[2]._type, OPERATION_TYPE_FEE);
+        assert_eq!(
+            operations_to_transfer(&operations, &currency()).unwrap(),
+            transfer()
+        );
+    }
+
+    #[test]
+    fn test_construction_flow() {
+        let operations = transfer_to_operations(&transfer(), &currency());
+        let metadata = ConstructionPayloadsRequestMetadata {
+            memo: Some(hex::encode(b"exchange deposit")),
+            created_at_time: Some(NOW - 1_000),
+            ingress_start: None,
+            ingress_end: None,
+        };
+
+        let preprocess =
+            construction_preprocess(&operations, Some(metadata.to_json()), &currency()).unwrap();
+        assert_eq!(
+            preprocess.required_public_keys,
+            Some(vec![account_identifier_from_account(&transfer().from)])
+        );
+
+        let payloads = construction_payloads(
+            &operations,
+            preprocess.options,
+            &[public_key()],
+            ledger_id(),
+            &currency(),
+            NOW,
+        )
+        .unwrap();
+        // One call and one read state per ingress interval.
+        assert_eq!(payloads.payloads.len(), 2);
+
+        let unsigned =
+            construction_parse(&payloads.unsigned_transaction, false, &currency()).unwrap();
+        assert_eq!(unsigned.operations, operations);
+        assert_eq!(unsigned.account_identifier_signers, None);
+        assert_eq!(unsigned.metadata, Some(metadata.to_json()));
+
+        // Combining fails if a signature is missing.
+        assert!(construction_combine(
+            &payloads.unsigned_transaction,
+            &sign_all(&payloads.payloads[..1])
+        )
+        .is_err());
+
+        let signed = construction_combine(
+            &payloads.unsigned_transaction,
+            &sign_all(&payloads.payloads),
+        )
+        .unwrap();
+        let parsed = construction_parse(&signed.signed_transaction, true, &currency()).unwrap();
+        assert_eq!(parsed.operations, operations);
+        assert_eq!(
+            parsed.account_identifier_signers,
+            Some(vec![account_identifier_from_account(&transfer().from)])
+        );
+
+        let hash = construction_hash(&signed.signed_transaction).unwrap();
+        let transaction = ic_icrc1::Transaction {
+            operation: ic_icrc1::Operation::Transfer {
+                from: transfer().from,
+                to: transfer().to,
+                spender: None,
+                amount: transfer().amount,
+                fee: transfer().fee,
+            },
+            created_at_time: metadata.created_at_time,
+            memo: Some(Memo(ByteBuf::from(b"exchange deposit".to_vec()))),
+        };
+        assert_eq!(
+            hash.transaction_identifier.hash,
+            hex::encode(transaction.hash().as_slice())
+        );
+    }
+
+    #[test]
+    fn test_payloads_cover_the_ingress_interval() {
+        let operations = transfer_to_operations(&transfer(), &currency());
+        let interval = ingress_interval().as_nanos() as u64;
+        let metadata = ConstructionPayloadsRequestMetadata {
+            ingress_start: Some(NOW),
+            ingress_end: Some(NOW + 3 * interval),
+            ..Default::default()
+        };
+        let payloads = construction_payloads(
+            &operations,
+            Some(metadata.to_json()),
+            &[public_key()],
+            ledger_id(),
+            &currency(),
+            NOW,
+        )
+        .unwrap();
+        let unsigned = UnsignedTransaction::from_str(&payloads.unsigned_transaction).unwrap();
+        assert_eq!(unsigned.updates.len(), 3);
+        // All the calls carry the same argument so that the ledger
+        // deduplicates them.
+        assert!(unsigned
+            .updates
+            .iter()
+            .all(|u| u.arg == unsigned.updates[0].arg));
+        let (_, arg) = transfer_from_update(&unsigned.updates[0]).unwrap();
+        assert_eq!(arg.created_at_time, Some(NOW));
+    }
+
+    #[test]
+    fn test_payloads_require_the_public_key_of_the_source() {
+        let operations = transfer_to_operations(&transfer(), &currency());
+        let other_key = PublicKey {
+            hex_bytes: hex::encode([8u8; 32]),
+            curve_type: CurveType::Edwards25519,
+        };
+        assert!(construction_payloads(
+            &operations,
+            None,
+            &[other_key],
+            ledger_id(),
+            &currency(),
+            NOW
+        )
+        .is_err());
+    }
+}
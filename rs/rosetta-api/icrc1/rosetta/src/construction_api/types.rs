@@ -0,0 +1,140 @@
+use crate::common::types::Error;
+use ic_types::messages::{
+    HttpCallContent, HttpCanisterUpdate, HttpReadStateContent, HttpRequestEnvelope,
+};
+use icrc_ledger_types::icrc1::account::Account;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+pub const OPERATION_TYPE_TRANSACTION: &str = "TRANSACTION";
+pub const OPERATION_TYPE_FEE: &str = "FEE";
+
+/// The method of the ledger that the construction API calls.
+pub const TRANSFER_METHOD_NAME: &str = "icrc1_transfer";
+
+/// The metadata of a transfer that the caller can set in `/construction/preprocess`
+/// and `/construction/payloads`. `/construction/metadata` returns the same
+/// metadata so that the options can flow from one endpoint to the next.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionPayloadsRequestMetadata {
+    /// The hex encoded memo of the transfer (at most 32 bytes).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+
+    /// The time in nanoseconds since the UNIX epoch used by the ledger to
+    /// deduplicate the transfer. Rosetta sets it to the current time if
+    /// not specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at_time: Option<u64>,
+
+    /// The start of the interval in which the signed transaction can be
+    /// submitted, in nanoseconds since the UNIX epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ingress_start: Option<u64>,
+
+    /// The end of the interval in which the signed transaction can be
+    /// submitted, in nanoseconds since the UNIX epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ingress_end: Option<u64>,
+}
+
+impl ConstructionPayloadsRequestMetadata {
+    pub fn from_json(value: Option<serde_json::Value>) -> Result<Self, Error> {
+        match value {
+            None => Ok(Self::default()),
+            Some(value) => serde_json::from_value(value)
+                .map_err(|e| Error::invalid_request(format!("Invalid metadata: {}", e))),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("bug: failed to serialize the metadata")
+    }
+}
+
+/// The intent of a transfer, as described by the Rosetta operations.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Transfer {
+    pub from: Account,
+    pub to: Account,
+    pub amount: u64,
+    /// The fee that the caller expects to pay. The ledger rejects the
+    /// transfer if it doesn't match the current fee.
+    pub fee: Option<u64>,
+}
+
+/// An unsigned transaction contains one `icrc1_transfer` call per ingress
+/// interval. All the calls carry the same argument, so the ledger
+/// deduplicates them if more than one is submitted.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct UnsignedTransaction {
+    pub updates: Vec<HttpCanisterUpdate>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct EnvelopePair {
+    pub update: HttpRequestEnvelope<HttpCallContent>,
+    pub read_state: HttpRequestEnvelope<HttpReadStateContent>,
+}
+
+impl EnvelopePair {
+    pub fn update_content(&self) -> &HttpCanisterUpdate {
+        let HttpCallContent::Call { update } = &self.update.content;
+        update
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SignedTransaction {
+    pub envelope_pairs: Vec<EnvelopePair>,
+}
+
+fn to_hex_cbor<T: Serialize>(value: &T) -> String {
+    hex::encode(serde_cbor::to_vec(value).expect("bug: failed to encode a transaction"))
+}
+
+fn from_hex_cbor<T: for<'a> Deserialize<'a>>(s: &str) -> Result<T, Error> {
+    let bytes = hex::decode(s)
+        .map_err(|e| Error::invalid_request(format!("Transaction is not hex encoded: {}", e)))?;
+    serde_cbor::from_slice(&bytes)
+        .map_err(|e| Error::invalid_request(format!("Could not decode the transaction: {}", e)))
+}
+
+impl fmt::Display for UnsignedTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", to_hex_cbor(self))
+    }
+}
+
+impl FromStr for UnsignedTransaction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tx: Self = from_hex_cbor(s)?;
+        if tx.updates.is_empty() {
+            return Err(Error::invalid_request("The transaction contains no calls"));
+        }
+        Ok(tx)
+    }
+}
+
+impl fmt::Display for SignedTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", to_hex_cbor(self))
+    }
+}
+
+impl FromStr for SignedTransaction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tx: Self = from_hex_cbor(s)?;
+        if tx.envelope_pairs.is_empty() {
+            return Err(Error::invalid_request(
+                "The transaction contains no envelopes",
+            ));
+        }
+        Ok(tx)
+    }
+}
@@ -0,0 +1,303 @@
+use crate::common::types::{
+    AccountIdentifier, Amount, Currency, CurveType, Error, Operation, OperationIdentifier,
+    PublicKey, SubAccountIdentifier,
+};
+use crate::construction_api::types::{
+    Transfer, OPERATION_TYPE_FEE, OPERATION_TYPE_TRANSACTION, TRANSFER_METHOD_NAME,
+};
+use candid::{Decode, Nat, Principal};
+use ic_crypto_tree_hash::Path;
+use ic_icrc1::Transaction;
+use ic_ledger_canister_core::ledger::LedgerTransaction;
+use ic_types::crypto::DOMAIN_IC_REQUEST;
+use ic_types::messages::{HttpCanisterUpdate, HttpReadState, MessageId};
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use icrc_ledger_types::icrc1::transfer::{Memo, TransferArg};
+use num_traits::ToPrimitive;
+use serde_bytes::ByteBuf;
+
+pub fn account_identifier_from_account(account: &Account) -> AccountIdentifier {
+    // The ledger treats the default subaccount and no subaccount the same
+    // way, so only non-default subaccounts are part of the identifier.
+    let sub_account = account
+        .subaccount
+        .filter(|subaccount| subaccount != &Subaccount::default())
+        .map(|subaccount| SubAccountIdentifier {
+            address: hex::encode(subaccount),
+            metadata: None,
+        });
+    AccountIdentifier {
+        address: account.owner.to_text(),
+        sub_account,
+        metadata: None,
+    }
+}
+
+pub fn account_from_account_identifier(
+    account_identifier: &AccountIdentifier,
+) -> Result<Account, Error> {
+    let owner = Principal::from_text(&account_identifier.address).map_err(|e| {
+        Error::invalid_request(format!(
+            "Invalid account address {}: {}",
+            account_identifier.address, e
+        ))
+    })?;
+    let subaccount = match &account_identifier.sub_account {
+        None => None,
+        Some(sub_account) => {
+            let bytes = hex::decode(&sub_account.address).map_err(|e| {
+                Error::invalid_request(format!("Invalid subaccount {}: {}", sub_account.address, e))
+            })?;
+            let subaccount = Subaccount::try_from(bytes.as_slice()).map_err(|_| {
+                Error::invalid_request(format!(
+                    "Invalid subaccount {}: expected 32 bytes, got {}",
+                    sub_account.address,
+                    bytes.len()
+                ))
+            })?;
+            Some(subaccount)
+        }
+    };
+    Ok(Account { owner, subaccount })
+}
+
+/// Returns the DER encoding of the given public key.
+pub fn der_encode_public_key(public_key: &PublicKey) -> Result<Vec<u8>, Error> {
+    let bytes = hex::decode(&public_key.hex_bytes)
+        .map_err(|e| Error::invalid_request(format!("Public key is not hex encoded: {}", e)))?;
+    match public_key.curve_type {
+        CurveType::Edwards25519 => Ok(ic_canister_client_sender::ed25519_public_key_to_der(bytes)),
+        CurveType::Secp256K1 => ic_crypto_ecdsa_secp256k1::PublicKey::deserialize_sec1(&bytes)
+            .map(|pk| pk.serialize_der())
+            .map_err(|e| Error::invalid_request(format!("Invalid secp256k1 public key: {:?}", e))),
+    }
+}
+
+pub fn principal_from_public_key(public_key: &PublicKey) -> Result<Principal, Error> {
+    Ok(Principal::self_authenticating(der_encode_public_key(
+        public_key,
+    )?))
+}
+
+/// Returns the bytes that the sender must sign to authenticate the message
+/// with the given id.
+pub fn make_sig_data(message_id: &MessageId) -> Vec<u8> {
+    let mut sig_data = vec![];
+    sig_data.extend_from_slice(DOMAIN_IC_REQUEST);
+    sig_data.extend_from_slice(message_id.as_bytes());
+    sig_data
+}
+
+pub fn make_read_state_from_update(update: &HttpCanisterUpdate) -> HttpReadState {
+    let path = Path::new(vec!["request_status".into(), update.id().into()]);
+    HttpReadState {
+        sender: update.sender.clone(),
+        paths: vec![path],
+        nonce: None,
+        ingress_expiry: update.ingress_expiry,
+    }
+}
+
+fn amount_value(amount: &Option<Amount>, currency: &Currency) -> Result<i128, Error> {
+    let amount = amount
+        .as_ref()
+        .ok_or_else(|| Error::invalid_request("Operation is missing the amount"))?;
+    if amount.currency != *currency {
+        return Err(Error::invalid_request(format!(
+            "Invalid currency {:?}, expected {:?}",
+            amount.currency, currency
+        )));
+    }
+    amount
+        .value
+        .parse()
+        .map_err(|e| Error::invalid_request(format!("Invalid amount {}: {}", amount.value, e)))
+}
+
+fn operation_account(operation: &Operation) -> Result<Account, Error> {
+    operation
+        .account
+        .as_ref()
+        .ok_or_else(|| Error::invalid_request("Operation is missing the account"))
+        .and_then(account_from_account_identifier)
+}
+
+fn to_u64(value: i128) -> Result<u64, Error> {
+    u64::try_from(value)
+        .map_err(|_| Error::invalid_request(format!("Amount {} is out of range", value)))
+}
+
+/// Converts the operations of a construction request into a transfer.
+/// A transfer consists of two operations of type TRANSACTION, one that
+/// debits the source account and one that credits the destination account,
+/// and an optional operation of type FEE that debits the source account.
+pub fn operations_to_transfer(
+    operations: &[Operation],
+    currency: &Currency,
+) -> Result<Transfer, Error> {
+    let mut debit = None;
+    let mut credit = None;
+    let mut fee = None;
+    for operation in operations {
+        let account = operation_account(operation)?;
+        let value = amount_value(&operation.amount, currency)?;
+        match operation._type.as_str() {
+            OPERATION_TYPE_TRANSACTION if value < 0 && debit.is_none() => {
+                debit = Some((account, to_u64(-value)?));
+            }
+            OPERATION_TYPE_TRANSACTION if value >= 0 && credit.is_none() => {
+                credit = Some((account, to_u64(value)?));
+            }
+            OPERATION_TYPE_FEE if value <= 0 && fee.is_none() => {
+                fee = Some((account, to_u64(-value)?));
+            }
+            _ => {
+                return Err(Error::invalid_request(format!(
+                    "Unexpected operation {} with amount {}",
+                    operation._type, value
+                )))
+            }
+        }
+    }
+
+    let (from, debit_amount) =
+        debit.ok_or_else(|| Error::invalid_request("The transfer has no debit operation"))?;
+    let (to, amount) =
+        credit.ok_or_else(|| Error::invalid_request("The transfer has no credit operation"))?;
+    if debit_amount != amount {
+        return Err(Error::invalid_request(format!(
+            "The debited amount {} does not match the credited amount {}",
+            debit_amount, amount
+        )));
+    }
+    let fee = match fee {
+        Some((fee_payer, _)) if fee_payer != from => {
+            return Err(Error::invalid_request(format!(
+                "The fee must be paid by the source account {}, not by {}",
+                from, fee_payer
+            )))
+        }
+        Some((_, fee)) => Some(fee),
+        None => None,
+    };
+    Ok(Transfer {
+        from,
+        to,
+        amount,
+        fee,
+    })
+}
+
+fn make_amount(value: i128, currency: &Currency) -> Option<Amount> {
+    Some(Amount {
+        value: value.to_string(),
+        currency: currency.clone(),
+        metadata: None,
+    })
+}
+
+fn make_operation(
+    index: u64,
+    operation_type: &str,
+    account: &Account,
+    value: i128,
+    currency: &Currency,
+) -> Operation {
+    Operation {
+        operation_identifier: OperationIdentifier {
+            index,
+            network_index: None,
+        },
+        related_operations: None,
+        _type: operation_type.to_string(),
+        status: None,
+        account: Some(account_identifier_from_account(account)),
+        amount: make_amount(value, currency),
+        metadata: None,
+    }
+}
+
+/// The inverse of [operations_to_transfer].
+pub fn transfer_to_operations(transfer: &Transfer, currency: &Currency) -> Vec<Operation> {
+    let amount = transfer.amount as i128;
+    let mut operations = vec![
+        make_operation(
+            0,
+            OPERATION_TYPE_TRANSACTION,
+            &transfer.from,
+            -amount,
+            currency,
+        ),
+        make_operation(
+            1,
+            OPERATION_TYPE_TRANSACTION,
+            &transfer.to,
+            amount,
+            currency,
+        ),
+    ];
+    if let Some(fee) = transfer.fee {
+        operations.push(make_operation(
+            2,
+            OPERATION_TYPE_FEE,
+            &transfer.from,
+            -(fee as i128),
+            currency,
+        ));
+    }
+    operations
+}
+
+pub fn decode_memo(memo: &str) -> Result<Memo, Error> {
+    let bytes = hex::decode(memo)
+        .map_err(|e| Error::invalid_request(format!("Memo is not hex encoded: {}", e)))?;
+    Memo::try_from(ByteBuf::from(bytes)).map_err(Error::invalid_request)
+}
+
+fn nat_to_u64(n: &Nat) -> Result<u64, Error> {
+    n.0.to_u64()
+        .ok_or_else(|| Error::invalid_request(format!("{} does not fit in a u64", n)))
+}
+
+/// Decodes the `icrc1_transfer` call of an unsigned or signed transaction.
+pub fn transfer_from_update(update: &HttpCanisterUpdate) -> Result<(Transfer, TransferArg), Error> {
+    if update.method_name != TRANSFER_METHOD_NAME {
+        return Err(Error::invalid_request(format!(
+            "Unexpected method {}, expected {}",
+            update.method_name, TRANSFER_METHOD_NAME
+        )));
+    }
+    let owner = Principal::try_from_slice(&update.sender.0)
+        .map_err(|e| Error::invalid_request(format!("Invalid sender: {}", e)))?;
+    let arg = Decode!(&update.arg.0, TransferArg)
+        .map_err(|e| Error::invalid_request(format!("Invalid transfer argument: {}", e)))?;
+    let transfer = Transfer {
+        from: Account {
+            owner,
+            subaccount: arg.from_subaccount,
+        },
+        to: arg.to.clone(),
+        amount: nat_to_u64(&arg.amount)?,
+        fee: arg.fee.as_ref().map(nat_to_u64).transpose()?,
+    };
+    Ok((transfer, arg))
+}
+
+/// Returns the hash of the ledger transaction that the given call creates.
+/// The hash matches the one computed by the ledger as long as the
+/// destination is not the minting account.
+pub fn transaction_hash(update: &HttpCanisterUpdate) -> Result<String, Error> {
+    let (transfer, arg) = transfer_from_update(update)?;
+    let transaction = Transaction {
+        operation: ic_icrc1::Operation::Transfer {
+            from: transfer.from,
+            to: transfer.to,
+            spender: None,
+            amount: transfer.amount,
+            fee: transfer.fee,
+        },
+        created_at_time: arg.created_at_time,
+        memo: arg.memo,
+    };
+    Ok(hex::encode(transaction.hash().as_slice()))
+}
@@ -3,11 +3,21 @@ use std::sync::Arc;
 use axum::{extract::State, http::StatusCode, response::Result, Json};
 use ic_icrc_rosetta::{
     common::types::{
-        Allow, Error, MetadataRequest, NetworkIdentifier, NetworkListResponse,
-        NetworkOptionsResponse, NetworkRequest, Version,
+        Allow, ConstructionCombineRequest, ConstructionCombineResponse, ConstructionDeriveRequest,
+        ConstructionDeriveResponse, ConstructionHashRequest, ConstructionMetadataRequest,
+        ConstructionMetadataResponse, ConstructionParseRequest, ConstructionParseResponse,
+        ConstructionPayloadsRequest, ConstructionPayloadsResponse, ConstructionPreprocessRequest,
+        ConstructionPreprocessResponse, ConstructionSubmitRequest, Currency, Error,
+        MetadataRequest, NetworkIdentifier, NetworkListResponse, NetworkOptionsResponse,
+        NetworkRequest, TransactionIdentifierResponse, Version,
+    },
+    construction_api::{
+        services,
+        types::{OPERATION_TYPE_FEE, OPERATION_TYPE_TRANSACTION},
     },
     AppState,
 };
+use icrc_ledger_agent::CallMode;
 
 const ROSETTA_VERSION: &str = "1.4.13";
 const NODE_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -20,6 +30,38 @@ fn verify_network_id(network_identifier: &NetworkIdentifier, state: &AppState) -
     Ok(())
 }
 
+async fn currency(state: &AppState) -> Result<Currency> {
+    let currency = state
+        .currency
+        .get_or_try_init(|| async {
+            let symbol = state
+                .icrc1_agent
+                .symbol(CallMode::Query)
+                .await
+                .map_err(|e| {
+                    Error::ledger_communication(format!("Unable to fetch the symbol: {:?}", e))
+                })?;
+            let decimals = state
+                .icrc1_agent
+                .decimals(CallMode::Query)
+                .await
+                .map_err(|e| {
+                    Error::ledger_communication(format!("Unable to fetch the decimals: {:?}", e))
+                })?;
+            Ok::<_, Error>(Currency {
+                symbol,
+                decimals: decimals as i32,
+                metadata: None,
+            })
+        })
+        .await?;
+    Ok(currency.clone())
+}
+
+fn current_time_nanos() -> u64 {
+    ic_types::time::current_time().as_nanos_since_unix_epoch()
+}
+
 pub async fn health() -> (StatusCode, Json<()>) {
     (StatusCode::OK, Json(()))
 }
@@ -47,10 +89,17 @@ pub async fn network_options(
         },
         allow: Allow {
             operation_statuses: vec![],
-            operation_types: vec![],
-            errors: vec![Error::invalid_network_id(
-                &NetworkIdentifier::for_ledger_id(state.ledger_id),
-            )],
+            operation_types: vec![
+                OPERATION_TYPE_TRANSACTION.to_string(),
+                OPERATION_TYPE_FEE.to_string(),
+            ],
+            errors: vec![
+                Error::invalid_network_id(&NetworkIdentifier::for_ledger_id(state.ledger_id)),
+                Error::invalid_request("The request is invalid"),
+                Error::ledger_communication("The ledger could not be reached"),
+                Error::transaction_rejected("The ledger rejected the transaction"),
+                Error::transaction_expired(),
+            ],
             historical_balance_lookup: true,
             timestamp_start_index: None,
             call_methods: vec![],
@@ -61,3 +110,102 @@ pub async fn network_options(
         },
     }))
 }
+
+pub async fn construction_derive(
+    State(state): State<Arc<AppState>>,
+    request: Json<ConstructionDeriveRequest>,
+) -> Result<Json<ConstructionDeriveResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    Ok(Json(services::construction_derive(&request.public_key)?))
+}
+
+pub async fn construction_preprocess(
+    State(state): State<Arc<AppState>>,
+    request: Json<ConstructionPreprocessRequest>,
+) -> Result<Json<ConstructionPreprocessResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    let currency = currency(&state).await?;
+    Ok(Json(services::construction_preprocess(
+        &request.operations,
+        request.0.metadata,
+        &currency,
+    )?))
+}
+
+pub async fn construction_metadata(
+    State(state): State<Arc<AppState>>,
+    request: Json<ConstructionMetadataRequest>,
+) -> Result<Json<ConstructionMetadataResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    let currency = currency(&state).await?;
+    Ok(Json(
+        services::construction_metadata(request.0.options, &state.icrc1_agent, &currency).await?,
+    ))
+}
+
+pub async fn construction_payloads(
+    State(state): State<Arc<AppState>>,
+    request: Json<ConstructionPayloadsRequest>,
+) -> Result<Json<ConstructionPayloadsResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    let currency = currency(&state).await?;
+    let request = request.0;
+    Ok(Json(services::construction_payloads(
+        &request.operations,
+        request.metadata,
+        &request.public_keys.unwrap_or_default(),
+        state.ledger_id.get().0,
+        &currency,
+        current_time_nanos(),
+    )?))
+}
+
+pub async fn construction_combine(
+    State(state): State<Arc<AppState>>,
+    request: Json<ConstructionCombineRequest>,
+) -> Result<Json<ConstructionCombineResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    Ok(Json(services::construction_combine(
+        &request.unsigned_transaction,
+        &request.signatures,
+    )?))
+}
+
+pub async fn construction_parse(
+    State(state): State<Arc<AppState>>,
+    request: Json<ConstructionParseRequest>,
+) -> Result<Json<ConstructionParseResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    let currency = currency(&state).await?;
+    Ok(Json(services::construction_parse(
+        &request.transaction,
+        request.signed,
+        &currency,
+    )?))
+}
+
+pub async fn construction_hash(
+    State(state): State<Arc<AppState>>,
+    request: Json<ConstructionHashRequest>,
+) -> Result<Json<TransactionIdentifierResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    Ok(Json(services::construction_hash(
+        &request.signed_transaction,
+    )?))
+}
+
+pub async fn construction_submit(
+    State(state): State<Arc<AppState>>,
+    request: Json<ConstructionSubmitRequest>,
+) -> Result<Json<TransactionIdentifierResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    Ok(Json(
+        services::construction_submit(
+            &request.signed_transaction,
+            state.ledger_id.get().0,
+            &state.icrc1_agent,
+            current_time_nanos(),
+        )
+        .await?,
+    ))
+}
@@ -293,6 +293,21 @@ async fn fetch_blocks_interval(
     Ok(result)
 }
 
+/// Decodes the last block index from the hash tree. Ledgers encode it as
+/// LEB128 as specified by ICRC-3, older ledgers as 8 big-endian bytes.
+fn decode_last_block_index(bytes: &[u8]) -> anyhow::Result<u64> {
+    let mut reader = bytes;
+    if let Ok(index) = leb128::read::unsigned(&mut reader) {
+        if reader.is_empty() {
+            return Ok(index);
+        }
+    }
+    let bytes: [u8; 8] = bytes.try_into().map_err(|_| {
+        anyhow::Error::msg(format!("Invalid last block index encoding: {:?}", bytes))
+    })?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
 /// Fetches the data certificate from the ledger and validates it
 /// Returns the tip index and hash of the ledger
 async fn fetch_blockchain_tip_data(agent: Arc<Icrc1Agent>) -> anyhow::Result<(u64, Hash)> {
@@ -309,13 +324,7 @@ async fn fetch_blockchain_tip_data(agent: Arc<Icrc1Agent>) -> anyhow::Result<(u6
     // Extract the last block index from the hash tree
     let last_block_index = match hash_tree.lookup(&[b"last_block_index"]) {
         Found(x) => match x {
-            MixedHashTree::Leaf(l) => {
-                let mut bytes: [u8; 8] = [0u8; 8];
-                for (i, e) in l.iter().enumerate() {
-                    bytes[i] = *e;
-                }
-                Ok(u64::from_be_bytes(bytes))
-            }
+            MixedHashTree::Leaf(l) => decode_last_block_index(l),
             _ => Err(anyhow::Error::msg(
                 "Last block index was found, but MixedHashTree is no a Leaf",
             )),
@@ -387,6 +396,20 @@ mod tests {
     use rand::seq::SliceRandom;
     use serde_bytes::ByteBuf;
 
+    #[test]
+    fn test_decode_last_block_index() {
+        for index in [0, 1, 127, 128, 1_000_000, u64::MAX] {
+            let mut leb128_bytes = vec![];
+            leb128::write::unsigned(&mut leb128_bytes, index).unwrap();
+            assert_eq!(decode_last_block_index(&leb128_bytes).unwrap(), index);
+            assert_eq!(
+                decode_last_block_index(&index.to_be_bytes()).unwrap(),
+                index
+            );
+        }
+        assert!(decode_last_block_index(&[0x80]).is_err());
+    }
+
     proptest! {
             #[test]
         fn test_valid_blockchain(blockchain in valid_blockchain_strategy(1000)){
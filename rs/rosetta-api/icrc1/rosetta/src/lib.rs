@@ -1,12 +1,19 @@
 use common::storage::storage_client::StorageClient;
+use common::types::Currency;
 use ic_base_types::CanisterId;
+use icrc_ledger_agent::Icrc1Agent;
 use std::sync::Arc;
+use tokio::sync::OnceCell;
 
 pub mod common;
+pub mod construction_api;
 
 pub mod ledger_blocks_synchronization;
 
 pub struct AppState {
     pub ledger_id: CanisterId,
+    pub icrc1_agent: Arc<Icrc1Agent>,
+    /// The symbol and decimals of the ledger, fetched on first use.
+    pub currency: OnceCell<Currency>,
     pub _storage: Arc<StorageClient>,
 }
@@ -5,7 +5,11 @@ use axum::{
     Router,
 };
 use clap::{Parser, ValueEnum};
-use endpoints::{health, network_list, network_options};
+use endpoints::{
+    construction_combine, construction_derive, construction_hash, construction_metadata,
+    construction_parse, construction_payloads, construction_preprocess, construction_submit,
+    health, network_list, network_options,
+};
 use http::Request;
 use ic_agent::{
     agent::http_transport::ReqwestHttpReplicaV2Transport, identity::AnonymousIdentity, Agent,
@@ -16,6 +20,7 @@ use icrc_ledger_agent::Icrc1Agent;
 use lazy_static::lazy_static;
 use std::path::PathBuf;
 use std::{net::TcpListener, sync::Arc};
+use tokio::sync::OnceCell;
 use tower_http::classify::{ServerErrorsAsFailures, SharedClassifier};
 use tower_http::trace::TraceLayer;
 use tower_request_id::{RequestId, RequestIdLayer};
@@ -150,11 +155,6 @@ async fn main() -> Result<()> {
         StoreType::File => StorageClient::new_persistent(&args.store_file)?,
     });
 
-    let shared_state = Arc::new(AppState {
-        ledger_id: args.ledger_id,
-        _storage: storage.clone(),
-    });
-
     let network_url = args.effective_network_url();
 
     let ic_agent = Agent::builder()
@@ -178,15 +178,30 @@ async fn main() -> Result<()> {
         ic_agent.status().await?.replica_health_status
     );
 
-    let _icrc1_agent = Icrc1Agent {
+    let icrc1_agent = Arc::new(Icrc1Agent {
         agent: ic_agent,
         ledger_canister_id: args.ledger_id.into(),
-    };
+    });
+
+    let shared_state = Arc::new(AppState {
+        ledger_id: args.ledger_id,
+        icrc1_agent,
+        currency: OnceCell::new(),
+        _storage: storage.clone(),
+    });
 
     let app = Router::new()
         .route("/health", get(health))
         .route("/network/list", post(network_list))
         .route("/network/options", post(network_options))
+        .route("/construction/derive", post(construction_derive))
+        .route("/construction/preprocess", post(construction_preprocess))
+        .route("/construction/metadata", post(construction_metadata))
+        .route("/construction/payloads", post(construction_payloads))
+        .route("/construction/combine", post(construction_combine))
+        .route("/construction/parse", post(construction_parse))
+        .route("/construction/hash", post(construction_hash))
+        .route("/construction/submit", post(construction_submit))
         // This layer creates a span for each http request and attaches
         // the request_id, HTTP Method and path to it.
         .layer(add_request_span())
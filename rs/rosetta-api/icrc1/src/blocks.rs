@@ -3,6 +3,7 @@ use ciborium::value::Value as CiboriumValue;
 use ic_ledger_core::block::EncodedBlock;
 use icrc_ledger_types::icrc::generic_value::Value as GenericValue;
 use icrc_ledger_types::icrc3::blocks::GenericBlock;
+use num_traits::ToPrimitive;
 use serde_bytes::ByteBuf;
 use std::collections::BTreeMap;
 
@@ -49,3 +50,47 @@ fn icrc1_block_from_value(value: &CiboriumValue) -> GenericBlock {
         _ => panic!("unsupported value type: {:?}", value),
     }
 }
+
+/// Converts a generic block back into its CBOR encoding.
+///
+/// This is the inverse of [icrc1_block_from_encoded].
+pub fn encoded_block_from_generic_block(block: GenericBlock) -> Result<EncodedBlock, String> {
+    // Tag for Self-described CBOR; see Section 3.4.6 https://www.rfc-editor.org/rfc/rfc8949.html
+    const SELF_DESCRIBED_CBOR_TAG: u64 = 55799;
+
+    let value = CiboriumValue::Tag(
+        SELF_DESCRIBED_CBOR_TAG,
+        Box::new(icrc1_block_to_value(block)?),
+    );
+    let mut bytes = vec![];
+    ciborium::ser::into_writer(&value, &mut bytes)
+        .map_err(|e| format!("failed to encode a block: {}", e))?;
+    Ok(EncodedBlock::from_vec(bytes))
+}
+
+fn icrc1_block_to_value(value: GenericValue) -> Result<CiboriumValue, String> {
+    match value {
+        GenericValue::Nat(nat) => {
+            let uv = nat
+                .0
+                .to_u64()
+                .ok_or_else(|| format!("nat {} in icrc1 block does not fit into u64", nat))?;
+            Ok(CiboriumValue::Integer(uv.into()))
+        }
+        GenericValue::Nat64(uv) => Ok(CiboriumValue::Integer(uv.into())),
+        GenericValue::Int(int) => Err(format!("int {} is not supported in icrc1 blocks", int)),
+        GenericValue::Blob(bytes) => Ok(CiboriumValue::Bytes(bytes.into_vec())),
+        GenericValue::Text(text) => Ok(CiboriumValue::Text(text)),
+        GenericValue::Array(values) => Ok(CiboriumValue::Array(
+            values
+                .into_iter()
+                .map(icrc1_block_to_value)
+                .collect::<Result<_, _>>()?,
+        )),
+        GenericValue::Map(map) => Ok(CiboriumValue::Map(
+            map.into_iter()
+                .map(|(k, v)| icrc1_block_to_value(v).map(|v| (CiboriumValue::Text(k), v)))
+                .collect::<Result<_, _>>()?,
+        )),
+    }
+}
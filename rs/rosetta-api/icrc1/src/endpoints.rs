@@ -3,7 +3,9 @@ use candid::types::number::Nat;
 use candid::CandidType;
 use ic_ledger_canister_core::ledger::TransferError as CoreTransferError;
 use icrc_ledger_types::icrc1::transfer::TransferError;
-use icrc_ledger_types::icrc3::transactions::{Burn, Mint, Transaction, Transfer};
+use icrc_ledger_types::icrc2::approve::ApproveError;
+use icrc_ledger_types::icrc2::transfer_from::TransferFromError;
+use icrc_ledger_types::icrc3::transactions::{Approve, Burn, Mint, Transaction, Transfer};
 use serde::Deserialize;
 
 pub fn convert_transfer_error(err: CoreTransferError) -> TransferError {
@@ -25,8 +27,70 @@ pub fn convert_transfer_error(err: CoreTransferError) -> TransferError {
         LTE::TxDuplicate { duplicate_of } => TE::Duplicate {
             duplicate_of: Nat::from(duplicate_of),
         },
-        LTE::InsufficientAllowance { .. } => todo!(),
-        LTE::ExpiredApproval { .. } => todo!(),
+        LTE::InsufficientAllowance { .. }
+        | LTE::ExpiredApproval { .. }
+        | LTE::AllowanceChanged { .. } => {
+            unreachable!("icrc1_transfer does not use approvals")
+        }
+    }
+}
+
+pub fn convert_approve_error(err: CoreTransferError) -> ApproveError {
+    use ic_ledger_canister_core::ledger::TransferError as LTE;
+    use ApproveError as AE;
+
+    match err {
+        LTE::BadFee { expected_fee } => AE::BadFee {
+            expected_fee: Nat::from(expected_fee.get_e8s()),
+        },
+        LTE::InsufficientFunds { balance } => AE::InsufficientFunds {
+            balance: Nat::from(balance.get_e8s()),
+        },
+        LTE::AllowanceChanged { current_allowance } => AE::AllowanceChanged {
+            current_allowance: Nat::from(current_allowance.get_e8s()),
+        },
+        LTE::ExpiredApproval { ledger_time } => AE::Expired {
+            ledger_time: ledger_time.as_nanos_since_unix_epoch(),
+        },
+        LTE::TxTooOld { .. } => AE::TooOld,
+        LTE::TxCreatedInFuture { ledger_time } => AE::CreatedInFuture {
+            ledger_time: ledger_time.as_nanos_since_unix_epoch(),
+        },
+        LTE::TxThrottled => AE::TemporarilyUnavailable,
+        LTE::TxDuplicate { duplicate_of } => AE::Duplicate {
+            duplicate_of: Nat::from(duplicate_of),
+        },
+        LTE::InsufficientAllowance { .. } => {
+            unreachable!("icrc2_approve does not use allowances")
+        }
+    }
+}
+
+pub fn convert_transfer_from_error(err: CoreTransferError) -> TransferFromError {
+    use ic_ledger_canister_core::ledger::TransferError as LTE;
+    use TransferFromError as TFE;
+
+    match err {
+        LTE::BadFee { expected_fee } => TFE::BadFee {
+            expected_fee: Nat::from(expected_fee.get_e8s()),
+        },
+        LTE::InsufficientFunds { balance } => TFE::InsufficientFunds {
+            balance: Nat::from(balance.get_e8s()),
+        },
+        LTE::InsufficientAllowance { allowance } => TFE::InsufficientAllowance {
+            allowance: Nat::from(allowance.get_e8s()),
+        },
+        LTE::TxTooOld { .. } => TFE::TooOld,
+        LTE::TxCreatedInFuture { ledger_time } => TFE::CreatedInFuture {
+            ledger_time: ledger_time.as_nanos_since_unix_epoch(),
+        },
+        LTE::TxThrottled => TFE::TemporarilyUnavailable,
+        LTE::TxDuplicate { duplicate_of } => TFE::Duplicate {
+            duplicate_of: Nat::from(duplicate_of),
+        },
+        LTE::ExpiredApproval { .. } | LTE::AllowanceChanged { .. } => {
+            unreachable!("icrc2_transfer_from does not create approvals")
+        }
     }
 }
 
@@ -47,6 +111,7 @@ impl From<Block> for Transaction {
             mint: None,
             burn: None,
             transfer: None,
+            approve: None,
             timestamp: b.timestamp,
         };
         let created_at_time = b.transaction.created_at_time;
@@ -62,10 +127,15 @@ impl From<Block> for Transaction {
                     memo,
                 });
             }
-            Operation::Burn { from, amount } => {
+            Operation::Burn {
+                from,
+                spender,
+                amount,
+            } => {
                 tx.kind = "burn".to_string();
                 tx.burn = Some(Burn {
                     from,
+                    spender,
                     amount: Nat::from(amount),
                     created_at_time,
                     memo,
@@ -74,6 +144,7 @@ impl From<Block> for Transaction {
             Operation::Transfer {
                 from,
                 to,
+                spender,
                 amount,
                 fee,
             } => {
@@ -81,7 +152,30 @@ impl From<Block> for Transaction {
                 tx.transfer = Some(Transfer {
                     from,
                     to,
+                    spender,
+                    amount: Nat::from(amount),
+                    fee: fee
+                        .map(Nat::from)
+                        .or_else(|| b.effective_fee.map(Nat::from)),
+                    created_at_time,
+                    memo,
+                });
+            }
+            Operation::Approve {
+                from,
+                spender,
+                amount,
+                expected_allowance,
+                expires_at,
+                fee,
+            } => {
+                tx.kind = "approve".to_string();
+                tx.approve = Some(Approve {
+                    from,
+                    spender,
                     amount: Nat::from(amount),
+                    expected_allowance: expected_allowance.map(Nat::from),
+                    expires_at,
                     fee: fee
                         .map(Nat::from)
                         .or_else(|| b.effective_fee.map(Nat::from)),
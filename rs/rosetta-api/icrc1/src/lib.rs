@@ -3,10 +3,11 @@ mod compact_account;
 pub mod endpoints;
 pub mod hash;
 
+use candid::Nat;
 use ciborium::tag::Required;
-use ic_base_types::PrincipalId;
 use ic_ledger_canister_core::ledger::{LedgerContext, LedgerTransaction, TxApplyError};
 use ic_ledger_core::{
+    approvals::Approvals,
     balances::Balances,
     block::{BlockType, EncodedBlock, FeeCollector, HashOf},
     timestamp::TimeStamp,
@@ -35,6 +36,9 @@ pub enum Operation {
         from: Account,
         #[serde(with = "compact_account")]
         to: Account,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[serde(with = "compact_account::opt")]
+        spender: Option<Account>,
         #[serde(rename = "amt")]
         amount: u64,
         #[serde(skip_serializing_if = "Option::is_none")]
@@ -44,9 +48,27 @@ pub enum Operation {
     Burn {
         #[serde(with = "compact_account")]
         from: Account,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[serde(with = "compact_account::opt")]
+        spender: Option<Account>,
         #[serde(rename = "amt")]
         amount: u64,
     },
+    #[serde(rename = "approve")]
+    Approve {
+        #[serde(with = "compact_account")]
+        from: Account,
+        #[serde(with = "compact_account")]
+        spender: Account,
+        #[serde(rename = "amt")]
+        amount: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expected_allowance: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expires_at: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        fee: Option<u64>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Hash, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -66,7 +88,7 @@ pub struct Transaction {
 
 impl LedgerTransaction for Transaction {
     type AccountId = Account;
-    type SpenderId = PrincipalId;
+    type SpenderId = Account;
 
     fn burn(
         from: Account,
@@ -77,6 +99,7 @@ impl LedgerTransaction for Transaction {
         Self {
             operation: Operation::Burn {
                 from,
+                spender: None,
                 amount: amount.get_e8s(),
             },
             created_at_time: created_at_time.map(|t| t.as_nanos_since_unix_epoch()),
@@ -107,11 +130,11 @@ impl LedgerTransaction for Transaction {
     fn apply<C>(
         &self,
         context: &mut C,
-        _now: TimeStamp,
+        now: TimeStamp,
         effective_fee: Tokens,
     ) -> Result<(), TxApplyError>
     where
-        C: LedgerContext<AccountId = Self::AccountId>,
+        C: LedgerContext<AccountId = Self::AccountId, SpenderId = Self::SpenderId>,
     {
         let fee_collector = context.fee_collector().map(|fc| fc.fee_collector);
         let fee_collector = fee_collector.as_ref();
@@ -119,21 +142,108 @@ impl LedgerTransaction for Transaction {
             Operation::Transfer {
                 from,
                 to,
+                spender,
                 amount,
                 fee,
-            } => context.balances_mut().transfer(
+            } => {
+                let amount = Tokens::from_e8s(*amount);
+                let fee = fee.map(Tokens::from_e8s).unwrap_or(effective_fee);
+                match spender {
+                    // NB. The allowance must cover both the amount and the fee.
+                    Some(spender) if spender != from => {
+                        let allowance = context.approvals().allowance(from, spender, now);
+                        let used_allowance = amount.saturating_add(fee);
+                        if allowance.amount < used_allowance {
+                            return Err(TxApplyError::InsufficientAllowance {
+                                allowance: allowance.amount,
+                            });
+                        }
+                        context
+                            .balances_mut()
+                            .transfer(from, to, amount, fee, fee_collector)?;
+                        context
+                            .approvals_mut()
+                            .use_allowance(from, spender, used_allowance, now)
+                            .expect("bug: cannot use allowance");
+                    }
+                    _ => context
+                        .balances_mut()
+                        .transfer(from, to, amount, fee, fee_collector)?,
+                }
+            }
+            Operation::Burn {
                 from,
-                to,
-                Tokens::from_e8s(*amount),
-                fee.map(Tokens::from_e8s).unwrap_or(effective_fee),
-                fee_collector,
-            )?,
-            Operation::Burn { from, amount } => context
-                .balances_mut()
-                .burn(from, Tokens::from_e8s(*amount))?,
+                spender,
+                amount,
+            } => {
+                let amount = Tokens::from_e8s(*amount);
+                match spender {
+                    Some(spender) if spender != from => {
+                        let allowance = context.approvals().allowance(from, spender, now);
+                        if allowance.amount < amount {
+                            return Err(TxApplyError::InsufficientAllowance {
+                                allowance: allowance.amount,
+                            });
+                        }
+                        context.balances_mut().burn(from, amount)?;
+                        context
+                            .approvals_mut()
+                            .use_allowance(from, spender, amount, now)
+                            .expect("bug: cannot use allowance");
+                    }
+                    _ => context.balances_mut().burn(from, amount)?,
+                }
+            }
             Operation::Mint { to, amount } => {
                 context.balances_mut().mint(to, Tokens::from_e8s(*amount))?
             }
+            Operation::Approve {
+                from,
+                spender,
+                amount,
+                expected_allowance,
+                expires_at,
+                fee,
+            } => {
+                let amount = Tokens::from_e8s(*amount);
+                let current_allowance = context.approvals().allowance(from, spender, now).amount;
+                if let Some(expected_allowance) = expected_allowance {
+                    if current_allowance != Tokens::from_e8s(*expected_allowance) {
+                        return Err(TxApplyError::AllowanceChanged { current_allowance });
+                    }
+                }
+
+                let fee = fee.map(Tokens::from_e8s).unwrap_or(effective_fee);
+                context.balances_mut().burn(from, fee)?;
+
+                // ICRC-2 approvals replace the current allowance, while the
+                // approvals table can only increase or decrease it.
+                let expires_at = expires_at.map(TimeStamp::from_nanos_since_unix_epoch);
+                let result = if amount >= current_allowance && amount != Tokens::ZERO {
+                    context.approvals_mut().approve(
+                        from,
+                        spender,
+                        amount.saturating_sub(current_allowance),
+                        expires_at,
+                        now,
+                    )
+                } else {
+                    context.approvals_mut().decrease_allowance(
+                        from,
+                        spender,
+                        current_allowance.saturating_sub(amount),
+                        expires_at,
+                        now,
+                    )
+                };
+                if let Err(e) = result {
+                    context
+                        .balances_mut()
+                        .mint(from, fee)
+                        .expect("bug: failed to refund approval fee");
+                    return Err(e.into());
+                }
+            }
         }
         Ok(())
     }
@@ -168,6 +278,7 @@ impl Transaction {
             operation: Operation::Transfer {
                 from,
                 to,
+                spender: None,
                 amount: amount.get_e8s(),
                 fee: fee.map(Tokens::get_e8s),
             },
@@ -175,6 +286,31 @@ impl Transaction {
             memo,
         }
     }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn approve(
+        from: Account,
+        spender: Account,
+        amount: Tokens,
+        expected_allowance: Option<Tokens>,
+        expires_at: Option<TimeStamp>,
+        fee: Option<Tokens>,
+        created_at_time: Option<TimeStamp>,
+        memo: Option<Memo>,
+    ) -> Self {
+        Self {
+            operation: Operation::Approve {
+                from,
+                spender,
+                amount: amount.get_e8s(),
+                expected_allowance: expected_allowance.map(Tokens::get_e8s),
+                expires_at: expires_at.map(|t| t.as_nanos_since_unix_epoch()),
+                fee: fee.map(Tokens::get_e8s),
+            },
+            created_at_time: created_at_time.map(|t| t.as_nanos_since_unix_epoch()),
+            memo,
+        }
+    }
 }
 
 impl TryFrom<icrc_ledger_types::icrc3::transactions::Transaction> for Transaction {
@@ -206,6 +342,7 @@ impl TryFrom<icrc_ledger_types::icrc3::transactions::Transaction> for Transactio
                 .ok_or_else(|| "Could not convert Nat to u64".to_owned())?;
             let operation = Operation::Burn {
                 from: burn.from,
+                spender: burn.spender,
                 amount,
             };
             return Ok(Self {
@@ -231,6 +368,7 @@ impl TryFrom<icrc_ledger_types::icrc3::transactions::Transaction> for Transactio
                         to: transfer.to,
                         amount,
                         from: transfer.from,
+                        spender: transfer.spender,
                         fee: Some(fee),
                     };
                     return Ok(Self {
@@ -244,6 +382,7 @@ impl TryFrom<icrc_ledger_types::icrc3::transactions::Transaction> for Transactio
                         to: transfer.to,
                         amount,
                         from: transfer.from,
+                        spender: transfer.spender,
                         fee: None,
                     };
                     return Ok(Self {
@@ -254,7 +393,26 @@ impl TryFrom<icrc_ledger_types::icrc3::transactions::Transaction> for Transactio
                 }
             }
         }
-        Err("Transaction has neither mint, burn nor transfer operation".to_owned())
+        if let Some(approve) = value.approve {
+            let to_u64 = |n: Nat| {
+                n.0.to_u64()
+                    .ok_or_else(|| "Could not convert Nat to u64".to_owned())
+            };
+            let operation = Operation::Approve {
+                from: approve.from,
+                spender: approve.spender,
+                amount: to_u64(approve.amount)?,
+                expected_allowance: approve.expected_allowance.map(to_u64).transpose()?,
+                expires_at: approve.expires_at,
+                fee: approve.fee.map(to_u64).transpose()?,
+            };
+            return Ok(Self {
+                operation,
+                created_at_time: approve.created_at_time,
+                memo: approve.memo,
+            });
+        }
+        Err("Transaction has neither mint, burn, transfer nor approve operation".to_owned())
     }
 }
 
@@ -331,10 +489,11 @@ impl BlockType for Block {
         effective_fee: Tokens,
         fee_collector: Option<FeeCollector<Self::AccountId>>,
     ) -> Self {
-        let effective_fee = if let Operation::Transfer { fee, .. } = &transaction.operation {
-            fee.is_none().then_some(effective_fee.get_e8s())
-        } else {
-            None
+        let effective_fee = match &transaction.operation {
+            Operation::Transfer { fee, .. } | Operation::Approve { fee, .. } => {
+                fee.is_none().then_some(effective_fee.get_e8s())
+            }
+            _ => None,
         };
         let (fee_collector, fee_collector_block_index) = match fee_collector {
             Some(FeeCollector {
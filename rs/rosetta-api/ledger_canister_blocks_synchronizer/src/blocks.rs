@@ -1,5 +1,6 @@
 use ic_ledger_core::block::{BlockIndex, BlockType, EncodedBlock, HashOf};
-use icp_ledger::{AccountIdentifier, Block, Tokens};
+use ic_ledger_core::timestamp::TimeStamp;
+use icp_ledger::{AccountIdentifier, Block, Memo, Tokens};
 use rusqlite::params;
 use serde::{Deserialize, Serialize};
 use std::convert::TryInto;
@@ -8,14 +9,14 @@ use std::sync::Mutex;
 
 mod database_access {
     use super::vec_into_array;
-    use crate::blocks::{BlockStoreError, HashedBlock};
+    use crate::blocks::{BlockStoreError, HashedBlock, TransactionFilter};
     use ic_ledger_canister_core::ledger::LedgerTransaction;
     use ic_ledger_core::{
         block::{BlockType, EncodedBlock, HashOf},
         Tokens,
     };
-    use icp_ledger::{AccountIdentifier, Block, Operation};
-    use rusqlite::{params, types::Null, Connection, Error, Statement};
+    use icp_ledger::{AccountIdentifier, Block, Memo, Operation};
+    use rusqlite::{params, params_from_iter, types::Value, Connection, Error, Statement};
 
     pub fn push_hashed_block(
         con: &mut Connection,
@@ -43,86 +44,141 @@ mod database_access {
         Ok(())
     }
 
+    pub const INSERT_TRANSACTION: &str = "INSERT INTO transactions (block_idx,tx_hash,operation_type,from_account,to_account,amount,fee,spender_account,allowance,expires_at,memo,icrc1_memo,timestamp) VALUES (?1, ?2, ?3, ?4, ?5,?6,?7,?8,?9,?10,?11,?12,?13)";
+
     pub fn push_transaction(
         connection: &mut Connection,
-        tx: &icp_ledger::Transaction,
+        block: &Block,
         index: &u64,
     ) -> Result<(), BlockStoreError> {
         let mut stmt = connection
-        .prepare("INSERT INTO transactions (block_idx,tx_hash,operation_type,from_account,to_account,amount,fee) VALUES (?1, ?2, ?3, ?4, ?5,?6,?7)")
-        .map_err(|e| BlockStoreError::Other(e.to_string()))?;
-        push_transaction_execution(tx, &mut stmt, index)
+            .prepare(INSERT_TRANSACTION)
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        push_transaction_execution(block, &mut stmt, index)
     }
 
     pub fn push_transaction_execution(
-        tx: &icp_ledger::Transaction,
+        block: &Block,
         stmt: &mut Statement,
         index: &u64,
     ) -> Result<(), BlockStoreError> {
+        let tx = &block.transaction;
         let tx_hash = tx.hash().into_bytes().to_vec();
-        let operation_type = tx.operation.clone();
-        match operation_type {
-            Operation::Burn { from, amount } => {
-                let op_string: &str = operation_type.into();
-                let from_account = from.to_hex();
-                let tokens = amount.get_e8s();
-                let to_account = Null;
-                let fees = Null;
-                stmt.execute(params![
-                    index,
-                    tx_hash,
-                    op_string,
-                    from_account,
-                    to_account,
-                    tokens,
-                    fees
-                ])
-                .map_err(|e| BlockStoreError::Other(e.to_string()))?;
-            }
-            Operation::Mint { to, amount } => {
-                let op_string: &str = operation_type.into();
-                let from_account = Null;
-                let tokens = amount.get_e8s();
-                let to_account = to.to_hex();
-                let fees = Null;
-                stmt.execute(params![
-                    index,
-                    tx_hash,
-                    op_string,
-                    from_account,
-                    to_account,
-                    tokens,
-                    fees
-                ])
-                .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        let op_string: &str = tx.operation.clone().into();
+        let mut spender_account = None;
+        let mut allowance = None;
+        let mut expires_at = None;
+        let (from_account, to_account, tokens, fees) = match &tx.operation {
+            Operation::Burn { from, amount } => (Some(from.to_hex()), None, amount.get_e8s(), None),
+            Operation::Mint { to, amount } => (None, Some(to.to_hex()), amount.get_e8s(), None),
+            Operation::Approve {
+                from,
+                spender,
+                allowance: signed_allowance,
+                expires_at: expiration,
+                fee,
+            } => {
+                spender_account = Some(spender.to_hex());
+                // The change of the allowance is negative if it decreased.
+                allowance = Some(i64::try_from(signed_allowance.to_i128()).map_err(|_| {
+                    BlockStoreError::Other(format!(
+                        "Allowance of block {} does not fit in an i64",
+                        index
+                    ))
+                })?);
+                expires_at = expiration.map(|t| t.as_nanos_since_unix_epoch());
+                // Approvals do not move tokens.
+                (Some(from.to_hex()), None, 0, Some(fee.get_e8s()))
             }
-            Operation::Approve { .. } => todo!(),
-            Operation::TransferFrom { .. } => todo!(),
             Operation::Transfer {
                 from,
                 to,
                 amount,
                 fee,
+            } => (
+                Some(from.to_hex()),
+                Some(to.to_hex()),
+                amount.get_e8s(),
+                Some(fee.get_e8s()),
+            ),
+            Operation::TransferFrom {
+                from,
+                to,
+                spender,
+                amount,
+                fee,
             } => {
-                let op_string: &str = operation_type.into();
-                let from_account = from.to_hex();
-                let tokens = amount.get_e8s();
-                let to_account = to.to_hex();
-                let fees = fee.get_e8s();
-                stmt.execute(params![
-                    index,
-                    tx_hash,
-                    op_string,
-                    from_account,
-                    to_account,
-                    tokens,
-                    fees
-                ])
+                spender_account = Some(spender.to_hex());
+                (
+                    Some(from.to_hex()),
+                    Some(to.to_hex()),
+                    amount.get_e8s(),
+                    Some(fee.get_e8s()),
+                )
+            }
+        };
+        let icrc1_memo = tx.icrc1_memo.as_ref().map(|memo| memo.to_vec());
+        stmt.execute(params![
+            index,
+            tx_hash,
+            op_string,
+            from_account,
+            to_account,
+            tokens,
+            fees,
+            spender_account,
+            allowance,
+            expires_at,
+            memo_to_sql(tx.memo),
+            icrc1_memo,
+            block.timestamp.as_nanos_since_unix_epoch()
+        ])
+        .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        Ok(())
+    }
+
+    /// SQLite integers are signed, so memos are stored with the bits of the
+    /// u64 reinterpreted as an i64. Equality lookups are unaffected.
+    pub fn memo_to_sql(memo: Memo) -> i64 {
+        memo.0 as i64
+    }
+
+    /// Fills the memo and timestamp columns of the transactions that were
+    /// stored before these columns were added.
+    pub fn backfill_transactions(connection: &mut Connection) -> Result<(), BlockStoreError> {
+        let tx = connection
+            .transaction()
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        {
+            let mut stmt_select = tx
+                .prepare("SELECT blocks.idx, blocks.block FROM transactions JOIN blocks ON transactions.block_idx = blocks.idx WHERE transactions.timestamp IS NULL")
                 .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+            let mut stmt_update = tx
+                .prepare("UPDATE transactions SET memo = ?1, icrc1_memo = ?2, timestamp = ?3 WHERE block_idx = ?4")
+                .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+            let blocks = stmt_select
+                .query_map(params![], |row| {
+                    Ok((row.get::<_, u64>(0)?, row.get::<_, Vec<u8>>(1)?))
+                })
+                .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+            for block in blocks {
+                let (index, block) = block.map_err(|e| BlockStoreError::Other(e.to_string()))?;
+                let block = Block::decode(EncodedBlock::from_vec(block))
+                    .map_err(|e| BlockStoreError::Other(format!("{}", e)))?;
+                stmt_update
+                    .execute(params![
+                        memo_to_sql(block.transaction.memo),
+                        block.transaction.icrc1_memo.map(|memo| memo.to_vec()),
+                        block.timestamp.as_nanos_since_unix_epoch(),
+                        index
+                    ])
+                    .map_err(|e| BlockStoreError::Other(e.to_string()))?;
             }
         }
-        Ok(())
+        tx.commit()
+            .map_err(|e| BlockStoreError::Other(e.to_string()))
     }
+
     pub fn get_all_block_indices_from_blocks_table(
         connection: &mut Connection,
     ) -> Result<Vec<u64>, BlockStoreError> {
@@ -286,6 +342,121 @@ mod database_access {
         Ok(result)
     }
 
+    pub fn get_block_idxs_by_spender(
+        connection: &mut Connection,
+        spender: &AccountIdentifier,
+    ) -> Result<Vec<u64>, BlockStoreError> {
+        let mut stmt = connection
+            .prepare(
+                "SELECT block_idx FROM transactions WHERE spender_account = ? ORDER BY block_idx",
+            )
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        let block_idxs = stmt
+            .query_map(params![spender.to_hex()], |row| row.get(0))
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        block_idxs
+            .map(|idx| idx.map_err(|e| BlockStoreError::Other(e.to_string())))
+            .collect()
+    }
+
+    /// Returns the indices of at most `limit` blocks matching `filter` from
+    /// the most recent to the oldest, skipping the first `offset` matches.
+    pub fn search_transactions(
+        connection: &mut Connection,
+        filter: &TransactionFilter,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<u64>, BlockStoreError> {
+        let mut conditions = vec![];
+        let mut values = vec![];
+        let mut push = |condition: &str, value: Value| {
+            conditions.push(condition.to_string());
+            values.push(value);
+        };
+        if let Some(memo) = filter.memo {
+            push("memo = ?", Value::Integer(memo_to_sql(memo)));
+        }
+        if let Some(icrc1_memo) = &filter.icrc1_memo {
+            push("icrc1_memo = ?", Value::Blob(icrc1_memo.clone()));
+        }
+        let mut push_u64 = |condition: &str, value: u64| -> Result<(), BlockStoreError> {
+            let value = i64::try_from(value).map_err(|_| {
+                BlockStoreError::Other(format!("Search bound {} does not fit in an i64", value))
+            })?;
+            push(condition, Value::Integer(value));
+            Ok(())
+        };
+        if let Some(min_amount) = filter.min_amount {
+            push_u64("amount >= ?", min_amount.get_e8s())?;
+        }
+        if let Some(max_amount) = filter.max_amount {
+            push_u64("amount <= ?", max_amount.get_e8s())?;
+        }
+        if let Some(min_timestamp) = filter.min_timestamp {
+            push_u64("timestamp >= ?", min_timestamp.as_nanos_since_unix_epoch())?;
+        }
+        if let Some(max_timestamp) = filter.max_timestamp {
+            push_u64("timestamp <= ?", max_timestamp.as_nanos_since_unix_epoch())?;
+        }
+        if let Some(max_block) = filter.max_block {
+            push_u64("block_idx <= ?", max_block)?;
+        }
+        if let Some(account) = &filter.account {
+            conditions
+                .push("(from_account = ? OR to_account = ? OR spender_account = ?)".to_string());
+            values.extend([
+                Value::Text(account.to_hex()),
+                Value::Text(account.to_hex()),
+                Value::Text(account.to_hex()),
+            ]);
+        }
+        if let Some(operation_types) = &filter.operation_types {
+            let placeholders = vec!["?"; operation_types.len()].join(",");
+            conditions.push(format!("operation_type IN ({})", placeholders));
+            values.extend(operation_types.iter().cloned().map(Value::Text));
+        }
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let mut stmt = connection
+            .prepare(&format!(
+                "SELECT block_idx FROM transactions {} ORDER BY block_idx DESC LIMIT {} OFFSET {}",
+                where_clause,
+                limit.min(i64::MAX as u64),
+                offset.min(i64::MAX as u64)
+            ))
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        let block_idxs = stmt
+            .query_map(params_from_iter(values.iter()), |row| row.get(0))
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?
+            .collect::<Result<Vec<u64>, _>>()
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        Ok(block_idxs)
+    }
+
+    /// Adds `column` to `table` unless a previous version of the store
+    /// already created it.
+    pub fn add_column_if_missing(
+        connection: &Connection,
+        table: &str,
+        column: &str,
+        definition: &str,
+    ) -> Result<(), Error> {
+        let mut stmt = connection.prepare(&format!("PRAGMA table_info({})", table))?;
+        let mut columns = stmt.query_map(params![], |row| row.get::<_, String>(1))?;
+        if columns.any(|name| name.map(|name| name == column).unwrap_or(false)) {
+            return Ok(());
+        }
+        connection.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+        Ok(())
+    }
+
     pub fn get_block_idx_by_block_hash(
         connection: &mut Connection,
         hash: &HashOf<EncodedBlock>,
@@ -655,6 +826,30 @@ impl HashedBlock {
     }
 }
 
+/// The filters of [Blocks::search_transactions]. Unset filters match all
+/// transactions.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransactionFilter {
+    /// Matches the transactions debiting or crediting the account, or in
+    /// which the account is the spender.
+    pub account: Option<AccountIdentifier>,
+    /// Matches any of the given operation types, which are the names of the
+    /// `icp_ledger::Operation` variants, e.g. `Transfer`.
+    pub operation_types: Option<Vec<String>>,
+    pub memo: Option<Memo>,
+    pub icrc1_memo: Option<Vec<u8>>,
+    /// The inclusive bounds of the amount, which is zero for approvals.
+    pub min_amount: Option<Tokens>,
+    pub max_amount: Option<Tokens>,
+    /// The inclusive bounds of the block timestamp.
+    pub min_timestamp: Option<TimeStamp>,
+    pub max_timestamp: Option<TimeStamp>,
+    /// Matches the blocks up to `max_block`. Paging by setting `max_block`
+    /// below the last block of the previous page, instead of using an
+    /// offset, lets SQLite seek to the page directly.
+    pub max_block: Option<BlockIndex>,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum BlockStoreError {
     NotFound(BlockIndex),
@@ -704,6 +899,7 @@ impl Blocks {
         store.create_tables().map_err(|e| {
             BlockStoreError::Other(format!("Failed to initialize SQLite database: {}", e))
         })?;
+        database_access::backfill_transactions(&mut store.connection.lock().unwrap())?;
 
         store.check_table_coherence()?;
         Ok(store)
@@ -732,6 +928,12 @@ impl Blocks {
                 to_account VARCHAR(64) ,
                 amount INTEGER NOT NULL,
                 fee INTEGER,
+                spender_account VARCHAR(64),
+                allowance INTEGER,
+                expires_at INTEGER,
+                memo INTEGER,
+                icrc1_memo BLOB,
+                timestamp INTEGER,
                 PRIMARY KEY(block_idx),
                 FOREIGN KEY(block_idx) REFERENCES blocks(idx)
             )
@@ -750,6 +952,44 @@ impl Blocks {
             [],
         )?;
 
+        // Stores created by earlier versions lack the columns added since,
+        // see `backfill_transactions` for how existing rows are filled.
+        for (column, definition) in [
+            ("spender_account", "VARCHAR(64)"),
+            ("allowance", "INTEGER"),
+            ("expires_at", "INTEGER"),
+            ("memo", "INTEGER"),
+            ("icrc1_memo", "BLOB"),
+            ("timestamp", "INTEGER"),
+        ] {
+            database_access::add_column_if_missing(
+                &connection,
+                "transactions",
+                column,
+                definition,
+            )?;
+        }
+        // Each index also covers block_idx, which is the rowid of the table,
+        // so the matches of a filter can be read in block order.
+        for column in [
+            "operation_type",
+            "from_account",
+            "to_account",
+            "spender_account",
+            "amount",
+            "memo",
+            "icrc1_memo",
+            "timestamp",
+        ] {
+            connection.execute(
+                &format!(
+                    "CREATE INDEX IF NOT EXISTS {0}_index ON transactions({0})",
+                    column
+                ),
+                [],
+            )?;
+        }
+
         Ok(())
     }
 
@@ -795,6 +1035,28 @@ impl Blocks {
         let mut connection = self.connection.lock().unwrap();
         database_access::get_block_idx_by_transaction_hash(&mut connection, hash)
     }
+    /// Returns the indices of at most `limit` blocks matching `filter` from
+    /// the most recent to the oldest, skipping the first `offset` matches.
+    pub fn search_transactions(
+        &self,
+        filter: &TransactionFilter,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<u64>, BlockStoreError> {
+        let mut connection = self.connection.lock().unwrap();
+        database_access::search_transactions(&mut connection, filter, offset, limit)
+    }
+
+    /// Returns the indices of the approve and transfer_from blocks in which
+    /// `spender` is the spender.
+    pub fn get_block_idxs_by_spender(
+        &self,
+        spender: &AccountIdentifier,
+    ) -> Result<Vec<u64>, BlockStoreError> {
+        let mut connection = self.connection.lock().unwrap();
+        database_access::get_block_idxs_by_spender(&mut connection, spender)
+    }
+
     pub fn get_account_balance_history(
         &self,
         acc: &AccountIdentifier,
@@ -915,7 +1177,7 @@ impl Blocks {
                     database_access::get_hashed_block(&mut connection, &missing_index)?;
                 database_access::push_transaction(
                     &mut connection,
-                    &Block::decode(missing_block.block).unwrap().transaction,
+                    &Block::decode(missing_block.block).unwrap(),
                     &missing_index,
                 )?;
             }
@@ -1029,7 +1291,7 @@ impl Blocks {
         database_access::push_hashed_block(&mut con, hb)?;
         database_access::push_transaction(
             &mut con,
-            &Block::decode(hb.block.clone()).unwrap().transaction,
+            &Block::decode(hb.block.clone()).unwrap(),
             &hb.index,
         )?;
         database_access::update_balance_book(&mut con, hb)?;
@@ -1051,8 +1313,9 @@ impl Blocks {
             .map_err(|e| BlockStoreError::Other(format!("{}", e)))?;
         let mut stmt_hb =  connection .prepare("INSERT INTO blocks (hash, block, parent_hash, idx, verified) VALUES (?1, ?2, ?3, ?4, FALSE)")
         .map_err(|e| BlockStoreError::Other(e.to_string()))?;
-        let mut stmt_tx = connection .prepare("INSERT INTO transactions (block_idx,tx_hash,operation_type,from_account,to_account,amount,fee) VALUES (?1, ?2, ?3, ?4, ?5,?6,?7)")
-        .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        let mut stmt_tx = connection
+            .prepare(database_access::INSERT_TRANSACTION)
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
         let mut stmt_select =  connection
         .prepare("SELECT block_idx,account,tokens FROM account_balances WHERE account=?1 AND block_idx<=?2 ORDER BY block_idx DESC LIMIT 1")
         .map_err(|e| BlockStoreError::Other(e.to_string()))?;
@@ -1071,7 +1334,7 @@ impl Blocks {
                 }
             };
             match database_access::push_transaction_execution(
-                &Block::decode(hb.block.clone()).unwrap().transaction,
+                &Block::decode(hb.block.clone()).unwrap(),
                 &mut stmt_tx,
                 &hb.index,
             ) {
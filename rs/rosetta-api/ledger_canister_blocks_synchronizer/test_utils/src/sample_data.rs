@@ -1,5 +1,6 @@
 use ic_ledger_canister_blocks_synchronizer::blocks::HashedBlock;
 use ic_ledger_core::block::BlockType;
+use ic_ledger_core::tokens::SignedTokens;
 use ic_types::PrincipalId;
 use icp_ledger::{
     AccountIdentifier, Block, BlockIndex, Memo, Operation, Tokens, Transaction,
@@ -24,6 +25,13 @@ enum Trans {
     Buy(AccountIdentifier, Tokens),
     Sell(AccountIdentifier, Tokens),
     Transfer(AccountIdentifier, AccountIdentifier, Tokens),
+    Approve(AccountIdentifier, AccountIdentifier, Tokens),
+    TransferFrom(
+        AccountIdentifier,
+        AccountIdentifier,
+        AccountIdentifier,
+        Tokens,
+    ),
 }
 
 pub struct Scribe {
@@ -173,6 +181,57 @@ impl Scribe {
         self.add_block(transaction, DEFAULT_TRANSFER_FEE);
     }
 
+    pub fn approve(&mut self, src: AccountIdentifier, spender: AccountIdentifier, allowance: u64) {
+        let allowance = Tokens::from_e8s(allowance);
+        self.transactions
+            .push_back(Trans::Approve(src, spender, allowance));
+        *self.balance_book.get_mut(&src).unwrap() -= DEFAULT_TRANSFER_FEE;
+        let memo = self.next_message();
+        let transaction = Transaction {
+            operation: Operation::Approve {
+                from: src,
+                spender,
+                allowance: SignedTokens::Plus(allowance),
+                expires_at: None,
+                fee: DEFAULT_TRANSFER_FEE,
+            },
+            memo,
+            icrc1_memo: None,
+            created_at_time: Some(self.time().into()),
+        };
+        self.balance_history.push_back(self.balance_book.clone());
+        self.add_block(transaction, DEFAULT_TRANSFER_FEE);
+    }
+
+    pub fn transfer_from(
+        &mut self,
+        src: AccountIdentifier,
+        dst: AccountIdentifier,
+        spender: AccountIdentifier,
+        amount: u64,
+    ) {
+        let amount = Tokens::from_e8s(amount);
+        self.transactions
+            .push_back(Trans::TransferFrom(src, dst, spender, amount));
+        *self.balance_book.get_mut(&src).unwrap() -= (amount + DEFAULT_TRANSFER_FEE).unwrap();
+        *self.balance_book.get_mut(&dst).unwrap() += amount;
+        let memo = self.next_message();
+        let transaction = Transaction {
+            operation: Operation::TransferFrom {
+                from: src,
+                to: dst,
+                spender,
+                amount,
+                fee: DEFAULT_TRANSFER_FEE,
+            },
+            memo,
+            icrc1_memo: None,
+            created_at_time: Some(self.time().into()),
+        };
+        self.balance_history.push_back(self.balance_book.clone());
+        self.add_block(transaction, DEFAULT_TRANSFER_FEE);
+    }
+
     pub fn get_rand_account(&mut self, min_amount: Tokens) -> AccountIdentifier {
         let mut acc_idx = self.dice_num(self.num_accounts()) as usize;
         let mut acc = self.accounts[acc_idx];
@@ -1,6 +1,6 @@
 use ic_ledger_canister_blocks_synchronizer::{
     balance_book::{BalanceBook, ClientBalancesStore},
-    blocks::{BlockStoreError, Blocks},
+    blocks::{BlockStoreError, Blocks, TransactionFilter},
 };
 use ic_ledger_canister_blocks_synchronizer_test_utils::{
     create_tmp_dir, init_test_logger, sample_data::Scribe,
@@ -10,7 +10,7 @@ use ic_ledger_core::{
     approvals::AllowanceTable, balances::BalancesStore, block::BlockType, timestamp::TimeStamp,
     Tokens,
 };
-use icp_ledger::{apply_operation, AccountIdentifier, ApprovalKey, Block, Operation};
+use icp_ledger::{apply_operation, AccountIdentifier, ApprovalKey, Block, Memo, Operation};
 use rusqlite::params;
 use std::path::Path;
 
@@ -124,6 +124,285 @@ async fn store_coherance_test() {
     assert_eq!(store.get_all_accounts().unwrap().len(), 10);
 }
 
+#[actix_rt::test]
+async fn store_approve_and_transfer_from_test() {
+    init_test_logger();
+    let tmpdir = create_tmp_dir();
+    let mut store = sqlite_on_disk_store(tmpdir.path());
+    let mut scribe = Scribe::new_with_sample_data(10, 100);
+    let owner = scribe.accounts[0];
+    let receiver = scribe.accounts[1];
+    let spender = scribe.accounts[2];
+    scribe.buy(owner, 1_000_000);
+    scribe.approve(owner, spender, 500);
+    let approve_idx = scribe.blockchain.back().unwrap().index;
+    scribe.transfer_from(owner, receiver, spender, 200);
+    let transfer_from_idx = scribe.blockchain.back().unwrap().index;
+
+    for hb in &scribe.blockchain {
+        store.push(hb).unwrap();
+    }
+
+    for idx in [approve_idx, transfer_from_idx] {
+        let hb = scribe.blockchain.get(idx as usize).unwrap();
+        assert_eq!(
+            store.get_transaction(&idx).unwrap(),
+            Block::decode(hb.block.clone()).unwrap().transaction
+        );
+    }
+    assert_eq!(
+        store.get_block_idxs_by_spender(&spender).unwrap(),
+        vec![approve_idx, transfer_from_idx]
+    );
+    assert!(store.get_block_idxs_by_spender(&owner).unwrap().is_empty());
+    for account in [owner, receiver] {
+        assert_eq!(
+            store
+                .get_account_balance(&account, &transfer_from_idx)
+                .unwrap(),
+            *scribe.balance_book.get(&account).unwrap()
+        );
+    }
+
+    // Reopening the store must keep the indexed columns.
+    drop(store);
+    let store = sqlite_on_disk_store(tmpdir.path());
+    assert_eq!(
+        store.get_block_idxs_by_spender(&spender).unwrap(),
+        vec![approve_idx, transfer_from_idx]
+    );
+}
+
+/// Returns the indices of the blocks whose transaction satisfies `pred`,
+/// from the most recent to the oldest.
+fn matching_blocks(scribe: &Scribe, pred: impl Fn(&Block) -> bool) -> Vec<u64> {
+    scribe
+        .blockchain
+        .iter()
+        .rev()
+        .filter(|hb| pred(&Block::decode(hb.block.clone()).unwrap()))
+        .map(|hb| hb.index)
+        .collect()
+}
+
+fn operation_amount(operation: &Operation) -> Tokens {
+    match operation {
+        Operation::Burn { amount, .. }
+        | Operation::Mint { amount, .. }
+        | Operation::Transfer { amount, .. }
+        | Operation::TransferFrom { amount, .. } => *amount,
+        Operation::Approve { .. } => Tokens::ZERO,
+    }
+}
+
+#[actix_rt::test]
+async fn store_search_transactions_test() {
+    init_test_logger();
+    let tmpdir = create_tmp_dir();
+    let mut store = sqlite_on_disk_store(tmpdir.path());
+    let scribe = Scribe::new_with_sample_data(10, 100);
+
+    for hb in &scribe.blockchain {
+        store.push(hb).unwrap();
+    }
+
+    let search =
+        |filter: TransactionFilter| store.search_transactions(&filter, 0, u64::MAX).unwrap();
+
+    assert_eq!(
+        search(TransactionFilter::default()),
+        matching_blocks(&scribe, |_| true)
+    );
+
+    assert_eq!(
+        search(TransactionFilter {
+            operation_types: Some(vec!["Mint".to_string()]),
+            ..Default::default()
+        }),
+        matching_blocks(&scribe, |b| matches!(
+            b.transaction.operation,
+            Operation::Mint { .. }
+        ))
+    );
+
+    let account = scribe.accounts[3];
+    assert_eq!(
+        search(TransactionFilter {
+            account: Some(account),
+            operation_types: Some(vec!["Transfer".to_string()]),
+            ..Default::default()
+        }),
+        matching_blocks(&scribe, |b| matches!(
+            b.transaction.operation,
+            Operation::Transfer { from, to, .. } if from == account || to == account
+        ))
+    );
+
+    // The account filter also matches the blocks in which the account is
+    // the spender.
+    for account in &scribe.accounts {
+        assert_eq!(
+            search(TransactionFilter {
+                account: Some(*account),
+                ..Default::default()
+            }),
+            matching_blocks(&scribe, |b| match b.transaction.operation {
+                Operation::Burn { from, .. } => from == *account,
+                Operation::Mint { to, .. } => to == *account,
+                Operation::Transfer { from, to, .. } => from == *account || to == *account,
+                Operation::Approve { from, spender, .. } => {
+                    from == *account || spender == *account
+                }
+                Operation::TransferFrom {
+                    from, to, spender, ..
+                } => from == *account || to == *account || spender == *account,
+            })
+        );
+    }
+
+    // The scribe uses the block index as memo.
+    assert_eq!(
+        search(TransactionFilter {
+            memo: Some(Memo(42)),
+            ..Default::default()
+        }),
+        vec![42]
+    );
+
+    let (min_amount, max_amount) = (Tokens::from_e8s(150), Tokens::from_e8s(250));
+    assert_eq!(
+        search(TransactionFilter {
+            min_amount: Some(min_amount),
+            max_amount: Some(max_amount),
+            ..Default::default()
+        }),
+        matching_blocks(&scribe, |b| {
+            let amount = operation_amount(&b.transaction.operation);
+            min_amount <= amount && amount <= max_amount
+        })
+    );
+
+    let block_timestamp = |idx: usize| {
+        Block::decode(scribe.blockchain[idx].block.clone())
+            .unwrap()
+            .timestamp
+    };
+    assert_eq!(
+        search(TransactionFilter {
+            min_timestamp: Some(block_timestamp(20)),
+            max_timestamp: Some(block_timestamp(29)),
+            ..Default::default()
+        }),
+        (20..30).rev().collect::<Vec<u64>>()
+    );
+
+    // Page through the transfers with max_block as the cursor.
+    let transfers = TransactionFilter {
+        operation_types: Some(vec!["Transfer".to_string()]),
+        ..Default::default()
+    };
+    let mut pages = vec![];
+    let mut cursor = None;
+    loop {
+        let page = store
+            .search_transactions(
+                &TransactionFilter {
+                    max_block: cursor,
+                    ..transfers.clone()
+                },
+                0,
+                7,
+            )
+            .unwrap();
+        let last = page.last().copied();
+        pages.extend(page);
+        match last {
+            Some(last) if last > 0 => cursor = Some(last - 1),
+            _ => break,
+        }
+    }
+    assert_eq!(pages, search(transfers.clone()));
+
+    let page = store.search_transactions(&transfers, 5, 3).unwrap();
+    assert_eq!(page, search(transfers)[5..8].to_vec());
+}
+
+#[actix_rt::test]
+async fn store_migration_test() {
+    init_test_logger();
+    let tmpdir = create_tmp_dir();
+    let scribe = Scribe::new_with_sample_data(10, 100);
+
+    // Create a store with the schema used before the transactions could be
+    // searched by memo and timestamp.
+    std::fs::create_dir_all(tmpdir.path()).unwrap();
+    let con = rusqlite::Connection::open(tmpdir.path().join("db.sqlite")).unwrap();
+    con.execute_batch(
+        r#"
+        CREATE TABLE blocks (
+            hash BLOB NOT NULL,
+            block BLOB NOT NULL,
+            parent_hash BLOB,
+            idx INTEGER NOT NULL PRIMARY KEY,
+            verified BOOLEAN);
+        CREATE TABLE transactions (
+            block_idx INTEGER NOT NULL,
+            tx_hash BLOB NOT NULL,
+            operation_type VARCHAR NOT NULL,
+            from_account VARCHAR(64) ,
+            to_account VARCHAR(64) ,
+            amount INTEGER NOT NULL,
+            fee INTEGER,
+            PRIMARY KEY(block_idx),
+            FOREIGN KEY(block_idx) REFERENCES blocks(idx));
+        "#,
+    )
+    .unwrap();
+    for hb in &scribe.blockchain {
+        let tx = Block::decode(hb.block.clone()).unwrap().transaction;
+        let operation_type: &str = tx.operation.clone().into();
+        con.execute(
+            "INSERT INTO blocks (hash, block, parent_hash, idx, verified) VALUES (?1, ?2, ?3, ?4, FALSE)",
+            params![
+                hb.hash.into_bytes().to_vec(),
+                hb.block.clone().into_vec(),
+                hb.parent_hash.map(|ph| ph.into_bytes().to_vec()),
+                hb.index
+            ],
+        )
+        .unwrap();
+        con.execute(
+            "INSERT INTO transactions (block_idx, tx_hash, operation_type, amount) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                hb.index,
+                tx.hash().into_bytes().to_vec(),
+                operation_type,
+                operation_amount(&tx.operation).get_e8s()
+            ],
+        )
+        .unwrap();
+    }
+    drop(con);
+
+    let store = sqlite_on_disk_store(tmpdir.path());
+    let block_timestamp = Block::decode(scribe.blockchain[42].block.clone())
+        .unwrap()
+        .timestamp;
+    let idxs = store
+        .search_transactions(
+            &TransactionFilter {
+                memo: Some(Memo(42)),
+                min_timestamp: Some(block_timestamp),
+                max_timestamp: Some(block_timestamp),
+                ..Default::default()
+            },
+            0,
+            10,
+        )
+        .unwrap();
+    assert_eq!(idxs, vec![42]);
+}
+
 #[actix_rt::test]
 async fn store_account_balances_test() {
     init_test_logger();
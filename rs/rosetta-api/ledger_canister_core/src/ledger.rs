@@ -26,6 +26,7 @@ pub enum TxApplyError {
     InsufficientFunds { balance: Tokens },
     InsufficientAllowance { allowance: Tokens },
     ExpiredApproval { now: TimeStamp },
+    AllowanceChanged { current_allowance: Tokens },
 }
 
 impl From<BalanceError> for TxApplyError {
@@ -169,6 +170,7 @@ pub enum TransferError {
     InsufficientFunds { balance: Tokens },
     InsufficientAllowance { allowance: Tokens },
     ExpiredApproval { ledger_time: TimeStamp },
+    AllowanceChanged { current_allowance: Tokens },
     TxTooOld { allowed_window_nanos: u64 },
     TxCreatedInFuture { ledger_time: TimeStamp },
     TxThrottled,
@@ -229,6 +231,9 @@ where
             TxApplyError::ExpiredApproval { now } => {
                 TransferError::ExpiredApproval { ledger_time: now }
             }
+            TxApplyError::AllowanceChanged { current_allowance } => {
+                TransferError::AllowanceChanged { current_allowance }
+            }
         })?;
 
     let fee_collector = ledger.fee_collector().cloned();
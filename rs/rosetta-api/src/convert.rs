@@ -11,22 +11,28 @@ use crate::request::transaction_operation_results::TransactionOperationResults;
 use crate::request::transaction_results::TransactionResults;
 use crate::request::Request;
 use crate::request_types::{
-    ChangeAutoStakeMaturityMetadata, DisburseMetadata, FollowMetadata, KeyMetadata,
-    MergeMaturityMetadata, NeuronIdentifierMetadata, NeuronInfoMetadata, PublicKeyOrPrincipal,
-    RegisterVoteMetadata, RequestResultMetadata, SetDissolveTimestampMetadata, SpawnMetadata,
-    StakeMaturityMetadata, Status, STATUS_COMPLETED,
+    Approve, ApproveMetadata, ChangeAutoStakeMaturityMetadata, DisburseMetadata, FollowMetadata,
+    KeyMetadata, MergeMaturityMetadata, NeuronIdentifierMetadata, NeuronInfoMetadata,
+    PublicKeyOrPrincipal, RegisterVoteMetadata, RequestResultMetadata,
+    SetDissolveTimestampMetadata, SpawnMetadata, StakeMaturityMetadata, Status, STATUS_COMPLETED,
 };
 use crate::transaction_id::TransactionIdentifier;
 use crate::{convert, errors};
+use candid::Nat;
 use dfn_protobuf::ProtoBuf;
 use ic_canister_client_sender::Ed25519KeyPair as EdKeypair;
 use ic_canister_client_sender::Secp256k1KeyPair;
 use ic_crypto_tree_hash::Path;
 use ic_ledger_canister_blocks_synchronizer::blocks::HashedBlock;
 use ic_ledger_core::block::{BlockType, HashOf};
+use ic_ledger_core::timestamp::TimeStamp;
 use ic_types::messages::{HttpCanisterUpdate, HttpReadState};
 use ic_types::{CanisterId, PrincipalId};
-use icp_ledger::{Block, BlockIndex, Operation as LedgerOperation, SendArgs, Subaccount, Tokens};
+use icp_ledger::{
+    Block, BlockIndex, Operation as LedgerOperation, SendArgs, Subaccount, Tokens,
+    DEFAULT_TRANSFER_FEE,
+};
+use icrc_ledger_types::icrc2::approve::ApproveArgs;
 use on_wire::{FromWire, IntoWire};
 use serde_json::map::Map;
 use serde_json::{from_value, Number, Value};
@@ -114,8 +120,25 @@ pub fn operations_to_requests(
             }
         };
 
+        // Transfers on behalf of another account are only ever read from
+        // blocks, they cannot be constructed.
+        let validate_not_transfer_from = || {
+            if o.metadata
+                .as_ref()
+                .map_or(false, |m| m.contains_key("spender"))
+            {
+                Err(op_error(
+                    o,
+                    "TransferFrom operations are not supported through Rosetta".into(),
+                ))
+            } else {
+                Ok(())
+            }
+        };
+
         match o._type {
             OperationType::Transaction => {
+                validate_not_transfer_from()?;
                 let amount = o
                     .amount
                     .as_ref()
@@ -124,6 +147,7 @@ pub fn operations_to_requests(
                 state.transaction(account, amount)?;
             }
             OperationType::Fee => {
+                validate_not_transfer_from()?;
                 let amount = o
                     .amount
                     .as_ref()
@@ -268,6 +292,60 @@ pub fn operations_to_requests(
                 };
                 state.follow(account, pid, neuron_index, topic, followees)?;
             }
+            OperationType::Approve => {
+                if o.amount.is_some() {
+                    return Err(op_error(
+                        o,
+                        "APPROVE operations cannot have an amount".into(),
+                    ));
+                }
+                let ApproveMetadata {
+                    spender,
+                    spender_principal,
+                    spender_subaccount,
+                    allowance,
+                    expires_at,
+                } = o.metadata.clone().try_into()?;
+                let spender_principal = spender_principal.ok_or_else(|| {
+                    op_error(o, "The principal of the spender must be populated".into())
+                })?;
+                let spender_subaccount = match spender_subaccount {
+                    None => None,
+                    Some(s) => {
+                        Some(Subaccount(from_hex(&s)?.try_into().map_err(|_| {
+                            op_error(o, format!("Invalid subaccount {}", s))
+                        })?))
+                    }
+                };
+                let allowance = from_amount(&allowance, token_name).map_err(|e| op_error(o, e))?;
+                let allowance = u64::try_from(allowance).map_err(|_| {
+                    op_error(
+                        o,
+                        format!(
+                            "Only positive allowance changes can be approved, got {}",
+                            allowance
+                        ),
+                    )
+                })?;
+                let approve = Approve {
+                    account,
+                    spender: spender_principal,
+                    spender_subaccount,
+                    allowance: Tokens::from_e8s(allowance),
+                    expires_at: expires_at.map(TimeStamp::from_nanos_since_unix_epoch),
+                    fee: Tokens::ZERO,
+                };
+                if approve.spender_account() != spender {
+                    return Err(op_error(
+                        o,
+                        format!(
+                            "The spender {} does not match the spender principal {}",
+                            spender, spender_principal
+                        ),
+                    ));
+                }
+                state.approve(approve)?;
+            }
         }
     }
 
@@ -400,6 +478,27 @@ pub fn to_arg(args: SendArgs) -> Vec<u8> {
     ProtoBuf(args).into_bytes().expect("Serialization failed")
 }
 
+/// Converts the arguments of an `icrc2_approve` call made by `sender`.
+pub fn from_approve_args(sender: PrincipalId, args: ApproveArgs) -> Result<Approve, ApiError> {
+    let to_tokens = |n: Nat| {
+        u64::try_from(n.0)
+            .map(Tokens::from_e8s)
+            .map_err(|e| ApiError::invalid_request(format!("Invalid approve amount: {}", e)))
+    };
+    Ok(Approve {
+        account: icp_ledger::AccountIdentifier::new(sender, args.from_subaccount.map(Subaccount)),
+        spender: PrincipalId::from(args.spender.owner),
+        spender_subaccount: args.spender.subaccount.map(Subaccount),
+        allowance: to_tokens(args.amount)?,
+        expires_at: args.expires_at.map(TimeStamp::from_nanos_since_unix_epoch),
+        fee: args
+            .fee
+            .map(to_tokens)
+            .transpose()?
+            .unwrap_or(DEFAULT_TRANSFER_FEE),
+    })
+}
+
 pub fn from_hash<T>(hash: &HashOf<T>) -> String {
     format!("{}", *hash)
 }
@@ -448,6 +547,12 @@ pub fn from_transaction_operation_results(
                 op_idx += 3;
                 fee
             }
+            (Request::Approve(_), [approve, fee, ..])
+                if approve._type == OperationType::Approve && fee._type == OperationType::Fee =>
+            {
+                op_idx += 2;
+                fee
+            }
             (_, [o, ..]) => {
                 op_idx += 1;
                 o
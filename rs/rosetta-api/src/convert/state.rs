@@ -2,7 +2,7 @@ use crate::errors::ApiError;
 use crate::models::seconds::Seconds;
 use crate::request::Request;
 use crate::request_types::{
-    AddHotKey, ChangeAutoStakeMaturity, Disburse, Follow, MergeMaturity, NeuronInfo,
+    AddHotKey, Approve, ChangeAutoStakeMaturity, Disburse, Follow, MergeMaturity, NeuronInfo,
     PublicKeyOrPrincipal, RegisterVote, RemoveHotKey, SetDissolveTimestamp, Spawn, Stake,
     StakeMaturity, StartDissolve, StopDissolve,
 };
@@ -10,13 +10,14 @@ use ic_types::PrincipalId;
 use icp_ledger::{Operation, Tokens, DEFAULT_TRANSFER_FEE};
 
 /// Helper for `from_operations` that creates `Transfer`s from related
-/// debit/credit/fee operations.
+/// debit/credit/fee operations, and `Approve`s from approve/fee operations.
 pub struct State {
     preprocessing: bool,
     pub(crate) actions: Vec<Request>,
     credit: Option<AccountTokens>,
     debit: Option<AccountTokens>,
     fee: Option<AccountTokens>,
+    approve: Option<Approve>,
 }
 
 impl State {
@@ -33,6 +34,7 @@ impl State {
             credit,
             debit,
             fee,
+            approve: None,
         }
     }
 
@@ -45,6 +47,10 @@ impl State {
             Err(err)
         };
 
+        if let Some(approve) = self.approve.take() {
+            return self.flush_approve(approve);
+        }
+
         if self.credit.is_none() && self.debit.is_none() && self.fee.is_none() {
             return Ok(());
         }
@@ -97,11 +103,41 @@ impl State {
         Ok(())
     }
 
+    /// Create an `Approve` from the approve/fee operations seen previously.
+    fn flush_approve(&mut self, mut approve: Approve) -> Result<(), ApiError> {
+        let trans_err = |msg: String| {
+            let msg = format!("Bad transaction: {}", msg);
+            Err(ApiError::InvalidTransaction(false, msg.into()))
+        };
+
+        if self.credit.is_some() || self.debit.is_some() {
+            return trans_err("An approval cannot be combined with a transfer".to_string());
+        }
+        let fee = match self.fee.take() {
+            Some(fee) => fee,
+            // If you're preprocessing just continue with the default fee
+            None if self.preprocessing => AccountTokens {
+                tokens: DEFAULT_TRANSFER_FEE,
+                account: approve.account,
+            },
+            None => return trans_err("An approval must be followed by a fee".to_string()),
+        };
+        if fee.account != approve.account {
+            return trans_err(format!("Fee should be taken from {}", approve.account));
+        }
+        approve.fee = fee.tokens;
+        self.actions.push(Request::Approve(approve));
+        Ok(())
+    }
+
     pub fn transaction(
         &mut self,
         account: icp_ledger::AccountIdentifier,
         amount: i128,
     ) -> Result<(), ApiError> {
+        if self.approve.is_some() {
+            self.flush()?;
+        }
         if amount > 0 || self.debit.is_some() && amount == 0 {
             if self.credit.is_some() {
                 self.flush()?;
@@ -137,6 +173,12 @@ impl State {
         Ok(())
     }
 
+    pub fn approve(&mut self, approve: Approve) -> Result<(), ApiError> {
+        self.flush()?;
+        self.approve = Some(approve);
+        Ok(())
+    }
+
     pub fn stake(
         &mut self,
         account: icp_ledger::AccountIdentifier,
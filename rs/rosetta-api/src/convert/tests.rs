@@ -1,8 +1,9 @@
 use super::*;
 use crate::models::amount::signed_amount;
 use crate::models::operation::{OperationIdentifier, OperationType};
-use crate::request_types::Stake;
+use crate::request_types::{Approve, ApproveMetadata, Stake, TransferFromMetadata};
 use crate::DEFAULT_TOKEN_SYMBOL;
+use ic_ledger_core::tokens::SignedTokens;
 use icp_ledger::AccountIdentifier;
 use icp_ledger::Operation as LedgerOperation;
 
@@ -47,6 +48,13 @@ impl OperationBuilder {
         })
     }
 
+    fn metadata(self, metadata: impl Into<models::Object>) -> Self {
+        Self(Operation {
+            metadata: Some(metadata.into()),
+            ..self.0
+        })
+    }
+
     fn build(self) -> Operation {
         self.0
     }
@@ -175,6 +183,113 @@ fn test_can_handle_multiple_transfers() {
     );
 }
 
+#[test]
+fn test_approve_and_transfer_from_blocks_to_operations() {
+    let expires_at = TimeStamp::from_nanos_since_unix_epoch(1_000_000);
+    assert_eq!(
+        Request::requests_to_operations(
+            &[
+                Request::Transfer(LedgerOperation::Approve {
+                    from: test_account(1),
+                    spender: test_account(2),
+                    allowance: SignedTokens::Minus(Tokens::from_e8s(50)),
+                    expires_at: Some(expires_at),
+                    fee: Tokens::from_e8s(10),
+                }),
+                Request::Transfer(LedgerOperation::TransferFrom {
+                    from: test_account(1),
+                    to: test_account(3),
+                    spender: test_account(2),
+                    amount: Tokens::from_e8s(100),
+                    fee: Tokens::from_e8s(10),
+                }),
+            ],
+            DEFAULT_TOKEN_SYMBOL
+        ),
+        Ok(vec![
+            OperationBuilder::new(0, OperationType::Approve)
+                .account(test_account(1))
+                .metadata(ApproveMetadata {
+                    spender: test_account(2),
+                    spender_principal: None,
+                    spender_subaccount: None,
+                    allowance: signed_amount(-50, DEFAULT_TOKEN_SYMBOL),
+                    expires_at: Some(1_000_000),
+                })
+                .build(),
+            OperationBuilder::new(1, OperationType::Fee)
+                .account(test_account(1))
+                .amount(-10)
+                .build(),
+            OperationBuilder::new(2, OperationType::Transaction)
+                .account(test_account(1))
+                .amount(-100)
+                .metadata(TransferFromMetadata {
+                    spender: test_account(2),
+                })
+                .build(),
+            OperationBuilder::new(3, OperationType::Transaction)
+                .account(test_account(3))
+                .amount(100)
+                .metadata(TransferFromMetadata {
+                    spender: test_account(2),
+                })
+                .build(),
+            OperationBuilder::new(4, OperationType::Fee)
+                .account(test_account(1))
+                .amount(-10)
+                .metadata(TransferFromMetadata {
+                    spender: test_account(2),
+                })
+                .build(),
+        ])
+    );
+}
+
+#[test]
+fn test_approve_operations_round_trip() {
+    let approve = Request::Approve(Approve {
+        account: test_account(1),
+        spender: PrincipalId::new_user_test_id(2),
+        spender_subaccount: Some(Subaccount([7; 32])),
+        allowance: Tokens::from_e8s(100),
+        expires_at: Some(TimeStamp::from_nanos_since_unix_epoch(1_000_000)),
+        fee: Tokens::from_e8s(10),
+    });
+    let operations =
+        Request::requests_to_operations(&[approve.clone()], DEFAULT_TOKEN_SYMBOL).unwrap();
+    assert_eq!(operations.len(), 2);
+    assert_eq!(
+        operations_to_requests(&operations, false, DEFAULT_TOKEN_SYMBOL),
+        Ok(vec![approve])
+    );
+
+    // Without the spender principal the ledger call cannot be constructed.
+    let mut operations = operations;
+    operations[0]
+        .metadata
+        .as_mut()
+        .unwrap()
+        .remove("spender_principal");
+    assert!(operations_to_requests(&operations, false, DEFAULT_TOKEN_SYMBOL).is_err());
+}
+
+#[test]
+fn test_transfer_from_operations_are_not_constructible() {
+    let operations = Request::requests_to_operations(
+        &[Request::Transfer(LedgerOperation::TransferFrom {
+            from: test_account(1),
+            to: test_account(3),
+            spender: test_account(2),
+            amount: Tokens::from_e8s(100),
+            fee: Tokens::from_e8s(10),
+        })],
+        DEFAULT_TOKEN_SYMBOL,
+    )
+    .unwrap();
+    assert!(operations_to_requests(&operations, false, DEFAULT_TOKEN_SYMBOL).is_err());
+}
+
 #[test]
 fn account_identifier_decode_test() {
     // a good address
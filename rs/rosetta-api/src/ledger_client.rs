@@ -1,4 +1,5 @@
 mod handle_add_hotkey;
+mod handle_approve;
 mod handle_change_auto_stake_maturity;
 mod handle_disburse;
 mod handle_follow;
@@ -49,7 +50,7 @@ use crate::convert;
 use crate::errors::{ApiError, Details, ICError};
 use crate::ledger_client::neuron_response::NeuronResponse;
 use crate::ledger_client::{
-    handle_add_hotkey::handle_add_hotkey,
+    handle_add_hotkey::handle_add_hotkey, handle_approve::handle_approve,
     handle_change_auto_stake_maturity::handle_change_auto_stake_maturity,
     handle_disburse::handle_disburse, handle_follow::handle_follow,
     handle_merge_maturity::handle_merge_maturity, handle_neuron_info::handle_neuron_info,
@@ -739,6 +740,7 @@ impl LedgerClient {
     ) -> Result<Result<Option<OperationOutput>, ApiError>, String> {
         match request_type.clone() {
             RequestType::AddHotKey { .. } => handle_add_hotkey(bytes),
+            RequestType::Approve => handle_approve(bytes),
             RequestType::Disburse { .. } => handle_disburse(bytes),
             RequestType::Follow { .. } => handle_follow(bytes),
             RequestType::MergeMaturity { .. } => handle_merge_maturity(bytes),
@@ -0,0 +1,23 @@
+use crate::errors::ApiError;
+use crate::ledger_client::OperationOutput;
+use candid::Nat;
+use icrc_ledger_types::icrc2::approve::ApproveError;
+
+pub fn handle_approve(bytes: Vec<u8>) -> Result<Result<Option<OperationOutput>, ApiError>, String> {
+    let res: Result<Nat, ApproveError> = candid::decode_one(&bytes)
+        .map_err(|err| format!("While parsing the reply of the approve call: {:?}", err))?;
+    let block_index = match res {
+        Ok(block_index) => block_index,
+        // The approval was already applied by a previous submission.
+        Err(ApproveError::Duplicate { duplicate_of }) => duplicate_of,
+        Err(err) => {
+            return Ok(Err(ApiError::TransactionRejected(
+                false,
+                format!("Could not approve: {:?}", err).into(),
+            )))
+        }
+    };
+    let block_index = u64::try_from(block_index.0)
+        .map_err(|err| format!("Invalid block index in the approve reply: {}", err))?;
+    Ok(Ok(Some(OperationOutput::BlockIndex(block_index))))
+}
@@ -1824,9 +1824,9 @@ impl ::std::fmt::Display for Operator {
     }
 }
 
-/// SearchTransactionsRequest models a small subset of the /search/transactions
-/// endpoint. Currently we only support looking up a transaction given its hash;
-/// this functionality is desired by our crypto exchanges partners.
+/// SearchTransactionsRequest models a subset of the /search/transactions
+/// endpoint. Besides the standard fields, the `metadata` may hold the
+/// filters of `request_types::SearchTransactionsMetadata`.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "conversion", derive(LabelledGeneric))]
 pub struct SearchTransactionsRequest {
@@ -1880,6 +1880,10 @@ pub struct SearchTransactionsRequest {
     #[serde(rename = "success")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub success: Option<bool>,
+
+    #[serde(rename = "metadata")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Object>,
 }
 
 impl SearchTransactionsRequest {
@@ -1902,6 +1906,7 @@ impl SearchTransactionsRequest {
             _type: None,
             address: None,
             success: None,
+            metadata: None,
         }
     }
 }
@@ -1940,6 +1945,12 @@ pub struct SearchTransactionsResponse {
     #[serde(rename = "next_offset")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub next_offset: Option<i64>,
+
+    /// The `max_block` to request the next page of a filtered search with.
+    /// Unlike `next_offset`, it stays valid while new blocks are added.
+    #[serde(rename = "next_max_block")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_max_block: Option<i64>,
 }
 
 impl SearchTransactionsResponse {
@@ -1952,6 +1963,7 @@ impl SearchTransactionsResponse {
             transactions,
             total_count,
             next_offset,
+            next_max_block: None,
         }
     }
 }
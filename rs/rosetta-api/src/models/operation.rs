@@ -170,4 +170,7 @@ pub enum OperationType {
     #[serde(rename = "FOLLOW")]
     #[strum(serialize = "FOLLOW")]
     Follow,
+    #[serde(rename = "APPROVE")]
+    #[strum(serialize = "APPROVE")]
+    Approve,
 }
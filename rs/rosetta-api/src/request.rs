@@ -58,6 +58,8 @@ pub enum Request {
     NeuronInfo(NeuronInfo),
     #[serde(rename = "FOLLOW")]
     Follow(Follow),
+    #[serde(rename = "APPROVE")]
+    Approve(Approve),
 }
 
 impl Request {
@@ -147,6 +149,7 @@ impl Request {
                 neuron_index: *neuron_index,
                 controller: controller.map(PublicKeyOrPrincipal::Principal),
             }),
+            Request::Approve(_) => Ok(RequestType::Approve),
         }
     }
 
@@ -176,6 +179,7 @@ impl Request {
                 Request::StakeMaturity(o) => builder.stake_maturity(o),
                 Request::NeuronInfo(o) => builder.neuron_info(o),
                 Request::Follow(o) => builder.follow(o),
+                Request::Approve(o) => builder.approve(o, token_name),
             };
         }
         Ok(builder.build())
@@ -494,6 +498,12 @@ impl TryFrom<&models::Request> for Request {
                     Err(ApiError::invalid_request("Invalid follow request."))
                 }
             }
+            RequestType::Approve => {
+                let args = candid::decode_one(&payload.update_content().arg.0).map_err(|e| {
+                    ApiError::invalid_request(format!("Could not parse approve arguments: {}", e))
+                })?;
+                Ok(Request::Approve(convert::from_approve_args(pid, args)?))
+            }
         }
     }
 }
@@ -77,6 +77,17 @@ impl TransactionOperationResults {
                     merge_metadata(fee, rr);
                     op_idx += 3;
                 }
+                (
+                    RequestResult {
+                        _type: Request::Approve(_),
+                        ..
+                    },
+                    [approve, fee, ..],
+                ) if approve._type == OperationType::Approve && fee._type == OperationType::Fee => {
+                    merge_metadata(approve, rr);
+                    merge_metadata(fee, rr);
+                    op_idx += 2;
+                }
                 (rr, [o, ..]) => {
                     merge_metadata(o, rr);
                     op_idx += 1
@@ -10,11 +10,15 @@ mod construction_submit;
 use crate::ledger_client::pending_proposals_response::PendingProposalsResponse;
 use crate::ledger_client::proposal_info_response::ProposalInfoResponse;
 use crate::models::{CallResponse, Object};
-use crate::request_types::GetProposalInfo;
+use crate::request_types::{
+    GetProposalInfo, SearchTransactionsMetadata, APPROVE, BURN, FEE, MINT, TRANSACTION,
+};
 use crate::{convert, models, API_VERSION, NODE_VERSION};
 use ic_ledger_canister_blocks_synchronizer::blocks::Blocks;
 use ic_ledger_canister_blocks_synchronizer::blocks::HashedBlock;
+use ic_ledger_canister_blocks_synchronizer::blocks::TransactionFilter;
 use ic_ledger_core::block::BlockType;
+use ic_ledger_core::timestamp::TimeStamp;
 use ic_nns_common::pb::v1::NeuronId;
 
 use ic_nns_governance::pb::v1::manage_neuron::NeuronIdOrSubaccount;
@@ -22,7 +26,7 @@ use ic_nns_governance::pb::v1::manage_neuron::NeuronIdOrSubaccount;
 use ic_types::crypto::DOMAIN_IC_REQUEST;
 use ic_types::messages::MessageId;
 use ic_types::CanisterId;
-use icp_ledger::{Block, BlockIndex};
+use icp_ledger::{Block, BlockIndex, Memo, Tokens};
 use std::convert::{TryFrom, TryInto};
 use std::sync::Arc;
 use strum::IntoEnumIterator;
@@ -427,6 +431,54 @@ impl RosettaRequestHandler {
         ))
     }
 
+    async fn get_filtered_transactions(
+        &self,
+        mut filter: TransactionFilter,
+        offset: usize,
+        limit: usize,
+    ) -> Result<SearchTransactionsResponse, ApiError> {
+        let blocks = self.ledger.read_blocks().await;
+
+        let last_idx = blocks.get_latest_verified_hashed_block()?.index;
+        filter.max_block = Some(filter.max_block.map_or(last_idx, |b| b.min(last_idx)));
+
+        // Fetch one more match than requested to learn whether there is a
+        // next page without counting all the matches.
+        let mut heights =
+            blocks.search_transactions(&filter, offset as u64, (limit as u64).saturating_add(1))?;
+        let has_more = heights.len() > limit;
+        heights.truncate(limit);
+
+        let mut txs: Vec<BlockTransaction> = Vec::new();
+        for i in &heights {
+            let hb = blocks.get_hashed_block(i)?;
+            txs.push(BlockTransaction::new(
+                convert::block_id(&hb)?,
+                convert::block_to_transaction(&hb, self.ledger.token_symbol())?,
+            ));
+        }
+
+        let next = offset
+            .checked_add(heights.len())
+            .ok_or_else(|| ApiError::internal_error("offset + limit overflow"))?;
+        let next = i64::try_from(next).map_err(|e| {
+            ApiError::internal_error(format!("Next offset cannot fit in i64: {}", e))
+        })?;
+        let (next_offset, next_max_block) = match heights.last() {
+            Some(&last) if has_more => (Some(next), Some(last as i64 - 1)),
+            _ => (None, None),
+        };
+        // The total count is not computed exactly, as that would scan all
+        // the matches on every page. It counts the matches up to this page,
+        // plus one if there are more.
+        let total_count = if has_more { next + 1 } else { next };
+
+        Ok(SearchTransactionsResponse {
+            next_max_block,
+            ..SearchTransactionsResponse::new(txs, total_count, next_offset)
+        })
+    }
+
     /// Search for a transaction given its hash
     pub async fn search_transactions(
         &self,
@@ -450,10 +502,6 @@ impl RosettaRequestHandler {
             return Err(ApiError::invalid_request("status not supported"));
         }
 
-        if msg._type.is_some() {
-            return Err(ApiError::invalid_request("type not supported"));
-        }
-
         if msg.address.is_some() {
             return Err(ApiError::invalid_request("address not supported"));
         }
@@ -483,6 +531,16 @@ impl RosettaRequestHandler {
         };
         let limit = std::cmp::min(limit, MAX_SEARCH_LIMIT);
 
+        if msg._type.is_some() || msg.metadata.is_some() {
+            if msg.transaction_identifier.is_some() {
+                return Err(ApiError::invalid_request(
+                    "transaction_identifier cannot be combined with other filters",
+                ));
+            }
+            let filter = transaction_filter(&msg, max_block)?;
+            return self.get_filtered_transactions(filter, offset, limit).await;
+        }
+
         if msg.transaction_identifier.is_none() && msg.account_identifier.is_none() {
             return self.get_blocks_range(max_block, offset, limit).await;
         }
@@ -680,6 +738,62 @@ fn get_block(
     }
 }
 
+/// Builds the block store filter of a search request that uses the
+/// operation type or the metadata filters.
+fn transaction_filter(
+    msg: &models::SearchTransactionsRequest,
+    max_block: Option<u64>,
+) -> Result<TransactionFilter, ApiError> {
+    let metadata = SearchTransactionsMetadata::try_from(msg.metadata.clone())?;
+    let account = msg
+        .account_identifier
+        .as_ref()
+        .map(|aid| {
+            from_model_account_identifier(aid)
+                .map_err(|e| ApiError::InvalidAccountId(false, e.into()))
+        })
+        .transpose()?;
+    // The names of the ledger operations that produce a Rosetta operation
+    // of the requested type.
+    let operation_types = match msg._type.as_deref() {
+        None => None,
+        Some(TRANSACTION) => Some(vec!["Transfer", "TransferFrom"]),
+        Some(MINT) => Some(vec!["Mint"]),
+        Some(BURN) => Some(vec!["Burn"]),
+        Some(APPROVE) => Some(vec!["Approve"]),
+        Some(FEE) => Some(vec!["Transfer", "TransferFrom", "Approve"]),
+        Some(t) => {
+            return Err(ApiError::invalid_request(format!(
+                "Searching for operations of type {} is not supported",
+                t
+            )))
+        }
+    };
+    let icrc1_memo = metadata
+        .icrc1_memo
+        .map(|memo| {
+            hex::decode(&memo).map_err(|e| {
+                ApiError::invalid_request(format!("icrc1_memo is not hex encoded: {}", e))
+            })
+        })
+        .transpose()?;
+    Ok(TransactionFilter {
+        account,
+        operation_types: operation_types.map(|types| types.into_iter().map(String::from).collect()),
+        memo: metadata.memo.map(Memo),
+        icrc1_memo,
+        min_amount: metadata.min_amount_e8s.map(Tokens::from_e8s),
+        max_amount: metadata.max_amount_e8s.map(Tokens::from_e8s),
+        min_timestamp: metadata
+            .min_timestamp
+            .map(TimeStamp::from_nanos_since_unix_epoch),
+        max_timestamp: metadata
+            .max_timestamp
+            .map(TimeStamp::from_nanos_since_unix_epoch),
+        max_block,
+    })
+}
+
 fn verify_network_id(canister_id: &CanisterId, net_id: &NetworkIdentifier) -> Result<(), ApiError> {
     verify_network_blockchain(net_id)?;
     let id: CanisterId = net_id.try_into()?;
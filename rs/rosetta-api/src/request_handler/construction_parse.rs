@@ -45,9 +45,9 @@ impl RosettaRequestHandler {
         let mut from_ai = vec![];
 
         for (request_type, HttpCanisterUpdate { arg, sender, .. }) in updates {
-            let from = PrincipalId::try_from(sender.0)
-                .map_err(|e| ApiError::internal_error(e.to_string()))?
-                .into();
+            let sender = PrincipalId::try_from(sender.0)
+                .map_err(|e| ApiError::internal_error(e.to_string()))?;
+            let from = AccountIdentifier::from(sender);
             if msg.signed {
                 from_ai.push(from);
             }
@@ -98,6 +98,7 @@ impl RosettaRequestHandler {
                     neuron_index,
                     controller,
                 } => follow(&mut requests, arg, from, neuron_index, controller)?,
+                RequestType::Approve => approve(&mut requests, arg, sender)?,
             }
         }
 
@@ -128,6 +129,15 @@ fn send(requests: &mut Vec<Request>, arg: Blob, from: AccountIdentifier) -> Resu
     Ok(())
 }
 
+/// Handle APPROVE.
+fn approve(requests: &mut Vec<Request>, arg: Blob, sender: PrincipalId) -> Result<(), ApiError> {
+    let args = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!("Could not decode approve argument: {:?}", e))
+    })?;
+    requests.push(Request::Approve(convert::from_approve_args(sender, args)?));
+    Ok(())
+}
+
 /// Handle STAKE.
 fn stake(
     requests: &mut Vec<Request>,
@@ -209,6 +209,12 @@ impl RosettaRequestHandler {
                     &pks_map,
                     &ingress_expiries,
                 )?,
+                Request::Approve(_) => {
+                    return Err(ApiError::invalid_request(
+                        "Approve operations are not supported through Rosetta: \
+                         the ICP ledger does not implement icrc2_approve.",
+                    ));
+                }
             }
         }
 
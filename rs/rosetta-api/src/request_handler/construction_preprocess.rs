@@ -75,5 +75,9 @@ fn required_public_key(request: Request) -> Result<icp_ledger::AccountIdentifier
         | Request::StakeMaturity(StakeMaturity { account, .. })
         | Request::NeuronInfo(NeuronInfo { account, .. })
         | Request::Follow(Follow { account, .. }) => Ok(account),
+        Request::Approve(_) => Err(ApiError::invalid_request(
+            "Approve operations are not supported through rosetta: \
+             the ICP ledger does not implement icrc2_approve",
+        )),
     }
 }
@@ -7,8 +7,9 @@ use crate::{
     models::{self, operation::Operation, Object},
     transaction_id::TransactionIdentifier,
 };
+use ic_ledger_core::timestamp::TimeStamp;
 use ic_types::PrincipalId;
-use icp_ledger::{AccountIdentifier, BlockIndex, Operation as LedgerOperation, Tokens};
+use icp_ledger::{AccountIdentifier, BlockIndex, Operation as LedgerOperation, Subaccount, Tokens};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::convert::TryFrom;
@@ -36,6 +37,7 @@ pub const REGISTER_VOTE: &str = "REGISTER_VOTE";
 pub const STAKE_MATURITY: &str = "STAKE_MATURITY";
 pub const NEURON_INFO: &str = "NEURON_INFO";
 pub const FOLLOW: &str = "FOLLOW";
+pub const APPROVE: &str = "APPROVE";
 
 /// `RequestType` contains all supported values of `Operation.type`.
 /// Extra information, such as `neuron_index` should only be included
@@ -94,6 +96,9 @@ pub enum RequestType {
         neuron_index: u64,
         controller: Option<PublicKeyOrPrincipal>,
     },
+    #[serde(rename = "APPROVE")]
+    #[serde(alias = "Approve")]
+    Approve,
 }
 
 impl RequestType {
@@ -114,11 +119,13 @@ impl RequestType {
             RequestType::StakeMaturity { .. } => STAKE_MATURITY,
             RequestType::NeuronInfo { .. } => NEURON_INFO,
             RequestType::Follow { .. } => FOLLOW,
+            RequestType::Approve => APPROVE,
         }
     }
 
+    /// Returns true if the request creates a ledger transaction.
     pub const fn is_transfer(&self) -> bool {
-        matches!(self, RequestType::Send)
+        matches!(self, RequestType::Send | RequestType::Approve)
     }
 
     pub const fn is_neuron_management(&self) -> bool {
@@ -274,6 +281,25 @@ pub struct StopDissolve {
     pub neuron_index: u64,
 }
 
+/// Increases the allowance of `spender` on the account.
+/// Unlike `icp_ledger::Operation::Approve`, the spender is identified by its
+/// principal and subaccount because the ledger `icrc2_approve` call needs them.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Approve {
+    pub account: icp_ledger::AccountIdentifier,
+    pub spender: PrincipalId,
+    pub spender_subaccount: Option<Subaccount>,
+    pub allowance: Tokens,
+    pub expires_at: Option<TimeStamp>,
+    pub fee: Tokens,
+}
+
+impl Approve {
+    pub fn spender_account(&self) -> icp_ledger::AccountIdentifier {
+        icp_ledger::AccountIdentifier::new(self.spender, self.spender_subaccount)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Stake {
     pub account: icp_ledger::AccountIdentifier,
@@ -512,6 +538,117 @@ impl From<DisburseMetadata> for Object {
     }
 }
 
+/// The metadata of an APPROVE operation.
+/// Blocks only record the account identifier of the spender, so
+/// `spender_principal` and `spender_subaccount` are only set on operations
+/// that go through the construction API, where they are required.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ApproveMetadata {
+    pub spender: AccountIdentifier,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub spender_principal: Option<PrincipalId>,
+    /// The hex encoded subaccount of the spender.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub spender_subaccount: Option<String>,
+    /// The change of the allowance, negative if the allowance decreased.
+    pub allowance: models::Amount,
+    /// The number of nanoseconds since Unix epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+impl TryFrom<Option<Object>> for ApproveMetadata {
+    type Error = ApiError;
+
+    fn try_from(o: Option<Object>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse APPROVE operation metadata from a JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl From<ApproveMetadata> for Object {
+    fn from(m: ApproveMetadata) -> Self {
+        match serde_json::to_value(m) {
+            Ok(Value::Object(o)) => o,
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// The filters that `/search/transactions` accepts in its metadata, in
+/// addition to the standard fields of the request.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchTransactionsMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub memo: Option<u64>,
+    /// The hex encoded ICRC-1 memo.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub icrc1_memo: Option<String>,
+    /// The inclusive bounds of the transferred amount in e8s.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub min_amount_e8s: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub max_amount_e8s: Option<u64>,
+    /// The inclusive bounds of the block timestamp in nanoseconds since
+    /// Unix epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub min_timestamp: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub max_timestamp: Option<u64>,
+}
+
+impl TryFrom<Option<Object>> for SearchTransactionsMetadata {
+    type Error = ApiError;
+
+    fn try_from(o: Option<Object>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::invalid_request(format!(
+                "Could not parse search transactions metadata from a JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl From<SearchTransactionsMetadata> for Object {
+    fn from(m: SearchTransactionsMetadata) -> Self {
+        match serde_json::to_value(m) {
+            Ok(Value::Object(o)) => o,
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// The metadata of the operations of a transfer made on behalf of the
+/// account owner by an approved spender.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TransferFromMetadata {
+    pub spender: AccountIdentifier,
+}
+
+impl From<TransferFromMetadata> for Object {
+    fn from(m: TransferFromMetadata) -> Self {
+        match serde_json::to_value(m) {
+            Ok(Value::Object(o)) => o,
+            _ => unreachable!(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 pub struct KeyMetadata {
     #[serde(flatten)]
@@ -862,56 +999,143 @@ impl TransactionBuilder {
         operation: &LedgerOperation,
         token_name: &str,
     ) -> Result<(), ApiError> {
-        let mut push_op = |_type: OperationType, account: &AccountIdentifier, amount: i128| {
+        let mut push_op = |_type: OperationType,
+                           account: &AccountIdentifier,
+                           amount: Option<i128>,
+                           metadata: Option<Object>| {
             let operation_identifier = self.allocate_op_id();
             self.ops.push(Operation {
                 operation_identifier,
                 _type,
                 status: None,
                 account: Some(to_model_account_identifier(account)),
-                amount: Some(signed_amount(amount, token_name)),
+                amount: amount.map(|amount| signed_amount(amount, token_name)),
                 related_operations: None,
                 coin_change: None,
-                metadata: None,
+                metadata,
             });
         };
 
         match operation {
             LedgerOperation::Burn { from, amount } => {
-                push_op(OperationType::Burn, from, -i128::from(amount.get_e8s()));
+                push_op(
+                    OperationType::Burn,
+                    from,
+                    Some(-i128::from(amount.get_e8s())),
+                    None,
+                );
             }
             LedgerOperation::Mint { to, amount } => {
-                push_op(OperationType::Mint, to, i128::from(amount.get_e8s()));
+                push_op(
+                    OperationType::Mint,
+                    to,
+                    Some(i128::from(amount.get_e8s())),
+                    None,
+                );
             }
             LedgerOperation::Approve {
-                from, spender, fee, ..
+                from,
+                spender,
+                allowance,
+                expires_at,
+                fee,
             } => {
-                push_op(OperationType::Transaction, from, 0);
-                push_op(OperationType::Transaction, spender, 0);
-                push_op(OperationType::Fee, from, -i128::from(fee.get_e8s()));
+                let metadata = ApproveMetadata {
+                    spender: *spender,
+                    spender_principal: None,
+                    spender_subaccount: None,
+                    allowance: signed_amount(allowance.to_i128(), token_name),
+                    expires_at: expires_at.map(|t| t.as_nanos_since_unix_epoch()),
+                };
+                push_op(OperationType::Approve, from, None, Some(metadata.into()));
+                push_op(
+                    OperationType::Fee,
+                    from,
+                    Some(-i128::from(fee.get_e8s())),
+                    None,
+                );
             }
             LedgerOperation::Transfer {
                 from,
                 to,
                 amount,
                 fee,
+            } => {
+                let amount = i128::from(amount.get_e8s());
+                push_op(OperationType::Transaction, from, Some(-amount), None);
+                push_op(OperationType::Transaction, to, Some(amount), None);
+                push_op(
+                    OperationType::Fee,
+                    from,
+                    Some(-i128::from(fee.get_e8s())),
+                    None,
+                );
             }
-            | LedgerOperation::TransferFrom {
+            LedgerOperation::TransferFrom {
                 from,
                 to,
+                spender,
                 amount,
                 fee,
-                ..
             } => {
+                let metadata = || Some(TransferFromMetadata { spender: *spender }.into());
                 let amount = i128::from(amount.get_e8s());
-                push_op(OperationType::Transaction, from, -amount);
-                push_op(OperationType::Transaction, to, amount);
-                push_op(OperationType::Fee, from, -i128::from(fee.get_e8s()));
+                push_op(OperationType::Transaction, from, Some(-amount), metadata());
+                push_op(OperationType::Transaction, to, Some(amount), metadata());
+                push_op(
+                    OperationType::Fee,
+                    from,
+                    Some(-i128::from(fee.get_e8s())),
+                    metadata(),
+                );
             }
         };
         Ok(())
     }
 
+    /// Add a `Request::Approve` to the Transaction.
+    pub fn approve(&mut self, approve: &Approve, token_name: &str) {
+        let Approve {
+            account,
+            spender,
+            spender_subaccount,
+            allowance,
+            expires_at,
+            fee,
+        } = approve;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::Approve,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                ApproveMetadata {
+                    spender: approve.spender_account(),
+                    spender_principal: Some(*spender),
+                    spender_subaccount: spender_subaccount.map(|s| hex::encode(s.0)),
+                    allowance: signed_amount(i128::from(allowance.get_e8s()), token_name),
+                    expires_at: expires_at.map(|t| t.as_nanos_since_unix_epoch()),
+                }
+                .into(),
+            ),
+        });
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::Fee,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: Some(signed_amount(-i128::from(fee.get_e8s()), token_name)),
+            related_operations: None,
+            coin_change: None,
+            metadata: None,
+        });
+    }
+
     pub fn stake(&mut self, stake: &Stake) {
         let Stake {
             account,
@@ -2,11 +2,14 @@ use std::{convert::TryFrom, str::FromStr};
 
 use ic_ledger_canister_core::ledger::LedgerTransaction;
 use ic_ledger_core::block::HashOf;
+use ic_ledger_core::timestamp::TimeStamp;
+use ic_ledger_core::tokens::SignedTokens;
 use ic_types::{
     messages::{HttpCallContent, HttpRequestEnvelope},
     PrincipalId,
 };
-use icp_ledger::{SendArgs, Transaction};
+use icp_ledger::{Memo, Operation, SendArgs, Transaction};
+use icrc_ledger_types::icrc2::approve::ApproveArgs;
 use serde::{Deserialize, Serialize};
 
 use crate::{convert, errors::ApiError, request_types::RequestType};
@@ -64,6 +67,33 @@ impl TransactionIdentifier {
 
                 Ok(TransactionIdentifier::from(&hash))
             }
+            RequestType::Approve => {
+                let HttpCallContent::Call { update } = &signed_transaction.content;
+                let from = PrincipalId::try_from(update.sender.clone().0)
+                    .map_err(|e| ApiError::internal_error(e.to_string()))?;
+                let args: ApproveArgs = candid::decode_one(&update.arg.0).map_err(|e| {
+                    ApiError::internal_error(format!("Could not decode approve argument: {}", e))
+                })?;
+                let created_at_time = args.created_at_time.ok_or_else(|| ApiError::internal_error(
+                    "A transaction ID cannot be generated from a constructed transaction without an explicit 'created_at_time'"
+                ))?;
+                let approve = convert::from_approve_args(from, args)?;
+
+                let transaction = Transaction {
+                    operation: Operation::Approve {
+                        from: approve.account,
+                        spender: approve.spender_account(),
+                        allowance: SignedTokens::from_e8s(approve.allowance.get_e8s()),
+                        expires_at: approve.expires_at,
+                        fee: approve.fee,
+                    },
+                    memo: Memo(0),
+                    icrc1_memo: None,
+                    created_at_time: Some(TimeStamp::from_nanos_since_unix_epoch(created_at_time)),
+                };
+
+                Ok(TransactionIdentifier::from(&transaction.hash()))
+            }
             RequestType::Stake { .. }
             | RequestType::StartDissolve { .. }
             | RequestType::StopDissolve { .. }
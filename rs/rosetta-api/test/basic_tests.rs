@@ -15,6 +15,7 @@ use ic_rosetta_api::models::{
     SyncStatus,
 };
 use ic_rosetta_api::request_handler::RosettaRequestHandler;
+use ic_rosetta_api::request_types::SearchTransactionsMetadata;
 use ic_rosetta_api::transaction_id::TransactionIdentifier;
 use ic_rosetta_api::{models, API_VERSION, NODE_VERSION};
 use std::sync::Arc;
@@ -353,6 +354,83 @@ async fn blocks_test() {
     assert_eq!(resp.next_offset, Some(50));
 }
 
+#[actix_rt::test]
+async fn search_transactions_filters_test() {
+    init_test_logger();
+
+    let ledger = Arc::new(TestLedger::new());
+    let req_handler = RosettaRequestHandler::new_with_default_blockchain(ledger.clone());
+    let scribe = Scribe::new_with_sample_data(10, 100);
+
+    for b in &scribe.blockchain {
+        ledger.add_block(b.clone()).await.ok();
+    }
+
+    let mints: Vec<i64> = scribe
+        .blockchain
+        .iter()
+        .rev()
+        .filter(|hb| {
+            matches!(
+                Block::decode(hb.block.clone())
+                    .unwrap()
+                    .transaction
+                    .operation,
+                icp_ledger::Operation::Mint { .. }
+            )
+        })
+        .map(|hb| hb.index as i64)
+        .collect();
+
+    let mut req = SearchTransactionsRequest::new(req_handler.network_id(), None, None);
+    req._type = Some("MINT".to_string());
+    req.limit = Some(5);
+    let resp = req_handler.search_transactions(req.clone()).await.unwrap();
+    let indices: Vec<i64> = resp
+        .transactions
+        .iter()
+        .map(|t| t.block_identifier.index)
+        .collect();
+    assert_eq!(indices, mints[..5].to_vec());
+    // The total count only tells that there are more matches than returned.
+    assert_eq!(resp.total_count, 6);
+    assert_eq!(resp.next_offset, Some(5));
+    assert_eq!(resp.next_max_block, Some(mints[4] - 1));
+
+    let mut by_offset = req.clone();
+    by_offset.offset = resp.next_offset;
+    let resp_by_offset = req_handler.search_transactions(by_offset).await.unwrap();
+    assert_eq!(
+        resp_by_offset.transactions[0].block_identifier.index,
+        mints[5]
+    );
+
+    let mut by_max_block = req.clone();
+    by_max_block.max_block = resp.next_max_block;
+    let resp_by_max_block = req_handler.search_transactions(by_max_block).await.unwrap();
+    assert_eq!(resp_by_max_block.transactions, resp_by_offset.transactions);
+
+    // The scribe uses the block index as memo.
+    let mut req = SearchTransactionsRequest::new(req_handler.network_id(), None, None);
+    req.metadata = Some(
+        SearchTransactionsMetadata {
+            memo: Some(42),
+            ..Default::default()
+        }
+        .into(),
+    );
+    let resp = req_handler.search_transactions(req).await.unwrap();
+    assert_eq!(resp.transactions.len(), 1);
+    assert_eq!(resp.transactions[0].block_identifier.index, 42);
+    assert_eq!(resp.total_count, 1);
+    assert_eq!(resp.next_offset, None);
+    assert_eq!(resp.next_max_block, None);
+
+    let mut req = SearchTransactionsRequest::new(req_handler.network_id(), None, None);
+    req._type = Some("STAKE".to_string());
+    assert!(req_handler.search_transactions(req).await.is_err());
+}
+
 #[actix_rt::test]
 async fn balances_test() {
     init_test_logger();
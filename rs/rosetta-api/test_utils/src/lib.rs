@@ -10,8 +10,9 @@ use ic_rosetta_api::models::{
 };
 use ic_rosetta_api::models::{ConstructionSubmitResponse, Error as RosettaError};
 use ic_rosetta_api::request_types::{
-    AddHotKey, ChangeAutoStakeMaturity, Disburse, Follow, MergeMaturity, NeuronInfo, RegisterVote,
-    RemoveHotKey, SetDissolveTimestamp, Spawn, Stake, StakeMaturity, StartDissolve, StopDissolve,
+    AddHotKey, Approve, ChangeAutoStakeMaturity, Disburse, Follow, MergeMaturity, NeuronInfo,
+    RegisterVote, RemoveHotKey, SetDissolveTimestamp, Spawn, Stake, StakeMaturity, StartDissolve,
+    StopDissolve,
 };
 use ic_rosetta_api::transaction_id::TransactionIdentifier;
 use ic_rosetta_api::{convert, errors, errors::ApiError, DEFAULT_TOKEN_SYMBOL};
@@ -121,6 +122,13 @@ where
             | Request::Follow(Follow { account, .. }) => {
                 all_sender_account_ids.push(to_model_account_identifier(&account));
             }
+            Request::Approve(Approve { account, fee, .. }) => {
+                trans_fee_amount = Some(tokens_to_amount(fee, token_name).unwrap());
+                all_sender_account_ids.push(to_model_account_identifier(&account));
+
+                // just a sanity check
+                assert!(fee_found, "There should be a fee op in operations");
+            }
             Request::Transfer(Operation::Burn { .. }) => {
                 panic!("Burn operations are supported here")
             }
@@ -5781,6 +5781,9 @@ mod tests {
             0,
             Some(0),
             0,
+            None,
+            None,
+            0,
             0,
         )
     }
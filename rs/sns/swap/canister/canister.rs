@@ -683,6 +683,9 @@ mod tests {
             0,
             None,
             0,
+            None,
+            None,
+            0,
             0,
         )
     }
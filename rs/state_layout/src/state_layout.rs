@@ -2,6 +2,7 @@ use crate::error::LayoutError;
 use crate::utils::do_copy;
 
 use ic_base_types::{NumBytes, NumSeconds};
+use ic_ic00_types::{CanisterLogRecord, LogVisibility};
 use ic_logger::{error, info, ReplicaLogger};
 use ic_metrics::{buckets::decimal_buckets, MetricsRegistry};
 use ic_protobuf::{
@@ -16,12 +17,13 @@ use ic_protobuf::{
 };
 use ic_replicated_state::{
     canister_state::{execution_state::WasmMetadata, system_state::CyclesUseCase},
-    CallContextManager, CanisterStatus, ExecutionTask, ExportedFunctions, Global, NumWasmPages,
+    CallContextManager, CanisterLog, CanisterStatus, ExecutionTask, ExportedFunctions, Global,
+    NumWasmPages, SnapshotId,
 };
 use ic_sys::mmap::ScopedMmap;
 use ic_types::{
     nominal_cycles::NominalCycles, AccumulatedPriority, CanisterId, ComputeAllocation, Cycles,
-    ExecutionRound, Height, MemoryAllocation, NumInstructions, PrincipalId,
+    ExecutionRound, Height, MemoryAllocation, NumInstructions, PrincipalId, Time,
 };
 use ic_utils::fs::sync_path;
 use ic_utils::thread::parallel_map;
@@ -140,6 +142,23 @@ pub struct CanisterStateBits {
     pub global_timer_nanos: Option<u64>,
     pub canister_version: u64,
     pub consumed_cycles_since_replica_started_by_use_cases: BTreeMap<CyclesUseCase, NominalCycles>,
+    pub log_visibility: LogVisibility,
+    pub canister_log: CanisterLog,
+    pub wasm_memory_limit: Option<NumBytes>,
+    pub reserved_balance: Cycles,
+    pub reserved_balance_limit: Option<Cycles>,
+}
+
+/// This struct contains bits of a `CanisterSnapshot` that are not already
+/// covered by the snapshot's Wasm binary and memory files.
+#[derive(Debug)]
+pub struct CanisterSnapshotBits {
+    pub snapshot_id: SnapshotId,
+    pub taken_at_timestamp: Time,
+    pub canister_version: u64,
+    pub certified_data: Vec<u8>,
+    pub execution_state_bits: ExecutionStateBits,
+    pub stable_memory_size: NumWasmPages,
 }
 
 #[derive(Clone)]
@@ -188,7 +207,8 @@ struct CheckpointRefData {
 /// │   │       ├── queues.pbuf
 /// │   │       ├── software.wasm
 /// │   │       ├── stable_memory.bin
-/// │   │       └── vmemory_0.bin
+/// │   │       ├── vmemory_0.bin
+/// │   │       └── wasm_chunk_store.bin
 /// │   ├── subnet_queues.pbuf
 /// │   └── system_metadata.pbuf
 /// │
@@ -201,7 +221,8 @@ struct CheckpointRefData {
 /// │      │       ├── queues.pbuf
 /// │      │       ├── software.wasm
 /// │      │       ├── stable_memory.bin
-/// │      │       └── vmemory_0.bin
+/// │      │       ├── vmemory_0.bin
+/// │      │       └── wasm_chunk_store.bin
 /// │      ├── subnet_queues.pbuf
 /// │      └── system_metadata.pbuf
 /// │
@@ -350,6 +371,27 @@ impl TipHandler {
         }
         Ok(())
     }
+
+    /// Deletes snapshots from tip if they are not in ids.
+    pub fn filter_tip_snapshots(
+        &mut self,
+        height: Height,
+        ids: &BTreeSet<SnapshotId>,
+    ) -> Result<(), LayoutError> {
+        let tip = self.tip(height)?;
+        let snapshots_on_disk = tip.snapshot_ids()?;
+        for id in snapshots_on_disk {
+            if !ids.contains(&id) {
+                let snapshot_path = tip.snapshot(&id)?.raw_path();
+                std::fs::remove_dir_all(&snapshot_path).map_err(|err| LayoutError::IoError {
+                    path: snapshot_path,
+                    message: "Cannot remove snapshot.".to_string(),
+                    io_err: err,
+                })?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl StateLayout {
@@ -1243,6 +1285,33 @@ impl<Permissions: AccessPolicy> CheckpointLayout<Permissions> {
         )
     }
 
+    pub fn snapshot_ids(&self) -> Result<Vec<SnapshotId>, LayoutError> {
+        // Unlike `canister_states`, the `snapshots` directory only exists
+        // if the state contains at least one snapshot.
+        let snapshots_dir = self.root.join("snapshots");
+        collect_subdirs(snapshots_dir.as_path(), |p| {
+            let blob = hex::decode(p).unwrap_or_else(|err| {
+                panic!(
+                    "Failed to convert directory name {} into a snapshot id: {}",
+                    p, err
+                )
+            });
+
+            SnapshotId::try_from(&blob[..]).expect("failed to parse snapshot id")
+        })
+    }
+
+    pub fn snapshot(
+        &self,
+        snapshot_id: &SnapshotId,
+    ) -> Result<SnapshotLayout<Permissions>, LayoutError> {
+        SnapshotLayout::new(
+            self.root
+                .join("snapshots")
+                .join(hex::encode(snapshot_id.to_vec())),
+        )
+    }
+
     pub fn height(&self) -> Height {
         self.height
     }
@@ -1297,6 +1366,52 @@ impl<Permissions: AccessPolicy> CanisterLayout<Permissions> {
     pub fn stable_memory_blob(&self) -> PathBuf {
         self.canister_root.join("stable_memory.bin")
     }
+
+    /// The Wasm chunk store, encoded as protobuf. Unlike `.pbuf` files, it is
+    /// kept when the tip is reset to a checkpoint, so it only needs to be
+    /// rewritten when the store changes.
+    pub fn wasm_chunk_store(
+        &self,
+    ) -> ProtoFileWith<pb_canister_state_bits::WasmChunkStore, Permissions> {
+        self.canister_root.join("wasm_chunk_store.bin").into()
+    }
+}
+
+pub struct SnapshotLayout<Permissions: AccessPolicy> {
+    snapshot_root: PathBuf,
+    permissions_tag: PhantomData<Permissions>,
+}
+
+impl<Permissions: AccessPolicy> SnapshotLayout<Permissions> {
+    pub fn new(snapshot_root: PathBuf) -> Result<Self, LayoutError> {
+        Permissions::check_dir(&snapshot_root)?;
+        Ok(Self {
+            snapshot_root,
+            permissions_tag: PhantomData,
+        })
+    }
+
+    pub fn raw_path(&self) -> PathBuf {
+        self.snapshot_root.clone()
+    }
+
+    pub fn snapshot(
+        &self,
+    ) -> ProtoFileWith<pb_canister_state_bits::CanisterSnapshotBits, Permissions> {
+        self.snapshot_root.join("snapshot.pbuf").into()
+    }
+
+    pub fn wasm(&self) -> WasmFile<Permissions> {
+        self.snapshot_root.join("software.wasm").into()
+    }
+
+    pub fn vmemory_0(&self) -> PathBuf {
+        self.snapshot_root.join("vmemory_0.bin")
+    }
+
+    pub fn stable_memory_blob(&self) -> PathBuf {
+        self.snapshot_root.join("stable_memory.bin")
+    }
 }
 
 fn open_for_write(path: &Path) -> Result<std::fs::File, LayoutError> {
@@ -1556,6 +1671,21 @@ impl From<CanisterStateBits> for pb_canister_state_bits::CanisterStateBits {
                     cycles: Some((&entry.1).into()),
                 })
                 .collect(),
+            log_visibility: pb_canister_state_bits::LogVisibility::from(item.log_visibility).into(),
+            canister_log_records: item
+                .canister_log
+                .records()
+                .iter()
+                .map(|record| pb_canister_state_bits::CanisterLogRecord {
+                    idx: record.idx,
+                    timestamp_nanos: record.timestamp_nanos,
+                    content: record.content.clone(),
+                })
+                .collect(),
+            next_canister_log_record_idx: item.canister_log.next_idx(),
+            wasm_memory_limit: item.wasm_memory_limit.map(|limit| limit.get()),
+            reserved_balance: Some(item.reserved_balance.into()),
+            reserved_balance_limit: item.reserved_balance_limit.map(|limit| limit.into()),
         }
     }
 }
@@ -1595,6 +1725,17 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
             .transpose()?
             .unwrap_or_else(Cycles::zero);
 
+        let reserved_balance = value
+            .reserved_balance
+            .map(|c| c.try_into())
+            .transpose()?
+            .unwrap_or_else(Cycles::zero);
+
+        let reserved_balance_limit = value
+            .reserved_balance_limit
+            .map(|c| c.try_into())
+            .transpose()?;
+
         let task_queue = value
             .task_queue
             .into_iter()
@@ -1651,6 +1792,26 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
                     )
                 })
                 .collect(),
+            // Checkpoints written before log visibility was introduced have the
+            // field unset, which maps to the default visibility.
+            log_visibility: pb_canister_state_bits::LogVisibility::from_i32(value.log_visibility)
+                .and_then(|v| LogVisibility::try_from(v).ok())
+                .unwrap_or_default(),
+            canister_log: CanisterLog::new(
+                value.next_canister_log_record_idx,
+                value
+                    .canister_log_records
+                    .into_iter()
+                    .map(|record| CanisterLogRecord {
+                        idx: record.idx,
+                        timestamp_nanos: record.timestamp_nanos,
+                        content: record.content,
+                    })
+                    .collect(),
+            ),
+            wasm_memory_limit: value.wasm_memory_limit.map(NumBytes::from),
+            reserved_balance,
+            reserved_balance_limit,
         })
     }
 }
@@ -1708,6 +1869,39 @@ impl TryFrom<pb_canister_state_bits::ExecutionStateBits> for ExecutionStateBits
     }
 }
 
+impl From<CanisterSnapshotBits> for pb_canister_state_bits::CanisterSnapshotBits {
+    fn from(item: CanisterSnapshotBits) -> Self {
+        Self {
+            snapshot_id: item.snapshot_id.local_id(),
+            canister_id: Some(item.snapshot_id.canister_id().into()),
+            taken_at_timestamp_nanos: item.taken_at_timestamp.as_nanos_since_unix_epoch(),
+            canister_version: item.canister_version,
+            certified_data: item.certified_data,
+            execution_state_bits: Some((&item.execution_state_bits).into()),
+            stable_memory_size64: item.stable_memory_size.get() as u64,
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::CanisterSnapshotBits> for CanisterSnapshotBits {
+    type Error = ProxyDecodeError;
+    fn try_from(value: pb_canister_state_bits::CanisterSnapshotBits) -> Result<Self, Self::Error> {
+        let canister_id: CanisterId =
+            try_from_option_field(value.canister_id, "CanisterSnapshotBits::canister_id")?;
+        Ok(Self {
+            snapshot_id: SnapshotId::new(canister_id, value.snapshot_id),
+            taken_at_timestamp: Time::from_nanos_since_unix_epoch(value.taken_at_timestamp_nanos),
+            canister_version: value.canister_version,
+            certified_data: value.certified_data,
+            execution_state_bits: try_from_option_field(
+                value.execution_state_bits,
+                "CanisterSnapshotBits::execution_state_bits",
+            )?,
+            stable_memory_size: NumWasmPages::from(value.stable_memory_size64 as usize),
+        })
+    }
+}
+
 fn dir_file_names(p: &Path) -> std::io::Result<Vec<String>> {
     if !p.exists() {
         return Ok(vec![]);
@@ -2000,6 +2194,11 @@ mod test {
             global_timer_nanos: None,
             canister_version: 0,
             consumed_cycles_since_replica_started_by_use_cases: BTreeMap::new(),
+            log_visibility: LogVisibility::default(),
+            canister_log: CanisterLog::default(),
+            wasm_memory_limit: None,
+            reserved_balance: Cycles::zero(),
+            reserved_balance_limit: None,
         }
     }
 
@@ -2061,6 +2260,107 @@ mod test {
         assert_eq!(canister_state_bits.controllers, expected_controllers);
     }
 
+    #[test]
+    fn test_encode_decode_canister_log() {
+        let mut canister_log = CanisterLog::default();
+        canister_log.add_record(10, b"hello".to_vec());
+        canister_log.add_record(20, b"world".to_vec());
+
+        let canister_state_bits = CanisterStateBits {
+            log_visibility: LogVisibility::Public,
+            canister_log: canister_log.clone(),
+            ..default_canister_state_bits()
+        };
+
+        let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+        let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+        assert_eq!(canister_state_bits.log_visibility, LogVisibility::Public);
+        assert_eq!(canister_state_bits.canister_log, canister_log);
+    }
+
+    #[test]
+    fn test_encode_decode_wasm_memory_limit() {
+        for wasm_memory_limit in [None, Some(NumBytes::from(1 << 30))] {
+            let canister_state_bits = CanisterStateBits {
+                wasm_memory_limit,
+                ..default_canister_state_bits()
+            };
+
+            let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+            let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+            assert_eq!(canister_state_bits.wasm_memory_limit, wasm_memory_limit);
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_reserved_balance() {
+        for reserved_balance_limit in [None, Some(Cycles::new(1_000_000))] {
+            let canister_state_bits = CanisterStateBits {
+                reserved_balance: Cycles::new(12_345),
+                reserved_balance_limit,
+                ..default_canister_state_bits()
+            };
+
+            let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+            let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+            assert_eq!(canister_state_bits.reserved_balance, Cycles::new(12_345));
+            assert_eq!(
+                canister_state_bits.reserved_balance_limit,
+                reserved_balance_limit
+            );
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_snapshot_bits() {
+        let snapshot_id = SnapshotId::new(canister_test_id(7), 42);
+        let snapshot_bits = CanisterSnapshotBits {
+            snapshot_id,
+            taken_at_timestamp: mock_time(),
+            canister_version: 3,
+            certified_data: vec![1, 2, 3],
+            execution_state_bits: ExecutionStateBits {
+                exported_globals: vec![Global::I64(5)],
+                heap_size: NumWasmPages::from(2),
+                exports: ExportedFunctions::new(BTreeSet::new()),
+                last_executed_round: ExecutionRound::from(0),
+                metadata: WasmMetadata::default(),
+                binary_hash: None,
+            },
+            stable_memory_size: NumWasmPages::from(4),
+        };
+
+        let pb_bits = pb_canister_state_bits::CanisterSnapshotBits::from(snapshot_bits);
+        let snapshot_bits = CanisterSnapshotBits::try_from(pb_bits).unwrap();
+
+        assert_eq!(snapshot_bits.snapshot_id, snapshot_id);
+        assert_eq!(snapshot_bits.canister_version, 3);
+        assert_eq!(snapshot_bits.certified_data, vec![1, 2, 3]);
+        assert_eq!(snapshot_bits.execution_state_bits.heap_size, 2.into());
+        assert_eq!(snapshot_bits.stable_memory_size, 4.into());
+    }
+
+    #[test]
+    fn test_snapshot_ids_roundtrip_through_layout() {
+        let tempdir = tmpdir("checkpoint");
+        let checkpoint_layout: CheckpointLayout<WriteOnly> =
+            CheckpointLayout::new_untracked(tempdir.path().to_owned(), Height::new(0)).unwrap();
+        let snapshot_ids = vec![
+            SnapshotId::new(canister_test_id(1), 0),
+            SnapshotId::new(canister_test_id(2), 1),
+        ];
+        for snapshot_id in &snapshot_ids {
+            checkpoint_layout.snapshot(snapshot_id).unwrap();
+        }
+
+        let mut snapshot_ids_on_disk = checkpoint_layout.snapshot_ids().unwrap();
+        snapshot_ids_on_disk.sort();
+        assert_eq!(snapshot_ids_on_disk, snapshot_ids);
+    }
+
     #[test]
     fn test_encode_decode_empty_history() {
         // A canister state with empty history.
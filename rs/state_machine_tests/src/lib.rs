@@ -86,7 +86,7 @@ use ic_types::{
         SignedIngress, UserQuery,
     },
     xnet::StreamIndex,
-    CryptoHashOfPartialState, Height, NodeId, NumberOfNodes, Randomness, RegistryVersion,
+    CryptoHashOfPartialState, Height, NodeId, NumBytes, NumberOfNodes, Randomness, RegistryVersion,
 };
 pub use ic_types::{
     ingress::{IngressState, IngressStatus, WasmResult},
@@ -762,6 +762,7 @@ impl StateMachine {
             registry_version: self.registry_client.get_latest_version(),
             time: self.time.get(),
             consensus_responses: payload.consensus_responses,
+            canister_http_received_bytes: payload.canister_http_received_bytes,
         };
         self.message_routing
             .deliver_batch(batch)
@@ -1724,6 +1725,7 @@ pub struct PayloadBuilder {
     ingress_messages: Vec<SignedIngress>,
     xnet_payload: XNetPayload,
     consensus_responses: Vec<Response>,
+    canister_http_received_bytes: BTreeMap<CallbackId, NumBytes>,
 }
 
 impl Default for PayloadBuilder {
@@ -1734,6 +1736,7 @@ impl Default for PayloadBuilder {
             ingress_messages: Default::default(),
             xnet_payload: Default::default(),
             consensus_responses: Default::default(),
+            canister_http_received_bytes: Default::default(),
         }
         .with_max_expiry_time_from_now(GENESIS.into())
     }
@@ -1798,6 +1801,15 @@ impl PayloadBuilder {
     }
 
     pub fn http_response(mut self, id: CallbackId, payload: &CanisterHttpResponsePayload) -> Self {
+        // The adapter counts the bytes of the headers and the body it receives.
+        let received_bytes = payload.body.len()
+            + payload
+                .headers
+                .iter()
+                .map(|h| h.name.len() + h.value.len())
+                .sum::<usize>();
+        self.canister_http_received_bytes
+            .insert(id, NumBytes::from(received_bytes as u64));
         self.consensus_responses.push(Response {
             originator: CanisterId::ic_00(),
             respondent: CanisterId::ic_00(),
@@ -8,10 +8,13 @@ use ic_replicated_state::page_map::PageAllocatorFileDescriptor;
 use ic_replicated_state::Memory;
 use ic_replicated_state::{
     canister_state::execution_state::WasmBinary, canister_state::system_state::CanisterHistory,
-    page_map::PageMap, CanisterMetrics, CanisterState, ExecutionState, ReplicatedState,
-    SchedulerState, SystemState,
+    page_map::PageMap, CanisterMetrics, CanisterSnapshot, CanisterSnapshots, CanisterState,
+    ExecutionState, ReplicatedState, SchedulerState, SnapshotId, SystemState, WasmChunkStore,
+};
+use ic_state_layout::{
+    CanisterLayout, CanisterSnapshotBits, CanisterStateBits, CheckpointLayout, ReadOnly,
+    ReadPolicy, SnapshotLayout,
 };
-use ic_state_layout::{CanisterLayout, CanisterStateBits, CheckpointLayout, ReadOnly, ReadPolicy};
 use ic_types::{CanisterTimer, Height, LongExecutionMode, Time};
 use ic_utils::thread::parallel_map;
 use std::collections::BTreeMap;
@@ -66,6 +69,13 @@ pub(crate) fn make_checkpoint(
         })
         .unwrap();
 
+    tip_channel
+        .send(TipRequest::FilterTipSnapshots {
+            height,
+            ids: state.canister_snapshots.snapshot_ids(),
+        })
+        .unwrap();
+
     let cp = {
         let _timer = metrics
             .make_checkpoint_step_duration
@@ -214,7 +224,31 @@ pub fn load_checkpoint<P: ReadPolicy + Send + Sync>(
         canister_states
     };
 
-    let state = ReplicatedState::new_from_checkpoint(canister_states, metadata, subnet_queues);
+    let canister_snapshots = {
+        let _timer = metrics
+            .load_checkpoint_step_duration
+            .with_label_values(&["canister_snapshots"])
+            .start_timer();
+
+        let mut canister_snapshots = CanisterSnapshots::default();
+        for snapshot_id in checkpoint_layout.snapshot_ids()? {
+            let snapshot = load_snapshot(
+                &checkpoint_layout.snapshot(&snapshot_id)?,
+                &snapshot_id,
+                checkpoint_layout.height(),
+                Arc::clone(&fd_factory),
+            )?;
+            canister_snapshots.push(snapshot_id, Arc::new(snapshot));
+        }
+        canister_snapshots
+    };
+
+    let state = ReplicatedState::new_from_checkpoint(
+        canister_states,
+        metadata,
+        subnet_queues,
+        canister_snapshots,
+    );
 
     Ok(state)
 }
@@ -287,6 +321,18 @@ pub fn load_canister_state<P: ReadPolicy>(
     })?;
     durations.insert("canister_metadata", starting_time.elapsed());
 
+    let starting_time = Instant::now();
+    // A missing file means an empty store, e.g. in checkpoints written before
+    // the chunk store existed.
+    let wasm_chunk_store = WasmChunkStore::from_checkpoint(
+        canister_layout
+            .wasm_chunk_store()
+            .deserialize_opt()?
+            .unwrap_or_default()
+            .chunks,
+    );
+    durations.insert("wasm_chunk_store", starting_time.elapsed());
+
     let session_nonce = None;
 
     let execution_state = match canister_state_bits.execution_state_bits {
@@ -374,6 +420,12 @@ pub fn load_canister_state<P: ReadPolicy>(
         CanisterTimer::from_nanos_since_unix_epoch(canister_state_bits.global_timer_nanos),
         canister_state_bits.canister_version,
         canister_history,
+        canister_state_bits.log_visibility,
+        canister_state_bits.canister_log,
+        wasm_chunk_store,
+        canister_state_bits.wasm_memory_limit,
+        canister_state_bits.reserved_balance,
+        canister_state_bits.reserved_balance_limit,
     );
 
     let canister_state = CanisterState {
@@ -413,3 +465,56 @@ fn load_canister_state_from_checkpoint<P: ReadPolicy>(
         Arc::clone(&fd_factory),
     )
 }
+
+/// Loads the canister snapshot with the given id from the given layout.
+pub fn load_snapshot<P: ReadPolicy>(
+    snapshot_layout: &SnapshotLayout<P>,
+    snapshot_id: &SnapshotId,
+    height: Height,
+    fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+) -> Result<CanisterSnapshot, CheckpointError> {
+    let pb_snapshot_bits = snapshot_layout.snapshot().deserialize()?;
+    let snapshot_bits = CanisterSnapshotBits::try_from(pb_snapshot_bits).map_err(|err| {
+        CheckpointError::ProtoError {
+            path: snapshot_layout.raw_path(),
+            field: format!("snapshots[{}]::snapshot_bits", snapshot_id),
+            proto_err: err.to_string(),
+        }
+    })?;
+    let execution_state_bits = snapshot_bits.execution_state_bits;
+
+    let wasm_memory = Memory::new(
+        PageMap::open(
+            &snapshot_layout.vmemory_0(),
+            height,
+            Arc::clone(&fd_factory),
+        )?,
+        execution_state_bits.heap_size,
+    );
+    let stable_memory = Memory::new(
+        PageMap::open(
+            &snapshot_layout.stable_memory_blob(),
+            height,
+            Arc::clone(&fd_factory),
+        )?,
+        snapshot_bits.stable_memory_size,
+    );
+    let wasm_binary = WasmBinary::new(
+        snapshot_layout
+            .wasm()
+            .deserialize(execution_state_bits.binary_hash)?,
+    );
+
+    Ok(CanisterSnapshot::new(
+        snapshot_id.canister_id(),
+        snapshot_bits.taken_at_timestamp,
+        snapshot_bits.canister_version,
+        snapshot_bits.certified_data,
+        wasm_binary,
+        wasm_memory,
+        stable_memory,
+        execution_state_bits.exported_globals,
+        execution_state_bits.exports,
+        execution_state_bits.metadata,
+    ))
+}
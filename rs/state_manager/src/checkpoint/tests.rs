@@ -13,6 +13,7 @@ use ic_replicated_state::{
 use ic_state_layout::StateLayout;
 use ic_sys::PAGE_SIZE;
 use ic_test_utilities::{
+    mock_time,
     state::{canister_ids, new_canister_state},
     types::{
         ids::{canister_test_id, message_test_id, subnet_test_id, user_test_id},
@@ -307,6 +308,78 @@ fn can_recover_from_a_checkpoint() {
     });
 }
 
+#[test]
+fn can_recover_canister_snapshots() {
+    with_test_replica_logger(|log| {
+        let tmp = tmpdir("checkpoint");
+        let root = tmp.path().to_path_buf();
+        let layout = StateLayout::try_new(log.clone(), root, &MetricsRegistry::new()).unwrap();
+        let tip_handler = layout.capture_tip_handler();
+        let state_manager_metrics = state_manager_metrics();
+        let (_tip_thread, tip_channel) = spawn_tip_thread(
+            log,
+            tip_handler,
+            layout.clone(),
+            state_manager_metrics.clone(),
+            MaliciousFlags::default(),
+        );
+
+        let canister_id: CanisterId = canister_test_id(10);
+        let mut canister_state = new_canister_state(
+            canister_id,
+            user_test_id(24).get(),
+            INITIAL_CYCLES,
+            NumSeconds::from(100_000),
+        );
+        canister_state.execution_state = Some(ExecutionState {
+            canister_root: "NOT_USED".into(),
+            session_nonce: None,
+            wasm_binary: WasmBinary::new(empty_wasm()),
+            wasm_memory: one_page_of(1),
+            stable_memory: one_page_of(2),
+            exported_globals: vec![],
+            exports: ExportedFunctions::new(BTreeSet::new()),
+            metadata: WasmMetadata::default(),
+            last_executed_round: ExecutionRound::from(0),
+        });
+        canister_state.system_state.certified_data = vec![3, 4];
+
+        let own_subnet_type = SubnetType::Application;
+        let mut state = ReplicatedState::new(subnet_test_id(1), own_subnet_type);
+        let snapshot_id = state.metadata.generate_new_snapshot_id(canister_id);
+        let snapshot = CanisterSnapshot::from_canister(&canister_state, mock_time()).unwrap();
+        state
+            .canister_snapshots
+            .push(snapshot_id, Arc::new(snapshot.clone()));
+        state.put_canister_state(canister_state);
+
+        let state = make_checkpoint_and_get_state(&state, Height::new(1), &tip_channel);
+        let checkpoint = layout.checkpoint(Height::new(1)).unwrap();
+        assert_eq!(checkpoint.snapshot_ids().unwrap(), vec![snapshot_id]);
+
+        let recovered_state = load_checkpoint(
+            &checkpoint,
+            own_subnet_type,
+            &state_manager_metrics.checkpoint_metrics,
+            Some(&mut thread_pool()),
+            Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+        )
+        .unwrap();
+        let recovered_snapshot = recovered_state
+            .canister_snapshots
+            .get(&snapshot_id)
+            .unwrap();
+        assert_eq!(**recovered_snapshot, snapshot);
+
+        // Deleted snapshots are removed from subsequent checkpoints.
+        let mut state = state;
+        state.canister_snapshots.remove(&snapshot_id);
+        let _state = make_checkpoint_and_get_state(&state, Height::new(2), &tip_channel);
+        let checkpoint = layout.checkpoint(Height::new(2)).unwrap();
+        assert!(checkpoint.snapshot_ids().unwrap().is_empty());
+    });
+}
+
 #[test]
 fn can_recover_an_empty_state() {
     with_test_replica_logger(|log| {
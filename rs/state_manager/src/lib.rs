@@ -1030,6 +1030,7 @@ pub struct DirtyPageMap {
 pub enum FileType {
     PageMap(PageMapType),
     WasmBinary(CanisterId),
+    WasmChunkStore(CanisterId),
 }
 
 pub type DirtyPages = Vec<DirtyPageMap>;
@@ -1088,6 +1089,24 @@ pub fn get_dirty_pages(
         });
 
         result.extend(dirty_pages);
+
+        // Likewise for the Wasm chunk stores that have not changed since the
+        // last checkpoint.
+        let unchanged_chunk_stores = state.canisters_iter().filter_map(|state| {
+            let canister_id = state.canister_id();
+            let previous_state = previous_snapshot.state.canister_state(&canister_id)?;
+            if state.system_state.wasm_chunk_store == previous_state.system_state.wasm_chunk_store {
+                Some(DirtyPageMap {
+                    height: previous_snapshot.height,
+                    file_type: FileType::WasmChunkStore(canister_id),
+                    page_delta_indices: vec![], // the whole file is unchanged
+                })
+            } else {
+                None
+            }
+        });
+
+        result.extend(unchanged_chunk_stores);
     }
 
     result
@@ -1144,6 +1163,14 @@ fn switch_to_checkpoint(tip: &mut ReplicatedState, src: &ReplicatedState) {
             tip_canister.system_state.canister_id,
             src_canister.system_state.canister_id
         );
+        debug_assert_eq!(
+            tip_canister.system_state.wasm_chunk_store,
+            src_canister.system_state.wasm_chunk_store
+        );
+        // The chunks are the same, but the store of `src` is backed by the
+        // checkpoint file.
+        tip_canister.system_state.wasm_chunk_store =
+            src_canister.system_state.wasm_chunk_store.clone();
         assert_eq!(
             tip_canister.execution_state.is_some(),
             src_canister.execution_state.is_some(),
@@ -1176,6 +1203,14 @@ fn switch_to_checkpoint(tip: &mut ReplicatedState, src: &ReplicatedState) {
             tip_state.stable_memory.sandbox_memory = SandboxMemory::new();
         }
     }
+
+    // Snapshots are immutable, so the checkpointed ones (backed by the
+    // checkpoint files instead of in-memory deltas) can be shared as is.
+    assert_eq!(
+        tip.canister_snapshots.snapshot_ids(),
+        src.canister_snapshots.snapshot_ids()
+    );
+    tip.canister_snapshots = src.canister_snapshots.clone();
 }
 
 /// Persists metadata after releasing the write lock
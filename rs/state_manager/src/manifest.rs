@@ -803,6 +803,13 @@ fn dirty_pages_to_dirty_chunks(
                     .canister(&canister_id)
                     .map(|can| can.wasm().raw_path().to_owned())
             }
+            FileType::WasmChunkStore(canister_id) => {
+                assert!(dirty_page.page_delta_indices.is_empty());
+
+                checkpoint
+                    .canister(&canister_id)
+                    .map(|can| can.wasm_chunk_store().raw_path().to_owned())
+            }
         };
 
         if let Ok(path) = path {
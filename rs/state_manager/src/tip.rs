@@ -6,18 +6,19 @@ use crate::{
 use crossbeam_channel::{unbounded, Sender};
 use ic_logger::{error, fatal, info, ReplicaLogger};
 use ic_protobuf::state::canister_metadata::v1::CanisterMetadata;
+use ic_protobuf::state::canister_state_bits::v1 as pb_canister_state_bits;
 use ic_protobuf::state::system_metadata::v1::SystemMetadata;
 #[allow(unused)]
 use ic_replicated_state::{
-    canister_state::execution_state::SandboxMemory, CanisterState, NumWasmPages, PageMap,
-    ReplicatedState,
+    canister_state::execution_state::SandboxMemory, CanisterSnapshot, CanisterState, NumWasmPages,
+    PageMap, ReplicatedState, SnapshotId,
 };
 use ic_state_layout::{
-    error::LayoutError, CanisterStateBits, CheckpointLayout, ExecutionStateBits, ReadOnly,
-    RwPolicy, StateLayout, TipHandler,
+    error::LayoutError, CanisterSnapshotBits, CanisterStateBits, CheckpointLayout,
+    ExecutionStateBits, ReadOnly, RwPolicy, StateLayout, TipHandler,
 };
 use ic_types::state_sync::{FILE_GROUP_CHUNK_ID_OFFSET, MANIFEST_CHUNK_ID_OFFSET};
-use ic_types::{malicious_flags::MaliciousFlags, CanisterId, Height};
+use ic_types::{malicious_flags::MaliciousFlags, CanisterId, ExecutionRound, Height};
 use ic_utils::fs::defrag_file_partially;
 use ic_utils::thread::parallel_map;
 use ic_utils::thread::JoinOnDrop;
@@ -62,6 +63,12 @@ pub(crate) enum TipRequest {
         height: Height,
         ids: BTreeSet<CanisterId>,
     },
+    /// Filter canister snapshots in tip. Remove ones not present in the set.
+    /// State: !Empty
+    FilterTipSnapshots {
+        height: Height,
+        ids: BTreeSet<SnapshotId>,
+    },
     /// Truncate PageMaps's path.
     /// State: ReadyForPageDeltas(h) -> ReadyForPageDeltas(height), height >= h
     TruncatePageMapsPath {
@@ -162,6 +169,21 @@ pub(crate) fn spawn_tip_thread(
                                     )
                                 });
                         }
+                        TipRequest::FilterTipSnapshots { height, ids } => {
+                            debug_assert_ne!(tip_state, TipState::Empty);
+
+                            let _timer = request_timer(&metrics, "filter_tip_snapshots");
+                            tip_handler
+                                .filter_tip_snapshots(height, &ids)
+                                .unwrap_or_else(|err| {
+                                    fatal!(
+                                        log,
+                                        "Failed to filter tip snapshots for height @{}: {}",
+                                        height,
+                                        err
+                                    )
+                                });
+                        }
                         TipRequest::TipToCheckpoint { height, sender } => {
                             debug_assert_eq!(tip_state, TipState::Serialized(height));
                             debug_assert!(have_latest_manifest);
@@ -383,6 +405,76 @@ fn serialize_to_tip(
         result?;
     }
 
+    let results = parallel_map(
+        thread_pool,
+        state.canister_snapshots.iter(),
+        |(snapshot_id, snapshot)| serialize_snapshot_to_tip(log, snapshot_id, snapshot, tip),
+    );
+
+    for result in results.into_iter() {
+        result?;
+    }
+
+    Ok(())
+}
+
+fn serialize_snapshot_to_tip(
+    log: &ReplicaLogger,
+    snapshot_id: &SnapshotId,
+    snapshot: &CanisterSnapshot,
+    tip: &CheckpointLayout<RwPolicy<TipHandler>>,
+) -> Result<(), CheckpointError> {
+    let snapshot_layout = tip.snapshot(snapshot_id)?;
+
+    let wasm = snapshot_layout.wasm();
+    if !wasm.raw_path().exists() {
+        match snapshot.wasm_binary().binary.file() {
+            Some(path) => {
+                ic_state_layout::utils::do_copy(log, path, wasm.raw_path()).map_err(|io_err| {
+                    CheckpointError::IoError {
+                        path: path.to_path_buf(),
+                        message: "failed to copy Wasm file".to_string(),
+                        io_err: io_err.to_string(),
+                    }
+                })?;
+            }
+            None => wasm.serialize(&snapshot.wasm_binary().binary)?,
+        }
+    }
+
+    // Snapshots are immutable, so their memory only needs to be written once:
+    // after that the files are carried over from checkpoint to checkpoint.
+    let vmemory_0 = snapshot_layout.vmemory_0();
+    if !vmemory_0.exists() {
+        snapshot.wasm_memory().page_map.persist_all(&vmemory_0)?;
+    }
+    let stable_memory_blob = snapshot_layout.stable_memory_blob();
+    if !stable_memory_blob.exists() {
+        snapshot
+            .stable_memory()
+            .page_map
+            .persist_all(&stable_memory_blob)?;
+    }
+
+    snapshot_layout.snapshot().serialize(
+        CanisterSnapshotBits {
+            snapshot_id: *snapshot_id,
+            taken_at_timestamp: snapshot.taken_at_timestamp(),
+            canister_version: snapshot.canister_version(),
+            certified_data: snapshot.certified_data().clone(),
+            execution_state_bits: ExecutionStateBits {
+                exported_globals: snapshot.exported_globals().clone(),
+                heap_size: snapshot.wasm_memory().size,
+                exports: snapshot.exports().clone(),
+                // Snapshots are never executed.
+                last_executed_round: ExecutionRound::from(0),
+                metadata: snapshot.metadata().clone(),
+                binary_hash: Some(snapshot.wasm_binary().binary.module_hash().into()),
+            },
+            stable_memory_size: snapshot.stable_memory().size,
+        }
+        .into(),
+    )?;
     Ok(())
 }
 
@@ -446,6 +538,19 @@ fn serialize_canister_to_tip(
             None
         }
     };
+    let wasm_chunk_store = &canister_state.system_state.wasm_chunk_store;
+    // The tip contains the file of the checkpoint the store was loaded from, so
+    // it only needs to be written if the store changed since then.
+    if !wasm_chunk_store.is_persisted() || !canister_layout.wasm_chunk_store().raw_path().exists() {
+        canister_layout
+            .wasm_chunk_store()
+            .serialize(pb_canister_state_bits::WasmChunkStore {
+                chunks: wasm_chunk_store
+                    .chunks()
+                    .map(|chunk| chunk.to_vec())
+                    .collect(),
+            })?;
+    }
     // Priority credit must be zero at this point
     assert_eq!(canister_state.scheduler_state.priority_credit.get(), 0);
     canister_layout.canister().serialize(
@@ -506,6 +611,11 @@ fn serialize_canister_to_tip(
                 .canister_metrics
                 .get_consumed_cycles_since_replica_started_by_use_cases()
                 .clone(),
+            log_visibility: canister_state.system_state.log_visibility,
+            canister_log: canister_state.system_state.canister_log.clone(),
+            wasm_memory_limit: canister_state.system_state.wasm_memory_limit,
+            reserved_balance: canister_state.system_state.reserved_balance(),
+            reserved_balance_limit: canister_state.system_state.reserved_balance_limit(),
         }
         .into(),
     )?;
@@ -387,6 +387,34 @@ fn tip_can_be_recovered_from_metadata_checkpoint() {
     });
 }
 
+#[test]
+fn wasm_chunk_store_is_recovered_from_checkpoint() {
+    state_manager_restart_test(|state_manager, restart_fn| {
+        let canister_id: CanisterId = canister_test_id(100);
+        let (_height, mut state) = state_manager.take_tip();
+        insert_dummy_canister(&mut state, canister_id);
+        let hash = state
+            .canister_state_mut(&canister_id)
+            .unwrap()
+            .system_state
+            .wasm_chunk_store
+            .insert_chunk(vec![1, 2, 3])
+            .unwrap();
+        state_manager.commit_and_certify(state, height(1), CertificationScope::Full);
+
+        let state_manager = restart_fn(state_manager, None);
+
+        let (_height, recovered_tip) = state_manager.take_tip();
+        let wasm_chunk_store = &recovered_tip
+            .canister_state(&canister_id)
+            .unwrap()
+            .system_state
+            .wasm_chunk_store;
+        assert!(wasm_chunk_store.is_persisted());
+        assert_eq!(wasm_chunk_store.get_chunk(&hash), Some(&[1u8, 2, 3][..]));
+    });
+}
+
 #[test]
 fn tip_can_be_recovered_from_valid_checkpoint() {
     state_manager_restart_test(|state_manager, restart_fn| {
@@ -3089,6 +3117,21 @@ fn can_get_dirty_pages() {
                 file_type: FileType::WasmBinary(canister_test_id(100)),
                 page_delta_indices: vec![],
             },
+            DirtyPageMap {
+                height: height(1),
+                file_type: FileType::WasmChunkStore(canister_test_id(80)),
+                page_delta_indices: vec![],
+            },
+            DirtyPageMap {
+                height: height(1),
+                file_type: FileType::WasmChunkStore(canister_test_id(90)),
+                page_delta_indices: vec![],
+            },
+            DirtyPageMap {
+                height: height(1),
+                file_type: FileType::WasmChunkStore(canister_test_id(100)),
+                page_delta_indices: vec![],
+            },
         ];
 
         dirty_pages.sort();
@@ -3107,6 +3150,13 @@ fn can_get_dirty_pages() {
         drop_page_map(&mut state, canister_test_id(100));
         update_state(&mut state, canister_test_id(100));
         replace_wasm(&mut state, canister_test_id(100));
+        state
+            .canister_state_mut(&canister_test_id(100))
+            .unwrap()
+            .system_state
+            .wasm_chunk_store
+            .insert_chunk(vec![1, 2, 3])
+            .unwrap();
         let mut dirty_pages = get_dirty_pages(&state, Some(&snapshot2));
         // wasm memory was dropped, but stable memory wasn't
         let mut expected_dirty_pages = vec![
@@ -3145,6 +3195,16 @@ fn can_get_dirty_pages() {
                 file_type: FileType::WasmBinary(canister_test_id(90)),
                 page_delta_indices: vec![],
             },
+            DirtyPageMap {
+                height: height(2),
+                file_type: FileType::WasmChunkStore(canister_test_id(80)),
+                page_delta_indices: vec![],
+            },
+            DirtyPageMap {
+                height: height(2),
+                file_type: FileType::WasmChunkStore(canister_test_id(90)),
+                page_delta_indices: vec![],
+            },
         ];
 
         dirty_pages.sort();
@@ -4186,6 +4246,7 @@ fn tip_is_initialized_correctly() {
         assert!(canister_layout.wasm().raw_path().exists());
         assert!(canister_layout.vmemory_0().exists());
         assert!(canister_layout.stable_memory_blob().exists());
+        assert!(canister_layout.wasm_chunk_store().raw_path().exists());
 
         let (_height, state) = state_manager.take_tip();
         state_manager.commit_and_certify(state, height(2), CertificationScope::Full);
@@ -4204,6 +4265,7 @@ fn tip_is_initialized_correctly() {
         assert!(canister_layout.wasm().raw_path().exists());
         assert!(canister_layout.vmemory_0().exists());
         assert!(canister_layout.stable_memory_blob().exists());
+        assert!(canister_layout.wasm_chunk_store().raw_path().exists());
     });
 }
 
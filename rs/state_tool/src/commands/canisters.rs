@@ -0,0 +1,169 @@
+//! Inspects the canisters of a checkpoint.
+
+use ic_registry_subnet_type::SubnetType;
+use ic_replicated_state::{
+    page_map::{PageIndex, TestPageAllocatorFileDescriptorImpl, PAGE_SIZE},
+    CanisterState, Memory, PageMap, ReplicatedState,
+};
+use ic_state_layout::CompleteCheckpointLayout;
+use ic_state_manager::{checkpoint::load_checkpoint, CheckpointMetrics};
+use ic_types::{CanisterId, Height};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Loads the checkpoint at `path`.
+pub(crate) fn load_state(path: &Path) -> Result<ReplicatedState, String> {
+    let cp_layout = CompleteCheckpointLayout::new_untracked(path.to_path_buf(), Height::new(0))
+        .map_err(|e| format!("failed to create checkpoint layout: {}", e))?;
+
+    let dummy_metrics_registry = ic_metrics::MetricsRegistry::new();
+    let dummy_metrics = CheckpointMetrics::new(&dummy_metrics_registry);
+
+    load_checkpoint(
+        &cp_layout,
+        SubnetType::Application,
+        &dummy_metrics,
+        None,
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+    )
+    .map_err(|e| format!("failed to load checkpoint at {}: {}", path.display(), e))
+}
+
+/// Looks up the canister with the textual ID `canister_id` in `state`.
+fn find_canister<'a>(
+    state: &'a ReplicatedState,
+    canister_id: &str,
+) -> Result<&'a CanisterState, String> {
+    let canister_id = CanisterId::from_str(canister_id)
+        .map_err(|e| format!("invalid canister ID {}: {}", canister_id, e))?;
+    state
+        .canister_state(&canister_id)
+        .ok_or_else(|| format!("canister {} not found in the checkpoint", canister_id))
+}
+
+/// Lists the canisters of the checkpoint at `path` with their status, memory
+/// usage, cycles balance, module hash and controllers.
+pub fn do_list_canisters(path: PathBuf) -> Result<(), String> {
+    let state = load_state(&path)?;
+
+    println!("canister_id\tstatus\tmemory_usage\tcycles\tmodule_hash\tcontrollers");
+    for canister in state.canisters_iter() {
+        let module_hash = canister
+            .execution_state
+            .as_ref()
+            .map(|es| hex::encode(es.wasm_binary.binary.module_hash()))
+            .unwrap_or_else(|| "-".to_string());
+        let controllers: Vec<_> = canister
+            .controllers()
+            .iter()
+            .map(|controller| controller.to_string())
+            .collect();
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            canister.canister_id(),
+            canister.system_state.status_string(),
+            canister.memory_usage(SubnetType::Application),
+            canister.system_state.balance(),
+            module_hash,
+            controllers.join(",")
+        );
+    }
+
+    Ok(())
+}
+
+/// Writes the first `num_bytes` bytes of `page_map` to `path`.
+fn write_page_map(page_map: &PageMap, num_bytes: usize, path: &Path) -> Result<(), String> {
+    let file = File::create(path)
+        .map_err(|e| format!("failed to create file {}: {}", path.display(), e))?;
+    let mut writer = BufWriter::new(file);
+    let num_pages = (num_bytes + PAGE_SIZE - 1) / PAGE_SIZE;
+    for page in 0..num_pages {
+        let bytes = page_map.get_page(PageIndex::new(page as u64));
+        let len = PAGE_SIZE.min(num_bytes - page * PAGE_SIZE);
+        writer
+            .write_all(&bytes[..len])
+            .map_err(|e| format!("failed to write to {}: {}", path.display(), e))?;
+    }
+    writer
+        .flush()
+        .map_err(|e| format!("failed to write to {}: {}", path.display(), e))
+}
+
+fn write_memory(memory: &Memory, path: &Path) -> Result<(), String> {
+    let num_bytes = ic_replicated_state::num_bytes_try_from(memory.size)?.get() as usize;
+    write_page_map(&memory.page_map, num_bytes, path)?;
+    println!("Wrote {} bytes to {}", num_bytes, path.display());
+    Ok(())
+}
+
+/// Dumps the heap and the stable memory of `canister_id` in the checkpoint at
+/// `path` to the raw files `heap.bin` and `stable_memory.bin` in `output`.
+pub fn do_dump_canister_memory(
+    path: PathBuf,
+    canister_id: String,
+    output: PathBuf,
+) -> Result<(), String> {
+    let state = load_state(&path)?;
+    let canister = find_canister(&state, &canister_id)?;
+    let execution_state = canister
+        .execution_state
+        .as_ref()
+        .ok_or_else(|| format!("canister {} has no Wasm module installed", canister_id))?;
+
+    std::fs::create_dir_all(&output)
+        .map_err(|e| format!("failed to create directory {}: {}", output.display(), e))?;
+    write_memory(&execution_state.wasm_memory, &output.join("heap.bin"))?;
+    write_memory(
+        &execution_state.stable_memory,
+        &output.join("stable_memory.bin"),
+    )?;
+
+    Ok(())
+}
+
+/// Prints the input and output queues and the call contexts of `canister_id`
+/// in the checkpoint at `path`.
+pub fn do_canister_messages(path: PathBuf, canister_id: String) -> Result<(), String> {
+    let state = load_state(&path)?;
+    let canister = find_canister(&state, &canister_id)?;
+
+    println!("QUEUES:\n{:#?}", canister.system_state.queues());
+    match canister.system_state.call_context_manager() {
+        Some(call_context_manager) => println!(
+            "CALL CONTEXTS:\n{:#?}\nCALLBACKS:\n{:#?}",
+            call_context_manager.call_contexts(),
+            call_context_manager.callbacks()
+        ),
+        None => println!("CALL CONTEXTS: none, the canister is stopped"),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_page_map_test() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("memory.bin");
+
+        let mut page_map = PageMap::new_for_testing();
+        let page = [7u8; PAGE_SIZE];
+        page_map.update(&[(PageIndex::new(1), &page)]);
+
+        let num_bytes = 2 * PAGE_SIZE + 10;
+        write_page_map(&page_map, num_bytes, &path).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes.len(), num_bytes);
+        assert!(bytes[..PAGE_SIZE].iter().all(|b| *b == 0));
+        assert!(bytes[PAGE_SIZE..2 * PAGE_SIZE].iter().all(|b| *b == 7));
+        assert!(bytes[2 * PAGE_SIZE..].iter().all(|b| *b == 0));
+    }
+}
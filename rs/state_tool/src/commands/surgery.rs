@@ -0,0 +1,362 @@
+//! Edits canisters in a copy of a checkpoint, so that operators can recover a
+//! subnet whose state contains a canister that prevents it from making
+//! progress.
+//!
+//! All commands copy the checkpoint at `--state` to a fresh directory
+//! `--output`, apply the change there using the `state_layout` API, and print
+//! the manifest root hash and the partial state hash (`chash`) of the result.
+//! The original checkpoint is never modified.
+
+use crate::commands::{canisters::load_state, import_state::copy_recursively};
+use ic_config::embedders::Config as EmbeddersConfig;
+use ic_embedders::{
+    wasm_executor::get_initial_globals_and_memory,
+    wasm_utils::{compile, decoding::decode_wasm},
+    WasmtimeEmbedder,
+};
+use ic_logger::replica_logger::no_op_logger;
+use ic_protobuf::state::queues::v1 as pb_queues;
+use ic_replicated_state::{
+    CallContextManager, CanisterStatus, ExportedFunctions, NumWasmPages, PageMap,
+};
+use ic_state_layout::{CanisterLayout, CanisterStateBits, CheckpointLayout, RwPolicy};
+use ic_state_manager::{
+    manifest::{manifest_from_path, manifest_hash},
+    tree_hash::hash_state,
+};
+use ic_types::{CanisterId, Height};
+use ic_wasm_types::{CanisterModule, WasmHash};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+type RwCheckpointLayout = CheckpointLayout<RwPolicy<'static, ()>>;
+
+/// Copies the checkpoint at `state` to `output`, which must not exist yet, and
+/// returns a writable layout of the copy.
+fn copy_checkpoint(state: &Path, output: &Path) -> Result<RwCheckpointLayout, String> {
+    if output.exists() {
+        return Err(format!("output path {} already exists", output.display()));
+    }
+    copy_recursively(state, output)?;
+    CheckpointLayout::new_untracked(output.to_path_buf(), Height::new(0))
+        .map_err(|e| format!("failed to create checkpoint layout: {}", e))
+}
+
+/// Returns the layout of `canister_id` in `cp_layout`, failing if the
+/// checkpoint does not contain the canister.
+fn canister_layout(
+    cp_layout: &RwCheckpointLayout,
+    canister_id: &str,
+) -> Result<(CanisterId, CanisterLayout<RwPolicy<'static, ()>>), String> {
+    let canister_id = CanisterId::from_str(canister_id)
+        .map_err(|e| format!("invalid canister ID {}: {}", canister_id, e))?;
+    let canister_ids = cp_layout
+        .canister_ids()
+        .map_err(|e| format!("failed to list canisters: {}", e))?;
+    // Creating the layout of a writable checkpoint creates the canister
+    // directory, so make sure the canister exists first.
+    if !canister_ids.contains(&canister_id) {
+        return Err(format!(
+            "canister {} not found in the checkpoint",
+            canister_id
+        ));
+    }
+    let layout = cp_layout
+        .canister(&canister_id)
+        .map_err(|e| format!("failed to open layout of canister {}: {}", canister_id, e))?;
+    Ok((canister_id, layout))
+}
+
+/// Checkpoint files are read-only, so the copies have to be made writable
+/// before they can be overwritten.
+fn make_writable(path: &Path) -> Result<(), String> {
+    let mut permissions = std::fs::metadata(path)
+        .map_err(|e| format!("failed to get metadata of {}: {}", path.display(), e))?
+        .permissions();
+    permissions.set_readonly(false);
+    std::fs::set_permissions(path, permissions)
+        .map_err(|e| format!("failed to make {} writable: {}", path.display(), e))
+}
+
+fn load_canister_state_bits(
+    layout: &CanisterLayout<RwPolicy<'static, ()>>,
+) -> Result<CanisterStateBits, String> {
+    let canister_file = layout.canister();
+    let bits = canister_file.deserialize().map_err(|e| {
+        format!(
+            "failed to read {}: {}",
+            canister_file.raw_path().display(),
+            e
+        )
+    })?;
+    CanisterStateBits::try_from(bits).map_err(|e| {
+        format!(
+            "failed to decode {}: {}",
+            canister_file.raw_path().display(),
+            e
+        )
+    })
+}
+
+fn store_canister_state_bits(
+    layout: &CanisterLayout<RwPolicy<'static, ()>>,
+    bits: CanisterStateBits,
+) -> Result<(), String> {
+    let canister_file = layout.canister();
+    make_writable(canister_file.raw_path())?;
+    canister_file.serialize(bits.into()).map_err(|e| {
+        format!(
+            "failed to write {}: {}",
+            canister_file.raw_path().display(),
+            e
+        )
+    })
+}
+
+/// Prints the manifest root hash and the partial state hash of the
+/// checkpoint at `path`.
+fn print_hashes(path: &Path) -> Result<(), String> {
+    let state = load_state(path)?;
+    let manifest = manifest_from_path(path).map_err(|e| {
+        format!(
+            "Failed to compute manifest of checkpoint at {}: {}",
+            path.display(),
+            e
+        )
+    })?;
+
+    println!("ROOT HASH: {}", hex::encode(manifest_hash(&manifest)));
+    println!("PARTIAL STATE HASH: {}", hash_state(&state).digest());
+
+    Ok(())
+}
+
+/// Removes `canister_id` and its snapshots from a copy of the checkpoint at
+/// `state`. Messages addressed to the canister are rejected once the subnet
+/// resumes from the new checkpoint.
+pub fn do_delete_canister(
+    state: PathBuf,
+    output: PathBuf,
+    canister_id: String,
+) -> Result<(), String> {
+    let cp_layout = copy_checkpoint(&state, &output)?;
+    let (canister_id, layout) = canister_layout(&cp_layout, &canister_id)?;
+
+    let snapshot_ids = cp_layout
+        .snapshot_ids()
+        .map_err(|e| format!("failed to list snapshots: {}", e))?;
+    for snapshot_id in snapshot_ids
+        .iter()
+        .filter(|snapshot_id| snapshot_id.canister_id() == canister_id)
+    {
+        let snapshot_layout = cp_layout
+            .snapshot(snapshot_id)
+            .map_err(|e| format!("failed to open layout of snapshot {}: {}", snapshot_id, e))?;
+        std::fs::remove_dir_all(snapshot_layout.raw_path())
+            .map_err(|e| format!("failed to delete snapshot {}: {}", snapshot_id, e))?;
+    }
+    std::fs::remove_dir_all(layout.raw_path())
+        .map_err(|e| format!("failed to delete canister {}: {}", canister_id, e))?;
+
+    print_hashes(&output)
+}
+
+/// Empties the input and output queues of `canister_id` in a copy of the
+/// checkpoint at `state`. The dropped messages, including the cycles they
+/// carry, are lost. Responses to the canister's outstanding calls are dropped
+/// as well, so this is usually combined with `reset_call_contexts`.
+pub fn do_clear_canister_queues(
+    state: PathBuf,
+    output: PathBuf,
+    canister_id: String,
+) -> Result<(), String> {
+    let cp_layout = copy_checkpoint(&state, &output)?;
+    let (_, layout) = canister_layout(&cp_layout, &canister_id)?;
+
+    let queues_file = layout.queues();
+    if queues_file.raw_path().exists() {
+        make_writable(queues_file.raw_path())?;
+    }
+    queues_file
+        .serialize(pb_queues::CanisterQueues::default())
+        .map_err(|e| {
+            format!(
+                "failed to write {}: {}",
+                queues_file.raw_path().display(),
+                e
+            )
+        })?;
+
+    print_hashes(&output)
+}
+
+/// Drops the call contexts and callbacks of `call_context_manager`, keeping its
+/// ID counters so that in-flight responses to the dropped callbacks can never
+/// match a callback registered later.
+fn reset_call_context_manager(mut call_context_manager: CallContextManager) -> CallContextManager {
+    call_context_manager.drop_call_contexts_and_callbacks();
+    call_context_manager
+}
+
+/// Resets the call context manager in `status`. A stopped canister has no call
+/// contexts and is left untouched.
+fn reset_status_call_contexts(status: CanisterStatus) -> CanisterStatus {
+    match status {
+        CanisterStatus::Running {
+            call_context_manager,
+        } => CanisterStatus::Running {
+            call_context_manager: reset_call_context_manager(call_context_manager),
+        },
+        CanisterStatus::Stopping {
+            call_context_manager,
+            stop_contexts,
+        } => CanisterStatus::Stopping {
+            call_context_manager: reset_call_context_manager(call_context_manager),
+            stop_contexts,
+        },
+        CanisterStatus::Stopped => CanisterStatus::Stopped,
+    }
+}
+
+/// Drops all call contexts and callbacks of `canister_id` in a copy of the
+/// checkpoint at `state`. Callers of the dropped call contexts never receive
+/// a response and a stopping canister can stop once the change is applied.
+pub fn do_reset_call_contexts(
+    state: PathBuf,
+    output: PathBuf,
+    canister_id: String,
+) -> Result<(), String> {
+    let cp_layout = copy_checkpoint(&state, &output)?;
+    let (_, layout) = canister_layout(&cp_layout, &canister_id)?;
+
+    let mut bits = load_canister_state_bits(&layout)?;
+    bits.status = reset_status_call_contexts(bits.status);
+    bits.call_context_manager = match &bits.status {
+        CanisterStatus::Running { .. } | CanisterStatus::Stopping { .. } => Some(
+            reset_call_context_manager(bits.call_context_manager.take().unwrap_or_default()),
+        ),
+        CanisterStatus::Stopped => None,
+    };
+    store_canister_state_bits(&layout, bits)?;
+
+    print_hashes(&output)
+}
+
+/// Installs the Wasm module in the file `wasm` as the code of `canister_id` in
+/// a copy of the checkpoint at `state`. The heap and the stable memory of the
+/// canister are kept, while its globals are reset to the initial values of the
+/// new module. No canister methods (e.g. upgrade hooks) are run.
+pub fn do_replace_canister_wasm(
+    state: PathBuf,
+    output: PathBuf,
+    canister_id: String,
+    wasm: PathBuf,
+) -> Result<(), String> {
+    let wasm_bytes = std::fs::read(&wasm)
+        .map_err(|e| format!("failed to read Wasm module {}: {}", wasm.display(), e))?;
+
+    let cp_layout = copy_checkpoint(&state, &output)?;
+    let (canister_id, layout) = canister_layout(&cp_layout, &canister_id)?;
+
+    let mut bits = load_canister_state_bits(&layout)?;
+    let execution_state_bits = bits
+        .execution_state_bits
+        .as_mut()
+        .ok_or_else(|| format!("canister {} has no Wasm module installed", canister_id))?;
+
+    let decoded = decode_wasm(Arc::new(wasm_bytes.clone()))
+        .map_err(|e| format!("failed to decode Wasm module {}: {}", wasm.display(), e))?;
+    let embedder = WasmtimeEmbedder::new(EmbeddersConfig::default(), no_op_logger());
+    let (embedder_cache, result) = compile(&embedder, &decoded);
+    let (_, serialized_module) =
+        result.map_err(|e| format!("failed to compile Wasm module {}: {}", wasm.display(), e))?;
+    let (exported_globals, _, heap_size) = get_initial_globals_and_memory(
+        &serialized_module.data_segments,
+        &embedder_cache,
+        &embedder,
+        &mut PageMap::new_for_testing(),
+        canister_id,
+        &PageMap::new_for_testing(),
+    )
+    .map_err(|e| {
+        format!(
+            "failed to instantiate Wasm module {}: {}",
+            wasm.display(),
+            e
+        )
+    })?;
+
+    let module = CanisterModule::new(wasm_bytes);
+    execution_state_bits.exported_globals = exported_globals;
+    execution_state_bits.exports = ExportedFunctions::new(serialized_module.exported_functions);
+    execution_state_bits.metadata = serialized_module.wasm_metadata;
+    execution_state_bits.binary_hash = Some(WasmHash::from(module.module_hash()));
+    execution_state_bits.heap_size =
+        NumWasmPages::new(execution_state_bits.heap_size.get().max(heap_size.get()));
+
+    let wasm_file = layout.wasm();
+    make_writable(wasm_file.raw_path())?;
+    wasm_file
+        .serialize(&module)
+        .map_err(|e| format!("failed to write {}: {}", wasm_file.raw_path().display(), e))?;
+    store_canister_state_bits(&layout, bits)?;
+
+    print_hashes(&output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_replicated_state::CallOrigin;
+    use ic_types::{messages::CallContextId, Cycles, Time};
+
+    #[test]
+    fn reset_status_call_contexts_keeps_status_and_stop_contexts() {
+        assert_eq!(
+            reset_status_call_contexts(CanisterStatus::new_running()),
+            CanisterStatus::new_running()
+        );
+        assert_eq!(
+            reset_status_call_contexts(CanisterStatus::Stopped),
+            CanisterStatus::Stopped
+        );
+        match reset_status_call_contexts(CanisterStatus::Stopping {
+            call_context_manager: CallContextManager::default(),
+            stop_contexts: vec![],
+        }) {
+            CanisterStatus::Stopping {
+                call_context_manager,
+                stop_contexts,
+            } => {
+                assert_eq!(call_context_manager, CallContextManager::default());
+                assert!(stop_contexts.is_empty());
+            }
+            status => panic!("unexpected status {:?}", status),
+        }
+    }
+
+    #[test]
+    fn reset_call_context_manager_keeps_ids() {
+        let mut call_context_manager = CallContextManager::default();
+        call_context_manager.new_call_context(
+            CallOrigin::SystemTask,
+            Cycles::zero(),
+            Time::from_nanos_since_unix_epoch(0),
+        );
+        let next_callback_id = call_context_manager.next_callback_id();
+
+        let mut call_context_manager = reset_call_context_manager(call_context_manager);
+        assert!(call_context_manager.call_contexts().is_empty());
+        assert!(call_context_manager.callbacks().is_empty());
+        assert_eq!(call_context_manager.next_callback_id(), next_callback_id);
+        assert_eq!(
+            call_context_manager.new_call_context(
+                CallOrigin::SystemTask,
+                Cycles::zero(),
+                Time::from_nanos_since_unix_epoch(0),
+            ),
+            CallContextId::from(2)
+        );
+    }
+}
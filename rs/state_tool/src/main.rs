@@ -2,7 +2,7 @@
 //!
 //! A command-line tool to manage Internet Computer replicated states (decode
 //! persisted state files, diff checkpoints, compute partial state hashes and
-//! checkpoint manifests, import state trees).
+//! checkpoint manifests, import state trees, inspect and edit canisters).
 
 use clap::Parser;
 use std::path::PathBuf;
@@ -113,6 +113,107 @@ enum Opt {
         config: PathBuf,
     },
 
+    /// Lists the canisters of a checkpoint with their status, memory usage,
+    /// cycles balance, module hash and controllers.
+    #[clap(name = "list_canisters")]
+    ListCanisters {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+    },
+
+    /// Dumps the heap and the stable memory of a canister to raw files.
+    #[clap(name = "dump_canister_memory")]
+    DumpCanisterMemory {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+        /// Textual ID of the canister.
+        #[clap(long = "canister")]
+        canister: String,
+        /// Directory to write `heap.bin` and `stable_memory.bin` to.
+        #[clap(long = "output")]
+        output: PathBuf,
+    },
+
+    /// Displays the input and output queues and the call contexts of a
+    /// canister.
+    #[clap(name = "canister_messages")]
+    CanisterMessages {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+        /// Textual ID of the canister.
+        #[clap(long = "canister")]
+        canister: String,
+    },
+
+    /// Writes a copy of a checkpoint without the given canister and its
+    /// snapshots, and prints its root hash and partial state hash.
+    #[clap(name = "delete_canister")]
+    DeleteCanister {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+        /// Path to write the new checkpoint to; must not exist.
+        #[clap(long = "output")]
+        output: PathBuf,
+        /// Textual ID of the canister.
+        #[clap(long = "canister")]
+        canister: String,
+    },
+
+    /// Writes a copy of a checkpoint in which the input and output queues of
+    /// the given canister are empty, and prints its root hash and partial
+    /// state hash.
+    #[clap(name = "clear_canister_queues")]
+    ClearCanisterQueues {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+        /// Path to write the new checkpoint to; must not exist.
+        #[clap(long = "output")]
+        output: PathBuf,
+        /// Textual ID of the canister.
+        #[clap(long = "canister")]
+        canister: String,
+    },
+
+    /// Writes a copy of a checkpoint in which the given canister has no call
+    /// contexts and callbacks, and prints its root hash and partial state
+    /// hash.
+    #[clap(name = "reset_call_contexts")]
+    ResetCallContexts {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+        /// Path to write the new checkpoint to; must not exist.
+        #[clap(long = "output")]
+        output: PathBuf,
+        /// Textual ID of the canister.
+        #[clap(long = "canister")]
+        canister: String,
+    },
+
+    /// Writes a copy of a checkpoint in which the Wasm module of the given
+    /// canister is replaced, and prints its root hash and partial state hash.
+    /// The memory of the canister is kept and no canister methods are run.
+    #[clap(name = "replace_canister_wasm")]
+    ReplaceCanisterWasm {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+        /// Path to write the new checkpoint to; must not exist.
+        #[clap(long = "output")]
+        output: PathBuf,
+        /// Textual ID of the canister.
+        #[clap(long = "canister")]
+        canister: String,
+        /// Path to the new Wasm module (optionally gzip-compressed).
+        #[clap(long = "wasm")]
+        wasm: PathBuf,
+    },
+
     /// Displays a pretty-printed debug view of a state file.
     #[clap(name = "decode")]
     Decode {
@@ -162,6 +263,36 @@ fn main() {
             commands::verify_manifest::do_canister_hash(&file, &canister)
         }
         Opt::ListStates { config } => commands::list::do_list(config),
+        Opt::ListCanisters { path } => commands::canisters::do_list_canisters(path),
+        Opt::DumpCanisterMemory {
+            path,
+            canister,
+            output,
+        } => commands::canisters::do_dump_canister_memory(path, canister, output),
+        Opt::CanisterMessages { path, canister } => {
+            commands::canisters::do_canister_messages(path, canister)
+        }
+        Opt::DeleteCanister {
+            path,
+            output,
+            canister,
+        } => commands::surgery::do_delete_canister(path, output, canister),
+        Opt::ClearCanisterQueues {
+            path,
+            output,
+            canister,
+        } => commands::surgery::do_clear_canister_queues(path, output, canister),
+        Opt::ResetCallContexts {
+            path,
+            output,
+            canister,
+        } => commands::surgery::do_reset_call_contexts(path, output, canister),
+        Opt::ReplaceCanisterWasm {
+            path,
+            output,
+            canister,
+            wasm,
+        } => commands::surgery::do_replace_canister_wasm(path, output, canister, wasm),
         Opt::Decode { file } => commands::decode::do_decode(file),
         Opt::CanisterIdToHex { canister_id } => {
             commands::convert_ids::do_canister_id_to_hex(canister_id)
@@ -7,7 +7,9 @@ pub mod system_api_empty;
 
 use ic_base_types::PrincipalIdBlobParseError;
 use ic_config::flag_status::FlagStatus;
+use ic_cycles_account_manager::ResourceSaturation;
 use ic_error_types::RejectCode;
+use ic_ic00_types::CanisterLogRecord;
 use ic_interfaces::execution_environment::{
     ExecutionComplexity, ExecutionMode,
     HypervisorError::{self, *},
@@ -41,7 +43,7 @@ use std::{
 
 const MULTIPLIER_MAX_SIZE_LOCAL_SUBNET: u64 = 5;
 const MAX_NON_REPLICATED_QUERY_REPLY_SIZE: NumBytes = NumBytes::new(3 << 20);
-const CERTIFIED_DATA_MAX_LENGTH: u32 = 32;
+const CERTIFIED_DATA_MAX_LENGTH: usize = 32;
 
 // Enables tracing of system calls for local debugging.
 const TRACE_SYSCALLS: bool = false;
@@ -78,10 +80,10 @@ macro_rules! trace_syscall {
 
 // This helper is used in system calls for displaying a summary hash of a heap region.
 #[inline]
-fn summarize(heap: &[u8], start: u32, size: u32) -> u64 {
+fn summarize(heap: &[u8], start: usize, size: usize) -> u64 {
     if TRACE_SYSCALLS {
-        let start = (start as usize).min(heap.len());
-        let end = (start + (size as usize)).min(heap.len());
+        let start = start.min(heap.len());
+        let end = start.saturating_add(size).min(heap.len());
         // The actual hash function doesn't matter much as long as it is
         // cheap to compute and maps the input to u64 reasonably well.
         let mut sum = 0;
@@ -157,6 +159,7 @@ pub struct ExecutionParameters {
     pub compute_allocation: ComputeAllocation,
     pub subnet_type: SubnetType,
     pub execution_mode: ExecutionMode,
+    pub subnet_memory_saturation: ResourceSaturation,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
@@ -789,6 +792,92 @@ impl SystemApiImpl {
         }
     }
 
+    /// Returns the `wasm_memory_limit` of the canister if it applies to the
+    /// current execution. The limit applies only to replicated message
+    /// executions, so that upgrades and queries keep working after a canister
+    /// has reached its limit.
+    fn effective_wasm_memory_limit(&self) -> Option<NumBytes> {
+        let limit = self.sandbox_safe_system_state.wasm_memory_limit()?;
+        match &self.api_type {
+            ApiType::Update { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::ReplyCallback {
+                execution_mode: ExecutionMode::Replicated,
+                ..
+            }
+            | ApiType::RejectCallback {
+                execution_mode: ExecutionMode::Replicated,
+                ..
+            } => Some(limit),
+            ApiType::Start { .. }
+            | ApiType::Init { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::InspectMessage { .. }
+            | ApiType::Cleanup { .. } => None,
+        }
+    }
+
+    /// Returns the number of Wasm pages up to which `memory.grow` may grow the
+    /// Wasm heap without exceeding the `wasm_memory_limit` of the canister.
+    pub fn wasm_memory_limit_in_pages(&self) -> Option<NumWasmPages> {
+        self.effective_wasm_memory_limit().map(|limit| {
+            let pages = limit.get() / WASM_PAGE_SIZE_IN_BYTES as u64;
+            NumWasmPages::new(pages.try_into().unwrap_or(usize::MAX))
+        })
+    }
+
+    /// Returns an error if a Wasm heap of the given size exceeds the
+    /// `wasm_memory_limit` of the canister.
+    pub fn check_wasm_memory_limit(&self, wasm_heap_size: NumWasmPages) -> HypervisorResult<()> {
+        let limit = match self.effective_wasm_memory_limit() {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        let bytes = NumBytes::new(
+            (wasm_heap_size.get() as u64).saturating_mul(WASM_PAGE_SIZE_IN_BYTES as u64),
+        );
+        if bytes > limit {
+            return Err(HypervisorError::WasmMemoryLimitExceeded { bytes, limit });
+        }
+        Ok(())
+    }
+
+    /// Reserves cycles for the storage of newly allocated memory if the subnet
+    /// memory usage is above the threshold. Executions whose changes are not
+    /// persisted do not reserve cycles.
+    fn reserve_storage_cycles(&mut self, allocated_bytes: NumBytes) -> HypervisorResult<()> {
+        match &self.api_type {
+            ApiType::Start { .. }
+            | ApiType::Init { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::Update { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::Cleanup { .. } => {}
+            ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::InspectMessage { .. } => return Ok(()),
+        }
+        if self.execution_parameters.execution_mode == ExecutionMode::NonReplicated {
+            return Ok(());
+        }
+        self.sandbox_safe_system_state.reserve_storage_cycles(
+            allocated_bytes,
+            &self.execution_parameters.subnet_memory_saturation,
+        )?;
+        // Subsequent allocations of this execution see the increased usage.
+        self.execution_parameters.subnet_memory_saturation = self
+            .execution_parameters
+            .subnet_memory_saturation
+            .add(allocated_bytes.get());
+        Ok(())
+    }
+
     /// Gets the result of execution, assuming there is no error from
     /// running the canister. Returns any cycles used for an outgoing request
     /// that doesn't get sent and returns allocated memory to the subnet if the
@@ -1164,6 +1253,10 @@ impl SystemApiImpl {
         self.sandbox_safe_system_state.take_changes()
     }
 
+    pub fn take_canister_log_records(&mut self) -> Vec<CanisterLogRecord> {
+        self.sandbox_safe_system_state.take_canister_log_records()
+    }
+
     pub fn stable_memory_size(&self) -> NumWasmPages {
         self.stable_memory().stable_memory_size
     }
@@ -1233,6 +1326,38 @@ impl SystemApiImpl {
             panic!("{}", WASM_NATIVE_STABLE_MEMORY_ERROR)
         }
     }
+
+    /// The time attached to messages produced by `ic0.debug_print`.
+    fn debug_print_time(&self) -> Time {
+        match &self.api_type {
+            ApiType::Start { time }
+            | ApiType::Init { time, .. }
+            | ApiType::SystemTask { time, .. }
+            | ApiType::Update { time, .. }
+            | ApiType::Cleanup { time, .. }
+            | ApiType::NonReplicatedQuery { time, .. }
+            | ApiType::ReplicatedQuery { time, .. }
+            | ApiType::PreUpgrade { time, .. }
+            | ApiType::ReplyCallback { time, .. }
+            | ApiType::RejectCallback { time, .. }
+            | ApiType::InspectMessage { time, .. } => *time,
+        }
+    }
+}
+
+/// Reads the message of `ic0.debug_print` from the heap.
+fn debug_print_message(src: usize, size: usize, heap: &[u8]) -> String {
+    const MAX_DEBUG_MESSAGE_SIZE: usize = 32 * 1024;
+    let size = size.min(MAX_DEBUG_MESSAGE_SIZE);
+    match valid_subslice("ic0.debug_print", src, size, heap) {
+        Ok(bytes) => String::from_utf8_lossy(bytes).to_string(),
+        Err(_) => {
+            // Do not trap here!
+            // debug.print should never fail, so if the specified memory range
+            // is invalid, we ignore it and print the error message
+            "(debug message out of memory bounds)".to_string()
+        }
+    }
 }
 
 impl SystemApi for SystemApiImpl {
@@ -1310,9 +1435,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_caller_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match self.get_msg_caller_id("ic0_msg_caller_copy") {
@@ -1320,7 +1445,6 @@ impl SystemApi for SystemApiImpl {
                 let id_bytes = caller_id.as_slice();
                 valid_subslice("ic0.msg_caller_copy heap", dst, size, heap)?;
                 let slice = valid_subslice("ic0.msg_caller_copy id", offset, size, id_bytes)?;
-                let (dst, size) = (dst as usize, size as usize);
                 deterministic_copy_from_slice(&mut heap[dst..dst + size], slice);
                 Ok(())
             }
@@ -1370,9 +1494,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_arg_data_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
@@ -1406,7 +1530,6 @@ impl SystemApi for SystemApiImpl {
                     size,
                     incoming_payload,
                 )?;
-                let (dst, size) = (dst as usize, size as usize);
                 deterministic_copy_from_slice(&mut heap[dst..dst + size], payload_subslice);
                 Ok(())
             }
@@ -1443,9 +1566,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_method_name_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
@@ -1467,7 +1590,6 @@ impl SystemApi for SystemApiImpl {
                     size,
                     method_name.as_bytes(),
                 )?;
-                let (dst, size) = (dst as usize, size as usize);
                 deterministic_copy_from_slice(&mut heap[dst..dst + size], payload_subslice);
                 Ok(())
             }
@@ -1534,15 +1656,15 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_reply_data_append(
         &mut self,
-        src: u32,
-        size: u32,
+        src: usize,
+        size: usize,
         heap: &[u8],
     ) -> HypervisorResult<()> {
         let result = match self.get_response_info() {
             None => Err(self.error_for("ic0_msg_reply_data_append")),
             Some((data, max_reply_size, response_status)) => match response_status {
                 ResponseStatus::NotRepliedYet => {
-                    let payload_size = (data.len() + size as usize) as u64;
+                    let payload_size = data.len().saturating_add(size) as u64;
                     if payload_size > max_reply_size.get() {
                         let string = format!(
                             "ic0.msg_reply_data_append: application payload size ({}) cannot be larger than {}",
@@ -1572,7 +1694,7 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_msg_reject(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
+    fn ic0_msg_reject(&mut self, src: usize, size: usize, heap: &[u8]) -> HypervisorResult<()> {
         let result = match self.get_response_info() {
             None => Err(self.error_for("ic0_msg_reject")),
             Some((_, max_reply_size, response_status)) => match response_status {
@@ -1629,9 +1751,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_reject_msg_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = {
@@ -1641,10 +1763,8 @@ impl SystemApi for SystemApiImpl {
             valid_subslice("ic0.msg_reject_msg_copy heap", dst, size, heap)?;
 
             let msg = reject_context.message();
-            let dst = dst as usize;
             let msg_bytes =
                 valid_subslice("ic0.msg_reject_msg_copy msg", offset, size, msg.as_bytes())?;
-            let size = size as usize;
             deterministic_copy_from_slice(&mut heap[dst..dst + size], msg_bytes);
             Ok(())
         };
@@ -1685,9 +1805,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_canister_self_copy(
         &mut self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
@@ -1706,7 +1826,6 @@ impl SystemApi for SystemApiImpl {
                 let canister_id = self.sandbox_safe_system_state.canister_id;
                 let id_bytes = canister_id.get_ref().as_slice();
                 let slice = valid_subslice("ic0.canister_self_copy id", offset, size, id_bytes)?;
-                let (dst, size) = (dst as usize, size as usize);
                 deterministic_copy_from_slice(&mut heap[dst..dst + size], slice);
                 Ok(())
             }
@@ -1745,9 +1864,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_controller_copy(
         &mut self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
@@ -1766,7 +1885,6 @@ impl SystemApi for SystemApiImpl {
                 let controller = self.sandbox_safe_system_state.controller;
                 let id_bytes = controller.as_slice();
                 let slice = valid_subslice("ic0.controller_copy id", offset, size, id_bytes)?;
-                let (dst, size) = (dst as usize, size as usize);
                 deterministic_copy_from_slice(&mut heap[dst..dst + size], slice);
                 Ok(())
             }
@@ -1777,10 +1895,10 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_call_new(
         &mut self,
-        callee_src: u32,
-        callee_size: u32,
-        name_src: u32,
-        name_len: u32,
+        callee_src: usize,
+        callee_size: usize,
+        name_src: usize,
+        name_len: usize,
         reply_fun: u32,
         reply_env: u32,
         reject_fun: u32,
@@ -1855,7 +1973,12 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_call_data_append(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
+    fn ic0_call_data_append(
+        &mut self,
+        src: usize,
+        size: usize,
+        heap: &[u8],
+    ) -> HypervisorResult<()> {
         let result = match &mut self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
@@ -2068,13 +2191,26 @@ impl SystemApi for SystemApiImpl {
                 match self.memory_usage.allocate_pages(additional_pages as usize) {
                     Ok(()) => {
                         let res = self.stable_memory_mut().stable_grow(additional_pages);
-                        match &res {
-                            Err(_) | Ok(-1) => self
-                                .memory_usage
-                                .deallocate_pages(additional_pages as usize),
-                            _ => {}
+                        match res {
+                            Err(_) | Ok(-1) => {
+                                self.memory_usage
+                                    .deallocate_pages(additional_pages as usize);
+                                res
+                            }
+                            _ => {
+                                let bytes = NumBytes::new(
+                                    additional_pages as u64 * WASM_PAGE_SIZE_IN_BYTES as u64,
+                                );
+                                match self.reserve_storage_cycles(bytes) {
+                                    Ok(()) => res,
+                                    Err(err) => {
+                                        self.memory_usage
+                                            .deallocate_pages(additional_pages as usize);
+                                        Err(err)
+                                    }
+                                }
+                            }
                         }
-                        res
                     }
                     Err(_err) => Ok(-1),
                 }
@@ -2114,7 +2250,7 @@ impl SystemApi for SystemApiImpl {
             dst,
             offset,
             size,
-            summarize(heap, dst, size)
+            summarize(heap, dst as usize, size as usize)
         );
         result
     }
@@ -2151,7 +2287,7 @@ impl SystemApi for SystemApiImpl {
             offset,
             src,
             size,
-            summarize(heap, src, size)
+            summarize(heap, src as usize, size as usize)
         );
         result
     }
@@ -2196,13 +2332,26 @@ impl SystemApi for SystemApiImpl {
                 match self.memory_usage.allocate_pages(additional_pages as usize) {
                     Ok(()) => {
                         let res = self.stable_memory_mut().stable64_grow(additional_pages);
-                        match &res {
-                            Err(_) | Ok(-1) => self
-                                .memory_usage
-                                .deallocate_pages(additional_pages as usize),
-                            _ => {}
+                        match res {
+                            Err(_) | Ok(-1) => {
+                                self.memory_usage
+                                    .deallocate_pages(additional_pages as usize);
+                                res
+                            }
+                            _ => {
+                                let bytes = NumBytes::new(
+                                    additional_pages as u64 * WASM_PAGE_SIZE_IN_BYTES as u64,
+                                );
+                                match self.reserve_storage_cycles(bytes) {
+                                    Ok(()) => res,
+                                    Err(err) => {
+                                        self.memory_usage
+                                            .deallocate_pages(additional_pages as usize);
+                                        Err(err)
+                                    }
+                                }
+                            }
                         }
-                        res
                     }
                     Err(_err) => Ok(-1),
                 }
@@ -2242,7 +2391,7 @@ impl SystemApi for SystemApiImpl {
             dst,
             offset,
             size,
-            summarize(heap, dst as u32, size as u32)
+            summarize(heap, dst as usize, size as usize)
         );
         result
     }
@@ -2290,7 +2439,7 @@ impl SystemApi for SystemApiImpl {
             offset,
             src,
             size,
-            summarize(heap, src as u32, size as u32)
+            summarize(heap, src as usize, size as usize)
         );
         result
     }
@@ -2421,7 +2570,13 @@ impl SystemApi for SystemApiImpl {
                 .ok_or(HypervisorError::OutOfMemory)?;
 
             match self.memory_usage.allocate_memory(bytes, NumBytes::new(0)) {
-                Ok(()) => Ok(()),
+                Ok(()) => {
+                    if let Err(err) = self.reserve_storage_cycles(bytes) {
+                        self.memory_usage.deallocate_memory(bytes, NumBytes::new(0));
+                        return Err(err);
+                    }
+                    Ok(())
+                }
                 Err(_err) => Err(HypervisorError::OutOfMemory),
             }
         };
@@ -2457,7 +2612,15 @@ impl SystemApi for SystemApiImpl {
             return Ok(StableGrowOutcome::Failure);
         }
         match self.memory_usage.allocate_pages(additional_pages as usize) {
-            Ok(()) => Ok(StableGrowOutcome::Success),
+            Ok(()) => {
+                let bytes = NumBytes::new(additional_pages * WASM_PAGE_SIZE_IN_BYTES as u64);
+                if let Err(err) = self.reserve_storage_cycles(bytes) {
+                    self.memory_usage
+                        .deallocate_pages(additional_pages as usize);
+                    return Err(err);
+                }
+                Ok(StableGrowOutcome::Success)
+            }
             Err(_) => Ok(StableGrowOutcome::Failure),
         }
     }
@@ -2476,7 +2639,7 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_canister_cycle_balance128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()> {
+    fn ic0_canister_cycle_balance128(&self, dst: usize, heap: &mut [u8]) -> HypervisorResult<()> {
         let result = {
             let method_name = "ic0_canister_cycle_balance128";
             let cycles = self.ic0_canister_cycle_balance_helper(method_name)?;
@@ -2506,7 +2669,7 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_msg_cycles_available128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()> {
+    fn ic0_msg_cycles_available128(&self, dst: usize, heap: &mut [u8]) -> HypervisorResult<()> {
         let result = {
             let method_name = "ic0_msg_cycles_available128";
             let cycles = self.ic0_msg_cycles_available_helper(method_name)?;
@@ -2531,7 +2694,7 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_msg_cycles_refunded128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()> {
+    fn ic0_msg_cycles_refunded128(&self, dst: usize, heap: &mut [u8]) -> HypervisorResult<()> {
         let result = {
             let method_name = "ic0_msg_cycles_refunded128";
             let cycles = self.ic0_msg_cycles_refunded_helper(method_name)?;
@@ -2570,7 +2733,7 @@ impl SystemApi for SystemApiImpl {
     fn ic0_msg_cycles_accept128(
         &mut self,
         max_amount: Cycles,
-        dst: u32,
+        dst: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = {
@@ -2635,9 +2798,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_data_certificate_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
@@ -2657,8 +2820,6 @@ impl SystemApi for SystemApiImpl {
                 data_certificate, ..
             } => match data_certificate {
                 Some(data_certificate) => {
-                    let (dst, offset, size) = (dst as usize, offset as usize, size as usize);
-
                     let (upper_bound, overflow) = offset.overflowing_add(size);
                     if overflow || upper_bound > data_certificate.len() {
                         return Err(ContractViolation(format!(
@@ -2704,7 +2865,12 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_certified_data_set(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
+    fn ic0_certified_data_set(
+        &mut self,
+        src: usize,
+        size: usize,
+        heap: &[u8],
+    ) -> HypervisorResult<()> {
         let result = match &mut self.api_type {
             ApiType::Start { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2731,7 +2897,6 @@ impl SystemApi for SystemApiImpl {
                     )));
                 }
 
-                let (src, size) = (src as usize, size as usize);
                 let (upper_bound, overflow) = src.overflowing_add(size);
                 if overflow || upper_bound > heap.len() {
                     return Err(ContractViolation(format!(
@@ -2806,40 +2971,28 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_debug_print(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
-        const MAX_DEBUG_MESSAGE_SIZE: u32 = 32 * 1024;
-        let size = size.min(MAX_DEBUG_MESSAGE_SIZE);
-        let msg = match valid_subslice("ic0.debug_print", src, size, heap) {
-            Ok(bytes) => String::from_utf8_lossy(bytes).to_string(),
-            Err(_) => {
-                // Do not trap here!
-                // debug.print should never fail, so if the specified memory range
-                // is invalid, we ignore it and print the error message
-                "(debug message out of memory bounds)".to_string()
-            }
-        };
-        match &self.api_type {
-            ApiType::Start { time }
-            | ApiType::Init { time, .. }
-            | ApiType::SystemTask { time, .. }
-            | ApiType::Update { time, .. }
-            | ApiType::Cleanup { time, .. }
-            | ApiType::NonReplicatedQuery { time, .. }
-            | ApiType::ReplicatedQuery { time, .. }
-            | ApiType::PreUpgrade { time, .. }
-            | ApiType::ReplyCallback { time, .. }
-            | ApiType::RejectCallback { time, .. }
-            | ApiType::InspectMessage { time, .. } => eprintln!(
-                "{}: [Canister {}] {}",
-                time, self.sandbox_safe_system_state.canister_id, msg
-            ),
-        }
+    fn ic0_debug_print(&mut self, src: usize, size: usize, heap: &[u8]) -> HypervisorResult<()> {
+        let msg = debug_print_message(src, size, heap);
+        let time = self.debug_print_time();
+        eprintln!(
+            "{}: [Canister {}] {}",
+            time, self.sandbox_safe_system_state.canister_id, msg
+        );
+        self.sandbox_safe_system_state
+            .append_canister_log(&time, msg.into_bytes());
         trace_syscall!(self, ic0_debug_print, src, size, summarize(heap, src, size));
         Ok(())
     }
 
-    fn ic0_trap(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
-        const MAX_ERROR_MESSAGE_SIZE: u32 = 16 * 1024;
+    fn save_log_message(&mut self, src: usize, size: usize, heap: &[u8]) {
+        let msg = debug_print_message(src, size, heap);
+        let time = self.debug_print_time();
+        self.sandbox_safe_system_state
+            .append_canister_log(&time, msg.into_bytes());
+    }
+
+    fn ic0_trap(&self, src: usize, size: usize, heap: &[u8]) -> HypervisorResult<()> {
+        const MAX_ERROR_MESSAGE_SIZE: usize = 16 * 1024;
         let size = size.min(MAX_ERROR_MESSAGE_SIZE);
         let result = {
             let msg = valid_subslice("trap", src, size, heap)
@@ -2851,7 +3004,7 @@ impl SystemApi for SystemApiImpl {
         Err(result)
     }
 
-    fn ic0_is_controller(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<u32> {
+    fn ic0_is_controller(&self, src: usize, size: usize, heap: &[u8]) -> HypervisorResult<u32> {
         let result = match &self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
@@ -2905,7 +3058,7 @@ impl OutOfInstructionsHandler for DefaultOutOfInstructionsHandler {
 
 pub(crate) fn copy_cycles_to_heap(
     cycles: Cycles,
-    dst: u32,
+    dst: usize,
     heap: &mut [u8],
     method_name: &str,
 ) -> HypervisorResult<()> {
@@ -2914,7 +3067,6 @@ pub(crate) fn copy_cycles_to_heap(
     let size = bytes.len();
     assert_eq!(size, 16);
 
-    let dst = dst as usize;
     let (upper_bound, overflow) = dst.overflowing_add(size);
     if overflow || upper_bound > heap.len() {
         return Err(ContractViolation(format!(
@@ -2932,22 +3084,20 @@ pub(crate) fn copy_cycles_to_heap(
 
 pub(crate) fn valid_subslice<'a>(
     ctx: &str,
-    src: u32,
-    len: u32,
+    src: usize,
+    len: usize,
     slice: &'a [u8],
 ) -> HypervisorResult<&'a [u8]> {
-    let len = len as usize;
-    let src = src as usize;
-    if slice.len() < src + len {
-        return Err(ContractViolation(format!(
+    match src.checked_add(len) {
+        Some(end) if end <= slice.len() => Ok(&slice[src..end]),
+        _ => Err(ContractViolation(format!(
             "{}: src={} + length={} exceeds the slice size={}",
             ctx,
             src,
             len,
             slice.len()
-        )));
+        ))),
     }
-    Ok(&slice[src..src + len])
 }
 
 #[cfg(test)]
@@ -2972,5 +3122,8 @@ mod test {
         assert!(valid_subslice("", 3, 2, &[1, 2, 3, 4]).is_err());
         assert!(valid_subslice("", 0, 5, &[1, 2, 3, 4]).is_err());
         assert!(valid_subslice("", 4, 1, &[1, 2, 3, 4]).is_err());
+
+        // src + length overflows
+        assert!(valid_subslice("", usize::MAX, 2, &[1, 2, 3, 4]).is_err());
     }
 }
@@ -7,9 +7,10 @@ use ic_error_types::UserError;
 use ic_ic00_types::{
     BitcoinGetBalanceArgs, BitcoinGetCurrentFeePercentilesArgs, BitcoinGetUtxosArgs,
     BitcoinSendTransactionArgs, CanisterIdRecord, ComputeInitialEcdsaDealingsArgs,
-    ECDSAPublicKeyArgs, EcdsaKeyId, InstallCodeArgs, Method as Ic00Method, Payload,
-    ProvisionalTopUpCanisterArgs, SetControllerArgs, SignWithECDSAArgs, UninstallCodeArgs,
-    UpdateSettingsArgs,
+    DeleteCanisterSnapshotArgs, ECDSAPublicKeyArgs, EcdsaKeyId, InstallChunkedCodeArgs,
+    InstallCodeArgs, LoadCanisterSnapshotArgs, Method as Ic00Method, Payload,
+    ProvisionalTopUpCanisterArgs, SetControllerArgs, SignWithECDSAArgs, TakeCanisterSnapshotArgs,
+    UninstallCodeArgs, UpdateSettingsArgs, UploadChunkArgs,
 };
 use ic_replicated_state::NetworkTopology;
 
@@ -77,6 +78,29 @@ pub(super) fn resolve_destination(
                     ResolveDestinationError::SubnetNotFound(canister_id, Ic00Method::InstallCode)
                 })
         }
+        Ok(Ic00Method::InstallChunkedCode) => {
+            // Find the destination canister from the payload.
+            let args = InstallChunkedCodeArgs::decode(payload)?;
+            let canister_id = args.target_canister_id();
+            network_topology
+                .routing_table
+                .route(canister_id.get())
+                .map(|subnet_id| subnet_id.get())
+                .ok_or_else(|| {
+                    ResolveDestinationError::SubnetNotFound(canister_id, method.unwrap())
+                })
+        }
+        Ok(Ic00Method::UploadChunk) => {
+            let args = UploadChunkArgs::decode(payload)?;
+            let canister_id = args.get_canister_id();
+            network_topology
+                .routing_table
+                .route(canister_id.get())
+                .map(|subnet_id| subnet_id.get())
+                .ok_or_else(|| {
+                    ResolveDestinationError::SubnetNotFound(canister_id, method.unwrap())
+                })
+        }
         Ok(Ic00Method::SetController) => {
             let args = SetControllerArgs::decode(payload)?;
             let canister_id = args.get_canister_id();
@@ -92,7 +116,11 @@ pub(super) fn resolve_destination(
         | Ok(Ic00Method::StartCanister)
         | Ok(Ic00Method::StopCanister)
         | Ok(Ic00Method::DeleteCanister)
-        | Ok(Ic00Method::DepositCycles) => {
+        | Ok(Ic00Method::DepositCycles)
+        | Ok(Ic00Method::ListCanisterSnapshots)
+        | Ok(Ic00Method::FetchCanisterLogs)
+        | Ok(Ic00Method::ClearChunkStore)
+        | Ok(Ic00Method::StoredChunks) => {
             let args = CanisterIdRecord::decode(payload)?;
             let canister_id = args.get_canister_id();
             network_topology
@@ -114,6 +142,39 @@ pub(super) fn resolve_destination(
                     ResolveDestinationError::SubnetNotFound(canister_id, method.unwrap())
                 })
         }
+        Ok(Ic00Method::TakeCanisterSnapshot) => {
+            let args = TakeCanisterSnapshotArgs::decode(payload)?;
+            let canister_id = args.get_canister_id();
+            network_topology
+                .routing_table
+                .route(canister_id.get())
+                .map(|subnet_id| subnet_id.get())
+                .ok_or_else(|| {
+                    ResolveDestinationError::SubnetNotFound(canister_id, method.unwrap())
+                })
+        }
+        Ok(Ic00Method::LoadCanisterSnapshot) => {
+            let args = LoadCanisterSnapshotArgs::decode(payload)?;
+            let canister_id = args.get_canister_id();
+            network_topology
+                .routing_table
+                .route(canister_id.get())
+                .map(|subnet_id| subnet_id.get())
+                .ok_or_else(|| {
+                    ResolveDestinationError::SubnetNotFound(canister_id, method.unwrap())
+                })
+        }
+        Ok(Ic00Method::DeleteCanisterSnapshot) => {
+            let args = DeleteCanisterSnapshotArgs::decode(payload)?;
+            let canister_id = args.get_canister_id();
+            network_topology
+                .routing_table
+                .route(canister_id.get())
+                .map(|subnet_id| subnet_id.get())
+                .ok_or_else(|| {
+                    ResolveDestinationError::SubnetNotFound(canister_id, method.unwrap())
+                })
+        }
         Ok(Ic00Method::ProvisionalTopUpCanister) => {
             let args = ProvisionalTopUpCanisterArgs::decode(payload)?;
             let canister_id = args.get_canister_id();
@@ -3,10 +3,13 @@ use std::collections::{BTreeMap, BTreeSet};
 use crate::routing::ResolveDestinationError;
 use ic_base_types::{CanisterId, NumBytes, NumSeconds, PrincipalId, SubnetId};
 use ic_constants::{LOG_CANISTER_OPERATION_CYCLES_THRESHOLD, SMALL_APP_SUBNET_MAX_SIZE};
-use ic_cycles_account_manager::{CyclesAccountManager, CyclesAccountManagerError};
+use ic_cycles_account_manager::{
+    CyclesAccountManager, CyclesAccountManagerError, ResourceSaturation,
+};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
-    CreateCanisterArgs, InstallCodeArgs, Method as Ic00Method, Payload,
+    CanisterLogRecord, CreateCanisterArgs, InstallChunkedCodeArgs, InstallCodeArgs,
+    LoadCanisterSnapshotArgs, Method as Ic00Method, Payload,
     ProvisionalCreateCanisterWithCyclesArgs, SetControllerArgs, UninstallCodeArgs,
     UpdateSettingsArgs, IC_00,
 };
@@ -16,7 +19,7 @@ use ic_nns_constants::CYCLES_MINTING_CANISTER_ID;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
     canister_state::{system_state::CyclesUseCase, DEFAULT_QUEUE_CAPACITY},
-    CallOrigin, CanisterStatus, NetworkTopology, SystemState,
+    CallOrigin, CanisterLog, CanisterStatus, NetworkTopology, SystemState,
 };
 use ic_types::{
     messages::{CallContextId, CallbackId, RejectContext, Request},
@@ -64,6 +67,10 @@ pub struct SystemStateChanges {
     request_slots_used: BTreeMap<CanisterId, usize>,
     requests: Vec<Request>,
     pub(super) new_global_timer: Option<CanisterTimer>,
+    canister_log: CanisterLog,
+    // Cycles moved from the main balance to the reserved balance. Note that
+    // `cycles_balance_change` already accounts for their removal.
+    reserved_cycles: Cycles,
 }
 
 impl Default for SystemStateChanges {
@@ -77,6 +84,8 @@ impl Default for SystemStateChanges {
             request_slots_used: BTreeMap::new(),
             requests: vec![],
             new_global_timer: None,
+            canister_log: CanisterLog::default(),
+            reserved_cycles: Cycles::zero(),
         }
     }
 }
@@ -199,6 +208,8 @@ impl SystemStateChanges {
             Ok(Ic00Method::InstallCode) => {
                 InstallCodeArgs::decode(payload).map(|record| record.get_sender_canister_version())
             }
+            Ok(Ic00Method::InstallChunkedCode) => InstallChunkedCodeArgs::decode(payload)
+                .map(|record| record.get_sender_canister_version()),
             Ok(Ic00Method::CreateCanister) => CreateCanisterArgs::decode(payload)
                 .map(|record| record.get_sender_canister_version()),
             Ok(Ic00Method::UpdateSettings) => UpdateSettingsArgs::decode(payload)
@@ -207,6 +218,8 @@ impl SystemStateChanges {
                 .map(|record| record.get_sender_canister_version()),
             Ok(Ic00Method::UninstallCode) => UninstallCodeArgs::decode(payload)
                 .map(|record| record.get_sender_canister_version()),
+            Ok(Ic00Method::LoadCanisterSnapshot) => LoadCanisterSnapshotArgs::decode(payload)
+                .map(|record| record.get_sender_canister_version()),
             Ok(Ic00Method::ProvisionalCreateCanisterWithCycles) => {
                 ProvisionalCreateCanisterWithCyclesArgs::decode(payload)
                     .map(|record| record.get_sender_canister_version())
@@ -228,7 +241,14 @@ impl SystemStateChanges {
             | Ok(Ic00Method::BitcoinGetBalance)
             | Ok(Ic00Method::BitcoinGetUtxos)
             | Ok(Ic00Method::BitcoinSendTransaction)
-            | Ok(Ic00Method::BitcoinGetCurrentFeePercentiles) => Ok(None),
+            | Ok(Ic00Method::BitcoinGetCurrentFeePercentiles)
+            | Ok(Ic00Method::TakeCanisterSnapshot)
+            | Ok(Ic00Method::ListCanisterSnapshots)
+            | Ok(Ic00Method::DeleteCanisterSnapshot)
+            | Ok(Ic00Method::FetchCanisterLogs)
+            | Ok(Ic00Method::UploadChunk)
+            | Ok(Ic00Method::ClearChunkStore)
+            | Ok(Ic00Method::StoredChunks) => Ok(None),
             Err(_) => Err(UserError::new(
                 ErrorCode::CanisterMethodNotFound,
                 format!("Management canister has no method '{}'", msg.method_name),
@@ -258,7 +278,7 @@ impl SystemStateChanges {
     /// Verify that the changes to the system state are sound and apply them to
     /// the system state if they are.
     pub fn apply_changes(
-        self,
+        mut self,
         time: Time,
         system_state: &mut SystemState,
         network_topology: &NetworkTopology,
@@ -267,7 +287,20 @@ impl SystemStateChanges {
     ) -> HypervisorResult<()> {
         // Verify total cycle change is not positive and update cycles balance.
         self.validate_cycle_change(system_state.canister_id == CYCLES_MINTING_CANISTER_ID)?;
+        // Verify the reservation before any change so that a failure does
+        // not leave the balance changes half-applied.
+        let balance_after =
+            match self.cycles_balance_change + CyclesBalanceChange::added(self.reserved_cycles) {
+                CyclesBalanceChange::Added(added) => system_state.balance() + added,
+                CyclesBalanceChange::Removed(removed) => system_state.balance() - removed,
+            };
+        system_state
+            .validate_reservation(self.reserved_cycles, balance_after)
+            .map_err(|err| Self::error(format!("Failed to reserve cycles: {}", err)))?;
         let consumed_cycles = self.apply_balance_changes(system_state);
+        system_state
+            .reserve_cycles(self.reserved_cycles)
+            .map_err(|err| Self::error(format!("Failed to reserve cycles: {}", err)))?;
 
         // Observe consumed cycles.
         system_state.observe_consumed_cycles(consumed_cycles);
@@ -410,7 +443,7 @@ impl SystemStateChanges {
 
         // Verify new certified data isn't too long and set it.
         if let Some(certified_data) = self.new_certified_data.as_ref() {
-            if certified_data.len() > CERTIFIED_DATA_MAX_LENGTH as usize {
+            if certified_data.len() > CERTIFIED_DATA_MAX_LENGTH {
                 return Err(Self::error("Certified data is too large"));
             }
             system_state.certified_data = certified_data.clone();
@@ -421,6 +454,11 @@ impl SystemStateChanges {
             system_state.global_timer = new_global_timer;
         }
 
+        // Append the records produced by `ic0.debug_print`.
+        system_state
+            .canister_log
+            .append_delta_log(&mut self.canister_log);
+
         Ok(())
     }
 
@@ -433,10 +471,15 @@ impl SystemStateChanges {
             removed_consumed_cycles += *amount;
         }
 
+        // The reserved cycles are part of `cycles_balance_change`, but they are
+        // moved to the reserved balance separately, so they are not removed here.
+        let cycles_balance_change =
+            self.cycles_balance_change + CyclesBalanceChange::added(self.reserved_cycles);
+
         // The final balance of the canister should reflect `cycles_balance_change`.
         // Since we removed `removed_consumed_cycles` above, we need to add it back
         // to the `cycles_balance_change` to make sure the final balance of the canister is correct.
-        match self.cycles_balance_change {
+        match cycles_balance_change {
             CyclesBalanceChange::Added(added) => {
                 // When 'cycles_balance_change' is positive we should add 'removed_consumed_cycles'.
                 state.add_cycles(added + removed_consumed_cycles, CyclesUseCase::NonConsumed);
@@ -514,6 +557,9 @@ pub struct SandboxSafeSystemState {
     global_timer: CanisterTimer,
     canister_version: u64,
     controllers: BTreeSet<PrincipalId>,
+    wasm_memory_limit: Option<NumBytes>,
+    initial_reserved_balance: Cycles,
+    reserved_balance_limit: Option<Cycles>,
 }
 
 impl SandboxSafeSystemState {
@@ -538,6 +584,9 @@ impl SandboxSafeSystemState {
         global_timer: CanisterTimer,
         canister_version: u64,
         controllers: BTreeSet<PrincipalId>,
+        wasm_memory_limit: Option<NumBytes>,
+        initial_reserved_balance: Cycles,
+        reserved_balance_limit: Option<Cycles>,
     ) -> Self {
         Self {
             canister_id,
@@ -559,6 +608,9 @@ impl SandboxSafeSystemState {
             global_timer,
             canister_version,
             controllers,
+            wasm_memory_limit,
+            initial_reserved_balance,
+            reserved_balance_limit,
         }
     }
 
@@ -620,6 +672,9 @@ impl SandboxSafeSystemState {
             system_state.global_timer,
             system_state.canister_version,
             system_state.controllers.clone(),
+            system_state.wasm_memory_limit,
+            system_state.reserved_balance(),
+            system_state.reserved_balance_limit(),
         )
     }
 
@@ -635,12 +690,30 @@ impl SandboxSafeSystemState {
         self.canister_version
     }
 
+    /// Returns the upper bound on the Wasm heap size, if any.
+    pub fn wasm_memory_limit(&self) -> Option<NumBytes> {
+        self.wasm_memory_limit
+    }
+
     pub fn set_global_timer(&mut self, timer: CanisterTimer) {
         // Update both sandbox global timer and the changes.
         self.system_state_changes.new_global_timer = Some(timer);
         self.global_timer = timer;
     }
 
+    /// Records a message in the canister log delta of this execution.
+    pub fn append_canister_log(&mut self, time: &Time, content: Vec<u8>) {
+        self.system_state_changes
+            .canister_log
+            .add_record(time.as_nanos_since_unix_epoch(), content);
+    }
+
+    /// Takes the records added to the canister log so far. Unlike the rest of
+    /// the changes, the caller keeps them even if the execution fails.
+    pub fn take_canister_log_records(&mut self) -> Vec<CanisterLogRecord> {
+        self.system_state_changes.canister_log.take_records()
+    }
+
     pub fn changes(self) -> SystemStateChanges {
         self.system_state_changes
     }
@@ -680,6 +753,51 @@ impl SandboxSafeSystemState {
         cycles_change.apply(self.initial_cycles_balance)
     }
 
+    /// Returns the reserved balance including the cycles reserved during the
+    /// current execution.
+    pub fn reserved_balance(&self) -> Cycles {
+        self.initial_reserved_balance + self.system_state_changes.reserved_cycles
+    }
+
+    /// Moves cycles from the main balance to the reserved balance to pay for
+    /// future storage of `allocated_bytes`. The amount depends on how saturated
+    /// the subnet memory is and is zero below the subnet memory threshold.
+    pub(super) fn reserve_storage_cycles(
+        &mut self,
+        allocated_bytes: NumBytes,
+        subnet_memory_saturation: &ResourceSaturation,
+    ) -> HypervisorResult<()> {
+        let cycles_to_reserve = self.cycles_account_manager.storage_reservation_cycles(
+            allocated_bytes,
+            subnet_memory_saturation,
+            self.subnet_size,
+        );
+        if cycles_to_reserve.get() == 0 {
+            return Ok(());
+        }
+        if let Some(limit) = self.reserved_balance_limit {
+            let requested = self.reserved_balance() + cycles_to_reserve;
+            if requested > limit {
+                return Err(HypervisorError::ReservedCyclesLimitExceededInMemoryGrow {
+                    bytes: allocated_bytes,
+                    requested,
+                    limit,
+                });
+            }
+        }
+        let old_balance = self.cycles_balance();
+        if old_balance < cycles_to_reserve {
+            return Err(HypervisorError::InsufficientCyclesInMemoryGrow {
+                bytes: allocated_bytes,
+                available: old_balance,
+                required: cycles_to_reserve,
+            });
+        }
+        self.update_balance_change(old_balance - cycles_to_reserve);
+        self.system_state_changes.reserved_cycles += cycles_to_reserve;
+        Ok(())
+    }
+
     pub(super) fn msg_cycles_available(&self, call_context_id: CallContextId) -> Cycles {
         let initial_available = *self
             .call_context_balances
@@ -884,8 +1002,14 @@ mod tests {
     use std::collections::BTreeMap;
 
     use ic_base_types::NumSeconds;
-    use ic_replicated_state::{canister_state::system_state::CyclesUseCase, SystemState};
-    use ic_test_utilities::types::ids::{canister_test_id, user_test_id};
+    use ic_logger::replica_logger::no_op_logger;
+    use ic_replicated_state::{
+        canister_state::system_state::CyclesUseCase, NetworkTopology, SystemState,
+    };
+    use ic_test_utilities::{
+        mock_time,
+        types::ids::{canister_test_id, subnet_test_id, user_test_id},
+    };
     use ic_types::Cycles;
 
     use crate::{
@@ -953,4 +1077,37 @@ mod tests {
 
         assert_eq!(initial_cycles_balance + added, system_state.balance());
     }
+
+    #[test]
+    fn failed_reservation_does_not_change_balance() {
+        let mut system_state = SystemState::new_running(
+            canister_test_id(0),
+            user_test_id(1).get(),
+            Cycles::new(1_000_000_000),
+            NumSeconds::from(100_000),
+        );
+        system_state.set_reserved_balance_limit(Some(Cycles::new(1_000)));
+        let initial_cycles_balance = system_state.balance();
+
+        let reserved = Cycles::new(2_000);
+        let consumed = Cycles::new(100_000);
+        let mut system_state_changes = SystemStateChanges::default_with_cycles_changes(
+            CyclesBalanceChange::Removed(reserved + consumed),
+            BTreeMap::from([(CyclesUseCase::RequestAndResponseTransmission, consumed)]),
+        );
+        system_state_changes.reserved_cycles = reserved;
+
+        system_state_changes
+            .apply_changes(
+                mock_time(),
+                &mut system_state,
+                &NetworkTopology::default(),
+                subnet_test_id(1),
+                &no_op_logger(),
+            )
+            .unwrap_err();
+
+        assert_eq!(initial_cycles_balance, system_state.balance());
+        assert_eq!(Cycles::zero(), system_state.reserved_balance());
+    }
 }
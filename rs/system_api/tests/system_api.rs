@@ -1319,6 +1319,39 @@ fn certified_data_set() {
     assert_eq!(system_state.certified_data, vec![10; 32])
 }
 
+#[test]
+fn debug_print_and_save_log_message_record_in_canister_log() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let mut system_state = SystemStateBuilder::default().build();
+    let mut api = get_system_api(
+        ApiTypeBuilder::build_update_api(),
+        &system_state,
+        cycles_account_manager,
+    );
+    let heap = b"hello world".to_vec();
+
+    api.ic0_debug_print(0, 5, &heap).unwrap();
+    api.save_log_message(6, 5, &heap);
+
+    let system_state_changes = api.into_system_state_changes();
+    system_state_changes
+        .apply_changes(
+            mock_time(),
+            &mut system_state,
+            &default_network_topology(),
+            subnet_test_id(1),
+            &no_op_logger(),
+        )
+        .unwrap();
+    let contents: Vec<_> = system_state
+        .canister_log
+        .records()
+        .iter()
+        .map(|r| r.content.clone())
+        .collect();
+    assert_eq!(contents, vec![b"hello".to_vec(), b"world".to_vec()]);
+}
+
 #[test]
 fn data_certificate_copy() {
     let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
@@ -1865,7 +1898,7 @@ fn ic0_is_controller_test() {
     for i in 1..5 {
         let controller = user_test_id(i).get();
         assert_eq!(
-            api.ic0_is_controller(0, controller.as_slice().len() as u32, controller.as_slice())
+            api.ic0_is_controller(0, controller.as_slice().len(), controller.as_slice())
                 .unwrap(),
             (i <= 2) as u32
         );
@@ -1881,7 +1914,7 @@ fn ic0_is_controller_invalid_principal_id() {
     );
     let controller = [0u8; 70];
     assert!(matches!(
-        api.ic0_is_controller(0, controller.len() as u32, &controller),
+        api.ic0_is_controller(0, controller.len(), &controller),
         Err(HypervisorError::InvalidPrincipalId(
             PrincipalIdBlobParseError(..)
         ))
@@ -68,6 +68,7 @@ use maplit::btreemap;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::TryFrom;
 use std::sync::Arc;
+use std::time::Duration;
 use std::{os::unix::prelude::FileExt, str::FromStr};
 use tempfile::NamedTempFile;
 
@@ -1422,6 +1423,7 @@ pub struct ExecutionTestBuilder {
     instruction_limit_without_dts: NumInstructions,
     initial_canister_cycles: Cycles,
     subnet_total_memory: i64,
+    subnet_memory_threshold: i64,
     subnet_message_memory: i64,
     subnet_wasm_custom_sections_memory: i64,
     registry_settings: RegistryExecutionSettings,
@@ -1432,6 +1434,7 @@ pub struct ExecutionTestBuilder {
     composite_queries: bool,
     query_caching: bool,
     query_cache_capacity: u64,
+    query_cache_max_expiry_time: Duration,
     allocatable_compute_capacity_in_percent: usize,
     subnet_features: String,
     bitcoin_privileged_access: Vec<CanisterId>,
@@ -1450,6 +1453,9 @@ impl Default for ExecutionTestBuilder {
         let subnet_total_memory = ic_config::execution_environment::Config::default()
             .subnet_memory_capacity
             .get() as i64;
+        let subnet_memory_threshold = ic_config::execution_environment::Config::default()
+            .subnet_memory_threshold
+            .get() as i64;
         let subnet_message_memory = ic_config::execution_environment::Config::default()
             .subnet_message_memory_capacity
             .get() as i64;
@@ -1476,6 +1482,7 @@ impl Default for ExecutionTestBuilder {
                 .max_instructions_per_message_without_dts,
             initial_canister_cycles: INITIAL_CANISTER_CYCLES,
             subnet_total_memory,
+            subnet_memory_threshold,
             subnet_message_memory,
             subnet_wasm_custom_sections_memory,
             registry_settings: test_registry_settings(),
@@ -1483,9 +1490,10 @@ impl Default for ExecutionTestBuilder {
             rate_limiting_of_instructions: false,
             deterministic_time_slicing: false,
             canister_sandboxing: true,
-            composite_queries: false,
+            composite_queries: true,
             query_caching: false,
             query_cache_capacity: 100_000_000, // 100MB
+            query_cache_max_expiry_time: Duration::from_secs(1),
             allocatable_compute_capacity_in_percent: 100,
             subnet_features: String::default(),
             bitcoin_privileged_access: Vec::default(),
@@ -1613,6 +1621,13 @@ impl ExecutionTestBuilder {
         }
     }
 
+    pub fn with_subnet_memory_threshold(self, subnet_memory_threshold: i64) -> Self {
+        Self {
+            subnet_memory_threshold,
+            ..self
+        }
+    }
+
     pub fn with_subnet_message_memory(self, subnet_message_memory: i64) -> Self {
         Self {
             subnet_message_memory,
@@ -1682,6 +1697,13 @@ impl ExecutionTestBuilder {
         }
     }
 
+    pub fn without_composite_queries(self) -> Self {
+        Self {
+            composite_queries: false,
+            ..self
+        }
+    }
+
     pub fn with_query_caching(self) -> Self {
         Self {
             query_caching: true,
@@ -1696,6 +1718,13 @@ impl ExecutionTestBuilder {
         }
     }
 
+    pub fn with_query_cache_max_expiry_time(self, max_expiry_time: Duration) -> Self {
+        Self {
+            query_cache_max_expiry_time: max_expiry_time,
+            ..self
+        }
+    }
+
     pub fn with_allocatable_compute_capacity_in_percent(
         self,
         allocatable_compute_capacity_in_percent: usize,
@@ -1862,8 +1891,10 @@ impl ExecutionTestBuilder {
             composite_queries,
             query_caching,
             query_cache_capacity: self.query_cache_capacity.into(),
+            query_cache_max_expiry_time: self.query_cache_max_expiry_time,
             allocatable_compute_capacity_in_percent: self.allocatable_compute_capacity_in_percent,
             subnet_memory_capacity: NumBytes::from(self.subnet_total_memory as u64),
+            subnet_memory_threshold: NumBytes::from(self.subnet_memory_threshold as u64),
             subnet_message_memory_capacity: NumBytes::from(self.subnet_message_memory as u64),
             bitcoin: BitcoinConfig {
                 privileged_access: self.bitcoin_privileged_access,
@@ -1910,6 +1941,8 @@ impl ExecutionTestBuilder {
             100,
             config.clone(),
             Arc::clone(&cycles_account_manager),
+            1,
+            Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
         );
         let query_handler = InternalHttpQueryHandler::new(
             self.log.clone(),
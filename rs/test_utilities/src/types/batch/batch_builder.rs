@@ -23,6 +23,7 @@ impl Default for BatchBuilder {
                 registry_version: RegistryVersion::from(1),
                 time: mock_time(),
                 consensus_responses: vec![],
+                canister_http_received_bytes: BTreeMap::new(),
             },
         }
     }
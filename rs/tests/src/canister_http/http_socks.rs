@@ -146,6 +146,7 @@ pub fn test(env: TestEnv) {
                             }),
                             method: HttpMethod::GET,
                             max_response_bytes: None,
+                            is_replicated: None,
                         },
                         cycles: 500_000_000_000,
                     },
@@ -194,6 +195,7 @@ pub fn test(env: TestEnv) {
                             }),
                             method: HttpMethod::GET,
                             max_response_bytes: None,
+                            is_replicated: None,
                         },
                         cycles: 500_000_000_000,
                     },
@@ -31,8 +31,8 @@ use ic_rosetta_api::request::Request;
 use ic_rosetta_api::request_types::ChangeAutoStakeMaturity;
 use ic_rosetta_api::request_types::RegisterVote;
 use ic_rosetta_api::request_types::{
-    AddHotKey, Disburse, Follow, MergeMaturity, NeuronInfo, RemoveHotKey, SetDissolveTimestamp,
-    Spawn, Stake, StakeMaturity, StartDissolve, StopDissolve,
+    AddHotKey, Approve, Disburse, Follow, MergeMaturity, NeuronInfo, RemoveHotKey,
+    SetDissolveTimestamp, Spawn, Stake, StakeMaturity, StartDissolve, StopDissolve,
 };
 use ic_rosetta_api::transaction_id::TransactionIdentifier;
 use ic_rosetta_api::{convert, errors, DEFAULT_TOKEN_SYMBOL};
@@ -363,6 +363,13 @@ where
             | Request::Follow(Follow { account, .. }) => {
                 all_sender_account_ids.push(to_model_account_identifier(&account));
             }
+            Request::Approve(Approve { account, fee, .. }) => {
+                trans_fee_amount = Some(tokens_to_amount(fee, token_name).unwrap());
+                all_sender_account_ids.push(to_model_account_identifier(&account));
+
+                // just a sanity check
+                assert!(fee_found, "There should be a fee op in operations");
+            }
             Request::Transfer(Operation::Burn { .. }) => {
                 panic!("Burn operations are not supported here")
             }
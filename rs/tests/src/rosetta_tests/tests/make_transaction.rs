@@ -9,9 +9,12 @@ use crate::rosetta_tests::rosetta_client::RosettaApiClient;
 use crate::rosetta_tests::setup::{setup, TRANSFER_FEE};
 use crate::util::block_on;
 use ic_ledger_core::Tokens;
-use ic_rosetta_api::models::{EdKeypair, RosettaSupportedKeyPair};
+use ic_rosetta_api::models::{EdKeypair, PublicKey, RosettaSupportedKeyPair};
 use ic_rosetta_api::request::Request;
+use ic_rosetta_api::request_types::Approve;
+use ic_rosetta_api::DEFAULT_TOKEN_SYMBOL;
 use ic_rosetta_test_utils::RequestInfo;
+use ic_types::PrincipalId;
 use icp_ledger::{AccountIdentifier, Operation};
 use lazy_static::lazy_static;
 use slog::{debug, info, Logger};
@@ -32,9 +35,9 @@ pub fn test(env: TestEnv) {
     // Accounts for test and initial ledger balances.
     let mut ledger_balances = HashMap::new();
 
-    let (acc_a, kp_a, _pk_a, _pid_a) = make_user(100);
+    let (acc_a, kp_a, _pk_a, pid_a) = make_user(100);
     let kp_a = Arc::new(kp_a);
-    let (acc_b, kp_b, _pk_b, _pid_b) = make_user(101);
+    let (acc_b, kp_b, pk_b, _pid_b) = make_user(101);
     let kp_b = Arc::new(kp_b);
     let (acc_secp256k1, kp_secp256k1, _pk_secp256k1, _pid_secp256k1) =
         make_user_ecdsa_secp256k1(200);
@@ -115,6 +118,8 @@ pub fn test(env: TestEnv) {
         test_multiple_transfers(&client, &ledger_client, acc_b, Arc::clone(&kp_b)).await;
         info!(logger, "Test multiple transfers (fail)");
         test_multiple_transfers_fail(&client, &ledger_client, acc_b, Arc::clone(&kp_b)).await;
+        info!(logger, "Test approve (fail)");
+        test_approve_fail(&client, &ledger_client, acc_b, pk_b, pid_a).await;
     });
 }
 
@@ -429,3 +434,43 @@ async fn test_multiple_transfers_fail(
     check_balance(ros, ledger, &dst_acc2, Tokens::ZERO).await;
     check_balance(ros, ledger, &dst_acc3, Tokens::ZERO).await;
 }
+
+/// The ICP ledger has no `icrc2_approve` endpoint, so Rosetta must refuse to
+/// construct approve transactions.
+async fn test_approve_fail(
+    ros: &RosettaApiClient,
+    ledger_client: &LedgerClient,
+    acc: AccountIdentifier,
+    pk: PublicKey,
+    spender: PrincipalId,
+) {
+    let (_, tip_idx_before) = ledger_client.get_tip().await;
+
+    let approve = Request::Approve(Approve {
+        account: acc,
+        spender,
+        spender_subaccount: None,
+        allowance: Tokens::from_e8s(1000),
+        expires_at: None,
+        fee: Tokens::from_e8s(TRANSFER_FEE),
+    });
+    let ops = Request::requests_to_operations(&[approve], DEFAULT_TOKEN_SYMBOL).unwrap();
+
+    let err = ros
+        .construction_preprocess(ops.clone())
+        .await
+        .unwrap()
+        .unwrap_err();
+    assert_canister_error(&err, 701, "icrc2_approve");
+
+    let err = ros
+        .construction_payloads(None, ops, Some(vec![pk]))
+        .await
+        .unwrap()
+        .unwrap_err();
+    assert_canister_error(&err, 701, "icrc2_approve");
+
+    // Nothing reached the ledger.
+    let (_, tip_idx_after) = ledger_client.get_tip().await;
+    assert_eq!(tip_idx_before, tip_idx_after);
+}
@@ -63,6 +63,7 @@ impl From<ErrorCode> for RejectCode {
         match err {
             SubnetOversubscribed => SysFatal,
             MaxNumberOfCanistersReached => SysFatal,
+            CanisterHttpResponseTooLarge => SysFatal,
             CanisterOutputQueueFull => SysTransient,
             IngressMessageTimeout => SysTransient,
             CanisterQueueNotEmpty => SysTransient,
@@ -72,6 +73,7 @@ impl From<ErrorCode> for RejectCode {
             CanisterMethodNotFound => DestinationInvalid,
             CanisterFunctionNotFound => CanisterError,
             CanisterWasmModuleNotFound => DestinationInvalid,
+            CanisterSnapshotNotFound => DestinationInvalid,
             CanisterAlreadyInstalled => DestinationInvalid,
             CanisterNonEmpty => CanisterError,
             CanisterOutOfCycles => CanisterError,
@@ -103,6 +105,10 @@ impl From<ErrorCode> for RejectCode {
             CompositeQueryCalledInReplicatedMode => CanisterError,
             CanisterNotHostedBySubnet => CanisterReject,
             QueryTimeLimitExceeded => CanisterError,
+            ReservedCyclesLimitExceededInMemoryAllocation => CanisterError,
+            ReservedCyclesLimitExceededInMemoryGrow => CanisterError,
+            InsufficientCyclesInMemoryGrow => CanisterError,
+            ReservedCyclesLimitIsTooLow => CanisterError,
         }
     }
 }
@@ -117,6 +123,7 @@ impl From<ErrorCode> for RejectCode {
 pub enum ErrorCode {
     SubnetOversubscribed = 101,
     MaxNumberOfCanistersReached = 102,
+    CanisterHttpResponseTooLarge = 103,
     CanisterOutputQueueFull = 201,
     IngressMessageTimeout = 202,
     CanisterQueueNotEmpty = 203,
@@ -125,6 +132,7 @@ pub enum ErrorCode {
     CanisterMethodNotFound = 302,
     CanisterAlreadyInstalled = 303,
     CanisterWasmModuleNotFound = 304,
+    CanisterSnapshotNotFound = 305,
     InsufficientMemoryAllocation = 402,
     InsufficientCyclesForCreateCanister = 403,
     SubnetNotFound = 404,
@@ -157,6 +165,10 @@ pub enum ErrorCode {
     QueryCallGraphTotalInstructionLimitExceeded = 526,
     CompositeQueryCalledInReplicatedMode = 527,
     QueryTimeLimitExceeded = 528,
+    ReservedCyclesLimitExceededInMemoryAllocation = 529,
+    ReservedCyclesLimitExceededInMemoryGrow = 530,
+    InsufficientCyclesInMemoryGrow = 531,
+    ReservedCyclesLimitIsTooLow = 532,
 }
 
 impl TryFrom<u64> for ErrorCode {
@@ -165,6 +177,7 @@ impl TryFrom<u64> for ErrorCode {
         match err {
             101 => Ok(ErrorCode::SubnetOversubscribed),
             102 => Ok(ErrorCode::MaxNumberOfCanistersReached),
+            103 => Ok(ErrorCode::CanisterHttpResponseTooLarge),
             201 => Ok(ErrorCode::CanisterOutputQueueFull),
             202 => Ok(ErrorCode::IngressMessageTimeout),
             203 => Ok(ErrorCode::CanisterQueueNotEmpty),
@@ -173,6 +186,7 @@ impl TryFrom<u64> for ErrorCode {
             302 => Ok(ErrorCode::CanisterMethodNotFound),
             303 => Ok(ErrorCode::CanisterAlreadyInstalled),
             304 => Ok(ErrorCode::CanisterWasmModuleNotFound),
+            305 => Ok(ErrorCode::CanisterSnapshotNotFound),
             402 => Ok(ErrorCode::InsufficientMemoryAllocation),
             403 => Ok(ErrorCode::InsufficientCyclesForCreateCanister),
             404 => Ok(ErrorCode::SubnetNotFound),
@@ -205,6 +219,10 @@ impl TryFrom<u64> for ErrorCode {
             526 => Ok(ErrorCode::QueryCallGraphTotalInstructionLimitExceeded),
             527 => Ok(ErrorCode::CompositeQueryCalledInReplicatedMode),
             528 => Ok(ErrorCode::QueryTimeLimitExceeded),
+            529 => Ok(ErrorCode::ReservedCyclesLimitExceededInMemoryAllocation),
+            530 => Ok(ErrorCode::ReservedCyclesLimitExceededInMemoryGrow),
+            531 => Ok(ErrorCode::InsufficientCyclesInMemoryGrow),
+            532 => Ok(ErrorCode::ReservedCyclesLimitIsTooLow),
             _ => Err(TryFromError::ValueOutOfRange(err)),
         }
     }
@@ -258,6 +276,7 @@ impl UserError {
             ErrorCode::CanisterWasmEngineError => true,
             ErrorCode::SubnetOversubscribed
             | ErrorCode::MaxNumberOfCanistersReached
+            | ErrorCode::CanisterHttpResponseTooLarge
             | ErrorCode::CanisterOutputQueueFull
             | ErrorCode::IngressMessageTimeout
             | ErrorCode::CanisterQueueNotEmpty
@@ -265,6 +284,7 @@ impl UserError {
             | ErrorCode::CanisterMethodNotFound
             | ErrorCode::CanisterAlreadyInstalled
             | ErrorCode::CanisterWasmModuleNotFound
+            | ErrorCode::CanisterSnapshotNotFound
             | ErrorCode::InsufficientMemoryAllocation
             | ErrorCode::InsufficientCyclesForCreateCanister
             | ErrorCode::SubnetNotFound
@@ -296,7 +316,11 @@ impl UserError {
             | ErrorCode::QueryCallGraphTooDeep
             | ErrorCode::QueryCallGraphTotalInstructionLimitExceeded
             | ErrorCode::CompositeQueryCalledInReplicatedMode
-            | ErrorCode::QueryTimeLimitExceeded => false,
+            | ErrorCode::QueryTimeLimitExceeded
+            | ErrorCode::ReservedCyclesLimitExceededInMemoryAllocation
+            | ErrorCode::ReservedCyclesLimitExceededInMemoryGrow
+            | ErrorCode::InsufficientCyclesInMemoryGrow
+            | ErrorCode::ReservedCyclesLimitIsTooLow => false,
         }
     }
 
@@ -57,12 +57,13 @@ pub struct TransformContext {
 //     url : text;
 //     max_response_bytes: opt nat64;
 //     headers : vec http_header;
-//     method : variant { get; head; post };
+//     method : variant { get; head; post; put; patch; delete };
 //     body : opt blob;
 //     transform : opt record {
 //       function : func (record {response : http_response; context : blob}) -> (http_response) query;
 //       context : blob;
 //     };
+//     is_replicated : opt bool;
 //   })`
 #[derive(CandidType, Deserialize, Debug, Clone)]
 pub struct CanisterHttpRequestArgs {
@@ -72,6 +73,10 @@ pub struct CanisterHttpRequestArgs {
     pub body: Option<Vec<u8>>,
     pub method: HttpMethod,
     pub transform: Option<TransformContext>,
+    /// If set to `Some(false)`, only a single node of the subnet makes the
+    /// request and its response is delivered without consensus on the content.
+    /// Defaults to a fully replicated request.
+    pub is_replicated: Option<bool>,
 }
 
 impl Payload<'_> for CanisterHttpRequestArgs {}
@@ -107,6 +112,12 @@ pub enum HttpMethod {
     POST,
     #[serde(rename = "head")]
     HEAD,
+    #[serde(rename = "put")]
+    PUT,
+    #[serde(rename = "patch")]
+    PATCH,
+    #[serde(rename = "delete")]
+    DELETE,
 }
 
 /// Represents the response for a canister http request.
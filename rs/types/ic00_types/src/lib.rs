@@ -14,6 +14,7 @@ use ic_protobuf::proxy::{try_decode_hash, try_from_option_field};
 use ic_protobuf::registry::crypto::v1::PublicKey;
 use ic_protobuf::registry::subnet::v1::{InitialIDkgDealings, InitialNiDkgTranscriptRecord};
 use ic_protobuf::state::canister_metadata::v1 as pb_canister_metadata;
+use ic_protobuf::state::canister_state_bits::v1 as pb_canister_state_bits;
 use ic_protobuf::types::v1::CanisterInstallMode as CanisterInstallModeProto;
 use ic_protobuf::{proxy::ProxyDecodeError, registry::crypto::v1 as pb_registry_crypto};
 use num_traits::cast::ToPrimitive;
@@ -59,6 +60,21 @@ pub enum Method {
     UpdateSettings,
     ComputeInitialEcdsaDealings,
 
+    // Canister snapshots.
+    TakeCanisterSnapshot,
+    LoadCanisterSnapshot,
+    ListCanisterSnapshots,
+    DeleteCanisterSnapshot,
+
+    // Canister logs.
+    FetchCanisterLogs,
+
+    // Chunked code installation.
+    UploadChunk,
+    ClearChunkStore,
+    StoredChunks,
+    InstallChunkedCode,
+
     // Bitcoin Interface.
     BitcoinGetBalance,
     BitcoinGetUtxos,
@@ -538,11 +554,251 @@ impl UninstallCodeArgs {
 
 impl Payload<'_> for UninstallCodeArgs {}
 
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     replace_snapshot: opt blob;
+/// })`
+#[derive(CandidType, Serialize, Deserialize, Debug)]
+pub struct TakeCanisterSnapshotArgs {
+    canister_id: PrincipalId,
+    replace_snapshot: Option<Vec<u8>>,
+}
+
+impl TakeCanisterSnapshotArgs {
+    pub fn new(canister_id: CanisterId, replace_snapshot: Option<Vec<u8>>) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            replace_snapshot,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.canister_id).unwrap()
+    }
+
+    pub fn replace_snapshot(&self) -> Option<&[u8]> {
+        self.replace_snapshot.as_deref()
+    }
+}
+
+impl Payload<'_> for TakeCanisterSnapshotArgs {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     snapshot_id: blob;
+///     sender_canister_version: opt nat64;
+/// })`
+#[derive(CandidType, Serialize, Deserialize, Debug)]
+pub struct LoadCanisterSnapshotArgs {
+    canister_id: PrincipalId,
+    #[serde(with = "serde_bytes")]
+    snapshot_id: Vec<u8>,
+    sender_canister_version: Option<u64>,
+}
+
+impl LoadCanisterSnapshotArgs {
+    pub fn new(
+        canister_id: CanisterId,
+        snapshot_id: Vec<u8>,
+        sender_canister_version: Option<u64>,
+    ) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            snapshot_id,
+            sender_canister_version,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.canister_id).unwrap()
+    }
+
+    pub fn snapshot_id(&self) -> &[u8] {
+        &self.snapshot_id
+    }
+
+    pub fn get_sender_canister_version(&self) -> Option<u64> {
+        self.sender_canister_version
+    }
+}
+
+impl Payload<'_> for LoadCanisterSnapshotArgs {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     snapshot_id: blob;
+/// })`
+#[derive(CandidType, Serialize, Deserialize, Debug)]
+pub struct DeleteCanisterSnapshotArgs {
+    canister_id: PrincipalId,
+    #[serde(with = "serde_bytes")]
+    snapshot_id: Vec<u8>,
+}
+
+impl DeleteCanisterSnapshotArgs {
+    pub fn new(canister_id: CanisterId, snapshot_id: Vec<u8>) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            snapshot_id,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.canister_id).unwrap()
+    }
+
+    pub fn snapshot_id(&self) -> &[u8] {
+        &self.snapshot_id
+    }
+}
+
+impl Payload<'_> for DeleteCanisterSnapshotArgs {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     id: blob;
+///     taken_at_timestamp: nat64;
+///     total_size: nat64;
+/// })`
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CanisterSnapshotResponse {
+    #[serde(with = "serde_bytes")]
+    pub id: Vec<u8>,
+    pub taken_at_timestamp: u64,
+    pub total_size: u64,
+}
+
+impl CanisterSnapshotResponse {
+    pub fn new(id: Vec<u8>, taken_at_timestamp: u64, total_size: u64) -> Self {
+        Self {
+            id,
+            taken_at_timestamp,
+            total_size,
+        }
+    }
+}
+
+impl Payload<'_> for CanisterSnapshotResponse {}
+
+/// Struct used for encoding/decoding `(vec canister_snapshot)`, the response of
+/// `list_canister_snapshots`.
+pub type ListCanisterSnapshotsResponse = Vec<CanisterSnapshotResponse>;
+
+impl Payload<'_> for ListCanisterSnapshotsResponse {}
+
+/// Log visibility for a canister.
+/// ```text
+/// (variant {
+///     controllers;
+///     public;
+/// })
+/// ```
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LogVisibility {
+    #[default]
+    #[serde(rename = "controllers")]
+    Controllers,
+    #[serde(rename = "public")]
+    Public,
+}
+
+impl From<LogVisibility> for pb_canister_state_bits::LogVisibility {
+    fn from(item: LogVisibility) -> Self {
+        match item {
+            LogVisibility::Controllers => pb_canister_state_bits::LogVisibility::Controllers,
+            LogVisibility::Public => pb_canister_state_bits::LogVisibility::Public,
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::LogVisibility> for LogVisibility {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: pb_canister_state_bits::LogVisibility) -> Result<Self, Self::Error> {
+        match item {
+            pb_canister_state_bits::LogVisibility::Controllers => Ok(LogVisibility::Controllers),
+            pb_canister_state_bits::LogVisibility::Public => Ok(LogVisibility::Public),
+            pb_canister_state_bits::LogVisibility::Unspecified => {
+                Err(ProxyDecodeError::ValueOutOfRange {
+                    typ: "LogVisibility",
+                    err: format!("Unable to convert {:?} to a LogVisibility", item),
+                })
+            }
+        }
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+/// })`
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FetchCanisterLogsRequest {
+    pub canister_id: PrincipalId,
+}
+
+impl FetchCanisterLogsRequest {
+    pub fn new(canister_id: CanisterId) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.canister_id).unwrap()
+    }
+}
+
+impl Payload<'_> for FetchCanisterLogsRequest {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     idx: nat64;
+///     timestamp_nanos: nat64;
+///     content: blob;
+/// })`
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CanisterLogRecord {
+    pub idx: u64,
+    pub timestamp_nanos: u64,
+    #[serde(with = "serde_bytes")]
+    pub content: Vec<u8>,
+}
+
+impl CanisterLogRecord {
+    /// Returns the number of bytes the record takes up in the canister log.
+    pub fn data_size(&self) -> usize {
+        size_of::<u64>() * 2 + self.content.len()
+    }
+}
+
+impl Payload<'_> for CanisterLogRecord {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_log_records: vec canister_log_record;
+/// })`
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FetchCanisterLogsResponse {
+    pub canister_log_records: Vec<CanisterLogRecord>,
+}
+
+impl Payload<'_> for FetchCanisterLogsResponse {}
+
 /// Struct used for encoding/decoding
 /// `(record {
 ///     controller : principal;
 ///     compute_allocation: nat;
 ///     memory_allocation: opt nat;
+///     wasm_memory_limit: opt nat;
+///     reserved_cycles_limit: opt nat;
 /// })`
 #[derive(CandidType, Deserialize, Debug, Eq, PartialEq)]
 pub struct DefiniteCanisterSettingsArgs {
@@ -551,6 +807,8 @@ pub struct DefiniteCanisterSettingsArgs {
     compute_allocation: candid::Nat,
     memory_allocation: candid::Nat,
     freezing_threshold: candid::Nat,
+    wasm_memory_limit: Option<candid::Nat>,
+    reserved_cycles_limit: Option<candid::Nat>,
 }
 
 impl DefiniteCanisterSettingsArgs {
@@ -560,6 +818,8 @@ impl DefiniteCanisterSettingsArgs {
         compute_allocation: u64,
         memory_allocation: Option<u64>,
         freezing_threshold: u64,
+        wasm_memory_limit: Option<u64>,
+        reserved_cycles_limit: Option<u128>,
     ) -> Self {
         let memory_allocation = match memory_allocation {
             None => candid::Nat::from(0),
@@ -571,12 +831,26 @@ impl DefiniteCanisterSettingsArgs {
             compute_allocation: candid::Nat::from(compute_allocation),
             memory_allocation,
             freezing_threshold: candid::Nat::from(freezing_threshold),
+            wasm_memory_limit: wasm_memory_limit.map(candid::Nat::from),
+            reserved_cycles_limit: reserved_cycles_limit.map(candid::Nat::from),
         }
     }
 
     pub fn controllers(&self) -> Vec<PrincipalId> {
         self.controllers.clone()
     }
+
+    pub fn wasm_memory_limit(&self) -> Option<u64> {
+        self.wasm_memory_limit
+            .as_ref()
+            .map(|limit| limit.0.to_u64().unwrap())
+    }
+
+    pub fn reserved_cycles_limit(&self) -> Option<u128> {
+        self.reserved_cycles_limit
+            .as_ref()
+            .map(|limit| limit.0.to_u128().unwrap())
+    }
 }
 
 impl Payload<'_> for DefiniteCanisterSettingsArgs {}
@@ -646,6 +920,7 @@ impl Payload<'_> for CanisterStatusResult {}
 ///     memory_size: nat;
 ///     cycles: nat;
 ///     idle_cycles_burned_per_day: nat;
+///     reserved_cycles: nat;
 /// })`
 #[derive(CandidType, Debug, Deserialize, Eq, PartialEq)]
 pub struct CanisterStatusResultV2 {
@@ -659,6 +934,7 @@ pub struct CanisterStatusResultV2 {
     balance: Vec<(Vec<u8>, candid::Nat)>,
     freezing_threshold: candid::Nat,
     idle_cycles_burned_per_day: candid::Nat,
+    reserved_cycles: candid::Nat,
 }
 
 impl CanisterStatusResultV2 {
@@ -673,7 +949,10 @@ impl CanisterStatusResultV2 {
         compute_allocation: u64,
         memory_allocation: Option<u64>,
         freezing_threshold: u64,
+        wasm_memory_limit: Option<u64>,
+        reserved_cycles_limit: Option<u128>,
         idle_cycles_burned_per_day: u128,
+        reserved_cycles: u128,
     ) -> Self {
         Self {
             status,
@@ -690,9 +969,12 @@ impl CanisterStatusResultV2 {
                 compute_allocation,
                 memory_allocation,
                 freezing_threshold,
+                wasm_memory_limit,
+                reserved_cycles_limit,
             ),
             freezing_threshold: candid::Nat::from(freezing_threshold),
             idle_cycles_burned_per_day: candid::Nat::from(idle_cycles_burned_per_day),
+            reserved_cycles: candid::Nat::from(reserved_cycles),
         }
     }
 
@@ -724,9 +1006,21 @@ impl CanisterStatusResultV2 {
         self.freezing_threshold.0.to_u64().unwrap()
     }
 
+    pub fn wasm_memory_limit(&self) -> Option<u64> {
+        self.settings.wasm_memory_limit()
+    }
+
+    pub fn reserved_cycles_limit(&self) -> Option<u128> {
+        self.settings.reserved_cycles_limit()
+    }
+
     pub fn idle_cycles_burned_per_day(&self) -> u128 {
         self.idle_cycles_burned_per_day.0.to_u128().unwrap()
     }
+
+    pub fn reserved_cycles(&self) -> u128 {
+        self.reserved_cycles.0.to_u128().unwrap()
+    }
 }
 
 /// Indicates whether the canister is running, stopping, or stopped.
@@ -934,6 +1228,115 @@ impl std::fmt::Display for InstallCodeArgs {
 
 impl Payload<'_> for InstallCodeArgs {}
 
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     chunk: blob;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug)]
+pub struct UploadChunkArgs {
+    pub canister_id: PrincipalId,
+    #[serde(with = "serde_bytes")]
+    pub chunk: Vec<u8>,
+}
+
+impl UploadChunkArgs {
+    pub fn new(canister_id: CanisterId, chunk: Vec<u8>) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            chunk,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.canister_id).unwrap()
+    }
+}
+
+impl Payload<'_> for UploadChunkArgs {}
+
+/// Struct used for encoding/decoding `(record { hash: blob })`, the response
+/// of `upload_chunk` and the entries of the response of `stored_chunks`.
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ChunkHash {
+    #[serde(with = "serde_bytes")]
+    pub hash: Vec<u8>,
+}
+
+impl Payload<'_> for ChunkHash {}
+
+/// Struct used for encoding/decoding `(vec chunk_hash)`, the response of
+/// `stored_chunks`.
+pub type StoredChunksResponse = Vec<ChunkHash>;
+
+impl Payload<'_> for StoredChunksResponse {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     mode : variant { install; reinstall; upgrade };
+///     target_canister: principal;
+///     store_canister: opt principal;
+///     chunk_hashes_list: vec chunk_hash;
+///     wasm_module_hash: blob;
+///     arg: blob;
+///     sender_canister_version: opt nat64;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug)]
+pub struct InstallChunkedCodeArgs {
+    pub mode: CanisterInstallMode,
+    pub target_canister: PrincipalId,
+    pub store_canister: Option<PrincipalId>,
+    pub chunk_hashes_list: Vec<ChunkHash>,
+    #[serde(with = "serde_bytes")]
+    pub wasm_module_hash: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    pub arg: Vec<u8>,
+    pub sender_canister_version: Option<u64>,
+}
+
+impl InstallChunkedCodeArgs {
+    pub fn new(
+        mode: CanisterInstallMode,
+        target_canister: CanisterId,
+        store_canister: Option<CanisterId>,
+        chunk_hashes_list: Vec<Vec<u8>>,
+        wasm_module_hash: Vec<u8>,
+        arg: Vec<u8>,
+    ) -> Self {
+        Self {
+            mode,
+            target_canister: target_canister.into(),
+            store_canister: store_canister.map(|canister_id| canister_id.into()),
+            chunk_hashes_list: chunk_hashes_list
+                .into_iter()
+                .map(|hash| ChunkHash { hash })
+                .collect(),
+            wasm_module_hash,
+            arg,
+            sender_canister_version: None,
+        }
+    }
+
+    pub fn target_canister_id(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.target_canister).unwrap()
+    }
+
+    /// Returns the canister whose chunk store holds the chunks, which defaults
+    /// to the target canister.
+    pub fn store_canister_id(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.store_canister.unwrap_or(self.target_canister)).unwrap()
+    }
+
+    pub fn get_sender_canister_version(&self) -> Option<u64> {
+        self.sender_canister_version
+    }
+}
+
+impl Payload<'_> for InstallChunkedCodeArgs {}
+
 impl InstallCodeArgs {
     pub fn new(
         mode: CanisterInstallMode,
@@ -1036,6 +1439,10 @@ impl Payload<'_> for UpdateSettingsArgs {}
 ///     controllers: opt vec principal;
 ///     compute_allocation: opt nat;
 ///     memory_allocation: opt nat;
+///     freezing_threshold: opt nat;
+///     log_visibility: opt log_visibility;
+///     wasm_memory_limit: opt nat;
+///     reserved_cycles_limit: opt nat;
 /// })`
 #[derive(Default, Clone, CandidType, Deserialize, Debug)]
 pub struct CanisterSettingsArgs {
@@ -1045,6 +1452,9 @@ pub struct CanisterSettingsArgs {
     pub compute_allocation: Option<candid::Nat>,
     pub memory_allocation: Option<candid::Nat>,
     pub freezing_threshold: Option<candid::Nat>,
+    pub log_visibility: Option<LogVisibility>,
+    pub wasm_memory_limit: Option<candid::Nat>,
+    pub reserved_cycles_limit: Option<candid::Nat>,
 }
 
 impl Payload<'_> for CanisterSettingsArgs {}
@@ -1062,6 +1472,9 @@ impl CanisterSettingsArgs {
             compute_allocation: compute_allocation.map(candid::Nat::from),
             memory_allocation: memory_allocation.map(candid::Nat::from),
             freezing_threshold: freezing_threshold.map(candid::Nat::from),
+            log_visibility: None,
+            wasm_memory_limit: None,
+            reserved_cycles_limit: None,
         }
     }
 
@@ -1077,6 +1490,9 @@ pub struct CanisterSettingsArgsBuilder {
     compute_allocation: Option<candid::Nat>,
     memory_allocation: Option<candid::Nat>,
     freezing_threshold: Option<candid::Nat>,
+    log_visibility: Option<LogVisibility>,
+    wasm_memory_limit: Option<candid::Nat>,
+    reserved_cycles_limit: Option<candid::Nat>,
 }
 
 #[allow(dead_code)]
@@ -1092,6 +1508,9 @@ impl CanisterSettingsArgsBuilder {
             compute_allocation: self.compute_allocation,
             memory_allocation: self.memory_allocation,
             freezing_threshold: self.freezing_threshold,
+            log_visibility: self.log_visibility,
+            wasm_memory_limit: self.wasm_memory_limit,
+            reserved_cycles_limit: self.reserved_cycles_limit,
         }
     }
 
@@ -1135,6 +1554,33 @@ impl CanisterSettingsArgsBuilder {
             ..self
         }
     }
+
+    /// Sets who is allowed to fetch the canister logs.
+    pub fn with_log_visibility(self, log_visibility: LogVisibility) -> Self {
+        Self {
+            log_visibility: Some(log_visibility),
+            ..self
+        }
+    }
+
+    /// Sets the Wasm memory limit in bytes. For more details see
+    /// the description of this field in the IC specification.
+    pub fn with_wasm_memory_limit(self, wasm_memory_limit: u64) -> Self {
+        Self {
+            wasm_memory_limit: Some(candid::Nat::from(wasm_memory_limit)),
+            ..self
+        }
+    }
+
+    /// Sets the upper bound on the cycles reserved for future storage
+    /// payments. For more details see the description of this field in
+    /// the IC specification.
+    pub fn with_reserved_cycles_limit(self, reserved_cycles_limit: u128) -> Self {
+        Self {
+            reserved_cycles_limit: Some(candid::Nat::from(reserved_cycles_limit)),
+            ..self
+        }
+    }
 }
 
 /// Struct used for encoding/decoding
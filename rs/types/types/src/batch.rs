@@ -12,9 +12,9 @@ pub use self::self_validating::{SelfValidatingPayload, MAX_BITCOIN_PAYLOAD_IN_BY
 pub use self::xnet::XNetPayload;
 
 use super::{
-    messages::{Response, SignedIngress},
+    messages::{CallbackId, Response, SignedIngress},
     xnet::CertifiedStreamSlice,
-    Height, Randomness, RegistryVersion, SubnetId, Time,
+    Height, NumBytes, Randomness, RegistryVersion, SubnetId, Time,
 };
 use crate::crypto::canister_threshold_sig::MasterEcdsaPublicKey;
 use ic_btc_types_internal::BitcoinAdapterResponse;
@@ -42,6 +42,9 @@ pub struct Batch {
     pub time: Time,
     /// Responses to subnet calls that require consensus' involvement.
     pub consensus_responses: Vec<Response>,
+    /// The number of bytes the https outcalls adapter received for each
+    /// canister http response in `consensus_responses`.
+    pub canister_http_received_bytes: BTreeMap<CallbackId, NumBytes>,
 }
 
 /// The context built by Consensus for deterministic processing. Captures all
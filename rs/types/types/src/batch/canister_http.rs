@@ -9,7 +9,7 @@ use crate::{
     signature::{BasicSignature, BasicSignatureBatch},
     CountBytes, Time,
 };
-use ic_base_types::{NodeId, PrincipalId, RegistryVersion};
+use ic_base_types::{NodeId, NumBytes, PrincipalId, RegistryVersion};
 use ic_error_types::RejectCode;
 use ic_protobuf::{canister_http::v1 as canister_http_pb, types::v1 as pb};
 use serde::{Deserialize, Serialize};
@@ -57,6 +57,7 @@ impl From<&CanisterHttpPayload> for pb::CanisterHttpPayload {
                                 &payload.content.content,
                             )),
                             canister_id: Some(pb::CanisterId::from(payload.content.canister_id)),
+                            received_bytes: payload.content.received_bytes.get(),
                         }),
                         hash: payload.proof.content.content_hash.clone().get().0,
                         registry_version: payload.proof.content.registry_version.get(),
@@ -182,6 +183,7 @@ impl TryFrom<pb::CanisterHttpPayload> for CanisterHttpPayload {
                                         "Error: canistrer_http_response does not contain content",
                                     )?,
                                 )?,
+                                received_bytes: NumBytes::from(response.received_bytes),
                             },
                             proof: Signed {
                                 content: CanisterHttpResponseMetadata {
@@ -321,6 +323,7 @@ mod tests {
                         })
                         .unwrap(),
                     ),
+                    received_bytes: NumBytes::from(1234),
                 },
                 proof: Signed {
                     content: CanisterHttpResponseMetadata {
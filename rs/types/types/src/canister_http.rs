@@ -41,11 +41,16 @@
 //! The blockmaker indicates, which requests have timed out, i.e. the blocktime of the latest finalized block is higher than
 //! the timestamp of a request plus the timeout interval. This condition is verifiable by the other nodes in the network.
 //! Once a timeout has made it into a finalized block, the request is answered with an error message.
+//!
+//! A canister may opt out of replication for a request, see [`Replication::NonReplicated`]. In that case
+//! only the designated node makes the request (1a), and its single share is sufficient to include the
+//! response in a block (3b). Divergence (4b) cannot occur, but timeouts (4c) still apply.
 use crate::{
     crypto::{CryptoHashOf, Signed},
     messages::{CallbackId, RejectContext, Request},
+    node_id_into_protobuf, node_id_try_from_protobuf,
     signature::*,
-    CanisterId, CountBytes, RegistryVersion, Time,
+    CanisterId, CountBytes, NodeId, RegistryVersion, Time,
 };
 use ic_base_types::{NumBytes, PrincipalId};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
@@ -58,6 +63,7 @@ use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use std::{
     convert::{TryFrom, TryInto},
+    hash::{Hash, Hasher},
     mem::size_of,
 };
 
@@ -108,6 +114,18 @@ impl From<TransformContext> for Transform {
     }
 }
 
+/// Specifies which nodes make a [`CanisterHttpRequest`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Replication {
+    /// All nodes of the subnet make the request and consensus is reached on
+    /// the response.
+    #[default]
+    FullyReplicated,
+    /// Only the given node makes the request and its response is delivered
+    /// without consensus on the content.
+    NonReplicated(NodeId),
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct CanisterHttpRequestContext {
     pub request: Request,
@@ -119,6 +137,16 @@ pub struct CanisterHttpRequestContext {
     pub http_method: CanisterHttpMethod,
     pub transform: Option<Transform>,
     pub time: Time,
+    #[serde(default)]
+    pub replication: Replication,
+    /// The size of the subnet when the request fee was charged. The refund of
+    /// unused response bytes is computed for the same subnet size.
+    #[serde(default)]
+    pub subnet_size: Option<usize>,
+    /// The number of response bytes the adapter received, as agreed on by
+    /// consensus. Set when the response is delivered to the subnet.
+    #[serde(default)]
+    pub received_response_bytes: Option<NumBytes>,
 }
 
 impl From<&CanisterHttpRequestContext> for pb_metadata::CanisterHttpRequestContext {
@@ -149,6 +177,14 @@ impl From<&CanisterHttpRequestContext> for pb_metadata::CanisterHttpRequestConte
                 .map(|transform| transform.context.clone()),
             http_method: pb_metadata::HttpMethod::from(&context.http_method).into(),
             time: context.time.as_nanos_since_unix_epoch(),
+            non_replicated_node: match context.replication {
+                Replication::FullyReplicated => None,
+                Replication::NonReplicated(node_id) => Some(node_id_into_protobuf(node_id)),
+            },
+            subnet_size: context.subnet_size.map(|subnet_size| subnet_size as u64),
+            received_response_bytes: context
+                .received_response_bytes
+                .map(|received_response_bytes| received_response_bytes.get()),
         }
     }
 }
@@ -206,6 +242,15 @@ impl TryFrom<pb_metadata::CanisterHttpRequestContext> for CanisterHttpRequestCon
                 .try_into()?,
             transform,
             time: Time::from_nanos_since_unix_epoch(context.time),
+            replication: match context.non_replicated_node {
+                None => Replication::FullyReplicated,
+                Some(node_id) => Replication::NonReplicated(
+                    node_id_try_from_protobuf(node_id)
+                        .map_err(|err| ProxyDecodeError::InvalidPrincipalId(Box::new(err)))?,
+                ),
+            },
+            subnet_size: context.subnet_size.map(|subnet_size| subnet_size as usize),
+            received_response_bytes: context.received_response_bytes.map(NumBytes::from),
         })
     }
 }
@@ -321,14 +366,31 @@ impl TryFrom<(Time, &Request, CanisterHttpRequestArgs)> for CanisterHttpRequestC
                 HttpMethod::GET => CanisterHttpMethod::GET,
                 HttpMethod::POST => CanisterHttpMethod::POST,
                 HttpMethod::HEAD => CanisterHttpMethod::HEAD,
+                HttpMethod::PUT => CanisterHttpMethod::PUT,
+                HttpMethod::PATCH => CanisterHttpMethod::PATCH,
+                HttpMethod::DELETE => CanisterHttpMethod::DELETE,
             },
             transform: args.transform.map(From::from),
             time,
+            // Execution designates the node of non-replicated requests, as it
+            // requires the subnet topology.
+            replication: Replication::FullyReplicated,
+            // Execution records the subnet size when it charges the fee.
+            subnet_size: None,
+            received_response_bytes: None,
         })
     }
 }
 
 impl CanisterHttpRequestContext {
+    /// Returns true if the given node is expected to make this request.
+    pub fn is_requested_from(&self, node_id: &NodeId) -> bool {
+        match &self.replication {
+            Replication::FullyReplicated => true,
+            Replication::NonReplicated(designated_node) => designated_node == node_id,
+        }
+    }
+
     /// Calculate the size of all unbounded struct elements.
     pub fn variable_parts_size(&self) -> NumBytes {
         let request_size = self.url.len()
@@ -449,17 +511,37 @@ pub struct CanisterHttpRequest {
 }
 
 /// The content of a response after the transformation
-#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CanisterHttpResponse {
     pub id: CanisterHttpRequestId,
     pub timeout: Time,
     pub canister_id: CanisterId,
     pub content: CanisterHttpResponseContent,
+    /// The number of bytes (headers and body) that the adapter received from
+    /// the server before the transformation. Zero for rejected requests.
+    #[serde(default)]
+    pub received_bytes: NumBytes,
+}
+
+/// The hash of a [`CanisterHttpResponse`] is what the replicas sign to reach
+/// consensus, so it only covers the transformed response. Servers commonly
+/// return raw responses of slightly different sizes to different replicas;
+/// all replicas use the `received_bytes` of the response included in the block.
+impl Hash for CanisterHttpResponse {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+        self.timeout.hash(state);
+        self.canister_id.hash(state);
+        self.content.hash(state);
+    }
 }
 
 impl CountBytes for CanisterHttpResponse {
     fn count_bytes(&self) -> usize {
-        size_of::<CallbackId>() + size_of::<Time>() + self.content.count_bytes()
+        size_of::<CallbackId>()
+            + size_of::<Time>()
+            + size_of::<NumBytes>()
+            + self.content.count_bytes()
     }
 }
 
@@ -517,6 +599,9 @@ pub enum CanisterHttpMethod {
     GET,
     POST,
     HEAD,
+    PUT,
+    PATCH,
+    DELETE,
 }
 
 impl From<&CanisterHttpMethod> for pb_metadata::HttpMethod {
@@ -525,6 +610,9 @@ impl From<&CanisterHttpMethod> for pb_metadata::HttpMethod {
             CanisterHttpMethod::GET => pb_metadata::HttpMethod::Get,
             CanisterHttpMethod::POST => pb_metadata::HttpMethod::Post,
             CanisterHttpMethod::HEAD => pb_metadata::HttpMethod::Head,
+            CanisterHttpMethod::PUT => pb_metadata::HttpMethod::Put,
+            CanisterHttpMethod::PATCH => pb_metadata::HttpMethod::Patch,
+            CanisterHttpMethod::DELETE => pb_metadata::HttpMethod::Delete,
         }
     }
 }
@@ -537,6 +625,9 @@ impl TryFrom<pb_metadata::HttpMethod> for CanisterHttpMethod {
             pb_metadata::HttpMethod::Get => Ok(CanisterHttpMethod::GET),
             pb_metadata::HttpMethod::Post => Ok(CanisterHttpMethod::POST),
             pb_metadata::HttpMethod::Head => Ok(CanisterHttpMethod::HEAD),
+            pb_metadata::HttpMethod::Put => Ok(CanisterHttpMethod::PUT),
+            pb_metadata::HttpMethod::Patch => Ok(CanisterHttpMethod::PATCH),
+            pb_metadata::HttpMethod::Delete => Ok(CanisterHttpMethod::DELETE),
             pb_metadata::HttpMethod::Unspecified => Err(ProxyDecodeError::ValueOutOfRange {
                 typ: "ic_protobuf::state::system_metadata::v1::HttpMethod",
                 err: "Unspecified HttpMethod".to_string(),
@@ -662,6 +753,9 @@ mod tests {
                 method_payload: Vec::new(),
             },
             time: UNIX_EPOCH,
+            replication: Replication::FullyReplicated,
+            subnet_size: None,
+            received_response_bytes: None,
         };
 
         let expected_size = context.url.len()
@@ -702,6 +796,9 @@ mod tests {
                 method_payload: Vec::new(),
             },
             time: UNIX_EPOCH,
+            replication: Replication::FullyReplicated,
+            subnet_size: None,
+            received_response_bytes: None,
         };
 
         let expected_size = context.url.len()
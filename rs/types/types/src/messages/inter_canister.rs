@@ -1,8 +1,9 @@
 use crate::{ingress::WasmResult, CanisterId, CountBytes, Cycles, Funds, NumBytes};
 use ic_error_types::{RejectCode, TryFromError, UserError};
 use ic_ic00_types::{
-    CanisterIdRecord, InstallCodeArgs, Method, Payload as _, ProvisionalTopUpCanisterArgs,
-    SetControllerArgs, UpdateSettingsArgs,
+    CanisterIdRecord, DeleteCanisterSnapshotArgs, InstallChunkedCodeArgs, InstallCodeArgs,
+    LoadCanisterSnapshotArgs, Method, Payload as _, ProvisionalTopUpCanisterArgs,
+    SetControllerArgs, TakeCanisterSnapshotArgs, UpdateSettingsArgs, UploadChunkArgs,
 };
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
@@ -78,6 +79,10 @@ impl Request {
             | Ok(Method::DeleteCanister)
             | Ok(Method::UninstallCode)
             | Ok(Method::DepositCycles)
+            | Ok(Method::ListCanisterSnapshots)
+            | Ok(Method::FetchCanisterLogs)
+            | Ok(Method::ClearChunkStore)
+            | Ok(Method::StoredChunks)
             | Ok(Method::StopCanister) => match CanisterIdRecord::decode(&self.method_payload) {
                 Ok(record) => Some(record.get_canister_id()),
                 Err(_) => None,
@@ -94,12 +99,40 @@ impl Request {
                 Ok(record) => Some(record.get_canister_id()),
                 Err(_) => None,
             },
+            Ok(Method::InstallChunkedCode) => {
+                match InstallChunkedCodeArgs::decode(&self.method_payload) {
+                    Ok(record) => Some(record.target_canister_id()),
+                    Err(_) => None,
+                }
+            }
+            Ok(Method::UploadChunk) => match UploadChunkArgs::decode(&self.method_payload) {
+                Ok(record) => Some(record.get_canister_id()),
+                Err(_) => None,
+            },
             Ok(Method::ProvisionalTopUpCanister) => {
                 match ProvisionalTopUpCanisterArgs::decode(&self.method_payload) {
                     Ok(record) => Some(record.get_canister_id()),
                     Err(_) => None,
                 }
             }
+            Ok(Method::TakeCanisterSnapshot) => {
+                match TakeCanisterSnapshotArgs::decode(&self.method_payload) {
+                    Ok(record) => Some(record.get_canister_id()),
+                    Err(_) => None,
+                }
+            }
+            Ok(Method::LoadCanisterSnapshot) => {
+                match LoadCanisterSnapshotArgs::decode(&self.method_payload) {
+                    Ok(record) => Some(record.get_canister_id()),
+                    Err(_) => None,
+                }
+            }
+            Ok(Method::DeleteCanisterSnapshot) => {
+                match DeleteCanisterSnapshotArgs::decode(&self.method_payload) {
+                    Ok(record) => Some(record.get_canister_id()),
+                    Err(_) => None,
+                }
+            }
             Ok(Method::CreateCanister)
             | Ok(Method::SetupInitialDKG)
             | Ok(Method::HttpRequest)
@@ -36,6 +36,8 @@ pub enum WasmValidationError {
     InvalidDataSection(String),
     /// Module contains an invalid custom section
     InvalidCustomSection(String),
+    /// Module contains an invalid memory section
+    InvalidMemorySection(String),
     /// Module contains too many globals.
     TooManyGlobals { defined: usize, allowed: usize },
     /// Module contains too many functions.
@@ -85,6 +87,9 @@ impl std::fmt::Display for WasmValidationError {
             Self::InvalidCustomSection(err) => {
                 write!(f, "Wasm module has an invalid custom section. {}", err)
             }
+            Self::InvalidMemorySection(err) => {
+                write!(f, "Wasm module has an invalid memory section. {}", err)
+            }
             Self::TooManyGlobals { defined, allowed } => write!(
                 f,
                 "Wasm module defined {} globals which exceeds the maximum number allowed {}.",
@@ -63,7 +63,8 @@ where
     /// It also marks the item as the most-recently used.
     /// The size parameter specifies the size of the item,
     /// which must not exceed `MAX_SIZE = (2^63 - 1)`.
-    pub fn push(&mut self, key: K, value: V) {
+    /// Returns the items evicted to restore the capacity guarantee.
+    pub fn push(&mut self, key: K, value: V) -> Vec<(K, V)> {
         let size = key.count_bytes() + value.count_bytes();
         assert!(size <= MAX_SIZE);
         if let Some((prev_key, prev_value)) = self.cache.push(key, value) {
@@ -77,8 +78,9 @@ where
         // `self.size <= self.capacity <= MAX_SIZE`
         // and `size <= MAX_SIZE == usize::MAX / 2`.
         self.size += size;
-        self.evict();
+        let evicted = self.evict();
         self.check_invariants();
+        evicted
     }
 
     /// Removes and returns the value corresponding to the key from the cache or
@@ -95,6 +97,16 @@ where
         }
     }
 
+    /// Returns the number of items in the cache.
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Returns true if the cache contains no items.
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
     /// Clears the cache by removing all items.
     pub fn clear(&mut self) {
         self.cache.clear();
@@ -103,7 +115,9 @@ where
     }
 
     /// Evicts as many items as needed to restore the capacity guarantee.
-    fn evict(&mut self) {
+    /// Returns the evicted items.
+    fn evict(&mut self) -> Vec<(K, V)> {
+        let mut evicted = vec![];
         while self.size > self.capacity {
             match self.cache.pop_lru() {
                 Some((key, value)) => {
@@ -112,10 +126,12 @@ where
                     // This cannot underflow because we know that `self.size` is
                     // the sum of sizes of all items in the cache.
                     self.size -= size;
+                    evicted.push((key, value));
                 }
                 None => break,
             }
         }
+        evicted
     }
 
     fn check_invariants(&self) {
@@ -310,4 +326,20 @@ mod tests {
         lru.pop(&Key(1));
         assert_eq!(0, lru.count_bytes());
     }
+
+    #[test]
+    fn lru_cache_push_returns_evicted_entries() {
+        let mut lru = LruCache::<Key, ValueSize>::new(NumBytes::new(10));
+        assert!(lru.push(Key(0), ValueSize(0, 4)).is_empty());
+        assert!(lru.push(Key(1), ValueSize(1, 6)).is_empty());
+        assert_eq!(2, lru.len());
+        assert_eq!(
+            lru.push(Key(2), ValueSize(2, 10)),
+            vec![(Key(0), ValueSize(0, 4)), (Key(1), ValueSize(1, 6))]
+        );
+        assert_eq!(1, lru.len());
+        // Replacing an entry is not an eviction.
+        assert!(lru.push(Key(2), ValueSize(3, 10)).is_empty());
+        assert_eq!(1, lru.len());
+    }
 }